@@ -2,9 +2,9 @@ use crate::engine_out::EngineOut;
 use crossbeam_channel::Receiver;
 use movegen::r#move::Move;
 use movegen::side::Side;
-use search::search::SearchResult;
+use search::search::{CurrentMove, SearchProgress, SearchResult};
 use search::SearchOptions;
-use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
 use std::thread;
 
 pub struct BestMoveHandler {
@@ -16,44 +16,73 @@ impl BestMoveHandler {
         receiver: Receiver<BestMoveCommand>,
         engine_out: impl EngineOut + Send + 'static,
     ) -> Self {
-        let options = Arc::new(Mutex::new(SearchOptions::default()));
         let mut side_to_move = None;
-        let mut best_move = None;
+        // One entry per search that hasn't finished yet, in the order the
+        // searches were started. The searcher reports exactly one best move per
+        // search, so each finished search can be matched with its entry.
+        let mut pending = VecDeque::new();
+        // The best move of a finished infinite search, which must not be sent
+        // before the search is stopped
+        let mut held_best_move = None;
 
         let thread = thread::spawn(move || loop {
             let message = receiver.recv().expect("Error receiving BestMoveCommand");
             match message {
-                BestMoveCommand::SetOptions(new_options) => match options.lock() {
-                    Ok(mut opt) => *opt = *new_options,
-                    Err(e) => panic!("{}", e),
-                },
+                BestMoveCommand::SetOptions(new_options) => {
+                    // Infinite searches without a stop command are replaced by
+                    // the new search
+                    held_best_move = None;
+                    for p in pending.iter_mut() {
+                        if *p == PendingSearch::Infinite {
+                            *p = PendingSearch::Superseded;
+                        }
+                    }
+                    pending.push_back(match new_options.infinite {
+                        true => PendingSearch::Infinite,
+                        false => PendingSearch::Finite,
+                    });
+                }
                 BestMoveCommand::SetSideToMove(s) => side_to_move = s,
                 BestMoveCommand::DepthFinished(res) => engine_out
                     .info_depth_finished(Self::search_result_to_relative(Some(res), side_to_move))
                     .expect("Error writing search info"),
+                BestMoveCommand::CurrentMove(current_move) => engine_out
+                    .info_current_move(current_move)
+                    .expect("Error writing search info"),
+                BestMoveCommand::Progress(progress) => engine_out
+                    .info_progress(progress)
+                    .expect("Error writing search info"),
+                BestMoveCommand::Trace(line) => {
+                    engine_out.trace(&line).expect("Error writing search trace")
+                }
                 BestMoveCommand::Stop(StopReason::Command) => {
-                    match options.lock() {
-                        Ok(mut opt) => opt.infinite = false,
-                        Err(e) => panic!("{}", e),
+                    for p in pending.iter_mut() {
+                        if *p == PendingSearch::Infinite {
+                            *p = PendingSearch::Finite;
+                        }
+                    }
+                    if let Some(best_move) = held_best_move.take() {
+                        engine_out
+                            .best_move(Some(best_move))
+                            .expect("Error writing best move");
                     }
-                    engine_out
-                        .best_move(best_move.take())
-                        .expect("Error writing best move");
                 }
-                BestMoveCommand::Stop(StopReason::Finished(new_best_move)) => {
-                    best_move = Some(new_best_move);
-                    match options.lock() {
-                        Ok(opt) => {
-                            if !opt.infinite {
-                                engine_out
-                                    .best_move(best_move.take())
-                                    .expect("Error writing best move");
-                            }
-                        }
-                        Err(e) => panic!("{}", e),
+                BestMoveCommand::Stop(StopReason::Finished(best_move)) => {
+                    match pending.pop_front() {
+                        Some(PendingSearch::Infinite) => held_best_move = Some(best_move),
+                        Some(PendingSearch::Superseded) => {}
+                        Some(PendingSearch::Finite) | None => engine_out
+                            .best_move(Some(best_move))
+                            .expect("Error writing best move"),
                     }
                 }
-                BestMoveCommand::Terminate => break,
+                BestMoveCommand::Terminate => {
+                    // Nothing is written after this, so make sure the output
+                    // is complete. The front-end may have closed the pipe
+                    // already, which is not an error when shutting down.
+                    let _ = engine_out.flush();
+                    break;
+                }
             }
         });
 
@@ -75,11 +104,24 @@ impl BestMoveHandler {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PendingSearch {
+    // The best move is sent as soon as the search has finished
+    Finite,
+    // The best move is held back until the search is stopped
+    Infinite,
+    // An infinite search replaced by a new one. Its best move is dropped.
+    Superseded,
+}
+
 #[derive(Clone, Debug)]
 pub enum BestMoveCommand {
     SetOptions(Box<SearchOptions>),
     SetSideToMove(Option<Side>),
     DepthFinished(SearchResult),
+    CurrentMove(CurrentMove),
+    Progress(SearchProgress),
+    Trace(String),
     Stop(StopReason),
     Terminate,
 }
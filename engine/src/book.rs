@@ -0,0 +1,258 @@
+use movegen::move_generator::MoveGenerator;
+use movegen::piece;
+use movegen::polyglot::PolyglotKey;
+use movegen::position::Position;
+use movegen::r#move::{Move, MoveList, MoveType};
+use movegen::square::Square;
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read};
+use std::path::Path;
+
+// An entry of a Polyglot book consists of 16 bytes, all big-endian:
+// key (u64), move (u16), weight (u16), learn (u32)
+const ENTRY_BYTES: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BookEntry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+// Opening book in the Polyglot (.bin) format
+#[derive(Clone, Debug, Default)]
+pub struct Book {
+    // Sorted by key
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        let mut buf = [0; ENTRY_BYTES];
+        loop {
+            match reader.read_exact(&mut buf) {
+                Ok(()) => entries.push(BookEntry {
+                    key: u64::from_be_bytes(buf[0..8].try_into().unwrap()),
+                    raw_move: u16::from_be_bytes(buf[8..10].try_into().unwrap()),
+                    weight: u16::from_be_bytes(buf[10..12].try_into().unwrap()),
+                }),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        // Books are usually sorted already, but we rely on it for the lookup
+        entries.sort_by_key(|e| e.key);
+        Ok(Self { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // The legal book moves of the position with their weights. Entries that
+    // don't match any legal move (e.g. because of a key collision) are
+    // skipped.
+    pub fn moves(&self, pos: &Position) -> Vec<(Move, u16)> {
+        let key = u64::from(PolyglotKey::new(pos));
+        let first = self.entries.partition_point(|e| e.key < key);
+        let mut legal_moves = MoveList::new();
+        MoveGenerator::generate_moves(&mut legal_moves, pos);
+
+        self.entries[first..]
+            .iter()
+            .take_while(|e| e.key == key)
+            .filter_map(|e| {
+                legal_moves
+                    .iter()
+                    .find(|m| Self::encode_move(pos, **m) == e.raw_move)
+                    .map(|m| (*m, e.weight))
+            })
+            .collect()
+    }
+
+    // Chooses one of the book moves with a probability proportional to its
+    // weight. The choice only depends on the seed.
+    pub fn choose(&self, pos: &Position, seed: u64) -> Option<Move> {
+        let moves = self.moves(pos);
+        let total_weight: u64 = moves.iter().map(|&(_, w)| w as u64).sum();
+        if total_weight == 0 {
+            return None;
+        }
+        let mut r = seed % total_weight;
+        for (m, w) in moves {
+            if r < w as u64 {
+                return Some(m);
+            }
+            r -= w as u64;
+        }
+        unreachable!();
+    }
+
+    // Bits 0-2: target file, 3-5: target rank, 6-8: origin file,
+    // 9-11: origin rank, 12-14: promotion piece (none, N, B, R, Q).
+    // Castling moves are encoded as the king capturing its own rook.
+    fn encode_move(pos: &Position, m: Move) -> u16 {
+        let origin = m.origin();
+        let target = match m.is_castle() {
+            true => {
+                // In Chess960, the king may move towards the queenside rook
+                // to a higher file, so the side is taken from the move type
+                let rook_file = match m.move_type() == MoveType::CASTLE_KINGSIDE {
+                    true => pos.kingside_castling_file(),
+                    false => pos.queenside_castling_file(),
+                };
+                Square::from_file_and_rank(rook_file, origin.rank())
+            }
+            false => m.target(),
+        };
+        let promo = match m.promotion_piece() {
+            None => 0,
+            Some(piece::Type::Knight) => 1,
+            Some(piece::Type::Bishop) => 2,
+            Some(piece::Type::Rook) => 3,
+            Some(_) => 4,
+        };
+        (target.file().idx()
+            | target.rank().idx() << 3
+            | origin.file().idx() << 6
+            | origin.rank().idx() << 9
+            | promo << 12) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use movegen::fen::Fen;
+
+    fn entry_bytes(key: u64, raw_move: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&key.to_be_bytes());
+        bytes.extend_from_slice(&raw_move.to_be_bytes());
+        bytes.extend_from_slice(&weight.to_be_bytes());
+        bytes.extend_from_slice(&0_u32.to_be_bytes());
+        bytes
+    }
+
+    fn raw_move(origin: Square, target: Square) -> u16 {
+        (target.file().idx()
+            | target.rank().idx() << 3
+            | origin.file().idx() << 6
+            | origin.rank().idx() << 9) as u16
+    }
+
+    #[test]
+    fn read_and_probe() {
+        let start_key = u64::from(PolyglotKey::new(&Position::initial()));
+        let mut bytes = Vec::new();
+        // Not sorted by key
+        bytes.extend(entry_bytes(
+            start_key + 1,
+            raw_move(Square::G1, Square::F3),
+            1,
+        ));
+        bytes.extend(entry_bytes(start_key, raw_move(Square::E2, Square::E4), 3));
+        bytes.extend(entry_bytes(start_key, raw_move(Square::D2, Square::D4), 1));
+        // Illegal move
+        bytes.extend(entry_bytes(start_key, raw_move(Square::E2, Square::E5), 10));
+        bytes.extend(entry_bytes(
+            start_key - 1,
+            raw_move(Square::B1, Square::C3),
+            1,
+        ));
+        // Trailing incomplete entry
+        bytes.extend([0; 5]);
+
+        let book = Book::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(5, book.len());
+        assert_eq!(
+            vec![
+                (
+                    Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH),
+                    3
+                ),
+                (
+                    Move::new(Square::D2, Square::D4, MoveType::DOUBLE_PAWN_PUSH),
+                    1
+                ),
+            ],
+            book.moves(&Position::initial())
+        );
+
+        let mut e4_count = 0;
+        for seed in 0..1000 {
+            let m = book.choose(&Position::initial(), seed).unwrap();
+            // Reproducible
+            assert_eq!(Some(m), book.choose(&Position::initial(), seed));
+            if m.target() == Square::E4 {
+                e4_count += 1;
+            }
+        }
+        assert_eq!(750, e4_count);
+
+        let pos = Fen::str_to_pos("8/8/8/8/8/5k2/8/4K3 w - - 0 1").unwrap();
+        assert!(book.moves(&pos).is_empty());
+        assert_eq!(None, book.choose(&pos, 0));
+    }
+
+    #[test]
+    fn zero_weights_are_never_chosen() {
+        let start_key = u64::from(PolyglotKey::new(&Position::initial()));
+        let bytes = entry_bytes(start_key, raw_move(Square::E2, Square::E4), 0);
+        let book = Book::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(1, book.moves(&Position::initial()).len());
+        assert_eq!(None, book.choose(&Position::initial(), 0));
+    }
+
+    #[test]
+    fn castling_and_promotion() {
+        let pos = Fen::str_to_pos("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let key = u64::from(PolyglotKey::new(&pos));
+        let mut bytes = Vec::new();
+        bytes.extend(entry_bytes(key, raw_move(Square::E1, Square::H1), 1));
+        bytes.extend(entry_bytes(key, raw_move(Square::E1, Square::A1), 1));
+        bytes.extend(entry_bytes(
+            key,
+            raw_move(Square::B7, Square::A8) | 1 << 12,
+            1,
+        ));
+        let book = Book::from_reader(bytes.as_slice()).unwrap();
+        let moves: Vec<Move> = book.moves(&pos).iter().map(|&(m, _)| m).collect();
+        assert_eq!(
+            vec![
+                Move::new(Square::E1, Square::G1, MoveType::CASTLE_KINGSIDE),
+                Move::new(Square::E1, Square::C1, MoveType::CASTLE_QUEENSIDE),
+                Move::new(Square::B7, Square::A8, MoveType::PROMOTION_CAPTURE_KNIGHT),
+            ],
+            moves
+        );
+    }
+
+    #[test]
+    fn castling_chess_960() {
+        // The king moves from b1 to c1 when castling queenside
+        let pos = Fen::str_to_pos_chess_960("1k6/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap();
+        let key = u64::from(PolyglotKey::new(&pos));
+        let mut bytes = Vec::new();
+        bytes.extend(entry_bytes(key, raw_move(Square::B1, Square::A1), 1));
+        bytes.extend(entry_bytes(key, raw_move(Square::B1, Square::H1), 1));
+        let book = Book::from_reader(bytes.as_slice()).unwrap();
+        let moves: Vec<Move> = book.moves(&pos).iter().map(|&(m, _)| m).collect();
+        assert_eq!(
+            vec![
+                Move::new(Square::B1, Square::C1, MoveType::CASTLE_QUEENSIDE),
+                Move::new(Square::B1, Square::G1, MoveType::CASTLE_KINGSIDE),
+            ],
+            moves
+        );
+    }
+}
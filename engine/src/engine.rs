@@ -1,16 +1,25 @@
 use crate::best_move_handler::{BestMoveCommand, BestMoveHandler, StopReason};
 use crate::engine_out::EngineOut;
-use crate::{EngineOptions, Variant};
+use crate::{
+    Book, EngineOptions, Variant, MAX_BOOK_DEPTH, MAX_CONTEMPT, MAX_HASH_MB, MAX_SEED,
+    MAX_TRACE_DEPTH, MIN_BOOK_DEPTH, MIN_CONTEMPT, MIN_HASH_MB,
+};
 use crossbeam_channel::{unbounded, Sender};
+use eval::Score;
 use movegen::position::Position;
 use movegen::position_history::PositionHistory;
+use movegen::r#move::Move;
 use movegen::side::Side;
+use movegen::zobrist::Zobrist;
 use search::search::{Search, SearchInfo};
 use search::search_params::SearchParamsEachAlgo;
 use search::searcher::Searcher;
+use search::strength::{SkillLevel, StrengthLimit, MAX_SKILL_LEVEL};
 use search::SearchOptions;
+use std::io;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, thiserror::Error)]
 pub enum EngineError {
@@ -21,6 +30,7 @@ pub enum EngineError {
 pub struct Engine {
     searcher: Searcher,
     pos_hist: Option<PositionHistory>,
+    book: Option<Book>,
     best_move_handler: BestMoveHandler,
     best_move_sender: Sender<BestMoveCommand>,
     engine_options: Arc<Mutex<EngineOptions>>,
@@ -40,6 +50,18 @@ impl Engine {
             SearchInfo::DepthFinished(res) => {
                 let _ = best_move_sender_clone.send(BestMoveCommand::DepthFinished(res.clone()));
             }
+            SearchInfo::AspirationFail(res) => {
+                let _ = best_move_sender_clone.send(BestMoveCommand::DepthFinished(res.clone()));
+            }
+            SearchInfo::CurrentMove(current_move) => {
+                let _ = best_move_sender_clone.send(BestMoveCommand::CurrentMove(current_move));
+            }
+            SearchInfo::Progress(progress) => {
+                let _ = best_move_sender_clone.send(BestMoveCommand::Progress(progress));
+            }
+            SearchInfo::Trace(line) => {
+                let _ = best_move_sender_clone.send(BestMoveCommand::Trace(line));
+            }
             SearchInfo::Stopped(best_move) => {
                 let _ = best_move_sender_clone
                     .send(BestMoveCommand::Stop(StopReason::Finished(best_move)));
@@ -52,6 +74,7 @@ impl Engine {
         Self {
             searcher,
             pos_hist: None,
+            book: None,
             best_move_handler,
             best_move_sender,
             engine_options,
@@ -65,12 +88,36 @@ impl Engine {
         }
     }
 
-    pub fn set_hash_size(&self, bytes: usize) {
+    // Returns the actual hash size. The size is clamped to the allowed range
+    // and rounded down to a power of two, because the transposition table
+    // can't use the remaining memory anyway.
+    // If a search is running, the new size is applied after the search has
+    // finished.
+    pub fn set_hash_size(&self, bytes: usize) -> usize {
+        let bytes = bytes.clamp(MIN_HASH_MB * 2_usize.pow(20), MAX_HASH_MB * 2_usize.pow(20));
+        let bytes = match bytes.is_power_of_two() {
+            true => bytes,
+            false => bytes.next_power_of_two() / 2,
+        };
+        // Returns after the table has been resized or, during a search, after
+        // the command has been deferred
+        self.searcher.set_hash_size(bytes);
         match self.engine_options.lock() {
             Ok(mut opt) => opt.hash_size = bytes,
             Err(e) => panic!("{}", e),
         }
-        self.searcher.set_hash_size(bytes);
+        bytes
+    }
+
+    pub fn clear_hash_table(&self) {
+        self.searcher.clear_hash_table();
+    }
+
+    // Returns once all pending option changes have been applied. During a
+    // search, it returns immediately and the changes are applied after the
+    // search.
+    pub fn wait_until_ready(&self) {
+        self.searcher.wait_until_ready();
     }
 
     pub fn move_overhead(&self) -> Duration {
@@ -105,21 +152,221 @@ impl Engine {
         };
     }
 
+    pub fn show_wdl(&self) -> bool {
+        match self.engine_options.lock() {
+            Ok(opt) => opt.show_wdl,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_show_wdl(&self, enable: bool) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.show_wdl = enable,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn strength_limit(&self) -> Option<StrengthLimit> {
+        match self.engine_options.lock() {
+            Ok(opt) => match opt.limit_strength {
+                true => Some(StrengthLimit::new(opt.elo)),
+                false => None,
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_limit_strength(&self, enable: bool) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.limit_strength = enable,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn set_elo(&self, elo: u16) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.elo = elo,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    // None at the maximum level, so that the search isn't weakened at all
+    pub fn skill_level(&self) -> Option<SkillLevel> {
+        match self.engine_options.lock() {
+            Ok(opt) => match opt.skill_level < MAX_SKILL_LEVEL {
+                true => Some(SkillLevel::new(opt.skill_level)),
+                false => None,
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_skill_level(&self, level: u8) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.skill_level = level,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    // None if the randomization is disabled
+    pub fn equal_moves_seed(&self) -> Option<u64> {
+        match self.engine_options.lock() {
+            Ok(opt) => opt.randomize_equal_moves.then_some(opt.seed),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_randomize_equal_moves(&self, enable: bool) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.randomize_equal_moves = enable,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn set_seed(&self, seed: u64) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.seed = seed.min(MAX_SEED),
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn analyse_mode(&self) -> bool {
+        match self.engine_options.lock() {
+            Ok(opt) => opt.analyse_mode,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_analyse_mode(&self, enable: bool) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.analyse_mode = enable,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn contempt(&self) -> Score {
+        match self.engine_options.lock() {
+            Ok(opt) => opt.contempt,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_contempt(&self, contempt: Score) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.contempt = contempt.clamp(MIN_CONTEMPT, MAX_CONTEMPT),
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn own_book(&self) -> bool {
+        match self.engine_options.lock() {
+            Ok(opt) => opt.own_book,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_own_book(&self, enable: bool) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.own_book = enable,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn book_depth(&self) -> usize {
+        match self.engine_options.lock() {
+            Ok(opt) => opt.book_depth,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_book_depth(&self, depth: usize) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.book_depth = depth.clamp(MIN_BOOK_DEPTH, MAX_BOOK_DEPTH),
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    // The search tree is only traced in debug mode
+    pub fn trace_depth(&self) -> Option<usize> {
+        match self.engine_options.lock() {
+            Ok(opt) => (opt.debug && opt.trace_depth > 0).then_some(opt.trace_depth),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn set_trace_depth(&self, depth: usize) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.trace_depth = depth.min(MAX_TRACE_DEPTH),
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    pub fn set_debug(&self, enable: bool) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.debug = enable,
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    // The log file is opened by the protocol output when it writes the next
+    // line. An empty path disables logging.
+    pub fn set_debug_log_file(&self, path: &str) {
+        match self.engine_options.lock() {
+            Ok(mut opt) => opt.debug_log_file = String::from(path),
+            Err(e) => panic!("{}", e),
+        };
+    }
+
+    // Returns the number of book entries. An empty path removes the book. If
+    // the book can't be read, the previous one is kept.
+    pub fn set_book_file(&mut self, path: &str) -> io::Result<usize> {
+        if path.is_empty() {
+            self.book = None;
+            return Ok(0);
+        }
+        let book = Book::open(Path::new(path))?;
+        let len = book.len();
+        self.book = Some(book);
+        Ok(len)
+    }
+
     pub fn set_position_history(&mut self, pos_hist: Option<PositionHistory>) {
         self.pos_hist = pos_hist;
     }
 
     pub fn clear_position_history(&mut self) {
         self.pos_hist = None;
-        self.searcher.clear_hash_table();
+        self.clear_hash_table();
     }
 
+    // Once a search has been started, exactly one best move is sent for it
     pub fn search(&mut self, options: SearchOptions) -> Result<(), EngineError> {
+        if self.pos_hist.is_none() {
+            return Err(EngineError::SearchWithoutPosition);
+        }
         let mut search_options = options.clone();
         search_options.move_overhead = self.move_overhead();
+        search_options.strength_limit = self.strength_limit();
+        // UCI_LimitStrength takes precedence over the skill level
+        search_options.skill_level = match search_options.strength_limit {
+            Some(_) => None,
+            None => self.skill_level(),
+        };
+        search_options.analyse_mode = self.analyse_mode();
+        search_options.equal_moves_seed = self.equal_moves_seed();
+        search_options.trace_depth = self.trace_depth();
+        // Analysis should show the objective score
+        search_options.contempt = match search_options.analyse_mode {
+            true => 0,
+            false => self.contempt(),
+        };
         self.clear_best_move();
+        let book_move = self.book_move(&options);
         self.set_search_options(options);
-        self.search_with_options(search_options)?;
+        match book_move {
+            Some(m) => self.play_book_move(m),
+            None => self.search_with_options(search_options)?,
+        }
         Ok(())
     }
 
@@ -128,12 +375,22 @@ impl Engine {
         self.searcher.stop();
     }
 
+    pub fn position_history(&self) -> Option<&PositionHistory> {
+        self.pos_hist.as_ref()
+    }
+
     pub fn position(&self) -> Option<&Position> {
         self.pos_hist
             .as_ref()
             .map(|pos_hist| pos_hist.current_pos())
     }
 
+    pub fn position_hash(&self) -> Option<Zobrist> {
+        self.pos_hist
+            .as_ref()
+            .map(|pos_hist| pos_hist.current_pos_hash())
+    }
+
     fn search_with_options(&mut self, search_options: SearchOptions) -> Result<(), EngineError> {
         match &self.pos_hist {
             Some(pos_hist) => {
@@ -145,6 +402,35 @@ impl Engine {
         }
     }
 
+    // Book moves are played instantly, but only in regular games. Infinite
+    // searches, pondering, restricted root moves and analysis are always
+    // searched.
+    fn book_move(&self, options: &SearchOptions) -> Option<Move> {
+        let book = self.book.as_ref()?;
+        let pos_hist = self.pos_hist.as_ref()?;
+        let pos = pos_hist.current_pos();
+        if !self.own_book()
+            || self.analyse_mode()
+            || options.infinite
+            || options.ponder
+            || options.search_moves.is_some()
+            || pos.move_count() > self.book_depth()
+        {
+            return None;
+        }
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos() as u64);
+        book.choose(pos, nanos ^ u64::from(pos_hist.current_pos_hash()))
+    }
+
+    fn play_book_move(&self, m: Move) {
+        self.set_side_to_move(self.position().map(|pos| pos.side_to_move()));
+        self.best_move_sender
+            .send(BestMoveCommand::Stop(StopReason::Finished(m)))
+            .expect("Error sending BestMoveCommand");
+    }
+
     fn clear_best_move(&self) {
         self.searcher.stop();
     }
@@ -170,6 +456,9 @@ impl Engine {
 
 impl Drop for Engine {
     fn drop(&mut self) {
+        // The best move handler is terminated first, so that nothing is
+        // written after the output has been flushed. The search is stopped
+        // afterwards, when the searcher is dropped.
         self.best_move_sender
             .send(BestMoveCommand::Terminate)
             .expect("Error sending BestMoveCommand");
@@ -1,16 +1,56 @@
+use eval::Score;
 use movegen::file::File;
+use search::strength::{MAX_SKILL_LEVEL, MIN_ELO};
 use std::time::Duration;
 
 pub const DEFAULT_HASH_MB: usize = 16;
 pub const DEFAULT_HASH_BYTES: usize = DEFAULT_HASH_MB * 2_usize.pow(20);
+pub const MIN_HASH_MB: usize = 1;
+pub const MAX_HASH_MB: usize = 65536;
 
 pub const DEFAULT_MOVE_OVERHEAD_MILLIS: usize = 10;
 
+pub const DEFAULT_ELO: u16 = MIN_ELO;
+
+// Full strength by default
+pub const DEFAULT_SKILL_LEVEL: u8 = MAX_SKILL_LEVEL;
+
+// In centipawns
+pub const DEFAULT_CONTEMPT: Score = 0;
+pub const MIN_CONTEMPT: Score = -100;
+pub const MAX_CONTEMPT: Score = 100;
+
+// Book moves are only played up to this move number
+pub const DEFAULT_BOOK_DEPTH: usize = 20;
+pub const MIN_BOOK_DEPTH: usize = 1;
+pub const MAX_BOOK_DEPTH: usize = 100;
+
+// Seed for the RandomizeEqualMoves option
+pub const DEFAULT_SEED: u64 = 0;
+pub const MAX_SEED: u64 = u32::MAX as u64;
+
+// The search tree is traced up to this ply in debug mode. 0 disables tracing.
+pub const DEFAULT_TRACE_DEPTH: usize = 0;
+pub const MAX_TRACE_DEPTH: usize = 8;
+
 #[derive(Clone, Debug)]
 pub struct EngineOptions {
     pub hash_size: usize,
     pub move_overhead: Duration,
     pub variant: Variant,
+    pub show_wdl: bool,
+    pub limit_strength: bool,
+    pub elo: u16,
+    pub skill_level: u8,
+    pub analyse_mode: bool,
+    pub contempt: Score,
+    pub own_book: bool,
+    pub book_depth: usize,
+    pub debug_log_file: String,
+    pub debug: bool,
+    pub trace_depth: usize,
+    pub randomize_equal_moves: bool,
+    pub seed: u64,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -25,6 +65,19 @@ impl Default for EngineOptions {
             hash_size: DEFAULT_HASH_BYTES,
             move_overhead: Duration::from_millis(DEFAULT_MOVE_OVERHEAD_MILLIS as u64),
             variant: Variant::Standard,
+            show_wdl: false,
+            limit_strength: false,
+            elo: DEFAULT_ELO,
+            skill_level: DEFAULT_SKILL_LEVEL,
+            analyse_mode: false,
+            contempt: DEFAULT_CONTEMPT,
+            own_book: false,
+            book_depth: DEFAULT_BOOK_DEPTH,
+            debug_log_file: String::new(),
+            debug: false,
+            trace_depth: DEFAULT_TRACE_DEPTH,
+            randomize_equal_moves: false,
+            seed: DEFAULT_SEED,
         }
     }
 }
@@ -1,5 +1,5 @@
 use movegen::r#move::Move;
-use search::search::SearchResult;
+use search::search::{CurrentMove, SearchProgress, SearchResult};
 use std::error::Error;
 
 pub trait EngineOut {
@@ -8,7 +8,16 @@ pub trait EngineOut {
         search_result: Option<SearchResult>,
     ) -> Result<(), Box<dyn Error>>;
 
+    fn info_current_move(&self, current_move: CurrentMove) -> Result<(), Box<dyn Error>>;
+
+    fn info_progress(&self, progress: SearchProgress) -> Result<(), Box<dyn Error>>;
+
+    // One traced node of the search tree (see SearchInfo::Trace)
+    fn trace(&self, line: &str) -> Result<(), Box<dyn Error>>;
+
     fn info_string(&self, s: &str) -> Result<(), Box<dyn Error>>;
 
     fn best_move(&self, search_result: Option<Move>) -> Result<(), Box<dyn Error>>;
+
+    fn flush(&self) -> Result<(), Box<dyn Error>>;
 }
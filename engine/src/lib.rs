@@ -1,10 +1,15 @@
+pub use crate::book::Book;
 pub use crate::engine::{Engine, EngineError};
 pub use crate::engine_options::{
-    EngineOptions, Variant, DEFAULT_HASH_BYTES, DEFAULT_HASH_MB, DEFAULT_MOVE_OVERHEAD_MILLIS,
+    EngineOptions, Variant, DEFAULT_BOOK_DEPTH, DEFAULT_CONTEMPT, DEFAULT_ELO, DEFAULT_HASH_BYTES,
+    DEFAULT_HASH_MB, DEFAULT_MOVE_OVERHEAD_MILLIS, DEFAULT_SEED, DEFAULT_SKILL_LEVEL,
+    DEFAULT_TRACE_DEPTH, MAX_BOOK_DEPTH, MAX_CONTEMPT, MAX_HASH_MB, MAX_SEED, MAX_TRACE_DEPTH,
+    MIN_BOOK_DEPTH, MIN_CONTEMPT, MIN_HASH_MB,
 };
 pub use crate::engine_out::EngineOut;
 
 mod best_move_handler;
+mod book;
 mod engine;
 mod engine_options;
 mod engine_out;
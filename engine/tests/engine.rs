@@ -1,6 +1,6 @@
 mod mock_engine_out;
 
-use crossbeam_channel::unbounded;
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use engine::{Engine, EngineOptions};
 use eval::material_mobility::MaterialMobility;
 use mock_engine_out::MockEngineOut;
@@ -8,6 +8,8 @@ use more_asserts::assert_le;
 use movegen::position::Position;
 use movegen::position_history::PositionHistory;
 use search::alpha_beta::AlphaBeta;
+use search::search::{Search, SearchCommand, SearchInfo};
+use search::search_params::SearchParamsEachAlgo;
 use search::SearchOptions;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -17,6 +19,30 @@ use std::time::Instant;
 const EVALUATOR: MaterialMobility = MaterialMobility::new();
 const TABLE_SIZE: usize = 16 * 1024;
 
+// A search that ignores all commands and never reports a result
+struct UnresponsiveSearch {
+    duration: Duration,
+}
+
+impl Search for UnresponsiveSearch {
+    fn set_hash_size(&mut self, _bytes: usize) {}
+
+    fn clear_hash_table(&mut self) {}
+
+    fn set_params(&mut self, _params: SearchParamsEachAlgo) {}
+
+    fn search(
+        &mut self,
+        _pos_history: PositionHistory,
+        _search_options: SearchOptions,
+        _command_receiver: &Receiver<SearchCommand>,
+        _info_sender: &Sender<SearchInfo>,
+    ) -> Option<SearchCommand> {
+        thread::sleep(self.duration);
+        None
+    }
+}
+
 #[test]
 fn search_timeout() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
@@ -91,3 +117,124 @@ fn search_timeout_aborted() {
     );
     println!("Search time (abort): {:?}", stop.duration_since(start));
 }
+
+#[test]
+fn set_hash_size() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let (sender, receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    let mut engine = Engine::new(
+        search_algo,
+        MockEngineOut::new(
+            Box::new(move |res| {
+                if let Some(r) = res {
+                    info_sender.send(r.hash_load_factor_permille()).unwrap();
+                }
+            }),
+            Box::new(move |res| {
+                sender.send(res).unwrap();
+            }),
+        ),
+        Arc::new(Mutex::new(EngineOptions::default())),
+    );
+    let mb = 2_usize.pow(20);
+
+    // Clamped to the minimum and rounded down to a power of two
+    assert_eq!(mb, engine.set_hash_size(0));
+    assert_eq!(mb, engine.hash_size());
+    assert_eq!(mb, engine.set_hash_size(mb + 1));
+    assert_eq!(4 * mb, engine.set_hash_size(7 * mb));
+    assert_eq!(4 * mb, engine.hash_size());
+
+    // During a search, the new size doesn't block and is applied after the search
+    engine.set_position_history(Some(PositionHistory::new(Position::initial())));
+    assert!(engine
+        .search(SearchOptions {
+            infinite: true,
+            ..Default::default()
+        })
+        .is_ok());
+    // Wait until the search has started filling the table
+    let mut hashfull_before = 0;
+    while hashfull_before == 0 {
+        hashfull_before = info_receiver
+            .recv_timeout(Duration::from_millis(10000))
+            .unwrap();
+    }
+    let start = Instant::now();
+    assert_eq!(2 * mb, engine.set_hash_size(2 * mb));
+    assert_le!(start.elapsed().as_millis(), 100);
+    engine.stop();
+    // Wait until the search has finished
+    while let Ok(None) = receiver.recv_timeout(Duration::from_millis(1000)) {}
+    assert_eq!(2 * mb, engine.hash_size());
+
+    // The search has filled the small table. The new table is empty.
+    let hashfull_before = info_receiver.try_iter().last().unwrap_or(hashfull_before);
+    assert!(engine
+        .search(SearchOptions {
+            depth: Some(1),
+            ..Default::default()
+        })
+        .is_ok());
+    assert!(matches!(
+        receiver.recv_timeout(Duration::from_millis(1000)),
+        Ok(Some(_))
+    ));
+    let hashfull_after = info_receiver.try_iter().last().unwrap();
+    assert!(hashfull_after < hashfull_before);
+}
+
+#[test]
+fn drop_during_infinite_search() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let (sender, receiver) = unbounded();
+    let mut engine = Engine::new(
+        search_algo,
+        MockEngineOut::new(
+            Box::new(|_res| {}),
+            Box::new(move |res| {
+                sender.send(res).unwrap();
+            }),
+        ),
+        Arc::new(Mutex::new(EngineOptions::default())),
+    );
+    engine.set_position_history(Some(PositionHistory::new(Position::initial())));
+    assert!(engine
+        .search(SearchOptions {
+            infinite: true,
+            ..Default::default()
+        })
+        .is_ok());
+    thread::sleep(Duration::from_millis(200));
+
+    // The search is aborted without sending a best move
+    let start = Instant::now();
+    drop(engine);
+    assert_le!(start.elapsed().as_millis(), 200);
+    assert_eq!(0, receiver.try_iter().count());
+}
+
+#[test]
+fn drop_with_unresponsive_search() {
+    let search_algo = UnresponsiveSearch {
+        duration: Duration::from_secs(60),
+    };
+    let mut engine = Engine::new(
+        search_algo,
+        MockEngineOut::new(Box::new(|_res| {}), Box::new(|_res| {})),
+        Arc::new(Mutex::new(EngineOptions::default())),
+    );
+    engine.set_position_history(Some(PositionHistory::new(Position::initial())));
+    assert!(engine
+        .search(SearchOptions {
+            infinite: true,
+            ..Default::default()
+        })
+        .is_ok());
+
+    // The search thread is detached after a timeout
+    let start = Instant::now();
+    drop(engine);
+    assert_le!(start.elapsed().as_millis(), 2000);
+}
@@ -1,6 +1,6 @@
 use engine::EngineOut;
 use movegen::r#move::Move;
-use search::search::SearchResult;
+use search::search::{CurrentMove, SearchProgress, SearchResult};
 use std::error::Error;
 
 pub struct MockEngineOut {
@@ -20,6 +20,18 @@ impl EngineOut for MockEngineOut {
         Ok(())
     }
 
+    fn info_current_move(&self, _current_move: CurrentMove) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn info_progress(&self, _progress: SearchProgress) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn trace(&self, _line: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
     fn info_string(&self, _s: &str) -> Result<(), Box<dyn Error>> {
         Ok(())
     }
@@ -28,6 +40,10 @@ impl EngineOut for MockEngineOut {
         (self.best_move_callback)(search_result);
         Ok(())
     }
+
+    fn flush(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
 }
 
 impl MockEngineOut {
@@ -0,0 +1,114 @@
+use movegen::{
+    bishop::Bishop, bitboard::Bitboard, knight::Knight, pawn::Pawn, piece, position::Position,
+    queen::Queen, rook::Rook, side::Side,
+};
+
+// A side can't have more pieces than this, excluding the king
+const MAX_PIECES: usize = 15;
+
+// Squares attacked by the pawns and pieces (excluding the king) of one side.
+// They are generated once per evaluation and shared by the mobility and the
+// king safety.
+#[derive(Debug, Clone, Copy)]
+pub struct Attacks {
+    pawns: Bitboard,
+    // Targets of each knight, bishop, rook and queen, in this order
+    piece_targets: [(piece::Type, Bitboard); MAX_PIECES],
+    piece_count: usize,
+    all: Bitboard,
+}
+
+impl Attacks {
+    pub fn new(pos: &Position, side: Side) -> Self {
+        let occupancy = pos.occupancy();
+        let pawns = Pawn::attack_targets(pos.piece_occupancy(side, piece::Type::Pawn), side);
+        let mut attacks = Self {
+            pawns,
+            piece_targets: [(piece::Type::Knight, Bitboard::EMPTY); MAX_PIECES],
+            piece_count: 0,
+            all: pawns,
+        };
+        for piece_type in [
+            piece::Type::Knight,
+            piece::Type::Bishop,
+            piece::Type::Rook,
+            piece::Type::Queen,
+        ] {
+            let mut pieces = pos.piece_occupancy(side, piece_type);
+            while pieces != Bitboard::EMPTY {
+                let origin = pieces.square_scan_forward_reset();
+                let targets = match piece_type {
+                    piece::Type::Knight => Knight::targets(origin),
+                    piece::Type::Bishop => Bishop::targets(origin, occupancy),
+                    piece::Type::Rook => Rook::targets(origin, occupancy),
+                    _ => Queen::targets(origin, occupancy),
+                };
+                attacks.piece_targets[attacks.piece_count] = (piece_type, targets);
+                attacks.piece_count += 1;
+                attacks.all |= targets;
+            }
+        }
+        attacks
+    }
+
+    // Attacks of both sides, indexed by side
+    pub fn both(pos: &Position) -> [Self; 2] {
+        [Self::new(pos, Side::White), Self::new(pos, Side::Black)]
+    }
+
+    pub fn pawns(&self) -> Bitboard {
+        self.pawns
+    }
+
+    pub fn piece_targets(&self) -> &[(piece::Type, Bitboard)] {
+        &self.piece_targets[..self.piece_count]
+    }
+
+    // All squares attacked by the pawns and pieces
+    pub fn all(&self) -> Bitboard {
+        self.all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use movegen::{bitboard::Bitboard, fen::Fen, piece, side::Side, square::Square};
+
+    use super::Attacks;
+
+    #[test]
+    fn attacks() {
+        let pos = Fen::str_to_pos("4k3/8/8/8/3N4/8/P1P5/RB2K3 w - - 0 1").unwrap();
+        let attacks = Attacks::both(&pos);
+        let white = &attacks[Side::White as usize];
+        assert_eq!(
+            Bitboard::from_square(Square::B3) | Bitboard::from_square(Square::D3),
+            white.pawns()
+        );
+        // The pieces are ordered by type. The bishop is blocked by the pawns
+        // and the rook by a pawn and the bishop.
+        let types: Vec<_> = white.piece_targets().iter().map(|(t, _)| *t).collect();
+        assert_eq!(
+            vec![piece::Type::Knight, piece::Type::Bishop, piece::Type::Rook],
+            types
+        );
+        assert_eq!(8, white.piece_targets()[0].1.pop_count());
+        assert_eq!(
+            Bitboard::from_square(Square::A2) | Bitboard::from_square(Square::C2),
+            white.piece_targets()[1].1
+        );
+        assert_eq!(
+            Bitboard::from_square(Square::A2) | Bitboard::from_square(Square::B1),
+            white.piece_targets()[2].1
+        );
+        let attacked = |square| white.all() & Bitboard::from_square(square) != Bitboard::EMPTY;
+        assert!(attacked(Square::D3));
+        assert!(attacked(Square::E6));
+        // The king doesn't count
+        assert!(!attacked(Square::D1));
+
+        let black = &attacks[Side::Black as usize];
+        assert_eq!(Bitboard::EMPTY, black.all());
+        assert!(black.piece_targets().is_empty());
+    }
+}
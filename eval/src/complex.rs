@@ -1,60 +1,42 @@
+use crate::attacks::Attacks;
 use crate::eval::HasMatingMaterial;
 use crate::game_phase::{GamePhase, PieceCounts};
+use crate::king_safety::KingSafety;
+use crate::kpk::Kpk;
 use crate::mobility::Mobility;
 use crate::params;
+use crate::pawn_hash_table::PawnHashTable;
 use crate::pawn_structure::PawnStructure;
+use crate::piece_placement::PiecePlacement;
+use crate::scale_factor::ScaleFactor;
 use crate::score_pair::ScorePair;
+use crate::trace::{EvalRecorder, EvalTrace, NoTrace, Term};
 use crate::{Eval, Score, EQ_POSITION};
 use movegen::bitboard::Bitboard;
 use movegen::piece::{self, Piece};
 use movegen::position::Position;
 use movegen::side::Side;
+use movegen::zobrist::Zobrist;
 
 #[derive(Debug, Clone)]
 pub struct Complex {
     current_pos: Position,
     game_phase: GamePhase,
     piece_counts: PieceCounts,
-    pst_scores: ScorePair,
     pawn_structure: PawnStructure,
     mobility: Mobility,
-    king_tropism: ScorePair,
+    king_safety: KingSafety,
+    // Indexed by the side of the king the distances are measured to
+    king_tropism: [ScorePair; 2],
 }
 
 impl Eval for Complex {
     fn eval(&mut self, pos: &Position) -> Score {
-        self.update(pos);
-
-        let white_mating_material = self.has_mating_material(Side::White);
-        let black_mating_material = self.has_mating_material(Side::Black);
-        if !white_mating_material && !black_mating_material {
-            return EQ_POSITION;
-        }
-
-        let tempo_multiplier = 1 - 2 * (pos.side_to_move() as i16);
-        let tempo_scores = tempo_multiplier * params::TEMPO;
-        self.pawn_structure.update(pos);
-        let pawn_scores = self.pawn_structure.scores();
-        let mobility_scores = self.mobility.scores(pos);
-        let bishop_pair_scores = Self::bishop_pair_factor(pos) * params::BISHOP_PAIR;
-        let scores = self.pst_scores
-            + tempo_scores
-            + pawn_scores
-            + mobility_scores
-            + bishop_pair_scores
-            + self.king_tropism;
-        let game_phase = self.game_phase.game_phase_clamped();
-        let tapered_score = ((game_phase as i64 * scores.0 as i64
-            + (GamePhase::MAX - game_phase) as i64 * scores.1 as i64)
-            / GamePhase::MAX as i64) as Score;
+        self.eval_recorded(pos, Zobrist::pawns(pos), &mut NoTrace)
+    }
 
-        if !white_mating_material {
-            std::cmp::min(EQ_POSITION, tapered_score)
-        } else if !black_mating_material {
-            std::cmp::max(EQ_POSITION, tapered_score)
-        } else {
-            tapered_score
-        }
+    fn eval_with_pawn_hash(&mut self, pos: &Position, pawn_hash: Zobrist) -> Score {
+        self.eval_recorded(pos, pawn_hash, &mut NoTrace)
     }
 }
 
@@ -107,13 +89,155 @@ impl Complex {
             current_pos: Position::empty(),
             game_phase: Default::default(),
             piece_counts: Default::default(),
-            pst_scores: ScorePair(0, 0),
             pawn_structure: PawnStructure::new(),
             mobility: Mobility,
-            king_tropism: ScorePair(0, 0),
+            king_safety: KingSafety,
+            king_tropism: [ScorePair(0, 0); 2],
         }
     }
 
+    // Evaluate the position and record the contribution of each term. The
+    // traced score is the same as the one returned by eval.
+    pub fn eval_trace(&mut self, pos: &Position) -> EvalTrace {
+        let mut trace = EvalTrace::default();
+        self.eval_recorded(pos, Zobrist::pawns(pos), &mut trace);
+        trace
+    }
+
+    pub fn pawn_hash_table(&self) -> &PawnHashTable {
+        self.pawn_structure.table()
+    }
+
+    fn eval_recorded<R: EvalRecorder>(
+        &mut self,
+        pos: &Position,
+        pawn_hash: Zobrist,
+        recorder: &mut R,
+    ) -> Score {
+        self.update(pos);
+
+        let white_mating_material = self.has_mating_material(Side::White);
+        let black_mating_material = self.has_mating_material(Side::Black);
+        if !white_mating_material && !black_mating_material && !R::ENABLED {
+            return EQ_POSITION;
+        }
+
+        let tempo_multiplier = 1 - 2 * (pos.side_to_move() as i16);
+        let tempo_scores = tempo_multiplier * params::TEMPO;
+        self.pawn_structure.update(pos, pawn_hash);
+        let pawn_scores = self.pawn_structure.scores();
+        let attacks = Attacks::both(pos);
+        let mobility_scores = [
+            self.mobility.scores_one_side(pos, Side::White, &attacks),
+            self.mobility.scores_one_side(pos, Side::Black, &attacks),
+        ];
+        let bishop_pair_scores = Self::bishop_pair_factor(pos) * params::BISHOP_PAIR;
+        let rook_file_scores = [
+            PiecePlacement::rook_file_scores_one_side(pos, Side::White),
+            PiecePlacement::rook_file_scores_one_side(pos, Side::Black),
+        ];
+        let rook_seventh_scores = [
+            PiecePlacement::rook_seventh_scores_one_side(pos, Side::White),
+            PiecePlacement::rook_seventh_scores_one_side(pos, Side::Black),
+        ];
+        let outpost_scores = [
+            PiecePlacement::outpost_scores_one_side(pos, Side::White),
+            PiecePlacement::outpost_scores_one_side(pos, Side::Black),
+        ];
+        let king_safety_scores = [
+            self.king_safety.scores_one_side(pos, Side::White, &attacks),
+            self.king_safety.scores_one_side(pos, Side::Black, &attacks),
+        ];
+        // Material and piece-square scores are kept up to date by the position
+        let pst_scores = [
+            ScorePair::from(pos.piece_square_score(Side::White)),
+            ScorePair::from(pos.piece_square_score(Side::Black)),
+        ];
+        let scores = pst_scores[Side::White as usize] - pst_scores[Side::Black as usize]
+            + tempo_scores
+            + pawn_scores
+            + mobility_scores[Side::White as usize]
+            - mobility_scores[Side::Black as usize]
+            + bishop_pair_scores
+            + rook_file_scores[Side::White as usize]
+            - rook_file_scores[Side::Black as usize]
+            + rook_seventh_scores[Side::White as usize]
+            - rook_seventh_scores[Side::Black as usize]
+            + outpost_scores[Side::White as usize]
+            - outpost_scores[Side::Black as usize]
+            + self.king_tropism[Side::White as usize]
+            - self.king_tropism[Side::Black as usize]
+            + king_safety_scores[Side::White as usize]
+            - king_safety_scores[Side::Black as usize];
+        let game_phase = self.game_phase.game_phase_clamped();
+        let tapered_score = ((game_phase as i64 * scores.0 as i64
+            + (GamePhase::MAX - game_phase) as i64 * scores.1 as i64)
+            / GamePhase::MAX as i64) as Score;
+        let scale_factor = ScaleFactor::scale_factor(pos);
+        let scaled_score = (tapered_score as i32 * scale_factor as i32
+            / params::SCALE_FACTOR_NORMAL as i32) as Score;
+
+        // KPK is solved exactly by the bitbase
+        let score = if Kpk::is_kpk(pos) {
+            Kpk::score(pos)
+        } else if !white_mating_material && !black_mating_material {
+            EQ_POSITION
+        } else if !white_mating_material {
+            std::cmp::min(EQ_POSITION, scaled_score)
+        } else if !black_mating_material {
+            std::cmp::max(EQ_POSITION, scaled_score)
+        } else {
+            scaled_score
+        };
+
+        if R::ENABLED {
+            let [white, black] = [Side::White, Side::Black];
+            recorder.record(
+                Term::PieceSquare,
+                pst_scores[white as usize],
+                pst_scores[black as usize],
+            );
+            let tempo = |s: Side| (pos.side_to_move() == s) as Score * params::TEMPO;
+            recorder.record(Term::Tempo, tempo(white), tempo(black));
+            self.pawn_structure.record(recorder);
+            recorder.record(
+                Term::Mobility,
+                mobility_scores[white as usize],
+                mobility_scores[black as usize],
+            );
+            let bishop_pair = |s: Side| pos.has_bishop_pair(s) as Score * params::BISHOP_PAIR;
+            recorder.record(Term::BishopPair, bishop_pair(white), bishop_pair(black));
+            recorder.record(
+                Term::RookFile,
+                rook_file_scores[white as usize],
+                rook_file_scores[black as usize],
+            );
+            recorder.record(
+                Term::RookSeventh,
+                rook_seventh_scores[white as usize],
+                rook_seventh_scores[black as usize],
+            );
+            recorder.record(
+                Term::Outpost,
+                outpost_scores[white as usize],
+                outpost_scores[black as usize],
+            );
+            recorder.record(
+                Term::KingTropism,
+                self.king_tropism[white as usize],
+                self.king_tropism[black as usize],
+            );
+            recorder.record(
+                Term::KingSafety,
+                king_safety_scores[white as usize],
+                king_safety_scores[black as usize],
+            );
+            recorder.record_result(game_phase, tapered_score, scale_factor, score);
+        }
+
+        score
+    }
+
     fn update(&mut self, pos: &Position) {
         let old_white_king = self
             .current_pos
@@ -125,40 +249,34 @@ impl Complex {
         let new_black_king = pos.piece_occupancy(Side::Black, piece::Type::King);
         let white_king = new_white_king.to_square();
         let black_king = new_black_king.to_square();
-        for (piece_type, table, friendly_distance, enemy_distance) in [
+        for (piece_type, friendly_distance, enemy_distance) in [
             (
                 piece::Type::Pawn,
-                &params::PST_PAWN,
                 &params::DISTANCE_FRIENDLY_PAWN,
                 &params::DISTANCE_ENEMY_PAWN,
             ),
             (
                 piece::Type::Knight,
-                &params::PST_KNIGHT,
                 &params::DISTANCE_FRIENDLY_KNIGHT,
                 &params::DISTANCE_ENEMY_KNIGHT,
             ),
             (
                 piece::Type::Bishop,
-                &params::PST_BISHOP,
                 &params::DISTANCE_FRIENDLY_BISHOP,
                 &params::DISTANCE_ENEMY_BISHOP,
             ),
             (
                 piece::Type::Rook,
-                &params::PST_ROOK,
                 &params::DISTANCE_FRIENDLY_ROOK,
                 &params::DISTANCE_ENEMY_ROOK,
             ),
             (
                 piece::Type::Queen,
-                &params::PST_QUEEN,
                 &params::DISTANCE_FRIENDLY_QUEEN,
                 &params::DISTANCE_ENEMY_QUEEN,
             ),
             (
                 piece::Type::King,
-                &params::PST_KING,
                 &params::DISTANCE_FRIENDLY_KING,
                 &params::DISTANCE_ENEMY_KING,
             ),
@@ -169,20 +287,22 @@ impl Complex {
             let mut white_add = new_white & !old_white;
             while white_remove != Bitboard::EMPTY {
                 let square = white_remove.square_scan_forward_reset();
-                self.pst_scores -= table[square.idx()];
                 self.game_phase.remove_piece(piece_type);
                 self.piece_counts
                     .remove(Piece::new(Side::White, piece_type));
-                self.king_tropism -= friendly_distance[white_king.distance(square)];
-                self.king_tropism += enemy_distance[black_king.distance(square)];
+                self.king_tropism[Side::White as usize] -=
+                    friendly_distance[white_king.distance(square)];
+                self.king_tropism[Side::Black as usize] -=
+                    enemy_distance[black_king.distance(square)];
             }
             while white_add != Bitboard::EMPTY {
                 let square = white_add.square_scan_forward_reset();
-                self.pst_scores += table[square.idx()];
                 self.game_phase.add_piece(piece_type);
                 self.piece_counts.add(Piece::new(Side::White, piece_type));
-                self.king_tropism += friendly_distance[white_king.distance(square)];
-                self.king_tropism -= enemy_distance[black_king.distance(square)];
+                self.king_tropism[Side::White as usize] +=
+                    friendly_distance[white_king.distance(square)];
+                self.king_tropism[Side::Black as usize] +=
+                    enemy_distance[black_king.distance(square)];
             }
             let old_black = self.current_pos.piece_occupancy(Side::Black, piece_type);
             let new_black = pos.piece_occupancy(Side::Black, piece_type);
@@ -190,22 +310,22 @@ impl Complex {
             let mut black_add = new_black & !old_black;
             while black_remove != Bitboard::EMPTY {
                 let square = black_remove.square_scan_forward_reset();
-                let square_flipped = square.flip_vertical();
-                self.pst_scores += table[square_flipped.idx()];
                 self.game_phase.remove_piece(piece_type);
                 self.piece_counts
                     .remove(Piece::new(Side::Black, piece_type));
-                self.king_tropism -= enemy_distance[white_king.distance(square)];
-                self.king_tropism += friendly_distance[black_king.distance(square)];
+                self.king_tropism[Side::White as usize] -=
+                    enemy_distance[white_king.distance(square)];
+                self.king_tropism[Side::Black as usize] -=
+                    friendly_distance[black_king.distance(square)];
             }
             while black_add != Bitboard::EMPTY {
                 let square = black_add.square_scan_forward_reset();
-                let square_flipped = square.flip_vertical();
-                self.pst_scores -= table[square_flipped.idx()];
                 self.game_phase.add_piece(piece_type);
                 self.piece_counts.add(Piece::new(Side::Black, piece_type));
-                self.king_tropism += enemy_distance[white_king.distance(square)];
-                self.king_tropism -= friendly_distance[black_king.distance(square)];
+                self.king_tropism[Side::White as usize] +=
+                    enemy_distance[white_king.distance(square)];
+                self.king_tropism[Side::Black as usize] +=
+                    friendly_distance[black_king.distance(square)];
             }
         }
         if old_white_king != new_white_king || old_black_king != new_black_king {
@@ -213,6 +333,11 @@ impl Complex {
             self.king_tropism(pos);
         }
         self.current_pos = pos.clone();
+
+        // The game phase and counts are updated incrementally. Make sure they
+        // match a full recomputation.
+        debug_assert_eq!(GamePhase::new(pos), self.game_phase);
+        debug_assert_eq!(PieceCounts::new(pos), self.piece_counts);
     }
 
     fn king_tropism(&mut self, pos: &Position) {
@@ -222,7 +347,7 @@ impl Complex {
         let black_king = pos
             .piece_occupancy(Side::Black, piece::Type::King)
             .to_square();
-        self.king_tropism = ScorePair(0, 0);
+        self.king_tropism = [ScorePair(0, 0); 2];
         for (piece_type, friendly_distance, enemy_distance) in [
             (
                 piece::Type::Pawn,
@@ -258,14 +383,18 @@ impl Complex {
             let mut white_pieces = pos.piece_occupancy(Side::White, piece_type);
             while white_pieces != Bitboard::EMPTY {
                 let square = white_pieces.square_scan_forward_reset();
-                self.king_tropism += friendly_distance[white_king.distance(square)];
-                self.king_tropism -= enemy_distance[black_king.distance(square)];
+                self.king_tropism[Side::White as usize] +=
+                    friendly_distance[white_king.distance(square)];
+                self.king_tropism[Side::Black as usize] +=
+                    enemy_distance[black_king.distance(square)];
             }
             let mut black_pieces = pos.piece_occupancy(Side::Black, piece_type);
             while black_pieces != Bitboard::EMPTY {
                 let square = black_pieces.square_scan_forward_reset();
-                self.king_tropism += enemy_distance[white_king.distance(square)];
-                self.king_tropism -= friendly_distance[black_king.distance(square)];
+                self.king_tropism[Side::White as usize] +=
+                    enemy_distance[white_king.distance(square)];
+                self.king_tropism[Side::Black as usize] +=
+                    friendly_distance[black_king.distance(square)];
             }
         }
     }
@@ -278,10 +407,17 @@ impl Complex {
 #[cfg(test)]
 mod tests {
     use movegen::fen::Fen;
+    use movegen::move_generator::MoveGenerator;
+    use movegen::position_history::PositionHistory;
+    use movegen::r#move::MoveList;
 
-    use crate::{Eval, EQ_POSITION};
+    use crate::{Eval, Score, EQ_POSITION};
 
     use super::Complex;
+    use crate::kpk::Kpk;
+    use crate::params;
+    use crate::trace::Term;
+    use crate::GamePhase;
 
     #[test]
     fn draw_by_insufficient_material() {
@@ -325,4 +461,148 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn trace_matches_eval() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "7k/8/8/8/3KN3/8/8/8 w - - 0 1",
+            "7k/4B3/5p2/5K2/8/8/8/8 w - - 4 102",
+            "4k3/5p2/2b5/8/8/2B5/5PP1/4K3 w - - 0 1",
+            "k7/8/K7/P7/8/8/8/8 w - - 0 1",
+            "8/8/8/8/4p3/4k3/8/4K3 b - - 0 1",
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let score = Complex::new().eval(&pos);
+            // The trace must not depend on the previously evaluated position
+            let mut evaluator = Complex::new();
+            evaluator.eval(&Fen::str_to_pos("4k3/8/8/8/8/8/8/RN2K3 w - - 0 1").unwrap());
+            let trace = evaluator.eval_trace(&pos);
+            assert_eq!(score, trace.score(), "\nPosition: {fen}\n{trace}");
+            assert_eq!(14, trace.terms().len());
+            assert_eq!(Term::PieceSquare, trace.terms()[0].0);
+
+            let total = trace.total();
+            let phase = trace.game_phase() as i64;
+            let tapered = ((phase * total.0 as i64
+                + (GamePhase::MAX as i64 - phase) * total.1 as i64)
+                / GamePhase::MAX as i64) as Score;
+            assert_eq!(tapered, trace.blended_score(), "\nPosition: {fen}\n{trace}");
+            let scaled = (tapered as i32 * trace.scale_factor() as i32
+                / params::SCALE_FACTOR_NORMAL as i32) as Score;
+            // Positions without mating material and KPK endgames can override
+            // the scaled score
+            if score != EQ_POSITION && !Kpk::is_kpk(&pos) {
+                assert_eq!(scaled, score, "\nPosition: {fen}\n{trace}");
+            }
+        }
+    }
+
+    #[test]
+    fn bishop_pair() {
+        // White has the bishop pair, black has bishop and knight. The second
+        // position is the first one mirrored, with the colors swapped.
+        let bishop_pair = "4k3/pp3ppp/3nb3/8/8/3BB3/PP3PPP/4K3 w - - 0 1";
+        let mirrored = "4k3/pp3ppp/3bb3/8/8/3NB3/PP3PPP/4K3 b - - 0 1";
+
+        let mut evaluator = Complex::new();
+        let pos = Fen::str_to_pos(bishop_pair).unwrap();
+        let trace = evaluator.eval_trace(&pos);
+        let (_, white, black) = trace
+            .terms()
+            .iter()
+            .find(|(term, _, _)| *term == Term::BishopPair)
+            .copied()
+            .unwrap();
+        assert_eq!(
+            (params::BISHOP_PAIR.0, params::BISHOP_PAIR.1),
+            (white.0, white.1)
+        );
+        assert_eq!((0, 0), (black.0, black.1));
+
+        let score = evaluator.eval(&pos);
+        let mirrored_score = evaluator.eval(&Fen::str_to_pos(mirrored).unwrap());
+        assert_eq!(score, -mirrored_score);
+        assert!(score > 0);
+    }
+
+    #[test]
+    fn tempo() {
+        // In the middlegame, the side to move gets the tempo bonus
+        let mut evaluator = Complex::new();
+        let white_to_move = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let black_to_move = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1";
+        let white_score = evaluator.eval(&Fen::str_to_pos(white_to_move).unwrap());
+        let black_score = evaluator.eval(&Fen::str_to_pos(black_to_move).unwrap());
+        assert_eq!(2 * params::TEMPO.0, white_score - black_score);
+
+        // In a pawn endgame, having the move is worth nothing
+        let white_to_move = "4k3/pp3ppp/8/8/8/8/PP3PPP/4K3 w - - 0 1";
+        let black_to_move = "4k3/pp3ppp/8/8/8/8/PP3PPP/4K3 b - - 0 1";
+        let white_score = evaluator.eval(&Fen::str_to_pos(white_to_move).unwrap());
+        let black_score = evaluator.eval(&Fen::str_to_pos(black_to_move).unwrap());
+        assert_eq!(white_score, black_score);
+    }
+
+    #[test]
+    fn kpk() {
+        let mut evaluator = Complex::new();
+        // Drawn, although white is a pawn up
+        let pos = Fen::str_to_pos("k7/8/K7/P7/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(EQ_POSITION, evaluator.eval(&pos));
+        // Won for black
+        let pos = Fen::str_to_pos("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1").unwrap();
+        let score = evaluator.eval(&pos);
+        assert_eq!(Kpk::score(&pos), score);
+        assert!(score < EQ_POSITION);
+    }
+
+    #[test]
+    fn opposite_colored_bishops() {
+        // White is two pawns up in both positions, but with opposite-colored
+        // bishops the advantage is scaled down
+        let mut evaluator = Complex::new();
+        let same_colored = "4k3/5p2/3b4/8/8/2B5/P4PPP/4K3 w - - 0 1";
+        let opposite_colored = "4k3/5p2/2b5/8/8/2B5/P4PPP/4K3 w - - 0 1";
+        let trace = evaluator.eval_trace(&Fen::str_to_pos(same_colored).unwrap());
+        assert_eq!(params::SCALE_FACTOR_NORMAL, trace.scale_factor());
+        let trace = evaluator.eval_trace(&Fen::str_to_pos(opposite_colored).unwrap());
+        assert!(trace.scale_factor() < params::SCALE_FACTOR_NORMAL);
+        assert!(trace.score() > EQ_POSITION);
+        assert!(
+            evaluator.eval(&Fen::str_to_pos(opposite_colored).unwrap())
+                < evaluator.eval(&Fen::str_to_pos(same_colored).unwrap())
+        );
+    }
+
+    fn eval_tree(evaluator: &mut Complex, pos_history: &mut PositionHistory, depth: usize) {
+        evaluator.eval_with_pawn_hash(pos_history.current_pos(), pos_history.current_pawn_hash());
+        if depth == 0 {
+            return;
+        }
+        let mut move_list = MoveList::new();
+        MoveGenerator::generate_moves(&mut move_list, pos_history.current_pos());
+        for m in move_list.iter() {
+            pos_history.do_move(*m);
+            eval_tree(evaluator, pos_history, depth - 1);
+            pos_history.undo_last_move();
+        }
+    }
+
+    #[test]
+    fn pawn_hash_table_hit_rate() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut pos_history = PositionHistory::new(Fen::str_to_pos(fen).unwrap());
+        let mut evaluator = Complex::new();
+        eval_tree(&mut evaluator, &mut pos_history, 3);
+        let table = evaluator.pawn_hash_table();
+        assert!(table.probes() > 1000);
+        assert!(
+            table.hit_rate_permille() > 950,
+            "Hit rate: {} permille",
+            table.hit_rate_permille()
+        );
+    }
 }
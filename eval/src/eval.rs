@@ -1,17 +1,46 @@
 use crate::Score;
-use movegen::{position::Position, side::Side};
+use movegen::{position::Position, side::Side, zobrist::Zobrist};
 
-pub trait Eval {
+pub trait Eval: EvalClone {
     fn eval(&mut self, pos: &Position) -> Score;
 
+    // Like eval, with the Zobrist key of the pawns maintained by the caller.
+    // Evaluators with a pawn hash table use it for the lookup.
+    fn eval_with_pawn_hash(&mut self, pos: &Position, _pawn_hash: Zobrist) -> Score {
+        self.eval(pos)
+    }
+
     fn eval_relative(&mut self, pos: &Position) -> Score {
         match pos.side_to_move() {
             Side::White => self.eval(pos),
             Side::Black => -self.eval(pos),
         }
     }
+
+    fn eval_relative_with_pawn_hash(&mut self, pos: &Position, pawn_hash: Zobrist) -> Score {
+        match pos.side_to_move() {
+            Side::White => self.eval_with_pawn_hash(pos, pawn_hash),
+            Side::Black => -self.eval_with_pawn_hash(pos, pawn_hash),
+        }
+    }
 }
 
 pub trait HasMatingMaterial {
     fn has_mating_material(&self, s: Side) -> bool;
 }
+
+// Each search thread needs its own evaluator. Implemented for every
+// evaluator that is Clone, so evaluators don't need to implement it
+// themselves.
+pub trait EvalClone {
+    fn clone_box(&self) -> Box<dyn Eval + Send>;
+}
+
+impl<T> EvalClone for T
+where
+    T: Eval + Clone + Send + 'static,
+{
+    fn clone_box(&self) -> Box<dyn Eval + Send> {
+        Box::new(self.clone())
+    }
+}
@@ -1,6 +1,8 @@
 use std::cmp;
 
 use movegen::piece::{self, Piece};
+use movegen::position::Position;
+use movegen::side::Side;
 
 const KING_PHASE: usize = 0;
 const QUEEN_PHASE: usize = 4;
@@ -9,7 +11,7 @@ const BISHOP_PHASE: usize = 1;
 const KNIGHT_PHASE: usize = 1;
 const PAWN_PHASE: usize = 0;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct GamePhase(usize);
 
 impl GamePhase {
@@ -21,6 +23,21 @@ impl GamePhase {
             + 2 * KNIGHT_PHASE
             + 8 * PAWN_PHASE);
 
+    pub fn new(pos: &Position) -> Self {
+        let mut game_phase = Self::default();
+        for piece_type in [
+            piece::Type::Knight,
+            piece::Type::Bishop,
+            piece::Type::Rook,
+            piece::Type::Queen,
+        ] {
+            for _ in 0..pos.piece_type_occupancy(piece_type).pop_count() {
+                game_phase.add_piece(piece_type);
+            }
+        }
+        game_phase
+    }
+
     pub fn game_phase_clamped(&self) -> usize {
         cmp::min(Self::MAX, self.0)
     }
@@ -48,10 +65,28 @@ impl GamePhase {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct PieceCounts([usize; Piece::NUM_PIECES]);
 
 impl PieceCounts {
+    pub fn new(pos: &Position) -> Self {
+        let mut piece_counts = Self::default();
+        for side in [Side::White, Side::Black] {
+            for piece_type in [
+                piece::Type::Pawn,
+                piece::Type::Knight,
+                piece::Type::Bishop,
+                piece::Type::Rook,
+                piece::Type::Queen,
+                piece::Type::King,
+            ] {
+                piece_counts.0[Piece::new(side, piece_type).idx()] =
+                    pos.piece_occupancy(side, piece_type).pop_count();
+            }
+        }
+        piece_counts
+    }
+
     pub fn count(&self, p: Piece) -> usize {
         self.0[p.idx()]
     }
@@ -0,0 +1,114 @@
+use movegen::{bitboard::Bitboard, king::King, piece, position::Position, side::Side};
+
+use crate::{attacks::Attacks, params, score_pair::ScorePair};
+
+// King danger based on the attacks of the enemy pieces on the king ring (the
+// king square and the squares next to it). The danger index accumulates
+// weighted attacks, attacked ring squares without a defender and open files
+// next to the king. It is mapped through a non-linear table to a penalty.
+#[derive(Debug, Clone, Default)]
+pub struct KingSafety;
+
+impl KingSafety {
+    // King safety scores of one side, from that side's point of view. The
+    // attacks are indexed by side.
+    pub fn scores_one_side(&self, pos: &Position, side: Side, attacks: &[Attacks; 2]) -> ScorePair {
+        params::KING_DANGER[Self::danger_index(pos, side, attacks)]
+    }
+
+    // Danger index of the king of the given side, clamped to the size of the
+    // king danger table
+    pub fn danger_index(pos: &Position, side: Side, attacks: &[Attacks; 2]) -> usize {
+        let king = pos.piece_occupancy(side, piece::Type::King);
+        if king == Bitboard::EMPTY {
+            return 0;
+        }
+        let king_square = king.to_square();
+        let king_ring = King::targets(king_square) | king;
+
+        let mut attacker_count = 0;
+        let mut attack_weight = 0;
+        let mut attacked = Bitboard::EMPTY;
+        for (piece_type, targets) in attacks[!side as usize].piece_targets() {
+            let ring_attacks = *targets & king_ring;
+            if ring_attacks != Bitboard::EMPTY {
+                let weight = match piece_type {
+                    piece::Type::Knight => params::KING_ATTACK_WEIGHT_KNIGHT,
+                    piece::Type::Bishop => params::KING_ATTACK_WEIGHT_BISHOP,
+                    piece::Type::Rook => params::KING_ATTACK_WEIGHT_ROOK,
+                    _ => params::KING_ATTACK_WEIGHT_QUEEN,
+                };
+                attacker_count += 1;
+                attack_weight += weight * ring_attacks.pop_count();
+                attacked |= ring_attacks;
+            }
+        }
+        // A single attacker is rarely dangerous
+        if attacker_count < params::KING_ATTACKER_COUNT_MIN {
+            return 0;
+        }
+
+        // Defended by the pawns and pieces, excluding the king
+        let undefended = attacked & !attacks[side as usize].all();
+        let own_pawns = pos.piece_occupancy(side, piece::Type::Pawn);
+        let open_file_count = [king, king.east_one(), king.west_one()]
+            .iter()
+            .filter(|file| {
+                **file != Bitboard::EMPTY && file.file_fill() & own_pawns == Bitboard::EMPTY
+            })
+            .count();
+        let danger = attack_weight
+            + params::KING_DANGER_UNDEFENDED * undefended.pop_count()
+            + params::KING_DANGER_OPEN_FILE * open_file_count;
+        danger.min(params::KING_DANGER_LEN - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use movegen::fen::Fen;
+    use movegen::side::Side;
+
+    use super::KingSafety;
+    use crate::attacks::Attacks;
+    use crate::params;
+
+    #[test]
+    fn danger_index() {
+        for (fen, exp_white, exp_black) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0,
+                0,
+            ),
+            // Only one attacker
+            ("6k1/5ppp/8/8/7q/8/5PPP/6K1 w - - 0 1", 0, 0),
+            // The queen attacks f2 and h2, the knight attacks f2 and h2, both
+            // squares are undefended
+            ("6k1/5ppp/8/8/6nq/8/5PPP/6K1 w - - 0 1", 16, 0),
+            // Same as above, but the rook defends f2 and the f file next to
+            // the king is open
+            ("6k1/5ppp/8/8/6nq/8/6PP/5RK1 w - - 0 1", 17, 0),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert_eq!(
+                (exp_white, exp_black),
+                (
+                    KingSafety::danger_index(&pos, Side::White, &Attacks::both(&pos)),
+                    KingSafety::danger_index(&pos, Side::Black, &Attacks::both(&pos))
+                ),
+                "\nPosition: {fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn danger_index_is_clamped() {
+        let fen = "6k1/8/8/8/8/2bbnnqq/5r1r/6K1 w - - 0 1";
+        let pos = Fen::str_to_pos(fen).unwrap();
+        assert_eq!(
+            params::KING_DANGER_LEN - 1,
+            KingSafety::danger_index(&pos, Side::White, &Attacks::both(&pos))
+        );
+    }
+}
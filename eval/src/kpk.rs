@@ -0,0 +1,352 @@
+use std::sync::OnceLock;
+
+use movegen::bitboard::Bitboard;
+use movegen::king::King;
+use movegen::pawn::Pawn;
+use movegen::piece;
+use movegen::position::Position;
+use movegen::side::Side;
+use movegen::square::Square;
+
+use crate::Score;
+
+// Score of a won KPK position (from the strong side's point of view). The
+// relative rank of the pawn is added, so the engine pushes the pawn.
+const WIN_SCORE: Score = 1000;
+const PAWN_RANK_BONUS: Score = 20;
+
+// Pawns on files A-D, ranks 2-7. Positions with the pawn on files E-H are
+// mirrored.
+const NUM_PAWN_SQUARES: usize = 24;
+const NUM_POSITIONS: usize = 2 * Square::NUM_SQUARES * Square::NUM_SQUARES * NUM_PAWN_SQUARES;
+
+static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+
+// Bitbase for king and pawn versus king. It's generated by retrograde analysis
+// on first use. The side with the pawn (the strong side) is normalized to
+// white, so only white wins are stored.
+pub struct Kpk;
+
+impl Kpk {
+    // Check if the position is KPK (for either side)
+    pub fn is_kpk(pos: &Position) -> bool {
+        pos.occupancy().pop_count() == 3
+            && pos.piece_type_occupancy(piece::Type::Pawn).pop_count() == 1
+    }
+
+    // Score of a KPK position from white's point of view. Drawn positions are
+    // scored as 0.
+    pub fn score(pos: &Position) -> Score {
+        debug_assert!(Self::is_kpk(pos));
+        let pawn = pos.piece_type_occupancy(piece::Type::Pawn);
+        let strong_side = match pos.piece_occupancy(Side::White, piece::Type::Pawn) == pawn {
+            true => Side::White,
+            false => Side::Black,
+        };
+        let strong_king = pos
+            .piece_occupancy(strong_side, piece::Type::King)
+            .to_square();
+        let weak_king = pos
+            .piece_occupancy(!strong_side, piece::Type::King)
+            .to_square();
+        let pawn = pawn.to_square();
+        let (strong_king, weak_king, pawn) = match strong_side {
+            Side::White => (strong_king, weak_king, pawn),
+            Side::Black => (
+                strong_king.flip_vertical(),
+                weak_king.flip_vertical(),
+                pawn.flip_vertical(),
+            ),
+        };
+        let strong_to_move = pos.side_to_move() == strong_side;
+        if !Self::probe(strong_king, weak_king, pawn, strong_to_move) {
+            return 0;
+        }
+        let score = WIN_SCORE + PAWN_RANK_BONUS * pawn.rank().idx() as Score;
+        match strong_side {
+            Side::White => score,
+            Side::Black => -score,
+        }
+    }
+
+    // Check if the position is won for white. White has the pawn.
+    pub fn probe(
+        white_king: Square,
+        black_king: Square,
+        pawn: Square,
+        white_to_move: bool,
+    ) -> bool {
+        let (white_king, black_king, pawn) = match pawn.idx() < Square::NUM_SQUARES / 2 {
+            true => (white_king, black_king, pawn),
+            false => (
+                white_king.mirror_horizontal(),
+                black_king.mirror_horizontal(),
+                pawn.mirror_horizontal(),
+            ),
+        };
+        let idx = index(white_king, black_king, pawn, white_to_move);
+        let bitbase = BITBASE.get_or_init(generate);
+        bitbase[idx / 64] & (1 << (idx % 64)) != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+fn index(white_king: Square, black_king: Square, pawn: Square, white_to_move: bool) -> usize {
+    debug_assert!(pawn.idx() < Square::NUM_SQUARES / 2);
+    debug_assert!(pawn.rank().idx() >= 1 && pawn.rank().idx() <= 6);
+    let pawn_idx = pawn.file().idx() * 6 + pawn.rank().idx() - 1;
+    ((!white_to_move as usize * Square::NUM_SQUARES + white_king.idx()) * Square::NUM_SQUARES
+        + black_king.idx())
+        * NUM_PAWN_SQUARES
+        + pawn_idx
+}
+
+fn decode(idx: usize) -> (Square, Square, Square, bool) {
+    let pawn_idx = idx % NUM_PAWN_SQUARES;
+    let idx = idx / NUM_PAWN_SQUARES;
+    let black_king = Square::from_idx(idx % Square::NUM_SQUARES);
+    let idx = idx / Square::NUM_SQUARES;
+    let white_king = Square::from_idx(idx % Square::NUM_SQUARES);
+    let white_to_move = idx / Square::NUM_SQUARES == 0;
+    let pawn = Square::from_idx(pawn_idx / 6 * 8 + pawn_idx % 6 + 1);
+    (white_king, black_king, pawn, white_to_move)
+}
+
+fn pawn_attacks(pawn: Square) -> Bitboard {
+    Pawn::attack_targets(Bitboard::from_square(pawn), Side::White)
+}
+
+// Legal moves of the black king, including the capture of the pawn
+fn black_king_targets(white_king: Square, black_king: Square, pawn: Square) -> Bitboard {
+    King::targets(black_king) & !King::targets(white_king) & !pawn_attacks(pawn)
+}
+
+fn white_king_targets(white_king: Square, black_king: Square, pawn: Square) -> Bitboard {
+    King::targets(white_king) & !King::targets(black_king) & !Bitboard::from_square(pawn)
+}
+
+fn initial_outcome(idx: usize) -> Outcome {
+    let (white_king, black_king, pawn, white_to_move) = decode(idx);
+    let black_king_bb = Bitboard::from_square(black_king);
+    if white_king == black_king
+        || white_king == pawn
+        || black_king == pawn
+        || King::targets(white_king) & black_king_bb != Bitboard::EMPTY
+    {
+        return Outcome::Invalid;
+    }
+
+    if white_to_move {
+        // Black can't be in check with white to move
+        if pawn_attacks(pawn) & black_king_bb != Bitboard::EMPTY {
+            return Outcome::Invalid;
+        }
+        // The pawn promotes and the new queen can't be captured
+        if pawn.rank().idx() == 6 {
+            let promo_square = pawn.north_one();
+            if promo_square != white_king
+                && promo_square != black_king
+                && (black_king.distance(promo_square) > 1 || white_king.distance(promo_square) == 1)
+            {
+                return Outcome::Win;
+            }
+        }
+        return Outcome::Unknown;
+    }
+
+    let targets = black_king_targets(white_king, black_king, pawn);
+    // Black captures the pawn
+    if targets & Bitboard::from_square(pawn) != Bitboard::EMPTY {
+        return Outcome::Draw;
+    }
+    if targets == Bitboard::EMPTY {
+        // Checkmate or stalemate
+        return match pawn_attacks(pawn) & black_king_bb != Bitboard::EMPTY {
+            true => Outcome::Win,
+            false => Outcome::Draw,
+        };
+    }
+    Outcome::Unknown
+}
+
+// Classify the position from the outcomes of its successors. Returns Unknown
+// if that isn't possible yet.
+fn classify(outcomes: &[Outcome], idx: usize) -> Outcome {
+    let (white_king, black_king, pawn, white_to_move) = decode(idx);
+    let mut successors = Vec::with_capacity(10);
+
+    if white_to_move {
+        let mut targets = white_king_targets(white_king, black_king, pawn);
+        while targets != Bitboard::EMPTY {
+            let target = targets.square_scan_forward_reset();
+            successors.push(outcomes[index(target, black_king, pawn, false)]);
+        }
+        let single_push = pawn.north_one();
+        if single_push != white_king && single_push != black_king {
+            if pawn.rank().idx() == 6 {
+                // Black captures the new queen. Otherwise the position would
+                // already be classified as a win.
+                successors.push(Outcome::Draw);
+            } else {
+                successors.push(outcomes[index(white_king, black_king, single_push, false)]);
+                let double_push = single_push.north_one();
+                if pawn.rank().idx() == 1 && double_push != white_king && double_push != black_king
+                {
+                    successors.push(outcomes[index(white_king, black_king, double_push, false)]);
+                }
+            }
+        }
+        // White needs one winning move, black needs one drawing move
+        if successors.contains(&Outcome::Win) {
+            Outcome::Win
+        } else if successors.iter().all(|o| *o == Outcome::Draw) {
+            Outcome::Draw
+        } else {
+            Outcome::Unknown
+        }
+    } else {
+        let mut targets = black_king_targets(white_king, black_king, pawn);
+        while targets != Bitboard::EMPTY {
+            let target = targets.square_scan_forward_reset();
+            successors.push(outcomes[index(white_king, target, pawn, true)]);
+        }
+        if successors.contains(&Outcome::Draw) {
+            Outcome::Draw
+        } else if successors.iter().all(|o| *o == Outcome::Win) {
+            Outcome::Win
+        } else {
+            Outcome::Unknown
+        }
+    }
+}
+
+fn generate() -> Vec<u64> {
+    let mut outcomes: Vec<Outcome> = (0..NUM_POSITIONS).map(initial_outcome).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for idx in 0..NUM_POSITIONS {
+            if outcomes[idx] != Outcome::Unknown {
+                continue;
+            }
+            let outcome = classify(&outcomes, idx);
+            if outcome != Outcome::Unknown {
+                outcomes[idx] = outcome;
+                changed = true;
+            }
+        }
+    }
+
+    // Positions which are still unknown can't be won by white
+    let mut bitbase = vec![0; NUM_POSITIONS / 64];
+    for (idx, outcome) in outcomes.iter().enumerate() {
+        if *outcome == Outcome::Win {
+            bitbase[idx / 64] |= 1 << (idx % 64);
+        }
+    }
+    bitbase
+}
+
+#[cfg(test)]
+mod tests {
+    use movegen::fen::Fen;
+
+    use super::*;
+
+    fn assert_scores(fens: &[&str], is_win: impl Fn(Score) -> bool) {
+        for fen in fens {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert!(Kpk::is_kpk(&pos), "\nPosition: {fen}");
+            let score = Kpk::score(&pos);
+            assert!(is_win(score), "\nPosition: {fen}\nScore: {score}");
+        }
+    }
+
+    #[test]
+    fn is_kpk() {
+        for (fen, exp) in [
+            ("4k3/8/8/8/4P3/8/8/4K3 w - - 0 1", true),
+            ("4k3/4p3/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", false),
+            ("4k3/8/8/8/4N3/8/8/4K3 w - - 0 1", false),
+            ("4k3/4p3/8/8/4P3/8/8/4K3 w - - 0 1", false),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert_eq!(exp, Kpk::is_kpk(&pos), "\nPosition: {fen}");
+        }
+    }
+
+    #[test]
+    fn key_squares() {
+        // With the pawn on e4, the key squares are d6, e6 and f6. The king on
+        // a key square wins, no matter who's to move.
+        let wins = [
+            "4k3/8/3K4/8/4P3/8/8/8 w - - 0 1",
+            "4k3/8/3K4/8/4P3/8/8/8 b - - 0 1",
+            "4k3/8/4K3/8/4P3/8/8/8 w - - 0 1",
+            "4k3/8/4K3/8/4P3/8/8/8 b - - 0 1",
+            "4k3/8/5K2/8/4P3/8/8/8 w - - 0 1",
+            "4k3/8/5K2/8/4P3/8/8/8 b - - 0 1",
+            // The pawn outruns the king
+            "8/8/5k2/8/P7/8/8/7K w - - 0 1",
+        ];
+        assert_scores(&wins, |s| s > WIN_SCORE);
+        // With black to move, the king gets into the square of the pawn
+        assert_scores(&["8/8/5k2/8/P7/8/8/7K b - - 0 1"], |s| s == 0);
+    }
+
+    #[test]
+    fn opposition() {
+        // The king on the sixth rank in front of its pawn always wins
+        assert_scores(&["4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"], |s| s > WIN_SCORE);
+        assert_scores(&["4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"], |s| s > WIN_SCORE);
+        // Otherwise, the side to move loses the opposition
+        assert_scores(&["4k3/8/8/4K3/4P3/8/8/8 w - - 0 1"], |s| s > WIN_SCORE);
+        assert_scores(&["4k3/8/8/4K3/4P3/8/8/8 b - - 0 1"], |s| s == 0);
+        assert_scores(&["8/4k3/8/4K3/4P3/8/8/8 w - - 0 1"], |s| s == 0);
+        assert_scores(&["8/4k3/8/4K3/4P3/8/8/8 b - - 0 1"], |s| s > WIN_SCORE);
+    }
+
+    #[test]
+    fn draws() {
+        let draws = [
+            // The defending king is in front of a rook pawn
+            "k7/8/K7/P7/8/8/8/8 w - - 0 1",
+            "k7/8/K7/P7/8/8/8/8 b - - 0 1",
+            "8/8/8/8/8/k7/p7/1K6 b - - 0 1",
+            // Black captures the pawn
+            "8/8/8/8/8/8/3kP3/7K b - - 0 1",
+            // Stalemate
+            "k7/P7/1K6/8/8/8/8/8 b - - 0 1",
+        ];
+        assert_scores(&draws, |s| s == 0);
+    }
+
+    #[test]
+    fn colors_and_files_are_symmetric() {
+        // The positions are mirrored horizontally and with the colors
+        // swapped
+        assert_scores(
+            &[
+                "4k3/8/5K2/8/4P3/8/8/8 b - - 0 1",
+                "4k3/8/2K5/8/3P4/8/8/8 b - - 0 1",
+            ],
+            |s| s > WIN_SCORE,
+        );
+        assert_scores(
+            &[
+                "8/8/8/4p3/8/5k2/8/4K3 w - - 0 1",
+                "8/8/8/3p4/8/2k5/8/3K4 w - - 0 1",
+            ],
+            |s| s < -WIN_SCORE,
+        );
+        assert_scores(&["8/8/8/4p3/4k3/8/4K3/8 b - - 0 1"], |s| s == 0);
+    }
+}
@@ -2,13 +2,20 @@ pub use crate::eval::Eval;
 pub use crate::game_phase::GamePhase;
 pub use crate::score::{Score, ScoreVariant, BLACK_WIN, EQ_POSITION, NEG_INF, POS_INF, WHITE_WIN};
 
+pub mod attacks;
 pub mod complex;
 pub mod eval;
+pub mod king_safety;
+pub mod kpk;
 pub mod material_mobility;
 pub mod mobility;
 pub mod params;
+pub mod pawn_hash_table;
 pub mod pawn_structure;
+pub mod piece_placement;
+pub mod scale_factor;
 pub mod score;
 pub mod score_pair;
+pub mod trace;
 
 mod game_phase;
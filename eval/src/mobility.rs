@@ -1,9 +1,6 @@
-use movegen::{
-    bishop::Bishop, bitboard::Bitboard, knight::Knight, piece, position::Position, queen::Queen,
-    rook::Rook, side::Side,
-};
+use movegen::{piece, position::Position, side::Side};
 
-use crate::{params, score_pair::ScorePair, Score};
+use crate::{attacks::Attacks, params, score_pair::ScorePair, Score};
 
 #[derive(Debug, Clone, Default)]
 pub struct MobilityCounts {
@@ -18,7 +15,10 @@ pub struct Mobility;
 
 impl Mobility {
     pub fn scores(&self, pos: &Position) -> ScorePair {
-        let mob_counts = Self::mobility_counts(pos);
+        Self::scores_from_counts(&Self::mobility_counts(pos, &Attacks::both(pos)))
+    }
+
+    fn scores_from_counts(mob_counts: &MobilityCounts) -> ScorePair {
         let mut scores = ScorePair(0, 0);
 
         scores += mob_counts
@@ -49,44 +49,69 @@ impl Mobility {
         scores
     }
 
-    pub fn mobility_counts(pos: &Position) -> MobilityCounts {
+    // Mobility scores of one side, from that side's point of view. The
+    // attacks are indexed by side.
+    pub fn scores_one_side(&self, pos: &Position, side: Side, attacks: &[Attacks; 2]) -> ScorePair {
+        let mut mob_counts = MobilityCounts::default();
+        Self::mobility_counts_one_side(pos, side, attacks, &mut mob_counts);
+        let scores = Self::scores_from_counts(&mob_counts);
+        match side {
+            Side::White => scores,
+            Side::Black => ScorePair(0, 0) - scores,
+        }
+    }
+
+    pub fn mobility_counts(pos: &Position, attacks: &[Attacks; 2]) -> MobilityCounts {
         let mut mob_counts = MobilityCounts::default();
-        Self::mobility_counts_one_side(pos, Side::White, &mut mob_counts);
-        Self::mobility_counts_one_side(pos, Side::Black, &mut mob_counts);
+        Self::mobility_counts_one_side(pos, Side::White, attacks, &mut mob_counts);
+        Self::mobility_counts_one_side(pos, Side::Black, attacks, &mut mob_counts);
         mob_counts
     }
 
-    fn mobility_counts_one_side(pos: &Position, side: Side, mob_counts: &mut MobilityCounts) {
+    fn mobility_counts_one_side(
+        pos: &Position,
+        side: Side,
+        attacks: &[Attacks; 2],
+        mob_counts: &mut MobilityCounts,
+    ) {
         let side_as_int = 1 - 2 * (side as i8);
-        let occupancy = pos.occupancy();
         let own_occupancy = pos.side_occupancy(side);
+        // Squares attacked by enemy pawns don't count. A piece moving there
+        // would just be captured.
+        let available = !own_occupancy & !attacks[!side as usize].pawns();
 
-        let mut own_knights = pos.piece_occupancy(side, piece::Type::Knight);
-        while own_knights != Bitboard::EMPTY {
-            let origin = own_knights.square_scan_forward_reset();
-            let targets = Knight::targets(origin) & !own_occupancy;
-            mob_counts.knight_mob[targets.pop_count()] += side_as_int;
+        for (piece_type, targets) in attacks[side as usize].piece_targets() {
+            let count = (*targets & available).pop_count();
+            match piece_type {
+                piece::Type::Knight => mob_counts.knight_mob[count] += side_as_int,
+                piece::Type::Bishop => mob_counts.bishop_mob[count] += side_as_int,
+                piece::Type::Rook => mob_counts.rook_mob[count] += side_as_int,
+                piece::Type::Queen => mob_counts.queen_mob[count] += side_as_int,
+                _ => {}
+            }
         }
+    }
+}
 
-        let mut own_bishops = pos.piece_occupancy(side, piece::Type::Bishop);
-        while own_bishops != Bitboard::EMPTY {
-            let origin = own_bishops.square_scan_forward_reset();
-            let targets = Bishop::targets(origin, occupancy) & !own_occupancy;
-            mob_counts.bishop_mob[targets.pop_count()] += side_as_int;
-        }
+#[cfg(test)]
+mod tests {
+    use movegen::fen::Fen;
 
-        let mut own_rooks = pos.piece_occupancy(side, piece::Type::Rook);
-        while own_rooks != Bitboard::EMPTY {
-            let origin = own_rooks.square_scan_forward_reset();
-            let targets = Rook::targets(origin, occupancy) & !own_occupancy;
-            mob_counts.rook_mob[targets.pop_count()] += side_as_int;
-        }
+    use super::Mobility;
+    use crate::attacks::Attacks;
 
-        let mut own_queens = pos.piece_occupancy(side, piece::Type::Queen);
-        while own_queens != Bitboard::EMPTY {
-            let origin = own_queens.square_scan_forward_reset();
-            let targets = Queen::targets(origin, occupancy) & !own_occupancy;
-            mob_counts.queen_mob[targets.pop_count()] += side_as_int;
-        }
+    #[test]
+    fn squares_attacked_by_enemy_pawns_are_excluded() {
+        // The knight on d4 reaches 8 squares. c6 and e6 are attacked by the
+        // pawn on d7, b5 is attacked by the pawn on a6.
+        let pos = Fen::str_to_pos("4k3/3p4/p7/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+        let mob_counts = Mobility::mobility_counts(&pos, &Attacks::both(&pos));
+        assert_eq!(1, mob_counts.knight_mob[5]);
+        assert_eq!(1, mob_counts.knight_mob.iter().filter(|c| **c != 0).count());
+
+        // Own pawns don't restrict the mobility of own pieces this way
+        let pos = Fen::str_to_pos("4k3/8/8/8/3N4/2P1P3/8/4K3 w - - 0 1").unwrap();
+        let mob_counts = Mobility::mobility_counts(&pos, &Attacks::both(&pos));
+        assert_eq!(1, mob_counts.knight_mob[8]);
     }
 }
@@ -1,9 +1,5 @@
-use movegen::{file::File, rank::Rank, square::Square};
-
 use crate::{score_pair::ScorePair, Score};
 
-pub type PieceSquareTable = [ScorePair; 64];
-
 pub const KNIGHT_MOB_LEN: usize = 9;
 pub const BISHOP_MOB_LEN: usize = 14;
 pub const ROOK_MOB_LEN: usize = 15;
@@ -12,43 +8,100 @@ pub const MOB_LEN: usize = KNIGHT_MOB_LEN + BISHOP_MOB_LEN + ROOK_MOB_LEN + QUEE
 
 pub const DISTANCE_LEN: usize = 8;
 
-// (middlegame, endgame)
-const MATERIAL_KING: ScorePair = ScorePair(0, 0);
-const MATERIAL_QUEEN: ScorePair = ScorePair(0, 0);
-const MATERIAL_ROOK: ScorePair = ScorePair(0, 0);
-const MATERIAL_BISHOP: ScorePair = ScorePair(0, 0);
-const MATERIAL_KNIGHT: ScorePair = ScorePair(0, 0);
-const MATERIAL_PAWN: ScorePair = ScorePair(0, 0);
+pub const PASSED_PAWN_LEN: usize = 8;
+
+pub const KING_DANGER_LEN: usize = 32;
+
+pub const OCB_SCALE_FACTOR_LEN: usize = 4;
 
-// The side to move gets a small bonus
-pub const TEMPO: ScorePair = ScorePair(42, 25);
+// The side to move gets a small bonus in the middlegame. In the endgame, having
+// the move can just as well be a disadvantage (zugzwang), so the bonus tapers
+// to zero. Only the middlegame value is tuned.
+pub const TEMPO: ScorePair = ScorePair(42, 0);
 
-pub const PASSED_PAWN: ScorePair = ScorePair(-3, 36);
+// Passed pawns, indexed by the rank from the pawn's side's point of view
+const PASSED_PAWN_MG_EG: ([Score; PASSED_PAWN_LEN], [Score; PASSED_PAWN_LEN]) = (
+    [0, -5, -5, 0, 10, 30, 60, 0],
+    [0, 10, 15, 30, 55, 95, 140, 0],
+);
+// Bonuses for passed pawns defended by a pawn, with a passed pawn on an
+// adjacent file and without any piece on the way to the promotion square
+pub const PASSED_PAWN_PROTECTED: ScorePair = ScorePair(10, 15);
+pub const PASSED_PAWN_CONNECTED: ScorePair = ScorePair(5, 15);
+pub const PASSED_PAWN_FREE_PATH: ScorePair = ScorePair(0, 25);
 pub const ISOLATED_PAWN: ScorePair = ScorePair(-23, -6);
 pub const BACKWARD_PAWN: ScorePair = ScorePair(-19, 1);
 pub const DOUBLED_PAWN: ScorePair = ScorePair(-6, -4);
+// Pawn shield of the king, per file on and next to the king file. Shield
+// pawns hardly matter in the endgame.
+pub const PAWN_SHIELD_MISSING: ScorePair = ScorePair(-25, 0);
+pub const PAWN_SHIELD_ADVANCED: ScorePair = ScorePair(-10, 0);
+pub const PAWN_SHIELD_OPEN_FILE: ScorePair = ScorePair(-20, 0);
 
 pub const BISHOP_PAIR: ScorePair = ScorePair(49, 21);
 
+// Rooks on files without any pawns and on files without own pawns
+pub const ROOK_OPEN_FILE: ScorePair = ScorePair(40, 10);
+pub const ROOK_SEMI_OPEN_FILE: ScorePair = ScorePair(20, 10);
+// Rooks on the seventh rank, if the enemy king is on its back rank or there are
+// enemy pawns on the seventh rank. Mostly an endgame term.
+pub const ROOK_SEVENTH: ScorePair = ScorePair(5, 25);
+
+// Knights and bishops on squares in the enemy half that are defended by a pawn
+// and can't be attacked by enemy pawns
+pub const KNIGHT_OUTPOST: ScorePair = ScorePair(30, 15);
+pub const BISHOP_OUTPOST: ScorePair = ScorePair(15, 5);
+
+// King danger. Each enemy piece attacking the king ring adds its weight per
+// attacked ring square. Attacked ring squares without a defender and files
+// next to the king without own pawns add to the danger, too. The danger is
+// only counted if there are enough attackers.
+pub const KING_ATTACKER_COUNT_MIN: usize = 2;
+pub const KING_ATTACK_WEIGHT_KNIGHT: usize = 2;
+pub const KING_ATTACK_WEIGHT_BISHOP: usize = 2;
+pub const KING_ATTACK_WEIGHT_ROOK: usize = 3;
+pub const KING_ATTACK_WEIGHT_QUEEN: usize = 5;
+pub const KING_DANGER_UNDEFENDED: usize = 1;
+pub const KING_DANGER_OPEN_FILE: usize = 2;
+// Penalties, indexed by the danger index
+const KING_DANGER_MG_EG: ([Score; KING_DANGER_LEN], [Score; KING_DANGER_LEN]) = (
+    [
+        0, -1, -2, -5, -8, -13, -18, -25, -32, -41, -50, -61, -72, -85, -98, -113, -128, -145,
+        -162, -181, -200, -221, -242, -265, -288, -313, -338, -365, -392, -421, -450, -481,
+    ],
+    [
+        0, 0, -1, -1, -2, -2, -3, -3, -4, -4, -5, -5, -6, -6, -7, -7, -8, -8, -9, -9, -10, -10,
+        -11, -11, -12, -12, -13, -13, -14, -14, -15, -15,
+    ],
+);
+
+// Scale factors for drawish endgames. The final score is multiplied by the
+// scale factor and divided by SCALE_FACTOR_NORMAL.
+pub const SCALE_FACTOR_NORMAL: Score = 64;
+// Opposite-colored bishops without other pieces and with one rook per side,
+// indexed by the difference of the pawn counts
+pub const SCALE_FACTOR_OCB: [Score; OCB_SCALE_FACTOR_LEN] = [16, 24, 40, 56];
+pub const SCALE_FACTOR_OCB_ROOKS: [Score; OCB_SCALE_FACTOR_LEN] = [40, 48, 56, 64];
+
 const MOBILITY_KNIGHT_MG_EG: ([Score; KNIGHT_MOB_LEN], [Score; KNIGHT_MOB_LEN]) = (
-    [-26, 41, 53, 58, 68, 71, 71, 68, 68],
-    [-3, 4, -15, -19, -15, -7, -4, 6, -2],
+    [-25, 49, 57, 59, 63, 74, 73, 72, 76],
+    [-8, 6, -12, -13, -14, -8, 0, 19, 0],
 );
 const MOBILITY_BISHOP_MG_EG: ([Score; BISHOP_MOB_LEN], [Score; BISHOP_MOB_LEN]) = (
-    [0, 9, 24, 30, 36, 41, 48, 54, 66, 59, 78, 88, 40, 33],
-    [-35, -23, -23, -24, -9, -2, 6, 5, 3, 12, 8, 14, 21, 42],
+    [-8, 13, 27, 38, 41, 45, 46, 56, 67, 59, 74, 89, 41, 33],
+    [-30, -23, -24, -20, -4, -6, 3, 8, 8, 8, 5, 12, 23, 43],
 );
 const MOBILITY_ROOK_MG_EG: ([Score; ROOK_MOB_LEN], [Score; ROOK_MOB_LEN]) = (
-    [2, 8, 7, 13, 15, 15, 28, 41, 39, 54, 56, 58, 81, 81, 65],
-    [-27, -27, 7, -3, 4, 13, 5, 0, 13, 22, 8, 17, 14, 26, 21],
+    [-2, 5, 2, 15, 11, 18, 35, 35, 32, 50, 54, 55, 84, 83, 63],
+    [-27, -22, 7, 6, 8, 6, 5, -1, 1, 24, 8, 16, 16, 23, 17],
 );
 const MOBILITY_QUEEN_MG_EG: ([Score; QUEEN_MOB_LEN], [Score; QUEEN_MOB_LEN]) = (
     [
-        -29, -8, 9, 3, 14, 19, 25, 30, 23, 38, 38, 36, 41, 45, 42, 52, 46, 49, 66, 54, 52, 70, 55,
-        63, 11, 31, 9, 8,
+        -28, -17, 4, -1, 10, 21, 26, 39, 19, 31, 45, 41, 42, 52, 50, 51, 45, 51, 64, 55, 49, 71,
+        55, 63, 11, 31, 9, 8,
     ],
     [
-        0, 0, 5, 4, -11, -2, -13, -14, 5, 22, 23, 48, 51, 36, 49, 53, 35, 66, 61, 69, 63, 63, 54,
+        0, 0, 4, 4, -15, -3, -13, -14, 5, 24, 22, 48, 55, 36, 53, 55, 34, 67, 62, 70, 61, 64, 54,
         57, 14, 45, 15, 14,
     ],
 );
@@ -96,163 +149,6 @@ const DISTANCE_FRIENDLY_KING_MG_EG: ([Score; DISTANCE_LEN], [Score; DISTANCE_LEN
 const DISTANCE_ENEMY_KING_MG_EG: ([Score; DISTANCE_LEN], [Score; DISTANCE_LEN]) =
     ([0, 0, 0, 0, 0, 0, 0, 0], [0, 0, 0, 0, 0, 0, 0, 0]);
 
-// Piece square tables:
-// We only define values for the queenside (left side) and mirror them to the
-// kingside (right side) so that we end up with symmetrical PSTs.
-#[rustfmt::skip]
-const PST_PAWN_MG_EG: ([Score; 32], [Score; 32]) = (
-    [
-           0,    0,    0,    0,
-         150,  124,  156,  176,
-          78,  108,  169,  142,
-          95,  106,  107,  129,
-          64,   80,  101,  113,
-          76,   91,   91,   93,
-          62,  101,   78,   68,
-           0,    0,    0,    0,
-    ],
-    [
-           0,    0,    0,    0,
-         268,  283,  264,  216,
-         169,  140,  123,  135,
-          78,   67,   70,   48,
-          64,   57,   48,   37,
-          51,   47,   45,   47,
-          58,   45,   52,   55,
-           0,    0,    0,    0,
-    ],
-);
-#[rustfmt::skip]
-const PST_KNIGHT_MG_EG: ([Score; 32], [Score; 32]) = (
-    [
-         236,  297,  274,  300,
-         256,  284,  383,  344,
-         279,  319,  348,  355,
-         335,  316,  347,  344,
-         284,  312,  346,  327,
-         296,  307,  331,  343,
-         318,  308,  322,  335,
-         295,  315,  322,  296,
-    ],
-    [
-         246,  284,  273,  301,
-         288,  297,  274,  292,
-         299,  297,  333,  315,
-         299,  325,  320,  315,
-         321,  295,  309,  319,
-         296,  312,  293,  301,
-         275,  290,  308,  301,
-         288,  274,  298,  307,
-    ],
-);
-#[rustfmt::skip]
-const PST_BISHOP_MG_EG: ([Score; 32], [Score; 32]) = (
-    [
-         333,  307,  277,  269,
-         278,  306,  304,  275,
-         324,  312,  358,  352,
-         304,  311,  279,  334,
-         301,  312,  312,  354,
-         337,  344,  355,  329,
-         329,  372,  331,  337,
-         326,  307,  315,  321,
-    ],
-    [
-         302,  300,  269,  293,
-         289,  308,  306,  297,
-         294,  317,  300,  304,
-         304,  312,  323,  328,
-         300,  303,  315,  302,
-         287,  299,  294,  308,
-         284,  274,  283,  287,
-         298,  315,  290,  308,
-    ],
-);
-#[rustfmt::skip]
-const PST_ROOK_MG_EG: ([Score; 32], [Score; 32]) = (
-    [
-         504,  513,  519,  524,
-         551,  528,  553,  543,
-         537,  513,  498,  501,
-         522,  495,  502,  511,
-         475,  479,  483,  510,
-         473,  508,  507,  519,
-         463,  525,  526,  543,
-         544,  544,  573,  582,
-    ],
-    [
-         526,  523,  510,  512,
-         515,  519,  518,  511,
-         502,  510,  514,  512,
-         490,  501,  510,  509,
-         496,  517,  514,  510,
-         487,  483,  498,  496,
-         511,  496,  493,  505,
-         471,  476,  480,  478,
-    ],
-);
-#[rustfmt::skip]
-const PST_QUEEN_MG_EG: ([Score; 32], [Score; 32]) = (
-    [
-         968,  922,  928,  924,
-         945,  899,  929,  833,
-         928,  921,  916,  910,
-         920,  899,  915,  900,
-         899,  906,  918,  900,
-         924,  940,  937,  929,
-         944,  968,  963,  956,
-         949,  954,  973,  974,
-    ],
-    [
-         947,  934,  941,  920,
-         923,  943,  926,  940,
-         886,  900,  937,  941,
-         905,  918,  936,  949,
-         955,  922,  931,  961,
-         908,  916,  925,  924,
-         917,  935,  887,  923,
-         937,  915,  907,  902,
-    ],
-);
-#[rustfmt::skip]
-const PST_KING_MG_EG: ([Score; 32], [Score; 32]) = (
-    [
-           3,    3,   -1,   -3,
-           3,    5,   15,   10,
-         -10,   19,   16,   21,
-          -2,   17,   23,    9,
-         -20,   -7,  -11,  -23,
-         -19,   15,  -12,  -69,
-          18,   52,  -15,  -71,
-          31,   66,  -27,  -33,
-    ],
-    [
-         -27,   -4,  -21,  -17,
-           9,    6,    9,   18,
-          -8,    7,   16,   20,
-          -3,    3,    7,   12,
-         -19,   -5,   13,   18,
-         -11,   -1,    9,   27,
-         -24,   -7,   14,   41,
-         -58,  -35,    5,    5,
-    ],
-);
-
-const fn human_readable_to_file_rank(piece_value: Score, pst: [Score; 32]) -> [Score; 64] {
-    let mut res = [0; 64];
-    let mut idx = 0;
-    while idx < 32 {
-        let rank = 7 - idx / 4;
-        let file = idx % 4;
-        let new_idx = Square::from_file_and_rank(File::from_idx(file), Rank::from_idx(rank)).idx();
-        let mirrored_idx = Square::from_idx(new_idx).mirror_horizontal().idx();
-        res[new_idx] = piece_value + pst[idx];
-        res[mirrored_idx] = piece_value + pst[idx];
-        idx += 1;
-    }
-    res
-}
-
 const fn convert_distance(
     mg_eg: ([Score; DISTANCE_LEN], [Score; DISTANCE_LEN]),
 ) -> [ScorePair; DISTANCE_LEN] {
@@ -315,6 +211,30 @@ pub const MOBILITY_QUEEN: [ScorePair; 28] = {
     table
 };
 
+pub const PASSED_PAWN: [ScorePair; PASSED_PAWN_LEN] = {
+    let mg = PASSED_PAWN_MG_EG.0;
+    let eg = PASSED_PAWN_MG_EG.1;
+    let mut table = [ScorePair(0, 0); PASSED_PAWN_LEN];
+    let mut idx = 0;
+    while idx < PASSED_PAWN_LEN {
+        table[idx] = ScorePair(mg[idx], eg[idx]);
+        idx += 1;
+    }
+    table
+};
+
+pub const KING_DANGER: [ScorePair; KING_DANGER_LEN] = {
+    let mg = KING_DANGER_MG_EG.0;
+    let eg = KING_DANGER_MG_EG.1;
+    let mut table = [ScorePair(0, 0); KING_DANGER_LEN];
+    let mut idx = 0;
+    while idx < KING_DANGER_LEN {
+        table[idx] = ScorePair(mg[idx], eg[idx]);
+        idx += 1;
+    }
+    table
+};
+
 pub const DISTANCE_FRIENDLY_PAWN: [ScorePair; DISTANCE_LEN] =
     convert_distance(DISTANCE_FRIENDLY_PAWN_MG_EG);
 pub const DISTANCE_ENEMY_PAWN: [ScorePair; DISTANCE_LEN] =
@@ -346,111 +266,3 @@ pub const DISTANCE_FRIENDLY_KING: [ScorePair; DISTANCE_LEN] =
 // Both sides will cancel each other out, but it is included to avoid branches
 pub const DISTANCE_ENEMY_KING: [ScorePair; DISTANCE_LEN] =
     convert_distance(DISTANCE_ENEMY_KING_MG_EG);
-
-pub const PST_PAWN: PieceSquareTable = {
-    let mg = human_readable_to_file_rank(MATERIAL_PAWN.0, PST_PAWN_MG_EG.0);
-    let eg = human_readable_to_file_rank(MATERIAL_PAWN.1, PST_PAWN_MG_EG.1);
-    let mut table = [ScorePair(0, 0); 64];
-    let mut idx = 0;
-    while idx < 64 {
-        table[idx] = ScorePair(mg[idx], eg[idx]);
-        idx += 1;
-    }
-    table
-};
-
-pub const PST_KNIGHT: PieceSquareTable = {
-    let mg = human_readable_to_file_rank(MATERIAL_KNIGHT.0, PST_KNIGHT_MG_EG.0);
-    let eg = human_readable_to_file_rank(MATERIAL_KNIGHT.1, PST_KNIGHT_MG_EG.1);
-    let mut table = [ScorePair(0, 0); 64];
-    let mut idx = 0;
-    while idx < 64 {
-        table[idx] = ScorePair(mg[idx], eg[idx]);
-        idx += 1;
-    }
-    table
-};
-
-pub const PST_BISHOP: PieceSquareTable = {
-    let mg = human_readable_to_file_rank(MATERIAL_BISHOP.0, PST_BISHOP_MG_EG.0);
-    let eg = human_readable_to_file_rank(MATERIAL_BISHOP.1, PST_BISHOP_MG_EG.1);
-    let mut table = [ScorePair(0, 0); 64];
-    let mut idx = 0;
-    while idx < 64 {
-        table[idx] = ScorePair(mg[idx], eg[idx]);
-        idx += 1;
-    }
-    table
-};
-
-pub const PST_ROOK: PieceSquareTable = {
-    let mg = human_readable_to_file_rank(MATERIAL_ROOK.0, PST_ROOK_MG_EG.0);
-    let eg = human_readable_to_file_rank(MATERIAL_ROOK.1, PST_ROOK_MG_EG.1);
-    let mut table = [ScorePair(0, 0); 64];
-    let mut idx = 0;
-    while idx < 64 {
-        table[idx] = ScorePair(mg[idx], eg[idx]);
-        idx += 1;
-    }
-    table
-};
-
-pub const PST_QUEEN: PieceSquareTable = {
-    let mg = human_readable_to_file_rank(MATERIAL_QUEEN.0, PST_QUEEN_MG_EG.0);
-    let eg = human_readable_to_file_rank(MATERIAL_QUEEN.1, PST_QUEEN_MG_EG.1);
-    let mut table = [ScorePair(0, 0); 64];
-    let mut idx = 0;
-    while idx < 64 {
-        table[idx] = ScorePair(mg[idx], eg[idx]);
-        idx += 1;
-    }
-    table
-};
-
-pub const PST_KING: PieceSquareTable = {
-    let mg = human_readable_to_file_rank(MATERIAL_KING.0, PST_KING_MG_EG.0);
-    let eg = human_readable_to_file_rank(MATERIAL_KING.1, PST_KING_MG_EG.1);
-    let mut table = [ScorePair(0, 0); 64];
-    let mut idx = 0;
-    while idx < 64 {
-        table[idx] = ScorePair(mg[idx], eg[idx]);
-        idx += 1;
-    }
-    table
-};
-
-#[cfg(test)]
-mod tests {
-    use movegen::square::Square;
-
-    #[test]
-    fn human_readable_to_file_rank() {
-        #[rustfmt::skip]
-        let arr = [
-             0,  1,  2,  3,
-             8,  9, 10, 11,
-            16, 17, 18, 19,
-            24, 25, 26, 27,
-            32, 33, 34, 35,
-            40, 41, 42, 43,
-            48, 49, 50, 51,
-            56, 57, 58, 59,
-        ];
-
-        let res = super::human_readable_to_file_rank(100, arr);
-        assert_eq!(156, res[Square::A1.idx()]);
-        assert_eq!(148, res[Square::A2.idx()]);
-        assert_eq!(100, res[Square::A8.idx()]);
-        assert_eq!(157, res[Square::B1.idx()]);
-        assert_eq!(149, res[Square::B2.idx()]);
-        assert_eq!(142, res[Square::C3.idx()]);
-        assert_eq!(135, res[Square::D4.idx()]);
-        assert_eq!(127, res[Square::E5.idx()]);
-        assert_eq!(118, res[Square::F6.idx()]);
-        assert_eq!(109, res[Square::G7.idx()]);
-        assert_eq!(101, res[Square::G8.idx()]);
-        assert_eq!(156, res[Square::H1.idx()]);
-        assert_eq!(108, res[Square::H7.idx()]);
-        assert_eq!(100, res[Square::H8.idx()]);
-    }
-}
@@ -0,0 +1,80 @@
+use crate::score_pair::ScorePair;
+use crate::trace::Term;
+use movegen::bitboard::Bitboard;
+use movegen::zobrist::Zobrist;
+
+pub const PAWN_HASH_TABLE_ENTRIES: usize = 1 << 14;
+
+// The pawn structure scores of one pawn configuration
+#[derive(Debug, Clone, Copy)]
+pub struct PawnEntry {
+    pub scores: ScorePair,
+    // Indexed by side, from each side's point of view
+    pub term_scores: [(Term, [ScorePair; 2]); 5],
+    // Indexed by side
+    pub passed_pawns: [Bitboard; 2],
+}
+
+// Direct-mapped table of pawn structure scores, keyed by the Zobrist key of
+// the pawns and the king files. The pawns rarely change, so most lookups are
+// hits. Each search thread has its own table (as part of its evaluator).
+#[derive(Debug, Clone)]
+pub struct PawnHashTable {
+    entries: Box<[Option<(Zobrist, PawnEntry)>]>,
+    // Lookups and how many of them found an entry. Their ratio is the hit
+    // rate.
+    probes: u64,
+    hits: u64,
+}
+
+impl Default for PawnHashTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PawnHashTable {
+    pub fn new() -> Self {
+        debug_assert!(PAWN_HASH_TABLE_ENTRIES.is_power_of_two());
+        Self {
+            entries: vec![None; PAWN_HASH_TABLE_ENTRIES].into_boxed_slice(),
+            probes: 0,
+            hits: 0,
+        }
+    }
+
+    pub fn get(&mut self, k: &Zobrist) -> Option<PawnEntry> {
+        self.probes += 1;
+        match self.entries[Self::index(k)] {
+            Some((key, entry)) if key == *k => {
+                self.hits += 1;
+                Some(entry)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, k: Zobrist, entry: PawnEntry) {
+        self.entries[Self::index(&k)] = Some((k, entry));
+    }
+
+    pub fn probes(&self) -> u64 {
+        self.probes
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    // Fraction of lookups that found an entry, in permille
+    pub fn hit_rate_permille(&self) -> u64 {
+        match self.probes {
+            0 => 0,
+            _ => 1000 * self.hits / self.probes,
+        }
+    }
+
+    fn index(k: &Zobrist) -> usize {
+        u64::from(*k) as usize & (PAWN_HASH_TABLE_ENTRIES - 1)
+    }
+}
@@ -1,17 +1,35 @@
-use crate::params;
+use crate::params::{self, PASSED_PAWN_LEN};
+use crate::pawn_hash_table::{PawnEntry, PawnHashTable};
 use crate::score_pair::ScorePair;
+use crate::trace::{EvalRecorder, Term};
+use crate::Score;
 
 use movegen::bitboard::Bitboard;
 use movegen::pawn::Pawn;
-use movegen::piece;
+use movegen::piece::{self, Piece};
 use movegen::position::Position;
+use movegen::rank::Rank;
 use movegen::side::Side;
 use movegen::square::Square;
+use movegen::zobrist::Zobrist;
 
+// Pawn structure terms. They only depend on the pawns (and the pawn shield on
+// the king files), so they are cached in a pawn hash table. The exception is
+// the free path bonus of passed pawns, which depends on the other pieces. It
+// is calculated from the cached passed pawns on every update.
 #[derive(Debug, Clone)]
 pub struct PawnStructure {
-    current_pos: Position,
-    scores: ScorePair,
+    table: PawnHashTable,
+    entry: PawnEntry,
+    // Indexed by side, from each side's point of view
+    free_path_scores: [ScorePair; 2],
+}
+
+#[derive(Debug, Default)]
+struct PawnShieldCounts {
+    missing: i8,
+    advanced: i8,
+    open_files: i8,
 }
 
 impl Default for PawnStructure {
@@ -23,45 +41,179 @@ impl Default for PawnStructure {
 impl PawnStructure {
     pub fn new() -> Self {
         Self {
-            current_pos: Position::empty(),
-            scores: ScorePair(0, 0),
+            table: PawnHashTable::new(),
+            entry: PawnEntry {
+                scores: ScorePair(0, 0),
+                term_scores: [
+                    (Term::PassedPawn, [ScorePair(0, 0); 2]),
+                    (Term::IsolatedPawn, [ScorePair(0, 0); 2]),
+                    (Term::BackwardPawn, [ScorePair(0, 0); 2]),
+                    (Term::DoubledPawn, [ScorePair(0, 0); 2]),
+                    (Term::PawnShield, [ScorePair(0, 0); 2]),
+                ],
+                passed_pawns: [Bitboard::EMPTY; 2],
+            },
+            free_path_scores: [ScorePair(0, 0); 2],
         }
     }
 
     pub fn scores(&self) -> ScorePair {
-        self.scores
+        self.entry.scores + self.free_path_scores[Side::White as usize]
+            - self.free_path_scores[Side::Black as usize]
     }
 
-    pub fn update(&mut self, pos: &Position) {
-        let old_white_pawns = self
-            .current_pos
-            .piece_occupancy(Side::White, piece::Type::Pawn);
-        let new_white_pawns = pos.piece_occupancy(Side::White, piece::Type::Pawn);
-        let old_black_pawns = self
-            .current_pos
-            .piece_occupancy(Side::Black, piece::Type::Pawn);
-        let new_black_pawns = pos.piece_occupancy(Side::Black, piece::Type::Pawn);
+    pub fn table(&self) -> &PawnHashTable {
+        &self.table
+    }
 
-        if old_white_pawns != new_white_pawns || old_black_pawns != new_black_pawns {
-            let white_pawns = pos.piece_occupancy(Side::White, piece::Type::Pawn);
-            let black_pawns = pos.piece_occupancy(Side::Black, piece::Type::Pawn);
-            let passed_pawn_score =
-                Self::passed_pawn_count(white_pawns, black_pawns) as i16 * params::PASSED_PAWN;
-            let isolated_pawn_score =
-                Self::isolated_pawn_count(white_pawns, black_pawns) as i16 * params::ISOLATED_PAWN;
-            let backward_pawn_score =
-                Self::backward_pawn_count(white_pawns, black_pawns) as i16 * params::BACKWARD_PAWN;
-            let doubled_pawn_score =
-                Self::doubled_pawn_count(white_pawns, black_pawns) as i16 * params::DOUBLED_PAWN;
-            self.scores =
-                passed_pawn_score + isolated_pawn_score + backward_pawn_score + doubled_pawn_score;
-            self.current_pos = pos.clone();
+    pub fn record(&self, recorder: &mut impl EvalRecorder) {
+        for (term, [white, black]) in self.entry.term_scores {
+            match term {
+                Term::PassedPawn => recorder.record(
+                    term,
+                    white + self.free_path_scores[Side::White as usize],
+                    black + self.free_path_scores[Side::Black as usize],
+                ),
+                _ => recorder.record(term, white, black),
+            }
+        }
+    }
+
+    // The pawn hash is the Zobrist key of the pawns of the position
+    pub fn update(&mut self, pos: &Position, pawn_hash: Zobrist) {
+        let key = Self::key(pos, pawn_hash);
+        self.entry = match self.table.get(&key) {
+            Some(entry) => entry,
+            None => {
+                let entry = Self::evaluate(pos);
+                self.table.insert(key, entry);
+                entry
+            }
+        };
+        for side in [Side::White, Side::Black] {
+            let passed_pawns = self.entry.passed_pawns[side as usize];
+            self.free_path_scores[side as usize] =
+                Self::free_passed_pawns(passed_pawns, pos.occupancy(), side).pop_count() as Score
+                    * params::PASSED_PAWN_FREE_PATH;
+        }
+    }
+
+    // The pawn shield depends on the king files, so they are added to the
+    // pawn hash. The king ranks don't matter.
+    fn key(pos: &Position, pawn_hash: Zobrist) -> Zobrist {
+        let mut key = pawn_hash;
+        for (side, rank) in [(Side::White, Rank::R1), (Side::Black, Rank::R8)] {
+            let king = pos.piece_occupancy(side, piece::Type::King);
+            if king != Bitboard::EMPTY {
+                let square = Square::from_file_and_rank(king.to_square().file(), rank);
+                key.toggle_piece(Some(Piece::new(side, piece::Type::King)), square);
+            }
+        }
+        key
+    }
+
+    fn evaluate(pos: &Position) -> PawnEntry {
+        let white_pawns = pos.piece_occupancy(Side::White, piece::Type::Pawn);
+        let black_pawns = pos.piece_occupancy(Side::Black, piece::Type::Pawn);
+        let per_side = |count: fn(Bitboard, Bitboard, Side) -> i8, param: ScorePair| {
+            [
+                count(white_pawns, black_pawns, Side::White) as Score * param,
+                count(black_pawns, white_pawns, Side::Black) as Score * param,
+            ]
+        };
+        let passed_pawns = [
+            Self::passed_pawns(white_pawns, black_pawns, Side::White),
+            Self::passed_pawns(black_pawns, white_pawns, Side::Black),
+        ];
+        let term_scores = [
+            (
+                Term::PassedPawn,
+                [
+                    Self::passed_pawn_scores_one_side(white_pawns, passed_pawns[0], Side::White),
+                    Self::passed_pawn_scores_one_side(black_pawns, passed_pawns[1], Side::Black),
+                ],
+            ),
+            (
+                Term::IsolatedPawn,
+                per_side(
+                    |own, _, _| Self::isolated_pawn_count_one_side(own),
+                    params::ISOLATED_PAWN,
+                ),
+            ),
+            (
+                Term::BackwardPawn,
+                per_side(Self::backward_pawn_count_one_side, params::BACKWARD_PAWN),
+            ),
+            (
+                Term::DoubledPawn,
+                per_side(
+                    |own, _, _| Self::doubled_pawn_count_one_side(own),
+                    params::DOUBLED_PAWN,
+                ),
+            ),
+            (
+                Term::PawnShield,
+                [
+                    Self::pawn_shield_scores_one_side(pos, Side::White),
+                    Self::pawn_shield_scores_one_side(pos, Side::Black),
+                ],
+            ),
+        ];
+        let scores = term_scores
+            .iter()
+            .fold(ScorePair(0, 0), |acc, (_, [white, black])| {
+                acc + *white - *black
+            });
+        PawnEntry {
+            scores,
+            term_scores,
+            passed_pawns,
+        }
+    }
+
+    // Number of passed pawns on each rank (from the pawn's side's point of
+    // view), white minus black
+    pub fn passed_pawn_rank_counts(
+        white_pawns: Bitboard,
+        black_pawns: Bitboard,
+    ) -> [i8; PASSED_PAWN_LEN] {
+        let mut counts = [0; PASSED_PAWN_LEN];
+        for (side, own, opp, sign) in [
+            (Side::White, white_pawns, black_pawns, 1),
+            (Side::Black, black_pawns, white_pawns, -1),
+        ] {
+            let mut passed_pawns = Self::passed_pawns(own, opp, side);
+            while passed_pawns != Bitboard::EMPTY {
+                let pawn = passed_pawns.square_scan_forward_reset();
+                counts[Self::relative_rank(pawn, side)] += sign;
+            }
         }
+        counts
+    }
+
+    pub fn protected_passed_pawn_count(white_pawns: Bitboard, black_pawns: Bitboard) -> i8 {
+        let count = |own, opp, side| {
+            Self::protected_passed_pawns(own, Self::passed_pawns(own, opp, side), side).pop_count()
+                as i8
+        };
+        count(white_pawns, black_pawns, Side::White) - count(black_pawns, white_pawns, Side::Black)
     }
 
-    pub fn passed_pawn_count(white_pawns: Bitboard, black_pawns: Bitboard) -> i8 {
-        Self::passed_pawn_count_one_side(white_pawns, black_pawns, Side::White)
-            - Self::passed_pawn_count_one_side(black_pawns, white_pawns, Side::Black)
+    pub fn connected_passed_pawn_count(white_pawns: Bitboard, black_pawns: Bitboard) -> i8 {
+        let count = |own, opp, side| {
+            Self::connected_passed_pawns(Self::passed_pawns(own, opp, side)).pop_count() as i8
+        };
+        count(white_pawns, black_pawns, Side::White) - count(black_pawns, white_pawns, Side::Black)
+    }
+
+    pub fn free_passed_pawn_count(pos: &Position) -> i8 {
+        let white_pawns = pos.piece_occupancy(Side::White, piece::Type::Pawn);
+        let black_pawns = pos.piece_occupancy(Side::Black, piece::Type::Pawn);
+        let count = |own, opp, side| {
+            let passed_pawns = Self::passed_pawns(own, opp, side);
+            Self::free_passed_pawns(passed_pawns, pos.occupancy(), side).pop_count() as i8
+        };
+        count(white_pawns, black_pawns, Side::White) - count(black_pawns, white_pawns, Side::Black)
     }
 
     pub fn isolated_pawn_count(white_pawns: Bitboard, black_pawns: Bitboard) -> i8 {
@@ -75,6 +227,74 @@ impl PawnStructure {
     }
 
     pub fn doubled_pawn_count(white_pawns: Bitboard, black_pawns: Bitboard) -> i8 {
+        Self::doubled_pawn_count_one_side(white_pawns)
+            - Self::doubled_pawn_count_one_side(black_pawns)
+    }
+
+    // Squares in the enemy half (excluding the enemy back rank) that are
+    // defended by an own pawn and can never be attacked by an enemy pawn
+    pub fn outposts(own_pawns: Bitboard, opp_pawns: Bitboard, side: Side) -> Bitboard {
+        let enemy_half = match side {
+            Side::White => Bitboard::RANK_5 | Bitboard::RANK_6 | Bitboard::RANK_7,
+            Side::Black => Bitboard::RANK_4 | Bitboard::RANK_3 | Bitboard::RANK_2,
+        };
+        enemy_half
+            & Pawn::attack_targets(own_pawns, side)
+            & !Pawn::front_attack_span(opp_pawns, !side)
+    }
+
+    pub fn pawn_shield_missing_count(pos: &Position) -> i8 {
+        Self::pawn_shield_counts_one_side(pos, Side::White).missing
+            - Self::pawn_shield_counts_one_side(pos, Side::Black).missing
+    }
+
+    pub fn pawn_shield_advanced_count(pos: &Position) -> i8 {
+        Self::pawn_shield_counts_one_side(pos, Side::White).advanced
+            - Self::pawn_shield_counts_one_side(pos, Side::Black).advanced
+    }
+
+    pub fn pawn_shield_open_file_count(pos: &Position) -> i8 {
+        Self::pawn_shield_counts_one_side(pos, Side::White).open_files
+            - Self::pawn_shield_counts_one_side(pos, Side::Black).open_files
+    }
+
+    fn pawn_shield_scores_one_side(pos: &Position, side: Side) -> ScorePair {
+        let counts = Self::pawn_shield_counts_one_side(pos, side);
+        counts.missing as Score * params::PAWN_SHIELD_MISSING
+            + counts.advanced as Score * params::PAWN_SHIELD_ADVANCED
+            + counts.open_files as Score * params::PAWN_SHIELD_OPEN_FILE
+    }
+
+    // The shield files are the king file and the files next to it. A shield
+    // file is intact with an own pawn on the second rank, advanced with an
+    // own pawn on the third rank and missing otherwise.
+    fn pawn_shield_counts_one_side(pos: &Position, side: Side) -> PawnShieldCounts {
+        let mut counts = PawnShieldCounts::default();
+        let king = pos.piece_occupancy(side, piece::Type::King);
+        let own_pawns = pos.piece_occupancy(side, piece::Type::Pawn);
+        let all_pawns = own_pawns | pos.piece_occupancy(!side, piece::Type::Pawn);
+        let (home_rank, advanced_rank) = match side {
+            Side::White => (Bitboard::RANK_2, Bitboard::RANK_3),
+            Side::Black => (Bitboard::RANK_7, Bitboard::RANK_6),
+        };
+        for file in [king.west_one(), king, king.east_one()] {
+            if file == Bitboard::EMPTY {
+                continue;
+            }
+            let file = file.file_fill();
+            if file & own_pawns & home_rank != Bitboard::EMPTY {
+                continue;
+            }
+            match file & own_pawns & advanced_rank != Bitboard::EMPTY {
+                true => counts.advanced += 1,
+                false => counts.missing += 1,
+            }
+            counts.open_files += (file & all_pawns == Bitboard::EMPTY) as i8;
+        }
+        counts
+    }
+
+    fn doubled_pawn_count_one_side(own_pawns: Bitboard) -> i8 {
         let mut doubled_pawn_count = 0;
         for file in [
             Bitboard::FILE_A,
@@ -86,30 +306,74 @@ impl PawnStructure {
             Bitboard::FILE_G,
             Bitboard::FILE_H,
         ] {
-            let white_pawns_on_file = (white_pawns & file).pop_count() as i8;
-            doubled_pawn_count += std::cmp::max(0, white_pawns_on_file - 1);
-            let black_pawns_on_file = (black_pawns & file).pop_count() as i8;
-            doubled_pawn_count -= std::cmp::max(0, black_pawns_on_file - 1);
+            let own_pawns_on_file = (own_pawns & file).pop_count() as i8;
+            doubled_pawn_count += std::cmp::max(0, own_pawns_on_file - 1);
         }
         doubled_pawn_count
     }
 
-    fn passed_pawn_count_one_side(
+    fn passed_pawn_scores_one_side(
         own_pawns: Bitboard,
-        opp_pawns: Bitboard,
+        passed_pawns: Bitboard,
         side_to_move: Side,
-    ) -> i8 {
+    ) -> ScorePair {
+        let mut scores = ScorePair(0, 0);
+        let mut passed_pawns_mut = passed_pawns;
+        while passed_pawns_mut != Bitboard::EMPTY {
+            let pawn = passed_pawns_mut.square_scan_forward_reset();
+            scores += params::PASSED_PAWN[Self::relative_rank(pawn, side_to_move)];
+        }
+        let protected = Self::protected_passed_pawns(own_pawns, passed_pawns, side_to_move);
+        let connected = Self::connected_passed_pawns(passed_pawns);
+        scores
+            + protected.pop_count() as Score * params::PASSED_PAWN_PROTECTED
+            + connected.pop_count() as Score * params::PASSED_PAWN_CONNECTED
+    }
+
+    fn passed_pawns(own_pawns: Bitboard, opp_pawns: Bitboard, side_to_move: Side) -> Bitboard {
         let all_pawns = own_pawns | opp_pawns;
         let opp_pawn_attack_targets = Pawn::attack_targets(opp_pawns, !side_to_move);
 
-        let mut passed_count = 0;
+        let mut passed_pawns = Bitboard::EMPTY;
         let mut own_pawns_mut = own_pawns;
         while own_pawns_mut != Bitboard::EMPTY {
             let pawn = own_pawns_mut.square_scan_forward_reset();
-            passed_count +=
-                Self::is_passed(all_pawns, opp_pawn_attack_targets, pawn, side_to_move) as i8;
+            if Self::is_passed(all_pawns, opp_pawn_attack_targets, pawn, side_to_move) {
+                passed_pawns |= Bitboard::from_square(pawn);
+            }
+        }
+        passed_pawns
+    }
+
+    // Passed pawns defended by a pawn
+    fn protected_passed_pawns(
+        own_pawns: Bitboard,
+        passed_pawns: Bitboard,
+        side_to_move: Side,
+    ) -> Bitboard {
+        passed_pawns & Pawn::attack_targets(own_pawns, side_to_move)
+    }
+
+    // Passed pawns with another passed pawn on an adjacent file
+    fn connected_passed_pawns(passed_pawns: Bitboard) -> Bitboard {
+        let adjacent_files = (passed_pawns.east_one() | passed_pawns.west_one()).file_fill();
+        passed_pawns & adjacent_files
+    }
+
+    // Passed pawns without any piece in front of them
+    fn free_passed_pawns(
+        passed_pawns: Bitboard,
+        occupancy: Bitboard,
+        side_to_move: Side,
+    ) -> Bitboard {
+        passed_pawns & !Pawn::rear_span(occupancy, side_to_move)
+    }
+
+    fn relative_rank(square: Square, side: Side) -> usize {
+        match side {
+            Side::White => square.rank().idx(),
+            Side::Black => square.flip_vertical().rank().idx(),
         }
-        passed_count
     }
 
     fn is_passed(
@@ -152,3 +416,121 @@ impl PawnStructure {
         backward_pawns.pop_count() as i8
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use movegen::bitboard::Bitboard;
+    use movegen::fen::Fen;
+    use movegen::piece;
+    use movegen::side::Side;
+
+    use super::PawnStructure;
+
+    fn pawn_counts(fen: &str, count: fn(Bitboard) -> i8) -> (i8, i8) {
+        let pos = Fen::str_to_pos(fen).unwrap();
+        (
+            count(pos.piece_occupancy(Side::White, piece::Type::Pawn)),
+            count(pos.piece_occupancy(Side::Black, piece::Type::Pawn)),
+        )
+    }
+
+    #[test]
+    fn isolated_pawns() {
+        for (fen, exp_white, exp_black) in [
+            ("4k3/8/8/8/8/8/PPPPPPPP/4K3 w - - 0 1", 0, 0),
+            // a2 has no neighbor, c2 and d2 defend each other. f7 and h7 are
+            // both isolated.
+            ("4k3/5p1p/8/8/8/8/P1PP4/4K3 w - - 0 1", 1, 2),
+            // Doubled isolated pawns count twice
+            ("4k3/p7/p7/8/8/4P3/4P3/4K3 w - - 0 1", 2, 2),
+        ] {
+            assert_eq!(
+                (exp_white, exp_black),
+                pawn_counts(fen, PawnStructure::isolated_pawn_count_one_side),
+                "\nPosition: {fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn doubled_pawns() {
+        for (fen, exp_white, exp_black) in [
+            ("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1", 0, 0),
+            // One penalty per extra pawn on a file
+            ("4k3/3pp3/3p4/8/2P5/2P5/2P5/4K3 w - - 0 1", 2, 1),
+            ("4k3/p6p/p6p/8/8/P6P/P6P/4K3 w - - 0 1", 2, 2),
+        ] {
+            assert_eq!(
+                (exp_white, exp_black),
+                pawn_counts(fen, PawnStructure::doubled_pawn_count_one_side),
+                "\nPosition: {fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn backward_pawns() {
+        for (fen, exp_white, exp_black) in [
+            ("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1", 0, 0),
+            // d3 can't be defended by c4 or e4 and its stop square is
+            // controlled by e5. The same holds for d6 from black's point of
+            // view.
+            ("4k3/8/3p4/4p3/2P1P3/3P4/8/4K3 w - - 0 1", 1, 1),
+            // The stop square of d3 isn't attacked by a black pawn
+            ("4k3/8/8/8/2P1P3/3P4/8/4K3 w - - 0 1", 0, 0),
+            // c2 can still be defended by b2. b4 has no neighbor and c2
+            // controls its stop square.
+            ("4k3/8/8/8/1p6/8/1PP5/4K3 w - - 0 1", 0, 1),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let white_pawns = pos.piece_occupancy(Side::White, piece::Type::Pawn);
+            let black_pawns = pos.piece_occupancy(Side::Black, piece::Type::Pawn);
+            assert_eq!(
+                (exp_white, exp_black),
+                (
+                    PawnStructure::backward_pawn_count_one_side(
+                        white_pawns,
+                        black_pawns,
+                        Side::White
+                    ),
+                    PawnStructure::backward_pawn_count_one_side(
+                        black_pawns,
+                        white_pawns,
+                        Side::Black
+                    ),
+                ),
+                "\nPosition: {fen}"
+            );
+            assert_eq!(
+                exp_white - exp_black,
+                PawnStructure::backward_pawn_count(white_pawns, black_pawns)
+            );
+        }
+    }
+
+    #[test]
+    fn pawn_shield() {
+        for (fen, exp_white, exp_black) in [
+            // Intact shields
+            ("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", (0, 0, 0), (0, 0, 0)),
+            // g3 is advanced. Both f pawns are missing, so the f file is
+            // open.
+            ("6k1/6pp/8/8/8/6P1/7P/6K1 w - - 0 1", (1, 1, 1), (1, 0, 1)),
+            // A king on the edge only has two shield files. The g pawn on the
+            // fourth rank counts as missing.
+            ("7k/6pp/8/8/6P1/8/7P/7K w - - 0 1", (1, 0, 0), (0, 0, 0)),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            for (side, (exp_missing, exp_advanced, exp_open_files)) in
+                [(Side::White, exp_white), (Side::Black, exp_black)]
+            {
+                let counts = PawnStructure::pawn_shield_counts_one_side(&pos, side);
+                assert_eq!(
+                    (exp_missing, exp_advanced, exp_open_files),
+                    (counts.missing, counts.advanced, counts.open_files),
+                    "\nPosition: {fen}\nSide: {side:?}"
+                );
+            }
+        }
+    }
+}
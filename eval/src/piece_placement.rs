@@ -0,0 +1,201 @@
+use movegen::{bitboard::Bitboard, piece, position::Position, side::Side};
+
+use crate::{params, pawn_structure::PawnStructure, score_pair::ScorePair, Score};
+
+// Terms for the placement of single pieces relative to the pawns. They depend
+// on the pieces, so they can't be cached in the pawn hash table.
+#[derive(Debug, Clone, Default)]
+pub struct PiecePlacement;
+
+impl PiecePlacement {
+    // Rook file scores of one side, from that side's point of view
+    pub fn rook_file_scores_one_side(pos: &Position, side: Side) -> ScorePair {
+        let (open, semi_open) = Self::rook_file_counts_one_side(pos, side);
+        open as Score * params::ROOK_OPEN_FILE + semi_open as Score * params::ROOK_SEMI_OPEN_FILE
+    }
+
+    pub fn rook_open_file_count(pos: &Position) -> i8 {
+        Self::rook_file_counts_one_side(pos, Side::White).0
+            - Self::rook_file_counts_one_side(pos, Side::Black).0
+    }
+
+    pub fn rook_semi_open_file_count(pos: &Position) -> i8 {
+        Self::rook_file_counts_one_side(pos, Side::White).1
+            - Self::rook_file_counts_one_side(pos, Side::Black).1
+    }
+
+    // Rook on the seventh rank scores of one side, from that side's point of
+    // view
+    pub fn rook_seventh_scores_one_side(pos: &Position, side: Side) -> ScorePair {
+        Self::rook_seventh_count_one_side(pos, side) as Score * params::ROOK_SEVENTH
+    }
+
+    pub fn rook_seventh_count(pos: &Position) -> i8 {
+        Self::rook_seventh_count_one_side(pos, Side::White)
+            - Self::rook_seventh_count_one_side(pos, Side::Black)
+    }
+
+    // Number of rooks on the seventh rank (from the side's point of view).
+    // They only count if the enemy king is on its back rank or if there are
+    // enemy pawns on the seventh rank.
+    fn rook_seventh_count_one_side(pos: &Position, side: Side) -> i8 {
+        let (seventh_rank, eighth_rank) = match side {
+            Side::White => (Bitboard::RANK_7, Bitboard::RANK_8),
+            Side::Black => (Bitboard::RANK_2, Bitboard::RANK_1),
+        };
+        let opp_king = pos.piece_occupancy(!side, piece::Type::King);
+        let opp_pawns = pos.piece_occupancy(!side, piece::Type::Pawn);
+        if opp_king & eighth_rank == Bitboard::EMPTY && opp_pawns & seventh_rank == Bitboard::EMPTY
+        {
+            return 0;
+        }
+        (pos.piece_occupancy(side, piece::Type::Rook) & seventh_rank).pop_count() as i8
+    }
+
+    // Outpost scores of one side, from that side's point of view
+    pub fn outpost_scores_one_side(pos: &Position, side: Side) -> ScorePair {
+        let (knights, bishops) = Self::outpost_counts_one_side(pos, side);
+        knights as Score * params::KNIGHT_OUTPOST + bishops as Score * params::BISHOP_OUTPOST
+    }
+
+    pub fn knight_outpost_count(pos: &Position) -> i8 {
+        Self::outpost_counts_one_side(pos, Side::White).0
+            - Self::outpost_counts_one_side(pos, Side::Black).0
+    }
+
+    pub fn bishop_outpost_count(pos: &Position) -> i8 {
+        Self::outpost_counts_one_side(pos, Side::White).1
+            - Self::outpost_counts_one_side(pos, Side::Black).1
+    }
+
+    // Number of knights and bishops on outposts
+    fn outpost_counts_one_side(pos: &Position, side: Side) -> (i8, i8) {
+        let outposts = PawnStructure::outposts(
+            pos.piece_occupancy(side, piece::Type::Pawn),
+            pos.piece_occupancy(!side, piece::Type::Pawn),
+            side,
+        );
+        let knights = pos.piece_occupancy(side, piece::Type::Knight) & outposts;
+        let bishops = pos.piece_occupancy(side, piece::Type::Bishop) & outposts;
+        (knights.pop_count() as i8, bishops.pop_count() as i8)
+    }
+
+    // Number of rooks on open files (without any pawns) and on semi-open files
+    // (without own pawns, but with enemy pawns)
+    fn rook_file_counts_one_side(pos: &Position, side: Side) -> (i8, i8) {
+        let own_pawns = pos.piece_occupancy(side, piece::Type::Pawn);
+        let opp_pawns = pos.piece_occupancy(!side, piece::Type::Pawn);
+        let mut open = 0;
+        let mut semi_open = 0;
+        let mut rooks = pos.piece_occupancy(side, piece::Type::Rook);
+        while rooks != Bitboard::EMPTY {
+            let file = Bitboard::from_square(rooks.square_scan_forward_reset()).file_fill();
+            if file & own_pawns != Bitboard::EMPTY {
+                continue;
+            }
+            match file & opp_pawns == Bitboard::EMPTY {
+                true => open += 1,
+                false => semi_open += 1,
+            }
+        }
+        (open, semi_open)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use movegen::fen::Fen;
+    use movegen::side::Side;
+
+    use super::PiecePlacement;
+
+    #[test]
+    fn rook_files() {
+        for (fen, exp_white, exp_black) in [
+            (
+                "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1",
+                (0, 0),
+                (0, 0),
+            ),
+            // The d file is open and the a file is semi-open for black. The
+            // rook on f1 is behind its own pawn.
+            (
+                "r2rk3/1pp1pppp/8/8/8/8/P1P2PPP/3RKR2 w - - 0 1",
+                (1, 0),
+                (1, 1),
+            ),
+            // Doubled rooks on an open file both count
+            (
+                "4k3/ppp2ppp/8/8/8/3R4/PPP2PPP/3R1K2 w - - 0 1",
+                (2, 0),
+                (0, 0),
+            ),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert_eq!(
+                (exp_white, exp_black),
+                (
+                    PiecePlacement::rook_file_counts_one_side(&pos, Side::White),
+                    PiecePlacement::rook_file_counts_one_side(&pos, Side::Black)
+                ),
+                "\nPosition: {fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn rook_seventh() {
+        for (fen, exp_white, exp_black) in [
+            // The enemy king is on its back rank
+            ("6k1/R7/8/8/8/8/8/6K1 w - - 0 1", 1, 0),
+            // There are enemy pawns on the seventh rank
+            ("8/R4pk1/8/8/8/8/8/6K1 w - - 0 1", 1, 0),
+            // Neither condition holds
+            ("8/R7/6k1/8/8/8/8/6K1 w - - 0 1", 0, 0),
+            // The rook isn't on the seventh rank
+            ("6k1/8/R7/8/8/8/8/6K1 w - - 0 1", 0, 0),
+            // Doubled rooks on the seventh rank, for both sides
+            ("6k1/RR3p2/8/8/8/8/3P1rr1/6K1 w - - 0 1", 2, 2),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert_eq!(
+                (exp_white, exp_black),
+                (
+                    PiecePlacement::rook_seventh_count_one_side(&pos, Side::White),
+                    PiecePlacement::rook_seventh_count_one_side(&pos, Side::Black)
+                ),
+                "\nPosition: {fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn outposts() {
+        for (fen, exp_white, exp_black) in [
+            // The knight on d5 is defended by c4 and e4. The black pawns on
+            // b7 and f7 can never attack it.
+            ("4k3/1p3p2/8/3N4/2P1P3/8/8/4K3 w - - 0 1", (1, 0), (0, 0)),
+            // e6 attacks the knight
+            ("4k3/1p6/4p3/3N4/2P1P3/8/8/4K3 w - - 0 1", (0, 0), (0, 0)),
+            // c7 can still advance to c6 and attack the knight
+            ("4k3/2p2p2/8/3N4/2P1P3/8/8/4K3 w - - 0 1", (0, 0), (0, 0)),
+            // The knight isn't defended by a pawn
+            ("4k3/1p3p2/8/3N4/8/8/8/4K3 w - - 0 1", (0, 0), (0, 0)),
+            // d4 isn't in enemy territory
+            ("4k3/1p3p2/8/8/3N4/2P5/8/4K3 w - - 0 1", (0, 0), (0, 0)),
+            // Bishops on outposts, for both sides
+            ("4k3/1p3p2/8/3B4/2PbP3/3P4/8/4K3 w - - 0 1", (0, 1), (0, 0)),
+            ("4k3/8/8/2p1p3/1P1n4/8/6P1/4K3 b - - 0 1", (0, 0), (1, 0)),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert_eq!(
+                (exp_white, exp_black),
+                (
+                    PiecePlacement::outpost_counts_one_side(&pos, Side::White),
+                    PiecePlacement::outpost_counts_one_side(&pos, Side::Black)
+                ),
+                "\nPosition: {fen}"
+            );
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use movegen::bitboard::Bitboard;
+use movegen::piece;
+use movegen::position::Position;
+use movegen::side::Side;
+
+use crate::{params, Score};
+
+// Scale factors for endgames which are more drawish than the evaluation
+// terms suggest. The scale factor is applied to the final score.
+#[derive(Debug, Clone, Default)]
+pub struct ScaleFactor;
+
+impl ScaleFactor {
+    // Scale factor of the position, from 0 (dead draw) to
+    // params::SCALE_FACTOR_NORMAL (no scaling)
+    pub fn scale_factor(pos: &Position) -> Score {
+        if let Some(scale_factor) = Self::opposite_colored_bishops(pos) {
+            return scale_factor;
+        }
+        params::SCALE_FACTOR_NORMAL
+    }
+
+    // Each side has a single bishop, on squares of different colors. Other
+    // than that, there are only pawns and at most one rook per side.
+    fn opposite_colored_bishops(pos: &Position) -> Option<Score> {
+        let white_bishops = pos.piece_occupancy(Side::White, piece::Type::Bishop);
+        let black_bishops = pos.piece_occupancy(Side::Black, piece::Type::Bishop);
+        if white_bishops.pop_count() != 1
+            || black_bishops.pop_count() != 1
+            || (white_bishops & Bitboard::LIGHT_SQUARES == Bitboard::EMPTY)
+                == (black_bishops & Bitboard::LIGHT_SQUARES == Bitboard::EMPTY)
+            || pos.piece_type_occupancy(piece::Type::Knight) != Bitboard::EMPTY
+            || pos.piece_type_occupancy(piece::Type::Queen) != Bitboard::EMPTY
+        {
+            return None;
+        }
+
+        let white_rooks = pos
+            .piece_occupancy(Side::White, piece::Type::Rook)
+            .pop_count();
+        let black_rooks = pos
+            .piece_occupancy(Side::Black, piece::Type::Rook)
+            .pop_count();
+        let table = match (white_rooks, black_rooks) {
+            (0, 0) => &params::SCALE_FACTOR_OCB,
+            (1, 1) => &params::SCALE_FACTOR_OCB_ROOKS,
+            _ => return None,
+        };
+        let white_pawns = pos
+            .piece_occupancy(Side::White, piece::Type::Pawn)
+            .pop_count();
+        let black_pawns = pos
+            .piece_occupancy(Side::Black, piece::Type::Pawn)
+            .pop_count();
+        let pawn_diff = white_pawns.abs_diff(black_pawns);
+        Some(table[pawn_diff.min(params::OCB_SCALE_FACTOR_LEN - 1)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use movegen::fen::Fen;
+
+    use super::ScaleFactor;
+    use crate::params;
+
+    #[test]
+    fn opposite_colored_bishops() {
+        for (fen, exp) in [
+            // Pure OCB endgames, with different pawn count differences
+            (
+                "4k3/5p2/2b5/8/8/2B5/5PP1/4K3 w - - 0 1",
+                params::SCALE_FACTOR_OCB[1],
+            ),
+            (
+                "4k3/5pp1/2b5/8/8/2B5/5PP1/4K3 w - - 0 1",
+                params::SCALE_FACTOR_OCB[0],
+            ),
+            (
+                "4k3/8/2b5/8/8/2B5/PP3PPP/4K3 w - - 0 1",
+                params::SCALE_FACTOR_OCB[params::OCB_SCALE_FACTOR_LEN - 1],
+            ),
+            // With one rook per side
+            (
+                "r3k3/5p2/2b5/8/8/2B5/5PP1/R3K3 w - - 0 1",
+                params::SCALE_FACTOR_OCB_ROOKS[1],
+            ),
+            // Same-colored bishops
+            (
+                "4k3/5p2/3b4/8/8/2B5/5PP1/4K3 w - - 0 1",
+                params::SCALE_FACTOR_NORMAL,
+            ),
+            // Additional pieces
+            (
+                "4k3/5p2/2b5/8/8/2B5/5PP1/3NK3 w - - 0 1",
+                params::SCALE_FACTOR_NORMAL,
+            ),
+            (
+                "r3k3/5p2/2b5/8/8/2B5/5PP1/4K3 w - - 0 1",
+                params::SCALE_FACTOR_NORMAL,
+            ),
+            (
+                "4k3/5p2/2b5/8/8/2BB4/5PP1/4K3 w - - 0 1",
+                params::SCALE_FACTOR_NORMAL,
+            ),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert_eq!(exp, ScaleFactor::scale_factor(&pos), "\nPosition: {fen}");
+        }
+    }
+}
@@ -12,6 +12,19 @@ pub const EQ_POSITION: Score = 0;
 pub const BLACK_WIN: Score = NEG_INF + 1;
 pub const WHITE_WIN: Score = POS_INF - 1;
 
+// Win/draw/loss model. The expected win rate for a score s (in centipawns, from
+// the side to move's point of view) is modelled as
+// 1 / (1 + exp((WDL_MODEL_A - s) / WDL_MODEL_B)), the loss rate is the same
+// with -s. The draw rate is the remainder.
+// WDL_MODEL_A is the score at which a win becomes as likely as not,
+// WDL_MODEL_B controls the spread.
+// Fitted with `tuner fit-wdl` to the static evaluations and outcomes of 43k
+// quiet positions from 600 self-play games at 10000 nodes per move. Games at
+// longer time controls are drawn more often, so the draw rates are on the low
+// side.
+pub const WDL_MODEL_A: f64 = 176.0;
+pub const WDL_MODEL_B: f64 = 191.0;
+
 const MAX_MATE_DIST: Score = 255;
 const MIN_CP: Score = BLACK_WIN + MAX_MATE_DIST + 1;
 const MAX_CP: Score = WHITE_WIN - MAX_MATE_DIST - 1;
@@ -74,6 +87,20 @@ pub fn dec_mate_dist_by(s: Score, plies: usize) -> Score {
     }
 }
 
+// Expected win, draw and loss rates in permille. They always add up to 1000.
+pub fn win_draw_loss(s: Score) -> (u16, u16, u16) {
+    if is_white_mating(s) {
+        return (1000, 0, 0);
+    }
+    if is_black_mating(s) {
+        return (0, 0, 1000);
+    }
+    let logistic = |x: f64| 1.0 / (1.0 + ((WDL_MODEL_A - x) / WDL_MODEL_B).exp());
+    let win = (1000.0 * logistic(s as f64)).round() as u16;
+    let loss = (1000.0 * logistic(-s as f64)).round() as u16;
+    (win, 1000 - win - loss, loss)
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ScoreVariant {
     Centipawns(i16),
@@ -198,6 +225,34 @@ mod tests {
         assert_eq!(POS_INF, dec_mate_dist_by(POS_INF, 2));
     }
 
+    #[test]
+    fn win_draw_loss_model() {
+        let sum = |(w, d, l): (u16, u16, u16)| w + d + l;
+
+        let (w, d, l) = win_draw_loss(EQ_POSITION);
+        assert_eq!(w, l);
+        assert!(d > w);
+        assert_eq!(1000, sum((w, d, l)));
+
+        assert_eq!((1000, 0, 0), win_draw_loss(WHITE_WIN));
+        assert_eq!((1000, 0, 0), win_draw_loss(MAX_CP + 1));
+        assert_eq!((0, 0, 1000), win_draw_loss(BLACK_WIN));
+        assert_eq!((0, 0, 1000), win_draw_loss(MIN_CP - 1));
+
+        // Symmetrical and monotonic
+        let mut prev = win_draw_loss(MIN_CP);
+        for s in MIN_CP + 1..=MAX_CP {
+            let wdl = win_draw_loss(s);
+            let (w, d, l) = wdl;
+            assert_eq!(1000, sum(wdl));
+            assert_eq!((l, d, w), win_draw_loss(-s));
+            assert!(w >= prev.0);
+            assert!(l <= prev.2);
+            prev = wdl;
+        }
+        assert_eq!(500, win_draw_loss(WDL_MODEL_A as Score).0);
+    }
+
     #[test]
     #[should_panic]
     fn invalid_score_min() {
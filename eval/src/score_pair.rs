@@ -1,10 +1,17 @@
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 
 use crate::Score;
+use movegen::piece_square_table::PieceSquareScore;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ScorePair(pub Score, pub Score);
 
+impl From<PieceSquareScore> for ScorePair {
+    fn from(score: PieceSquareScore) -> Self {
+        Self(score.0, score.1)
+    }
+}
+
 impl Mul<Score> for ScorePair {
     type Output = Self;
 
@@ -0,0 +1,188 @@
+use crate::score_pair::ScorePair;
+use crate::{params, GamePhase, Score};
+use std::fmt;
+
+// The terms of the evaluation. Material is part of the piece-square tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    PieceSquare,
+    Tempo,
+    PassedPawn,
+    IsolatedPawn,
+    BackwardPawn,
+    DoubledPawn,
+    PawnShield,
+    Mobility,
+    BishopPair,
+    RookFile,
+    RookSeventh,
+    Outpost,
+    KingTropism,
+    KingSafety,
+}
+
+impl Term {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Term::PieceSquare => "Material + PST",
+            Term::Tempo => "Tempo",
+            Term::PassedPawn => "Passed pawns",
+            Term::IsolatedPawn => "Isolated pawns",
+            Term::BackwardPawn => "Backward pawns",
+            Term::DoubledPawn => "Doubled pawns",
+            Term::PawnShield => "Pawn shield",
+            Term::Mobility => "Mobility",
+            Term::BishopPair => "Bishop pair",
+            Term::RookFile => "Rook files",
+            Term::RookSeventh => "Rook on 7th",
+            Term::Outpost => "Outposts",
+            Term::KingTropism => "King tropism",
+            Term::KingSafety => "King safety",
+        }
+    }
+}
+
+// Receives the contributions of the evaluation terms. The scores of each side
+// are from that side's point of view. The evaluation is generic over the
+// recorder, so with NoTrace all recording compiles away.
+pub trait EvalRecorder {
+    const ENABLED: bool;
+
+    fn record(&mut self, term: Term, white: ScorePair, black: ScorePair);
+
+    // The blended score is the sum of the terms tapered by the game phase,
+    // before scaling and before endgame knowledge overrides it
+    fn record_result(
+        &mut self,
+        game_phase: usize,
+        blended: Score,
+        scale_factor: Score,
+        score: Score,
+    );
+}
+
+pub struct NoTrace;
+
+impl EvalRecorder for NoTrace {
+    const ENABLED: bool = false;
+
+    #[inline(always)]
+    fn record(&mut self, _term: Term, _white: ScorePair, _black: ScorePair) {}
+
+    #[inline(always)]
+    fn record_result(
+        &mut self,
+        _game_phase: usize,
+        _blended: Score,
+        _scale_factor: Score,
+        _score: Score,
+    ) {
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EvalTrace {
+    terms: Vec<(Term, ScorePair, ScorePair)>,
+    game_phase: usize,
+    blended_score: Score,
+    scale_factor: Score,
+    score: Score,
+}
+
+impl EvalRecorder for EvalTrace {
+    const ENABLED: bool = true;
+
+    fn record(&mut self, term: Term, white: ScorePair, black: ScorePair) {
+        self.terms.push((term, white, black));
+    }
+
+    fn record_result(
+        &mut self,
+        game_phase: usize,
+        blended: Score,
+        scale_factor: Score,
+        score: Score,
+    ) {
+        self.game_phase = game_phase;
+        self.blended_score = blended;
+        self.scale_factor = scale_factor;
+        self.score = score;
+    }
+}
+
+impl EvalTrace {
+    // Contributions of all terms as (term, white scores, black scores)
+    pub fn terms(&self) -> &[(Term, ScorePair, ScorePair)] {
+        &self.terms
+    }
+
+    // Sum of all terms (white minus black) before blending
+    pub fn total(&self) -> ScorePair {
+        self.terms
+            .iter()
+            .fold(ScorePair(0, 0), |acc, (_, w, b)| acc + *w - *b)
+    }
+
+    // From 0 (endgame) to GamePhase::MAX (middlegame)
+    pub fn game_phase(&self) -> usize {
+        self.game_phase
+    }
+
+    // Total tapered by the game phase, from white's point of view
+    pub fn blended_score(&self) -> Score {
+        self.blended_score
+    }
+
+    // Applied to the blended score, out of params::SCALE_FACTOR_NORMAL
+    pub fn scale_factor(&self) -> Score {
+        self.scale_factor
+    }
+
+    // Final score from white's point of view
+    pub fn score(&self) -> Score {
+        self.score
+    }
+}
+
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter, name: &str, w: ScorePair, b: ScorePair| {
+            let t = w - b;
+            writeln!(
+                f,
+                "{name:>15} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                w.0, w.1, b.0, b.1, t.0, t.1
+            )
+        };
+        let header = format!(
+            "{:>15} | {:^11} | {:^11} | {:^11}",
+            "Term", "White", "Black", "Total"
+        );
+        writeln!(f, "{}", header.trim_end())?;
+        writeln!(
+            f,
+            "{:>15} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+            "", "MG", "EG", "MG", "EG", "MG", "EG"
+        )?;
+        writeln!(f, "{:-<16}+{:-<13}+{:-<13}+{:-<12}", "", "", "", "")?;
+        for (term, w, b) in &self.terms {
+            row(f, term.name(), *w, *b)?;
+        }
+        writeln!(f, "{:-<16}+{:-<13}+{:-<13}+{:-<12}", "", "", "", "")?;
+        let (w, b) = self.terms.iter().fold(
+            (ScorePair(0, 0), ScorePair(0, 0)),
+            |(w_acc, b_acc), (_, w, b)| (w_acc + *w, b_acc + *b),
+        );
+        row(f, "Total", w, b)?;
+        writeln!(f)?;
+        writeln!(f, "Game phase: {} / {}", self.game_phase, GamePhase::MAX)?;
+        writeln!(f, "Blended score (white side): {}", self.blended_score)?;
+        writeln!(
+            f,
+            "Scale factor: {} / {}",
+            self.scale_factor,
+            params::SCALE_FACTOR_NORMAL
+        )?;
+        writeln!(f, "Final evaluation (white side): {}", self.score)
+    }
+}
@@ -1,33 +1,86 @@
-use engine::{Engine, EngineOptions, DEFAULT_HASH_BYTES};
+use engine::{Engine, EngineOptions, Variant, DEFAULT_HASH_BYTES};
 use eval::complex::Complex;
 use search::alpha_beta::AlphaBeta;
+use std::env;
 use std::error::Error;
 use std::io;
 use std::sync::{Arc, Mutex};
 use uci::uci_in::{
-    debug, go, is_ready, position, quit, set_option, stop, uci as cmd_uci, ucinewgame,
+    bench, d, debug, eval as cmd_eval, genfens, go, is_ready, perft, position, quit, set_option,
+    speedtest, stop, uci as cmd_uci, ucinewgame,
 };
 use uci::UciOut;
 use uci::{Parser, ParserMessage};
+use xboard::{Xboard, XboardMessage, XboardOut};
 
 pub fn run() -> Result<(), Box<dyn Error>> {
+    // `fatalii bench [depth]` runs the bench and exits
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if args.first().is_some_and(|a| a == "bench") {
+        let mut uci_out = UciOut::new(
+            Box::new(io::stdout()),
+            env!("CARGO_PKG_VERSION"),
+            Arc::new(Mutex::new(EngineOptions::default())),
+        );
+        let depth = bench::parse_depth(&args[1..].join(" "))?;
+        return bench::run(&mut uci_out, depth);
+    }
+    // OpenBench calls `fatalii "genfens <count> seed <seed> book <book>" quit`
+    if args
+        .first()
+        .is_some_and(|a| a.split_whitespace().next() == Some("genfens"))
+    {
+        let mut uci_out = UciOut::new(
+            Box::new(io::stdout()),
+            env!("CARGO_PKG_VERSION"),
+            Arc::new(Mutex::new(EngineOptions::default())),
+        );
+        let command = args
+            .iter()
+            .filter(|a| *a != "quit")
+            .map(String::as_str)
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let genfens_args = command.trim_start().trim_start_matches("genfens");
+        return genfens::run(&mut uci_out, genfens_args, Variant::Standard);
+    }
+
+    // The protocol is selected by the first command. Everything but `xboard`
+    // starts UCI.
+    let reader = io::stdin();
+    let mut buffer = String::new();
+    reader.read_line(&mut buffer)?;
+    match buffer.split_whitespace().next() {
+        Some("xboard") => run_xboard(reader, buffer),
+        _ => run_uci(reader, buffer),
+    }
+}
+
+fn run_uci(reader: io::Stdin, mut buffer: String) -> Result<(), Box<dyn Error>> {
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
     let uci_out = UciOut::new(
         Box::new(io::stdout()),
         env!("CARGO_PKG_VERSION"),
         Arc::clone(&engine_options),
     );
+
     let evaluator = Box::new(Complex::new());
     let search_algo = AlphaBeta::new(evaluator, DEFAULT_HASH_BYTES);
     let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
 
     let mut parser = Parser::new(uci_out);
+    parser.register_command(String::from("bench"), Box::new(bench::run_command));
+    parser.register_command(String::from("d"), Box::new(d::run_command));
     parser.register_command(String::from("debug"), Box::new(debug::run_command));
+    parser.register_command(String::from("eval"), Box::new(cmd_eval::run_command));
+    parser.register_command(String::from("genfens"), Box::new(genfens::run_command));
     parser.register_command(String::from("go"), Box::new(go::run_command));
     parser.register_command(String::from("isready"), Box::new(is_ready::run_command));
+    parser.register_command(String::from("perft"), Box::new(perft::run_command));
     parser.register_command(String::from("position"), Box::new(position::run_command));
     parser.register_command(String::from("quit"), Box::new(quit::run_command));
     parser.register_command(String::from("setoption"), Box::new(set_option::run_command));
+    parser.register_command(String::from("speedtest"), Box::new(speedtest::run_command));
     parser.register_command(String::from("stop"), Box::new(stop::run_command));
     parser.register_command(String::from("uci"), Box::new(cmd_uci::run_command));
     parser.register_command(
@@ -35,16 +88,41 @@ pub fn run() -> Result<(), Box<dyn Error>> {
         Box::new(ucinewgame::run_command),
     );
 
-    let reader = io::stdin();
-    let mut buffer = String::new();
     loop {
-        reader.read_line(&mut buffer)?;
         match parser.run_command(&buffer, &mut engine) {
             Ok(Some(ParserMessage::Quit)) => break,
             Err(e) => eprintln!("{e}"),
             _ => {}
         }
         buffer.clear();
+        // End of input is treated like `quit`
+        if reader.read_line(&mut buffer)? == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn run_xboard(reader: io::Stdin, mut buffer: String) -> Result<(), Box<dyn Error>> {
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let mut xboard_out = XboardOut::new(Box::new(io::stdout()), env!("CARGO_PKG_VERSION"));
+
+    let evaluator = Box::new(Complex::new());
+    let search_algo = AlphaBeta::new(evaluator, DEFAULT_HASH_BYTES);
+    let mut engine = Engine::new(search_algo, xboard_out.clone(), engine_options);
+
+    let mut xboard = Xboard::new(xboard_out.clone());
+    loop {
+        match xboard.run_command(&buffer, &mut engine) {
+            Ok(Some(XboardMessage::Quit)) => break,
+            Err(e) => xboard_out.error(e.as_ref())?,
+            _ => {}
+        }
+        buffer.clear();
+        // End of input is treated like `quit`
+        if reader.read_line(&mut buffer)? == 0 {
+            break;
+        }
     }
     Ok(())
 }
@@ -12,6 +12,7 @@ fn test_cli() -> Result<(), Error> {
     go_all_options()?;
     chess_960()?;
     stress()?;
+    bench()?;
 
     Ok(())
 }
@@ -148,3 +149,13 @@ fn stress() -> Result<(), Error> {
     }
     Ok(())
 }
+
+fn bench() -> Result<(), Error> {
+    let mut p = spawn("cargo run --release -- bench 4", Some(30000))?;
+    p.exp_string("Position  1:")?;
+    p.exp_string("Position 40:")?;
+    p.exp_string("Nodes searched:")?;
+    p.exp_string("Nodes/second:")?;
+    p.exp_eof()?;
+    Ok(())
+}
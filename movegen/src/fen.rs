@@ -171,38 +171,11 @@ impl Fen {
         );
     }
 
+    // The en passant square, halfmove clock and move count may be omitted.
+    // They default to no en passant square, halfmove clock = 0 and
+    // move count = 1.
     pub fn str_to_pos(fen: &str) -> Result<Position, FenError> {
-        let mut pos = Position::empty();
-        let mut iter_fen = fen.split_whitespace();
-        iter_fen
-            .next()
-            .map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_pieces(&mut pos, fen)
-            })
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_side_to_move(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_castling_rights(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_en_passant_square(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_plies_since_pawn_move_or_capture(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_move_count(&mut pos, fen)
-            }))
-            .and(
-                iter_fen
-                    .next()
-                    .map_or(Ok(()), |_| Err(FenError::TooManyParts)),
-            )
-            .map_or_else(
-                |e| Err(FenError::InvalidFenString(fen.to_string(), Box::new(e))),
-                |_| Ok(pos),
-            )
+        Self::str_to_pos_with_castling_rights(fen, Self::str_to_pos_castling_rights)
     }
 
     // For FEN strings without halfmove clock and move count.
@@ -236,37 +209,54 @@ impl Fen {
     }
 
     pub fn str_to_pos_chess_960(fen: &str) -> Result<Position, FenError> {
+        Self::str_to_pos_with_castling_rights(fen, Self::str_to_pos_castling_rights_chess_960)
+    }
+
+    fn str_to_pos_with_castling_rights(
+        fen: &str,
+        castling_rights: fn(&mut Position, &str) -> Result<(), FenError>,
+    ) -> Result<Position, FenError> {
         let mut pos = Position::empty();
-        let mut iter_fen = fen.split_whitespace();
-        iter_fen
-            .next()
-            .map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_pieces(&mut pos, fen)
-            })
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_side_to_move(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_castling_rights_chess_960(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_en_passant_square(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_plies_since_pawn_move_or_capture(&mut pos, fen)
-            }))
-            .and(iter_fen.next().map_or(Err(FenError::TooFewParts), |fen| {
-                Self::str_to_pos_move_count(&mut pos, fen)
-            }))
-            .and(
-                iter_fen
-                    .next()
-                    .map_or(Ok(()), |_| Err(FenError::TooManyParts)),
-            )
-            .map_or_else(
-                |e| Err(FenError::InvalidFenString(fen.to_string(), Box::new(e))),
-                |_| Ok(pos),
-            )
+        let parts = fen.split_whitespace().collect::<Vec<_>>();
+        Self::str_to_pos_parts(&mut pos, &parts, castling_rights).map_or_else(
+            |e| Err(FenError::InvalidFenString(fen.to_string(), Box::new(e))),
+            |_| Ok(pos),
+        )
+    }
+
+    fn str_to_pos_parts(
+        pos: &mut Position,
+        parts: &[&str],
+        castling_rights: fn(&mut Position, &str) -> Result<(), FenError>,
+    ) -> Result<(), FenError> {
+        let (pieces, side_to_move, castling, optional) = match parts {
+            [pieces, side_to_move, castling, optional @ ..] => {
+                (pieces, side_to_move, castling, optional)
+            }
+            _ => return Err(FenError::TooFewParts),
+        };
+        Self::str_to_pos_pieces(pos, pieces)?;
+        Self::str_to_pos_side_to_move(pos, side_to_move)?;
+        castling_rights(pos, castling)?;
+        // A number can't be an en passant square, so it must be the halfmove
+        // clock
+        let clocks = match optional.split_first() {
+            Some((ep, clocks)) if !ep.bytes().all(|b| b.is_ascii_digit()) => {
+                Self::str_to_pos_en_passant_square(pos, ep)?;
+                clocks
+            }
+            _ => optional,
+        };
+        match clocks {
+            [] => {}
+            [plies] => Self::str_to_pos_plies_since_pawn_move_or_capture(pos, plies)?,
+            [plies, move_count] => {
+                Self::str_to_pos_plies_since_pawn_move_or_capture(pos, plies)?;
+                Self::str_to_pos_move_count(pos, move_count)?;
+            }
+            _ => return Err(FenError::TooManyParts),
+        }
+        Ok(())
     }
 
     fn str_to_pos_pieces(pos: &mut Position, fen: &str) -> Result<(), FenError> {
@@ -579,7 +569,7 @@ mod tests {
         let invalid_move_count_nan = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 x";
         assert!(Fen::str_to_pos(invalid_move_count_nan).is_err());
         println!("{}", Fen::str_to_pos(invalid_move_count_nan).unwrap_err());
-        let too_few_parts = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0";
+        let too_few_parts = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w";
         assert!(Fen::str_to_pos(too_few_parts).is_err());
         println!("{}", Fen::str_to_pos(too_few_parts).unwrap_err());
         let too_many_parts = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 2";
@@ -673,6 +663,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn abbreviated_str_to_pos() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 1";
+        let abbreviated = [
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6",
+        ];
+        for short_fen in abbreviated {
+            assert_eq!(
+                Fen::str_to_pos(fen).unwrap(),
+                Fen::str_to_pos(short_fen).unwrap(),
+                "{short_fen}"
+            );
+        }
+
+        // Without en passant square
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 7 12";
+        let abbreviated = [
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq 7 12",
+                7,
+                12,
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 7",
+                7,
+                1,
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq 7",
+                7,
+                1,
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq",
+                0,
+                1,
+            ),
+        ];
+        for (short_fen, plies, move_count) in abbreviated {
+            let mut pos = Fen::str_to_pos(fen).unwrap();
+            pos.set_plies_since_pawn_move_or_capture(plies);
+            pos.set_move_count(move_count);
+            assert_eq!(pos, Fen::str_to_pos(short_fen).unwrap(), "{short_fen}");
+        }
+
+        let invalid = [
+            // Invalid en passant square
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e5",
+                "Invalid en passant square",
+            ),
+            // Invalid halfmove clock
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - x",
+                "Invalid plies since last pawn move or capture",
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq 0 0",
+                "Invalid move count",
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq 0 1 2",
+                "Too many parts",
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w",
+                "Too few parts",
+            ),
+        ];
+        for (short_fen, err) in invalid {
+            assert_eq!(
+                format!("Invalid FEN string: `{short_fen}`: {err}"),
+                Fen::str_to_pos(short_fen).unwrap_err().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn abbreviated_str_to_pos_chess_960() {
+        let fen = "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb - 0 1";
+        let abbreviated = [
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb - 0",
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb -",
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb 0 1",
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb 0",
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb",
+        ];
+        for short_fen in abbreviated {
+            assert_eq!(
+                Fen::str_to_pos_chess_960(fen).unwrap(),
+                Fen::str_to_pos_chess_960(short_fen).unwrap(),
+                "{short_fen}"
+            );
+        }
+        let invalid = [
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb x",
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w GBgb - 0 1 2",
+            "nrbkqbrn/pppppppp/8/8/8/8/PPPPPPPP/NRBKQBRN w",
+        ];
+        for short_fen in invalid {
+            assert!(Fen::str_to_pos_chess_960(short_fen).is_err(), "{short_fen}");
+        }
+    }
+
     #[test]
     fn invalid_fen_chess_960() {
         {
@@ -8,10 +8,13 @@ pub mod r#move;
 pub mod move_generator;
 pub mod pawn;
 pub mod piece;
+pub mod piece_square_table;
+pub mod polyglot;
 pub mod position;
 pub mod position_history;
 pub mod queen;
 pub mod rank;
+pub mod ray;
 pub mod rook;
 pub mod side;
 pub mod square;
@@ -22,6 +25,5 @@ mod attacks_to;
 mod castling_squares;
 mod direction;
 mod piece_targets;
-mod ray;
 mod ray_lookup_tables;
 mod repetition_tracker;
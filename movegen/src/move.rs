@@ -115,6 +115,19 @@ impl Move {
     }
 }
 
+// The raw encoding, e.g. for packing moves into hash table entries
+impl From<u16> for Move {
+    fn from(u: u16) -> Self {
+        Move(u)
+    }
+}
+
+impl From<Move> for u16 {
+    fn from(m: Move) -> Self {
+        m.0
+    }
+}
+
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let move_str = match *self {
@@ -1,8 +1,10 @@
+mod check_squares;
 mod king_in_check_generator;
 mod king_not_xrayed_generator;
 mod king_xrayed_generator;
 mod move_generator_template;
 
+use crate::move_generator::check_squares::CheckSquares;
 use crate::move_generator::king_in_check_generator::KingInCheckGenerator;
 use crate::move_generator::king_not_xrayed_generator::KingNotXrayedGenerator;
 use crate::move_generator::king_xrayed_generator::KingXrayedGenerator;
@@ -17,90 +19,186 @@ use crate::square::Square;
 
 pub struct MoveGenerator;
 
+// How the own king is attacked. It decides which generator is used.
+enum KingThreat {
+    DoubleCheck,
+    Check,
+    Xray,
+    NoThreat,
+}
+
 impl MoveGenerator {
     pub fn generate_moves(move_list: &mut MoveList, pos: &Position) {
         move_list.clear();
-
-        let own_king_bb = pos.piece_occupancy(pos.side_to_move(), piece::Type::King);
-        let own_king = own_king_bb.to_square();
-
-        let attacks_to_king = AttacksTo::new(pos, own_king, !pos.side_to_move());
-
-        let king_in_check = own_king_bb & attacks_to_king.all_attack_targets != Bitboard::EMPTY;
-        let king_xrayed = attacks_to_king.xrays_to_target != Bitboard::EMPTY;
-
-        if king_in_check {
-            debug_assert!(attacks_to_king.attack_origins.pop_count() >= 1);
-            debug_assert!(attacks_to_king.attack_origins.pop_count() <= 2);
-            debug_assert!(attacks_to_king.each_slider_attack.len() <= 2);
-            if attacks_to_king.attack_origins.pop_count() == 2 {
-                // Only king moves are legal in double check
-                KingInCheckGenerator::generate_king_moves(move_list, &attacks_to_king);
-            } else {
-                KingInCheckGenerator::generate_moves(move_list, &attacks_to_king);
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        match threat {
+            // Only king moves are legal in double check
+            KingThreat::DoubleCheck => {
+                KingInCheckGenerator::generate_king_moves(move_list, &attacks_to_king)
+            }
+            KingThreat::Check => KingInCheckGenerator::generate_moves(move_list, &attacks_to_king),
+            KingThreat::Xray => KingXrayedGenerator::generate_moves(move_list, &attacks_to_king),
+            KingThreat::NoThreat => {
+                KingNotXrayedGenerator::generate_moves(move_list, &attacks_to_king)
             }
-        } else if king_xrayed {
-            KingXrayedGenerator::generate_moves(move_list, &attacks_to_king);
-        } else {
-            KingNotXrayedGenerator::generate_moves(move_list, &attacks_to_king);
         }
     }
 
     pub fn generate_moves_quiescence(move_list: &mut MoveList, pos: &Position) {
         move_list.clear();
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        match threat {
+            KingThreat::DoubleCheck => {
+                KingInCheckGenerator::generate_king_captures(move_list, &attacks_to_king)
+            }
+            KingThreat::Check => {
+                KingInCheckGenerator::generate_moves_quiescence(move_list, &attacks_to_king)
+            }
+            KingThreat::Xray => {
+                KingXrayedGenerator::generate_moves_quiescence(move_list, &attacks_to_king)
+            }
+            KingThreat::NoThreat => {
+                KingNotXrayedGenerator::generate_moves_quiescence(move_list, &attacks_to_king)
+            }
+        }
+    }
 
-        let own_king_bb = pos.piece_occupancy(pos.side_to_move(), piece::Type::King);
-        let own_king = own_king_bb.to_square();
-
-        let attacks_to_king = AttacksTo::new(pos, own_king, !pos.side_to_move());
-
-        let king_in_check = own_king_bb & attacks_to_king.all_attack_targets != Bitboard::EMPTY;
-        let king_xrayed = attacks_to_king.xrays_to_target != Bitboard::EMPTY;
+    // Captures and promotions, including underpromotions
+    pub fn generate_moves_tactical(move_list: &mut MoveList, pos: &Position) {
+        move_list.clear();
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        match threat {
+            KingThreat::DoubleCheck => {
+                KingInCheckGenerator::generate_king_captures(move_list, &attacks_to_king)
+            }
+            KingThreat::Check => {
+                KingInCheckGenerator::generate_moves_tactical(move_list, &attacks_to_king)
+            }
+            KingThreat::Xray => {
+                KingXrayedGenerator::generate_moves_tactical(move_list, &attacks_to_king)
+            }
+            KingThreat::NoThreat => {
+                KingNotXrayedGenerator::generate_moves_tactical(move_list, &attacks_to_king)
+            }
+        }
+    }
 
-        if king_in_check {
-            debug_assert!(attacks_to_king.attack_origins.pop_count() >= 1);
-            debug_assert!(attacks_to_king.attack_origins.pop_count() <= 2);
-            debug_assert!(attacks_to_king.each_slider_attack.len() <= 2);
-            if attacks_to_king.attack_origins.pop_count() == 2 {
-                // Only king moves are legal in double check
-                KingInCheckGenerator::generate_king_captures(move_list, &attacks_to_king);
-            } else {
-                KingInCheckGenerator::generate_moves_quiescence(move_list, &attacks_to_king);
+    // The complement of the tactical moves
+    pub fn generate_moves_quiet(move_list: &mut MoveList, pos: &Position) {
+        move_list.clear();
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        match threat {
+            KingThreat::DoubleCheck => {
+                KingInCheckGenerator::generate_king_quiets(move_list, &attacks_to_king)
+            }
+            KingThreat::Check => {
+                KingInCheckGenerator::generate_moves_quiet(move_list, &attacks_to_king)
+            }
+            KingThreat::Xray => {
+                KingXrayedGenerator::generate_moves_quiet(move_list, &attacks_to_king)
+            }
+            KingThreat::NoThreat => {
+                KingNotXrayedGenerator::generate_moves_quiet(move_list, &attacks_to_king)
             }
-        } else if king_xrayed {
-            KingXrayedGenerator::generate_moves_quiescence(move_list, &attacks_to_king);
-        } else {
-            KingNotXrayedGenerator::generate_moves_quiescence(move_list, &attacks_to_king);
         }
     }
 
-    pub fn least_valuable_attacker(pos: &Position, target: Square) -> Option<Move> {
-        let own_king_bb = pos.piece_occupancy(pos.side_to_move(), piece::Type::King);
-        let own_king = own_king_bb.to_square();
+    // Checks if the move is legal in this position, without generating all
+    // moves. Useful to validate moves from the hash table or killer moves.
+    pub fn is_legal_move(pos: &Position, m: Move) -> bool {
+        match pos.piece_at(m.origin()) {
+            Some(p) if p.piece_side() == pos.side_to_move() => {}
+            _ => return false,
+        }
 
-        let attacks_to_king = AttacksTo::new(pos, own_king, !pos.side_to_move());
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        let mut move_list = MoveList::new();
+        match threat {
+            KingThreat::DoubleCheck => {
+                if m.origin() == attacks_to_king.target {
+                    KingInCheckGenerator::generate_king_moves(&mut move_list, &attacks_to_king);
+                }
+            }
+            KingThreat::Check => KingInCheckGenerator::generate_moves_from(
+                &mut move_list,
+                &attacks_to_king,
+                m.origin(),
+            ),
+            KingThreat::Xray => KingXrayedGenerator::generate_moves_from(
+                &mut move_list,
+                &attacks_to_king,
+                m.origin(),
+            ),
+            KingThreat::NoThreat => KingNotXrayedGenerator::generate_moves_from(
+                &mut move_list,
+                &attacks_to_king,
+                m.origin(),
+            ),
+        }
+        move_list.contains(&m)
+    }
 
-        let king_in_check = own_king_bb & attacks_to_king.all_attack_targets != Bitboard::EMPTY;
-        let king_xrayed = attacks_to_king.xrays_to_target != Bitboard::EMPTY;
+    // Quiet moves (no captures or promotions) that give check
+    pub fn generate_quiet_checks(move_list: &mut MoveList, pos: &Position) {
+        move_list.clear();
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        let check_squares = CheckSquares::new(pos);
+        match threat {
+            KingThreat::DoubleCheck => KingInCheckGenerator::generate_king_quiet_checks(
+                move_list,
+                &attacks_to_king,
+                &check_squares,
+            ),
+            KingThreat::Check => KingInCheckGenerator::generate_quiet_checks(
+                move_list,
+                &attacks_to_king,
+                &check_squares,
+            ),
+            KingThreat::Xray => KingXrayedGenerator::generate_quiet_checks(
+                move_list,
+                &attacks_to_king,
+                &check_squares,
+            ),
+            KingThreat::NoThreat => KingNotXrayedGenerator::generate_quiet_checks(
+                move_list,
+                &attacks_to_king,
+                &check_squares,
+            ),
+        }
+    }
 
-        if king_in_check {
-            debug_assert!(attacks_to_king.attack_origins.pop_count() >= 1);
-            debug_assert!(attacks_to_king.attack_origins.pop_count() <= 2);
-            debug_assert!(attacks_to_king.each_slider_attack.len() <= 2);
-            if attacks_to_king.attack_origins.pop_count() == 2 {
-                // Only king moves are legal in double check
+    pub fn least_valuable_attacker(pos: &Position, target: Square) -> Option<Move> {
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        match threat {
+            KingThreat::DoubleCheck => {
                 KingInCheckGenerator::king_attacker(&attacks_to_king, target)
-            } else {
+            }
+            KingThreat::Check => {
                 KingInCheckGenerator::least_valuable_attacker(&attacks_to_king, target)
             }
-        } else if king_xrayed {
-            KingXrayedGenerator::least_valuable_attacker(&attacks_to_king, target)
-        } else {
-            KingNotXrayedGenerator::least_valuable_attacker(&attacks_to_king, target)
+            KingThreat::Xray => {
+                KingXrayedGenerator::least_valuable_attacker(&attacks_to_king, target)
+            }
+            KingThreat::NoThreat => {
+                KingNotXrayedGenerator::least_valuable_attacker(&attacks_to_king, target)
+            }
         }
     }
 
     pub fn has_en_passant_capture(pos: &Position) -> bool {
+        let (attacks_to_king, threat) = Self::attacks_to_own_king(pos);
+        match threat {
+            KingThreat::DoubleCheck | KingThreat::Check => {
+                KingInCheckGenerator::has_en_passant_capture(&attacks_to_king)
+            }
+            KingThreat::Xray => KingXrayedGenerator::has_en_passant_capture(&attacks_to_king),
+            KingThreat::NoThreat => {
+                KingNotXrayedGenerator::has_en_passant_capture(&attacks_to_king)
+            }
+        }
+    }
+
+    fn attacks_to_own_king(pos: &Position) -> (AttacksTo<'_>, KingThreat) {
         let own_king_bb = pos.piece_occupancy(pos.side_to_move(), piece::Type::King);
         let own_king = own_king_bb.to_square();
 
@@ -109,16 +207,20 @@ impl MoveGenerator {
         let king_in_check = own_king_bb & attacks_to_king.all_attack_targets != Bitboard::EMPTY;
         let king_xrayed = attacks_to_king.xrays_to_target != Bitboard::EMPTY;
 
-        if king_in_check {
+        let threat = if king_in_check {
             debug_assert!(attacks_to_king.attack_origins.pop_count() >= 1);
             debug_assert!(attacks_to_king.attack_origins.pop_count() <= 2);
             debug_assert!(attacks_to_king.each_slider_attack.len() <= 2);
-            KingInCheckGenerator::has_en_passant_capture(&attacks_to_king)
+            match attacks_to_king.attack_origins.pop_count() {
+                2 => KingThreat::DoubleCheck,
+                _ => KingThreat::Check,
+            }
         } else if king_xrayed {
-            KingXrayedGenerator::has_en_passant_capture(&attacks_to_king)
+            KingThreat::Xray
         } else {
-            KingNotXrayedGenerator::has_en_passant_capture(&attacks_to_king)
-        }
+            KingThreat::NoThreat
+        };
+        (attacks_to_king, threat)
     }
 }
 
@@ -133,6 +235,84 @@ mod tests {
     use crate::square::Square;
     use rand::seq::SliceRandom;
 
+    #[test]
+    fn quiet_checks() {
+        let fens = [
+            // Direct checks, discovered checks and a pinned piece
+            "4k3/8/8/8/1B6/8/3N4/R3K3 w Q - 0 1",
+            "k7/8/8/3N4/8/1Q6/8/4K2R w K - 0 1",
+            "3k4/8/3N4/8/8/8/3R4/4K3 w - - 0 1",
+            "4k3/4r3/8/8/8/8/4B3/4K2R w K - 0 1",
+            // Castling gives check
+            "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+            "3k4/8/8/8/8/8/8/R3K3 w Q - 0 1",
+            // Pawn pushes
+            "8/8/3k4/8/2P5/8/4P3/4K3 w - - 0 1",
+            // The king discovers a check, except along the line of the rook
+            "4k3/8/8/8/8/8/4K3/4R3 w - - 0 1",
+            // In check, only the blocks and king moves can give check
+            "8/8/8/8/1bk5/8/8/4KN2 w - - 0 1",
+        ];
+        for fen in fens {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let mut pos_hist = PositionHistory::new(pos.clone());
+            let mut all_moves = MoveList::new();
+            MoveGenerator::generate_moves(&mut all_moves, &pos);
+            let expected = all_moves
+                .iter()
+                .filter(|m| !m.is_capture() && !m.is_promotion() && pos_hist.gives_check(**m))
+                .copied()
+                .collect::<Vec<_>>();
+            assert!(!expected.is_empty(), "{fen}");
+
+            let mut quiet_checks = MoveList::new();
+            MoveGenerator::generate_quiet_checks(&mut quiet_checks, &pos);
+            assert_eq!(expected.as_slice(), quiet_checks.as_slice(), "{fen}");
+        }
+
+        // Chess960 castling
+        let pos = Fen::str_to_pos_chess_960("5k2/8/8/8/8/8/8/1R3KR1 w GB - 0 1").unwrap();
+        let mut quiet_checks = MoveList::new();
+        MoveGenerator::generate_quiet_checks(&mut quiet_checks, &pos);
+        assert!(quiet_checks.contains(&Move::new(
+            Square::F1,
+            Square::G1,
+            MoveType::CASTLE_KINGSIDE
+        )));
+    }
+
+    #[test]
+    fn quiet_checks_random_positions() {
+        let pos = Position::initial();
+        let mut pos_hist = PositionHistory::new(pos.clone());
+        let max_moves = 2000;
+
+        let mut move_list = MoveList::new();
+        let mut quiet_checks = MoveList::new();
+
+        for _ in 0..max_moves {
+            let current_pos = pos_hist.current_pos().clone();
+            MoveGenerator::generate_moves(&mut move_list, &current_pos);
+            MoveGenerator::generate_quiet_checks(&mut quiet_checks, &current_pos);
+            let expected = move_list
+                .iter()
+                .filter(|m| !m.is_capture() && !m.is_promotion() && pos_hist.gives_check(**m))
+                .copied()
+                .collect::<Vec<_>>();
+            assert_eq!(
+                expected.as_slice(),
+                quiet_checks.as_slice(),
+                "{}",
+                Fen::pos_to_str(&current_pos)
+            );
+
+            match move_list.choose(&mut rand::thread_rng()) {
+                Some(m) => pos_hist.do_move(*m),
+                None => pos_hist = PositionHistory::new(pos.clone()),
+            }
+        }
+    }
+
     #[test]
     fn initial_position() {
         let mut move_list = MoveList::new();
@@ -1150,6 +1330,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn generate_moves_tactical_and_quiet() {
+        let pos = Position::initial();
+        let mut pos_hist = PositionHistory::new(pos.clone());
+        let max_moves = 1000;
+
+        let mut move_list = MoveList::new();
+        let mut tactical = MoveList::new();
+        let mut quiet = MoveList::new();
+        let mut prev_move_list = MoveList::new();
+
+        for _ in 0..max_moves {
+            let current_pos = pos_hist.current_pos();
+            MoveGenerator::generate_moves(&mut move_list, current_pos);
+            MoveGenerator::generate_moves_tactical(&mut tactical, current_pos);
+            MoveGenerator::generate_moves_quiet(&mut quiet, current_pos);
+
+            // The tactical and quiet moves partition all moves
+            assert_eq!(move_list.len(), tactical.len() + quiet.len());
+            for m in tactical.iter() {
+                assert!(m.is_capture() || m.is_promotion());
+                assert!(move_list.contains(m));
+            }
+            for m in quiet.iter() {
+                assert!(!m.is_capture() && !m.is_promotion());
+                assert!(move_list.contains(m));
+            }
+
+            // Exactly the generated moves are legal. The moves of the previous
+            // position make good candidates for illegal moves.
+            for m in move_list.iter() {
+                assert!(MoveGenerator::is_legal_move(current_pos, *m));
+            }
+            for m in prev_move_list.iter() {
+                assert_eq!(
+                    move_list.contains(m),
+                    MoveGenerator::is_legal_move(current_pos, *m)
+                );
+            }
+
+            prev_move_list = move_list.clone();
+            match move_list.choose(&mut rand::thread_rng()) {
+                Some(m) => pos_hist.do_move(*m),
+                None => {
+                    pos_hist = PositionHistory::new(pos.clone());
+                    prev_move_list.clear();
+                }
+            }
+        }
+
+        // In check
+        let pos = Fen::str_to_pos("4k3/8/8/8/8/5n2/3PP3/4K2R w K - 0 1").unwrap();
+        MoveGenerator::generate_moves(&mut move_list, &pos);
+        MoveGenerator::generate_moves_tactical(&mut tactical, &pos);
+        MoveGenerator::generate_moves_quiet(&mut quiet, &pos);
+        assert_eq!(move_list.len(), tactical.len() + quiet.len());
+        assert!(!MoveGenerator::is_legal_move(
+            &pos,
+            Move::new(Square::E1, Square::G1, MoveType::CASTLE_KINGSIDE)
+        ));
+        assert!(!MoveGenerator::is_legal_move(
+            &pos,
+            Move::new(Square::D2, Square::D4, MoveType::DOUBLE_PAWN_PUSH)
+        ));
+        assert!(MoveGenerator::is_legal_move(
+            &pos,
+            Move::new(Square::E2, Square::F3, MoveType::CAPTURE)
+        ));
+    }
+
     #[test]
     fn castles_chess_960_castling_rights() {
         // White queenside
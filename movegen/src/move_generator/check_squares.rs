@@ -0,0 +1,144 @@
+use crate::bishop::Bishop;
+use crate::bitboard::Bitboard;
+use crate::file::File;
+use crate::knight::Knight;
+use crate::piece;
+use crate::position::Position;
+use crate::r#move::{Move, MoveType};
+use crate::rook::Rook;
+use crate::side::Side;
+use crate::square::Square;
+
+// The squares from which the side to move gives check to the opponent's
+// king, for each piece type. Pieces which block an attack of their own
+// sliders on the opponent's king give a discovered check by leaving the line
+// between the slider and the king.
+pub struct CheckSquares<'a> {
+    pos: &'a Position,
+    king: Square,
+    pawn_checks: Bitboard,
+    knight_checks: Bitboard,
+    bishop_checks: Bitboard,
+    rook_checks: Bitboard,
+    discovered_candidates: Bitboard,
+}
+
+impl CheckSquares<'_> {
+    pub fn new(pos: &Position) -> CheckSquares<'_> {
+        let side_to_move = pos.side_to_move();
+        let king_bb = pos.piece_occupancy(!side_to_move, piece::Type::King);
+        let king = king_bb.to_square();
+        let occupancy = pos.occupancy();
+        let bishop_checks = Bishop::targets(king, occupancy);
+        let rook_checks = Rook::targets(king, occupancy);
+        let own_pieces = pos.side_occupancy(side_to_move);
+        let own_queens = pos.piece_occupancy(side_to_move, piece::Type::Queen);
+        let own_bishops = pos.piece_occupancy(side_to_move, piece::Type::Bishop) | own_queens;
+        let own_rooks = pos.piece_occupancy(side_to_move, piece::Type::Rook) | own_queens;
+        let mut discovered_candidates = Bitboard::EMPTY;
+        for (checks, sliders, targets) in [
+            (
+                bishop_checks,
+                own_bishops,
+                Bishop::targets as fn(Square, Bitboard) -> Bitboard,
+            ),
+            (rook_checks, own_rooks, Rook::targets),
+        ] {
+            let mut blockers = checks & own_pieces;
+            while blockers != Bitboard::EMPTY {
+                let blocker = blockers.square_scan_forward_reset();
+                let occupancy_without_blocker = occupancy & !Bitboard::from_square(blocker);
+                let uncovered = targets(king, occupancy_without_blocker) & !checks;
+                if uncovered & sliders != Bitboard::EMPTY {
+                    discovered_candidates |= Bitboard::from_square(blocker);
+                }
+            }
+        }
+
+        CheckSquares {
+            pos,
+            king,
+            pawn_checks: Self::pawn_check_squares(king_bb, side_to_move),
+            knight_checks: Knight::targets(king),
+            bishop_checks,
+            rook_checks,
+            discovered_candidates,
+        }
+    }
+
+    // Like Pawn::attack_origins, but the king may be on any rank
+    fn pawn_check_squares(king_bb: Bitboard, side_to_move: Side) -> Bitboard {
+        match side_to_move {
+            Side::White => king_bb.south_west_one() | king_bb.south_east_one(),
+            Side::Black => king_bb.north_west_one() | king_bb.north_east_one(),
+        }
+    }
+
+    // The targets of a quiet move of a piece of this type from the origin
+    // which give check
+    pub fn targets(&self, piece_type: piece::Type, origin: Square) -> Bitboard {
+        let direct = match piece_type {
+            piece::Type::Pawn => self.pawn_checks,
+            piece::Type::Knight => self.knight_checks,
+            piece::Type::Bishop => self.bishop_checks,
+            piece::Type::Rook => self.rook_checks,
+            piece::Type::Queen => self.bishop_checks | self.rook_checks,
+            piece::Type::King => Bitboard::EMPTY,
+        };
+        direct | self.discovered_targets(origin)
+    }
+
+    // A discovered check is given by moving off the line between the slider
+    // and the king
+    fn discovered_targets(&self, origin: Square) -> Bitboard {
+        let origin_bb = Bitboard::from_square(origin);
+        if self.discovered_candidates & origin_bb == Bitboard::EMPTY {
+            return Bitboard::EMPTY;
+        }
+        let side_to_move = self.pos.side_to_move();
+        let occupancy_without_origin = self.pos.occupancy() & !origin_bb;
+        let own_queens = self.pos.piece_occupancy(side_to_move, piece::Type::Queen);
+        let mut line = Bitboard::EMPTY;
+        for (piece_type, targets) in [
+            (
+                piece::Type::Bishop,
+                Bishop::targets as fn(Square, Bitboard) -> Bitboard,
+            ),
+            (piece::Type::Rook, Rook::targets),
+        ] {
+            let sliders = self.pos.piece_occupancy(side_to_move, piece_type) | own_queens;
+            let king_targets = targets(self.king, occupancy_without_origin);
+            // Only the slider behind the origin is uncovered
+            let uncovered = king_targets & !targets(self.king, self.pos.occupancy()) & sliders;
+            if uncovered != Bitboard::EMPTY {
+                let slider = uncovered.to_square();
+                line |= king_targets & targets(slider, occupancy_without_origin);
+            }
+        }
+        !line
+    }
+
+    pub fn castle_gives_check(&self, m: Move) -> bool {
+        debug_assert!(m.is_castle());
+        let pos = self.pos;
+        let side_to_move = pos.side_to_move();
+        let rank = m.origin().rank();
+        let (rook_file, rook_target_file) = match m.move_type() {
+            MoveType::CASTLE_KINGSIDE => (pos.kingside_castling_file(), File::F),
+            _ => (pos.queenside_castling_file(), File::D),
+        };
+        let rook_origin = Bitboard::from_square(Square::from_file_and_rank(rook_file, rank));
+        let rook_target = Bitboard::from_square(Square::from_file_and_rank(rook_target_file, rank));
+        let occupancy_after_move =
+            pos.occupancy() & !Bitboard::from_square(m.origin()) & !rook_origin
+                | Bitboard::from_square(m.target())
+                | rook_target;
+        let own_queens = pos.piece_occupancy(side_to_move, piece::Type::Queen);
+        let own_rooks =
+            pos.piece_occupancy(side_to_move, piece::Type::Rook) & !rook_origin | rook_target;
+        let own_bishops = pos.piece_occupancy(side_to_move, piece::Type::Bishop);
+        Rook::targets(self.king, occupancy_after_move) & (own_rooks | own_queens) != Bitboard::EMPTY
+            || Bishop::targets(self.king, occupancy_after_move) & (own_bishops | own_queens)
+                != Bitboard::EMPTY
+    }
+}
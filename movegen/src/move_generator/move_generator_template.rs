@@ -3,6 +3,7 @@ use crate::bishop::Bishop;
 use crate::bitboard::Bitboard;
 use crate::king::King;
 use crate::knight::Knight;
+use crate::move_generator::check_squares::CheckSquares;
 use crate::pawn::Pawn;
 use crate::piece;
 use crate::piece_targets::PieceTargets;
@@ -107,6 +108,165 @@ pub trait MoveGeneratorTemplate {
         Self::generate_king_captures(move_list, attacks_to_king);
     }
 
+    // Captures and promotions
+    fn generate_moves_tactical(move_list: &mut MoveList, attacks_to_king: &AttacksTo) {
+        Self::generate_pawn_pushes(move_list, attacks_to_king);
+        move_list.retain(|m| m.is_promotion());
+        Self::generate_pawn_captures(move_list, attacks_to_king);
+        Self::generate_knight_captures(move_list, attacks_to_king);
+        Self::generate_sliding_piece_captures(
+            move_list,
+            attacks_to_king,
+            piece::Type::Bishop,
+            Bishop::targets,
+        );
+        Self::generate_sliding_piece_captures(
+            move_list,
+            attacks_to_king,
+            piece::Type::Rook,
+            Rook::targets,
+        );
+        Self::generate_sliding_piece_captures(
+            move_list,
+            attacks_to_king,
+            piece::Type::Queen,
+            Queen::targets,
+        );
+        Self::generate_king_captures(move_list, attacks_to_king);
+    }
+
+    // All moves which are neither captures nor promotions
+    fn generate_moves_quiet(move_list: &mut MoveList, attacks_to_king: &AttacksTo) {
+        Self::generate_pawn_pushes(move_list, attacks_to_king);
+        move_list.retain(|m| !m.is_promotion());
+        Self::generate_knight_quiets(move_list, attacks_to_king);
+        Self::generate_sliding_piece_quiets(
+            move_list,
+            attacks_to_king,
+            piece::Type::Bishop,
+            Bishop::targets,
+        );
+        Self::generate_sliding_piece_quiets(
+            move_list,
+            attacks_to_king,
+            piece::Type::Rook,
+            Rook::targets,
+        );
+        Self::generate_sliding_piece_quiets(
+            move_list,
+            attacks_to_king,
+            piece::Type::Queen,
+            Queen::targets,
+        );
+        Self::generate_king_quiets(move_list, attacks_to_king);
+        Self::generate_castles(move_list, attacks_to_king);
+    }
+
+    // Quiet moves (no captures or promotions) which give check. The
+    // targets are restricted to the check squares before the legality checks.
+    fn generate_quiet_checks(
+        move_list: &mut MoveList,
+        attacks_to_king: &AttacksTo,
+        check_squares: &CheckSquares,
+    ) {
+        debug_assert!(move_list.is_empty());
+        Self::generate_pawn_pushes(move_list, attacks_to_king);
+        move_list.retain(|m| {
+            !m.is_promotion()
+                && check_squares.targets(piece::Type::Pawn, m.origin())
+                    & Bitboard::from_square(m.target())
+                    != Bitboard::EMPTY
+        });
+
+        let pos = attacks_to_king.pos;
+        let mut knights = pos.piece_occupancy(pos.side_to_move(), piece::Type::Knight);
+        while knights != Bitboard::EMPTY {
+            let origin = knights.square_scan_forward_reset();
+            let targets =
+                Knight::targets(origin) & check_squares.targets(piece::Type::Knight, origin);
+            Self::generate_piece_quiets(move_list, attacks_to_king, origin, targets);
+        }
+        for (piece_type, piece_targets) in [
+            (
+                piece::Type::Bishop,
+                Bishop::targets as fn(Square, Bitboard) -> Bitboard,
+            ),
+            (piece::Type::Rook, Rook::targets),
+            (piece::Type::Queen, Queen::targets),
+        ] {
+            let mut piece_occupancy = pos.piece_occupancy(pos.side_to_move(), piece_type);
+            while piece_occupancy != Bitboard::EMPTY {
+                let origin = piece_occupancy.square_scan_forward_reset();
+                let targets = piece_targets(origin, pos.occupancy())
+                    & check_squares.targets(piece_type, origin);
+                Self::generate_piece_quiets(move_list, attacks_to_king, origin, targets);
+            }
+        }
+
+        Self::generate_king_quiet_checks(move_list, attacks_to_king, check_squares);
+
+        let mut castles = MoveList::new();
+        Self::generate_castles(&mut castles, attacks_to_king);
+        for m in castles.iter() {
+            if check_squares.castle_gives_check(*m) {
+                move_list.push(*m);
+            }
+        }
+    }
+
+    // The king can only give a discovered check
+    fn generate_king_quiet_checks(
+        move_list: &mut MoveList,
+        attacks_to_king: &AttacksTo,
+        check_squares: &CheckSquares,
+    ) {
+        let pos = attacks_to_king.pos;
+        let origin = attacks_to_king.target;
+        let mut quiets = King::targets(origin)
+            & !pos.occupancy()
+            & !attacks_to_king.all_attack_targets
+            & check_squares.targets(piece::Type::King, origin);
+        while quiets != Bitboard::EMPTY {
+            let target = quiets.square_scan_forward_reset();
+            if Self::is_legal_king_move(attacks_to_king, origin, target) {
+                move_list.push(Move::new(origin, target, MoveType::QUIET));
+            }
+        }
+    }
+
+    // All moves of the piece on the origin square
+    fn generate_moves_from(move_list: &mut MoveList, attacks_to_king: &AttacksTo, origin: Square) {
+        let pos = attacks_to_king.pos;
+        let own_occupancy = pos.side_occupancy(pos.side_to_move());
+        match pos.piece_at(origin).map(|p| p.piece_type()) {
+            Some(piece::Type::Pawn) => {
+                Self::generate_pawn_moves(move_list, attacks_to_king);
+                move_list.retain(|m| m.origin() == origin);
+            }
+            Some(piece::Type::Knight) => {
+                let targets = Knight::targets(origin) & !own_occupancy;
+                Self::generate_piece_moves(move_list, attacks_to_king, origin, targets);
+            }
+            Some(piece::Type::Bishop) => {
+                let targets = Bishop::targets(origin, pos.occupancy()) & !own_occupancy;
+                Self::generate_piece_moves(move_list, attacks_to_king, origin, targets);
+            }
+            Some(piece::Type::Rook) => {
+                let targets = Rook::targets(origin, pos.occupancy()) & !own_occupancy;
+                Self::generate_piece_moves(move_list, attacks_to_king, origin, targets);
+            }
+            Some(piece::Type::Queen) => {
+                let targets = Queen::targets(origin, pos.occupancy()) & !own_occupancy;
+                Self::generate_piece_moves(move_list, attacks_to_king, origin, targets);
+            }
+            Some(piece::Type::King) => {
+                Self::generate_king_moves(move_list, attacks_to_king);
+                Self::generate_castles(move_list, attacks_to_king);
+            }
+            None => {}
+        }
+    }
+
     fn generate_king_moves(move_list: &mut MoveList, attacks_to_king: &AttacksTo) {
         let pos = attacks_to_king.pos;
         let own_occupancy = pos.side_occupancy(pos.side_to_move());
@@ -148,6 +308,21 @@ pub trait MoveGeneratorTemplate {
         }
     }
 
+    fn generate_king_quiets(move_list: &mut MoveList, attacks_to_king: &AttacksTo) {
+        let pos = attacks_to_king.pos;
+        let origin = attacks_to_king.target;
+        let targets =
+            King::targets(origin) & !pos.occupancy() & !attacks_to_king.all_attack_targets;
+
+        let mut quiets = targets;
+        while quiets != Bitboard::EMPTY {
+            let target = quiets.square_scan_forward_reset();
+            if Self::is_legal_king_move(attacks_to_king, origin, target) {
+                move_list.push(Move::new(origin, target, MoveType::QUIET));
+            }
+        }
+    }
+
     fn generate_knight_moves(move_list: &mut MoveList, attacks_to_king: &AttacksTo) {
         let pos = attacks_to_king.pos;
         let mut knights = pos.piece_occupancy(pos.side_to_move(), piece::Type::Knight);
@@ -170,6 +345,17 @@ pub trait MoveGeneratorTemplate {
         }
     }
 
+    fn generate_knight_quiets(move_list: &mut MoveList, attacks_to_king: &AttacksTo) {
+        let pos = attacks_to_king.pos;
+        let mut knights = pos.piece_occupancy(pos.side_to_move(), piece::Type::Knight);
+        let own_occupancy = pos.side_occupancy(pos.side_to_move());
+        while knights != Bitboard::EMPTY {
+            let origin = knights.square_scan_forward_reset();
+            let targets = Knight::targets(origin) & !own_occupancy;
+            Self::generate_piece_quiets(move_list, attacks_to_king, origin, targets);
+        }
+    }
+
     fn generate_sliding_piece_moves(
         move_list: &mut MoveList,
         attacks_to_king: &AttacksTo,
@@ -202,6 +388,22 @@ pub trait MoveGeneratorTemplate {
         }
     }
 
+    fn generate_sliding_piece_quiets(
+        move_list: &mut MoveList,
+        attacks_to_king: &AttacksTo,
+        piece_type: piece::Type,
+        piece_targets: fn(Square, Bitboard) -> Bitboard,
+    ) {
+        let pos = attacks_to_king.pos;
+        let mut piece_occupancy = pos.piece_occupancy(pos.side_to_move(), piece_type);
+        let own_occupancy = pos.side_occupancy(pos.side_to_move());
+        while piece_occupancy != Bitboard::EMPTY {
+            let origin = piece_occupancy.square_scan_forward_reset();
+            let targets = piece_targets(origin, pos.occupancy()) & !own_occupancy;
+            Self::generate_piece_quiets(move_list, attacks_to_king, origin, targets);
+        }
+    }
+
     fn generate_piece_moves(
         move_list: &mut MoveList,
         attacks_to_king: &AttacksTo,
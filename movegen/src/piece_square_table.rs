@@ -0,0 +1,349 @@
+use crate::bitboard::Bitboard;
+use crate::file::File;
+use crate::piece::{self, Piece};
+use crate::position::Position;
+use crate::rank::Rank;
+use crate::side::Side;
+use crate::square::Square;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+// The material and piece-square values of the evaluation. They are tuned with
+// the other evaluation parameters, but live here, so that the position can sum
+// them up incrementally while moves are made and unmade.
+
+type Score = i16;
+
+// (middlegame, endgame)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PieceSquareScore(pub Score, pub Score);
+
+impl Add for PieceSquareScore {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl AddAssign for PieceSquareScore {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for PieceSquareScore {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl SubAssign for PieceSquareScore {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+// Indexed by the square from white's point of view
+pub type PieceSquareTable = [PieceSquareScore; 64];
+
+const MATERIAL_KING: PieceSquareScore = PieceSquareScore(0, 0);
+const MATERIAL_QUEEN: PieceSquareScore = PieceSquareScore(0, 0);
+const MATERIAL_ROOK: PieceSquareScore = PieceSquareScore(0, 0);
+const MATERIAL_BISHOP: PieceSquareScore = PieceSquareScore(0, 0);
+const MATERIAL_KNIGHT: PieceSquareScore = PieceSquareScore(0, 0);
+const MATERIAL_PAWN: PieceSquareScore = PieceSquareScore(0, 0);
+
+// Piece square tables:
+// We only define values for the queenside (left side) and mirror them to the
+// kingside (right side) so that we end up with symmetrical PSTs.
+#[rustfmt::skip]
+const PST_PAWN_MG_EG: ([Score; 32], [Score; 32]) = (
+    [
+           0,    0,    0,    0,
+         150,  124,  156,  176,
+          78,  108,  169,  142,
+          95,  106,  107,  129,
+          64,   80,  101,  113,
+          76,   91,   91,   93,
+          62,  101,   78,   68,
+           0,    0,    0,    0,
+    ],
+    [
+           0,    0,    0,    0,
+         268,  283,  264,  216,
+         169,  140,  123,  135,
+          78,   67,   70,   48,
+          64,   57,   48,   37,
+          51,   47,   45,   47,
+          58,   45,   52,   55,
+           0,    0,    0,    0,
+    ],
+);
+#[rustfmt::skip]
+const PST_KNIGHT_MG_EG: ([Score; 32], [Score; 32]) = (
+    [
+         236,  297,  274,  300,
+         256,  284,  383,  344,
+         279,  319,  348,  355,
+         335,  316,  347,  344,
+         284,  312,  346,  327,
+         296,  307,  331,  343,
+         318,  308,  322,  335,
+         295,  315,  322,  296,
+    ],
+    [
+         246,  284,  273,  301,
+         288,  297,  274,  292,
+         299,  297,  333,  315,
+         299,  325,  320,  315,
+         321,  295,  309,  319,
+         296,  312,  293,  301,
+         275,  290,  308,  301,
+         288,  274,  298,  307,
+    ],
+);
+#[rustfmt::skip]
+const PST_BISHOP_MG_EG: ([Score; 32], [Score; 32]) = (
+    [
+         333,  307,  277,  269,
+         278,  306,  304,  275,
+         324,  312,  358,  352,
+         304,  311,  279,  334,
+         301,  312,  312,  354,
+         337,  344,  355,  329,
+         329,  372,  331,  337,
+         326,  307,  315,  321,
+    ],
+    [
+         302,  300,  269,  293,
+         289,  308,  306,  297,
+         294,  317,  300,  304,
+         304,  312,  323,  328,
+         300,  303,  315,  302,
+         287,  299,  294,  308,
+         284,  274,  283,  287,
+         298,  315,  290,  308,
+    ],
+);
+#[rustfmt::skip]
+const PST_ROOK_MG_EG: ([Score; 32], [Score; 32]) = (
+    [
+         504,  513,  519,  524,
+         551,  528,  553,  543,
+         537,  513,  498,  501,
+         522,  495,  502,  511,
+         475,  479,  483,  510,
+         473,  508,  507,  519,
+         463,  525,  526,  543,
+         544,  544,  573,  582,
+    ],
+    [
+         526,  523,  510,  512,
+         515,  519,  518,  511,
+         502,  510,  514,  512,
+         490,  501,  510,  509,
+         496,  517,  514,  510,
+         487,  483,  498,  496,
+         511,  496,  493,  505,
+         471,  476,  480,  478,
+    ],
+);
+#[rustfmt::skip]
+const PST_QUEEN_MG_EG: ([Score; 32], [Score; 32]) = (
+    [
+         968,  922,  928,  924,
+         945,  899,  929,  833,
+         928,  921,  916,  910,
+         920,  899,  915,  900,
+         899,  906,  918,  900,
+         924,  940,  937,  929,
+         944,  968,  963,  956,
+         949,  954,  973,  974,
+    ],
+    [
+         947,  934,  941,  920,
+         923,  943,  926,  940,
+         886,  900,  937,  941,
+         905,  918,  936,  949,
+         955,  922,  931,  961,
+         908,  916,  925,  924,
+         917,  935,  887,  923,
+         937,  915,  907,  902,
+    ],
+);
+#[rustfmt::skip]
+const PST_KING_MG_EG: ([Score; 32], [Score; 32]) = (
+    [
+           3,    3,   -1,   -3,
+           3,    5,   15,   10,
+         -10,   19,   16,   21,
+          -2,   17,   23,    9,
+         -20,   -7,  -11,  -23,
+         -19,   15,  -12,  -69,
+          18,   52,  -15,  -71,
+          31,   66,  -27,  -33,
+    ],
+    [
+         -27,   -4,  -21,  -17,
+           9,    6,    9,   18,
+          -8,    7,   16,   20,
+          -3,    3,    7,   12,
+         -19,   -5,   13,   18,
+         -11,   -1,    9,   27,
+         -24,   -7,   14,   41,
+         -58,  -35,    5,    5,
+    ],
+);
+
+const fn human_readable_to_file_rank(piece_value: Score, pst: [Score; 32]) -> [Score; 64] {
+    let mut res = [0; 64];
+    let mut idx = 0;
+    while idx < 32 {
+        let rank = 7 - idx / 4;
+        let file = idx % 4;
+        let new_idx = Square::from_file_and_rank(File::from_idx(file), Rank::from_idx(rank)).idx();
+        let mirrored_idx = Square::from_idx(new_idx).mirror_horizontal().idx();
+        res[new_idx] = piece_value + pst[idx];
+        res[mirrored_idx] = piece_value + pst[idx];
+        idx += 1;
+    }
+    res
+}
+
+pub const PST_PAWN: PieceSquareTable = piece_square_table(MATERIAL_PAWN, PST_PAWN_MG_EG);
+pub const PST_KNIGHT: PieceSquareTable = piece_square_table(MATERIAL_KNIGHT, PST_KNIGHT_MG_EG);
+pub const PST_BISHOP: PieceSquareTable = piece_square_table(MATERIAL_BISHOP, PST_BISHOP_MG_EG);
+pub const PST_ROOK: PieceSquareTable = piece_square_table(MATERIAL_ROOK, PST_ROOK_MG_EG);
+pub const PST_QUEEN: PieceSquareTable = piece_square_table(MATERIAL_QUEEN, PST_QUEEN_MG_EG);
+pub const PST_KING: PieceSquareTable = piece_square_table(MATERIAL_KING, PST_KING_MG_EG);
+
+// Indexed by the piece type
+const PIECE_SQUARE_TABLES: [PieceSquareTable; 6] = {
+    let mut tables = [[PieceSquareScore(0, 0); 64]; 6];
+    tables[piece::Type::Pawn as usize] = PST_PAWN;
+    tables[piece::Type::Knight as usize] = PST_KNIGHT;
+    tables[piece::Type::Bishop as usize] = PST_BISHOP;
+    tables[piece::Type::Rook as usize] = PST_ROOK;
+    tables[piece::Type::Queen as usize] = PST_QUEEN;
+    tables[piece::Type::King as usize] = PST_KING;
+    tables
+};
+
+const fn piece_square_table(
+    material: PieceSquareScore,
+    mg_eg: ([Score; 32], [Score; 32]),
+) -> PieceSquareTable {
+    let mg = human_readable_to_file_rank(material.0, mg_eg.0);
+    let eg = human_readable_to_file_rank(material.1, mg_eg.1);
+    let mut table = [PieceSquareScore(0, 0); 64];
+    let mut idx = 0;
+    while idx < 64 {
+        table[idx] = PieceSquareScore(mg[idx], eg[idx]);
+        idx += 1;
+    }
+    table
+}
+
+// The score of a piece on a square for the piece's side. Black's squares are
+// flipped vertically.
+pub fn score(piece: Piece, square: Square) -> PieceSquareScore {
+    let square = match piece.piece_side() {
+        Side::White => square,
+        Side::Black => square.flip_vertical(),
+    };
+    PIECE_SQUARE_TABLES[piece.piece_type() as usize][square.idx()]
+}
+
+// The scores of both sides, calculated from scratch. The position keeps them
+// up to date incrementally.
+pub fn scores(pos: &Position) -> [PieceSquareScore; 2] {
+    let mut scores = [PieceSquareScore(0, 0); 2];
+    for side in [Side::White, Side::Black] {
+        for piece_type in [
+            piece::Type::Pawn,
+            piece::Type::Knight,
+            piece::Type::Bishop,
+            piece::Type::Rook,
+            piece::Type::Queen,
+            piece::Type::King,
+        ] {
+            let mut pieces = pos.piece_occupancy(side, piece_type);
+            while pieces != Bitboard::EMPTY {
+                let square = pieces.square_scan_forward_reset();
+                scores[side as usize] += score(Piece::new(side, piece_type), square);
+            }
+        }
+    }
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_readable_to_file_rank() {
+        #[rustfmt::skip]
+        let arr = [
+             0,  1,  2,  3,
+             8,  9, 10, 11,
+            16, 17, 18, 19,
+            24, 25, 26, 27,
+            32, 33, 34, 35,
+            40, 41, 42, 43,
+            48, 49, 50, 51,
+            56, 57, 58, 59,
+        ];
+
+        let res = super::human_readable_to_file_rank(100, arr);
+        assert_eq!(156, res[Square::A1.idx()]);
+        assert_eq!(148, res[Square::A2.idx()]);
+        assert_eq!(100, res[Square::A8.idx()]);
+        assert_eq!(157, res[Square::B1.idx()]);
+        assert_eq!(149, res[Square::B2.idx()]);
+        assert_eq!(142, res[Square::C3.idx()]);
+        assert_eq!(135, res[Square::D4.idx()]);
+        assert_eq!(127, res[Square::E5.idx()]);
+        assert_eq!(118, res[Square::F6.idx()]);
+        assert_eq!(109, res[Square::G7.idx()]);
+        assert_eq!(101, res[Square::G8.idx()]);
+        assert_eq!(156, res[Square::H1.idx()]);
+        assert_eq!(108, res[Square::H7.idx()]);
+        assert_eq!(100, res[Square::H8.idx()]);
+    }
+
+    #[test]
+    fn scores_of_initial_position() {
+        let pos = Position::initial();
+        assert_eq!(scores(&pos), pos.piece_square_scores());
+        assert_eq!(
+            pos.piece_square_score(Side::White),
+            pos.piece_square_score(Side::Black)
+        );
+    }
+
+    #[test]
+    fn set_piece_at_updates_scores() {
+        let mut pos = Position::empty();
+        pos.set_piece_at(Square::E4, Some(Piece::WHITE_KNIGHT));
+        assert_eq!(
+            PST_KNIGHT[Square::E4.idx()],
+            pos.piece_square_score(Side::White)
+        );
+        assert_eq!(PieceSquareScore(0, 0), pos.piece_square_score(Side::Black));
+
+        // Replaces the knight
+        pos.set_piece_at(Square::E4, Some(Piece::BLACK_PAWN));
+        assert_eq!(PieceSquareScore(0, 0), pos.piece_square_score(Side::White));
+        assert_eq!(
+            PST_PAWN[Square::E5.idx()],
+            pos.piece_square_score(Side::Black)
+        );
+        assert_eq!(scores(&pos), pos.piece_square_scores());
+
+        pos.set_piece_at(Square::E4, None);
+        assert_eq!([PieceSquareScore(0, 0); 2], pos.piece_square_scores());
+    }
+}
@@ -0,0 +1,930 @@
+use crate::bitboard::Bitboard;
+use crate::piece::{self, Piece};
+use crate::position::{CastlingRights, Position};
+use crate::side::Side;
+use crate::square::Square;
+
+// Hash key of the Polyglot opening book format. It is computed like the
+// Zobrist key, but with Polyglot's fixed table of random numbers and index
+// layout, so it matches the keys stored in book files.
+//
+// Indices
+// 0-767: Pieces, 64 * kind + 8 * rank + file, where kind is
+//        bp, wp, bn, wn, bb, wb, br, wr, bq, wq, bk, wk
+// 768 - 771: Castling rights (KQkq)
+// 772 - 779: En passant file
+// 780: White to move
+const IDX_FIRST_CASTLING_RIGHT: usize = 768;
+const IDX_FIRST_EN_PASSANT_FILE: usize = 772;
+const IDX_WHITE_TO_MOVE: usize = 780;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PolyglotKey(u64);
+
+impl PolyglotKey {
+    pub fn new(pos: &Position) -> Self {
+        let mut key = 0;
+
+        let mut occupancy = pos.occupancy();
+        while occupancy != Bitboard::EMPTY {
+            let square = occupancy.square_scan_forward_reset();
+            if let Some(p) = pos.piece_at(square) {
+                key ^= Self::KEYS[Self::piece_idx(p, square)].0;
+            }
+        }
+
+        for (idx, cr) in [
+            CastlingRights::WHITE_KINGSIDE,
+            CastlingRights::WHITE_QUEENSIDE,
+            CastlingRights::BLACK_KINGSIDE,
+            CastlingRights::BLACK_QUEENSIDE,
+        ]
+        .iter()
+        .enumerate()
+        {
+            if pos.castling_rights().contains(*cr) {
+                key ^= Self::KEYS[IDX_FIRST_CASTLING_RIGHT + idx].0;
+            }
+        }
+
+        // Polyglot only hashes the en passant file if the side to move has a
+        // pawn that could capture en passant
+        let en_passant = pos.en_passant_square();
+        if en_passant != Bitboard::EMPTY {
+            let side_to_move = pos.side_to_move();
+            let capturing_pawns = pos.piece_occupancy(side_to_move, piece::Type::Pawn);
+            let attackers = match side_to_move {
+                Side::White => en_passant.south_east_one() | en_passant.south_west_one(),
+                Side::Black => en_passant.north_east_one() | en_passant.north_west_one(),
+            };
+            if attackers & capturing_pawns != Bitboard::EMPTY {
+                let file = en_passant.to_square().file();
+                key ^= Self::KEYS[IDX_FIRST_EN_PASSANT_FILE + file.idx()].0;
+            }
+        }
+
+        if pos.side_to_move() == Side::White {
+            key ^= Self::KEYS[IDX_WHITE_TO_MOVE].0;
+        }
+
+        Self(key)
+    }
+
+    fn piece_idx(p: Piece, square: Square) -> usize {
+        let type_idx = match p.piece_type() {
+            piece::Type::Pawn => 0,
+            piece::Type::Knight => 1,
+            piece::Type::Bishop => 2,
+            piece::Type::Rook => 3,
+            piece::Type::Queen => 4,
+            piece::Type::King => 5,
+        };
+        let kind = 2 * type_idx + (p.piece_side() == Side::White) as usize;
+        64 * kind + 8 * square.rank().idx() + square.file().idx()
+    }
+}
+
+impl From<PolyglotKey> for u64 {
+    fn from(k: PolyglotKey) -> Self {
+        k.0
+    }
+}
+
+impl PolyglotKey {
+    const NUM_KEYS: usize = 781;
+    const KEYS: [PolyglotKey; Self::NUM_KEYS] = [
+        PolyglotKey(0x9d39247e33776d41),
+        PolyglotKey(0x2af7398005aaa5c7),
+        PolyglotKey(0x44db015024623547),
+        PolyglotKey(0x9c15f73e62a76ae2),
+        PolyglotKey(0x75834465489c0c89),
+        PolyglotKey(0x3290ac3a203001bf),
+        PolyglotKey(0x0fbbad1f61042279),
+        PolyglotKey(0xe83a908ff2fb60ca),
+        PolyglotKey(0x0d7e765d58755c10),
+        PolyglotKey(0x1a083822ceafe02d),
+        PolyglotKey(0x9605d5f0e25ec3b0),
+        PolyglotKey(0xd021ff5cd13a2ed5),
+        PolyglotKey(0x40bdf15d4a672e32),
+        PolyglotKey(0x011355146fd56395),
+        PolyglotKey(0x5db4832046f3d9e5),
+        PolyglotKey(0x239f8b2d7ff719cc),
+        PolyglotKey(0x05d1a1ae85b49aa1),
+        PolyglotKey(0x679f848f6e8fc971),
+        PolyglotKey(0x7449bbff801fed0b),
+        PolyglotKey(0x7d11cdb1c3b7adf0),
+        PolyglotKey(0x82c7709e781eb7cc),
+        PolyglotKey(0xf3218f1c9510786c),
+        PolyglotKey(0x331478f3af51bbe6),
+        PolyglotKey(0x4bb38de5e7219443),
+        PolyglotKey(0xaa649c6ebcfd50fc),
+        PolyglotKey(0x8dbd98a352afd40b),
+        PolyglotKey(0x87d2074b81d79217),
+        PolyglotKey(0x19f3c751d3e92ae1),
+        PolyglotKey(0xb4ab30f062b19abf),
+        PolyglotKey(0x7b0500ac42047ac4),
+        PolyglotKey(0xc9452ca81a09d85d),
+        PolyglotKey(0x24aa6c514da27500),
+        PolyglotKey(0x4c9f34427501b447),
+        PolyglotKey(0x14a68fd73c910841),
+        PolyglotKey(0xa71b9b83461cbd93),
+        PolyglotKey(0x03488b95b0f1850f),
+        PolyglotKey(0x637b2b34ff93c040),
+        PolyglotKey(0x09d1bc9a3dd90a94),
+        PolyglotKey(0x3575668334a1dd3b),
+        PolyglotKey(0x735e2b97a4c45a23),
+        PolyglotKey(0x18727070f1bd400b),
+        PolyglotKey(0x1fcbacd259bf02e7),
+        PolyglotKey(0xd310a7c2ce9b6555),
+        PolyglotKey(0xbf983fe0fe5d8244),
+        PolyglotKey(0x9f74d14f7454a824),
+        PolyglotKey(0x51ebdc4ab9ba3035),
+        PolyglotKey(0x5c82c505db9ab0fa),
+        PolyglotKey(0xfcf7fe8a3430b241),
+        PolyglotKey(0x3253a729b9ba3dde),
+        PolyglotKey(0x8c74c368081b3075),
+        PolyglotKey(0xb9bc6c87167c33e7),
+        PolyglotKey(0x7ef48f2b83024e20),
+        PolyglotKey(0x11d505d4c351bd7f),
+        PolyglotKey(0x6568fca92c76a243),
+        PolyglotKey(0x4de0b0f40f32a7b8),
+        PolyglotKey(0x96d693460cc37e5d),
+        PolyglotKey(0x42e240cb63689f2f),
+        PolyglotKey(0x6d2bdcdae2919661),
+        PolyglotKey(0x42880b0236e4d951),
+        PolyglotKey(0x5f0f4a5898171bb6),
+        PolyglotKey(0x39f890f579f92f88),
+        PolyglotKey(0x93c5b5f47356388b),
+        PolyglotKey(0x63dc359d8d231b78),
+        PolyglotKey(0xec16ca8aea98ad76),
+        PolyglotKey(0x5355f900c2a82dc7),
+        PolyglotKey(0x07fb9f855a997142),
+        PolyglotKey(0x5093417aa8a7ed5e),
+        PolyglotKey(0x7bcbc38da25a7f3c),
+        PolyglotKey(0x19fc8a768cf4b6d4),
+        PolyglotKey(0x637a7780decfc0d9),
+        PolyglotKey(0x8249a47aee0e41f7),
+        PolyglotKey(0x79ad695501e7d1e8),
+        PolyglotKey(0x14acbaf4777d5776),
+        PolyglotKey(0xf145b6beccdea195),
+        PolyglotKey(0xdabf2ac8201752fc),
+        PolyglotKey(0x24c3c94df9c8d3f6),
+        PolyglotKey(0xbb6e2924f03912ea),
+        PolyglotKey(0x0ce26c0b95c980d9),
+        PolyglotKey(0xa49cd132bfbf7cc4),
+        PolyglotKey(0xe99d662af4243939),
+        PolyglotKey(0x27e6ad7891165c3f),
+        PolyglotKey(0x8535f040b9744ff1),
+        PolyglotKey(0x54b3f4fa5f40d873),
+        PolyglotKey(0x72b12c32127fed2b),
+        PolyglotKey(0xee954d3c7b411f47),
+        PolyglotKey(0x9a85ac909a24eaa1),
+        PolyglotKey(0x70ac4cd9f04f21f5),
+        PolyglotKey(0xf9b89d3e99a075c2),
+        PolyglotKey(0x87b3e2b2b5c907b1),
+        PolyglotKey(0xa366e5b8c54f48b8),
+        PolyglotKey(0xae4a9346cc3f7cf2),
+        PolyglotKey(0x1920c04d47267bbd),
+        PolyglotKey(0x87bf02c6b49e2ae9),
+        PolyglotKey(0x092237ac237f3859),
+        PolyglotKey(0xff07f64ef8ed14d0),
+        PolyglotKey(0x8de8dca9f03cc54e),
+        PolyglotKey(0x9c1633264db49c89),
+        PolyglotKey(0xb3f22c3d0b0b38ed),
+        PolyglotKey(0x390e5fb44d01144b),
+        PolyglotKey(0x5bfea5b4712768e9),
+        PolyglotKey(0x1e1032911fa78984),
+        PolyglotKey(0x9a74acb964e78cb3),
+        PolyglotKey(0x4f80f7a035dafb04),
+        PolyglotKey(0x6304d09a0b3738c4),
+        PolyglotKey(0x2171e64683023a08),
+        PolyglotKey(0x5b9b63eb9ceff80c),
+        PolyglotKey(0x506aacf489889342),
+        PolyglotKey(0x1881afc9a3a701d6),
+        PolyglotKey(0x6503080440750644),
+        PolyglotKey(0xdfd395339cdbf4a7),
+        PolyglotKey(0xef927dbcf00c20f2),
+        PolyglotKey(0x7b32f7d1e03680ec),
+        PolyglotKey(0xb9fd7620e7316243),
+        PolyglotKey(0x05a7e8a57db91b77),
+        PolyglotKey(0xb5889c6e15630a75),
+        PolyglotKey(0x4a750a09ce9573f7),
+        PolyglotKey(0xcf464cec899a2f8a),
+        PolyglotKey(0xf538639ce705b824),
+        PolyglotKey(0x3c79a0ff5580ef7f),
+        PolyglotKey(0xede6c87f8477609d),
+        PolyglotKey(0x799e81f05bc93f31),
+        PolyglotKey(0x86536b8cf3428a8c),
+        PolyglotKey(0x97d7374c60087b73),
+        PolyglotKey(0xa246637cff328532),
+        PolyglotKey(0x043fcae60cc0eba0),
+        PolyglotKey(0x920e449535dd359e),
+        PolyglotKey(0x70eb093b15b290cc),
+        PolyglotKey(0x73a1921916591cbd),
+        PolyglotKey(0x56436c9fe1a1aa8d),
+        PolyglotKey(0xefac4b70633b8f81),
+        PolyglotKey(0xbb215798d45df7af),
+        PolyglotKey(0x45f20042f24f1768),
+        PolyglotKey(0x930f80f4e8eb7462),
+        PolyglotKey(0xff6712ffcfd75ea1),
+        PolyglotKey(0xae623fd67468aa70),
+        PolyglotKey(0xdd2c5bc84bc8d8fc),
+        PolyglotKey(0x7eed120d54cf2dd9),
+        PolyglotKey(0x22fe545401165f1c),
+        PolyglotKey(0xc91800e98fb99929),
+        PolyglotKey(0x808bd68e6ac10365),
+        PolyglotKey(0xdec468145b7605f6),
+        PolyglotKey(0x1bede3a3aef53302),
+        PolyglotKey(0x43539603d6c55602),
+        PolyglotKey(0xaa969b5c691ccb7a),
+        PolyglotKey(0xa87832d392efee56),
+        PolyglotKey(0x65942c7b3c7e11ae),
+        PolyglotKey(0xded2d633cad004f6),
+        PolyglotKey(0x21f08570f420e565),
+        PolyglotKey(0xb415938d7da94e3c),
+        PolyglotKey(0x91b859e59ecb6350),
+        PolyglotKey(0x10cff333e0ed804a),
+        PolyglotKey(0x28aed140be0bb7dd),
+        PolyglotKey(0xc5cc1d89724fa456),
+        PolyglotKey(0x5648f680f11a2741),
+        PolyglotKey(0x2d255069f0b7dab3),
+        PolyglotKey(0x9bc5a38ef729abd4),
+        PolyglotKey(0xef2f054308f6a2bc),
+        PolyglotKey(0xaf2042f5cc5c2858),
+        PolyglotKey(0x480412bab7f5be2a),
+        PolyglotKey(0xaef3af4a563dfe43),
+        PolyglotKey(0x19afe59ae451497f),
+        PolyglotKey(0x52593803dff1e840),
+        PolyglotKey(0xf4f076e65f2ce6f0),
+        PolyglotKey(0x11379625747d5af3),
+        PolyglotKey(0xbce5d2248682c115),
+        PolyglotKey(0x9da4243de836994f),
+        PolyglotKey(0x066f70b33fe09017),
+        PolyglotKey(0x4dc4de189b671a1c),
+        PolyglotKey(0x51039ab7712457c3),
+        PolyglotKey(0xc07a3f80c31fb4b4),
+        PolyglotKey(0xb46ee9c5e64a6e7c),
+        PolyglotKey(0xb3819a42abe61c87),
+        PolyglotKey(0x21a007933a522a20),
+        PolyglotKey(0x2df16f761598aa4f),
+        PolyglotKey(0x763c4a1371b368fd),
+        PolyglotKey(0xf793c46702e086a0),
+        PolyglotKey(0xd7288e012aeb8d31),
+        PolyglotKey(0xde336a2a4bc1c44b),
+        PolyglotKey(0x0bf692b38d079f23),
+        PolyglotKey(0x2c604a7a177326b3),
+        PolyglotKey(0x4850e73e03eb6064),
+        PolyglotKey(0xcfc447f1e53c8e1b),
+        PolyglotKey(0xb05ca3f564268d99),
+        PolyglotKey(0x9ae182c8bc9474e8),
+        PolyglotKey(0xa4fc4bd4fc5558ca),
+        PolyglotKey(0xe755178d58fc4e76),
+        PolyglotKey(0x69b97db1a4c03dfe),
+        PolyglotKey(0xf9b5b7c4acc67c96),
+        PolyglotKey(0xfc6a82d64b8655fb),
+        PolyglotKey(0x9c684cb6c4d24417),
+        PolyglotKey(0x8ec97d2917456ed0),
+        PolyglotKey(0x6703df9d2924e97e),
+        PolyglotKey(0xc547f57e42a7444e),
+        PolyglotKey(0x78e37644e7cad29e),
+        PolyglotKey(0xfe9a44e9362f05fa),
+        PolyglotKey(0x08bd35cc38336615),
+        PolyglotKey(0x9315e5eb3a129ace),
+        PolyglotKey(0x94061b871e04df75),
+        PolyglotKey(0xdf1d9f9d784ba010),
+        PolyglotKey(0x3bba57b68871b59d),
+        PolyglotKey(0xd2b7adeeded1f73f),
+        PolyglotKey(0xf7a255d83bc373f8),
+        PolyglotKey(0xd7f4f2448c0ceb81),
+        PolyglotKey(0xd95be88cd210ffa7),
+        PolyglotKey(0x336f52f8ff4728e7),
+        PolyglotKey(0xa74049dac312ac71),
+        PolyglotKey(0xa2f61bb6e437fdb5),
+        PolyglotKey(0x4f2a5cb07f6a35b3),
+        PolyglotKey(0x87d380bda5bf7859),
+        PolyglotKey(0x16b9f7e06c453a21),
+        PolyglotKey(0x7ba2484c8a0fd54e),
+        PolyglotKey(0xf3a678cad9a2e38c),
+        PolyglotKey(0x39b0bf7dde437ba2),
+        PolyglotKey(0xfcaf55c1bf8a4424),
+        PolyglotKey(0x18fcf680573fa594),
+        PolyglotKey(0x4c0563b89f495ac3),
+        PolyglotKey(0x40e087931a00930d),
+        PolyglotKey(0x8cffa9412eb642c1),
+        PolyglotKey(0x68ca39053261169f),
+        PolyglotKey(0x7a1ee967d27579e2),
+        PolyglotKey(0x9d1d60e5076f5b6f),
+        PolyglotKey(0x3810e399b6f65ba2),
+        PolyglotKey(0x32095b6d4ab5f9b1),
+        PolyglotKey(0x35cab62109dd038a),
+        PolyglotKey(0xa90b24499fcfafb1),
+        PolyglotKey(0x77a225a07cc2c6bd),
+        PolyglotKey(0x513e5e634c70e331),
+        PolyglotKey(0x4361c0ca3f692f12),
+        PolyglotKey(0xd941aca44b20a45b),
+        PolyglotKey(0x528f7c8602c5807b),
+        PolyglotKey(0x52ab92beb9613989),
+        PolyglotKey(0x9d1dfa2efc557f73),
+        PolyglotKey(0x722ff175f572c348),
+        PolyglotKey(0x1d1260a51107fe97),
+        PolyglotKey(0x7a249a57ec0c9ba2),
+        PolyglotKey(0x04208fe9e8f7f2d6),
+        PolyglotKey(0x5a110c6058b920a0),
+        PolyglotKey(0x0cd9a497658a5698),
+        PolyglotKey(0x56fd23c8f9715a4c),
+        PolyglotKey(0x284c847b9d887aae),
+        PolyglotKey(0x04feabfbbdb619cb),
+        PolyglotKey(0x742e1e651c60ba83),
+        PolyglotKey(0x9a9632e65904ad3c),
+        PolyglotKey(0x881b82a13b51b9e2),
+        PolyglotKey(0x506e6744cd974924),
+        PolyglotKey(0xb0183db56ffc6a79),
+        PolyglotKey(0x0ed9b915c66ed37e),
+        PolyglotKey(0x5e11e86d5873d484),
+        PolyglotKey(0xf678647e3519ac6e),
+        PolyglotKey(0x1b85d488d0f20cc5),
+        PolyglotKey(0xdab9fe6525d89021),
+        PolyglotKey(0x0d151d86adb73615),
+        PolyglotKey(0xa865a54edcc0f019),
+        PolyglotKey(0x93c42566aef98ffb),
+        PolyglotKey(0x99e7afeabe000731),
+        PolyglotKey(0x48cbff086ddf285a),
+        PolyglotKey(0x7f9b6af1ebf78baf),
+        PolyglotKey(0x58627e1a149bba21),
+        PolyglotKey(0x2cd16e2abd791e33),
+        PolyglotKey(0xd363eff5f0977996),
+        PolyglotKey(0x0ce2a38c344a6eed),
+        PolyglotKey(0x1a804aadb9cfa741),
+        PolyglotKey(0x907f30421d78c5de),
+        PolyglotKey(0x501f65edb3034d07),
+        PolyglotKey(0x37624ae5a48fa6e9),
+        PolyglotKey(0x957baf61700cff4e),
+        PolyglotKey(0x3a6c27934e31188a),
+        PolyglotKey(0xd49503536abca345),
+        PolyglotKey(0x088e049589c432e0),
+        PolyglotKey(0xf943aee7febf21b8),
+        PolyglotKey(0x6c3b8e3e336139d3),
+        PolyglotKey(0x364f6ffa464ee52e),
+        PolyglotKey(0xd60f6dcedc314222),
+        PolyglotKey(0x56963b0dca418fc0),
+        PolyglotKey(0x16f50edf91e513af),
+        PolyglotKey(0xef1955914b609f93),
+        PolyglotKey(0x565601c0364e3228),
+        PolyglotKey(0xecb53939887e8175),
+        PolyglotKey(0xbac7a9a18531294b),
+        PolyglotKey(0xb344c470397bba52),
+        PolyglotKey(0x65d34954daf3cebd),
+        PolyglotKey(0xb4b81b3fa97511e2),
+        PolyglotKey(0xb422061193d6f6a7),
+        PolyglotKey(0x071582401c38434d),
+        PolyglotKey(0x7a13f18bbedc4ff5),
+        PolyglotKey(0xbc4097b116c524d2),
+        PolyglotKey(0x59b97885e2f2ea28),
+        PolyglotKey(0x99170a5dc3115544),
+        PolyglotKey(0x6f423357e7c6a9f9),
+        PolyglotKey(0x325928ee6e6f8794),
+        PolyglotKey(0xd0e4366228b03343),
+        PolyglotKey(0x565c31f7de89ea27),
+        PolyglotKey(0x30f5611484119414),
+        PolyglotKey(0xd873db391292ed4f),
+        PolyglotKey(0x7bd94e1d8e17debc),
+        PolyglotKey(0xc7d9f16864a76e94),
+        PolyglotKey(0x947ae053ee56e63c),
+        PolyglotKey(0xc8c93882f9475f5f),
+        PolyglotKey(0x3a9bf55ba91f81ca),
+        PolyglotKey(0xd9a11fbb3d9808e4),
+        PolyglotKey(0x0fd22063edc29fca),
+        PolyglotKey(0xb3f256d8aca0b0b9),
+        PolyglotKey(0xb03031a8b4516e84),
+        PolyglotKey(0x35dd37d5871448af),
+        PolyglotKey(0xe9f6082b05542e4e),
+        PolyglotKey(0xebfafa33d7254b59),
+        PolyglotKey(0x9255abb50d532280),
+        PolyglotKey(0xb9ab4ce57f2d34f3),
+        PolyglotKey(0x693501d628297551),
+        PolyglotKey(0xc62c58f97dd949bf),
+        PolyglotKey(0xcd454f8f19c5126a),
+        PolyglotKey(0xbbe83f4ecc2bdecb),
+        PolyglotKey(0xdc842b7e2819e230),
+        PolyglotKey(0xba89142e007503b8),
+        PolyglotKey(0xa3bc941d0a5061cb),
+        PolyglotKey(0xe9f6760e32cd8021),
+        PolyglotKey(0x09c7e552bc76492f),
+        PolyglotKey(0x852f54934da55cc9),
+        PolyglotKey(0x8107fccf064fcf56),
+        PolyglotKey(0x098954d51fff6580),
+        PolyglotKey(0x23b70edb1955c4bf),
+        PolyglotKey(0xc330de426430f69d),
+        PolyglotKey(0x4715ed43e8a45c0a),
+        PolyglotKey(0xa8d7e4dab780a08d),
+        PolyglotKey(0x0572b974f03ce0bb),
+        PolyglotKey(0xb57d2e985e1419c7),
+        PolyglotKey(0xe8d9ecbe2cf3d73f),
+        PolyglotKey(0x2fe4b17170e59750),
+        PolyglotKey(0x11317ba87905e790),
+        PolyglotKey(0x7fbf21ec8a1f45ec),
+        PolyglotKey(0x1725cabfcb045b00),
+        PolyglotKey(0x964e915cd5e2b207),
+        PolyglotKey(0x3e2b8bcbf016d66d),
+        PolyglotKey(0xbe7444e39328a0ac),
+        PolyglotKey(0xf85b2b4fbcde44b7),
+        PolyglotKey(0x49353fea39ba63b1),
+        PolyglotKey(0x1dd01aafcd53486a),
+        PolyglotKey(0x1fca8a92fd719f85),
+        PolyglotKey(0xfc7c95d827357afa),
+        PolyglotKey(0x18a6a990c8b35ebd),
+        PolyglotKey(0xcccb7005c6b9c28d),
+        PolyglotKey(0x3bdbb92c43b17f26),
+        PolyglotKey(0xaa70b5b4f89695a2),
+        PolyglotKey(0xe94c39a54a98307f),
+        PolyglotKey(0xb7a0b174cff6f36e),
+        PolyglotKey(0xd4dba84729af48ad),
+        PolyglotKey(0x2e18bc1ad9704a68),
+        PolyglotKey(0x2de0966daf2f8b1c),
+        PolyglotKey(0xb9c11d5b1e43a07e),
+        PolyglotKey(0x64972d68dee33360),
+        PolyglotKey(0x94628d38d0c20584),
+        PolyglotKey(0xdbc0d2b6ab90a559),
+        PolyglotKey(0xd2733c4335c6a72f),
+        PolyglotKey(0x7e75d99d94a70f4d),
+        PolyglotKey(0x6ced1983376fa72b),
+        PolyglotKey(0x97fcaacbf030bc24),
+        PolyglotKey(0x7b77497b32503b12),
+        PolyglotKey(0x8547eddfb81ccb94),
+        PolyglotKey(0x79999cdff70902cb),
+        PolyglotKey(0xcffe1939438e9b24),
+        PolyglotKey(0x829626e3892d95d7),
+        PolyglotKey(0x92fae24291f2b3f1),
+        PolyglotKey(0x63e22c147b9c3403),
+        PolyglotKey(0xc678b6d860284a1c),
+        PolyglotKey(0x5873888850659ae7),
+        PolyglotKey(0x0981dcd296a8736d),
+        PolyglotKey(0x9f65789a6509a440),
+        PolyglotKey(0x9ff38fed72e9052f),
+        PolyglotKey(0xe479ee5b9930578c),
+        PolyglotKey(0xe7f28ecd2d49eecd),
+        PolyglotKey(0x56c074a581ea17fe),
+        PolyglotKey(0x5544f7d774b14aef),
+        PolyglotKey(0x7b3f0195fc6f290f),
+        PolyglotKey(0x12153635b2c0cf57),
+        PolyglotKey(0x7f5126dbba5e0ca7),
+        PolyglotKey(0x7a76956c3eafb413),
+        PolyglotKey(0x3d5774a11d31ab39),
+        PolyglotKey(0x8a1b083821f40cb4),
+        PolyglotKey(0x7b4a38e32537df62),
+        PolyglotKey(0x950113646d1d6e03),
+        PolyglotKey(0x4da8979a0041e8a9),
+        PolyglotKey(0x3bc36e078f7515d7),
+        PolyglotKey(0x5d0a12f27ad310d1),
+        PolyglotKey(0x7f9d1a2e1ebe1327),
+        PolyglotKey(0xda3a361b1c5157b1),
+        PolyglotKey(0xdcdd7d20903d0c25),
+        PolyglotKey(0x36833336d068f707),
+        PolyglotKey(0xce68341f79893389),
+        PolyglotKey(0xab9090168dd05f34),
+        PolyglotKey(0x43954b3252dc25e5),
+        PolyglotKey(0xb438c2b67f98e5e9),
+        PolyglotKey(0x10dcd78e3851a492),
+        PolyglotKey(0xdbc27ab5447822bf),
+        PolyglotKey(0x9b3cdb65f82ca382),
+        PolyglotKey(0xb67b7896167b4c84),
+        PolyglotKey(0xbfced1b0048eac50),
+        PolyglotKey(0xa9119b60369ffebd),
+        PolyglotKey(0x1fff7ac80904bf45),
+        PolyglotKey(0xac12fb171817eee7),
+        PolyglotKey(0xaf08da9177dda93d),
+        PolyglotKey(0x1b0cab936e65c744),
+        PolyglotKey(0xb559eb1d04e5e932),
+        PolyglotKey(0xc37b45b3f8d6f2ba),
+        PolyglotKey(0xc3a9dc228caac9e9),
+        PolyglotKey(0xf3b8b6675a6507ff),
+        PolyglotKey(0x9fc477de4ed681da),
+        PolyglotKey(0x67378d8eccef96cb),
+        PolyglotKey(0x6dd856d94d259236),
+        PolyglotKey(0xa319ce15b0b4db31),
+        PolyglotKey(0x073973751f12dd5e),
+        PolyglotKey(0x8a8e849eb32781a5),
+        PolyglotKey(0xe1925c71285279f5),
+        PolyglotKey(0x74c04bf1790c0efe),
+        PolyglotKey(0x4dda48153c94938a),
+        PolyglotKey(0x9d266d6a1cc0542c),
+        PolyglotKey(0x7440fb816508c4fe),
+        PolyglotKey(0x13328503df48229f),
+        PolyglotKey(0xd6bf7baee43cac40),
+        PolyglotKey(0x4838d65f6ef6748f),
+        PolyglotKey(0x1e152328f3318dea),
+        PolyglotKey(0x8f8419a348f296bf),
+        PolyglotKey(0x72c8834a5957b511),
+        PolyglotKey(0xd7a023a73260b45c),
+        PolyglotKey(0x94ebc8abcfb56dae),
+        PolyglotKey(0x9fc10d0f989993e0),
+        PolyglotKey(0xde68a2355b93cae6),
+        PolyglotKey(0xa44cfe79ae538bbe),
+        PolyglotKey(0x9d1d84fcce371425),
+        PolyglotKey(0x51d2b1ab2ddfb636),
+        PolyglotKey(0x2fd7e4b9e72cd38c),
+        PolyglotKey(0x65ca5b96b7552210),
+        PolyglotKey(0xdd69a0d8ab3b546d),
+        PolyglotKey(0x604d51b25fbf70e2),
+        PolyglotKey(0x73aa8a564fb7ac9e),
+        PolyglotKey(0x1a8c1e992b941148),
+        PolyglotKey(0xaac40a2703d9bea0),
+        PolyglotKey(0x764dbeae7fa4f3a6),
+        PolyglotKey(0x1e99b96e70a9be8b),
+        PolyglotKey(0x2c5e9deb57ef4743),
+        PolyglotKey(0x3a938fee32d29981),
+        PolyglotKey(0x26e6db8ffdf5adfe),
+        PolyglotKey(0x469356c504ec9f9d),
+        PolyglotKey(0xc8763c5b08d1908c),
+        PolyglotKey(0x3f6c6af859d80055),
+        PolyglotKey(0x7f7cc39420a3a545),
+        PolyglotKey(0x9bfb227ebdf4c5ce),
+        PolyglotKey(0x89039d79d6fc5c5c),
+        PolyglotKey(0x8fe88b57305e2ab6),
+        PolyglotKey(0xa09e8c8c35ab96de),
+        PolyglotKey(0xfa7e393983325753),
+        PolyglotKey(0xd6b6d0ecc617c699),
+        PolyglotKey(0xdfea21ea9e7557e3),
+        PolyglotKey(0xb67c1fa481680af8),
+        PolyglotKey(0xca1e3785a9e724e5),
+        PolyglotKey(0x1cfc8bed0d681639),
+        PolyglotKey(0xd18d8549d140caea),
+        PolyglotKey(0x4ed0fe7e9dc91335),
+        PolyglotKey(0xe4dbf0634473f5d2),
+        PolyglotKey(0x1761f93a44d5aefe),
+        PolyglotKey(0x53898e4c3910da55),
+        PolyglotKey(0x734de8181f6ec39a),
+        PolyglotKey(0x2680b122baa28d97),
+        PolyglotKey(0x298af231c85bafab),
+        PolyglotKey(0x7983eed3740847d5),
+        PolyglotKey(0x66c1a2a1a60cd889),
+        PolyglotKey(0x9e17e49642a3e4c1),
+        PolyglotKey(0xedb454e7badc0805),
+        PolyglotKey(0x50b704cab602c329),
+        PolyglotKey(0x4cc317fb9cddd023),
+        PolyglotKey(0x66b4835d9eafea22),
+        PolyglotKey(0x219b97e26ffc81bd),
+        PolyglotKey(0x261e4e4c0a333a9d),
+        PolyglotKey(0x1fe2cca76517db90),
+        PolyglotKey(0xd7504dfa8816edbb),
+        PolyglotKey(0xb9571fa04dc089c8),
+        PolyglotKey(0x1ddc0325259b27de),
+        PolyglotKey(0xcf3f4688801eb9aa),
+        PolyglotKey(0xf4f5d05c10cab243),
+        PolyglotKey(0x38b6525c21a42b0e),
+        PolyglotKey(0x36f60e2ba4fa6800),
+        PolyglotKey(0xeb3593803173e0ce),
+        PolyglotKey(0x9c4cd6257c5a3603),
+        PolyglotKey(0xaf0c317d32adaa8a),
+        PolyglotKey(0x258e5a80c7204c4b),
+        PolyglotKey(0x8b889d624d44885d),
+        PolyglotKey(0xf4d14597e660f855),
+        PolyglotKey(0xd4347f66ec8941c3),
+        PolyglotKey(0xe699ed85b0dfb40d),
+        PolyglotKey(0x2472f6207c2d0484),
+        PolyglotKey(0xc2a1e7b5b459aeb5),
+        PolyglotKey(0xab4f6451cc1d45ec),
+        PolyglotKey(0x63767572ae3d6174),
+        PolyglotKey(0xa59e0bd101731a28),
+        PolyglotKey(0x116d0016cb948f09),
+        PolyglotKey(0x2cf9c8ca052f6e9f),
+        PolyglotKey(0x0b090a7560a968e3),
+        PolyglotKey(0xabeeddb2dde06ff1),
+        PolyglotKey(0x58efc10b06a2068d),
+        PolyglotKey(0xc6e57a78fbd986e0),
+        PolyglotKey(0x2eab8ca63ce802d7),
+        PolyglotKey(0x14a195640116f336),
+        PolyglotKey(0x7c0828dd624ec390),
+        PolyglotKey(0xd74bbe77e6116ac7),
+        PolyglotKey(0x804456af10f5fb53),
+        PolyglotKey(0xebe9ea2adf4321c7),
+        PolyglotKey(0x03219a39ee587a30),
+        PolyglotKey(0x49787fef17af9924),
+        PolyglotKey(0xa1e9300cd8520548),
+        PolyglotKey(0x5b45e522e4b1b4ef),
+        PolyglotKey(0xb49c3b3995091a36),
+        PolyglotKey(0xd4490ad526f14431),
+        PolyglotKey(0x12a8f216af9418c2),
+        PolyglotKey(0x001f837cc7350524),
+        PolyglotKey(0x1877b51e57a764d5),
+        PolyglotKey(0xa2853b80f17f58ee),
+        PolyglotKey(0x993e1de72d36d310),
+        PolyglotKey(0xb3598080ce64a656),
+        PolyglotKey(0x252f59cf0d9f04bb),
+        PolyglotKey(0xd23c8e176d113600),
+        PolyglotKey(0x1bda0492e7e4586e),
+        PolyglotKey(0x21e0bd5026c619bf),
+        PolyglotKey(0x3b097adaf088f94e),
+        PolyglotKey(0x8d14dedb30be846e),
+        PolyglotKey(0xf95cffa23af5f6f4),
+        PolyglotKey(0x3871700761b3f743),
+        PolyglotKey(0xca672b91e9e4fa16),
+        PolyglotKey(0x64c8e531bff53b55),
+        PolyglotKey(0x241260ed4ad1e87d),
+        PolyglotKey(0x106c09b972d2e822),
+        PolyglotKey(0x7fba195410e5ca30),
+        PolyglotKey(0x7884d9bc6cb569d8),
+        PolyglotKey(0x0647dfedcd894a29),
+        PolyglotKey(0x63573ff03e224774),
+        PolyglotKey(0x4fc8e9560f91b123),
+        PolyglotKey(0x1db956e450275779),
+        PolyglotKey(0xb8d91274b9e9d4fb),
+        PolyglotKey(0xa2ebee47e2fbfce1),
+        PolyglotKey(0xd9f1f30ccd97fb09),
+        PolyglotKey(0xefed53d75fd64e6b),
+        PolyglotKey(0x2e6d02c36017f67f),
+        PolyglotKey(0xa9aa4d20db084e9b),
+        PolyglotKey(0xb64be8d8b25396c1),
+        PolyglotKey(0x70cb6af7c2d5bcf0),
+        PolyglotKey(0x98f076a4f7a2322e),
+        PolyglotKey(0xbf84470805e69b5f),
+        PolyglotKey(0x94c3251f06f90cf3),
+        PolyglotKey(0x3e003e616a6591e9),
+        PolyglotKey(0xb925a6cd0421aff3),
+        PolyglotKey(0x61bdd1307c66e300),
+        PolyglotKey(0xbf8d5108e27e0d48),
+        PolyglotKey(0x240ab57a8b888b20),
+        PolyglotKey(0xfc87614baf287e07),
+        PolyglotKey(0xef02cdd06ffdb432),
+        PolyglotKey(0xa1082c0466df6c0a),
+        PolyglotKey(0x8215e577001332c8),
+        PolyglotKey(0xd39bb9c3a48db6cf),
+        PolyglotKey(0x2738259634305c14),
+        PolyglotKey(0x61cf4f94c97df93d),
+        PolyglotKey(0x1b6baca2ae4e125b),
+        PolyglotKey(0x758f450c88572e0b),
+        PolyglotKey(0x959f587d507a8359),
+        PolyglotKey(0xb063e962e045f54d),
+        PolyglotKey(0x60e8ed72c0dff5d1),
+        PolyglotKey(0x7b64978555326f9f),
+        PolyglotKey(0xfd080d236da814ba),
+        PolyglotKey(0x8c90fd9b083f4558),
+        PolyglotKey(0x106f72fe81e2c590),
+        PolyglotKey(0x7976033a39f7d952),
+        PolyglotKey(0xa4ec0132764ca04b),
+        PolyglotKey(0x733ea705fae4fa77),
+        PolyglotKey(0xb4d8f77bc3e56167),
+        PolyglotKey(0x9e21f4f903b33fd9),
+        PolyglotKey(0x9d765e419fb69f6d),
+        PolyglotKey(0xd30c088ba61ea5ef),
+        PolyglotKey(0x5d94337fbfaf7f5b),
+        PolyglotKey(0x1a4e4822eb4d7a59),
+        PolyglotKey(0x6ffe73e81b637fb3),
+        PolyglotKey(0xddf957bc36d8b9ca),
+        PolyglotKey(0x64d0e29eea8838b3),
+        PolyglotKey(0x08dd9bdfd96b9f63),
+        PolyglotKey(0x087e79e5a57d1d13),
+        PolyglotKey(0xe328e230e3e2b3fb),
+        PolyglotKey(0x1c2559e30f0946be),
+        PolyglotKey(0x720bf5f26f4d2eaa),
+        PolyglotKey(0xb0774d261cc609db),
+        PolyglotKey(0x443f64ec5a371195),
+        PolyglotKey(0x4112cf68649a260e),
+        PolyglotKey(0xd813f2fab7f5c5ca),
+        PolyglotKey(0x660d3257380841ee),
+        PolyglotKey(0x59ac2c7873f910a3),
+        PolyglotKey(0xe846963877671a17),
+        PolyglotKey(0x93b633abfa3469f8),
+        PolyglotKey(0xc0c0f5a60ef4cdcf),
+        PolyglotKey(0xcaf21ecd4377b28c),
+        PolyglotKey(0x57277707199b8175),
+        PolyglotKey(0x506c11b9d90e8b1d),
+        PolyglotKey(0xd83cc2687a19255f),
+        PolyglotKey(0x4a29c6465a314cd1),
+        PolyglotKey(0xed2df21216235097),
+        PolyglotKey(0xb5635c95ff7296e2),
+        PolyglotKey(0x22af003ab672e811),
+        PolyglotKey(0x52e762596bf68235),
+        PolyglotKey(0x9aeba33ac6ecc6b0),
+        PolyglotKey(0x944f6de09134dfb6),
+        PolyglotKey(0x6c47bec883a7de39),
+        PolyglotKey(0x6ad047c430a12104),
+        PolyglotKey(0xa5b1cfdba0ab4067),
+        PolyglotKey(0x7c45d833aff07862),
+        PolyglotKey(0x5092ef950a16da0b),
+        PolyglotKey(0x9338e69c052b8e7b),
+        PolyglotKey(0x455a4b4cfe30e3f5),
+        PolyglotKey(0x6b02e63195ad0cf8),
+        PolyglotKey(0x6b17b224bad6bf27),
+        PolyglotKey(0xd1e0ccd25bb9c169),
+        PolyglotKey(0xde0c89a556b9ae70),
+        PolyglotKey(0x50065e535a213cf6),
+        PolyglotKey(0x9c1169fa2777b874),
+        PolyglotKey(0x78edefd694af1eed),
+        PolyglotKey(0x6dc93d9526a50e68),
+        PolyglotKey(0xee97f453f06791ed),
+        PolyglotKey(0x32ab0edb696703d3),
+        PolyglotKey(0x3a6853c7e70757a7),
+        PolyglotKey(0x31865ced6120f37d),
+        PolyglotKey(0x67fef95d92607890),
+        PolyglotKey(0x1f2b1d1f15f6dc9c),
+        PolyglotKey(0xb69e38a8965c6b65),
+        PolyglotKey(0xaa9119ff184cccf4),
+        PolyglotKey(0xf43c732873f24c13),
+        PolyglotKey(0xfb4a3d794a9a80d2),
+        PolyglotKey(0x3550c2321fd6109c),
+        PolyglotKey(0x371f77e76bb8417e),
+        PolyglotKey(0x6bfa9aae5ec05779),
+        PolyglotKey(0xcd04f3ff001a4778),
+        PolyglotKey(0xe3273522064480ca),
+        PolyglotKey(0x9f91508bffcfc14a),
+        PolyglotKey(0x049a7f41061a9e60),
+        PolyglotKey(0xfcb6be43a9f2fe9b),
+        PolyglotKey(0x08de8a1c7797da9b),
+        PolyglotKey(0x8f9887e6078735a1),
+        PolyglotKey(0xb5b4071dbfc73a66),
+        PolyglotKey(0x230e343dfba08d33),
+        PolyglotKey(0x43ed7f5a0fae657d),
+        PolyglotKey(0x3a88a0fbbcb05c63),
+        PolyglotKey(0x21874b8b4d2dbc4f),
+        PolyglotKey(0x1bdea12e35f6a8c9),
+        PolyglotKey(0x53c065c6c8e63528),
+        PolyglotKey(0xe34a1d250e7a8d6b),
+        PolyglotKey(0xd6b04d3b7651dd7e),
+        PolyglotKey(0x5e90277e7cb39e2d),
+        PolyglotKey(0x2c046f22062dc67d),
+        PolyglotKey(0xb10bb459132d0a26),
+        PolyglotKey(0x3fa9ddfb67e2f199),
+        PolyglotKey(0x0e09b88e1914f7af),
+        PolyglotKey(0x10e8b35af3eeab37),
+        PolyglotKey(0x9eedeca8e272b933),
+        PolyglotKey(0xd4c718bc4ae8ae5f),
+        PolyglotKey(0x81536d601170fc20),
+        PolyglotKey(0x91b534f885818a06),
+        PolyglotKey(0xec8177f83f900978),
+        PolyglotKey(0x190e714fada5156e),
+        PolyglotKey(0xb592bf39b0364963),
+        PolyglotKey(0x89c350c893ae7dc1),
+        PolyglotKey(0xac042e70f8b383f2),
+        PolyglotKey(0xb49b52e587a1ee60),
+        PolyglotKey(0xfb152fe3ff26da89),
+        PolyglotKey(0x3e666e6f69ae2c15),
+        PolyglotKey(0x3b544ebe544c19f9),
+        PolyglotKey(0xe805a1e290cf2456),
+        PolyglotKey(0x24b33c9d7ed25117),
+        PolyglotKey(0xe74733427b72f0c1),
+        PolyglotKey(0x0a804d18b7097475),
+        PolyglotKey(0x57e3306d881edb4f),
+        PolyglotKey(0x4ae7d6a36eb5dbcb),
+        PolyglotKey(0x2d8d5432157064c8),
+        PolyglotKey(0xd1e649de1e7f268b),
+        PolyglotKey(0x8a328a1cedfe552c),
+        PolyglotKey(0x07a3aec79624c7da),
+        PolyglotKey(0x84547ddc3e203c94),
+        PolyglotKey(0x990a98fd5071d263),
+        PolyglotKey(0x1a4ff12616eefc89),
+        PolyglotKey(0xf6f7fd1431714200),
+        PolyglotKey(0x30c05b1ba332f41c),
+        PolyglotKey(0x8d2636b81555a786),
+        PolyglotKey(0x46c9feb55d120902),
+        PolyglotKey(0xccec0a73b49c9921),
+        PolyglotKey(0x4e9d2827355fc492),
+        PolyglotKey(0x19ebb029435dcb0f),
+        PolyglotKey(0x4659d2b743848a2c),
+        PolyglotKey(0x963ef2c96b33be31),
+        PolyglotKey(0x74f85198b05a2e7d),
+        PolyglotKey(0x5a0f544dd2b1fb18),
+        PolyglotKey(0x03727073c2e134b1),
+        PolyglotKey(0xc7f6aa2de59aea61),
+        PolyglotKey(0x352787baa0d7c22f),
+        PolyglotKey(0x9853eab63b5e0b35),
+        PolyglotKey(0xabbdcdd7ed5c0860),
+        PolyglotKey(0xcf05daf5ac8d77b0),
+        PolyglotKey(0x49cad48cebf4a71e),
+        PolyglotKey(0x7a4c10ec2158c4a6),
+        PolyglotKey(0xd9e92aa246bf719e),
+        PolyglotKey(0x13ae978d09fe5557),
+        PolyglotKey(0x730499af921549ff),
+        PolyglotKey(0x4e4b705b92903ba4),
+        PolyglotKey(0xff577222c14f0a3a),
+        PolyglotKey(0x55b6344cf97aafae),
+        PolyglotKey(0xb862225b055b6960),
+        PolyglotKey(0xcac09afbddd2cdb4),
+        PolyglotKey(0xdaf8e9829fe96b5f),
+        PolyglotKey(0xb5fdfc5d3132c498),
+        PolyglotKey(0x310cb380db6f7503),
+        PolyglotKey(0xe87fbb46217a360e),
+        PolyglotKey(0x2102ae466ebb1148),
+        PolyglotKey(0xf8549e1a3aa5e00d),
+        PolyglotKey(0x07a69afdcc42261a),
+        PolyglotKey(0xc4c118bfe78feaae),
+        PolyglotKey(0xf9f4892ed96bd438),
+        PolyglotKey(0x1af3dbe25d8f45da),
+        PolyglotKey(0xf5b4b0b0d2deeeb4),
+        PolyglotKey(0x962aceefa82e1c84),
+        PolyglotKey(0x046e3ecaaf453ce9),
+        PolyglotKey(0xf05d129681949a4c),
+        PolyglotKey(0x964781ce734b3c84),
+        PolyglotKey(0x9c2ed44081ce5fbd),
+        PolyglotKey(0x522e23f3925e319e),
+        PolyglotKey(0x177e00f9fc32f791),
+        PolyglotKey(0x2bc60a63a6f3b3f2),
+        PolyglotKey(0x222bbfae61725606),
+        PolyglotKey(0x486289ddcc3d6780),
+        PolyglotKey(0x7dc7785b8efdfc80),
+        PolyglotKey(0x8af38731c02ba980),
+        PolyglotKey(0x1fab64ea29a2ddf7),
+        PolyglotKey(0xe4d9429322cd065a),
+        PolyglotKey(0x9da058c67844f20c),
+        PolyglotKey(0x24c0e332b70019b0),
+        PolyglotKey(0x233003b5a6cfe6ad),
+        PolyglotKey(0xd586bd01c5c217f6),
+        PolyglotKey(0x5e5637885f29bc2b),
+        PolyglotKey(0x7eba726d8c94094b),
+        PolyglotKey(0x0a56a5f0bfe39272),
+        PolyglotKey(0xd79476a84ee20d06),
+        PolyglotKey(0x9e4c1269baa4bf37),
+        PolyglotKey(0x17efee45b0dee640),
+        PolyglotKey(0x1d95b0a5fcf90bc6),
+        PolyglotKey(0x93cbe0b699c2585d),
+        PolyglotKey(0x65fa4f227a2b6d79),
+        PolyglotKey(0xd5f9e858292504d5),
+        PolyglotKey(0xc2b5a03f71471a6f),
+        PolyglotKey(0x59300222b4561e00),
+        PolyglotKey(0xce2f8642ca0712dc),
+        PolyglotKey(0x7ca9723fbb2e8988),
+        PolyglotKey(0x2785338347f2ba08),
+        PolyglotKey(0xc61bb3a141e50e8c),
+        PolyglotKey(0x150f361dab9dec26),
+        PolyglotKey(0x9f6a419d382595f4),
+        PolyglotKey(0x64a53dc924fe7ac9),
+        PolyglotKey(0x142de49fff7a7c3d),
+        PolyglotKey(0x0c335248857fa9e7),
+        PolyglotKey(0x0a9c32d5eae45305),
+        PolyglotKey(0xe6c42178c4bbb92e),
+        PolyglotKey(0x71f1ce2490d20b07),
+        PolyglotKey(0xf1bcc3d275afe51a),
+        PolyglotKey(0xe728e8c83c334074),
+        PolyglotKey(0x96fbf83a12884624),
+        PolyglotKey(0x81a1549fd6573da5),
+        PolyglotKey(0x5fa7867caf35e149),
+        PolyglotKey(0x56986e2ef3ed091b),
+        PolyglotKey(0x917f1dd5f8886c61),
+        PolyglotKey(0xd20d8c88c8ffe65f),
+        PolyglotKey(0x31d71dce64b2c310),
+        PolyglotKey(0xf165b587df898190),
+        PolyglotKey(0xa57e6339dd2cf3a0),
+        PolyglotKey(0x1ef6e6dbb1961ec9),
+        PolyglotKey(0x70cc73d90bc26e24),
+        PolyglotKey(0xe21a6b35df0c3ad7),
+        PolyglotKey(0x003a93d8b2806962),
+        PolyglotKey(0x1c99ded33cb890a1),
+        PolyglotKey(0xcf3145de0add4289),
+        PolyglotKey(0xd0e4427a5514fb72),
+        PolyglotKey(0x77c621cc9fb3a483),
+        PolyglotKey(0x67a34dac4356550b),
+        PolyglotKey(0xf8d626aaaf278509),
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::Fen;
+
+    #[test]
+    fn reference_keys() {
+        // Test positions from the Polyglot book format specification
+        for (fen, key) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b96181691fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c9b50fd114196,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                0x0756b94461c50fb0,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+                0x662fafb965db29d4,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a48b5a8e47ff78,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+                0x652a607ca3f242c1,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fdd303c946bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c8123ea7b067637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f9b829b279560,
+            ),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert_eq!(key, u64::from(PolyglotKey::new(&pos)), "{fen}");
+        }
+    }
+}
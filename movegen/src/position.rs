@@ -7,6 +7,7 @@ use crate::knight::Knight;
 use crate::pawn::Pawn;
 use crate::piece;
 use crate::piece::Piece;
+use crate::piece_square_table::{self, PieceSquareScore};
 use crate::queen::Queen;
 use crate::rank::Rank;
 use crate::rook::Rook;
@@ -40,6 +41,9 @@ pub struct Position {
     castling_squares: CastlingSquares,
     plies_since_pawn_move_or_capture: usize,
     move_count: usize,
+    // Material and piece-square scores of both sides, updated with every
+    // piece that is set or removed
+    piece_square_scores: [PieceSquareScore; 2],
 }
 
 impl Position {
@@ -56,6 +60,7 @@ impl Position {
             castling_squares: CastlingSquares::new(File::A, File::E, File::H),
             plies_since_pawn_move_or_capture: 0,
             move_count: 1,
+            piece_square_scores: [PieceSquareScore(0, 0); 2],
         }
     }
 
@@ -72,6 +77,7 @@ impl Position {
             castling_squares: CastlingSquares::new(File::A, File::E, File::H),
             plies_since_pawn_move_or_capture: 0,
             move_count: 1,
+            piece_square_scores: [PieceSquareScore(0, 0); 2],
         };
 
         pos.piece_side_occupancies[Side::White as usize] = Bitboard::RANK_1 | Bitboard::RANK_2;
@@ -86,6 +92,7 @@ impl Position {
             Bitboard::A1 | Bitboard::H1 | Bitboard::A8 | Bitboard::H8;
         pos.piece_type_occupancies[piece::Type::Queen as usize] = Bitboard::D1 | Bitboard::D8;
         pos.piece_type_occupancies[piece::Type::King as usize] = Bitboard::E1 | Bitboard::E8;
+        pos.piece_square_scores = piece_square_table::scores(&pos);
 
         pos
     }
@@ -223,6 +230,14 @@ impl Position {
 
     pub fn set_piece_at(&mut self, square: Square, piece: Option<Piece>) {
         let square_bit = Bitboard::from_square(square);
+        if let Some(p) = self.piece_at(square) {
+            self.piece_square_scores[p.piece_side() as usize] -=
+                piece_square_table::score(p, square);
+        }
+        if let Some(p) = piece {
+            self.piece_square_scores[p.piece_side() as usize] +=
+                piece_square_table::score(p, square);
+        }
 
         for pso in &mut self.piece_side_occupancies {
             *pso &= !square_bit;
@@ -237,6 +252,14 @@ impl Position {
         }
     }
 
+    pub fn piece_square_score(&self, side: Side) -> PieceSquareScore {
+        self.piece_square_scores[side as usize]
+    }
+
+    pub fn piece_square_scores(&self) -> [PieceSquareScore; 2] {
+        self.piece_square_scores
+    }
+
     pub fn occupancy(&self) -> Bitboard {
         self.piece_side_occupancies[Side::White as usize]
             | self.piece_side_occupancies[Side::Black as usize]
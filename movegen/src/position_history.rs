@@ -3,6 +3,7 @@ use crate::fen::Fen;
 use crate::move_generator::MoveGenerator;
 use crate::pawn::Pawn;
 use crate::piece::{self, Piece};
+use crate::piece_square_table;
 use crate::position::{CastlingRights, Position};
 use crate::r#move::{Move, MoveType};
 use crate::rank::Rank;
@@ -39,6 +40,10 @@ impl IrreversibleProperties {
 pub struct PositionHistory {
     pos: Position,
     pos_hash: Zobrist,
+    // Updated on every move that changes the pawns
+    pawn_hash: Zobrist,
+    // Hash of the position the history was created with
+    start_pos_hash: Zobrist,
     irreversible_props: Vec<IrreversibleProperties>,
     moves: Vec<Move>,
     rep_tracker: RepetitionTracker<Zobrist>,
@@ -47,9 +52,12 @@ pub struct PositionHistory {
 impl PositionHistory {
     pub fn new(pos: Position) -> Self {
         let pos_hash = Zobrist::new(&pos);
+        let pawn_hash = Zobrist::pawns(&pos);
         let rep_tracker = RepetitionTracker::new();
         let mut pos_hist = Self {
             pos_hash,
+            pawn_hash,
+            start_pos_hash: pos_hash,
             pos,
             irreversible_props: Vec::<IrreversibleProperties>::new(),
             moves: Vec::<Move>::new(),
@@ -57,6 +65,7 @@ impl PositionHistory {
         };
         let en_passant_square = pos_hist.current_pos().en_passant_square();
         pos_hist.clear_en_passant_square_if_irrelevant(en_passant_square);
+        pos_hist.start_pos_hash = pos_hist.current_pos_hash();
         pos_hist
             .rep_tracker
             .push(pos_hist.current_pos_hash(), false);
@@ -71,6 +80,18 @@ impl PositionHistory {
         self.pos_hash
     }
 
+    pub fn current_pawn_hash(&self) -> Zobrist {
+        self.pawn_hash
+    }
+
+    pub fn start_pos_hash(&self) -> Zobrist {
+        self.start_pos_hash
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
     pub fn last_move(&self) -> Option<&Move> {
         self.moves.last()
     }
@@ -109,6 +130,11 @@ impl PositionHistory {
         }
         debug_assert_eq!(self.irreversible_props.len(), self.moves.len());
         debug_assert_eq!(Zobrist::new(self.current_pos()), self.current_pos_hash());
+        debug_assert_eq!(Zobrist::pawns(self.current_pos()), self.current_pawn_hash());
+        debug_assert_eq!(
+            piece_square_table::scores(self.current_pos()),
+            self.current_pos().piece_square_scores()
+        );
     }
 
     pub fn undo_last_move(&mut self) {
@@ -125,6 +151,10 @@ impl PositionHistory {
         self.rep_tracker.current_pos_repetitions()
     }
 
+    pub fn plies_since_last_occurrence(&self) -> Option<usize> {
+        self.rep_tracker.plies_since_last_occurrence()
+    }
+
     pub fn gives_check(&mut self, m: Move) -> bool {
         self.do_move(m);
         let pos = self.current_pos();
@@ -444,6 +474,11 @@ impl PositionHistory {
             _ => self.undo_other_move(m, irr),
         }
         debug_assert_eq!(Zobrist::new(self.current_pos()), self.current_pos_hash());
+        debug_assert_eq!(Zobrist::pawns(self.current_pos()), self.current_pawn_hash());
+        debug_assert_eq!(
+            piece_square_table::scores(self.current_pos()),
+            self.current_pos().piece_square_scores()
+        );
     }
 
     fn undo_null_move(&mut self, irr: &IrreversibleProperties) {
@@ -564,13 +599,20 @@ impl PositionHistory {
     fn set_piece(&mut self, square: Square, piece: piece::Piece) {
         debug_assert_eq!(None, self.pos.piece_at(square));
         self.pos.set_piece_at(square, Some(piece));
-        self.pos_hash.toggle_piece(Some(piece), square);
+        self.toggle_piece_hashes(square, piece);
     }
 
     fn remove_piece(&mut self, square: Square, piece: piece::Piece) {
         debug_assert_eq!(Some(piece), self.pos.piece_at(square));
         self.pos.set_piece_at(square, None);
+        self.toggle_piece_hashes(square, piece);
+    }
+
+    fn toggle_piece_hashes(&mut self, square: Square, piece: piece::Piece) {
         self.pos_hash.toggle_piece(Some(piece), square);
+        if piece.piece_type() == piece::Type::Pawn {
+            self.pawn_hash.toggle_piece(Some(piece), square);
+        }
     }
 }
 
@@ -1465,6 +1507,7 @@ mod tests {
         assert_eq!(1, pos_history.current_pos_repetitions());
         pos_history.do_move(Move::new(Square::F6, Square::G8, MoveType::QUIET));
         assert_eq!(2, pos_history.current_pos_repetitions());
+        assert_eq!(Some(4), pos_history.plies_since_last_occurrence());
         pos_history.do_move(Move::NULL);
         assert_eq!(1, pos_history.current_pos_repetitions());
         pos_history.do_move(Move::NULL);
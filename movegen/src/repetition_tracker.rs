@@ -37,6 +37,21 @@ where
             .count()
     }
 
+    // Number of plies since the current position occurred the last time (None
+    // if it didn't occur before)
+    pub fn plies_since_last_occurrence(&self) -> Option<usize> {
+        let hash = self.history.last()?;
+        self.history
+            .iter()
+            .rev()
+            .take(self.plies_since_last_irreversible.last().unwrap_or(&0) + 1)
+            .enumerate()
+            .skip(2)
+            .step_by(2)
+            .find(|&(_, x)| x == hash)
+            .map(|(plies, _)| plies)
+    }
+
     pub fn push(&mut self, hash: K, is_reversible: bool) {
         self.history.push(hash);
         self.plies_since_last_irreversible
@@ -97,4 +112,35 @@ mod tests {
         assert_eq!(1, rep_tracker.current_pos_repetitions());
         rep_tracker.pop();
     }
+
+    #[test]
+    fn plies_since_last_occurrence() {
+        let key_0 = "0";
+        let key_1 = "1";
+        let key_2 = "2";
+        let key_irr = "irr";
+        let mut rep_tracker = RepetitionTracker::new();
+        assert_eq!(None, rep_tracker.plies_since_last_occurrence());
+
+        rep_tracker.push(key_0, true);
+        assert_eq!(None, rep_tracker.plies_since_last_occurrence());
+        rep_tracker.push(key_1, true);
+        assert_eq!(None, rep_tracker.plies_since_last_occurrence());
+        rep_tracker.push(key_2, true);
+        assert_eq!(None, rep_tracker.plies_since_last_occurrence());
+        rep_tracker.push(key_1, true);
+        assert_eq!(Some(2), rep_tracker.plies_since_last_occurrence());
+        rep_tracker.push(key_0, true);
+        assert_eq!(Some(4), rep_tracker.plies_since_last_occurrence());
+        rep_tracker.push(key_1, true);
+        assert_eq!(Some(2), rep_tracker.plies_since_last_occurrence());
+        rep_tracker.push(key_irr, false);
+        assert_eq!(None, rep_tracker.plies_since_last_occurrence());
+        rep_tracker.push(key_1, true);
+        assert_eq!(None, rep_tracker.plies_since_last_occurrence());
+
+        rep_tracker.pop();
+        rep_tracker.pop();
+        assert_eq!(Some(2), rep_tracker.plies_since_last_occurrence());
+    }
 }
@@ -174,13 +174,36 @@ impl Square {
         }
     }
 
+    // Chebyshev distance (the number of king moves between the squares)
     pub fn distance(self, other: Self) -> usize {
-        (self.file().idx() as i8 - other.file().idx() as i8)
-            .abs()
-            .max((self.rank().idx() as i8 - other.rank().idx() as i8).abs()) as usize
+        DISTANCE[self.idx()][other.idx()] as usize
     }
 }
 
+const fn chebyshev_distance(idx_1: usize, idx_2: usize) -> u8 {
+    let file_diff = (idx_1 >> 3).abs_diff(idx_2 >> 3);
+    let rank_diff = (idx_1 & 0x7).abs_diff(idx_2 & 0x7);
+    if file_diff > rank_diff {
+        file_diff as u8
+    } else {
+        rank_diff as u8
+    }
+}
+
+const DISTANCE: [[u8; Square::NUM_SQUARES]; Square::NUM_SQUARES] = {
+    let mut table = [[0; Square::NUM_SQUARES]; Square::NUM_SQUARES];
+    let mut idx_1 = 0;
+    while idx_1 < Square::NUM_SQUARES {
+        let mut idx_2 = 0;
+        while idx_2 < Square::NUM_SQUARES {
+            table[idx_1][idx_2] = chebyshev_distance(idx_1, idx_2);
+            idx_2 += 1;
+        }
+        idx_1 += 1;
+    }
+    table
+};
+
 impl fmt::Display for Square {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", str::from_utf8(&self.to_ascii()).unwrap())
@@ -367,6 +390,19 @@ mod tests {
         assert_eq!(7, Square::H8.distance(Square::H1));
         assert_eq!(7, Square::H8.distance(Square::A8));
         assert_eq!(7, Square::H8.distance(Square::A1));
+        assert_eq!(2, Square::C3.distance(Square::E4));
+        assert_eq!(3, Square::G2.distance(Square::D4));
+
+        for idx_1 in 0..Square::NUM_SQUARES {
+            for idx_2 in 0..Square::NUM_SQUARES {
+                let sq_1 = Square::from_idx(idx_1);
+                let sq_2 = Square::from_idx(idx_2);
+                let file_diff = sq_1.file().idx().abs_diff(sq_2.file().idx());
+                let rank_diff = sq_1.rank().idx().abs_diff(sq_2.rank().idx());
+                assert_eq!(file_diff.max(rank_diff), sq_1.distance(sq_2));
+                assert_eq!(sq_1.distance(sq_2), sq_2.distance(sq_1));
+            }
+        }
     }
 
     #[test]
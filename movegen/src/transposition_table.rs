@@ -12,7 +12,25 @@ pub trait TtEntry {
 
 pub const ENTRIES_PER_BUCKET: usize = 4;
 
-type Bucket<K, V> = [Option<(K, V)>; ENTRIES_PER_BUCKET];
+// Number of buckets sampled to estimate the table occupancy
+const HASHFULL_SAMPLE_BUCKETS: usize = 1000;
+
+pub const CACHE_LINE_SIZE: usize = 64;
+
+// All entries of a bucket are probed on every access. The buckets are
+// aligned to cache lines, so a bucket of small entries only touches a single
+// cache line.
+#[derive(Clone, Copy, Debug)]
+#[repr(align(64))]
+struct Bucket<K, V>([Option<(K, V)>; ENTRIES_PER_BUCKET]);
+
+impl<K: Copy, V: Copy> Bucket<K, V> {
+    const EMPTY: Self = Self([None; ENTRIES_PER_BUCKET]);
+
+    fn occupied(&self) -> usize {
+        self.0.iter().filter(|x| x.is_some()).count()
+    }
+}
 
 #[derive(Debug)]
 pub struct TranspositionTable<K, V> {
@@ -29,7 +47,7 @@ where
 {
     pub fn new(bytes: usize) -> TranspositionTable<K, V> {
         debug_assert!(bytes <= u64::MAX as usize);
-        let bucket_size = mem::size_of::<Option<(K, V)>>() * ENTRIES_PER_BUCKET;
+        let bucket_size = Self::bucket_size();
         // Reserve memory for at least 2 buckets, so that at least one index bit
         // is used (even if bytes is 0)
         let max_num_buckets = cmp::max(2, bytes / bucket_size);
@@ -38,7 +56,7 @@ where
         debug_assert!(index_bits <= 64);
         TranspositionTable {
             index_bits: index_bits as usize,
-            buckets: vec![[None; ENTRIES_PER_BUCKET]; 2_usize.pow(index_bits)].into_boxed_slice(),
+            buckets: vec![Bucket::EMPTY; 2_usize.pow(index_bits)].into_boxed_slice(),
             len: 0,
         }
     }
@@ -46,10 +64,7 @@ where
     pub fn len(&self) -> usize {
         debug_assert_eq!(
             self.len,
-            self.buckets
-                .iter()
-                .map(|b| b.iter().filter(|x| x.is_some()).count())
-                .sum()
+            self.buckets.iter().map(Bucket::occupied).sum::<usize>()
         );
         self.len
     }
@@ -57,7 +72,7 @@ where
     pub fn is_empty(&self) -> bool {
         debug_assert_eq!(
             self.len == 0,
-            self.buckets.iter().all(|b| b.iter().all(|x| x.is_none()))
+            self.buckets.iter().all(|b| b.occupied() == 0)
         );
         self.len == 0
     }
@@ -67,7 +82,7 @@ where
     }
 
     pub fn clear(&mut self) {
-        self.buckets.fill([None; ENTRIES_PER_BUCKET]);
+        self.buckets.fill(Bucket::EMPTY);
         self.len = 0;
     }
 
@@ -76,9 +91,18 @@ where
         (1000 * self.len() / self.capacity()) as u16
     }
 
+    // Estimate the occupancy in permille by sampling the first buckets. This
+    // is what gets reported as `hashfull`. It is cheap enough to be called
+    // after every iteration.
+    pub fn hashfull_permille(&self) -> u16 {
+        let sample = &self.buckets[..cmp::min(HASHFULL_SAMPLE_BUCKETS, self.buckets.len())];
+        let occupied: usize = sample.iter().map(Bucket::occupied).sum();
+        (1000 * occupied / (sample.len() * ENTRIES_PER_BUCKET)) as u16
+    }
+
     pub fn contains_key(&self, k: &K) -> bool {
         let bucket_idx = self.key_to_index(k);
-        for entry in self.buckets[bucket_idx] {
+        for entry in self.buckets[bucket_idx].0 {
             match entry {
                 Some(e) if e.0 == *k => return true,
                 _ => {}
@@ -90,7 +114,7 @@ where
     // Return entry with matching key
     pub fn get(&self, k: &K) -> Option<&V> {
         let bucket_idx = self.key_to_index(k);
-        for entry in &self.buckets[bucket_idx] {
+        for entry in &self.buckets[bucket_idx].0 {
             match entry {
                 Some(ref e) if e.0 == *k => return Some(&e.1),
                 _ => {}
@@ -115,7 +139,7 @@ where
         let mut replaced_idx = None;
         let mut replaced = None;
         let mut insert_value = value;
-        let bucket = &mut self.buckets[bucket_idx];
+        let bucket = &mut self.buckets[bucket_idx].0;
         for (i, entry) in bucket.iter_mut().enumerate() {
             match entry {
                 Some(ent) => {
@@ -157,7 +181,7 @@ where
         }
         match replaced_idx {
             Some(rep_idx) => {
-                self.buckets[bucket_idx][rep_idx] = Some((k, insert_value));
+                self.buckets[bucket_idx].0[rep_idx] = Some((k, insert_value));
                 self.len += replaced.is_none() as usize;
                 replaced
             }
@@ -169,6 +193,12 @@ where
         mem::size_of_val(&*self.buckets)
     }
 
+    // Entries larger than a quarter cache line make a bucket span multiple
+    // cache lines
+    pub const fn bucket_size() -> usize {
+        mem::size_of::<Bucket<K, V>>()
+    }
+
     fn key_to_index(&self, k: &K) -> usize {
         (u64::from(*k) >> (64 - self.index_bits)) as usize
     }
@@ -207,7 +237,8 @@ mod tests {
 
     #[test]
     fn new() {
-        let bucket_size = mem::size_of::<Option<(u64, u64)>>() * ENTRIES_PER_BUCKET;
+        let bucket_size = TranspositionTable::<u64, u64>::bucket_size();
+        assert_eq!(0, bucket_size % CACHE_LINE_SIZE);
 
         // Always reserve memory for at least two buckets
         let tt = TranspositionTable::<u64, u64>::new(0);
@@ -233,8 +264,8 @@ mod tests {
     #[test]
     fn insert_and_replace_and_clear() {
         let capacity = 8 * ENTRIES_PER_BUCKET;
-        let entry_size = mem::size_of::<Option<(u64, u64)>>();
-        let mut tt = TranspositionTable::<u64, u64>::new(capacity * entry_size);
+        let bucket_size = TranspositionTable::<u64, u64>::bucket_size();
+        let mut tt = TranspositionTable::<u64, u64>::new(8 * bucket_size);
 
         assert!(!tt.contains_key(&0));
         assert_eq!(None, tt.get(&0));
@@ -244,6 +275,7 @@ mod tests {
         assert!(tt.is_empty());
         assert_eq!(capacity, tt.capacity());
         assert_eq!(0, tt.load_factor_permille());
+        assert_eq!(0, tt.hashfull_permille());
 
         for i in 0..ENTRIES_PER_BUCKET {
             let num = i as u64;
@@ -264,6 +296,7 @@ mod tests {
             (1000 * tt.len() / tt.capacity()) as u16,
             tt.load_factor_permille()
         );
+        assert_eq!(tt.load_factor_permille(), tt.hashfull_permille());
 
         let inserted = ENTRIES_PER_BUCKET as u64;
         assert!(!tt.contains_key(&inserted));
@@ -294,6 +327,7 @@ mod tests {
             (1000 * tt.len() / tt.capacity()) as u16,
             tt.load_factor_permille()
         );
+        assert_eq!(tt.load_factor_permille(), tt.hashfull_permille());
 
         tt.clear();
         assert!(!tt.contains_key(&0));
@@ -306,6 +340,7 @@ mod tests {
         assert!(tt.is_empty());
         assert_eq!(capacity, tt.capacity());
         assert_eq!(0, tt.load_factor_permille());
+        assert_eq!(0, tt.hashfull_permille());
     }
 
     #[test]
@@ -348,9 +383,8 @@ mod tests {
 
     #[test]
     fn replace_correct_entry() {
-        let capacity = 8 * ENTRIES_PER_BUCKET;
-        let entry_size = mem::size_of::<Option<(u64, u64)>>();
-        let mut tt = TranspositionTable::<u64, u64>::new(capacity * entry_size);
+        let bucket_size = TranspositionTable::<u64, u64>::bucket_size();
+        let mut tt = TranspositionTable::<u64, u64>::new(8 * bucket_size);
         assert!(tt.is_empty());
 
         let replaced = tt.insert(0, 0);
@@ -367,4 +401,21 @@ mod tests {
         assert_eq!(Some((0, 1)), replaced);
         assert_eq!(2, tt.len());
     }
+
+    #[test]
+    fn hashfull_is_sampled() {
+        let bucket_size = TranspositionTable::<u64, u64>::bucket_size();
+        let mut tt = TranspositionTable::<u64, u64>::new(4 * HASHFULL_SAMPLE_BUCKETS * bucket_size);
+        assert_eq!(0, tt.hashfull_permille());
+
+        // Fill all buckets in the sampled region, none outside of it
+        for i in 0..HASHFULL_SAMPLE_BUCKETS as u64 {
+            let _ = tt.insert(i << (64 - tt.index_bits), i);
+        }
+        assert_eq!(250, tt.hashfull_permille());
+        assert!(tt.load_factor_permille() < tt.hashfull_permille());
+
+        tt.clear();
+        assert_eq!(0, tt.hashfull_permille());
+    }
 }
@@ -50,6 +50,19 @@ impl Zobrist {
         zobrist
     }
 
+    // Key of the pawns only, e.g. for a pawn hash table
+    pub fn pawns(pos: &Position) -> Self {
+        let mut zobrist = Self(0);
+        for side in [Side::White, Side::Black] {
+            let mut squares = pos.piece_occupancy(side, piece::Type::Pawn);
+            while squares != Bitboard::EMPTY {
+                let square = squares.square_scan_forward_reset();
+                zobrist.toggle_piece(Some(piece::Piece::new(side, piece::Type::Pawn)), square)
+            }
+        }
+        zobrist
+    }
+
     pub fn toggle_piece(&mut self, piece: Option<piece::Piece>, square: Square) {
         if let Some(p) = piece {
             let side_idx = p.piece_side() as usize * IDX_FIRST_BLACK_PIECE;
@@ -934,6 +947,7 @@ impl Zobrist {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fen::Fen;
     use crate::move_generator::MoveGenerator;
     use crate::position_history::PositionHistory;
     use crate::r#move::{Move, MoveList, MoveType};
@@ -1011,4 +1025,38 @@ mod tests {
         let hash = Zobrist::new(pos_history.current_pos());
         assert_eq!(hash, pos_history.current_pos_hash());
     }
+
+    #[test]
+    fn incremental_pawn_hash_values() {
+        // Pawn pushes, captures, en passant captures and promotions
+        let fen = "r3k2r/1P3ppp/8/3pP3/8/8/6PP/R3K2R w KQkq d6 0 1";
+        let mut pos_history = PositionHistory::new(Fen::str_to_pos(fen).unwrap());
+        let initial_pawn_hash = pos_history.current_pawn_hash();
+        assert_eq!(
+            Zobrist::pawns(pos_history.current_pos()),
+            pos_history.current_pawn_hash()
+        );
+
+        let mut move_list = MoveList::new();
+        MoveGenerator::generate_moves(&mut move_list, pos_history.current_pos());
+        for m in move_list.iter() {
+            let pawns_before = pos_history
+                .current_pos()
+                .piece_type_occupancy(piece::Type::Pawn);
+            pos_history.do_move(*m);
+            assert_eq!(
+                Zobrist::pawns(pos_history.current_pos()),
+                pos_history.current_pawn_hash()
+            );
+            let pawns_after = pos_history
+                .current_pos()
+                .piece_type_occupancy(piece::Type::Pawn);
+            assert_eq!(
+                pawns_before == pawns_after,
+                initial_pawn_hash == pos_history.current_pawn_hash()
+            );
+            pos_history.undo_last_move();
+            assert_eq!(initial_pawn_hash, pos_history.current_pawn_hash());
+        }
+    }
 }
@@ -6,6 +6,10 @@ use movegen::r#move::MoveList;
 use movegen::transposition_table::{TranspositionTable, TtEntry};
 use movegen::zobrist::Zobrist;
 
+// The table size of the perft command. The table is allocated in addition to
+// the engine's hash table, so it is kept small.
+pub const PERFT_HASH_BYTES: usize = 16 * 1024 * 1024;
+
 const AGE: u8 = 0;
 
 #[derive(Clone, Copy, Debug)]
@@ -7,6 +7,7 @@ use movegen::position::Position;
 use movegen::position_history::PositionHistory;
 use search::alpha_beta::AlphaBeta;
 use search::search::{Search, SearchInfo, SearchResult};
+use search::search_params::{AlphaBetaParams, SearchParamsEachAlgo};
 use search::searcher::Searcher;
 use search::SearchOptions;
 use std::time::Duration;
@@ -44,6 +45,10 @@ impl SearchBencher {
         loop {
             let search_result = match self.result_receiver.recv_timeout(TIMEOUT_PER_BENCH) {
                 Ok(SearchInfo::DepthFinished(res)) => res,
+                Ok(SearchInfo::AspirationFail(_)) => continue,
+                Ok(SearchInfo::CurrentMove(_)) => continue,
+                Ok(SearchInfo::Progress(_)) => continue,
+                Ok(SearchInfo::Trace(_)) => continue,
                 unexp => panic!("Expected SearchInfo::DepthFinished(_), got {:?}", unexp),
             };
             assert!(
@@ -109,9 +114,43 @@ fn alpha_beta_middlegame_position(c: &mut Criterion) {
     alpha_beta(c, group_name, pos, min_depth, max_depth);
 }
 
+// Time to depth with 4 threads, with thread-local and with shared history
+// and counter move tables
+fn alpha_beta_shared_history(c: &mut Criterion) {
+    let table_size = 16 * 1024 * 1024;
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let pos_history = PositionHistory::new(Fen::str_to_pos(fen).unwrap());
+    let depth = 8;
+
+    let mut group = c.benchmark_group("Alpha-Beta 4 threads middlegame position");
+    for shared in [false, true] {
+        group.bench_with_input(
+            BenchmarkId::new("shared history", shared),
+            &shared,
+            |b, &shared| {
+                b.iter_batched(
+                    || {
+                        let mut alpha_beta = AlphaBeta::new(Box::new(evaluator()), table_size);
+                        alpha_beta.set_threads(4);
+                        alpha_beta.set_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+                            shared_history: Some(shared),
+                            ..Default::default()
+                        }));
+                        SearchBencher::new(alpha_beta)
+                    },
+                    |mut searcher| searcher.search(pos_history.clone(), depth),
+                    BatchSize::SmallInput,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     alpha_beta_initial_position,
     alpha_beta_middlegame_position,
+    alpha_beta_shared_history,
 );
 criterion_main!(benches);
@@ -1,29 +1,41 @@
 use crate::alpha_beta_entry::{AlphaBetaEntry, ScoreType};
-use crate::aspiration_window::{AspirationWindow, GROW_RATE, INITIAL_WIDTH};
+use crate::aspiration_window::{AspirationWindow, ScoreVolatility, GROW_RATE, INITIAL_WIDTH};
+use crate::correction_history::CorrectionHistory;
 use crate::counter_table::CounterTable;
+use crate::eval_cache::{EvalCache, EVAL_CACHE_SIZE};
 use crate::history_table::HistoryTable;
 use crate::move_selector::{MoveSelector, Stage};
+use crate::pv_table;
 use crate::search::{
-    Search, SearchCommand, SearchInfo, SearchResult, MAX_SEARCH_DEPTH,
-    PLIES_WITHOUT_PAWN_MOVE_OR_CAPTURE_TO_DRAW, REPETITIONS_TO_DRAW,
+    CurrentMove, ScoreBound, Search, SearchCommand, SearchInfo, SearchProgress, SearchResult,
+    MAX_PLY, PLIES_WITHOUT_PAWN_MOVE_OR_CAPTURE_TO_DRAW, REPETITIONS_TO_DRAW,
 };
-use crate::search_data::SearchData;
+use crate::search_data::{DeferredCommand, SearchData, FRACTIONS_PER_EXTENSION, MAX_EXTENSIONS};
 use crate::search_params::SearchParamsEachAlgo;
-use crate::time_manager::TimeManager;
+use crate::search_tracer::{SearchTracer, TableProbe};
+use crate::shared_table::SharedTable;
+use crate::strength::{self, EQUAL_MOVES_SCORE_MARGIN};
+use crate::time_manager::{
+    TimeLimits, TimeManager, BEST_MOVE_NODES_MIN_DEPTH, SCORE_DROP_EXTENSION_PERCENT,
+    SCORE_DROP_THRESHOLD,
+};
 use crate::{static_exchange_eval as see, SearchOptions};
 use crossbeam_channel::{Receiver, Sender};
 use eval::score::is_valid;
-use eval::{Eval, Score, BLACK_WIN, EQ_POSITION, NEG_INF, POS_INF, WHITE_WIN};
+use eval::{Eval, GamePhase, Score, BLACK_WIN, NEG_INF, POS_INF, WHITE_WIN};
 use movegen::move_generator::MoveGenerator;
 use movegen::position_history::PositionHistory;
 use movegen::r#move::{Move, MoveList};
 use movegen::side::Side;
-use movegen::transposition_table::{TranspositionTable, TtEntry};
-use movegen::zobrist::Zobrist;
-use std::cmp;
-use std::time::Instant;
+use movegen::transposition_table::TtEntry;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub type AlphaBetaTable = TranspositionTable<Zobrist, AlphaBetaEntry>;
+// Only report the current root move after searching for this long, to avoid
+// flooding the output at fast time controls.
+const CURRENT_MOVE_INFO_MIN_TIME: Duration = Duration::from_secs(3);
 
 // Minimum depth for principal variation search. Disable null-window searches below this depth.
 const MIN_PVS_DEPTH: usize = 3;
@@ -31,23 +43,82 @@ const MIN_PVS_DEPTH: usize = 3;
 // Minimum depth for null move pruning.
 const MIN_NULL_MOVE_PRUNE_DEPTH: usize = 3;
 
+// Null move pruning is disabled if the side to move only has pawns, because
+// zugzwang is likely. At high depths, a null move cutoff is verified by a
+// reduced depth search without null moves.
+pub const NULL_MOVE_ZUGZWANG_GUARD: bool = true;
+pub const NULL_MOVE_VERIFICATION_MIN_DEPTH: usize = 8;
+
 // Minimum depth for late move reductions.
 const MIN_LATE_MOVE_REDUCTION_DEPTH: usize = 3;
 
+// Adjustments to the base late move reduction (in plies). Moves are reduced
+// more if the static evaluation isn't improving or at expected cut nodes
+// without a hash move and less if they are killers or counter moves. The
+// history value divided by the divisor is subtracted. PV nodes aren't
+// reduced.
+pub const LATE_MOVE_REDUCTION_NOT_IMPROVING: i32 = 1;
+pub const LATE_MOVE_REDUCTION_CUT_NODE: i32 = 1;
+pub const LATE_MOVE_REDUCTION_KILLER_OR_COUNTER: i32 = 1;
+pub const LATE_MOVE_REDUCTION_HISTORY_DIVISOR: i32 = 16384;
+
+// Internal iterative deepening: at PV nodes without a hash move, search at
+// reduced depth first to get a good move for the move ordering.
+const MIN_IID_DEPTH: usize = 5;
+const IID_DEPTH_REDUCTION: usize = 2;
+
+// Internal iterative reduction: reduce the depth by one ply at non-PV nodes
+// without a hash move
+pub const INTERNAL_ITERATIVE_REDUCTION: bool = true;
+const MIN_IIR_DEPTH: usize = 4;
+
+// Singular extensions. The hash move is extended if all other moves score
+// below the hash table score minus the margin, searched at about half depth.
+pub const SINGULAR_EXTENSION_MIN_DEPTH: usize = 6;
+pub const SINGULAR_EXTENSION_MAX_TABLE_DEPTH_DIFF: usize = 3;
+pub const SINGULAR_EXTENSION_MARGIN_PER_DEPTH: Score = 3;
+
 // Enable futility pruning if the evaluation plus this value is less than alpha.
 pub const FUTILITY_MARGIN_BASE: Score = 12;
 pub const FUTILITY_MARGIN_PER_DEPTH: Score = 235;
 pub const FUTILITY_PRUNING_MAX_DEPTH: usize = 5;
+// Added to the futility margin if the static evaluation is improving
+pub const FUTILITY_MARGIN_IMPROVING: Score = 50;
 
 // Enable reverse futility pruning if the evaluation plus this value is greater than or equal to beta.
 pub const REVERSE_FUTILITY_MARGIN_BASE: Score = 115;
 pub const REVERSE_FUTILITY_MARGIN_PER_DEPTH: Score = 51;
 pub const REVERSE_FUTILITY_PRUNING_MAX_DEPTH: usize = 6;
+// Subtracted from the reverse futility margin if the static evaluation is
+// improving
+pub const REVERSE_FUTILITY_MARGIN_IMPROVING: Score = 50;
+
+// Razoring: only do a quiescence search if the evaluation plus this value is
+// less than alpha.
+pub const RAZORING_MARGIN_BASE: Score = 250;
+pub const RAZORING_MARGIN_PER_DEPTH: Score = 200;
+pub const RAZORING_MAX_DEPTH: usize = 2;
+
+// ProbCut: prune if a capture beats beta plus the margin in a search with
+// the depth reduced by this value.
+pub const PROBCUT_MARGIN: Score = 150;
+pub const PROBCUT_DEPTH_REDUCTION: usize = 3;
+pub const PROBCUT_MIN_DEPTH: usize = 5;
+
+// Multicut: prune if enough of the first moves beat beta plus the margin in a
+// search with the depth reduced by this value.
+pub const MULTICUT_MARGIN: Score = 25;
+pub const MULTICUT_DEPTH_REDUCTION: usize = 4;
+pub const MULTICUT_MIN_DEPTH: usize = 8;
+pub const MULTICUT_MOVES: usize = 6;
+pub const MULTICUT_REQUIRED_CUTOFFS: usize = 3;
 
 // Late move pruning
 pub const LATE_MOVE_PRUNING_BASE: usize = 4;
 pub const LATE_MOVE_PRUNING_FACTOR: usize = 1;
 pub const LATE_MOVE_PRUNING_MAX_DEPTH: usize = 5;
+// Percentage of the late move count if the static evaluation isn't improving
+pub const LATE_MOVE_PRUNING_NOT_IMPROVING_PERCENT: usize = 50;
 
 // Prune a move if the static evaluation plus the move's potential improvement
 // plus this value is less than alpha.
@@ -56,35 +127,108 @@ const DELTA_PRUNING_MARGIN_MOVE: Score = 200;
 // Prune all moves if the static evaluation plus this value is less than alpha.
 const DELTA_PRUNING_MARGIN_ALL_MOVES: Score = 1800;
 
-// Static exchange evaluation pruning
+// Losing captures at low depth are pruned if their capture history is below
+// this margin times the depth
+const CAPTURE_HISTORY_PRUNING_MARGIN_PER_DEPTH: i32 = -2048;
+const CAPTURE_HISTORY_PRUNING_MAX_DEPTH: usize = 3;
+
+// The static evaluation is scaled towards zero when a draw by the fifty-move
+// rule is getting close. The scaling starts after this many plies without a
+// pawn move or capture. Each further ply takes 1/FIFTY_MOVE_SCALING_DIVISOR
+// of the evaluation away, so at the draw, 70% of it are left.
+const FIFTY_MOVE_SCALING_MIN_PLIES: usize = 40;
+const FIFTY_MOVE_SCALING_DIVISOR: i32 = 200;
+
+// Quiet checks are searched in this many plies at the start of the quiescence
+// search. Check evasions are always searched exhaustively.
+const QUIESCENCE_CHECK_PLIES: usize = 1;
+
+// Dynamic contempt: the contempt is scaled down linearly with the game phase
+// of the root position and with the hash table score of the root position
+// from the previous search. If the score is this much below the draw score
+// (relative to the side to move at the root), there is no contempt. A draw is
+// welcome when losing.
+pub const CONTEMPT_LOSING_SCORE: Score = 200;
+// Percentage of the contempt left without pieces. Endgames are drawn much
+// more often, and avoiding a draw there risks a loss more than in the
+// middlegame, so the contempt is smaller. But pawn endgames are still worth
+// playing on, so it doesn't go away entirely.
+pub const CONTEMPT_ENDGAME_PERCENT: i32 = 50;
+
+// Static exchange evaluation pruning. Tactical moves are pruned up to
+// SEE_PRUNING_MAX_DEPTH, quiet moves (which hang the moved piece) up to
+// SEE_PRUNING_MAX_DEPTH_QUIET.
 pub const SEE_PRUNING_MARGIN_QUIET: Score = -100;
 pub const SEE_PRUNING_MARGIN_TACTICAL: Score = -50;
 pub const SEE_PRUNING_MAX_DEPTH: usize = 4;
+pub const SEE_PRUNING_MAX_DEPTH_QUIET: usize = 6;
+
+// Share the history and counter move tables between the search threads,
+// instead of every thread learning the move ordering by itself. The threads
+// reach the same depth faster with shared tables.
+pub const SHARED_HISTORY: bool = true;
 
+#[derive(Clone)]
 struct SearchParams {
     futility_margin_base: Score,
     futility_margin_per_depth: Score,
     futility_pruning_max_depth: usize,
+    futility_margin_improving: Score,
     reverse_futility_margin_base: Score,
     reverse_futility_margin_per_depth: Score,
     reverse_futility_pruning_max_depth: usize,
+    reverse_futility_margin_improving: Score,
+    razoring_margin_base: Score,
+    razoring_margin_per_depth: Score,
+    razoring_max_depth: usize,
+    probcut_margin: Score,
+    probcut_depth_reduction: usize,
+    probcut_min_depth: usize,
+    multicut_margin: Score,
+    multicut_depth_reduction: usize,
+    multicut_min_depth: usize,
+    multicut_moves: usize,
+    multicut_required_cutoffs: usize,
     late_move_pruning_base: usize,
     late_move_pruning_factor: usize,
     late_move_pruning_max_depth: usize,
+    late_move_pruning_not_improving_percent: usize,
     see_pruning_margin_quiet: Score,
     see_pruning_margin_tactical: Score,
     see_pruning_max_depth: usize,
+    see_pruning_max_depth_quiet: usize,
+    null_move_zugzwang_guard: bool,
+    null_move_verification_min_depth: usize,
+    internal_iterative_reduction: bool,
+    singular_extension_min_depth: usize,
+    singular_extension_max_table_depth_diff: usize,
+    singular_extension_margin_per_depth: Score,
+    max_extensions: usize,
+    late_move_reduction_not_improving: i32,
+    late_move_reduction_cut_node: i32,
+    late_move_reduction_killer_or_counter: i32,
+    late_move_reduction_history_divisor: i32,
     aspiration_window_initial_width: i32,
     aspiration_window_grow_rate: i32,
+    score_drop_threshold: Score,
+    score_drop_extension_percent: i32,
+    contempt_losing_score: Score,
+    contempt_endgame_percent: i32,
+    shared_history: bool,
 }
 
-// Alpha-beta search with fail-hard cutoffs
+// Alpha-beta search with fail-hard cutoffs. The helpers are searching in
+// parallel (Lazy SMP). They only fill the shared transposition table, the
+// result of the search is the one of the main thread.
 pub struct AlphaBeta {
     evaluator: Box<dyn Eval + Send>,
-    transpos_table: AlphaBetaTable,
+    eval_cache: EvalCache,
+    transpos_table: Arc<SharedTable>,
     counter_table: CounterTable,
     history_table: HistoryTable,
+    correction_history: CorrectionHistory,
     search_params: SearchParams,
+    helpers: Vec<AlphaBeta>,
 }
 
 impl Search for AlphaBeta {
@@ -92,14 +236,28 @@ impl Search for AlphaBeta {
         debug_assert!(bytes <= u64::MAX as usize);
         // Clear the old table before creating a new one to avoid reserving
         // memory for two potentially large tables
-        self.transpos_table = AlphaBetaTable::new(0);
-        self.transpos_table = AlphaBetaTable::new(bytes);
+        self.transpos_table = Arc::new(SharedTable::new(0));
+        for helper in self.helpers.iter_mut() {
+            helper.transpos_table = Arc::clone(&self.transpos_table);
+        }
+        self.transpos_table = Arc::new(SharedTable::new(bytes));
+        for helper in self.helpers.iter_mut() {
+            helper.transpos_table = Arc::clone(&self.transpos_table);
+        }
     }
 
     fn clear_hash_table(&mut self) {
         self.transpos_table.clear();
+        self.eval_cache.clear();
         self.history_table.clear();
+        self.correction_history.clear();
         self.counter_table.clear();
+        for helper in self.helpers.iter_mut() {
+            helper.eval_cache.clear();
+            helper.history_table.clear();
+            helper.correction_history.clear();
+            helper.counter_table.clear();
+        }
     }
 
     fn set_params(&mut self, params: SearchParamsEachAlgo) {
@@ -113,6 +271,9 @@ impl Search for AlphaBeta {
         if let Some(fpmd) = abp.futility_pruning_max_depth {
             self.search_params.futility_pruning_max_depth = fpmd;
         }
+        if let Some(fmi) = abp.futility_margin_improving {
+            self.search_params.futility_margin_improving = fmi;
+        }
         if let Some(rfmb) = abp.reverse_futility_margin_base {
             self.search_params.reverse_futility_margin_base = rfmb;
         }
@@ -122,6 +283,42 @@ impl Search for AlphaBeta {
         if let Some(rfpmd) = abp.reverse_futility_pruning_max_depth {
             self.search_params.reverse_futility_pruning_max_depth = rfpmd;
         }
+        if let Some(rfmi) = abp.reverse_futility_margin_improving {
+            self.search_params.reverse_futility_margin_improving = rfmi;
+        }
+        if let Some(rmb) = abp.razoring_margin_base {
+            self.search_params.razoring_margin_base = rmb;
+        }
+        if let Some(rmpd) = abp.razoring_margin_per_depth {
+            self.search_params.razoring_margin_per_depth = rmpd;
+        }
+        if let Some(rmd) = abp.razoring_max_depth {
+            self.search_params.razoring_max_depth = rmd;
+        }
+        if let Some(pm) = abp.probcut_margin {
+            self.search_params.probcut_margin = pm;
+        }
+        if let Some(pdr) = abp.probcut_depth_reduction {
+            self.search_params.probcut_depth_reduction = pdr;
+        }
+        if let Some(pmd) = abp.probcut_min_depth {
+            self.search_params.probcut_min_depth = pmd;
+        }
+        if let Some(mm) = abp.multicut_margin {
+            self.search_params.multicut_margin = mm;
+        }
+        if let Some(mdr) = abp.multicut_depth_reduction {
+            self.search_params.multicut_depth_reduction = mdr;
+        }
+        if let Some(mmd) = abp.multicut_min_depth {
+            self.search_params.multicut_min_depth = mmd;
+        }
+        if let Some(mmo) = abp.multicut_moves {
+            self.search_params.multicut_moves = mmo;
+        }
+        if let Some(mrc) = abp.multicut_required_cutoffs {
+            self.search_params.multicut_required_cutoffs = mrc;
+        }
         if let Some(lmpb) = abp.late_move_pruning_base {
             self.search_params.late_move_pruning_base = lmpb;
         }
@@ -131,6 +328,9 @@ impl Search for AlphaBeta {
         if let Some(lmpmd) = abp.late_move_pruning_max_depth {
             self.search_params.late_move_pruning_max_depth = lmpmd;
         }
+        if let Some(lmpnip) = abp.late_move_pruning_not_improving_percent {
+            self.search_params.late_move_pruning_not_improving_percent = lmpnip;
+        }
         if let Some(spmq) = abp.see_pruning_margin_quiet {
             self.search_params.see_pruning_margin_quiet = spmq;
         }
@@ -140,55 +340,408 @@ impl Search for AlphaBeta {
         if let Some(spmd) = abp.see_pruning_max_depth {
             self.search_params.see_pruning_max_depth = spmd;
         }
+        if let Some(spmdq) = abp.see_pruning_max_depth_quiet {
+            self.search_params.see_pruning_max_depth_quiet = spmdq;
+        }
+        if let Some(nmzg) = abp.null_move_zugzwang_guard {
+            self.search_params.null_move_zugzwang_guard = nmzg;
+        }
+        if let Some(nmvmd) = abp.null_move_verification_min_depth {
+            self.search_params.null_move_verification_min_depth = nmvmd;
+        }
+        if let Some(iir) = abp.internal_iterative_reduction {
+            self.search_params.internal_iterative_reduction = iir;
+        }
+        if let Some(semd) = abp.singular_extension_min_depth {
+            self.search_params.singular_extension_min_depth = semd;
+        }
+        if let Some(semtdd) = abp.singular_extension_max_table_depth_diff {
+            self.search_params.singular_extension_max_table_depth_diff = semtdd;
+        }
+        if let Some(sempd) = abp.singular_extension_margin_per_depth {
+            self.search_params.singular_extension_margin_per_depth = sempd;
+        }
+        if let Some(me) = abp.max_extensions {
+            self.search_params.max_extensions = me;
+        }
+        if let Some(lmrni) = abp.late_move_reduction_not_improving {
+            self.search_params.late_move_reduction_not_improving = lmrni;
+        }
+        if let Some(lmrcn) = abp.late_move_reduction_cut_node {
+            self.search_params.late_move_reduction_cut_node = lmrcn;
+        }
+        if let Some(lmrkc) = abp.late_move_reduction_killer_or_counter {
+            self.search_params.late_move_reduction_killer_or_counter = lmrkc;
+        }
+        if let Some(lmrhd) = abp.late_move_reduction_history_divisor {
+            // The divisor must not be zero
+            self.search_params.late_move_reduction_history_divisor = lmrhd.max(1);
+        }
         if let Some(awiw) = abp.aspiration_window_initial_width {
             self.search_params.aspiration_window_initial_width = awiw;
         }
         if let Some(awgr) = abp.aspiration_window_grow_rate {
             self.search_params.aspiration_window_grow_rate = awgr;
         }
+        if let Some(sdt) = abp.score_drop_threshold {
+            self.search_params.score_drop_threshold = sdt;
+        }
+        if let Some(sdep) = abp.score_drop_extension_percent {
+            self.search_params.score_drop_extension_percent = sdep;
+        }
+        if let Some(cls) = abp.contempt_losing_score {
+            // Must be positive, the contempt is scaled by the score divided by it
+            self.search_params.contempt_losing_score = cls.max(1);
+        }
+        if let Some(cep) = abp.contempt_endgame_percent {
+            self.search_params.contempt_endgame_percent = cep.clamp(0, 100);
+        }
+        if let Some(sh) = abp.shared_history {
+            if sh != self.search_params.shared_history {
+                self.search_params.shared_history = sh;
+                self.reset_move_ordering_tables();
+            }
+        }
+        if let Some(hm) = abp.history_max {
+            self.history_table.set_max(hm);
+        }
+        if let Some(hbq) = abp.history_bonus_quadratic {
+            self.history_table.set_bonus_quadratic(hbq);
+        }
+        if let Some(hbl) = abp.history_bonus_linear {
+            self.history_table.set_bonus_linear(hbl);
+        }
+        for helper in self.helpers.iter_mut() {
+            helper.search_params = self.search_params.clone();
+            helper.history_table.copy_params(&self.history_table);
+        }
     }
 
     fn search(
         &mut self,
         pos_history: PositionHistory,
-        search_options: SearchOptions,
+        mut search_options: SearchOptions,
         command_receiver: &Receiver<SearchCommand>,
         info_sender: &Sender<SearchInfo>,
-    ) {
+    ) -> Option<SearchCommand> {
         let start_time = Instant::now();
-        let side_to_move = pos_history.current_pos().side_to_move();
-        let hard_time_limit = TimeManager::calc_movetime_hard_limit(side_to_move, &search_options);
-        let mut soft_time_limit = cmp::min(
-            hard_time_limit,
-            TimeManager::calc_movetime_soft_limit(side_to_move, &search_options),
-        );
+        search_options.depth = search_options.depth.map(|d| d.min(MAX_PLY));
+        let TimeLimits {
+            soft: soft_time_limit,
+            hard: hard_time_limit,
+        } = TimeManager::calc_time_limits(pos_history.current_pos(), &search_options);
         let has_time_limit = soft_time_limit.is_some();
+        let max_nodes = match search_options.strength_limit {
+            Some(limit) => Some(
+                search_options
+                    .nodes
+                    .map_or(limit.max_nodes(), |n| n.min(limit.max_nodes())),
+            ),
+            None => search_options.nodes,
+        };
+        // The position history of the search data is not restored if the
+        // search gets stopped, so keep a copy for choosing a weaker move.
+        let is_weakened =
+            search_options.strength_limit.is_some() || search_options.skill_level.is_some();
+        let is_randomized = search_options.equal_moves_seed.is_some();
+        let root_pos_history = (is_weakened || is_randomized).then(|| pos_history.clone());
+        let helper_nodes = self
+            .helpers
+            .iter()
+            .map(|_| AtomicU64::new(0))
+            .collect::<Vec<_>>();
         let mut search_data = SearchData::new(
             command_receiver,
-            info_sender,
+            Some(info_sender),
             pos_history,
             start_time,
             hard_time_limit,
-            search_options.nodes,
+            max_nodes,
         );
+        if search_options.analyse_mode {
+            search_data.collect_refutations();
+        }
+        // Only the main thread is traced
+        if let Some(max_ply) = search_options.trace_depth {
+            search_data.set_tracer(SearchTracer::new(max_ply));
+        }
+        // The contempt stays the same for the whole search and all threads,
+        // so that the draw scores in the shared hash table agree
+        let game_phase = GamePhase::new(search_data.current_pos()).game_phase_clamped();
+        let root_score = self
+            .transpos_table
+            .get(&search_data.current_pos_hash())
+            .filter(|entry| entry.depth() > 0 && !entry.is_path_dependent())
+            .map(|entry| entry.score());
+        let contempt = self.dynamic_contempt(search_options.contempt, game_phase, root_score);
+        search_data.set_contempt(contempt);
+        search_data.set_max_extensions(self.search_params.max_extensions);
+        search_data.set_helper_nodes(&helper_nodes);
+        // Every search is a new generation in the transposition table. The
+        // helpers are started afterwards and use the same generation.
+        search_data.set_age(self.transpos_table.next_generation());
 
         self.history_table.decay();
         let mut root_moves = MoveList::new();
         MoveGenerator::generate_moves(&mut root_moves, search_data.current_pos());
-        let mut best_move = Move::NULL;
+        if root_moves.is_empty() {
+            // Checkmate or stalemate. There is nothing to search, but the
+            // score is reported and the search ends without a best move.
+            self.send_game_over(&mut search_data);
+            info_sender
+                .send(SearchInfo::Stopped(Move::NULL))
+                .expect("Error sending SearchInfo");
+            return None;
+        }
         let move_count = root_moves.len();
-        if has_time_limit && move_count == 1 {
+        if has_time_limit && move_count == 1 && !search_options.analyse_mode {
             // Move is forced, no need to search
-            best_move = root_moves[0];
             info_sender
-                .send(SearchInfo::Stopped(best_move))
+                .send(SearchInfo::Stopped(root_moves[0]))
                 .expect("Error sending SearchInfo");
-            return;
+            return None;
         }
         search_data.set_root_moves(&root_moves);
+        let max_depth = search_options.depth.unwrap_or(MAX_PLY);
+        let mut helpers = std::mem::take(&mut self.helpers);
+        let mut best_move = thread::scope(|s| {
+            let root_moves = &root_moves;
+            let stop_senders = helpers
+                .iter_mut()
+                .zip(helper_nodes.iter())
+                .enumerate()
+                .map(|(idx, (helper, nodes))| {
+                    let (command_sender, command_receiver) = crossbeam_channel::unbounded();
+                    let pos_history = search_data.pos_history().clone();
+                    // Every other helper starts one depth deeper, so that the
+                    // threads don't search the same nodes in lockstep
+                    let first_depth = 1 + idx % 2;
+                    s.spawn(move || {
+                        helper.search_helper(
+                            pos_history,
+                            root_moves,
+                            first_depth,
+                            max_depth,
+                            contempt,
+                            &command_receiver,
+                            nodes,
+                        )
+                    });
+                    command_sender
+                })
+                .collect::<Vec<_>>();
+            let best_move = self.iterative_deepening(
+                &mut search_data,
+                &search_options,
+                root_moves,
+                soft_time_limit,
+            );
+            for sender in stop_senders {
+                // The helper may already have finished
+                let _ = sender.send(SearchCommand::Stop);
+            }
+            best_move
+        });
+        self.helpers = helpers;
+        if best_move == Move::NULL && !root_moves.is_empty() {
+            // The search was stopped before it produced a move, or depth 0
+            // found no capture better than standing pat. Any legal move is
+            // better than none.
+            best_move = root_moves[0];
+        }
+        if let Some(pos_history) = root_pos_history.filter(|_| best_move != Move::NULL) {
+            best_move = match (search_options.strength_limit, search_options.skill_level) {
+                (Some(limit), _) => {
+                    let seed = u64::from(pos_history.current_pos_hash());
+                    self.choose_weaker_move(
+                        command_receiver,
+                        info_sender,
+                        pos_history,
+                        &root_moves,
+                        best_move,
+                        |candidates, best_score| limit.choose(candidates, best_score, seed),
+                    )
+                }
+                (None, Some(level)) => {
+                    // The same game is reproducible, other games (from other
+                    // start positions) differ
+                    let seed = u64::from(pos_history.start_pos_hash())
+                        ^ u64::from(pos_history.current_pos_hash());
+                    self.choose_weaker_move(
+                        command_receiver,
+                        info_sender,
+                        pos_history,
+                        &root_moves,
+                        best_move,
+                        |candidates, best_score| level.choose(candidates, best_score, seed),
+                    )
+                }
+                (None, None) => match search_options.equal_moves_seed {
+                    Some(seed) => {
+                        let seed = seed
+                            ^ u64::from(pos_history.start_pos_hash())
+                            ^ u64::from(pos_history.current_pos_hash());
+                        self.choose_equal_move(
+                            &mut search_data,
+                            pos_history,
+                            &root_moves,
+                            best_move,
+                            seed,
+                        )
+                    }
+                    None => best_move,
+                },
+            };
+        }
+        let mut next_command = None;
+        for cmd in search_data.take_deferred_commands() {
+            match cmd {
+                DeferredCommand::SetHashSize(bytes) => self.set_hash_size(bytes),
+                DeferredCommand::ClearHashTable => self.clear_hash_table(),
+                DeferredCommand::SetSearchParams(params) => self.set_params(*params),
+                DeferredCommand::Search(inner) => {
+                    next_command = Some(SearchCommand::Search(inner));
+                }
+                DeferredCommand::Terminate => next_command = Some(SearchCommand::Terminate),
+            }
+        }
+        info_sender
+            .send(SearchInfo::Stopped(best_move))
+            .expect("Error sending SearchInfo");
+        next_command
+    }
+}
+
+impl AlphaBeta {
+    pub fn new(evaluator: Box<dyn Eval + Send>, table_size: usize) -> Self {
+        Self {
+            evaluator,
+            eval_cache: EvalCache::new(EVAL_CACHE_SIZE),
+            transpos_table: Arc::new(SharedTable::new(table_size)),
+            counter_table: CounterTable::new(SHARED_HISTORY),
+            history_table: HistoryTable::new(SHARED_HISTORY),
+            correction_history: CorrectionHistory::new(),
+            search_params: SearchParams {
+                futility_margin_base: FUTILITY_MARGIN_BASE,
+                futility_margin_per_depth: FUTILITY_MARGIN_PER_DEPTH,
+                futility_pruning_max_depth: FUTILITY_PRUNING_MAX_DEPTH,
+                futility_margin_improving: FUTILITY_MARGIN_IMPROVING,
+                reverse_futility_margin_base: REVERSE_FUTILITY_MARGIN_BASE,
+                reverse_futility_margin_per_depth: REVERSE_FUTILITY_MARGIN_PER_DEPTH,
+                reverse_futility_pruning_max_depth: REVERSE_FUTILITY_PRUNING_MAX_DEPTH,
+                reverse_futility_margin_improving: REVERSE_FUTILITY_MARGIN_IMPROVING,
+                razoring_margin_base: RAZORING_MARGIN_BASE,
+                razoring_margin_per_depth: RAZORING_MARGIN_PER_DEPTH,
+                razoring_max_depth: RAZORING_MAX_DEPTH,
+                probcut_margin: PROBCUT_MARGIN,
+                probcut_depth_reduction: PROBCUT_DEPTH_REDUCTION,
+                probcut_min_depth: PROBCUT_MIN_DEPTH,
+                multicut_margin: MULTICUT_MARGIN,
+                multicut_depth_reduction: MULTICUT_DEPTH_REDUCTION,
+                multicut_min_depth: MULTICUT_MIN_DEPTH,
+                multicut_moves: MULTICUT_MOVES,
+                multicut_required_cutoffs: MULTICUT_REQUIRED_CUTOFFS,
+                late_move_pruning_base: LATE_MOVE_PRUNING_BASE,
+                late_move_pruning_factor: LATE_MOVE_PRUNING_FACTOR,
+                late_move_pruning_max_depth: LATE_MOVE_PRUNING_MAX_DEPTH,
+                late_move_pruning_not_improving_percent: LATE_MOVE_PRUNING_NOT_IMPROVING_PERCENT,
+                see_pruning_margin_quiet: SEE_PRUNING_MARGIN_QUIET,
+                see_pruning_margin_tactical: SEE_PRUNING_MARGIN_TACTICAL,
+                see_pruning_max_depth: SEE_PRUNING_MAX_DEPTH,
+                see_pruning_max_depth_quiet: SEE_PRUNING_MAX_DEPTH_QUIET,
+                null_move_zugzwang_guard: NULL_MOVE_ZUGZWANG_GUARD,
+                null_move_verification_min_depth: NULL_MOVE_VERIFICATION_MIN_DEPTH,
+                internal_iterative_reduction: INTERNAL_ITERATIVE_REDUCTION,
+                singular_extension_min_depth: SINGULAR_EXTENSION_MIN_DEPTH,
+                singular_extension_max_table_depth_diff: SINGULAR_EXTENSION_MAX_TABLE_DEPTH_DIFF,
+                singular_extension_margin_per_depth: SINGULAR_EXTENSION_MARGIN_PER_DEPTH,
+                max_extensions: MAX_EXTENSIONS,
+                late_move_reduction_not_improving: LATE_MOVE_REDUCTION_NOT_IMPROVING,
+                late_move_reduction_cut_node: LATE_MOVE_REDUCTION_CUT_NODE,
+                late_move_reduction_killer_or_counter: LATE_MOVE_REDUCTION_KILLER_OR_COUNTER,
+                late_move_reduction_history_divisor: LATE_MOVE_REDUCTION_HISTORY_DIVISOR,
+                aspiration_window_initial_width: INITIAL_WIDTH,
+                aspiration_window_grow_rate: GROW_RATE,
+                score_drop_threshold: SCORE_DROP_THRESHOLD,
+                score_drop_extension_percent: SCORE_DROP_EXTENSION_PERCENT,
+                contempt_losing_score: CONTEMPT_LOSING_SCORE,
+                contempt_endgame_percent: CONTEMPT_ENDGAME_PERCENT,
+                shared_history: SHARED_HISTORY,
+            },
+            helpers: Vec::new(),
+        }
+    }
+
+    // The number of search threads, including the main thread
+    pub fn set_threads(&mut self, threads: usize) {
+        let num_helpers = threads.max(1) - 1;
+        self.helpers.truncate(num_helpers);
+        while self.helpers.len() < num_helpers {
+            let helper = self.new_helper();
+            self.helpers.push(helper);
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.helpers.len() + 1
+    }
+
+    // Helpers share the transposition table and optionally the history and
+    // counter move tables. Everything else is thread-local, so the helpers are
+    // cheap to create.
+    fn new_helper(&self) -> Self {
+        let mut helper = Self {
+            evaluator: self.evaluator.clone_box(),
+            eval_cache: self.eval_cache.clone(),
+            transpos_table: Arc::clone(&self.transpos_table),
+            counter_table: self
+                .counter_table
+                .share()
+                .unwrap_or_else(|| CounterTable::new(false)),
+            history_table: self
+                .history_table
+                .share()
+                .unwrap_or_else(|| HistoryTable::new(false)),
+            correction_history: CorrectionHistory::new(),
+            search_params: self.search_params.clone(),
+            helpers: Vec::new(),
+        };
+        helper.history_table.copy_params(&self.history_table);
+        helper
+    }
+
+    // Replaces the history and counter move tables of all threads with new
+    // (shared or thread-local) ones
+    fn reset_move_ordering_tables(&mut self) {
+        let shared = self.search_params.shared_history;
+        let mut history_table = HistoryTable::new(shared);
+        history_table.copy_params(&self.history_table);
+        self.history_table = history_table;
+        self.counter_table = CounterTable::new(shared);
+        let threads = self.threads();
+        self.helpers.clear();
+        self.set_threads(threads);
+    }
+
+    // The search of the main thread. Returns the best move found.
+    fn iterative_deepening(
+        &mut self,
+        search_data: &mut SearchData,
+        search_options: &SearchOptions,
+        root_moves: &MoveList,
+        mut soft_time_limit: Option<Duration>,
+    ) -> Move {
+        let mut best_move = Move::NULL;
         let mut aw = AspirationWindow::infinite();
+        let mut volatility = ScoreVolatility::default();
+        let mut nodes_time_scale = 1.0;
+        let mut score_drop_time_scale = 1.0;
 
-        for d in 1..=search_options.depth.unwrap_or(MAX_SEARCH_DEPTH) {
+        if search_options.depth == Some(0) {
+            best_move = self.search_root_quiescence(search_data, root_moves);
+        }
+
+        for d in 1..=search_options.depth.unwrap_or(MAX_PLY) {
             search_data.increase_search_depth();
 
             if search_data.search_depth() > 1 {
@@ -196,6 +749,11 @@ impl Search for AlphaBeta {
                     break;
                 }
                 if let Some(limit) = soft_time_limit {
+                    let limit = TimeManager::scaled_soft_limit(
+                        limit,
+                        nodes_time_scale * score_drop_time_scale,
+                        search_data.hard_time_limit(),
+                    );
                     if search_data.start_time().elapsed() > limit {
                         break;
                     }
@@ -204,25 +762,39 @@ impl Search for AlphaBeta {
 
             let mut stop_search = false;
             loop {
-                match self.search_recursive(&mut search_data, aw.alpha(), aw.beta()) {
+                match self.search_recursive(search_data, aw.alpha(), aw.beta(), false) {
                     Some(rel_alpha_beta_res) => {
                         if rel_alpha_beta_res.score() <= aw.alpha() {
                             // Fail low
                             debug_assert!(aw.alpha() > NEG_INF);
                             search_data.reset_current_search_depth();
-                            aw.widen_down();
+                            self.send_aspiration_fail(
+                                search_data,
+                                &rel_alpha_beta_res,
+                                ScoreBound::UpperBound,
+                                search_options.analyse_mode,
+                            );
+                            aw.widen_down(rel_alpha_beta_res.score());
                             continue;
                         }
                         if rel_alpha_beta_res.score() >= aw.beta() {
                             debug_assert!(aw.beta() < POS_INF);
                             // Fail high
                             search_data.reset_current_search_depth();
-                            aw.widen_up();
+                            self.send_aspiration_fail(
+                                search_data,
+                                &rel_alpha_beta_res,
+                                ScoreBound::LowerBound,
+                                search_options.analyse_mode,
+                            );
+                            aw.widen_up(rel_alpha_beta_res.score());
                             continue;
                         }
+                        volatility.update(rel_alpha_beta_res.score());
                         aw = AspirationWindow::new(
                             rel_alpha_beta_res.score(),
-                            self.search_params.aspiration_window_initial_width,
+                            volatility
+                                .window_width(self.search_params.aspiration_window_initial_width),
                             self.search_params.aspiration_window_grow_rate,
                         );
                         let abs_alpha_beta_res = match search_data.current_pos().side_to_move() {
@@ -234,16 +806,34 @@ impl Search for AlphaBeta {
                             d,
                             search_data.selective_depth(),
                             abs_alpha_beta_res.score(),
-                            search_data.node_counter().sum_nodes(),
-                            start_time.elapsed().as_micros() as u64,
-                            self.transpos_table.load_factor_permille(),
+                            search_data.searched_nodes() as u64,
+                            search_data.start_time().elapsed().as_micros() as u64,
+                            self.transpos_table.hashfull_permille(),
                             abs_alpha_beta_res.best_move(),
-                            search_data.pv_owned(d),
-                        );
+                            Self::legal_pv(search_data, search_data.pv_owned(d)),
+                        )
+                        .with_refutations(search_data.take_refutations());
                         search_data.send_info(SearchInfo::DepthFinished(search_res));
                         best_move = abs_alpha_beta_res.best_move();
+                        if d >= BEST_MOVE_NODES_MIN_DEPTH && best_move != Move::NULL {
+                            if let Some(fraction) = search_data.root_move_node_fraction(best_move) {
+                                nodes_time_scale = TimeManager::best_move_nodes_scale(fraction);
+                            }
+                        }
+                        // A falling score means that there may be a problem
+                        // with the best move, so take more time
+                        if let Some(prev_score) = search_data.prev_iteration_score() {
+                            score_drop_time_scale = TimeManager::score_drop_scale(
+                                prev_score,
+                                rel_alpha_beta_res.score(),
+                                self.search_params.score_drop_threshold,
+                                self.search_params.score_drop_extension_percent,
+                            );
+                        }
+                        search_data.set_prev_iteration(d, rel_alpha_beta_res.score());
                         let score = abs_alpha_beta_res.score();
-                        if eval::score::is_mating(score)
+                        if !search_options.analyse_mode
+                            && eval::score::is_mating(score)
                             && eval::score::mate_dist(score).unsigned_abs() as usize <= d
                         {
                             if let Some(ref mut limit) = soft_time_limit {
@@ -266,65 +856,424 @@ impl Search for AlphaBeta {
                 break;
             }
         }
-        info_sender
-            .send(SearchInfo::Stopped(best_move))
-            .expect("Error sending SearchInfo");
+        best_move
     }
-}
 
-impl AlphaBeta {
-    pub fn new(evaluator: Box<dyn Eval + Send>, table_size: usize) -> Self {
-        Self {
-            evaluator,
-            transpos_table: AlphaBetaTable::new(table_size),
-            counter_table: CounterTable::new(),
-            history_table: HistoryTable::new(),
-            search_params: SearchParams {
-                futility_margin_base: FUTILITY_MARGIN_BASE,
-                futility_margin_per_depth: FUTILITY_MARGIN_PER_DEPTH,
-                futility_pruning_max_depth: FUTILITY_PRUNING_MAX_DEPTH,
-                reverse_futility_margin_base: REVERSE_FUTILITY_MARGIN_BASE,
-                reverse_futility_margin_per_depth: REVERSE_FUTILITY_MARGIN_PER_DEPTH,
-                reverse_futility_pruning_max_depth: REVERSE_FUTILITY_PRUNING_MAX_DEPTH,
-                late_move_pruning_base: LATE_MOVE_PRUNING_BASE,
-                late_move_pruning_factor: LATE_MOVE_PRUNING_FACTOR,
-                late_move_pruning_max_depth: LATE_MOVE_PRUNING_MAX_DEPTH,
-                see_pruning_margin_quiet: SEE_PRUNING_MARGIN_QUIET,
-                see_pruning_margin_tactical: SEE_PRUNING_MARGIN_TACTICAL,
-                see_pruning_max_depth: SEE_PRUNING_MAX_DEPTH,
-                aspiration_window_initial_width: INITIAL_WIDTH,
-                aspiration_window_grow_rate: GROW_RATE,
+    // Helpers search with a full window at each depth until they are stopped
+    // or reach the maximum depth. They don't report anything.
+    #[allow(clippy::too_many_arguments)]
+    fn search_helper(
+        &mut self,
+        pos_history: PositionHistory,
+        root_moves: &MoveList,
+        first_depth: usize,
+        max_depth: usize,
+        contempt: Score,
+        command_receiver: &Receiver<SearchCommand>,
+        nodes: &AtomicU64,
+    ) {
+        let mut search_data = SearchData::new(
+            command_receiver,
+            None,
+            pos_history,
+            Instant::now(),
+            None,
+            None,
+        );
+        search_data.publish_nodes(nodes);
+        search_data.set_contempt(contempt);
+        search_data.set_max_extensions(self.search_params.max_extensions);
+        search_data.set_age(self.transpos_table.generation());
+        search_data.set_root_moves(root_moves);
+        // Shared entries have already been decayed by the main thread
+        if !self.history_table.is_shared() {
+            self.history_table.decay();
+        }
+        for _ in 1..=max_depth {
+            search_data.increase_search_depth();
+            if search_data.search_depth() < first_depth {
+                continue;
+            }
+            if search_data.search_depth() == first_depth {
+                // There is no PV from the skipped depths
+                search_data.end_prev_pv();
+            }
+            if self
+                .search_recursive(&mut search_data, NEG_INF, POS_INF, false)
+                .is_none()
+            {
+                break;
+            }
+        }
+    }
+
+    // Choose a weaker move among the root moves with the strength limit or the
+    // skill level. The root moves are scored with a quiescence search, the
+    // best move from the regular search is treated as the best one.
+    fn choose_weaker_move(
+        &mut self,
+        command_receiver: &Receiver<SearchCommand>,
+        info_sender: &Sender<SearchInfo>,
+        pos_history: PositionHistory,
+        root_moves: &MoveList,
+        best_move: Move,
+        choose: impl FnOnce(&[(Move, Score)], Score) -> Move,
+    ) -> Move {
+        let mut search_data = SearchData::new(
+            command_receiver,
+            Some(info_sender),
+            pos_history,
+            Instant::now(),
+            None,
+            None,
+        );
+        search_data.set_age(self.transpos_table.generation());
+        search_data.increase_search_depth();
+        let mut candidates = Vec::with_capacity(root_moves.len());
+        for &m in root_moves.iter() {
+            search_data.do_move(m);
+            let score = -self
+                .search_quiescence(&mut search_data, NEG_INF, POS_INF)
+                .score();
+            search_data.undo_last_move();
+            candidates.push((m, score));
+        }
+        let best_score = candidates.iter().map(|&(_, s)| s).max().unwrap_or(NEG_INF);
+        for (m, s) in candidates.iter_mut() {
+            if *m == best_move {
+                *s = best_score;
+            }
+        }
+        choose(&candidates, best_score)
+    }
+
+    // Chooses randomly among the best move and the root moves that score
+    // within EQUAL_MOVES_SCORE_MARGIN of it at the depth of the last completed
+    // iteration. Each other move is verified by a null window search at this
+    // depth. The verification obeys the limits of the main search: it only
+    // uses the nodes the main search has left and ends at the hard time limit
+    // or on a stop command. Unverified moves are never chosen.
+    fn choose_equal_move(
+        &mut self,
+        main_search_data: &mut SearchData,
+        pos_history: PositionHistory,
+        root_moves: &MoveList,
+        best_move: Move,
+        seed: u64,
+    ) -> Move {
+        let (depth, best_score) = match (
+            main_search_data.prev_iteration_depth(),
+            main_search_data.prev_iteration_score(),
+        ) {
+            (Some(depth), Some(score)) if !eval::score::is_mating(score) => (depth, score),
+            _ => return best_move,
+        };
+        if main_search_data.stop_requested() {
+            return best_move;
+        }
+        let max_nodes = match main_search_data.max_nodes() {
+            Some(max_nodes) => match max_nodes.checked_sub(main_search_data.searched_nodes()) {
+                Some(remaining) if remaining > 0 => Some(remaining),
+                _ => return best_move,
             },
+            None => None,
+        };
+        let mut search_data = SearchData::new(
+            main_search_data.command_receiver(),
+            main_search_data.info_sender(),
+            pos_history,
+            main_search_data.start_time(),
+            main_search_data.hard_time_limit(),
+            max_nodes,
+        );
+        search_data.set_age(self.transpos_table.generation());
+        search_data.set_contempt(main_search_data.contempt());
+        search_data.set_max_extensions(self.search_params.max_extensions);
+        for _ in 0..depth {
+            search_data.increase_search_depth();
         }
+        // There is no PV to follow
+        search_data.end_prev_pv();
+        let threshold = best_score - EQUAL_MOVES_SCORE_MARGIN;
+        let mut candidates = vec![(best_move, best_score)];
+        for &m in root_moves.iter().filter(|&&m| m != best_move) {
+            search_data.do_move(m);
+            search_data.set_current_extension(0);
+            // The other moves are expected to fail low, so their children
+            // are expected cut nodes
+            let opt_node =
+                self.search_recursive(&mut search_data, -threshold, -threshold + 1, true);
+            search_data.undo_last_move();
+            match opt_node {
+                Some(node) if -node.score() >= threshold => candidates.push((m, threshold)),
+                Some(_) => {}
+                None => break,
+            }
+        }
+        main_search_data.defer_commands(search_data.take_deferred_commands());
+        strength::choose_equal_move(&candidates, best_score, seed)
+    }
+
+    // Depth 0 only runs a quiescence search on the root position and reports
+    // its score. If the side to move is in check, all evasions are searched.
+    // Returns the first move of the best capture sequence or Move::NULL if
+    // standing pat is best.
+    fn search_root_quiescence(
+        &mut self,
+        search_data: &mut SearchData,
+        root_moves: &MoveList,
+    ) -> Move {
+        search_data.increase_search_depth();
+        let is_in_check = search_data.is_in_check(search_data.current_pos().side_to_move());
+        let rel_alpha_beta_res = match root_moves.is_empty() && !is_in_check {
+            // The quiescence search doesn't detect stalemates
+            true => AlphaBetaEntry::new(
+                0,
+                search_data.draw_score(),
+                ScoreType::Exact,
+                Move::NULL,
+                search_data.age(),
+            ),
+            false => self.search_quiescence(search_data, NEG_INF, POS_INF),
+        };
+        let abs_alpha_beta_res = match search_data.current_pos().side_to_move() {
+            Side::White => rel_alpha_beta_res,
+            Side::Black => -rel_alpha_beta_res,
+        };
+        let mut pv = MoveList::new();
+        if abs_alpha_beta_res.best_move() != Move::NULL {
+            pv.push(abs_alpha_beta_res.best_move());
+        }
+        // The best move may come from the hash table
+        let pv = Self::legal_pv(search_data, pv);
+        let best_move = pv.first().copied().unwrap_or(Move::NULL);
+        let search_res = SearchResult::new(
+            0,
+            search_data.selective_depth(),
+            abs_alpha_beta_res.score(),
+            search_data.searched_nodes() as u64,
+            search_data.start_time().elapsed().as_micros() as u64,
+            self.transpos_table.hashfull_permille(),
+            best_move,
+            pv,
+        );
+        search_data.send_info(SearchInfo::DepthFinished(search_res));
+        best_move
+    }
+
+    // The PV up to its first illegal move, so that only legal lines are
+    // reported
+    fn legal_pv(search_data: &SearchData, mut pv: MoveList) -> MoveList {
+        pv_table::truncate_at_illegal_move(&mut search_data.pos_history().clone(), &mut pv);
+        pv
     }
 
+    // Continues the PV with the best moves from the hash table, up to the
+    // search depth
+    fn complete_pv(&self, mut pos_history: PositionHistory, pv: &mut MoveList, depth: usize) {
+        pv_table::truncate_at_illegal_move(&mut pos_history, pv);
+        while pv.len() < depth && pos_history.current_pos_repetitions() < 2 {
+            let m = match self.transpos_table.get(&pos_history.current_pos_hash()) {
+                Some(entry) if entry.best_move() != Move::NULL => entry.best_move(),
+                _ => break,
+            };
+            let mut legal_moves = MoveList::new();
+            MoveGenerator::generate_moves(&mut legal_moves, pos_history.current_pos());
+            if !legal_moves.contains(&m) {
+                break;
+            }
+            pv.push(m);
+            pos_history.do_move(m);
+        }
+    }
+
+    // The contempt scaled down towards the endgame and when the side to move
+    // at the root is worse. prev_score is relative to the side to move at the
+    // root, like the contempt (the draw score flips the sign at odd plies).
+    // A score of a draw itself doesn't count as worse.
+    fn dynamic_contempt(
+        &self,
+        contempt: Score,
+        game_phase: usize,
+        prev_score: Option<Score>,
+    ) -> Score {
+        let losing_score = self.search_params.contempt_losing_score as i32;
+        let endgame_percent = self.search_params.contempt_endgame_percent;
+        let max_phase = GamePhase::MAX as i32;
+        let phase_percent =
+            endgame_percent + (100 - endgame_percent) * game_phase as i32 / max_phase;
+        let phase_contempt = contempt as i32 * phase_percent / 100;
+        let draw_score = -phase_contempt;
+        let score_factor = prev_score.map_or(losing_score, |s| {
+            (losing_score + s as i32 - draw_score).clamp(0, losing_score)
+        });
+        (phase_contempt * score_factor / losing_score) as Score
+    }
+
+    // The root position has no legal moves. Reports the mate or draw score
+    // without a PV.
+    fn send_game_over(&self, search_data: &mut SearchData) {
+        let rel_score = match search_data.is_in_check(search_data.current_pos().side_to_move()) {
+            true => BLACK_WIN,
+            false => search_data.draw_score(),
+        };
+        let score = match search_data.current_pos().side_to_move() {
+            Side::White => rel_score,
+            Side::Black => -rel_score,
+        };
+        let search_res = SearchResult::new(
+            0,
+            0,
+            score,
+            0,
+            search_data.start_time().elapsed().as_micros() as u64,
+            self.transpos_table.hashfull_permille(),
+            Move::NULL,
+            MoveList::new(),
+        );
+        search_data.send_info(SearchInfo::DepthFinished(search_res));
+    }
+
+    // Report a score outside the aspiration window as a bound. rel_bound is
+    // relative to the side to move. On a fail high, the PV consists only of
+    // the move that failed high (in analyse mode, it is continued from the
+    // hash table). On a fail low, the PV of the previous depth is reported.
+    fn send_aspiration_fail(
+        &self,
+        search_data: &SearchData,
+        rel_res: &AlphaBetaEntry,
+        rel_bound: ScoreBound,
+        analyse_mode: bool,
+    ) {
+        let depth = search_data.search_depth();
+        let (best_move, pv) = match rel_bound {
+            ScoreBound::LowerBound if rel_res.best_move() != Move::NULL => {
+                let mut pv = MoveList::new();
+                pv.push(rel_res.best_move());
+                if analyse_mode {
+                    self.complete_pv(search_data.pos_history().clone(), &mut pv, depth);
+                }
+                (rel_res.best_move(), pv)
+            }
+            _ => {
+                let pv = Self::legal_pv(search_data, search_data.pv_owned(depth - 1));
+                (pv.first().copied().unwrap_or(Move::NULL), pv)
+            }
+        };
+        let (score, bound) = match search_data.current_pos().side_to_move() {
+            Side::White => (rel_res.score(), rel_bound),
+            Side::Black => (-rel_res.score(), -rel_bound),
+        };
+        let search_res = SearchResult::new(
+            depth,
+            search_data.selective_depth(),
+            score,
+            search_data.searched_nodes() as u64,
+            search_data.start_time().elapsed().as_micros() as u64,
+            self.transpos_table.hashfull_permille(),
+            best_move,
+            pv,
+        )
+        .with_score_bound(bound);
+        search_data.send_info(SearchInfo::AspirationFail(search_res));
+    }
+
+    // The node type is expected from the window and the cut_node flag: PV
+    // nodes have an open window, the other nodes are expected to be cut nodes
+    // (some move fails high) or all nodes (all moves fail low). The flag is
+    // ignored at PV nodes.
     fn search_recursive(
         &mut self,
         search_data: &mut SearchData,
         alpha: Score,
         beta: Score,
+        cut_node: bool,
+    ) -> Option<AlphaBetaEntry> {
+        if !search_data.is_traced() {
+            return self.search_node(search_data, alpha, beta, cut_node);
+        }
+        search_data.trace_enter(alpha, beta);
+        let opt_node = self.search_node(search_data, alpha, beta, cut_node);
+        search_data.trace_leave(opt_node.as_ref());
+        opt_node
+    }
+
+    fn search_node(
+        &mut self,
+        search_data: &mut SearchData,
+        alpha: Score,
+        beta: Score,
+        cut_node: bool,
     ) -> Option<AlphaBetaEntry> {
         if search_data.should_stop_search_immediately() {
             return None;
         }
 
+        if search_data.progress_info_due() {
+            search_data.send_info(SearchInfo::Progress(SearchProgress::new(
+                search_data.searched_nodes() as u64,
+                search_data.start_time().elapsed().as_micros() as u64,
+                self.transpos_table.hashfull_permille(),
+            )));
+        }
+
         let is_pv_node = alpha + 1 != beta;
+        let cut_node = cut_node && !is_pv_node;
         if let Some(entry) = Self::is_draw(search_data, is_pv_node) {
             return Some(entry);
         }
+        if search_data.ply() >= MAX_PLY {
+            return Some(self.max_ply_entry(search_data));
+        }
 
-        if let Some(entry) = self.usable_table_entry(search_data, alpha, beta) {
-            return Some(entry);
+        // The result of a search with an excluded move is only valid for the
+        // singular extension verification, so it must not be stored or read
+        // from the hash table
+        let is_verification = search_data.excluded_move() != Move::NULL;
+        if !is_verification {
+            if let Some(entry) = self.usable_table_entry(search_data, alpha, beta) {
+                return Some(entry);
+            }
         }
 
         if search_data.remaining_depth() == 0 {
             return Some(self.search_quiescence(search_data, alpha, beta));
         }
 
-        match self.search_recursive_next_ply(search_data, alpha, beta) {
+        let has_hash_move = match self.transpos_table.get(&search_data.current_pos_hash()) {
+            Some(entry) => entry.best_move() != Move::NULL,
+            None => false,
+        };
+        let iir = Self::internal_iterative_reduction(
+            self.search_params.internal_iterative_reduction,
+            search_data.remaining_depth(),
+            cut_node && search_data.ply() != 0,
+            search_data.is_in_check(search_data.current_pos().side_to_move()),
+            has_hash_move || is_verification,
+        );
+        let opt_node = match iir {
+            0 => self.search_recursive_next_ply(search_data, alpha, beta, cut_node, has_hash_move),
+            _ => {
+                // Restore the reduction afterwards, because the parent node
+                // checks it to decide about a re-search
+                let prev_reduction = search_data.current_reduction();
+                search_data.set_current_reduction(prev_reduction + iir);
+                let opt_node = self.search_recursive_next_ply(
+                    search_data,
+                    alpha,
+                    beta,
+                    cut_node,
+                    has_hash_move,
+                );
+                search_data.set_current_reduction(prev_reduction);
+                opt_node
+            }
+        };
+
+        match opt_node {
             Some(node) => {
                 // If the score is not valid, we need to widen the aspiration window.
-                if is_valid(node.score()) {
+                if is_valid(node.score()) && !is_verification {
                     self.update_table(search_data, node);
                 }
                 Some(node)
@@ -333,37 +1282,72 @@ impl AlphaBeta {
         }
     }
 
+    // has_hash_move is passed from the table probe of the caller, so the
+    // table isn't probed again
     fn search_recursive_next_ply(
         &mut self,
         search_data: &mut SearchData,
         mut alpha: Score,
         beta: Score,
+        cut_node: bool,
+        has_hash_move: bool,
     ) -> Option<AlphaBetaEntry> {
-        if let Some(node) = self.prune_reverse_futility(search_data, alpha, beta) {
-            return Some(node);
-        }
+        // Stored before pruning, so that the improving flag is available to
+        // the pruning and to the nodes searched by it
+        let static_eval = match search_data.is_in_check(search_data.current_pos().side_to_move()) {
+            true => None,
+            false => Some(self.static_eval(search_data)),
+        };
+        search_data.set_static_eval(static_eval);
+        search_data.trace_static_eval(static_eval);
 
-        if let Some(opt_node) = self.prune_null_move(search_data, alpha, beta) {
-            return opt_node;
-        }
+        let is_verification = search_data.excluded_move() != Move::NULL;
+        if !is_verification {
+            if let Some(node) = self.prune_reverse_futility(search_data, alpha, beta) {
+                return Some(node);
+            }
 
-        let mut skip_quiets = self.prune_futility(search_data, alpha);
+            if let Some(node) = self.prune_razoring(search_data, alpha, beta) {
+                return Some(node);
+            }
 
-        let mut move_list = MoveList::new();
-        MoveGenerator::generate_moves(&mut move_list, search_data.current_pos());
-        if move_list.is_empty() {
-            return Some(self.checkmate_or_stalemate(search_data, alpha, beta));
+            if let Some(opt_node) =
+                self.prune_null_move(search_data, alpha, beta, cut_node, has_hash_move)
+            {
+                return opt_node;
+            }
+
+            if let Some(opt_node) = self.prune_probcut(search_data, alpha, beta, cut_node) {
+                return opt_node;
+            }
+
+            if let Some(opt_node) = self.prune_multicut(search_data, beta, cut_node) {
+                return opt_node;
+            }
         }
 
+        self.internal_iterative_deepening(search_data, alpha, beta, cut_node)?;
+
+        let improving = search_data.is_improving();
+
+        let mut skip_quiets = self.prune_futility(search_data, alpha, beta);
+
         let mut score_type = ScoreType::UpperBound;
         let mut best_score = NEG_INF;
         let mut best_move = Move::NULL;
+        let mut path_dependent = false;
 
         let depth = search_data.remaining_depth();
+        let singular_candidate = self.singular_extension_candidate(search_data, depth);
+        // The move ordering at an expected cut node without a hash move is
+        // probably bad, so the late moves are reduced more
+        let is_cut_node_without_hash_move = cut_node && !has_hash_move;
         let mut pvs_full_window = true;
         let mut move_count = 0;
         let mut quiets_tried = MoveList::new();
-        let mut move_selector = MoveSelector::new(move_list);
+        let mut captures_tried = MoveList::new();
+        let mut legal_move_count = 0;
+        let mut move_selector = MoveSelector::new_staged();
         let mut prev_node_count = search_data.node_counter().sum_nodes();
         search_data.reset_killers_next_ply();
         let see_margins = [
@@ -373,7 +1357,7 @@ impl AlphaBeta {
 
         while let Some(m) = move_selector.select_next_move(
             search_data,
-            &mut self.transpos_table,
+            &self.transpos_table,
             &self.counter_table,
             &self.history_table,
         ) {
@@ -383,12 +1367,20 @@ impl AlphaBeta {
                 true
             });
 
+            legal_move_count += 1;
+            if is_verification && m == search_data.excluded_move() {
+                continue;
+            }
+
             let is_pv_node = alpha != beta - 1;
             let is_quiet = !m.is_capture() && !m.is_promotion();
 
+            // Quiet moves may be skipped after futility or late move pruning.
+            // The PV move, hash moves and checking moves are always searched.
             skip_quiets |= self.prune_late_move(search_data, move_count);
             if skip_quiets
                 && is_quiet
+                && move_selector.stage() > Stage::Hash
                 && search_data.ply() != 0
                 && search_data.prev_pv_depth() == 0
                 && best_score > NEG_INF
@@ -398,8 +1390,16 @@ impl AlphaBeta {
                 continue;
             }
 
+            // Moves which lose material in the exchange on the target square.
+            // The hash move is never pruned (it is selected in an earlier
+            // stage), nor are quiet moves which give check (e.g. sacrifices
+            // for a mating attack).
+            let see_pruning_max_depth = match is_quiet {
+                true => self.search_params.see_pruning_max_depth_quiet,
+                false => self.search_params.see_pruning_max_depth,
+            };
             if search_data.ply() != 0
-                && depth <= self.search_params.see_pruning_max_depth
+                && depth <= see_pruning_max_depth
                 && best_score > NEG_INF
                 && move_selector.stage() > Stage::WinningOrEqualCaptures
                 && !see::static_exchange_eval(
@@ -407,50 +1407,145 @@ impl AlphaBeta {
                     m,
                     see_margins[is_quiet as usize],
                 )
+                && !(is_quiet && search_data.pos_history_mut().gives_check(m))
+            {
+                continue;
+            }
+
+            // Losing captures which have failed often before
+            if !is_pv_node
+                && search_data.ply() != 0
+                && depth <= CAPTURE_HISTORY_PRUNING_MAX_DEPTH
+                && best_score > NEG_INF
+                && move_selector.stage() == Stage::LosingCaptures
+                && (self
+                    .history_table
+                    .capture_value(m, search_data.current_pos()) as i32)
+                    < CAPTURE_HISTORY_PRUNING_MARGIN_PER_DEPTH * depth as i32
             {
                 continue;
             }
 
+            if search_data.ply() == 0
+                && search_data.start_time().elapsed() >= CURRENT_MOVE_INFO_MIN_TIME
+            {
+                search_data.send_info(SearchInfo::CurrentMove(CurrentMove::new(
+                    search_data.search_depth(),
+                    m,
+                    move_count + 1,
+                )));
+            }
+
+            let is_singular = match singular_candidate {
+                Some(entry) if entry.best_move() == m => {
+                    self.is_singular(search_data, entry, depth, cut_node)?
+                }
+                _ => false,
+            };
+
+            let history_value = match is_quiet {
+                true => {
+                    let p = search_data
+                        .current_pos()
+                        .piece_at(m.origin())
+                        .expect("Expected a piece at move origin");
+                    self.history_table
+                        .combined_value(p, m.target(), &search_data.prev_moves())
+                }
+                false => 0,
+            };
+            let is_recapture = search_data.is_recapture(m)
+                && see::static_exchange_eval(search_data.current_pos(), m, 0);
+
+            search_data.trace_move(m);
             search_data.do_move(m);
-            let extension = search_data.calc_extension(m);
+            let extension = match is_singular {
+                true => FRACTIONS_PER_EXTENSION,
+                false => search_data.calc_extension(m, is_recapture),
+            };
             search_data.set_current_extension(extension);
 
             // Late move reductions
+            let gives_check = search_data.is_in_check(search_data.current_pos().side_to_move());
+            // The first move isn't reduced, even if the adjustments would
+            // reduce it
             let reduction = if !is_pv_node
+                && move_count > 0
                 && depth >= MIN_LATE_MOVE_REDUCTION_DEPTH
                 && extension == 0
                 && is_quiet
+                && !Self::is_reduction_exempt(move_selector.stage(), gives_check)
             {
-                Self::late_move_depth_reduction(depth, quiets_tried.len())
+                self.late_move_depth_reduction(
+                    depth,
+                    quiets_tried.len(),
+                    improving,
+                    Self::is_killer_or_counter(move_selector.stage()),
+                    history_value,
+                    is_cut_node_without_hash_move,
+                )
             } else {
                 0
             };
             search_data.set_current_reduction(reduction);
 
-            let search_res =
-                match self.principal_variation_search(search_data, alpha, beta, pvs_full_window) {
-                    Some(node) => -node,
-                    None => return None,
-                };
+            let search_res = match self.principal_variation_search(
+                search_data,
+                alpha,
+                beta,
+                pvs_full_window,
+                cut_node,
+            ) {
+                Some(node) => -node,
+                None => return None,
+            };
             search_data.undo_last_move();
             let score = search_res.score();
 
             if score >= beta {
+                search_data.increment_beta_cutoffs(move_count == 0);
                 let node =
-                    AlphaBetaEntry::new(depth, score, ScoreType::LowerBound, m, search_data.age());
+                    AlphaBetaEntry::new(depth, score, ScoreType::LowerBound, m, search_data.age())
+                        .with_path_dependency(search_res.is_path_dependent());
                 if !m.is_capture() {
-                    search_data.insert_killer(m);
+                    // Promotions have their own stages in the move ordering
+                    if is_quiet {
+                        search_data.insert_killer(m);
+                    }
                     let last_move = search_data.pos_history().last_move().copied();
                     let last_moved_piece = search_data.pos_history().last_moved_piece();
                     if let (Some(lmp), Some(lm)) = (last_moved_piece, last_move) {
                         self.counter_table.update(lmp, lm.target(), m);
                     }
-                    self.history_table
-                        .update(m, depth, &quiets_tried, search_data.current_pos());
+                    self.history_table.update(
+                        m,
+                        depth,
+                        &quiets_tried,
+                        search_data.current_pos(),
+                        &search_data.prev_moves(),
+                    );
+                } else {
+                    self.history_table.update_captures(
+                        m,
+                        depth,
+                        &captures_tried,
+                        search_data.current_pos(),
+                    );
                 }
-
+                self.update_correction_history(search_data, &node);
                 return Some(node);
             }
+            // An upper bound or exact score depends on all moves
+            path_dependent |= search_res.is_path_dependent();
+            // The reply is only a refutation if it caused a beta cutoff in the
+            // child node. Other fail-lows (e.g. a draw or an upper bound from
+            // the child) have no refuting move.
+            if search_data.ply() == 0
+                && score <= alpha
+                && search_res.score_type() == ScoreType::LowerBound
+            {
+                search_data.add_refutation(m, search_res.best_move());
+            }
             if score > best_score {
                 best_score = score;
                 best_move = m;
@@ -475,109 +1570,457 @@ impl AlphaBeta {
             if is_quiet {
                 quiets_tried.push(m);
             }
+            if m.is_capture() {
+                captures_tried.push(m);
+            }
             if search_data.ply() == 0 {
                 let node_count = search_data.node_counter().sum_nodes();
                 search_data.set_subtree_size(m, node_count - prev_node_count);
                 prev_node_count = node_count;
             }
         }
-        let node = AlphaBetaEntry::new(depth, best_score, score_type, best_move, search_data.age());
+        if legal_move_count == 0 {
+            return Some(self.checkmate_or_stalemate(search_data, alpha, beta));
+        }
+        let node = AlphaBetaEntry::new(depth, best_score, score_type, best_move, search_data.age())
+            .with_path_dependency(path_dependent);
         debug_assert!(
             node.score_type() == ScoreType::Exact || node.score_type() == ScoreType::UpperBound
         );
+        self.update_correction_history(search_data, &node);
         Some(node)
     }
 
+    // The static evaluation can't see the outcome of captures and
+    // promotions, so only nodes with a quiet best move (or none) are used.
+    // Bounds are only used if they are on the right side of the static
+    // evaluation (e.g. a lower bound above it). Once the fifty-move scaling
+    // applies, the scores depend on the plies since the last pawn move or
+    // capture. They are not part of the hash, so the scores from the table
+    // may be scaled differently than the static evaluation. Such nodes are
+    // not used either.
+    fn update_correction_history(&mut self, search_data: &mut SearchData, node: &AlphaBetaEntry) {
+        let side_to_move = search_data.current_pos().side_to_move();
+        if search_data.is_in_check(side_to_move)
+            || search_data.excluded_move() != Move::NULL
+            || search_data.current_pos().plies_since_pawn_move_or_capture()
+                > FIFTY_MOVE_SCALING_MIN_PLIES
+            || node.best_move().is_capture()
+            || node.best_move().is_promotion()
+            || !eval::score::is_centipawns(node.score())
+        {
+            return;
+        }
+        let static_eval = self.static_eval(search_data);
+        let score = node.score();
+        match node.score_type() {
+            ScoreType::LowerBound if score <= static_eval => return,
+            ScoreType::UpperBound if score >= static_eval => return,
+            _ => {}
+        }
+        let diff = score.saturating_sub(self.uncorrected_static_eval(search_data));
+        self.correction_history.update(
+            side_to_move,
+            search_data.current_pawn_hash(),
+            node.depth(),
+            diff,
+        );
+    }
+
+    // Without a hash move, the move ordering at this node is probably bad, so
+    // it is searched at reduced depth. Only expected cut nodes are reduced:
+    // at all nodes, every move is searched anyway, so the move ordering
+    // hardly matters. Returns the reduction in plies.
+    fn internal_iterative_reduction(
+        enabled: bool,
+        depth: usize,
+        is_cut_node: bool,
+        is_in_check: bool,
+        has_hash_move: bool,
+    ) -> usize {
+        match enabled && depth >= MIN_IIR_DEPTH && is_cut_node && !is_in_check && !has_hash_move {
+            true => 1,
+            false => 0,
+        }
+    }
+
+    // The reduced search stores its best move in the hash table, where the
+    // move selector picks it up. It is not a new node, so the nodes are only
+    // counted once. Returns None if the search has been stopped.
+    fn internal_iterative_deepening(
+        &mut self,
+        search_data: &mut SearchData,
+        alpha: Score,
+        beta: Score,
+        cut_node: bool,
+    ) -> Option<()> {
+        let depth = search_data.remaining_depth();
+        let is_pv_node = alpha + 1 != beta;
+        if !is_pv_node
+            || search_data.ply() == 0
+            || depth < MIN_IID_DEPTH
+            || search_data.prev_pv_depth() != 0
+            || search_data.excluded_move() != Move::NULL
+        {
+            return Some(());
+        }
+        if let Some(entry) = self.transpos_table.get(&search_data.current_pos_hash()) {
+            if entry.best_move() != Move::NULL {
+                return Some(());
+            }
+        }
+
+        let prev_reduction = search_data.current_reduction();
+        search_data.set_current_reduction(prev_reduction + IID_DEPTH_REDUCTION);
+        let opt_res = self.search_recursive(search_data, alpha, beta, cut_node);
+        search_data.set_current_reduction(prev_reduction);
+        opt_res.map(|_| ())
+    }
+
+    // Returns the hash table entry if its move may be extended. The entry
+    // must be deep enough and its score must not be an upper bound.
+    fn singular_extension_candidate(
+        &self,
+        search_data: &SearchData,
+        depth: usize,
+    ) -> Option<AlphaBetaEntry> {
+        if search_data.ply() == 0
+            || depth < self.search_params.singular_extension_min_depth
+            || search_data.prev_pv_depth() != 0
+            || search_data.excluded_move() != Move::NULL
+            // Without an extension left, the verification would be wasted
+            || !search_data.can_extend()
+        {
+            return None;
+        }
+        match self.transpos_table.get(&search_data.current_pos_hash()) {
+            Some(entry)
+                if entry.best_move() != Move::NULL
+                    && entry.depth()
+                        + self.search_params.singular_extension_max_table_depth_diff
+                        >= depth
+                    && entry.score_type() != ScoreType::UpperBound
+                    && !entry.is_path_dependent()
+                    && eval::score::is_centipawns(entry.score()) =>
+            {
+                Some(entry)
+            }
+            _ => None,
+        }
+    }
+
+    // The hash move is singular if all other moves fail low against a bound
+    // below the hash table score. They are searched with a null window at
+    // reduced depth. Returns None if the search has been stopped.
+    fn is_singular(
+        &mut self,
+        search_data: &mut SearchData,
+        entry: AlphaBetaEntry,
+        depth: usize,
+        cut_node: bool,
+    ) -> Option<bool> {
+        let singular_beta =
+            entry.score() - self.search_params.singular_extension_margin_per_depth * depth as Score;
+        let reduction = depth - (depth - 1) / 2;
+        let prev_reduction = search_data.current_reduction();
+        search_data.set_current_reduction(prev_reduction + reduction);
+        search_data.set_excluded_move(entry.best_move());
+        let opt_res =
+            self.search_recursive(search_data, singular_beta - 1, singular_beta, cut_node);
+        search_data.set_excluded_move(Move::NULL);
+        search_data.set_current_reduction(prev_reduction);
+        opt_res.map(|res| res.score() < singular_beta)
+    }
+
+    // The children of cut nodes are expected to be all nodes and vice versa.
+    // If a child doesn't behave as expected and is searched again, its
+    // expected node type flips.
     fn principal_variation_search(
         &mut self,
         search_data: &mut SearchData<'_>,
         alpha: Score,
         beta: Score,
         pvs_full_window: bool,
+        cut_node: bool,
     ) -> Option<AlphaBetaEntry> {
         if (pvs_full_window || search_data.remaining_depth() < MIN_PVS_DEPTH)
             && search_data.total_reductions() == 0
         {
             // Full depth, full window search
-            return self.search_recursive(search_data, -beta, -alpha);
+            return self.search_recursive(search_data, -beta, -alpha, !cut_node);
         }
 
         // Reduced depth, null window search
-        let mut neg_res = self.search_recursive(search_data, -alpha - 1, -alpha)?;
+        let mut neg_res = self.search_recursive(search_data, -alpha - 1, -alpha, !cut_node)?;
         let score = -neg_res.score();
         if score > alpha && score < beta && search_data.current_reduction() != 0 {
             search_data.set_current_reduction(0);
             // Full depth, null window search
-            match self.search_recursive(search_data, -alpha - 1, -alpha) {
+            match self.search_recursive(search_data, -alpha - 1, -alpha, cut_node) {
                 Some(nr) => neg_res = nr,
                 None => return None,
             }
         }
-        let score = -neg_res.score();
-        if score > alpha && score < beta && search_data.total_reductions() == 0 {
-            // Full depth, full window search
-            self.search_recursive(search_data, -beta, -alpha)
-        } else {
-            Some(neg_res)
-        }
+        let score = -neg_res.score();
+        if score > alpha && score < beta && search_data.total_reductions() == 0 {
+            // Full depth, full window search
+            self.search_recursive(search_data, -beta, -alpha, false)
+        } else {
+            Some(neg_res)
+        }
+    }
+
+    // Meaning of the outer and inner options:
+    // - outer option: if None is returned, the condition for null move pruning
+    //   is not fulfilled (score < beta)
+    // - inner option: if Some(None) is returned, the search has been stopped
+    //   (time limit, node limit, stop command)
+    fn prune_null_move(
+        &mut self,
+        search_data: &mut SearchData<'_>,
+        alpha: Score,
+        beta: Score,
+        cut_node: bool,
+        has_hash_move: bool,
+    ) -> Option<Option<AlphaBetaEntry>> {
+        let depth = search_data.remaining_depth();
+        if depth >= MIN_NULL_MOVE_PRUNE_DEPTH
+            && search_data.ply() >= search_data.null_move_min_ply()
+            && search_data.prev_pv_depth() == 0
+            && search_data.pos_history().last_move() != Some(&Move::NULL)
+            && !search_data.is_in_check(search_data.current_pos().side_to_move())
+            && self.static_eval(search_data) >= beta
+            && (!self.search_params.null_move_zugzwang_guard
+                || search_data
+                    .current_pos()
+                    .has_minor_or_major_piece(search_data.current_pos().side_to_move()))
+        {
+            let reduction = Self::null_move_depth_reduction(depth);
+            search_data.do_move(Move::NULL);
+            search_data.set_current_reduction(reduction);
+            let opt_neg_res = self.search_recursive(search_data, -beta, -alpha, !cut_node);
+            search_data.undo_last_move();
+            match opt_neg_res {
+                Some(neg_search_res) => {
+                    let search_res = -neg_search_res;
+                    let score = search_res.score();
+                    if score >= beta {
+                        let node = AlphaBetaEntry::new(
+                            depth,
+                            score,
+                            ScoreType::LowerBound,
+                            Move::NULL,
+                            search_data.age(),
+                        )
+                        .with_path_dependency(search_res.is_path_dependent());
+                        if depth < self.search_params.null_move_verification_min_depth
+                            || search_data.ply() == 0
+                        {
+                            return Some(Some(node));
+                        }
+                        match self.verify_null_move(
+                            search_data,
+                            beta,
+                            reduction,
+                            cut_node,
+                            has_hash_move,
+                        ) {
+                            Some(true) => return Some(Some(node)),
+                            Some(false) => {}
+                            None => return Some(None),
+                        }
+                    }
+                }
+                None => return Some(None),
+            }
+        }
+        None
+    }
+
+    // If a good capture beats beta by a margin in a reduced depth search, the
+    // full depth search will very likely fail high, too. The captures are
+    // verified by a quiescence search first. The outer and inner options have
+    // the same meaning as for null move pruning.
+    fn prune_probcut(
+        &mut self,
+        search_data: &mut SearchData<'_>,
+        alpha: Score,
+        beta: Score,
+        cut_node: bool,
+    ) -> Option<Option<AlphaBetaEntry>> {
+        let depth = search_data.remaining_depth();
+        let is_pv_node = alpha + 1 != beta;
+        if is_pv_node
+            || depth < self.search_params.probcut_min_depth
+            || search_data.prev_pv_depth() != 0
+            || !eval::score::is_centipawns(beta)
+            || search_data.is_in_check(search_data.current_pos().side_to_move())
+        {
+            return None;
+        }
+        let probcut_beta = beta + self.search_params.probcut_margin;
+        let see_threshold = probcut_beta - self.static_eval(search_data);
+        let reduction = self.search_params.probcut_depth_reduction;
+        let mut move_list = MoveList::new();
+        MoveGenerator::generate_moves_quiescence(&mut move_list, search_data.current_pos());
+        let mut move_selector = MoveSelector::new(move_list);
+        while let Some(m) = move_selector.select_next_move_quiescence_capture(
+            search_data,
+            &self.transpos_table,
+            &self.history_table,
+        ) {
+            if !m.is_capture()
+                || !see::static_exchange_eval(search_data.current_pos(), m, see_threshold)
+            {
+                continue;
+            }
+            search_data.do_move(m);
+            let mut neg_res = self.search_quiescence(search_data, -probcut_beta, -probcut_beta + 1);
+            if -neg_res.score() >= probcut_beta {
+                search_data.set_current_reduction(reduction);
+                neg_res = match self.search_recursive(
+                    search_data,
+                    -probcut_beta,
+                    -probcut_beta + 1,
+                    !cut_node,
+                ) {
+                    Some(nr) => nr,
+                    None => return Some(None),
+                };
+            }
+            search_data.undo_last_move();
+            let score = -neg_res.score();
+            if score >= probcut_beta {
+                // The result is only as reliable as the reduced search, so
+                // don't store it with the full depth
+                let node = AlphaBetaEntry::new(
+                    depth - reduction,
+                    score,
+                    ScoreType::LowerBound,
+                    m,
+                    search_data.age(),
+                )
+                .with_path_dependency(neg_res.is_path_dependent());
+                return Some(Some(node));
+            }
+        }
+        None
     }
 
-    // Meaning of the outer and inner options:
-    // - outer option: if None is returned, the condition for null move pruning
-    //   is not fulfilled (score < beta)
-    // - inner option: if Some(None) is returned, the search has been stopped
-    //   (time limit, node limit, stop command)
-    fn prune_null_move(
+    // If several of the first moves beat beta by a margin in a reduced depth
+    // search, at least one of them will very likely fail high in the full
+    // depth search, too. Only applied at expected cut nodes, where some move
+    // is expected to fail high. Not applied during a singular extension
+    // verification, because the pruning could hide the moves being verified.
+    // The outer and inner options have the same meaning as for null move
+    // pruning.
+    fn prune_multicut(
         &mut self,
         search_data: &mut SearchData<'_>,
-        alpha: Score,
         beta: Score,
+        cut_node: bool,
     ) -> Option<Option<AlphaBetaEntry>> {
         let depth = search_data.remaining_depth();
-        if depth >= MIN_NULL_MOVE_PRUNE_DEPTH
-            && search_data.prev_pv_depth() == 0
-            && search_data.pos_history().last_move() != Some(&Move::NULL)
-            && !search_data.is_in_check(search_data.current_pos().side_to_move())
-            && search_data.eval_relative(&mut self.evaluator) >= beta
-            && search_data
-                .current_pos()
-                .has_minor_or_major_piece(search_data.current_pos().side_to_move())
+        if !cut_node
+            || depth < self.search_params.multicut_min_depth
+            || search_data.ply() == 0
+            || search_data.prev_pv_depth() != 0
+            || search_data.is_singular_verification()
+            || !eval::score::is_centipawns(beta)
+            || search_data.is_in_check(search_data.current_pos().side_to_move())
         {
-            let reduction = Self::null_move_depth_reduction(depth);
-            search_data.do_move(Move::NULL);
+            return None;
+        }
+        let multicut_beta = beta + self.search_params.multicut_margin;
+        let reduction = self.search_params.multicut_depth_reduction;
+        let mut cutoffs = 0;
+        let mut path_dependent = false;
+        let mut move_selector = MoveSelector::new_staged();
+        for _ in 0..self.search_params.multicut_moves {
+            let m = match move_selector.select_next_move(
+                search_data,
+                &self.transpos_table,
+                &self.counter_table,
+                &self.history_table,
+            ) {
+                Some(m) => m,
+                None => break,
+            };
+            search_data.do_move(m);
             search_data.set_current_reduction(reduction);
-            let opt_neg_res = self.search_recursive(search_data, -beta, -alpha);
+            let opt_neg_res =
+                self.search_recursive(search_data, -multicut_beta, -multicut_beta + 1, !cut_node);
             search_data.undo_last_move();
-            match opt_neg_res {
-                Some(neg_search_res) => {
-                    let search_res = -neg_search_res;
-                    let score = search_res.score();
-                    if score >= beta {
-                        let node = AlphaBetaEntry::new(
-                            depth,
-                            score,
-                            ScoreType::LowerBound,
-                            Move::NULL,
-                            search_data.age(),
-                        );
-                        return Some(Some(node));
-                    }
-                }
+            let neg_res = match opt_neg_res {
+                Some(nr) => nr,
                 None => return Some(None),
+            };
+            if -neg_res.score() >= multicut_beta {
+                cutoffs += 1;
+                path_dependent |= neg_res.is_path_dependent();
+                if cutoffs >= self.search_params.multicut_required_cutoffs {
+                    // The result is only as reliable as the reduced searches
+                    let node = AlphaBetaEntry::new(
+                        depth.saturating_sub(reduction),
+                        beta,
+                        ScoreType::LowerBound,
+                        Move::NULL,
+                        search_data.age(),
+                    )
+                    .with_path_dependency(path_dependent);
+                    return Some(Some(node));
+                }
             }
         }
         None
     }
 
-    fn prune_futility(&mut self, search_data: &mut SearchData<'_>, alpha: Score) -> bool {
+    // Searches the position again at reduced depth without null moves for the
+    // next plies. Returns whether the null move cutoff is confirmed or None if
+    // the search has been stopped.
+    fn verify_null_move(
+        &mut self,
+        search_data: &mut SearchData<'_>,
+        beta: Score,
+        reduction: usize,
+        cut_node: bool,
+        has_hash_move: bool,
+    ) -> Option<bool> {
+        let verification_depth = search_data.remaining_depth() - reduction;
+        let prev_min_ply = search_data.null_move_min_ply();
+        let prev_reduction = search_data.current_reduction();
+        search_data.set_null_move_min_ply(search_data.ply() + 1 + 3 * verification_depth / 4);
+        search_data.set_current_reduction(prev_reduction + reduction);
+        let opt_res =
+            self.search_recursive_next_ply(search_data, beta - 1, beta, cut_node, has_hash_move);
+        search_data.set_current_reduction(prev_reduction);
+        search_data.set_null_move_min_ply(prev_min_ply);
+        opt_res.map(|res| res.score() >= beta)
+    }
+
+    // Frontier nodes where the static evaluation plus the margin can't reach
+    // alpha. Not applied in PV nodes.
+    fn prune_futility(
+        &mut self,
+        search_data: &mut SearchData<'_>,
+        alpha: Score,
+        beta: Score,
+    ) -> bool {
         let depth = search_data.remaining_depth();
-        if depth <= self.search_params.futility_pruning_max_depth
+        let is_pv_node = alpha + 1 != beta;
+        if !is_pv_node
+            && depth <= self.search_params.futility_pruning_max_depth
             && !search_data.is_in_check(search_data.current_pos().side_to_move())
         {
-            let score = search_data.eval_relative(&mut self.evaluator);
+            let score = self.static_eval(search_data);
+            let improving_margin = match search_data.is_improving() {
+                true => self.search_params.futility_margin_improving,
+                false => 0,
+            };
             if score
                 + self.search_params.futility_margin_base
                 + (depth - 1) as Score * self.search_params.futility_margin_per_depth
+                + improving_margin
                 < alpha
             {
                 return true;
@@ -600,10 +2043,15 @@ impl AlphaBeta {
         {
             debug_assert_ne!(search_data.ply(), 0);
             debug_assert_eq!(search_data.prev_pv_depth(), 0);
-            let score = search_data.eval_relative(&mut self.evaluator);
+            let score = self.static_eval(search_data);
+            let improving_margin = match search_data.is_improving() {
+                true => self.search_params.reverse_futility_margin_improving,
+                false => 0,
+            };
             if score
                 - self.search_params.reverse_futility_margin_base
                 - (depth - 1) as Score * self.search_params.reverse_futility_margin_per_depth
+                + improving_margin
                 >= beta
             {
                 let node = AlphaBetaEntry::new(
@@ -619,20 +2067,63 @@ impl AlphaBeta {
         None
     }
 
+    // If the static evaluation is far below alpha, only a capture sequence can
+    // save us. Return the quiescence search result if it doesn't get above the
+    // razoring margin either.
+    fn prune_razoring(
+        &mut self,
+        search_data: &mut SearchData<'_>,
+        alpha: Score,
+        beta: Score,
+    ) -> Option<AlphaBetaEntry> {
+        let depth = search_data.remaining_depth();
+        let is_pv_node = alpha != beta - 1;
+        if !is_pv_node
+            && depth <= self.search_params.razoring_max_depth
+            && !search_data.is_in_check(search_data.current_pos().side_to_move())
+        {
+            let razor_alpha = alpha
+                - self.search_params.razoring_margin_base
+                - (depth - 1) as Score * self.search_params.razoring_margin_per_depth;
+            if self.static_eval(search_data) < razor_alpha {
+                let node = self.search_quiescence(search_data, razor_alpha, razor_alpha + 1);
+                if node.score() <= razor_alpha {
+                    return Some(node);
+                }
+            }
+        }
+        None
+    }
+
     fn prune_late_move(&self, search_data: &mut SearchData<'_>, move_count: usize) -> bool {
         let depth = search_data.remaining_depth();
-        let late_move_count = self.search_params.late_move_pruning_base
+        let mut late_move_count = self.search_params.late_move_pruning_base
             + self.search_params.late_move_pruning_factor * depth * depth;
+        if !search_data.is_improving() {
+            late_move_count =
+                late_move_count * self.search_params.late_move_pruning_not_improving_percent / 100;
+        }
         depth <= self.search_params.late_move_pruning_max_depth
             && move_count >= late_move_count
             && !search_data.is_in_check(search_data.current_pos().side_to_move())
     }
 
     fn search_quiescence(
+        &mut self,
+        search_data: &mut SearchData,
+        alpha: Score,
+        beta: Score,
+    ) -> AlphaBetaEntry {
+        self.search_quiescence_ply(search_data, alpha, beta, 0)
+    }
+
+    // qs_ply is the number of plies since entering the quiescence search
+    fn search_quiescence_ply(
         &mut self,
         search_data: &mut SearchData,
         mut alpha: Score,
         beta: Score,
+        qs_ply: usize,
     ) -> AlphaBetaEntry {
         let depth = 0;
 
@@ -640,6 +2131,9 @@ impl AlphaBeta {
         if let Some(entry) = Self::is_draw(search_data, is_pv_node) {
             return entry;
         }
+        if search_data.ply() >= MAX_PLY {
+            return self.max_ply_entry(search_data);
+        }
 
         if let Some(entry) = self.lookup_table_entry(search_data) {
             if let Some(bounded) = entry.bound_soft(alpha, beta) {
@@ -650,16 +2144,20 @@ impl AlphaBeta {
 
         let is_in_check = search_data.is_in_check(search_data.current_pos().side_to_move());
         if is_in_check {
-            return self.search_quiescence_check(search_data, alpha, beta);
+            return self.search_quiescence_check(search_data, alpha, beta, qs_ply);
         }
 
         // We might be evaluating a stalemate here. This is ok for now because checking for legal
         // moves is expensive here.
-        let stand_pat = search_data.eval_relative(&mut self.evaluator);
+        // Standing pat uses the corrected evaluation, like the pruning in the
+        // main search, so that the leaves agree with the margins of the nodes
+        // above them.
+        let stand_pat = self.static_eval(search_data);
         let mut score = stand_pat;
         let mut score_type = ScoreType::UpperBound;
         let mut best_score = stand_pat;
         let mut best_move = Move::NULL;
+        let mut path_dependent = false;
 
         if score >= beta {
             let node = AlphaBetaEntry::new(
@@ -686,25 +2184,29 @@ impl AlphaBeta {
         let mut move_list = MoveList::new();
         MoveGenerator::generate_moves_quiescence(&mut move_list, search_data.current_pos());
         let mut move_selector = MoveSelector::new(move_list);
-        while let Some(m) =
-            move_selector.select_next_move_quiescence_capture(search_data, &mut self.transpos_table)
-        {
+        while let Some(m) = move_selector.select_next_move_quiescence_capture(
+            search_data,
+            &self.transpos_table,
+            &self.history_table,
+        ) {
             let potential_improvement = see::gained_material_value(search_data.current_pos(), m);
             if stand_pat + potential_improvement + DELTA_PRUNING_MARGIN_MOVE < alpha {
                 continue;
             }
 
             search_data.do_move(m);
-            let search_result = -self.search_quiescence(search_data, -beta, -alpha);
+            let search_result = -self.search_quiescence_ply(search_data, -beta, -alpha, qs_ply + 1);
             score = search_result.score();
             search_data.undo_last_move();
 
             if score >= beta {
                 let node =
-                    AlphaBetaEntry::new(depth, score, ScoreType::LowerBound, m, search_data.age());
+                    AlphaBetaEntry::new(depth, score, ScoreType::LowerBound, m, search_data.age())
+                        .with_path_dependency(search_result.is_path_dependent());
                 self.update_table(search_data, node);
                 return node;
             }
+            path_dependent |= search_result.is_path_dependent();
             if score > best_score {
                 best_score = score;
                 best_move = m;
@@ -714,8 +2216,47 @@ impl AlphaBeta {
                 }
             }
         }
+
+        if qs_ply < QUIESCENCE_CHECK_PLIES {
+            let mut move_list = MoveList::new();
+            MoveGenerator::generate_quiet_checks(&mut move_list, search_data.current_pos());
+            for m in move_list.iter().copied() {
+                if !see::static_exchange_eval(search_data.current_pos(), m, 0) {
+                    continue;
+                }
+
+                search_data.do_move(m);
+                let search_result =
+                    -self.search_quiescence_ply(search_data, -beta, -alpha, qs_ply + 1);
+                score = search_result.score();
+                search_data.undo_last_move();
+
+                if score >= beta {
+                    let node = AlphaBetaEntry::new(
+                        depth,
+                        score,
+                        ScoreType::LowerBound,
+                        m,
+                        search_data.age(),
+                    )
+                    .with_path_dependency(search_result.is_path_dependent());
+                    self.update_table(search_data, node);
+                    return node;
+                }
+                path_dependent |= search_result.is_path_dependent();
+                if score > best_score {
+                    best_score = score;
+                    best_move = m;
+                    if score > alpha {
+                        alpha = score;
+                        score_type = ScoreType::Exact;
+                    }
+                }
+            }
+        }
         debug_assert!(score_type == ScoreType::Exact || score_type == ScoreType::UpperBound);
-        let node = AlphaBetaEntry::new(depth, best_score, score_type, best_move, search_data.age());
+        let node = AlphaBetaEntry::new(depth, best_score, score_type, best_move, search_data.age())
+            .with_path_dependency(path_dependent);
         self.update_table(search_data, node);
         node
     }
@@ -725,6 +2266,7 @@ impl AlphaBeta {
         search_data: &mut SearchData,
         mut alpha: Score,
         beta: Score,
+        qs_ply: usize,
     ) -> AlphaBetaEntry {
         debug_assert!(search_data.is_in_check(search_data.current_pos().side_to_move()));
 
@@ -734,6 +2276,7 @@ impl AlphaBeta {
         let mut score_type = ScoreType::UpperBound;
         let mut best_score = NEG_INF;
         let mut best_move = Move::NULL;
+        let mut path_dependent = false;
 
         let mut move_list = MoveList::new();
         MoveGenerator::generate_moves(&mut move_list, search_data.current_pos());
@@ -751,12 +2294,13 @@ impl AlphaBeta {
             let mut move_selector = MoveSelector::new(move_list);
             while let Some(m) = move_selector.select_next_move(
                 search_data,
-                &mut self.transpos_table,
+                &self.transpos_table,
                 &self.counter_table,
                 &self.history_table,
             ) {
                 search_data.do_move(m);
-                let search_result = -self.search_quiescence(search_data, -beta, -alpha);
+                let search_result =
+                    -self.search_quiescence_ply(search_data, -beta, -alpha, qs_ply + 1);
                 score = search_result.score();
                 search_data.undo_last_move();
 
@@ -767,10 +2311,12 @@ impl AlphaBeta {
                         ScoreType::LowerBound,
                         m,
                         search_data.age(),
-                    );
+                    )
+                    .with_path_dependency(search_result.is_path_dependent());
                     self.update_table(search_data, node);
                     return node;
                 }
+                path_dependent |= search_result.is_path_dependent();
                 if score > best_score {
                     best_score = score;
                     best_move = m;
@@ -783,7 +2329,8 @@ impl AlphaBeta {
         }
 
         debug_assert!(score_type == ScoreType::Exact || score_type == ScoreType::UpperBound);
-        let node = AlphaBetaEntry::new(depth, best_score, score_type, best_move, search_data.age());
+        let node = AlphaBetaEntry::new(depth, best_score, score_type, best_move, search_data.age())
+            .with_path_dependency(path_dependent);
         self.update_table(search_data, node);
         node
     }
@@ -796,10 +2343,14 @@ impl AlphaBeta {
         );
     }
 
-    fn lookup_table_entry(&mut self, search_data: &SearchData<'_>) -> Option<AlphaBetaEntry> {
+    // Path-dependent entries only provide a best move, their scores are never
+    // returned
+    fn lookup_table_entry(&mut self, search_data: &mut SearchData<'_>) -> Option<AlphaBetaEntry> {
         let depth = search_data.remaining_depth();
-        match self.transpos_table.get(&search_data.current_pos_hash()) {
-            Some(entry) if entry.depth() >= depth => {
+        let entry = self.transpos_table.get(&search_data.current_pos_hash());
+        search_data.increment_table_probes(entry.is_some());
+        match entry {
+            Some(entry) if entry.depth() >= depth && !entry.is_path_dependent() => {
                 // Convert mate distance from the current position to the search root
                 Some(entry.with_increased_mate_distance(search_data.ply()))
             }
@@ -813,34 +2364,51 @@ impl AlphaBeta {
         alpha: Score,
         beta: Score,
     ) -> Option<AlphaBetaEntry> {
-        if let Some(entry) = self.lookup_table_entry(search_data) {
-            if let Some(bounded) = entry.bound_soft(alpha, beta) {
-                search_data.increment_cache_hits();
-                match (bounded.score_type(), search_data.remaining_depth()) {
-                    (ScoreType::Exact, 0) => return Some(bounded),
-                    (ScoreType::Exact, 1) => {
-                        search_data.update_pv_move_and_truncate(bounded.best_move());
-                        // Root move ordering: move the new best move to the front
-                        if search_data.ply() == 0 {
-                            search_data.move_to_front(bounded.best_move());
-                        }
-                        return Some(bounded);
-                    }
-                    (ScoreType::Exact, _) => {
-                        // For greater depths, we need to keep searching in order to obtain the PV
-                    }
-                    _ => {
-                        // We're not in a PV node, but it might be in the previous search depth's PV.
-                        // So we make sure to remove it.
-                        search_data.end_prev_pv();
-                        return Some(bounded);
+        let Some(entry) = self.lookup_table_entry(search_data) else {
+            search_data.trace_table_probe(TableProbe::Miss);
+            return None;
+        };
+        search_data.trace_table_probe(TableProbe::Hit);
+        if let Some(bounded) = entry.bound_soft(alpha, beta) {
+            search_data.increment_cache_hits();
+            match (bounded.score_type(), search_data.remaining_depth()) {
+                (ScoreType::Exact, 0) => {}
+                (ScoreType::Exact, 1) => {
+                    search_data.update_pv_move_and_truncate(bounded.best_move());
+                    // Root move ordering: move the new best move to the front.
+                    // There is no best move if the root position is
+                    // checkmate or stalemate.
+                    if search_data.ply() == 0 && bounded.best_move() != Move::NULL {
+                        search_data.move_to_front(bounded.best_move());
                     }
                 }
+                (ScoreType::Exact, _) => {
+                    // For greater depths, we need to keep searching in order to obtain the PV
+                    return None;
+                }
+                _ => {
+                    // We're not in a PV node, but it might be in the previous search depth's PV.
+                    // So we make sure to remove it.
+                    search_data.end_prev_pv();
+                }
             }
+            search_data.trace_table_probe(TableProbe::Cutoff);
+            return Some(bounded);
         }
         None
     }
 
+    // The search doesn't go beyond MAX_PLY. The score is the static evaluation
+    // (or a draw if in check), which depends on the path to the node.
+    fn max_ply_entry(&mut self, search_data: &mut SearchData) -> AlphaBetaEntry {
+        let score = match search_data.is_in_check(search_data.current_pos().side_to_move()) {
+            true => search_data.draw_score(),
+            false => self.static_eval(search_data),
+        };
+        AlphaBetaEntry::new(0, score, ScoreType::Exact, Move::NULL, search_data.age())
+            .with_path_dependency(true)
+    }
+
     fn is_draw(search_data: &mut SearchData, is_pv_node: bool) -> Option<AlphaBetaEntry> {
         let node =
             Self::is_draw_by_rep(search_data).or_else(|| Self::is_draw_by_moves(search_data))?;
@@ -851,17 +2419,27 @@ impl AlphaBeta {
         Some(node)
     }
 
+    // A repetition of a position that occurred after the root is a draw.
+    // Repetitions of positions from the game history need the full threefold
+    // repetition.
     fn is_draw_by_rep(search_data: &mut SearchData) -> Option<AlphaBetaEntry> {
-        if search_data.pos_history().current_pos_repetitions() < REPETITIONS_TO_DRAW {
+        let pos_history = search_data.pos_history();
+        let repeats_in_tree = pos_history
+            .plies_since_last_occurrence()
+            .is_some_and(|plies| plies < search_data.ply());
+        if !repeats_in_tree && pos_history.current_pos_repetitions() < REPETITIONS_TO_DRAW {
             return None;
         }
-        Some(AlphaBetaEntry::new(
-            search_data.remaining_depth(),
-            EQ_POSITION,
-            ScoreType::Exact,
-            Move::NULL,
-            search_data.age(),
-        ))
+        Some(
+            AlphaBetaEntry::new(
+                search_data.remaining_depth(),
+                search_data.draw_score(),
+                ScoreType::Exact,
+                Move::NULL,
+                search_data.age(),
+            )
+            .with_path_dependency(true),
+        )
     }
 
     fn is_draw_by_moves(search_data: &mut SearchData) -> Option<AlphaBetaEntry> {
@@ -870,7 +2448,7 @@ impl AlphaBeta {
         {
             return None;
         }
-        let mut score = EQ_POSITION;
+        let mut score = search_data.draw_score();
         if search_data.is_in_check(search_data.current_pos().side_to_move()) {
             let mut move_list = MoveList::new();
             MoveGenerator::generate_moves(&mut move_list, search_data.current_pos());
@@ -878,13 +2456,49 @@ impl AlphaBeta {
                 score = BLACK_WIN + search_data.ply() as Score;
             }
         }
-        Some(AlphaBetaEntry::new(
-            search_data.remaining_depth(),
-            score,
-            ScoreType::Exact,
-            Move::NULL,
-            search_data.age(),
-        ))
+        Some(
+            AlphaBetaEntry::new(
+                search_data.remaining_depth(),
+                score,
+                ScoreType::Exact,
+                Move::NULL,
+                search_data.age(),
+            )
+            .with_path_dependency(true),
+        )
+    }
+
+    // The static evaluation plus the correction history of the pawn
+    // structure
+    fn static_eval(&mut self, search_data: &mut SearchData) -> Score {
+        let correction = self.correction_history.correction(
+            search_data.current_pos().side_to_move(),
+            search_data.current_pawn_hash(),
+        );
+        self.uncorrected_static_eval(search_data) + correction
+    }
+
+    // The eval cache stores the unscaled evaluation. The fifty move scaling
+    // depends on the plies since the last pawn move or capture, which are not
+    // part of the hash, so it's applied after the lookup.
+    fn uncorrected_static_eval(&mut self, search_data: &mut SearchData) -> Score {
+        let eval = search_data.eval_relative(&mut self.evaluator, &mut self.eval_cache);
+        Self::scale_for_fifty_move_rule(
+            eval,
+            search_data.current_pos().plies_since_pawn_move_or_capture(),
+        )
+    }
+
+    // Scales linearly from the full evaluation at the minimum plies. The
+    // evaluation is never scaled down more than with (200 - plies) / 200.
+    fn scale_for_fifty_move_rule(eval: Score, plies: usize) -> Score {
+        if plies <= FIFTY_MOVE_SCALING_MIN_PLIES {
+            return eval;
+        }
+        let scaled_plies = (plies.min(PLIES_WITHOUT_PAWN_MOVE_OR_CAPTURE_TO_DRAW)
+            - FIFTY_MOVE_SCALING_MIN_PLIES) as i32;
+        (eval as i32 * (FIFTY_MOVE_SCALING_DIVISOR - scaled_plies) / FIFTY_MOVE_SCALING_DIVISOR)
+            as Score
     }
 
     fn null_move_depth_reduction(depth: usize) -> usize {
@@ -904,7 +2518,7 @@ impl AlphaBeta {
         let score = if search_data.is_in_check(pos.side_to_move()) {
             BLACK_WIN + search_data.ply() as Score
         } else {
-            EQ_POSITION
+            search_data.draw_score()
         };
         let depth = search_data.remaining_depth();
         if score >= beta {
@@ -919,8 +2533,544 @@ impl AlphaBeta {
         AlphaBetaEntry::new(depth, score, score_type, best_move, search_data.age())
     }
 
-    fn late_move_depth_reduction(depth: usize, move_count: usize) -> usize {
+    // Moves which are likely to be good are never reduced, wherever they are
+    // in the move order: the PV and hash moves and moves which give check.
+    fn is_reduction_exempt(stage: Stage, gives_check: bool) -> bool {
+        gives_check || matches!(stage, Stage::PrincipalVariation | Stage::Hash)
+    }
+
+    // Killers and counter moves are reduced less, wherever they are in the
+    // move order
+    fn is_killer_or_counter(stage: Stage) -> bool {
+        matches!(stage, Stage::Killers | Stage::Counters)
+    }
+
+    // The base reduction depends on the depth and the move count. The
+    // adjustments may increase or decrease it, but at least one ply is left.
+    fn late_move_depth_reduction(
+        &self,
+        depth: usize,
+        move_count: usize,
+        improving: bool,
+        is_killer_or_counter: bool,
+        history_value: i32,
+        is_cut_node_without_hash_move: bool,
+    ) -> usize {
         debug_assert!(depth >= MIN_LATE_MOVE_REDUCTION_DEPTH);
-        ((move_count + 1) / 6).min(depth / 3)
+        let params = &self.search_params;
+        let base = ((move_count + 1) / 6).min(depth / 3) as i32;
+        let mut reduction = base - history_value / params.late_move_reduction_history_divisor;
+        if !improving {
+            reduction += params.late_move_reduction_not_improving;
+        }
+        if is_cut_node_without_hash_move {
+            reduction += params.late_move_reduction_cut_node;
+        }
+        if is_killer_or_counter {
+            reduction -= params.late_move_reduction_killer_or_counter;
+        }
+        reduction.clamp(0, depth as i32 - 1) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_table::HISTORY_MAX;
+    use eval::material_mobility::MaterialMobility;
+    use movegen::fen::Fen;
+    use movegen::position::Position;
+    use movegen::r#move::MoveType;
+    use movegen::square::Square;
+
+    #[test]
+    fn late_move_depth_reduction() {
+        let search = AlphaBeta::new(Box::new(MaterialMobility::new()), 0);
+        let depth = 9;
+        let move_count = 12;
+        let lmr =
+            |improving, is_killer_or_counter, history_value, is_cut_node_without_hash_move| {
+                search.late_move_depth_reduction(
+                    depth,
+                    move_count,
+                    improving,
+                    is_killer_or_counter,
+                    history_value,
+                    is_cut_node_without_hash_move,
+                )
+            };
+        let base = 2;
+        assert_eq!(base, lmr(true, false, 0, false));
+        assert_eq!(base + 1, lmr(false, false, 0, false));
+        assert_eq!(base - 1, lmr(true, true, 0, false));
+        assert_eq!(base + 1, lmr(true, false, 0, true));
+        assert_eq!(base - 1, lmr(true, false, HISTORY_MAX, false));
+        assert_eq!(base + 1, lmr(true, false, -HISTORY_MAX, false));
+        // The adjustments add up, but the reduction stays within its bounds
+        assert_eq!(base + 2, lmr(false, false, 0, true));
+        assert_eq!(base, lmr(false, true, 0, false));
+        assert_eq!(0, lmr(true, false, 3 * HISTORY_MAX, false));
+        assert_eq!(depth - 1, lmr(false, false, -20 * HISTORY_MAX, true));
+    }
+
+    #[test]
+    fn reduction_exempt_moves() {
+        let exempt = |stage| AlphaBeta::is_reduction_exempt(stage, false);
+        assert!(exempt(Stage::PrincipalVariation));
+        assert!(exempt(Stage::Hash));
+        assert!(!exempt(Stage::Killers));
+        assert!(!exempt(Stage::Counters));
+        assert!(!exempt(Stage::History));
+        assert!(!exempt(Stage::WinningOrEqualCaptures));
+        assert!(!exempt(Stage::LosingCaptures));
+        assert!(!exempt(Stage::UnderPromos));
+        // Checking moves are exempt in any stage
+        assert!(AlphaBeta::is_reduction_exempt(Stage::History, true));
+        assert!(AlphaBeta::is_reduction_exempt(Stage::LosingCaptures, true));
+
+        // Killers and counter moves are reduced less instead
+        assert!(AlphaBeta::is_killer_or_counter(Stage::Killers));
+        assert!(AlphaBeta::is_killer_or_counter(Stage::Counters));
+        assert!(!AlphaBeta::is_killer_or_counter(Stage::Hash));
+        assert!(!AlphaBeta::is_killer_or_counter(Stage::History));
+    }
+
+    #[test]
+    fn complete_pv_stops_at_illegal_hash_move() {
+        let search = AlphaBeta::new(Box::new(MaterialMobility::new()), 1024 * 1024);
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        let e7e5 = Move::new(Square::E7, Square::E5, MoveType::DOUBLE_PAWN_PUSH);
+        let e2e3 = Move::new(Square::E2, Square::E3, MoveType::QUIET);
+        let insert = |pos_history: &PositionHistory, m| {
+            let entry = AlphaBetaEntry::new(5, 0, ScoreType::Exact, m, 0);
+            search
+                .transpos_table
+                .insert(pos_history.current_pos_hash(), entry);
+        };
+        let mut pos_history = PositionHistory::new(Position::initial());
+        insert(&pos_history, e2e4);
+        pos_history.do_move(e2e4);
+        insert(&pos_history, e7e5);
+        pos_history.do_move(e7e5);
+        // A stale entry, white can't play e7e5
+        insert(&pos_history, e7e5);
+
+        let root_pos_history = PositionHistory::new(Position::initial());
+        let mut pv = MoveList::new();
+        search.complete_pv(root_pos_history.clone(), &mut pv, 6);
+        assert_eq!(MoveList::from(vec![e2e4, e7e5]), pv);
+
+        // An illegal move in the PV itself is dropped before the PV is
+        // completed
+        let mut pv = MoveList::from(vec![e2e4, e2e3]);
+        search.complete_pv(root_pos_history, &mut pv, 6);
+        assert_eq!(MoveList::from(vec![e2e4, e7e5]), pv);
+    }
+
+    #[test]
+    fn singular_extension() {
+        let mut search = AlphaBeta::new(Box::new(MaterialMobility::new()), 1024 * 1024);
+        let (_command_sender, command_receiver) = crossbeam_channel::unbounded();
+        let depth = 8;
+        // Searches the position after prev_move, then checks if the best move
+        // from the hash table is singular
+        let mut singular = |fen: &str, prev_move: Move| {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let mut search_data = SearchData::new(
+                &command_receiver,
+                None,
+                PositionHistory::new(pos),
+                Instant::now(),
+                None,
+                None,
+            );
+            for _ in 0..depth {
+                search_data.increase_search_depth();
+            }
+            search_data.end_prev_pv();
+            search_data.set_max_extensions(MAX_EXTENSIONS);
+            search_data.do_move(prev_move);
+            search_data.set_current_extension(0);
+            search_data.set_current_reduction(0);
+            search
+                .search_recursive(&mut search_data, NEG_INF, POS_INF, false)
+                .unwrap();
+            let remaining_depth = search_data.remaining_depth();
+            let entry = search
+                .singular_extension_candidate(&search_data, remaining_depth)
+                .unwrap();
+            let is_singular = search
+                .is_singular(&mut search_data, entry, remaining_depth, true)
+                .unwrap();
+            (entry.best_move(), is_singular)
+        };
+
+        // Only taking the queen doesn't get mated on the back rank
+        let kh8g8 = Move::new(Square::H8, Square::G8, MoveType::QUIET);
+        let qd1xd8 = Move::new(Square::D1, Square::D8, MoveType::CAPTURE);
+        assert_eq!(
+            (qd1xd8, true),
+            singular("3q3k/5pp1/7p/8/8/8/5PPP/3Q2K1 b - - 0 1", kh8g8)
+        );
+
+        // Both knights can take the queen with about the same result
+        let ke8d8 = Move::new(Square::E8, Square::D8, MoveType::QUIET);
+        let (_, is_singular) = singular("4k3/8/8/3q4/8/2N1N3/8/3K4 b - - 0 1", ke8d8);
+        assert!(!is_singular);
+    }
+
+    #[test]
+    fn multicut() {
+        let mut search = AlphaBeta::new(Box::new(MaterialMobility::new()), 1024 * 1024);
+        let (_command_sender, command_receiver) = crossbeam_channel::unbounded();
+        // Runs multicut pruning after prev_move at the minimum depth
+        let mut multicut = |fen: &str, prev_move: Move, beta: Score, cut_node: bool| {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let mut search_data = SearchData::new(
+                &command_receiver,
+                None,
+                PositionHistory::new(pos),
+                Instant::now(),
+                None,
+                None,
+            );
+            for _ in 0..MULTICUT_MIN_DEPTH + 1 {
+                search_data.increase_search_depth();
+            }
+            search_data.end_prev_pv();
+            search_data.set_max_extensions(MAX_EXTENSIONS);
+            search_data.do_move(prev_move);
+            search_data.set_current_extension(0);
+            search_data.set_current_reduction(0);
+            search
+                .prune_multicut(&mut search_data, beta, cut_node)
+                .map(|opt_node| opt_node.unwrap())
+        };
+
+        // White is a queen up, so many moves beat beta
+        let fen = "3qk3/8/8/8/8/8/PPPPPPPP/RNBQKBNR b KQ - 0 1";
+        let qd8d7 = Move::new(Square::D8, Square::D7, MoveType::QUIET);
+        let node = multicut(fen, qd8d7, 0, true).unwrap();
+        assert_eq!(0, node.score());
+        assert_eq!(ScoreType::LowerBound, node.score_type());
+        assert_eq!(MULTICUT_MIN_DEPTH - MULTICUT_DEPTH_REDUCTION, node.depth());
+        // Not applied at expected all nodes
+        assert_eq!(None, multicut(fen, qd8d7, 0, false));
+        // In the opening, no move beats a beta this high
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        assert_eq!(
+            None,
+            multicut(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                e2e4,
+                300,
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn razoring() {
+        let mut search = AlphaBeta::new(Box::new(MaterialMobility::new()), 1024 * 1024);
+        let (_command_sender, command_receiver) = crossbeam_channel::unbounded();
+        // Runs razoring after prev_move at the given depth
+        let mut razoring = |fen: &str, prev_move: Move, depth, alpha: Score, beta: Score| {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let mut search_data = SearchData::new(
+                &command_receiver,
+                None,
+                PositionHistory::new(pos),
+                Instant::now(),
+                None,
+                None,
+            );
+            for _ in 0..depth + 1 {
+                search_data.increase_search_depth();
+            }
+            search_data.end_prev_pv();
+            search_data.set_max_extensions(MAX_EXTENSIONS);
+            search_data.do_move(prev_move);
+            search_data.set_current_extension(0);
+            search_data.set_current_reduction(0);
+            search.prune_razoring(&mut search_data, alpha, beta)
+        };
+        let razor_alpha = |alpha, depth| {
+            alpha - RAZORING_MARGIN_BASE - (depth - 1) as Score * RAZORING_MARGIN_PER_DEPTH
+        };
+
+        // Black has only the king left, nothing can save it
+        let fen = "4k3/8/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1";
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        for depth in 1..=RAZORING_MAX_DEPTH {
+            let node = razoring(fen, e2e4, depth, 0, 1).unwrap();
+            assert!(node.score() <= razor_alpha(0, depth));
+        }
+        // Not applied at PV nodes or above the maximum depth
+        assert_eq!(None, razoring(fen, e2e4, 1, 0, 100));
+        assert_eq!(None, razoring(fen, e2e4, RAZORING_MAX_DEPTH + 1, 0, 1));
+
+        // Black is far behind, but can capture the queen
+        let fen = "4k3/8/8/8/2n5/8/8/3QK2R w - - 0 1";
+        let qd1d6 = Move::new(Square::D1, Square::D6, MoveType::QUIET);
+        assert_eq!(None, razoring(fen, qd1d6, RAZORING_MAX_DEPTH, 0, 1));
+    }
+
+    #[test]
+    fn probcut() {
+        let mut search = AlphaBeta::new(Box::new(MaterialMobility::new()), 1024 * 1024);
+        let (_command_sender, command_receiver) = crossbeam_channel::unbounded();
+        // Runs ProbCut after prev_move at the given depth
+        let mut probcut = |fen: &str, prev_move: Move, depth, alpha: Score, beta: Score| {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let mut search_data = SearchData::new(
+                &command_receiver,
+                None,
+                PositionHistory::new(pos),
+                Instant::now(),
+                None,
+                None,
+            );
+            for _ in 0..depth + 1 {
+                search_data.increase_search_depth();
+            }
+            search_data.end_prev_pv();
+            search_data.set_max_extensions(MAX_EXTENSIONS);
+            search_data.do_move(prev_move);
+            search_data.set_current_extension(0);
+            search_data.set_current_reduction(0);
+            search
+                .prune_probcut(&mut search_data, alpha, beta, true)
+                .map(|opt_node| opt_node.unwrap())
+        };
+
+        // Black hangs the queen, capturing it beats beta by far
+        let fen = "3qk3/8/8/8/8/8/PPP2PPP/3RK3 b - - 0 1";
+        let qd8d4 = Move::new(Square::D8, Square::D4, MoveType::QUIET);
+        let node = probcut(fen, qd8d4, PROBCUT_MIN_DEPTH, -1, 0).unwrap();
+        assert!(node.score() >= PROBCUT_MARGIN);
+        assert_eq!(ScoreType::LowerBound, node.score_type());
+        assert_eq!(PROBCUT_MIN_DEPTH - PROBCUT_DEPTH_REDUCTION, node.depth());
+        assert_eq!(
+            Move::new(Square::D1, Square::D4, MoveType::CAPTURE),
+            node.best_move()
+        );
+        // Not applied at PV nodes or below the minimum depth
+        assert_eq!(None, probcut(fen, qd8d4, PROBCUT_MIN_DEPTH, -100, 0));
+        assert_eq!(None, probcut(fen, qd8d4, PROBCUT_MIN_DEPTH - 1, -1, 0));
+
+        // There is nothing to capture
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        assert_eq!(None, probcut(fen, e2e4, PROBCUT_MIN_DEPTH, -1, 0));
+    }
+
+    #[test]
+    fn max_ply() {
+        let mut search = AlphaBeta::new(Box::new(MaterialMobility::new()), 0);
+        let (_command_sender, command_receiver) = crossbeam_channel::unbounded();
+        let pos = Fen::str_to_pos("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1").unwrap();
+        let mut search_data = SearchData::new(
+            &command_receiver,
+            None,
+            PositionHistory::new(pos),
+            Instant::now(),
+            None,
+            None,
+        );
+        search_data.increase_search_depth();
+
+        // Each round, both sides push a pawn and walk their kings along the
+        // back rank. This reaches MAX_PLY without a draw by repetition or by
+        // the fifty-move rule.
+        let mut do_move = |uci: String| {
+            let mut move_list = MoveList::new();
+            MoveGenerator::generate_moves(&mut move_list, search_data.current_pos());
+            let m = *move_list
+                .iter()
+                .find(|m| format!("{}{}", m.origin(), m.target()) == uci)
+                .unwrap();
+            search_data.do_move(m);
+        };
+        let king_walk = ["e", "f", "g", "h", "g", "f", "e"];
+        for round in 0..16 {
+            let file = "abcdefgh".chars().nth(round % 8).unwrap();
+            let (from, to) = match round < 8 {
+                true => (2, 3),
+                false => (3, 4),
+            };
+            do_move(format!("{file}{from}{file}{to}"));
+            do_move(format!("{file}{}{file}{}", 9 - from, 9 - to));
+            let walk = &king_walk[(round % 2) * 3..(round % 2) * 3 + 4];
+            for files in walk.windows(2) {
+                do_move(format!("{}1{}1", files[0], files[1]));
+                do_move(format!("{}8{}8", files[0], files[1]));
+            }
+        }
+        assert_eq!(MAX_PLY, search_data.ply());
+
+        // The static evaluation, not stored in the hash table
+        let static_eval = search.static_eval(&mut search_data);
+        let entry = search.search_quiescence(&mut search_data, NEG_INF, POS_INF);
+        assert_eq!(static_eval, entry.score());
+        assert!(entry.is_path_dependent());
+        let entry = search
+            .search_recursive(&mut search_data, NEG_INF, POS_INF, false)
+            .unwrap();
+        assert_eq!(static_eval, entry.score());
+        assert!(entry.is_path_dependent());
+    }
+
+    #[test]
+    fn dynamic_contempt() {
+        let search = AlphaBeta::new(Box::new(MaterialMobility::new()), 0);
+        let contempt = 40;
+        let max_phase = GamePhase::new(&Position::initial()).game_phase_clamped();
+        assert_eq!(GamePhase::MAX, max_phase);
+        let dc = |contempt, phase, score| search.dynamic_contempt(contempt, phase, score);
+        assert_eq!(contempt, dc(contempt, max_phase, None));
+        assert_eq!(contempt, dc(contempt, max_phase, Some(0)));
+        assert_eq!(contempt, dc(contempt, max_phase, Some(300)));
+        assert_eq!(contempt, dc(contempt, max_phase, Some(WHITE_WIN)));
+        // Scaled down towards the endgame
+        let half_phase_contempt = contempt * 3 / 4;
+        assert_eq!(half_phase_contempt, dc(contempt, max_phase / 2, None));
+        assert_eq!(contempt / 2, dc(contempt, 0, None));
+        // And when losing. Scores are compared to the draw score.
+        let draw_score = -contempt;
+        assert_eq!(contempt, dc(contempt, max_phase, Some(draw_score)));
+        assert_eq!(
+            contempt / 2,
+            dc(
+                contempt,
+                max_phase,
+                Some(draw_score - CONTEMPT_LOSING_SCORE / 2)
+            )
+        );
+        assert_eq!(
+            0,
+            dc(
+                contempt,
+                max_phase,
+                Some(draw_score - CONTEMPT_LOSING_SCORE)
+            )
+        );
+        assert_eq!(0, dc(contempt, max_phase, Some(BLACK_WIN)));
+        // Both factors apply, for either sign of the contempt. The draw score
+        // is scaled by the game phase.
+        let half_phase_draw_score = -half_phase_contempt;
+        assert_eq!(
+            half_phase_contempt / 2,
+            dc(
+                contempt,
+                max_phase / 2,
+                Some(half_phase_draw_score - CONTEMPT_LOSING_SCORE / 2)
+            )
+        );
+        assert_eq!(
+            -half_phase_contempt / 2,
+            dc(
+                -contempt,
+                max_phase / 2,
+                Some(-half_phase_draw_score - CONTEMPT_LOSING_SCORE / 2)
+            )
+        );
+    }
+
+    #[test]
+    fn previous_pv_ends_early() {
+        let search = AlphaBeta::new(Box::new(MaterialMobility::new()), 1024 * 1024);
+        let (_command_sender, command_receiver) = crossbeam_channel::unbounded();
+        let pos = Position::initial();
+        let mut search_data = SearchData::new(
+            &command_receiver,
+            None,
+            PositionHistory::new(pos),
+            Instant::now(),
+            None,
+            None,
+        );
+        // The PV of depth 2 ends after the first move, like after a table
+        // cutoff
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        search_data.increase_search_depth();
+        search_data.increase_search_depth();
+        search_data.update_pv_move_and_truncate(e2e4);
+        search_data.increase_search_depth();
+        assert_eq!(2, search_data.prev_pv_depth());
+
+        search_data.decrease_prev_pv_depth();
+        search_data.do_move(e2e4);
+        let mut move_selector = MoveSelector::new_staged();
+        let select = |move_selector: &mut MoveSelector, search_data: &mut SearchData| {
+            move_selector.select_next_move(
+                search_data,
+                &search.transpos_table,
+                &search.counter_table,
+                &search.history_table,
+            )
+        };
+        // The other moves are searched off the previous PV, so their children
+        // don't look for a PV move beyond its end
+        let m = select(&mut move_selector, &mut search_data).unwrap();
+        assert_eq!(0, search_data.prev_pv_depth());
+        search_data.do_move(m);
+        assert!(select(&mut MoveSelector::new_staged(), &mut search_data).is_some());
+    }
+
+    #[test]
+    fn internal_iterative_reduction() {
+        let depth = MIN_IIR_DEPTH;
+        assert_eq!(
+            1,
+            AlphaBeta::internal_iterative_reduction(true, depth, true, false, false)
+        );
+        // Not at PV or all nodes, in check, with a hash move or at low depth
+        assert_eq!(
+            0,
+            AlphaBeta::internal_iterative_reduction(true, depth, false, false, false)
+        );
+        assert_eq!(
+            0,
+            AlphaBeta::internal_iterative_reduction(true, depth, true, true, false)
+        );
+        assert_eq!(
+            0,
+            AlphaBeta::internal_iterative_reduction(true, depth, true, false, true)
+        );
+        assert_eq!(
+            0,
+            AlphaBeta::internal_iterative_reduction(true, depth - 1, true, false, false)
+        );
+        // Disabled
+        assert_eq!(
+            0,
+            AlphaBeta::internal_iterative_reduction(false, depth, true, false, false)
+        );
+    }
+
+    #[test]
+    fn scale_for_fifty_move_rule() {
+        for eval in [-300, 0, 17, 300] {
+            for plies in [0, 10, FIFTY_MOVE_SCALING_MIN_PLIES] {
+                assert_eq!(eval, AlphaBeta::scale_for_fifty_move_rule(eval, plies));
+            }
+            // Beyond the draw, the scaling doesn't increase
+            assert_eq!(
+                AlphaBeta::scale_for_fifty_move_rule(eval, 100),
+                AlphaBeta::scale_for_fifty_move_rule(eval, 120)
+            );
+        }
+        assert_eq!(210, AlphaBeta::scale_for_fifty_move_rule(300, 100));
+        assert_eq!(270, AlphaBeta::scale_for_fifty_move_rule(300, 60));
+        assert_eq!(-270, AlphaBeta::scale_for_fifty_move_rule(-300, 60));
+
+        // The closer the draw, the closer the score to zero, but never closer
+        // than with (200 - plies) / 200
+        let mut prev = 300;
+        for plies in FIFTY_MOVE_SCALING_MIN_PLIES..=100 {
+            let scaled = AlphaBeta::scale_for_fifty_move_rule(300, plies);
+            assert!(scaled <= prev && scaled as i32 >= 300 * (200 - plies as i32) / 200);
+            prev = scaled;
+        }
     }
 }
@@ -1,11 +1,8 @@
-use crate::search::MAX_SEARCH_DEPTH;
 use eval::score::{dec_mate_dist_by, inc_mate_dist_by};
 use eval::Score;
 use movegen::r#move::Move;
 use movegen::transposition_table::TtEntry;
-use movegen::zobrist::Zobrist;
 use std::cmp::Ordering;
-use std::mem;
 use std::ops::Neg;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -23,6 +20,11 @@ pub struct AlphaBetaEntry {
     score_type: ScoreType,
     best_move: Move,
     age: u8,
+    // The score depends on the path to the position, e.g. because a
+    // repetition was detected below it. The score may be wrong if the
+    // position is reached by another path. Such entries are stored in the
+    // hash table only for their best move, their scores are never used.
+    path_dependent: bool,
 }
 
 impl Neg for AlphaBetaEntry {
@@ -30,13 +32,48 @@ impl Neg for AlphaBetaEntry {
 
     // Changes the sign of the score and leaves the rest unchanged
     fn neg(self) -> Self::Output {
-        Self::new(
-            self.depth(),
-            -self.score(),
-            self.score_type(),
-            self.best_move(),
-            self.age(),
-        )
+        Self {
+            score: -self.score,
+            ..self
+        }
+    }
+}
+
+// The entries are packed into a u64 to be stored in atomics. Bits 57-63 are
+// always zero.
+// Bits 0-15: score
+// Bits 16-31: best move
+// Bits 32-39: depth
+// Bits 40-47: age
+// Bits 48-55: score type
+// Bit 56: path dependency
+impl From<AlphaBetaEntry> for u64 {
+    fn from(e: AlphaBetaEntry) -> Self {
+        e.score as u16 as u64
+            | (u16::from(e.best_move) as u64) << 16
+            | (e.depth as u64) << 32
+            | (e.age as u64) << 40
+            | (e.score_type as u64) << 48
+            | (e.path_dependent as u64) << 56
+    }
+}
+
+impl From<u64> for AlphaBetaEntry {
+    fn from(u: u64) -> Self {
+        let score_type = match (u >> 48) as u8 {
+            0 => ScoreType::Exact,
+            1 => ScoreType::LowerBound,
+            2 => ScoreType::UpperBound,
+            st => panic!("Invalid score type {st}"),
+        };
+        Self {
+            depth: (u >> 32) as u8,
+            score: u as u16 as Score,
+            score_type,
+            best_move: Move::from((u >> 16) as u16),
+            age: (u >> 40) as u8,
+            path_dependent: (u >> 56) & 1 != 0,
+        }
     }
 }
 
@@ -53,26 +90,39 @@ impl TtEntry for AlphaBetaEntry {
         self.age = age;
     }
 
+    // Entries from older searches are replaced first, even if they are deeper
+    // than the entries from the current search. Within a generation,
+    // path-dependent entries are replaced first, then quiescence search
+    // entries (depth 0), then exact scores and deeper entries are kept.
     fn prio(&self, other: &Self, age: u8) -> Ordering {
+        let generations_since_self = age.wrapping_sub(self.age());
+        let generations_since_other = age.wrapping_sub(other.age());
+        match generations_since_self.cmp(&generations_since_other) {
+            Ordering::Less => return Ordering::Less,
+            Ordering::Greater => return Ordering::Greater,
+            Ordering::Equal => {}
+        }
+        match (self.is_path_dependent(), other.is_path_dependent()) {
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            _ => {}
+        }
+        match (self.depth(), other.depth()) {
+            (0, d) if d > 0 => return Ordering::Greater,
+            (d, 0) if d > 0 => return Ordering::Less,
+            _ => {}
+        }
         if self.score_type() == ScoreType::Exact && other.score_type() != ScoreType::Exact {
             return Ordering::Less;
         }
         if other.score_type() == ScoreType::Exact && self.score_type() != ScoreType::Exact {
             return Ordering::Greater;
         }
-        let halfmoves_since_self = ((age as u16 + 256 - self.age() as u16) % 256) as u8;
-        let halfmoves_since_other = ((age as u16 + 256 - other.age() as u16) % 256) as u8;
-        match halfmoves_since_self.cmp(&halfmoves_since_other) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Equal => self.depth().cmp(&other.depth()).reverse(),
-            Ordering::Greater => Ordering::Greater,
-        }
+        self.depth().cmp(&other.depth()).reverse()
     }
 }
 
 impl AlphaBetaEntry {
-    pub const ENTRY_SIZE: usize = mem::size_of::<Option<(Zobrist, AlphaBetaEntry)>>();
-
     pub fn new(
         depth: usize,
         score: Score,
@@ -80,13 +130,15 @@ impl AlphaBetaEntry {
         best_move: Move,
         age: u8,
     ) -> Self {
-        debug_assert!(depth <= MAX_SEARCH_DEPTH);
+        // Extensions may add to the maximum search depth
+        debug_assert!(depth <= u8::MAX as usize);
         Self {
             depth: depth as u8,
             score,
             score_type,
             best_move,
             age,
+            path_dependent: false,
         }
     }
 
@@ -106,6 +158,24 @@ impl AlphaBetaEntry {
         self.age
     }
 
+    pub fn is_path_dependent(&self) -> bool {
+        self.path_dependent
+    }
+
+    pub fn with_path_dependency(&self, path_dependent: bool) -> Self {
+        Self {
+            path_dependent,
+            ..*self
+        }
+    }
+
+    pub fn with_best_move(&self, best_move: Move) -> Self {
+        Self { best_move, ..*self }
+    }
+
+    // Mate scores in the hash table are relative to the position of the entry.
+    // During the search, they are relative to the root. A probe at ply p
+    // increases the mate distance by p, a store at ply p decreases it by p.
     pub fn with_increased_mate_distance(&self, plies: usize) -> Self {
         Self {
             score: inc_mate_dist_by(self.score, plies),
@@ -161,3 +231,96 @@ impl AlphaBetaEntry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eval::score::mate_dist;
+    use eval::{BLACK_WIN, WHITE_WIN};
+    use movegen::r#move::MoveType;
+    use movegen::square::Square;
+
+    #[test]
+    fn prio_prefers_replacing_old_generations() {
+        let old_deep = AlphaBetaEntry::new(20, 0, ScoreType::Exact, Move::NULL, 3);
+        let new_shallow = AlphaBetaEntry::new(1, 0, ScoreType::UpperBound, Move::NULL, 4);
+        assert_eq!(Ordering::Greater, old_deep.prio(&new_shallow, 4));
+        assert_eq!(Ordering::Less, new_shallow.prio(&old_deep, 4));
+
+        // The generation wraps around
+        let old_deep = AlphaBetaEntry::new(20, 0, ScoreType::Exact, Move::NULL, 255);
+        let new_shallow = AlphaBetaEntry::new(1, 0, ScoreType::UpperBound, Move::NULL, 0);
+        assert_eq!(Ordering::Greater, old_deep.prio(&new_shallow, 0));
+
+        // Within the same generation, exact scores and deeper entries are kept
+        let shallow_exact = AlphaBetaEntry::new(1, 0, ScoreType::Exact, Move::NULL, 4);
+        let deep_bound = AlphaBetaEntry::new(20, 0, ScoreType::LowerBound, Move::NULL, 4);
+        let shallow_bound = AlphaBetaEntry::new(2, 0, ScoreType::LowerBound, Move::NULL, 4);
+        assert_eq!(Ordering::Greater, deep_bound.prio(&shallow_exact, 4));
+        assert_eq!(Ordering::Greater, shallow_bound.prio(&deep_bound, 4));
+
+        // Exact quiescence search entries don't replace main search entries
+        let quiescence_exact = AlphaBetaEntry::new(0, 0, ScoreType::Exact, Move::NULL, 4);
+        assert_eq!(Ordering::Greater, quiescence_exact.prio(&shallow_bound, 4));
+        assert_eq!(Ordering::Less, shallow_bound.prio(&quiescence_exact, 4));
+        let quiescence_bound = AlphaBetaEntry::new(0, 0, ScoreType::UpperBound, Move::NULL, 4);
+        assert_eq!(Ordering::Less, quiescence_exact.prio(&quiescence_bound, 4));
+
+        // Path-dependent entries don't replace any other entries of the same
+        // generation, only older ones
+        let deep_path_dependent = deep_bound.with_path_dependency(true);
+        assert_eq!(
+            Ordering::Greater,
+            deep_path_dependent.prio(&quiescence_bound, 4)
+        );
+        assert_eq!(
+            Ordering::Less,
+            quiescence_bound.prio(&deep_path_dependent, 4)
+        );
+        assert_eq!(Ordering::Less, deep_path_dependent.prio(&old_deep, 4));
+    }
+
+    #[test]
+    fn pack_and_unpack() {
+        let m = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        for score_type in [
+            ScoreType::Exact,
+            ScoreType::LowerBound,
+            ScoreType::UpperBound,
+        ] {
+            for path_dependent in [false, true] {
+                let entry = AlphaBetaEntry::new(17, -345, score_type, m, 200)
+                    .with_path_dependency(path_dependent);
+                assert_eq!(entry, AlphaBetaEntry::from(u64::from(entry)));
+                assert_eq!(0, u64::from(entry) >> 57);
+            }
+        }
+    }
+
+    #[test]
+    fn mate_distance_relative_to_node_in_table() {
+        // Found at ply 3 during the search: mate in 7 plies from the root,
+        // which is mate in 4 plies from the node
+        let entry = AlphaBetaEntry::new(5, WHITE_WIN - 7, ScoreType::Exact, Move::NULL, 0);
+        let stored = entry.with_decreased_mate_distance(3);
+        assert_eq!(4, mate_dist(stored.score()));
+
+        // Probed at ply 5 (a transposition reached two plies later)
+        let probed = stored.with_increased_mate_distance(5);
+        assert_eq!(9, mate_dist(probed.score()));
+        // Probed at the root
+        let probed = stored.with_increased_mate_distance(0);
+        assert_eq!(4, mate_dist(probed.score()));
+
+        let entry = AlphaBetaEntry::new(5, BLACK_WIN + 6, ScoreType::LowerBound, Move::NULL, 0);
+        let stored = entry.with_decreased_mate_distance(2);
+        assert_eq!(-4, mate_dist(stored.score()));
+        let probed = stored.with_increased_mate_distance(6);
+        assert_eq!(-10, mate_dist(probed.score()));
+
+        // Other scores are unchanged
+        let entry = AlphaBetaEntry::new(5, 123, ScoreType::Exact, Move::NULL, 0);
+        assert_eq!(entry, entry.with_decreased_mate_distance(3));
+        assert_eq!(entry, entry.with_increased_mate_distance(3));
+    }
+}
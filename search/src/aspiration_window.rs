@@ -1,14 +1,23 @@
+use eval::score::is_centipawns;
 use eval::{Score, NEG_INF, POS_INF};
 
 pub const INITIAL_WIDTH: i32 = 101;
 pub const GROW_RATE: i32 = 15;
 
+// After this many fails in the same iteration, the window is opened fully
+const MAX_FAILS: usize = 3;
+
+// The score changes between iterations are averaged over roughly this many
+// iterations
+const VOLATILITY_ITERATIONS: i32 = 4;
+
 #[derive(Debug)]
 pub struct AspirationWindow {
     score: i32,
     width_down: i32,
     width_up: i32,
     grow_rate: i32,
+    fails: usize,
     alpha: Score,
     beta: Score,
 }
@@ -20,6 +29,7 @@ impl AspirationWindow {
             width_up: POS_INF as i32,
             width_down: POS_INF as i32,
             grow_rate: GROW_RATE,
+            fails: 0,
             alpha: NEG_INF,
             beta: POS_INF,
         }
@@ -31,6 +41,7 @@ impl AspirationWindow {
             width_up: initial_width,
             width_down: initial_width,
             grow_rate,
+            fails: 0,
             alpha: calc_alpha(s as i32, initial_width),
             beta: calc_beta(s as i32, initial_width),
         }
@@ -44,15 +55,75 @@ impl AspirationWindow {
         self.beta
     }
 
-    pub fn widen_down(&mut self) {
-        self.width_down = (self.width_down * self.grow_rate).clamp(0, self.score - NEG_INF as i32);
+    pub fn fails(&self) -> usize {
+        self.fails
+    }
+
+    // Widen the window after a fail low with the given score. The width grows
+    // exponentially. A mate score opens the lower side fully, too many fails
+    // open the whole window.
+    pub fn widen_down(&mut self, s: Score) {
+        self.fails += 1;
+        if self.fails >= MAX_FAILS {
+            self.open_fully();
+            return;
+        }
+        self.width_down = match is_centipawns(s) {
+            true => self.width_down * self.grow_rate,
+            false => POS_INF as i32,
+        }
+        .clamp(0, self.score - NEG_INF as i32);
         self.alpha = (self.score - self.width_down) as Score;
     }
 
-    pub fn widen_up(&mut self) {
-        self.width_up = (self.width_up * self.grow_rate).clamp(0, POS_INF as i32 - self.score);
+    // Widen the window after a fail high with the given score
+    pub fn widen_up(&mut self, s: Score) {
+        self.fails += 1;
+        if self.fails >= MAX_FAILS {
+            self.open_fully();
+            return;
+        }
+        self.width_up = match is_centipawns(s) {
+            true => self.width_up * self.grow_rate,
+            false => POS_INF as i32,
+        }
+        .clamp(0, POS_INF as i32 - self.score);
         self.beta = (self.score + self.width_up) as Score;
     }
+
+    fn open_fully(&mut self) {
+        self.width_down = self.score - NEG_INF as i32;
+        self.width_up = POS_INF as i32 - self.score;
+        self.alpha = NEG_INF;
+        self.beta = POS_INF;
+    }
+}
+
+// Keeps track of how much the score changed between recent iterations. The
+// initial aspiration window is wider if the score is volatile.
+#[derive(Debug, Default)]
+pub struct ScoreVolatility {
+    prev_score: Option<Score>,
+    avg_change: i32,
+}
+
+impl ScoreVolatility {
+    // Mate scores are skipped, they would blow up the average
+    pub fn update(&mut self, s: Score) {
+        if !is_centipawns(s) {
+            return;
+        }
+        if let Some(prev) = self.prev_score {
+            let change = (s as i32 - prev as i32).abs();
+            self.avg_change =
+                (self.avg_change * (VOLATILITY_ITERATIONS - 1) + change) / VOLATILITY_ITERATIONS;
+        }
+        self.prev_score = Some(s);
+    }
+
+    pub fn window_width(&self, initial_width: i32) -> i32 {
+        initial_width + self.avg_change
+    }
 }
 
 fn calc_alpha(score: i32, width_down: i32) -> Score {
@@ -81,24 +152,24 @@ mod tests {
         assert_eq!(score - INITIAL_WIDTH as Score, aw.alpha());
         assert_eq!(score + INITIAL_WIDTH as Score, aw.beta());
 
-        aw.widen_down();
+        aw.widen_down(aw.alpha());
         assert_eq!(score - (INITIAL_WIDTH * GROW_RATE) as Score, aw.alpha());
         assert_eq!(score + INITIAL_WIDTH as Score, aw.beta());
 
-        aw.widen_up();
+        aw.widen_up(aw.beta());
         assert_eq!(score - (INITIAL_WIDTH * GROW_RATE) as Score, aw.alpha());
         assert_eq!(score + (INITIAL_WIDTH * GROW_RATE) as Score, aw.beta());
 
         let mut prev_alpha = aw.alpha() + 1;
         while aw.alpha() != prev_alpha {
             prev_alpha = aw.alpha();
-            aw.widen_down();
+            aw.widen_down(aw.alpha());
         }
         assert_eq!(NEG_INF, aw.alpha());
         let mut prev_beta = aw.beta() - 1;
         while aw.beta() != prev_beta {
             prev_beta = aw.beta();
-            aw.widen_up();
+            aw.widen_up(aw.beta());
         }
         assert_eq!(POS_INF, aw.beta());
 
@@ -107,14 +178,80 @@ mod tests {
         let mut prev_alpha = neg_aw.alpha() + 1;
         while neg_aw.alpha() != prev_alpha {
             prev_alpha = neg_aw.alpha();
-            neg_aw.widen_down();
+            neg_aw.widen_down(neg_aw.alpha());
         }
         assert_eq!(NEG_INF, neg_aw.alpha());
         let mut prev_beta = neg_aw.beta() - 1;
         while neg_aw.beta() != prev_beta {
             prev_beta = neg_aw.beta();
-            neg_aw.widen_up();
+            neg_aw.widen_up(neg_aw.beta());
         }
         assert_eq!(POS_INF, neg_aw.beta());
     }
+
+    #[test]
+    fn open_fully_after_max_fails() {
+        let mut aw = AspirationWindow::new(0, 10, 2);
+        for fails in 1..MAX_FAILS {
+            aw.widen_up(aw.beta());
+            assert_eq!(fails, aw.fails());
+            assert_eq!(-10, aw.alpha());
+            assert_eq!(10 * 2i32.pow(fails as u32), aw.beta() as i32);
+        }
+        aw.widen_up(aw.beta());
+        assert_eq!(NEG_INF, aw.alpha());
+        assert_eq!(POS_INF, aw.beta());
+    }
+
+    #[test]
+    fn open_fully_on_mate_score() {
+        let mut aw = AspirationWindow::new(100, 10, 2);
+        aw.widen_up(eval::WHITE_WIN - 5);
+        assert_eq!(90, aw.alpha());
+        assert_eq!(POS_INF, aw.beta());
+
+        let mut aw = AspirationWindow::new(-100, 10, 2);
+        aw.widen_down(eval::BLACK_WIN + 5);
+        assert_eq!(NEG_INF, aw.alpha());
+        assert_eq!(-90, aw.beta());
+    }
+
+    #[test]
+    fn volatility() {
+        let mut vol = ScoreVolatility::default();
+        vol.update(20);
+        assert_eq!(INITIAL_WIDTH, vol.window_width(INITIAL_WIDTH));
+        vol.update(20);
+        assert_eq!(INITIAL_WIDTH, vol.window_width(INITIAL_WIDTH));
+        vol.update(100);
+        assert_eq!(INITIAL_WIDTH + 20, vol.window_width(INITIAL_WIDTH));
+        // Mate scores are skipped
+        vol.update(eval::WHITE_WIN - 3);
+        assert_eq!(INITIAL_WIDTH + 20, vol.window_width(INITIAL_WIDTH));
+        vol.update(100);
+        assert_eq!(INITIAL_WIDTH + 15, vol.window_width(INITIAL_WIDTH));
+    }
+
+    #[test]
+    fn score_jump_few_researches() {
+        // The score is stable for a few iterations and then jumps by 300 cp
+        let mut vol = ScoreVolatility::default();
+        for s in [20, 25, 18, 22] {
+            vol.update(s);
+        }
+        let new_score = 22 + 300;
+        let mut aw = AspirationWindow::new(22, vol.window_width(INITIAL_WIDTH), GROW_RATE);
+        while new_score >= aw.beta() {
+            aw.widen_up(aw.beta());
+        }
+        assert!(aw.fails() <= 1);
+
+        // Even with a slowly growing window, the fallback to the infinite
+        // window limits the number of re-searches
+        let mut aw = AspirationWindow::new(22, vol.window_width(10), 2);
+        while new_score >= aw.beta() {
+            aw.widen_up(aw.beta());
+        }
+        assert!(aw.fails() <= MAX_FAILS);
+    }
 }
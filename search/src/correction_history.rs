@@ -0,0 +1,103 @@
+use eval::Score;
+use movegen::side::Side;
+use movegen::zobrist::Zobrist;
+
+// Number of entries per side to move, indexed by the pawn structure key
+pub const CORRECTION_HISTORY_ENTRIES: usize = 1 << 14;
+
+// The entries are stored with this many fractions per centipawn, so that small
+// updates aren't rounded away
+const CORRECTION_GRAIN: i32 = 256;
+// The weight of a new difference grows with the depth of the search that
+// produced it, up to this value (out of CORRECTION_WEIGHT_SCALE)
+const CORRECTION_MAX_WEIGHT: i32 = 16;
+const CORRECTION_WEIGHT_SCALE: i32 = 256;
+// The correction is limited to this many centipawns
+pub const CORRECTION_MAX: Score = 128;
+
+// Correction history: a running average of the difference between the search
+// score and the static evaluation for each pawn structure and side to move.
+// The static evaluation often misjudges a pawn structure in the same way, so
+// the average is added to the static evaluation used for pruning.
+#[derive(Debug, Clone)]
+pub struct CorrectionHistory {
+    table: Box<[i32]>,
+}
+
+impl Default for CorrectionHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorrectionHistory {
+    pub fn new() -> Self {
+        debug_assert!(CORRECTION_HISTORY_ENTRIES.is_power_of_two());
+        Self {
+            table: vec![0; 2 * CORRECTION_HISTORY_ENTRIES].into_boxed_slice(),
+        }
+    }
+
+    pub fn correction(&self, side_to_move: Side, pawn_hash: Zobrist) -> Score {
+        (self.table[Self::idx(side_to_move, pawn_hash)] / CORRECTION_GRAIN) as Score
+    }
+
+    // diff is the search score minus the uncorrected static evaluation
+    pub fn update(&mut self, side_to_move: Side, pawn_hash: Zobrist, depth: usize, diff: Score) {
+        let limit = CORRECTION_MAX as i32 * CORRECTION_GRAIN;
+        let weight = (depth as i32 + 1).min(CORRECTION_MAX_WEIGHT);
+        let entry = &mut self.table[Self::idx(side_to_move, pawn_hash)];
+        let target = (diff as i32 * CORRECTION_GRAIN).clamp(-limit, limit);
+        *entry = (*entry * (CORRECTION_WEIGHT_SCALE - weight) + target * weight)
+            / CORRECTION_WEIGHT_SCALE;
+    }
+
+    pub fn clear(&mut self) {
+        self.table.fill(0);
+    }
+
+    fn idx(side_to_move: Side, pawn_hash: Zobrist) -> usize {
+        side_to_move as usize * CORRECTION_HISTORY_ENTRIES
+            + (u64::from(pawn_hash) as usize & (CORRECTION_HISTORY_ENTRIES - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_average() {
+        let mut history = CorrectionHistory::new();
+        let k1 = Zobrist::from(0x0123_4567_89ab_cdef);
+        let k2 = Zobrist::from(0x0fed_cba9_8765_4321);
+        assert_eq!(0, history.correction(Side::White, k1));
+
+        // Converges to the difference
+        for _ in 0..1000 {
+            history.update(Side::White, k1, 10, 50);
+        }
+        assert_eq!(49, history.correction(Side::White, k1));
+        // Separate for each side and pawn structure
+        assert_eq!(0, history.correction(Side::Black, k1));
+        assert_eq!(0, history.correction(Side::White, k2));
+
+        // Deeper searches have more weight
+        let mut shallow = history.clone();
+        shallow.update(Side::White, k1, 0, -50);
+        history.update(Side::White, k1, 20, -50);
+        assert!(history.correction(Side::White, k1) < shallow.correction(Side::White, k1));
+        assert!(shallow.correction(Side::White, k1) <= 49);
+
+        // Limited (the rounded average stays just short of the limit)
+        for _ in 0..1000 {
+            history.update(Side::Black, k2, 10, Score::MIN);
+        }
+        let limited = history.correction(Side::Black, k2);
+        assert!((-CORRECTION_MAX..-CORRECTION_MAX + 2).contains(&limited));
+
+        history.clear();
+        assert_eq!(0, history.correction(Side::White, k1));
+        assert_eq!(0, history.correction(Side::Black, k2));
+    }
+}
@@ -1,28 +1,71 @@
 use movegen::{piece::Piece, r#move::Move, square::Square};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
 
-#[derive(Debug, Clone)]
+const NUM_ENTRIES: usize = Piece::NUM_PIECES * Square::NUM_SQUARES;
+
+// Like the history table, the counter moves are either owned by one search
+// thread or shared by all threads as relaxed atomics
+#[derive(Debug)]
+enum Entries {
+    Local(Box<[Move]>),
+    Shared(Arc<[AtomicU16]>),
+}
+
+#[derive(Debug)]
 pub struct CounterTable {
-    table: [Move; Piece::NUM_PIECES * Square::NUM_SQUARES],
+    table: Entries,
 }
 
 impl CounterTable {
-    pub fn new() -> Self {
-        CounterTable {
-            table: [Move::NULL; Piece::NUM_PIECES * Square::NUM_SQUARES],
+    // A shared table can be used by several search threads (see share)
+    pub fn new(shared: bool) -> Self {
+        let table = match shared {
+            true => Entries::Shared(
+                (0..NUM_ENTRIES)
+                    .map(|_| AtomicU16::new(u16::from(Move::NULL)))
+                    .collect(),
+            ),
+            false => Entries::Local(vec![Move::NULL; NUM_ENTRIES].into_boxed_slice()),
+        };
+        CounterTable { table }
+    }
+
+    // Returns a table with the same entries, or None if the table isn't
+    // shared
+    pub fn share(&self) -> Option<Self> {
+        match &self.table {
+            Entries::Local(_) => None,
+            Entries::Shared(entries) => Some(CounterTable {
+                table: Entries::Shared(Arc::clone(entries)),
+            }),
         }
     }
 
     pub fn update(&mut self, p: Piece, to: Square, m: Move) {
-        self.table[Self::idx(p, to)] = m;
+        let idx = Self::idx(p, to);
+        match &mut self.table {
+            Entries::Local(entries) => entries[idx] = m,
+            Entries::Shared(entries) => entries[idx].store(u16::from(m), Ordering::Relaxed),
+        }
     }
 
     pub fn counter(&self, p: Piece, to: Square) -> Move {
-        self.table[Self::idx(p, to)]
+        let idx = Self::idx(p, to);
+        match &self.table {
+            Entries::Local(entries) => entries[idx],
+            Entries::Shared(entries) => Move::from(entries[idx].load(Ordering::Relaxed)),
+        }
     }
 
     pub fn clear(&mut self) {
-        for entry in self.table.iter_mut() {
-            *entry = Move::NULL;
+        match &mut self.table {
+            Entries::Local(entries) => entries.fill(Move::NULL),
+            Entries::Shared(entries) => {
+                for entry in entries.iter() {
+                    entry.store(u16::from(Move::NULL), Ordering::Relaxed);
+                }
+            }
         }
     }
 
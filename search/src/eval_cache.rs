@@ -0,0 +1,90 @@
+use eval::Score;
+use movegen::zobrist::Zobrist;
+use std::cmp;
+use std::mem;
+
+pub const EVAL_CACHE_SIZE: usize = 2 * 1024 * 1024;
+
+const SCORE_BITS: u32 = 16;
+const SCORE_MASK: u64 = (1 << SCORE_BITS) - 1;
+
+// Direct-mapped cache of static evaluations, one per search thread. The
+// evaluation only depends on the position, so the entries stay valid across
+// searches.
+// An entry is packed into a u64: the lowest bits of the key (the index uses
+// the highest bits) and the score. An empty entry has a key of zero.
+#[derive(Debug, Clone)]
+pub struct EvalCache {
+    index_bits: usize,
+    entries: Box<[u64]>,
+}
+
+impl EvalCache {
+    pub fn new(bytes: usize) -> Self {
+        // Reserve memory for at least 2 entries, so that at least one index bit
+        // is used (even if bytes is 0)
+        let max_num_entries = cmp::max(2, bytes / mem::size_of::<u64>());
+        // The actual number of entries must be a power of 2.
+        let index_bits = 64 - max_num_entries.leading_zeros() - 1;
+        Self {
+            index_bits: index_bits as usize,
+            entries: vec![0; 1 << index_bits].into_boxed_slice(),
+        }
+    }
+
+    pub fn get(&self, k: &Zobrist) -> Option<Score> {
+        let entry = self.entries[self.index(k)];
+        match entry >> SCORE_BITS == Self::check_bits(k) {
+            true => Some((entry & SCORE_MASK) as u16 as Score),
+            false => None,
+        }
+    }
+
+    pub fn insert(&mut self, k: &Zobrist, score: Score) {
+        let index = self.index(k);
+        self.entries[index] = Self::check_bits(k) << SCORE_BITS | score as u16 as u64;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(0);
+    }
+
+    fn index(&self, k: &Zobrist) -> usize {
+        (u64::from(*k) >> (64 - self.index_bits)) as usize
+    }
+
+    fn check_bits(k: &Zobrist) -> u64 {
+        u64::from(*k) & (u64::MAX >> SCORE_BITS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_and_insert() {
+        let mut cache = EvalCache::new(1024);
+        assert_eq!(128, cache.entries.len());
+        let k1 = Zobrist::from(0x0123_4567_89ab_cdef);
+        let k2 = Zobrist::from(0x0fed_cba9_8765_4321);
+        // Same index as k1, but a different key
+        let k3 = Zobrist::from(0x0123_4567_89ab_cdee);
+        assert_eq!(None, cache.get(&k1));
+
+        cache.insert(&k1, -42);
+        cache.insert(&k2, 1234);
+        assert_eq!(Some(-42), cache.get(&k1));
+        assert_eq!(Some(1234), cache.get(&k2));
+        assert_eq!(None, cache.get(&k3));
+
+        // Replaces the entry of k1
+        cache.insert(&k3, Score::MIN);
+        assert_eq!(None, cache.get(&k1));
+        assert_eq!(Some(Score::MIN), cache.get(&k3));
+
+        cache.clear();
+        assert_eq!(None, cache.get(&k2));
+        assert_eq!(None, cache.get(&k3));
+    }
+}
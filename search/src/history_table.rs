@@ -1,69 +1,398 @@
 use movegen::{
-    piece::Piece,
+    piece::{self, Piece},
     position::Position,
     r#move::{Move, MoveList},
     square::Square,
 };
+use std::sync::atomic::{AtomicI16, Ordering};
+use std::sync::Arc;
 
-const MAX_BONUS: i32 = (i16::MAX / 2) as i32;
-const HISTORY_DIVISOR: i32 = 16384;
+// The history values are updated with the gravity formula
+// value += bonus - value * |bonus| / max, so they stay within [-max, max].
+// The bonus for a search depth d is quadratic * d^2 + linear * (d - 1),
+// limited to max.
+pub const HISTORY_MAX: i32 = 16384;
+pub const HISTORY_BONUS_QUADRATIC: i32 = 16;
+pub const HISTORY_BONUS_LINEAR: i32 = 128;
 
-#[derive(Debug, Clone)]
+// The continuation history is indexed by the piece and target square of a
+// previous move (one and two plies ago) and of the current move
+pub const CONTINUATION_PLIES: usize = 2;
+const PIECE_SQUARES: usize = Piece::NUM_PIECES * Square::NUM_SQUARES;
+
+// The capture history is indexed by the moving piece, the target square and
+// the captured piece type
+const NUM_PIECE_TYPES: usize = 6;
+
+// Piece and target square of the previous moves, starting with the last one.
+// None for null moves and before the root.
+pub type PrevMoves = [Option<(Piece, Square)>; CONTINUATION_PLIES];
+
+// The entries are either owned by one search thread or shared by all threads.
+// Shared entries are relaxed atomics. Concurrent updates of the same entry may
+// get lost, which only makes the move ordering a bit less exact.
+#[derive(Debug)]
+enum Entries {
+    Local(Box<[i16]>),
+    Shared(Arc<[AtomicI16]>),
+}
+
+impl Entries {
+    fn new(len: usize, shared: bool) -> Self {
+        match shared {
+            true => Self::Shared((0..len).map(|_| AtomicI16::new(0)).collect()),
+            false => Self::Local(vec![0; len].into_boxed_slice()),
+        }
+    }
+
+    fn get(&self, idx: usize) -> i16 {
+        match self {
+            Self::Local(entries) => entries[idx],
+            Self::Shared(entries) => entries[idx].load(Ordering::Relaxed),
+        }
+    }
+
+    fn update(&mut self, idx: usize, f: impl FnOnce(i16) -> i16) {
+        match self {
+            Self::Local(entries) => entries[idx] = f(entries[idx]),
+            Self::Shared(entries) => {
+                let entry = &entries[idx];
+                entry.store(f(entry.load(Ordering::Relaxed)), Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn update_all(&mut self, mut f: impl FnMut(i16) -> i16) {
+        for idx in 0..self.len() {
+            self.update(idx, &mut f);
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Local(entries) => entries.len(),
+            Self::Shared(entries) => entries.len(),
+        }
+    }
+
+    // Another handle to the same entries. Local entries can't be shared.
+    fn share(&self) -> Option<Self> {
+        match self {
+            Self::Local(_) => None,
+            Self::Shared(entries) => Some(Self::Shared(Arc::clone(entries))),
+        }
+    }
+
+    fn is_shared_with(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Shared(a), Self::Shared(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct HistoryTable {
-    table: [i16; Piece::NUM_PIECES * Square::NUM_SQUARES],
+    table: Entries,
+    continuation: Entries,
+    captures: Entries,
+    max: i32,
+    bonus_quadratic: i32,
+    bonus_linear: i32,
 }
 
 impl HistoryTable {
-    pub fn new() -> Self {
+    // A shared table can be used by several search threads (see share)
+    pub fn new(shared: bool) -> Self {
         HistoryTable {
-            table: [0; Piece::NUM_PIECES * Square::NUM_SQUARES],
+            table: Entries::new(PIECE_SQUARES, shared),
+            continuation: Entries::new(CONTINUATION_PLIES * PIECE_SQUARES * PIECE_SQUARES, shared),
+            captures: Entries::new(PIECE_SQUARES * NUM_PIECE_TYPES, shared),
+            max: HISTORY_MAX,
+            bonus_quadratic: HISTORY_BONUS_QUADRATIC,
+            bonus_linear: HISTORY_BONUS_LINEAR,
         }
     }
 
-    pub fn update(&mut self, m: Move, depth: usize, moves_tried: &MoveList, pos: &Position) {
-        let bonus = Self::bonus(depth);
+    // Returns a table with the same entries and parameters, or None if the
+    // table isn't shared
+    pub fn share(&self) -> Option<Self> {
+        Some(HistoryTable {
+            table: self.table.share()?,
+            continuation: self.continuation.share()?,
+            captures: self.captures.share()?,
+            max: self.max,
+            bonus_quadratic: self.bonus_quadratic,
+            bonus_linear: self.bonus_linear,
+        })
+    }
+
+    pub fn is_shared(&self) -> bool {
+        matches!(self.table, Entries::Shared(_))
+    }
+
+    // The maximum is limited to the range of the table entries. The entries
+    // are rescaled, so that they are still within the new bounds.
+    pub fn set_max(&mut self, max: i32) {
+        let max = max.clamp(1, i16::MAX as i32);
+        let prev_max = self.max;
+        self.update_all(|entry| (entry as i32 * max / prev_max) as i16);
+        self.max = max;
+    }
+
+    pub fn set_bonus_quadratic(&mut self, bonus_quadratic: i32) {
+        self.bonus_quadratic = bonus_quadratic;
+    }
+
+    pub fn set_bonus_linear(&mut self, bonus_linear: i32) {
+        self.bonus_linear = bonus_linear;
+    }
+
+    pub fn copy_params(&mut self, other: &Self) {
+        // Shared entries have already been rescaled by the other table
+        if self.table.is_shared_with(&other.table) {
+            self.max = other.max;
+        } else if self.max != other.max {
+            self.set_max(other.max);
+        }
+        self.bonus_quadratic = other.bonus_quadratic;
+        self.bonus_linear = other.bonus_linear;
+    }
+
+    pub fn update(
+        &mut self,
+        m: Move,
+        depth: usize,
+        moves_tried: &MoveList,
+        pos: &Position,
+        prev_moves: &PrevMoves,
+    ) {
+        let bonus = self.bonus(depth);
         // Add a bonus to the fail-high move
         let piece = pos
             .piece_at(m.origin())
             .expect("Expected a piece at move origin");
-        self.update_history(piece, m.target(), bonus);
+        self.update_history(piece, m.target(), prev_moves, bonus);
         // Subtract a penalty to all other tried moves
         for mt in moves_tried.iter() {
             let piece = pos
                 .piece_at(mt.origin())
                 .expect("Expected a piece at move origin");
-            self.update_history(piece, mt.target(), -bonus);
+            self.update_history(piece, mt.target(), prev_moves, -bonus);
+        }
+    }
+
+    // Update the capture history after a capture caused a beta cutoff
+    pub fn update_captures(
+        &mut self,
+        m: Move,
+        depth: usize,
+        captures_tried: &MoveList,
+        pos: &Position,
+    ) {
+        let bonus = self.bonus(depth);
+        let max = self.max;
+        self.captures.update(Self::capture_idx(m, pos), |entry| {
+            Self::updated_entry(entry, bonus, max)
+        });
+        for mt in captures_tried.iter() {
+            self.captures.update(Self::capture_idx(*mt, pos), |entry| {
+                Self::updated_entry(entry, -bonus, max)
+            });
         }
     }
 
-    fn bonus(depth: usize) -> i32 {
-        (16 * (depth * depth) as i32 + 128 * (depth as i32 - 1).max(0)).min(MAX_BONUS)
+    fn bonus(&self, depth: usize) -> i32 {
+        (self.bonus_quadratic * (depth * depth) as i32
+            + self.bonus_linear * (depth as i32 - 1).max(0))
+        .clamp(0, self.max)
     }
 
-    fn update_history(&mut self, p: Piece, s: Square, delta: i32) {
-        let idx = Self::idx(p, s);
-        let current = &mut self.table[idx];
-        *current += (delta - *current as i32 * delta.abs() / HISTORY_DIVISOR) as i16;
+    fn update_history(&mut self, p: Piece, s: Square, prev_moves: &PrevMoves, delta: i32) {
+        let max = self.max;
+        self.table.update(Self::idx(p, s), |entry| {
+            Self::updated_entry(entry, delta, max)
+        });
+        for (plies_ago, prev) in prev_moves.iter().enumerate() {
+            if let Some((prev_piece, prev_target)) = prev {
+                let idx = Self::continuation_idx(plies_ago, *prev_piece, *prev_target, p, s);
+                self.continuation
+                    .update(idx, |entry| Self::updated_entry(entry, delta, max));
+            }
+        }
+    }
+
+    fn updated_entry(entry: i16, delta: i32, max: i32) -> i16 {
+        debug_assert!(delta.abs() <= max);
+        // Clamped because of the rounding
+        let value = entry as i32 + delta - entry as i32 * delta.abs() / max;
+        value.clamp(-max, max) as i16
     }
 
     pub fn value(&self, p: Piece, to: Square) -> i16 {
-        self.table[Self::idx(p, to)]
+        self.table.get(Self::idx(p, to))
     }
 
-    pub fn clear(&mut self) {
-        for entry in self.table.iter_mut() {
-            *entry = 0;
+    // The sum of the main history and the continuation histories
+    pub fn combined_value(&self, p: Piece, to: Square, prev_moves: &PrevMoves) -> i32 {
+        let mut value = self.value(p, to) as i32;
+        for (plies_ago, prev) in prev_moves.iter().enumerate() {
+            if let Some((prev_piece, prev_target)) = prev {
+                let idx = Self::continuation_idx(plies_ago, *prev_piece, *prev_target, p, to);
+                value += self.continuation.get(idx) as i32;
+            }
         }
+        value
     }
 
-    // Reduce the weight of old entries
+    pub fn capture_value(&self, m: Move, pos: &Position) -> i16 {
+        self.captures.get(Self::capture_idx(m, pos))
+    }
+
+    pub fn clear(&mut self) {
+        self.update_all(|_| 0);
+    }
+
+    fn update_all(&mut self, mut f: impl FnMut(i16) -> i16) {
+        self.table.update_all(&mut f);
+        self.continuation.update_all(&mut f);
+        self.captures.update_all(&mut f);
+    }
+
+    // Reduce the weight of old entries. The continuation history is too large
+    // to be decayed before every search, its entries are only limited by the
+    // gravity formula.
+    // Shared entries must only be decayed by one of the threads.
     pub fn decay(&mut self) {
-        for entry in self.table.iter_mut() {
-            *entry /= 2;
-        }
+        self.table.update_all(|entry| entry / 2);
+        self.captures.update_all(|entry| entry / 2);
+    }
+
+    fn capture_idx(m: Move, pos: &Position) -> usize {
+        debug_assert!(m.is_capture());
+        let piece = pos
+            .piece_at(m.origin())
+            .expect("Expected a piece at move origin");
+        let captured = match m.is_en_passant() {
+            true => piece::Type::Pawn,
+            false => pos
+                .piece_at(m.target())
+                .expect("Expected a piece at move target")
+                .piece_type(),
+        };
+        Self::idx(piece, m.target()) * NUM_PIECE_TYPES + captured as usize
+    }
+
+    fn continuation_idx(
+        plies_ago: usize,
+        prev_piece: Piece,
+        prev_target: Square,
+        p: Piece,
+        s: Square,
+    ) -> usize {
+        (plies_ago * PIECE_SQUARES + Self::idx(prev_piece, prev_target)) * PIECE_SQUARES
+            + Self::idx(p, s)
     }
 
     fn idx(p: Piece, s: Square) -> usize {
         p.idx() * Square::NUM_SQUARES + s.idx()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use movegen::r#move::MoveType;
+
+    #[test]
+    fn values_stay_within_bounds() {
+        // Xorshift, so the test is reproducible
+        let mut rand_state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = || {
+            rand_state ^= rand_state << 13;
+            rand_state ^= rand_state >> 7;
+            rand_state ^= rand_state << 17;
+            rand_state
+        };
+
+        let pos = Position::initial();
+        let quiets = [
+            Move::new(Square::G1, Square::F3, MoveType::QUIET),
+            Move::new(Square::B1, Square::C3, MoveType::QUIET),
+            Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH),
+            Move::new(Square::D2, Square::D3, MoveType::QUIET),
+        ];
+        let prev_moves = [
+            Some((Piece::BLACK_PAWN, Square::E5)),
+            Some((Piece::WHITE_KNIGHT, Square::F3)),
+        ];
+
+        for (max, shared) in [
+            (HISTORY_MAX, false),
+            (i16::MAX as i32, false),
+            (100, false),
+            (100, true),
+        ] {
+            let mut history_table = HistoryTable::new(shared);
+            history_table.set_max(max);
+            history_table.set_bonus_quadratic(1000);
+            for _ in 0..1_000_000 {
+                let r = random();
+                let depth = (r % 64) as usize;
+                let m = quiets[(r >> 8) as usize % quiets.len()];
+                let moves_tried = match (r >> 16) % 2 {
+                    0 => MoveList::new(),
+                    _ => MoveList::from(
+                        quiets
+                            .iter()
+                            .copied()
+                            .filter(|&x| x != m)
+                            .collect::<Vec<_>>(),
+                    ),
+                };
+                history_table.update(m, depth, &moves_tried, &pos, &prev_moves);
+            }
+            for entries in [
+                &history_table.table,
+                &history_table.continuation,
+                &history_table.captures,
+            ] {
+                for idx in 0..entries.len() {
+                    assert!((entries.get(idx) as i32).abs() <= max);
+                }
+            }
+            for m in quiets {
+                let p = pos.piece_at(m.origin()).unwrap();
+                let value = history_table.combined_value(p, m.target(), &prev_moves);
+                assert!(value.abs() <= (1 + CONTINUATION_PLIES as i32) * max);
+            }
+        }
+    }
+
+    #[test]
+    fn shared_entries() {
+        let pos = Position::initial();
+        let m = Move::new(Square::G1, Square::F3, MoveType::QUIET);
+        let p = Piece::WHITE_KNIGHT;
+        let prev_moves = [None, None];
+
+        assert!(HistoryTable::new(false).share().is_none());
+        let mut main = HistoryTable::new(true);
+        let mut helper = main.share().unwrap();
+        assert!(main.is_shared() && helper.is_shared());
+
+        // Updates are visible to both tables
+        helper.update(m, 4, &MoveList::new(), &pos, &prev_moves);
+        let value = main.value(p, m.target());
+        assert!(value > 0);
+        assert_eq!(value, helper.value(p, m.target()));
+
+        // The shared entries are rescaled only once
+        main.set_max(HISTORY_MAX / 2);
+        helper.copy_params(&main);
+        assert_eq!(value / 2, helper.value(p, m.target()));
+
+        main.clear();
+        assert_eq!(0, helper.value(p, m.target()));
+    }
+}
@@ -2,12 +2,16 @@ pub use search_options::SearchOptions;
 
 pub mod alpha_beta;
 pub mod aspiration_window;
+pub mod bench;
 pub mod search;
 pub mod search_params;
 pub mod searcher;
+pub mod strength;
 
 mod alpha_beta_entry;
+mod correction_history;
 mod counter_table;
+mod eval_cache;
 mod history_table;
 mod move_candidates;
 mod move_selector;
@@ -15,5 +19,7 @@ mod node_counter;
 mod pv_table;
 mod search_data;
 mod search_options;
+mod search_tracer;
+mod shared_table;
 mod static_exchange_eval;
 mod time_manager;
@@ -1,4 +1,5 @@
 use movegen::r#move::{Move, MoveList};
+use std::cmp;
 
 #[derive(Debug, Clone)]
 pub struct MoveData {
@@ -40,9 +41,22 @@ impl MoveCandidates {
         self.move_list[idx].subtree_size = node_count;
     }
 
+    // Fraction of the nodes at the root that were searched in the subtree of
+    // the given move. None if no nodes have been counted yet.
+    pub fn node_fraction(&self, m: Move) -> Option<f64> {
+        let total: u64 = self.move_list.iter().map(|md| md.subtree_size).sum();
+        match total {
+            0 => None,
+            _ => Some(self.move_list[self.index(m)].subtree_size as f64 / total as f64),
+        }
+    }
+
+    // The moves that raised alpha in the last iteration stay in front (the
+    // best one first). The others are ordered by the size of their subtrees.
+    // The sort is stable, so moves with equal sizes (e.g. not searched
+    // because the iteration was stopped) keep their previous order.
     pub fn order_by_subtree_size(&mut self) {
-        self.move_list[self.alpha_raised_count..]
-            .sort_unstable_by_key(|md| u64::MAX - md.subtree_size);
+        self.move_list[self.alpha_raised_count..].sort_by_key(|md| cmp::Reverse(md.subtree_size));
     }
 
     pub fn reset_counts(&mut self) {
@@ -65,3 +79,56 @@ impl MoveCandidates {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use movegen::r#move::MoveType;
+    use movegen::square::Square;
+
+    fn moves() -> MoveList {
+        let mut move_list = MoveList::new();
+        for (origin, target) in [
+            (Square::A2, Square::A3),
+            (Square::B2, Square::B3),
+            (Square::C2, Square::C3),
+            (Square::D2, Square::D3),
+            (Square::E2, Square::E3),
+        ] {
+            move_list.push(Move::new(origin, target, MoveType::QUIET));
+        }
+        move_list
+    }
+
+    fn order(candidates: &MoveCandidates) -> Vec<Move> {
+        candidates.move_list.iter().map(|md| md.r#move).collect()
+    }
+
+    #[test]
+    fn order_by_subtree_size() {
+        let m = moves();
+        let mut candidates = MoveCandidates::from(&m);
+        assert_eq!(None, candidates.node_fraction(m[0]));
+
+        // The best move was moved to the front, the other moves are ordered by
+        // their subtree sizes. The last two moves haven't been searched, they
+        // keep their order.
+        candidates.set_subtree_size(m[0], 50);
+        candidates.set_subtree_size(m[1], 150);
+        candidates.set_subtree_size(m[2], 800);
+        candidates.move_to_front(m[2]);
+        candidates.alpha_raised_count += 1;
+        assert_eq!(Some(0.8), candidates.node_fraction(m[2]));
+        candidates.order_by_subtree_size();
+        candidates.reset_counts();
+        assert_eq!(vec![m[2], m[1], m[0], m[3], m[4]], order(&candidates));
+
+        // Equal sizes keep the order of the previous iteration
+        for &mv in m.iter() {
+            candidates.set_subtree_size(mv, 10);
+        }
+        candidates.set_subtree_size(m[4], 20);
+        candidates.order_by_subtree_size();
+        assert_eq!(vec![m[4], m[2], m[1], m[0], m[3]], order(&candidates));
+    }
+}
@@ -1,13 +1,17 @@
-use crate::alpha_beta::AlphaBetaTable;
 use crate::counter_table::CounterTable;
 use crate::history_table::HistoryTable;
 use crate::search_data::SearchData;
+use crate::shared_table::SharedTable;
 use crate::static_exchange_eval::static_exchange_eval;
 use eval::Score;
+use movegen::move_generator::MoveGenerator;
 use movegen::piece;
 use movegen::position::Position;
 use movegen::r#move::{Move, MoveList};
 
+const CAPTURE_SCORE_MVV_LVA_FACTOR: i32 = 512;
+const CAPTURE_SCORE_HISTORY_DIVISOR: i32 = 4;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u8)]
 pub enum Stage {
@@ -29,9 +33,17 @@ struct MoveInfo {
     r#move: Move,
 }
 
+// The moves are either passed in completely or generated in stages. In the
+// latter case, the tactical moves (captures and promotions) are generated
+// after the hash move and the quiet moves after the counter move. Moves
+// selected before their stage has been generated are validated and remembered
+// so that they are not selected twice.
 pub struct MoveSelector {
     stage: Stage,
     moves: Vec<MoveInfo>,
+    tactical_generated: bool,
+    quiets_generated: bool,
+    selected_before_generation: MoveList,
 }
 
 impl MoveSelector {
@@ -39,6 +51,19 @@ impl MoveSelector {
         MoveSelector {
             stage: Stage::PrincipalVariation,
             moves: move_list.iter().map(|&x| MoveInfo { r#move: x }).collect(),
+            tactical_generated: true,
+            quiets_generated: true,
+            selected_before_generation: MoveList::new(),
+        }
+    }
+
+    pub fn new_staged() -> Self {
+        MoveSelector {
+            stage: Stage::PrincipalVariation,
+            moves: Vec::new(),
+            tactical_generated: false,
+            quiets_generated: false,
+            selected_before_generation: MoveList::new(),
         }
     }
 
@@ -49,7 +74,7 @@ impl MoveSelector {
     pub fn select_next_move(
         &mut self,
         search_data: &mut SearchData,
-        transpos_table: &mut AlphaBetaTable,
+        transpos_table: &SharedTable,
         counter_table: &CounterTable,
         history_table: &HistoryTable,
     ) -> Option<Move> {
@@ -80,6 +105,13 @@ impl MoveSelector {
         }
 
         if self.stage == Stage::QueenPromoCaptures {
+            if !self.tactical_generated {
+                self.generate(
+                    search_data.current_pos(),
+                    MoveGenerator::generate_moves_tactical,
+                );
+                self.tactical_generated = true;
+            }
             if let Some(m) = self.select_queen_promo_capture() {
                 return Some(m);
             }
@@ -94,7 +126,7 @@ impl MoveSelector {
         }
 
         if self.stage == Stage::WinningOrEqualCaptures {
-            if let Some(m) = self.select_winning_capture(search_data) {
+            if let Some(m) = self.select_winning_capture(search_data, history_table) {
                 return Some(m);
             }
             self.stage = Stage::Killers;
@@ -115,6 +147,13 @@ impl MoveSelector {
         }
 
         if self.stage == Stage::History {
+            if !self.quiets_generated {
+                self.generate(
+                    search_data.current_pos(),
+                    MoveGenerator::generate_moves_quiet,
+                );
+                self.quiets_generated = true;
+            }
             if let Some(m) = self.select_history(search_data, history_table) {
                 return Some(m);
             }
@@ -122,7 +161,7 @@ impl MoveSelector {
         }
 
         if self.stage == Stage::LosingCaptures {
-            if let Some(m) = self.select_losing_capture(search_data) {
+            if let Some(m) = self.select_losing_capture(search_data, history_table) {
                 return Some(m);
             }
             self.stage = Stage::UnderPromoCaptures;
@@ -147,7 +186,8 @@ impl MoveSelector {
     pub fn select_next_move_quiescence_capture(
         &mut self,
         search_data: &mut SearchData,
-        transpos_table: &mut AlphaBetaTable,
+        transpos_table: &SharedTable,
+        history_table: &HistoryTable,
     ) -> Option<Move> {
         if self.stage == Stage::PrincipalVariation || self.stage == Stage::Hash {
             if let Some(m) = self.select_hash_move(search_data, transpos_table) {
@@ -171,7 +211,7 @@ impl MoveSelector {
         }
 
         debug_assert_eq!(Stage::WinningOrEqualCaptures, self.stage);
-        self.select_winning_capture(search_data)
+        self.select_winning_capture(search_data, history_table)
     }
 
     fn select_pv_move(&mut self, search_data: &mut SearchData) -> Option<Move> {
@@ -179,13 +219,24 @@ impl MoveSelector {
             // Select the PV move from the previous iteration
             let prev_pv = search_data.pv(search_data.search_depth() - 1);
             let pv_move = prev_pv[search_data.ply()];
+            if pv_move == Move::NULL {
+                // The previous PV ended here, e.g. in checkmate or at a table
+                // cutoff. The other moves of this node may still be searched,
+                // and their subtrees are off the previous PV.
+                search_data.end_prev_pv();
+                return None;
+            }
+            if let Some(m) = self.select_before_generation(search_data.current_pos(), pv_move) {
+                search_data.decrease_prev_pv_depth();
+                return Some(m);
+            }
             let idx = self
                 .moves
                 .iter()
                 .position(|x| x.r#move == pv_move)
                 .unwrap_or_else(|| {
-                    let move_list =
-                        MoveList::from(self.moves.iter().map(|x| x.r#move).collect::<Vec<_>>());
+                    let mut move_list = MoveList::new();
+                    MoveGenerator::generate_moves(&mut move_list, search_data.current_pos());
                     panic!(
                         "\nPV move not found in move list\n\
                         Search depth: {}\nNet search depth: {}\nRemaining depth: {}\nPly: {}\nPrevious PV depth: {}\n\
@@ -215,7 +266,7 @@ impl MoveSelector {
     fn select_hash_move(
         &mut self,
         search_data: &mut SearchData,
-        transpos_table: &mut AlphaBetaTable,
+        transpos_table: &SharedTable,
     ) -> Option<Move> {
         if let Some(entry) = transpos_table.get(&search_data.current_pos_hash()) {
             if let Some(idx) = self
@@ -225,10 +276,39 @@ impl MoveSelector {
             {
                 return Some(self.moves.swap_remove(idx).r#move);
             }
+            return self.select_before_generation(search_data.current_pos(), entry.best_move());
         }
         None
     }
 
+    // Selects a move whose stage has not been generated yet, if it is legal
+    fn select_before_generation(&mut self, pos: &Position, m: Move) -> Option<Move> {
+        let generated = match m.is_capture() || m.is_promotion() {
+            true => self.tactical_generated,
+            false => self.quiets_generated,
+        };
+        if m == Move::NULL
+            || generated
+            || self.selected_before_generation.contains(&m)
+            || !MoveGenerator::is_legal_move(pos, m)
+        {
+            return None;
+        }
+        self.selected_before_generation.push(m);
+        Some(m)
+    }
+
+    fn generate(&mut self, pos: &Position, generate_moves: fn(&mut MoveList, &Position)) {
+        let mut move_list = MoveList::new();
+        generate_moves(&mut move_list, pos);
+        self.moves.extend(
+            move_list
+                .iter()
+                .filter(|m| !self.selected_before_generation.contains(m))
+                .map(|&x| MoveInfo { r#move: x }),
+        );
+    }
+
     fn select_queen_promo_capture(&mut self) -> Option<Move> {
         if let Some(idx) = self.moves.iter().enumerate().position(|(_, x)| {
             let m = x.r#move;
@@ -279,14 +359,19 @@ impl MoveSelector {
         None
     }
 
-    fn select_winning_capture(&mut self, search_data: &mut SearchData) -> Option<Move> {
+    fn select_winning_capture(
+        &mut self,
+        search_data: &mut SearchData,
+        history_table: &HistoryTable,
+    ) -> Option<Move> {
         let mut end = self.moves.len();
         while let Some((idx, m, _)) = self.moves[..end]
             .iter()
             .enumerate()
             .filter(|(_, x)| x.r#move.is_capture())
             .map(|(idx, x)| {
-                let cap_score = Self::capture_score(search_data.current_pos(), x.r#move);
+                let cap_score =
+                    Self::capture_score(search_data.current_pos(), x.r#move, history_table);
                 (idx, x.r#move, cap_score)
             })
             .max_by_key(|&(_, _, cap_score)| cap_score)
@@ -303,14 +388,19 @@ impl MoveSelector {
         None
     }
 
-    fn select_losing_capture(&mut self, search_data: &mut SearchData) -> Option<Move> {
+    fn select_losing_capture(
+        &mut self,
+        search_data: &mut SearchData,
+        history_table: &HistoryTable,
+    ) -> Option<Move> {
         if let Some((idx, m, _)) = self
             .moves
             .iter()
             .enumerate()
             .filter(|(_, x)| x.r#move.is_capture())
             .map(|(idx, x)| {
-                let cap_score = Self::capture_score(search_data.current_pos(), x.r#move);
+                let cap_score =
+                    Self::capture_score(search_data.current_pos(), x.r#move, history_table);
                 (idx, x.r#move, cap_score)
             })
             .max_by_key(|&(_, _, cap_score)| cap_score)
@@ -343,11 +433,15 @@ impl MoveSelector {
             return None;
         }
 
-        for k in search_data.killers().iter().flatten() {
+        let killers = *search_data.killers();
+        for k in killers.iter().flatten() {
             if let Some(idx) = self.moves.iter().position(|x| x.r#move == *k) {
                 let next_move = self.moves.swap_remove(idx).r#move;
                 return Some(next_move);
             }
+            if let Some(m) = self.select_before_generation(search_data.current_pos(), *k) {
+                return Some(m);
+            }
         }
 
         None
@@ -365,6 +459,7 @@ impl MoveSelector {
                     if let Some(idx) = self.moves.iter().position(|x| x.r#move == counter) {
                         return Some(self.moves.swap_remove(idx).r#move);
                     }
+                    return self.select_before_generation(search_data.current_pos(), counter);
                 }
             }
         }
@@ -377,6 +472,7 @@ impl MoveSelector {
         search_data: &mut SearchData,
         history_table: &HistoryTable,
     ) -> Option<Move> {
+        let prev_moves = search_data.prev_moves();
         if let Some((idx, m)) = self
             .moves
             .iter()
@@ -387,7 +483,7 @@ impl MoveSelector {
                     .current_pos()
                     .piece_at(x.r#move.origin())
                     .expect("Expected a piece at move origin");
-                history_table.value(p, x.r#move.target())
+                history_table.combined_value(p, x.r#move.target(), &prev_moves)
             })
         {
             debug_assert_eq!(m.r#move, self.moves[idx].r#move);
@@ -399,7 +495,11 @@ impl MoveSelector {
     }
 
     fn select_root_move(&mut self, search_data: &mut SearchData) -> Option<Move> {
-        debug_assert_ne!(0, search_data.root_moves().current_idx);
+        // The PV move has already been selected, unless there is no PV from the
+        // previous iteration (helper threads may skip search depths)
+        debug_assert!(
+            search_data.root_moves().current_idx != 0 || search_data.prev_pv_depth() == 0
+        );
         let candidates = search_data.root_moves_mut();
         let moves = &candidates.move_list;
         let idx = &mut candidates.current_idx;
@@ -431,9 +531,12 @@ impl MoveSelector {
         MVV_LVA_SCORES[victim_idx][attacker_idx]
     }
 
-    fn capture_score(pos: &Position, m: Move) -> Score {
+    // MVV-LVA, adjusted by the capture history. The history can change the
+    // order of captures of the same victim, but rarely of different victims.
+    fn capture_score(pos: &Position, m: Move, history_table: &HistoryTable) -> i32 {
         let (attacker, target) = Self::capture_piece_types(pos, m);
-        Self::mvv_lva_score(attacker, target)
+        Self::mvv_lva_score(attacker, target) as i32 * CAPTURE_SCORE_MVV_LVA_FACTOR
+            + history_table.capture_value(m, pos) as i32 / CAPTURE_SCORE_HISTORY_DIVISOR
     }
 
     fn piece_type_idx(pt: piece::Type) -> usize {
@@ -1,9 +1,26 @@
 use std::fmt;
 
+#[derive(Debug, Clone, Copy, Default)]
+struct PlyCounts {
+    nodes: u64,
+    // Transposition table lookups and how many of them found an entry for the
+    // position. Their ratio is the table hit rate.
+    table_probes: u64,
+    table_entries_found: u64,
+    // Table entries that were usable for a cutoff
+    cache_hits: u64,
+    // Beta cutoffs in the main search and how many of them were caused by the
+    // first move. Their ratio measures the quality of the move ordering.
+    beta_cutoffs: u64,
+    first_move_cutoffs: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct NodeCounter {
-    node_counts: Vec<Vec<(u64, u64)>>,
+    node_counts: Vec<Vec<PlyCounts>>,
     eval_count: Vec<u64>,
+    // Evaluations found in the eval cache
+    eval_cache_hits: Vec<u64>,
     max_depth: usize,
 }
 
@@ -12,38 +29,89 @@ impl NodeCounter {
         Self {
             node_counts: Vec::new(),
             eval_count: Vec::new(),
+            eval_cache_hits: Vec::new(),
             max_depth: 0,
         }
     }
 
     pub fn increment_nodes(&mut self, search_depth: usize, ply: usize) {
-        self.reserve(search_depth);
-        self.node_counts[search_depth - 1][ply.min(search_depth)].0 += 1;
+        self.ply_counts_mut(search_depth, ply).nodes += 1;
+    }
+
+    pub fn increment_table_probes(&mut self, search_depth: usize, ply: usize, found: bool) {
+        let counts = self.ply_counts_mut(search_depth, ply);
+        counts.table_probes += 1;
+        counts.table_entries_found += found as u64;
     }
 
     pub fn increment_cache_hits(&mut self, search_depth: usize, ply: usize) {
-        self.reserve(search_depth);
-        self.node_counts[search_depth - 1][ply.min(search_depth)].1 += 1;
+        self.ply_counts_mut(search_depth, ply).cache_hits += 1;
     }
 
-    pub fn increment_eval_calls(&mut self, search_depth: usize) {
+    pub fn increment_beta_cutoffs(&mut self, search_depth: usize, ply: usize, first_move: bool) {
+        let counts = self.ply_counts_mut(search_depth, ply);
+        counts.beta_cutoffs += 1;
+        counts.first_move_cutoffs += first_move as u64;
+    }
+
+    pub fn increment_eval_calls(&mut self, search_depth: usize, cache_hit: bool) {
         self.reserve(search_depth);
         self.eval_count[search_depth - 1] += 1;
+        self.eval_cache_hits[search_depth - 1] += cache_hit as u64;
     }
 
     pub fn sum_nodes(&self) -> u64 {
         self.node_counts
             .iter()
-            .map(|nc| nc.iter().map(|x| x.0).sum::<u64>())
+            .map(|nc| nc.iter().map(|x| x.nodes).sum::<u64>())
             .sum()
     }
 
+    // Fraction of table probes that found an entry, in permille
+    pub fn table_hit_rate_permille(&self) -> u64 {
+        let (probes, found) = self.node_counts.iter().flatten().fold((0, 0), |(p, f), x| {
+            (p + x.table_probes, f + x.table_entries_found)
+        });
+        match probes {
+            0 => 0,
+            _ => 1000 * found / probes,
+        }
+    }
+
+    // Fraction of evaluate calls answered by the eval cache, in permille
+    pub fn eval_cache_hit_rate_permille(&self) -> u64 {
+        let calls = self.eval_count.iter().sum::<u64>();
+        let hits = self.eval_cache_hits.iter().sum::<u64>();
+        match calls {
+            0 => 0,
+            _ => 1000 * hits / calls,
+        }
+    }
+
+    // Fraction of beta cutoffs caused by the first move, in permille
+    pub fn first_move_cutoff_rate_permille(&self) -> u64 {
+        let (cutoffs, first) = self.node_counts.iter().flatten().fold((0, 0), |(c, f), x| {
+            (c + x.beta_cutoffs, f + x.first_move_cutoffs)
+        });
+        match cutoffs {
+            0 => 0,
+            _ => 1000 * first / cutoffs,
+        }
+    }
+
+    fn ply_counts_mut(&mut self, search_depth: usize, ply: usize) -> &mut PlyCounts {
+        self.reserve(search_depth);
+        &mut self.node_counts[search_depth - 1][ply.min(search_depth)]
+    }
+
     fn reserve(&mut self, search_depth: usize) {
-        if search_depth > self.max_depth {
-            debug_assert!(search_depth == self.max_depth + 1);
-            self.node_counts.push(vec![(0, 0); search_depth + 1]);
-            self.eval_count.push(0);
+        // Helper threads may skip search depths
+        while search_depth > self.max_depth {
             self.max_depth += 1;
+            self.node_counts
+                .push(vec![PlyCounts::default(); self.max_depth + 1]);
+            self.eval_count.push(0);
+            self.eval_cache_hits.push(0);
         }
     }
 }
@@ -52,16 +120,35 @@ impl fmt::Display for NodeCounter {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for d in 1..=self.max_depth {
             writeln!(f, "Search depth {d}:")?;
-            writeln!(f, "\tEvaluate calls: {}", self.eval_count[d - 1])?;
+            writeln!(
+                f,
+                "\tEvaluate calls / eval cache hits: {} / {}",
+                self.eval_count[d - 1],
+                self.eval_cache_hits[d - 1],
+            )?;
             for p in 0..=d {
                 let nc = &self.node_counts[d - 1][p];
                 writeln!(
                     f,
-                    "\tPly / moves made / cache hits: {} / {} / {}",
-                    p, nc.0, nc.1,
+                    "\tPly / moves made / table probes / entries found / cache hits: {} / {} / {} / {} / {}",
+                    p, nc.nodes, nc.table_probes, nc.table_entries_found, nc.cache_hits,
                 )?;
             }
         }
-        Ok(())
+        writeln!(
+            f,
+            "Table hit rate: {} permille",
+            self.table_hit_rate_permille()
+        )?;
+        writeln!(
+            f,
+            "Eval cache hit rate: {} permille",
+            self.eval_cache_hit_rate_permille()
+        )?;
+        writeln!(
+            f,
+            "First move cutoff rate: {} permille",
+            self.first_move_cutoff_rate_permille()
+        )
     }
 }
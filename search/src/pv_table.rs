@@ -1,6 +1,23 @@
+use movegen::move_generator::MoveGenerator;
+use movegen::position_history::PositionHistory;
 use movegen::r#move::{Move, MoveList};
 use std::fmt;
 
+// Plays the PV on pos_history and truncates it at the first move that isn't
+// legal. PV moves may come from the hash table, so a stale entry can graft an
+// illegal move onto the line. Null moves end the PV, too.
+pub fn truncate_at_illegal_move(pos_history: &mut PositionHistory, pv: &mut MoveList) {
+    let mut legal_moves = MoveList::new();
+    for i in 0..pv.len() {
+        MoveGenerator::generate_moves(&mut legal_moves, pos_history.current_pos());
+        if pv[i] == Move::NULL || !legal_moves.contains(&pv[i]) {
+            pv.truncate(i);
+            return;
+        }
+        pos_history.do_move(pv[i]);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PvTable {
     table: Vec<Move>,
@@ -66,11 +83,11 @@ impl PvTable {
     }
 
     fn reserve(&mut self, depth: usize) {
-        if depth > self.max_depth {
-            debug_assert!(depth == self.max_depth + 1);
+        // Helper threads may skip search depths
+        while depth > self.max_depth {
             self.indices.push(self.table.len());
             self.max_depth += 1;
-            for _ in 0..depth {
+            for _ in 0..self.max_depth {
                 self.table.push(Move::NULL);
             }
         }
@@ -85,3 +102,43 @@ impl fmt::Display for PvTable {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use movegen::position::Position;
+    use movegen::r#move::MoveType;
+    use movegen::square::Square;
+
+    #[test]
+    fn truncate_pv_at_illegal_move() {
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        let e7e5 = Move::new(Square::E7, Square::E5, MoveType::DOUBLE_PAWN_PUSH);
+        let g1f3 = Move::new(Square::G1, Square::F3, MoveType::QUIET);
+        // Legal in the initial position, but not after 1. e4 e5
+        let e2e3 = Move::new(Square::E2, Square::E3, MoveType::QUIET);
+
+        let mut pos_history = PositionHistory::new(Position::initial());
+        let mut pv = MoveList::from(vec![e2e4, e7e5, g1f3]);
+        truncate_at_illegal_move(&mut pos_history, &mut pv);
+        assert_eq!(MoveList::from(vec![e2e4, e7e5, g1f3]), pv);
+        assert_eq!(Some(&g1f3), pos_history.last_move());
+
+        let mut pos_history = PositionHistory::new(Position::initial());
+        let mut pv = MoveList::from(vec![e2e4, e7e5, e2e3, g1f3]);
+        truncate_at_illegal_move(&mut pos_history, &mut pv);
+        assert_eq!(MoveList::from(vec![e2e4, e7e5]), pv);
+        assert_eq!(Some(&e7e5), pos_history.last_move());
+
+        // Wrong side to move
+        let mut pos_history = PositionHistory::new(Position::initial());
+        let mut pv = MoveList::from(vec![e7e5, e2e4]);
+        truncate_at_illegal_move(&mut pos_history, &mut pv);
+        assert!(pv.is_empty());
+
+        let mut pos_history = PositionHistory::new(Position::initial());
+        let mut pv = MoveList::from(vec![e2e4, Move::NULL, g1f3]);
+        truncate_at_illegal_move(&mut pos_history, &mut pv);
+        assert_eq!(MoveList::from(vec![e2e4]), pv);
+    }
+}
@@ -7,20 +7,54 @@ use movegen::r#move::{Move, MoveList};
 use std::fmt;
 use std::ops::Neg;
 
-pub const MAX_SEARCH_DEPTH: usize = u8::MAX as usize;
+// Maximum distance of a node from the root, including extensions and the
+// quiescence search. It also limits the search depth. The depths, mate
+// distances and per-ply data of the search are bounded by it.
+pub const MAX_PLY: usize = 128;
+const _: () = assert!(MAX_PLY < u8::MAX as usize);
+
 pub const REPETITIONS_TO_DRAW: usize = 3;
 pub const PLIES_WITHOUT_PAWN_MOVE_OR_CAPTURE_TO_DRAW: usize = 100;
 
+// The nodes per second are computed with at least this elapsed time.
+// Otherwise, the first depths, which are searched within a few microseconds,
+// report absurdly high values.
+const MIN_NPS_TIME_US: u64 = 1000;
+
+// Whether the score is exact or only a bound. Like the score, this is from
+// white's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreBound {
+    Exact,
+    LowerBound,
+    UpperBound,
+}
+
+impl Neg for ScoreBound {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            ScoreBound::Exact => ScoreBound::Exact,
+            ScoreBound::LowerBound => ScoreBound::UpperBound,
+            ScoreBound::UpperBound => ScoreBound::LowerBound,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SearchResult {
     depth: u8,
     selective_depth: u8,
     score: Score,
+    score_bound: ScoreBound,
     nodes: u64,
     time_us: u64,
     hash_load_factor_permille: u16,
     best_move: Move,
     pv: MoveList,
+    // Root moves that failed low, each followed by the reply that refuted it
+    refutations: Vec<MoveList>,
 }
 
 impl SearchResult {
@@ -35,16 +69,18 @@ impl SearchResult {
         best_move: Move,
         pv: MoveList,
     ) -> Self {
-        debug_assert!(depth <= MAX_SEARCH_DEPTH);
+        debug_assert!(depth <= MAX_PLY);
         Self {
             depth: depth as u8,
             selective_depth: selective_depth as u8,
             score,
+            score_bound: ScoreBound::Exact,
             nodes,
             time_us,
             hash_load_factor_permille,
             best_move,
             pv,
+            refutations: Vec::new(),
         }
     }
 
@@ -60,6 +96,15 @@ impl SearchResult {
         self.score
     }
 
+    pub fn score_bound(&self) -> ScoreBound {
+        self.score_bound
+    }
+
+    pub fn with_score_bound(mut self, score_bound: ScoreBound) -> Self {
+        self.score_bound = score_bound;
+        self
+    }
+
     pub fn nodes(&self) -> u64 {
         self.nodes
     }
@@ -72,11 +117,8 @@ impl SearchResult {
         self.time_us
     }
 
-    pub fn nodes_per_second(&self) -> i32 {
-        match self.time_us() {
-            0 => -1,
-            _ => (1_000_000 * self.nodes() / self.time_us()) as i32,
-        }
+    pub fn nodes_per_second(&self) -> u64 {
+        (1_000_000 * self.nodes() as u128 / self.time_us().max(MIN_NPS_TIME_US) as u128) as u64
     }
 
     pub fn hash_load_factor_permille(&self) -> u16 {
@@ -90,6 +132,15 @@ impl SearchResult {
     pub fn principal_variation(&self) -> &MoveList {
         &self.pv
     }
+
+    pub fn refutations(&self) -> &[MoveList] {
+        &self.refutations
+    }
+
+    pub fn with_refutations(mut self, refutations: Vec<MoveList>) -> Self {
+        self.refutations = refutations;
+        self
+    }
 }
 
 impl Neg for SearchResult {
@@ -107,6 +158,8 @@ impl Neg for SearchResult {
             self.best_move(),
             self.principal_variation().clone(),
         )
+        .with_score_bound(-self.score_bound())
+        .with_refutations(self.refutations)
     }
 }
 
@@ -126,12 +179,110 @@ impl fmt::Display for SearchResult {
     }
 }
 
+// The root move that is currently being searched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentMove {
+    depth: u8,
+    current_move: Move,
+    move_number: u8,
+}
+
+impl CurrentMove {
+    pub fn new(depth: usize, current_move: Move, move_number: usize) -> Self {
+        debug_assert!(depth <= MAX_PLY);
+        debug_assert!(move_number <= u8::MAX as usize);
+        Self {
+            depth: depth as u8,
+            current_move,
+            move_number: move_number as u8,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth as usize
+    }
+
+    pub fn current_move(&self) -> Move {
+        self.current_move
+    }
+
+    // 1-based index of the move in the root move list
+    pub fn move_number(&self) -> usize {
+        self.move_number as usize
+    }
+}
+
+impl fmt::Display for CurrentMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "depth: {}, current move: {}, move number: {}",
+            self.depth(),
+            self.current_move(),
+            self.move_number()
+        )
+    }
+}
+
+// Progress of a search that is still within an iteration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchProgress {
+    nodes: u64,
+    time_us: u64,
+    hash_load_factor_permille: u16,
+}
+
+impl SearchProgress {
+    pub fn new(nodes: u64, time_us: u64, hash_load_factor_permille: u16) -> Self {
+        Self {
+            nodes,
+            time_us,
+            hash_load_factor_permille,
+        }
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn time_ms(&self) -> u64 {
+        self.time_us / 1000
+    }
+
+    pub fn time_us(&self) -> u64 {
+        self.time_us
+    }
+
+    pub fn nodes_per_second(&self) -> u64 {
+        (1_000_000 * self.nodes() as u128 / self.time_us().max(MIN_NPS_TIME_US) as u128) as u64
+    }
+
+    pub fn hash_load_factor_permille(&self) -> u16 {
+        self.hash_load_factor_permille
+    }
+}
+
+impl fmt::Display for SearchProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "nodes: {}, time in us: {}, hash load factor permille: {}",
+            self.nodes(),
+            self.time_us(),
+            self.hash_load_factor_permille()
+        )
+    }
+}
+
 #[derive(Debug)]
 pub enum SearchCommand {
     SetHashSize(usize, Sender<()>),
     ClearHashTable(Sender<()>),
-    SetSearchParams(SearchParamsEachAlgo, Sender<()>),
+    SetSearchParams(Box<SearchParamsEachAlgo>, Sender<()>),
     Search(Box<(PositionHistory, SearchOptions)>),
+    // The sender is dropped once all earlier commands have been handled or
+    // a search is running
+    IsReady(Sender<()>),
     Stop,
     Terminate,
 }
@@ -139,6 +290,13 @@ pub enum SearchCommand {
 #[derive(Debug)]
 pub enum SearchInfo {
     DepthFinished(SearchResult),
+    // The score of the current depth is outside the aspiration window. The
+    // depth gets searched again with a wider window.
+    AspirationFail(SearchResult),
+    CurrentMove(CurrentMove),
+    Progress(SearchProgress),
+    // A traced node of the search tree as a JSON line
+    Trace(String),
     Stopped(Move),
     Terminated,
 }
@@ -147,6 +305,10 @@ impl fmt::Display for SearchInfo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             SearchInfo::DepthFinished(search_res) => write!(f, "Depth finished: {search_res}"),
+            SearchInfo::AspirationFail(search_res) => write!(f, "Aspiration fail: {search_res}"),
+            SearchInfo::CurrentMove(current_move) => write!(f, "Current move: {current_move}"),
+            SearchInfo::Progress(progress) => write!(f, "Progress: {progress}"),
+            SearchInfo::Trace(line) => write!(f, "Trace: {line}"),
             SearchInfo::Stopped(best_move) => write!(f, "Search stopped: {best_move}"),
             SearchInfo::Terminated => write!(f, "Search terminated"),
         }
@@ -160,11 +322,47 @@ pub trait Search {
 
     fn set_params(&mut self, params: SearchParamsEachAlgo);
 
+    // Returns a new search or terminate command that was received during the
+    // search. The caller handles it next.
     fn search(
         &mut self,
         pos_history: PositionHistory,
         search_options: SearchOptions,
         command_receiver: &Receiver<SearchCommand>,
         info_sender: &Sender<SearchInfo>,
-    );
+    ) -> Option<SearchCommand>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search_result(nodes: u64, time_us: u64) -> SearchResult {
+        SearchResult::new(1, 1, 0, nodes, time_us, 0, Move::NULL, MoveList::new())
+    }
+
+    #[test]
+    fn nodes_per_second() {
+        assert_eq!(0, search_result(0, 0).nodes_per_second());
+        assert_eq!(2_000_000, search_result(3000, 1500).nodes_per_second());
+
+        // The elapsed time is floored. Nodes and time stay exact.
+        let res = search_result(20, 3);
+        assert_eq!(20_000, res.nodes_per_second());
+        assert_eq!(20, res.nodes());
+        assert_eq!(3, res.time_us());
+        assert_eq!(0, res.time_ms());
+        assert_eq!(20_000, search_result(20, 0).nodes_per_second());
+
+        // No overflow for large node counts
+        let nodes = 100_000_000_000;
+        assert_eq!(
+            1_000_000_000,
+            search_result(nodes, 100_000_000).nodes_per_second()
+        );
+        assert_eq!(
+            1_000_000_000_000,
+            search_result(nodes, 100_000).nodes_per_second()
+        );
+    }
 }
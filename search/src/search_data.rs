@@ -1,83 +1,156 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
+use crate::alpha_beta_entry::AlphaBetaEntry;
+use crate::eval_cache::EvalCache;
+use crate::history_table::PrevMoves;
 use crate::move_candidates::MoveCandidates;
 use crate::node_counter::NodeCounter;
 use crate::pv_table::PvTable;
-use crate::search::{SearchCommand, SearchInfo};
+use crate::search::{SearchCommand, SearchInfo, MAX_PLY};
+use crate::search_params::SearchParamsEachAlgo;
+use crate::search_tracer::{SearchTracer, TableProbe};
+use crate::SearchOptions;
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use eval::{Eval, Score};
+use movegen::piece::Piece;
 use movegen::position::Position;
 use movegen::position_history::PositionHistory;
 use movegen::r#move::{Move, MoveList};
 use movegen::side::Side;
+use movegen::square::Square;
 use movegen::zobrist::Zobrist;
 
 pub type Killers = [Option<Move>; NUM_KILLERS];
 
 const NUM_KILLERS: usize = 2;
 
-// The maximum number of search depth extensions
-const MAX_EXTENSIONS: usize = 2;
+// The maximum number of search depth extensions along a line. Once it's
+// reached, no more extensions are granted.
+pub const MAX_EXTENSIONS: usize = 2;
 
 // The number of fractions (i.e. the number of extending moves) needed to extend
 // the search by 1 ply
-const FRACTIONS_PER_EXTENSION: usize = 2;
+pub const FRACTIONS_PER_EXTENSION: usize = 2;
+
+// Progress info is reported at this interval during long iterations. Fast
+// searches finish before the first report.
+const PROGRESS_INFO_INTERVAL: Duration = Duration::from_secs(2);
+
+// Number of nodes between two checks of the elapsed time for the progress info
+const PROGRESS_INFO_CHECK_NODES: usize = 4096;
+
+// Commands received while searching. They are applied after the search has
+// finished. A new search or terminate command also ends the current search.
+#[derive(Debug, Clone)]
+pub enum DeferredCommand {
+    SetHashSize(usize),
+    ClearHashTable,
+    SetSearchParams(Box<SearchParamsEachAlgo>),
+    Search(Box<(PositionHistory, SearchOptions)>),
+    Terminate,
+}
 
 #[derive(Debug, Clone)]
 pub struct SearchData<'a> {
     command_receiver: &'a Receiver<SearchCommand>,
-    info_sender: &'a Sender<SearchInfo>,
+    // None for the helper threads, which don't report anything
+    info_sender: Option<&'a Sender<SearchInfo>>,
     pos_history: PositionHistory,
-    halfmove_count: usize,
+    // The generation of the search, stored in the transposition table entries
+    age: u8,
     start_time: Instant,
     hard_time_limit: Option<Duration>,
     max_nodes: Option<usize>,
     search_depth: usize,
     selective_depth: usize,
     extensions: Vec<usize>,
+    max_extensions: usize,
     reductions: Vec<usize>,
     ply: usize,
     prev_pv_depth: usize,
     pv_table: PvTable,
     prev_pv_table: PvTable,
     node_counter: NodeCounter,
+    // Helper threads publish their node count, so that the main thread can
+    // include it in the total
+    published_nodes: Option<&'a AtomicU64>,
+    helper_nodes: &'a [AtomicU64],
     killers: Vec<Killers>,
+    // Piece and target square of each move made during the search, for the
+    // continuation history
+    moved_pieces: Vec<Option<(Piece, Square)>>,
+    // Excluded from the search by singular extension verification, indexed
+    // by ply
+    excluded_moves: Vec<Move>,
+    // Null moves are disabled below this ply during a null move verification
+    null_move_min_ply: usize,
     root_moves: MoveCandidates,
     is_in_check: [Option<bool>; 2],
     eval_relative: Option<Score>,
+    // Static evaluation of the nodes on the current path, indexed by ply. None
+    // if the side to move is in check.
+    static_evals: Vec<Option<Score>>,
+    deferred_commands: Vec<DeferredCommand>,
+    stop_requested: bool,
+    // Only collected in analyse mode
+    refutations: Option<Vec<MoveList>>,
+    // Only set if the search tree is traced for debugging
+    tracer: Option<SearchTracer>,
+    contempt: Score,
+    // Depth and score of the last completed iteration
+    prev_iteration: Option<(usize, Score)>,
+    // The elapsed time is only checked every PROGRESS_INFO_CHECK_NODES nodes
+    progress_info_countdown: usize,
+    next_progress_info: Duration,
 }
 
 impl<'a> SearchData<'a> {
     pub fn new(
         command_receiver: &'a Receiver<SearchCommand>,
-        info_sender: &'a Sender<SearchInfo>,
+        info_sender: Option<&'a Sender<SearchInfo>>,
         pos_history: PositionHistory,
         start_time: Instant,
         hard_time_limit: Option<Duration>,
         max_nodes: Option<usize>,
     ) -> Self {
-        let halfmove_count = pos_history.current_pos().halfmove_count();
         Self {
             command_receiver,
             info_sender,
             pos_history,
-            halfmove_count,
+            age: 0,
             start_time,
             hard_time_limit,
             max_nodes,
             search_depth: 0,
             selective_depth: 0,
-            extensions: Default::default(),
-            reductions: Default::default(),
+            // The per-ply data has at most MAX_PLY + 1 entries
+            extensions: Vec::with_capacity(MAX_PLY + 1),
+            max_extensions: MAX_EXTENSIONS,
+            reductions: Vec::with_capacity(MAX_PLY + 1),
             ply: 0,
             prev_pv_depth: 0,
             pv_table: PvTable::new(),
             prev_pv_table: PvTable::new(),
             node_counter: NodeCounter::new(),
-            killers: Vec::new(),
+            published_nodes: None,
+            helper_nodes: &[],
+            killers: Vec::with_capacity(MAX_PLY + 1),
+            moved_pieces: Vec::with_capacity(MAX_PLY + 1),
+            excluded_moves: Vec::with_capacity(MAX_PLY + 1),
+            null_move_min_ply: 0,
             root_moves: MoveCandidates::default(),
             is_in_check: Default::default(),
             eval_relative: Default::default(),
+            static_evals: Vec::with_capacity(MAX_PLY + 1),
+            deferred_commands: Vec::new(),
+            stop_requested: false,
+            refutations: None,
+            tracer: None,
+            contempt: 0,
+            prev_iteration: None,
+            progress_info_countdown: PROGRESS_INFO_CHECK_NODES,
+            next_progress_info: PROGRESS_INFO_INTERVAL,
         }
     }
 
@@ -85,10 +158,28 @@ impl<'a> SearchData<'a> {
         self.command_receiver.try_recv()
     }
 
-    pub fn send_info(&self, search_info: SearchInfo) {
+    pub fn command_receiver(&self) -> &'a Receiver<SearchCommand> {
+        self.command_receiver
+    }
+
+    pub fn info_sender(&self) -> Option<&'a Sender<SearchInfo>> {
         self.info_sender
-            .send(search_info)
-            .expect("Error sending SearchInfo");
+    }
+
+    pub fn take_deferred_commands(&mut self) -> Vec<DeferredCommand> {
+        std::mem::take(&mut self.deferred_commands)
+    }
+
+    pub fn defer_commands(&mut self, commands: Vec<DeferredCommand>) {
+        self.deferred_commands.extend(commands);
+    }
+
+    pub fn send_info(&self, search_info: SearchInfo) {
+        if let Some(info_sender) = self.info_sender {
+            info_sender
+                .send(search_info)
+                .expect("Error sending SearchInfo");
+        }
     }
 
     pub fn pos_history(&self) -> &PositionHistory {
@@ -107,12 +198,16 @@ impl<'a> SearchData<'a> {
         self.pos_history.current_pos_hash()
     }
 
-    pub fn halfmove_count(&self) -> usize {
-        self.halfmove_count
+    pub fn current_pawn_hash(&self) -> Zobrist {
+        self.pos_history.current_pawn_hash()
     }
 
     pub fn age(&self) -> u8 {
-        (self.halfmove_count() % 256) as u8
+        self.age
+    }
+
+    pub fn set_age(&mut self, age: u8) {
+        self.age = age;
     }
 
     pub fn start_time(&self) -> Instant {
@@ -127,8 +222,26 @@ impl<'a> SearchData<'a> {
         self.max_nodes
     }
 
+    pub fn stop_requested(&self) -> bool {
+        self.stop_requested
+    }
+
+    // Includes the nodes of the helper threads
     pub fn searched_nodes(&self) -> usize {
-        self.node_counter.sum_nodes() as usize
+        let helper_nodes = self
+            .helper_nodes
+            .iter()
+            .map(|n| n.load(Ordering::Relaxed))
+            .sum::<u64>();
+        (self.node_counter.sum_nodes() + helper_nodes) as usize
+    }
+
+    pub fn publish_nodes(&mut self, nodes: &'a AtomicU64) {
+        self.published_nodes = Some(nodes);
+    }
+
+    pub fn set_helper_nodes(&mut self, helper_nodes: &'a [AtomicU64]) {
+        self.helper_nodes = helper_nodes;
     }
 
     pub fn search_depth(&self) -> usize {
@@ -144,7 +257,16 @@ impl<'a> SearchData<'a> {
     }
 
     pub fn total_extensions(&self) -> usize {
-        (self.extensions.iter().sum::<usize>() / FRACTIONS_PER_EXTENSION).min(MAX_EXTENSIONS)
+        (self.extensions.iter().sum::<usize>() / FRACTIONS_PER_EXTENSION).min(self.max_extensions)
+    }
+
+    pub fn set_max_extensions(&mut self, max_extensions: usize) {
+        self.max_extensions = max_extensions;
+    }
+
+    // Whether another extension would still increase the depth of this line
+    pub fn can_extend(&self) -> bool {
+        self.extensions.iter().sum::<usize>() < self.max_extensions * FRACTIONS_PER_EXTENSION
     }
 
     pub fn current_reduction(&mut self) -> usize {
@@ -241,6 +363,37 @@ impl<'a> SearchData<'a> {
         }
     }
 
+    pub fn excluded_move(&self) -> Move {
+        self.excluded_moves
+            .get(self.ply)
+            .copied()
+            .unwrap_or(Move::NULL)
+    }
+
+    pub fn set_excluded_move(&mut self, m: Move) {
+        if self.ply >= self.excluded_moves.len() {
+            self.excluded_moves.resize(self.ply + 1, Move::NULL);
+        }
+        self.excluded_moves[self.ply] = m;
+    }
+
+    // Whether a singular extension verification is in progress at this ply or
+    // at one of the previous plies
+    pub fn is_singular_verification(&self) -> bool {
+        self.excluded_moves
+            .iter()
+            .take(self.ply + 1)
+            .any(|&m| m != Move::NULL)
+    }
+
+    pub fn null_move_min_ply(&self) -> usize {
+        self.null_move_min_ply
+    }
+
+    pub fn set_null_move_min_ply(&mut self, ply: usize) {
+        self.null_move_min_ply = ply;
+    }
+
     pub fn reset_current_search_depth(&mut self) {
         // This method will be called if we fail low/high, i.e. we didn't find the best move inside
         // the aspiration window. At depth 1, we search with an infinite window, so this method
@@ -248,8 +401,12 @@ impl<'a> SearchData<'a> {
         debug_assert!(self.search_depth() > 1);
         self.pv_table = self.prev_pv_table.clone();
         self.prev_pv_depth = self.search_depth() - 1;
-        self.selective_depth = 0;
+        // The selective depth is kept, it is the maximum over all re-searches
+        // of the current iteration.
         self.root_moves_mut().reset_counts();
+        if let Some(refutations) = &mut self.refutations {
+            refutations.clear();
+        }
     }
 
     pub fn increase_search_depth(&mut self) {
@@ -260,6 +417,111 @@ impl<'a> SearchData<'a> {
         self.killers.push([None; NUM_KILLERS]);
         self.root_moves_mut().order_by_subtree_size();
         self.root_moves_mut().reset_counts();
+        if let Some(refutations) = &mut self.refutations {
+            refutations.clear();
+        }
+    }
+
+    // Score of the last completed iteration, relative to the side to move
+    pub fn prev_iteration_score(&self) -> Option<Score> {
+        self.prev_iteration.map(|(_, score)| score)
+    }
+
+    pub fn prev_iteration_depth(&self) -> Option<usize> {
+        self.prev_iteration.map(|(depth, _)| depth)
+    }
+
+    pub fn set_prev_iteration(&mut self, depth: usize, score: Score) {
+        self.prev_iteration = Some((depth, score));
+    }
+
+    pub fn contempt(&self) -> Score {
+        self.contempt
+    }
+
+    pub fn set_contempt(&mut self, contempt: Score) {
+        self.contempt = contempt;
+    }
+
+    // A draw is worse than an equal position for the side to move at the root
+    // by the contempt. Scores are relative to the side to move, so the sign
+    // alternates with the ply.
+    pub fn draw_score(&self) -> Score {
+        match self.ply % 2 {
+            0 => -self.contempt,
+            _ => self.contempt,
+        }
+    }
+
+    pub fn collect_refutations(&mut self) {
+        self.refutations = Some(Vec::new());
+    }
+
+    pub fn set_tracer(&mut self, tracer: SearchTracer) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn is_traced(&self) -> bool {
+        matches!(&self.tracer, Some(t) if t.is_traced(self.ply))
+    }
+
+    pub fn trace_enter(&mut self, alpha: Score, beta: Score) {
+        let depth = self.remaining_depth();
+        if let Some(tracer) = self.current_tracer() {
+            tracer.enter(alpha, beta, depth);
+        }
+    }
+
+    pub fn trace_static_eval(&mut self, static_eval: Option<Score>) {
+        if let Some(tracer) = self.current_tracer() {
+            tracer.set_static_eval(static_eval);
+        }
+    }
+
+    pub fn trace_table_probe(&mut self, table_probe: TableProbe) {
+        if let Some(tracer) = self.current_tracer() {
+            tracer.set_table_probe(table_probe);
+        }
+    }
+
+    pub fn trace_move(&mut self, m: Move) {
+        if let Some(tracer) = self.current_tracer() {
+            tracer.add_move(m);
+        }
+    }
+
+    pub fn trace_leave(&mut self, result: Option<&AlphaBetaEntry>) {
+        let ply = self.ply;
+        let iteration = self.search_depth;
+        if let Some(tracer) = self.tracer.as_mut().filter(|t| t.is_traced(ply)) {
+            let moves = self.pos_history.moves();
+            let line = tracer.leave(&moves[moves.len() - ply..], iteration, result);
+            self.send_info(SearchInfo::Trace(line));
+        }
+    }
+
+    // The tracer, if the current node is traced
+    fn current_tracer(&mut self) -> Option<&mut SearchTracer> {
+        let ply = self.ply;
+        self.tracer.as_mut().filter(|t| t.is_traced(ply))
+    }
+
+    // Remember the reply that refuted a root move. Only the first reply of
+    // the refutation is known, because the child node stops at the cutoff.
+    pub fn add_refutation(&mut self, root_move: Move, reply: Move) {
+        debug_assert_eq!(0, self.ply());
+        if let Some(refutations) = &mut self.refutations {
+            if reply != Move::NULL {
+                refutations.push(MoveList::from(vec![root_move, reply]));
+            }
+        }
+    }
+
+    pub fn take_refutations(&mut self) -> Vec<MoveList> {
+        self.refutations
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
     }
 
     pub fn decrease_prev_pv_depth(&mut self) {
@@ -273,6 +535,14 @@ impl<'a> SearchData<'a> {
     pub fn do_move(&mut self, m: Move) {
         self.node_counter
             .increment_nodes(self.search_depth(), self.ply);
+        if let Some(nodes) = self.published_nodes {
+            nodes.fetch_add(1, Ordering::Relaxed);
+        }
+        let moved_piece = match m {
+            Move::NULL => None,
+            _ => self.current_pos().piece_at(m.origin()),
+        };
+        self.moved_pieces.push(moved_piece.map(|p| (p, m.target())));
         self.pos_history_mut().do_move(m);
         self.ply += 1;
         self.selective_depth = self.selective_depth.max(self.ply);
@@ -289,6 +559,20 @@ impl<'a> SearchData<'a> {
         self.is_in_check = Default::default();
         self.ply -= 1;
         self.pos_history_mut().undo_last_move();
+        self.moved_pieces.pop();
+    }
+
+    pub fn prev_moves(&self) -> PrevMoves {
+        let mut prev_moves = PrevMoves::default();
+        for (prev, moved) in prev_moves.iter_mut().zip(self.moved_pieces.iter().rev()) {
+            *prev = *moved;
+        }
+        prev_moves
+    }
+
+    pub fn increment_table_probes(&mut self, found: bool) {
+        self.node_counter
+            .increment_table_probes(self.search_depth(), self.ply, found);
     }
 
     pub fn increment_cache_hits(&mut self) {
@@ -296,8 +580,14 @@ impl<'a> SearchData<'a> {
             .increment_cache_hits(self.search_depth(), self.ply);
     }
 
-    pub fn increment_eval_calls(&mut self) {
-        self.node_counter.increment_eval_calls(self.search_depth());
+    pub fn increment_beta_cutoffs(&mut self, first_move: bool) {
+        self.node_counter
+            .increment_beta_cutoffs(self.search_depth(), self.ply, first_move);
+    }
+
+    pub fn increment_eval_calls(&mut self, cache_hit: bool) {
+        self.node_counter
+            .increment_eval_calls(self.search_depth(), cache_hit);
     }
 
     pub fn set_root_moves(&mut self, root_moves: &MoveList) {
@@ -324,18 +614,62 @@ impl<'a> SearchData<'a> {
         }
     }
 
-    pub fn eval_relative(&mut self, evaluator: &mut Box<dyn Eval + Send>) -> Score {
+    pub fn eval_relative(
+        &mut self,
+        evaluator: &mut Box<dyn Eval + Send>,
+        eval_cache: &mut EvalCache,
+    ) -> Score {
         match self.eval_relative {
             Some(eval) => eval,
             None => {
-                self.increment_eval_calls();
-                let eval = evaluator.eval_relative(self.current_pos());
+                let hash = self.current_pos_hash();
+                let cached = eval_cache.get(&hash);
+                self.increment_eval_calls(cached.is_some());
+                let eval = cached.unwrap_or_else(|| {
+                    let eval = evaluator
+                        .eval_relative_with_pawn_hash(self.current_pos(), self.current_pawn_hash());
+                    eval_cache.insert(&hash, eval);
+                    eval
+                });
                 self.eval_relative = Some(eval);
                 eval
             }
         }
     }
 
+    pub fn set_static_eval(&mut self, eval: Option<Score>) {
+        if self.static_evals.len() <= self.ply {
+            self.static_evals.resize(self.ply + 1, None);
+        }
+        self.static_evals[self.ply] = eval;
+    }
+
+    // Compares the static evaluation with the one two plies ago, i.e. with the
+    // same side to move. If the side to move was in check two plies ago, four
+    // plies ago is used instead. Positions in check are never improving. If
+    // there is no previous evaluation (close to the root), it counts as
+    // improving.
+    pub fn is_improving(&self) -> bool {
+        let eval = match self.static_evals.get(self.ply) {
+            Some(Some(eval)) => *eval,
+            _ => return false,
+        };
+        for plies_ago in [2, 4] {
+            if let Some(Some(prev_eval)) = self
+                .ply
+                .checked_sub(plies_ago)
+                .and_then(|ply| self.static_evals.get(ply))
+            {
+                return eval > *prev_eval;
+            }
+        }
+        true
+    }
+
+    pub fn root_move_node_fraction(&self, m: Move) -> Option<f64> {
+        self.root_moves.node_fraction(m)
+    }
+
     pub fn set_subtree_size(&mut self, m: Move, node_count: u64) {
         self.root_moves_mut().set_subtree_size(m, node_count);
     }
@@ -345,9 +679,37 @@ impl<'a> SearchData<'a> {
         self.root_moves_mut().alpha_raised_count += 1;
     }
 
-    pub fn should_stop_search_immediately(&self) -> bool {
+    // Whether it's time to report the progress of a long iteration. Returns
+    // true at most once per PROGRESS_INFO_INTERVAL.
+    pub fn progress_info_due(&mut self) -> bool {
+        if self.info_sender.is_none() {
+            return false;
+        }
+        if self.progress_info_countdown > 0 {
+            self.progress_info_countdown -= 1;
+            return false;
+        }
+        self.progress_info_countdown = PROGRESS_INFO_CHECK_NODES;
+        let elapsed = self.start_time().elapsed();
+        if elapsed < self.next_progress_info {
+            return false;
+        }
+        self.next_progress_info = elapsed + PROGRESS_INFO_INTERVAL;
+        true
+    }
+
+    // Commands are polled at every depth, so that the caller of a deferred
+    // command never waits for depth 1 to finish. But depth 1 is always
+    // completed, however little time is left, so that the search has a best
+    // move. A stop command received during depth 1 is remembered. Once the
+    // search is stopped, no more commands are received. The commands after
+    // the stop (usually a new search or terminate) are left for the caller.
+    pub fn should_stop_search_immediately(&mut self) -> bool {
+        if !self.stop_requested {
+            self.poll_command();
+        }
         if self.search_depth() > 1 {
-            if let Ok(SearchCommand::Stop) = self.try_recv_cmd() {
+            if self.stop_requested {
                 return true;
             }
             if let Some(limit) = self.hard_time_limit() {
@@ -364,13 +726,245 @@ impl<'a> SearchData<'a> {
         false
     }
 
-    pub fn calc_extension(&mut self, m: Move) -> usize {
-        match m != Move::NULL
-            && self.ply() <= self.search_depth()
-            && self.is_in_check(self.current_pos().side_to_move())
-        {
-            true => 1,
-            false => 0,
+    fn poll_command(&mut self) {
+        // The senders of deferred commands are dropped here, so the caller
+        // doesn't block until the search is finished.
+        match self.try_recv_cmd() {
+            Ok(SearchCommand::Stop) => self.stop_requested = true,
+            Ok(SearchCommand::SetHashSize(bytes, _)) => self
+                .deferred_commands
+                .push(DeferredCommand::SetHashSize(bytes)),
+            Ok(SearchCommand::ClearHashTable(_)) => {
+                self.deferred_commands.push(DeferredCommand::ClearHashTable)
+            }
+            Ok(SearchCommand::SetSearchParams(params, _)) => self
+                .deferred_commands
+                .push(DeferredCommand::SetSearchParams(params)),
+            // Usually preceded by a stop command, but they end the search
+            // anyway
+            Ok(SearchCommand::Search(inner)) => {
+                self.stop_requested = true;
+                self.deferred_commands.push(DeferredCommand::Search(inner));
+            }
+            Ok(SearchCommand::Terminate) => {
+                self.stop_requested = true;
+                self.deferred_commands.push(DeferredCommand::Terminate);
+            }
+            // The engine is ready while searching
+            Ok(SearchCommand::IsReady(_)) | Err(_) => {}
+        }
+    }
+
+    // Whether m captures on the square where the opponent has just captured.
+    // Must be called before the move is made.
+    pub fn is_recapture(&self, m: Move) -> bool {
+        m.is_capture()
+            && matches!(
+                self.pos_history.last_move(),
+                Some(lm) if lm.is_capture() && lm.target() == m.target()
+            )
+    }
+
+    // Moves giving check and (non-losing) recaptures are extended by one
+    // fraction each. Called after the move is made.
+    pub fn calc_extension(&mut self, m: Move, is_recapture: bool) -> usize {
+        if m == Move::NULL || self.ply() > self.search_depth() || !self.can_extend() {
+            return 0;
+        }
+        let gives_check = self.is_in_check(self.current_pos().side_to_move());
+        gives_check as usize + is_recapture as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use movegen::fen::Fen;
+    use movegen::r#move::MoveType;
+
+    // SearchData borrows the channels, so they must outlive it
+    struct Channels {
+        _command_sender: Sender<SearchCommand>,
+        command_receiver: Receiver<SearchCommand>,
+        info_sender: Sender<SearchInfo>,
+        _info_receiver: Receiver<SearchInfo>,
+    }
+
+    impl Channels {
+        fn new() -> Self {
+            let (_command_sender, command_receiver) = crossbeam_channel::unbounded();
+            let (info_sender, _info_receiver) = crossbeam_channel::unbounded();
+            Self {
+                _command_sender,
+                command_receiver,
+                info_sender,
+                _info_receiver,
+            }
+        }
+
+        fn search_data(&self, pos: Position, start_time: Instant) -> SearchData<'_> {
+            SearchData::new(
+                &self.command_receiver,
+                Some(&self.info_sender),
+                PositionHistory::new(pos),
+                start_time,
+                None,
+                None,
+            )
+        }
+    }
+
+    #[test]
+    fn improving() {
+        let channels = Channels::new();
+        let mut search_data = channels.search_data(Position::initial(), Instant::now());
+        search_data.increase_search_depth();
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        let e7e5 = Move::new(Square::E7, Square::E5, MoveType::DOUBLE_PAWN_PUSH);
+        let g1f3 = Move::new(Square::G1, Square::F3, MoveType::QUIET);
+        let d2d4 = Move::new(Square::D2, Square::D4, MoveType::DOUBLE_PAWN_PUSH);
+
+        // No previous evaluation of the same side
+        search_data.set_static_eval(Some(10));
+        assert!(search_data.is_improving());
+        search_data.do_move(e2e4);
+        search_data.set_static_eval(Some(-30));
+        assert!(search_data.is_improving());
+
+        search_data.do_move(e7e5);
+        search_data.set_static_eval(Some(20));
+        assert!(search_data.is_improving());
+        search_data.set_static_eval(Some(10));
+        assert!(!search_data.is_improving());
+
+        // After a null move, the evaluation of the same side is still two
+        // plies ago
+        search_data.do_move(Move::NULL);
+        search_data.set_static_eval(Some(-40));
+        assert!(!search_data.is_improving());
+        search_data.undo_last_move();
+        search_data.undo_last_move();
+        search_data.do_move(Move::NULL);
+        search_data.set_static_eval(Some(0));
+        assert!(!search_data.is_improving());
+        search_data.set_static_eval(Some(20));
+        assert!(search_data.is_improving());
+
+        // In check, the evaluation is unknown and never improving. Two plies
+        // later, four plies ago is used instead.
+        search_data.do_move(g1f3);
+        search_data.set_static_eval(None);
+        assert!(!search_data.is_improving());
+        search_data.do_move(Move::NULL);
+        search_data.set_static_eval(Some(0));
+        search_data.do_move(d2d4);
+        search_data.set_static_eval(Some(-50));
+        assert!(!search_data.is_improving());
+        search_data.set_static_eval(Some(-10));
+        assert!(search_data.is_improving());
+    }
+
+    #[test]
+    fn singular_verification() {
+        let channels = Channels::new();
+        let mut search_data = channels.search_data(Position::initial(), Instant::now());
+        search_data.increase_search_depth();
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        let e7e5 = Move::new(Square::E7, Square::E5, MoveType::DOUBLE_PAWN_PUSH);
+
+        search_data.do_move(e2e4);
+        assert!(!search_data.is_singular_verification());
+        search_data.set_excluded_move(e7e5);
+        assert!(search_data.is_singular_verification());
+        // Still in progress in the subtree of the verification search
+        search_data.do_move(e7e5);
+        assert!(search_data.is_singular_verification());
+        search_data.undo_last_move();
+        search_data.set_excluded_move(Move::NULL);
+        assert!(!search_data.is_singular_verification());
+        search_data.undo_last_move();
+        assert!(!search_data.is_singular_verification());
+    }
+
+    #[test]
+    fn max_extensions() {
+        let channels = Channels::new();
+        let mut search_data = channels.search_data(Position::initial(), Instant::now());
+        search_data.set_max_extensions(1);
+        search_data.increase_search_depth();
+        let e2e4 = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        let e7e5 = Move::new(Square::E7, Square::E5, MoveType::DOUBLE_PAWN_PUSH);
+
+        assert!(search_data.can_extend());
+        search_data.do_move(e2e4);
+        search_data.set_current_extension(FRACTIONS_PER_EXTENSION - 1);
+        assert!(search_data.can_extend());
+        assert_eq!(0, search_data.total_extensions());
+        search_data.do_move(e7e5);
+        search_data.set_current_extension(FRACTIONS_PER_EXTENSION);
+        assert!(!search_data.can_extend());
+        assert_eq!(1, search_data.total_extensions());
+        // Extensions are granted again after leaving the line
+        search_data.undo_last_move();
+        assert!(search_data.can_extend());
+    }
+
+    #[test]
+    fn recapture_extension() {
+        let fen = "4k3/8/2n5/3p4/4P3/2N5/8/4K3 w - - 0 1";
+        let channels = Channels::new();
+        let mut search_data = channels.search_data(Fen::str_to_pos(fen).unwrap(), Instant::now());
+        search_data.increase_search_depth();
+        search_data.increase_search_depth();
+        let exd5 = Move::new(Square::E4, Square::D5, MoveType::CAPTURE);
+        let nb4 = Move::new(Square::C6, Square::B4, MoveType::QUIET);
+        let nxd5 = Move::new(Square::C6, Square::D5, MoveType::CAPTURE);
+        let nxd5_white = Move::new(Square::C3, Square::D5, MoveType::CAPTURE);
+
+        // The first capture isn't a recapture
+        assert!(!search_data.is_recapture(exd5));
+        search_data.do_move(exd5);
+        assert_eq!(0, search_data.calc_extension(exd5, false));
+        assert!(search_data.is_recapture(nxd5));
+        assert!(!search_data.is_recapture(nb4));
+        search_data.do_move(nxd5);
+        assert_eq!(1, search_data.calc_extension(nxd5, true));
+        assert!(search_data.is_recapture(nxd5_white));
+
+        // The opponent's last move must be a capture on the same square
+        search_data.undo_last_move();
+        search_data.do_move(nb4);
+        assert!(!search_data.is_recapture(nxd5_white));
+    }
+
+    #[test]
+    fn progress_info_rate_limit() {
+        let start_time = Instant::now() - 3 * PROGRESS_INFO_INTERVAL;
+        let channels = Channels::new();
+        let mut search_data = channels.search_data(Position::initial(), start_time);
+
+        // The time is only checked every PROGRESS_INFO_CHECK_NODES nodes
+        for _ in 0..PROGRESS_INFO_CHECK_NODES {
+            assert!(!search_data.progress_info_due());
+        }
+        assert!(search_data.progress_info_due());
+
+        // The next report is due one interval later
+        for _ in 0..=PROGRESS_INFO_CHECK_NODES {
+            assert!(!search_data.progress_info_due());
+        }
+
+        // Helper threads don't report their progress
+        let mut search_data = SearchData::new(
+            &channels.command_receiver,
+            None,
+            PositionHistory::new(Position::initial()),
+            start_time,
+            None,
+            None,
+        );
+        for _ in 0..=PROGRESS_INFO_CHECK_NODES {
+            assert!(!search_data.progress_info_due());
         }
     }
 }
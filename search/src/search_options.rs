@@ -1,3 +1,5 @@
+use crate::strength::{SkillLevel, StrengthLimit};
+use eval::Score;
 use movegen::r#move::MoveList;
 use std::time::Duration;
 
@@ -16,4 +18,14 @@ pub struct SearchOptions {
     pub movetime: Option<Duration>,
     pub infinite: bool,
     pub move_overhead: Duration,
+    pub strength_limit: Option<StrengthLimit>,
+    // None at the maximum skill level
+    pub skill_level: Option<SkillLevel>,
+    pub analyse_mode: bool,
+    // Trace the search tree up to this ply (for debugging)
+    pub trace_depth: Option<usize>,
+    pub contempt: Score,
+    // Choose randomly among the root moves with (almost) equal scores, with
+    // this seed. None disables the randomization.
+    pub equal_moves_seed: Option<u64>,
 }
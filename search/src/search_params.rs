@@ -2,27 +2,61 @@ use eval::Score;
 
 pub trait SearchParams {}
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct AlphaBetaParams {
     pub futility_margin_base: Option<Score>,
     pub futility_margin_per_depth: Option<Score>,
     pub futility_pruning_max_depth: Option<usize>,
+    pub futility_margin_improving: Option<Score>,
     pub reverse_futility_margin_base: Option<Score>,
     pub reverse_futility_margin_per_depth: Option<Score>,
     pub reverse_futility_pruning_max_depth: Option<usize>,
+    pub reverse_futility_margin_improving: Option<Score>,
+    pub razoring_margin_base: Option<Score>,
+    pub razoring_margin_per_depth: Option<Score>,
+    pub razoring_max_depth: Option<usize>,
+    pub probcut_margin: Option<Score>,
+    pub probcut_depth_reduction: Option<usize>,
+    pub probcut_min_depth: Option<usize>,
+    pub multicut_margin: Option<Score>,
+    pub multicut_depth_reduction: Option<usize>,
+    pub multicut_min_depth: Option<usize>,
+    pub multicut_moves: Option<usize>,
+    pub multicut_required_cutoffs: Option<usize>,
     pub late_move_pruning_base: Option<usize>,
     pub late_move_pruning_factor: Option<usize>,
     pub late_move_pruning_max_depth: Option<usize>,
+    pub late_move_pruning_not_improving_percent: Option<usize>,
     pub see_pruning_margin_quiet: Option<Score>,
     pub see_pruning_margin_tactical: Option<Score>,
     pub see_pruning_max_depth: Option<usize>,
+    pub see_pruning_max_depth_quiet: Option<usize>,
+    pub null_move_zugzwang_guard: Option<bool>,
+    pub null_move_verification_min_depth: Option<usize>,
+    pub internal_iterative_reduction: Option<bool>,
+    pub singular_extension_min_depth: Option<usize>,
+    pub singular_extension_max_table_depth_diff: Option<usize>,
+    pub singular_extension_margin_per_depth: Option<Score>,
+    pub max_extensions: Option<usize>,
+    pub history_max: Option<i32>,
+    pub history_bonus_quadratic: Option<i32>,
+    pub history_bonus_linear: Option<i32>,
+    pub late_move_reduction_not_improving: Option<i32>,
+    pub late_move_reduction_cut_node: Option<i32>,
+    pub late_move_reduction_killer_or_counter: Option<i32>,
+    pub late_move_reduction_history_divisor: Option<i32>,
     pub aspiration_window_initial_width: Option<i32>,
     pub aspiration_window_grow_rate: Option<i32>,
+    pub score_drop_threshold: Option<Score>,
+    pub score_drop_extension_percent: Option<i32>,
+    pub contempt_losing_score: Option<Score>,
+    pub contempt_endgame_percent: Option<i32>,
+    pub shared_history: Option<bool>,
 }
 
 impl SearchParams for AlphaBetaParams {}
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum SearchParamsEachAlgo {
     AlphaBeta(AlphaBetaParams),
 }
@@ -0,0 +1,129 @@
+use crate::alpha_beta_entry::{AlphaBetaEntry, ScoreType};
+use eval::Score;
+use movegen::r#move::{Move, MoveList};
+use std::fmt::Write;
+
+// Result of the hash table lookup at a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableProbe {
+    // No entry with enough depth
+    Miss,
+    // An entry with enough depth, but the node is searched anyway
+    Hit,
+    // The entry ends the search of the node
+    Cutoff,
+}
+
+#[derive(Debug, Clone)]
+struct TraceNode {
+    alpha: Score,
+    beta: Score,
+    depth: usize,
+    static_eval: Option<Score>,
+    table_probe: Option<TableProbe>,
+    moves: MoveList,
+}
+
+// Records the search tree up to a maximum ply for debugging. Each node is
+// written as one JSON line when it is left, so children come before their
+// parents. The path (the moves from the root) identifies the node.
+#[derive(Debug, Clone)]
+pub struct SearchTracer {
+    max_ply: usize,
+    nodes: Vec<TraceNode>,
+}
+
+impl SearchTracer {
+    pub fn new(max_ply: usize) -> Self {
+        Self {
+            max_ply,
+            nodes: Vec::new(),
+        }
+    }
+
+    pub fn is_traced(&self, ply: usize) -> bool {
+        ply <= self.max_ply
+    }
+
+    pub fn enter(&mut self, alpha: Score, beta: Score, depth: usize) {
+        self.nodes.push(TraceNode {
+            alpha,
+            beta,
+            depth,
+            static_eval: None,
+            table_probe: None,
+            moves: MoveList::new(),
+        });
+    }
+
+    pub fn set_static_eval(&mut self, static_eval: Option<Score>) {
+        if let Some(node) = self.nodes.last_mut() {
+            node.static_eval = static_eval;
+        }
+    }
+
+    pub fn set_table_probe(&mut self, table_probe: TableProbe) {
+        if let Some(node) = self.nodes.last_mut() {
+            node.table_probe = Some(table_probe);
+        }
+    }
+
+    pub fn add_move(&mut self, m: Move) {
+        if let Some(node) = self.nodes.last_mut() {
+            node.moves.push(m);
+        }
+    }
+
+    // Returns the line of the node. The path are the moves from the root to
+    // the node. The result is None if the search has been stopped.
+    pub fn leave(
+        &mut self,
+        path: &[Move],
+        iteration: usize,
+        result: Option<&AlphaBetaEntry>,
+    ) -> String {
+        let node = self.nodes.pop().expect("Expected a traced node");
+        let quoted = |moves: &[Move]| {
+            moves
+                .iter()
+                .map(|m| format!("\"{m}\""))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let opt_to_json = |s: Option<String>| s.unwrap_or_else(|| String::from("null"));
+        let table = node.table_probe.map(|p| match p {
+            TableProbe::Miss => "\"miss\"",
+            TableProbe::Hit => "\"hit\"",
+            TableProbe::Cutoff => "\"cutoff\"",
+        });
+        let bound = result.map(|r| match r.score_type() {
+            ScoreType::Exact => "\"exact\"",
+            ScoreType::LowerBound => "\"lower\"",
+            ScoreType::UpperBound => "\"upper\"",
+        });
+        let mut line = String::new();
+        let _ = write!(
+            line,
+            "{{\"iteration\":{iteration},\"ply\":{},\"path\":[{}],",
+            path.len(),
+            quoted(path),
+        );
+        let _ = write!(
+            line,
+            "\"depth\":{},\"alpha\":{},\"beta\":{},\"static_eval\":{},\"table\":{},",
+            node.depth,
+            node.alpha,
+            node.beta,
+            opt_to_json(node.static_eval.map(|s| s.to_string())),
+            table.unwrap_or("null"),
+        );
+        let _ = write!(
+            line,
+            "\"moves\":[{}],\"score\":{},\"bound\":{}}}",
+            quoted(&node.moves),
+            opt_to_json(result.map(|r| r.score().to_string())),
+            bound.unwrap_or("null"),
+        );
+        line
+    }
+}
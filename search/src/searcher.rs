@@ -3,10 +3,16 @@ use crate::{
     search_params::SearchParamsEachAlgo,
     SearchOptions,
 };
-use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender};
 use movegen::position_history::PositionHistory;
 
 use std::thread;
+use std::time::Duration;
+
+// How long dropping the searcher waits for the search thread to finish. A
+// search thread that doesn't react to the stop command within this time is
+// detached, so that shutting down never hangs.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
 
 pub struct Searcher {
     command_sender: Sender<SearchCommand>,
@@ -39,13 +45,28 @@ impl Searcher {
     pub fn set_search_params(&self, search_params: SearchParamsEachAlgo) {
         let (sender, receiver) = bounded(1);
         self.command_sender
-            .send(SearchCommand::SetSearchParams(search_params, sender))
+            .send(SearchCommand::SetSearchParams(
+                Box::new(search_params),
+                sender,
+            ))
             .expect("Error sending SearchCommand");
         receiver
             .recv()
             .expect_err("Expected sender to disconnect after SetSearchParams");
     }
 
+    // Blocks until the search thread has handled all earlier commands. If a
+    // search is running, it returns without waiting for the search to finish.
+    pub fn wait_until_ready(&self) {
+        let (sender, receiver) = bounded(1);
+        self.command_sender
+            .send(SearchCommand::IsReady(sender))
+            .expect("Error sending SearchCommand");
+        receiver
+            .recv()
+            .expect_err("Expected sender to disconnect after IsReady");
+    }
+
     pub fn search(&self, pos_hist: PositionHistory, search_options: SearchOptions) {
         self.stop();
         self.command_sender
@@ -84,14 +105,18 @@ impl Searcher {
 
 impl Drop for Searcher {
     fn drop(&mut self) {
-        self.command_sender
-            .send(SearchCommand::Stop)
-            .expect("Error sending SearchCommand");
-        self.command_sender
-            .send(SearchCommand::Terminate)
-            .expect("Error sending SearchCommand");
+        // The sends only fail if the search thread has panicked
+        let _ = self.command_sender.send(SearchCommand::Stop);
+        let _ = self.command_sender.send(SearchCommand::Terminate);
         if let Some(thread) = self.worker.thread.take() {
-            thread.join().expect("Error joining search thread");
+            match self.worker.done_receiver.recv_timeout(SHUTDOWN_TIMEOUT) {
+                // The thread has returned (or panicked), so joining won't block
+                Err(RecvTimeoutError::Disconnected) | Ok(()) => {
+                    thread.join().expect("Error joining search thread")
+                }
+                // Dropping the handle detaches the thread
+                Err(RecvTimeoutError::Timeout) => drop(thread),
+            }
         }
 
         self.info_sender
@@ -105,6 +130,8 @@ impl Drop for Searcher {
 
 struct Worker {
     thread: Option<thread::JoinHandle<()>>,
+    // Disconnects when the thread returns
+    done_receiver: Receiver<()>,
 }
 
 impl Worker {
@@ -113,37 +140,48 @@ impl Worker {
         mut command_receiver: Receiver<SearchCommand>,
         mut info_sender: Sender<SearchInfo>,
     ) -> Self {
-        let thread = thread::spawn(move || loop {
-            let message = command_receiver
-                .recv()
-                .expect("Error receiving SearchCommand");
-
-            match message {
-                SearchCommand::SetHashSize(bytes, _sender) => {
-                    Self::set_hash_size(&mut search_algo, bytes);
-                }
-                SearchCommand::ClearHashTable(_sender) => {
-                    Self::clear_hash_table(&mut search_algo);
-                }
-                SearchCommand::SetSearchParams(search_params, _sender) => {
-                    Self::set_search_params(&mut search_algo, search_params);
-                }
-                SearchCommand::Search(inner) => {
-                    let (pos_hist, search_options) = *inner;
-                    Self::search(
-                        &mut search_algo,
-                        pos_hist,
-                        search_options,
-                        &mut command_receiver,
-                        &mut info_sender,
-                    );
+        let (done_sender, done_receiver) = bounded(0);
+        let thread = thread::spawn(move || {
+            let _done_sender = done_sender;
+            // A command received by the previous search that is handled next
+            let mut pending = None;
+            loop {
+                let message = match pending.take() {
+                    Some(message) => message,
+                    None => command_receiver
+                        .recv()
+                        .expect("Error receiving SearchCommand"),
+                };
+
+                match message {
+                    SearchCommand::SetHashSize(bytes, _sender) => {
+                        Self::set_hash_size(&mut search_algo, bytes);
+                    }
+                    SearchCommand::ClearHashTable(_sender) => {
+                        Self::clear_hash_table(&mut search_algo);
+                    }
+                    SearchCommand::SetSearchParams(search_params, _sender) => {
+                        Self::set_search_params(&mut search_algo, *search_params);
+                    }
+                    SearchCommand::Search(inner) => {
+                        let (pos_hist, search_options) = *inner;
+                        pending = Self::search(
+                            &mut search_algo,
+                            pos_hist,
+                            search_options,
+                            &mut command_receiver,
+                            &mut info_sender,
+                        );
+                    }
+                    SearchCommand::IsReady(_sender) => {}
+                    SearchCommand::Stop => {}
+                    SearchCommand::Terminate => break,
                 }
-                SearchCommand::Stop => {}
-                SearchCommand::Terminate => break,
             }
         });
         Self {
             thread: Some(thread),
+            done_receiver,
         }
     }
 
@@ -165,8 +203,8 @@ impl Worker {
         search_options: SearchOptions,
         command_receiver: &mut Receiver<SearchCommand>,
         info_sender: &mut Sender<SearchInfo>,
-    ) {
-        search.search(pos_hist, search_options, command_receiver, info_sender);
+    ) -> Option<SearchCommand> {
+        search.search(pos_hist, search_options, command_receiver, info_sender)
     }
 }
 
@@ -0,0 +1,318 @@
+use crate::alpha_beta_entry::{AlphaBetaEntry, ScoreType};
+use movegen::r#move::Move;
+use movegen::transposition_table::{TtEntry, CACHE_LINE_SIZE, ENTRIES_PER_BUCKET};
+use movegen::zobrist::Zobrist;
+use std::cmp;
+use std::iter;
+use std::mem;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+// Number of buckets sampled to estimate the table occupancy
+const HASHFULL_SAMPLE_BUCKETS: usize = 1000;
+
+// Set in the packed data of every stored entry. The packed entry itself never
+// uses the highest bit, so an all-zero slot is empty.
+const OCCUPIED: u64 = 1 << 63;
+
+// An entry is stored in two atomics: the packed data and the key XORed with
+// the data. Reads and writes of the two atomics are not synchronized, so
+// another thread may write to the slot in between. Such a torn read combines
+// the data of one write with the key of another. Its key doesn't match the
+// probed key, so it is treated like a miss.
+#[derive(Debug, Default)]
+struct AtomicEntry {
+    key_xor_data: AtomicU64,
+    data: AtomicU64,
+}
+
+impl AtomicEntry {
+    fn load(&self) -> Option<(Zobrist, AlphaBetaEntry)> {
+        let data = self.data.load(Ordering::Relaxed);
+        let key_xor_data = self.key_xor_data.load(Ordering::Relaxed);
+        match data & OCCUPIED {
+            0 => None,
+            _ => Some((
+                Zobrist::from(key_xor_data ^ data),
+                AlphaBetaEntry::from(data & !OCCUPIED),
+            )),
+        }
+    }
+
+    fn store(&self, k: Zobrist, value: AlphaBetaEntry) {
+        let data = u64::from(value) | OCCUPIED;
+        self.key_xor_data
+            .store(u64::from(k) ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.key_xor_data.store(0, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+    }
+}
+
+// A bucket fills exactly one cache line
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct Bucket([AtomicEntry; ENTRIES_PER_BUCKET]);
+
+const _: () = assert!(mem::size_of::<Bucket>() == CACHE_LINE_SIZE);
+
+// Transposition table shared by all search threads. The entries are atomics,
+// so the threads never have to wait for each other.
+// The generation is increased for every new search and stored in the
+// entries, so that entries from old searches can be replaced first.
+#[derive(Debug)]
+pub struct SharedTable {
+    index_bits: usize,
+    buckets: Box<[Bucket]>,
+    generation: AtomicU8,
+}
+
+impl SharedTable {
+    pub fn new(bytes: usize) -> Self {
+        // Reserve memory for at least 2 buckets, so that at least one index bit
+        // is used (even if bytes is 0)
+        let max_num_buckets = cmp::max(2, bytes / mem::size_of::<Bucket>());
+        // The actual number of buckets must be a power of 2.
+        let index_bits = 64 - max_num_buckets.leading_zeros() - 1;
+        Self {
+            index_bits: index_bits as usize,
+            buckets: iter::repeat_with(Bucket::default)
+                .take(1 << index_bits)
+                .collect(),
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    pub fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    // Starts a new generation and returns it. The counter wraps around, the
+    // entries compare their generations relative to the current one.
+    pub fn next_generation(&self) -> u8 {
+        self.generation
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1)
+    }
+
+    pub fn get(&self, k: &Zobrist) -> Option<AlphaBetaEntry> {
+        self.bucket(k)
+            .0
+            .iter()
+            .filter_map(AtomicEntry::load)
+            .find(|(key, _)| key == k)
+            .map(|(_, entry)| entry)
+    }
+
+    // Replacement scheme:
+    // 1. Entry with the same key (the more important one of the old and the
+    //    new entry is kept, a new bound without a move keeps the old move)
+    // 2. Empty entry
+    // 3. Least important entry (i.e. highest prio)
+    pub fn insert(&self, k: Zobrist, value: AlphaBetaEntry) {
+        let age = value.age();
+        let bucket = &self.bucket(&k).0;
+        let mut replaced: Option<(&AtomicEntry, AlphaBetaEntry)> = None;
+        for slot in bucket.iter() {
+            match slot.load() {
+                Some((key, mut entry)) if key == k => {
+                    entry.set_age(age);
+                    let keep = match value.prio(&entry, age) {
+                        cmp::Ordering::Greater => entry,
+                        // Bounds without a move (e.g. from pruning) keep the
+                        // old move for the move ordering. Exact entries
+                        // without a move end the PV, so they stay as they are.
+                        _ if value.best_move() == Move::NULL
+                            && value.score_type() != ScoreType::Exact =>
+                        {
+                            value.with_best_move(entry.best_move())
+                        }
+                        _ => value,
+                    };
+                    slot.store(k, keep);
+                    return;
+                }
+                Some((_, entry)) => match replaced {
+                    Some((_, rep)) if entry.prio(&rep, age) != cmp::Ordering::Greater => {}
+                    _ => replaced = Some((slot, entry)),
+                },
+                None => {
+                    slot.store(k, value);
+                    return;
+                }
+            }
+        }
+        if let Some((slot, _)) = replaced {
+            slot.store(k, value);
+        }
+    }
+
+    // Must not be called while other threads are accessing the table
+    pub fn clear(&self) {
+        for bucket in self.buckets.iter() {
+            for slot in bucket.0.iter() {
+                slot.clear();
+            }
+        }
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    // Estimate the occupancy in permille by sampling the first buckets
+    pub fn hashfull_permille(&self) -> u16 {
+        let sample = &self.buckets[..cmp::min(HASHFULL_SAMPLE_BUCKETS, self.buckets.len())];
+        let occupied = sample
+            .iter()
+            .flat_map(|b| b.0.iter())
+            .filter(|slot| slot.load().is_some())
+            .count();
+        (1000 * occupied / (sample.len() * ENTRIES_PER_BUCKET)) as u16
+    }
+
+    fn bucket(&self, k: &Zobrist) -> &Bucket {
+        &self.buckets[(u64::from(*k) >> (64 - self.index_bits)) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eval::Score;
+    use movegen::r#move::MoveType;
+    use movegen::square::Square;
+    use std::sync::Arc;
+    use std::thread;
+
+    // The content of the entry is derived from the key, so that every entry
+    // read from the table can be checked
+    fn entry_for_key(k: u64) -> AlphaBetaEntry {
+        let score_type = match k % 3 {
+            0 => ScoreType::Exact,
+            1 => ScoreType::LowerBound,
+            _ => ScoreType::UpperBound,
+        };
+        AlphaBetaEntry::new(
+            (k % 32) as usize,
+            (k % 1000) as Score,
+            score_type,
+            Move::from((k >> 32) as u16 & 0xfff),
+            0,
+        )
+    }
+
+    #[test]
+    fn pack_entry() {
+        for k in [0, 1, 2, 999, 0x1234_5678_9abc_def0, u64::MAX] {
+            let entry = entry_for_key(k);
+            assert_eq!(entry, AlphaBetaEntry::from(u64::from(entry)));
+            assert_eq!(0, u64::from(entry) & OCCUPIED);
+            assert_eq!(-entry, AlphaBetaEntry::from(u64::from(-entry)));
+        }
+    }
+
+    #[test]
+    fn torn_entry_is_a_miss() {
+        let table = SharedTable::new(0);
+        let k1 = Zobrist::from(0x0123_4567_89ab_cdef);
+        let k2 = Zobrist::from(0x0fed_cba9_8765_4321);
+        table.insert(k1, entry_for_key(1));
+        assert_eq!(Some(entry_for_key(1)), table.get(&k1));
+
+        // Simulate another thread writing the data of a different key between
+        // the two stores
+        let slot = table.bucket(&k1).0.iter().find(|s| s.load().is_some());
+        let slot = slot.unwrap();
+        let data = u64::from(entry_for_key(2)) | OCCUPIED;
+        slot.data.store(data, Ordering::Relaxed);
+        assert_eq!(None, table.get(&k1));
+        assert_eq!(None, table.get(&k2));
+    }
+
+    #[test]
+    fn keep_move_on_overwrite() {
+        let table = SharedTable::new(0);
+        let k = Zobrist::from(0x0123_4567_89ab_cdef);
+        let m = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        table.insert(k, AlphaBetaEntry::new(3, 10, ScoreType::UpperBound, m, 1));
+
+        // A bound without a move keeps the old move
+        table.insert(
+            k,
+            AlphaBetaEntry::new(5, 20, ScoreType::LowerBound, Move::NULL, 1),
+        );
+        let expected = AlphaBetaEntry::new(5, 20, ScoreType::LowerBound, m, 1);
+        assert_eq!(Some(expected), table.get(&k));
+
+        // An exact score without a move ends the PV, it must not get a move
+        table.insert(
+            k,
+            AlphaBetaEntry::new(6, 30, ScoreType::Exact, Move::NULL, 1),
+        );
+        let expected = AlphaBetaEntry::new(6, 30, ScoreType::Exact, Move::NULL, 1);
+        assert_eq!(Some(expected), table.get(&k));
+    }
+
+    #[test]
+    fn quiescence_entry_keeps_deep_entry() {
+        let table = SharedTable::new(0);
+        let k = Zobrist::from(0x0123_4567_89ab_cdef);
+        let m = Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH);
+        let deep = AlphaBetaEntry::new(8, 10, ScoreType::LowerBound, m, 1);
+        table.insert(k, deep);
+        table.insert(
+            k,
+            AlphaBetaEntry::new(0, 30, ScoreType::Exact, Move::NULL, 1),
+        );
+        assert_eq!(Some(deep), table.get(&k));
+    }
+
+    #[test]
+    fn concurrent_access() {
+        const THREADS: u64 = 8;
+        const KEYS_PER_THREAD: u64 = 20_000;
+        let table = Arc::new(SharedTable::new(64 * 1024));
+        let threads = (0..THREADS)
+            .map(|t| {
+                let table = Arc::clone(&table);
+                thread::spawn(move || {
+                    // Overlapping key ranges, so that the threads write to
+                    // the same buckets
+                    for i in 0..KEYS_PER_THREAD {
+                        let k = (t * KEYS_PER_THREAD / 2 + i).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                        table.insert(Zobrist::from(k), entry_for_key(k));
+                        let probe = k.rotate_left(17);
+                        if let Some(entry) = table.get(&Zobrist::from(probe)) {
+                            assert_eq!(entry_for_key(probe), entry);
+                        }
+                        if let Some(entry) = table.get(&Zobrist::from(k)) {
+                            assert_eq!(entry_for_key(k), entry);
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert!(table.hashfull_permille() > 0);
+        table.clear();
+        assert_eq!(0, table.hashfull_permille());
+    }
+
+    #[test]
+    fn generation() {
+        let table = SharedTable::new(64 * 1024);
+        assert_eq!(0, table.generation());
+        assert_eq!(1, table.next_generation());
+        assert_eq!(2, table.next_generation());
+        assert_eq!(2, table.generation());
+        table.clear();
+        assert_eq!(0, table.generation());
+        for _ in 0..255 {
+            table.next_generation();
+        }
+        assert_eq!(255, table.generation());
+        assert_eq!(0, table.next_generation());
+    }
+}
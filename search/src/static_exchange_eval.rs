@@ -1,7 +1,7 @@
 use eval::Score;
 use movegen::{
     bishop::Bishop, bitboard::Bitboard, king::King, knight::Knight, pawn::Pawn, piece,
-    position::Position, r#move::Move, rook::Rook, side::Side, square::Square,
+    position::Position, r#move::Move, ray::Ray, rook::Rook, side::Side, square::Square,
 };
 
 pub fn static_exchange_eval(pos: &Position, m: Move, threshold: Score) -> bool {
@@ -27,17 +27,41 @@ pub fn static_exchange_eval(pos: &Position, m: Move, threshold: Score) -> bool {
     // anyway.
     let mut occupied = pos.occupancy() & !Bitboard::from_square(m.origin());
     if m.is_en_passant() {
-        occupied &= !pos.en_passant_square();
+        let captured_square = match pos.side_to_move() {
+            Side::White => see_square.south_one(),
+            Side::Black => see_square.north_one(),
+        };
+        occupied &= !Bitboard::from_square(captured_square);
     }
     let diagonal_sliders = pos.piece_type_occupancy(piece::Type::Bishop)
         | pos.piece_type_occupancy(piece::Type::Queen);
     let line_sliders =
         pos.piece_type_occupancy(piece::Type::Rook) | pos.piece_type_occupancy(piece::Type::Queen);
     let mut attackers = initial_attackers(pos, see_square, occupied);
+    // Pinned pieces and their pinners, indexed by side. Only computed when
+    // the side has to recapture.
+    let mut pins_each_side: [Option<(Bitboard, Bitboard)>; 2] = [None; 2];
     let mut side_to_move = !pos.side_to_move();
 
     loop {
-        if attackers & pos.side_occupancy(side_to_move) == Bitboard::EMPTY {
+        let mut side_attackers = attackers & pos.side_occupancy(side_to_move);
+        if side_attackers == Bitboard::EMPTY {
+            break;
+        }
+        let (pinned, pinners) = *pins_each_side[side_to_move as usize].get_or_insert_with(|| {
+            pins(
+                pos,
+                m,
+                side_to_move,
+                side_attackers,
+                occupied | Bitboard::from_square(see_square),
+            )
+        });
+        // Pinned pieces can't capture as long as the pinner is on the board
+        if pinners & occupied != Bitboard::EMPTY {
+            side_attackers &= !pinned;
+        }
+        if side_attackers == Bitboard::EMPTY {
             break;
         }
 
@@ -51,7 +75,7 @@ pub fn static_exchange_eval(pos: &Position, m: Move, threshold: Score) -> bool {
             piece::Type::Queen,
             piece::Type::King,
         ] {
-            let piece_type_attackers = attackers & pos.piece_occupancy(side_to_move, piece_type);
+            let piece_type_attackers = side_attackers & pos.piece_type_occupancy(piece_type);
             if piece_type_attackers != Bitboard::EMPTY {
                 attacker_type = piece_type;
                 attacker_origin = piece_type_attackers.square_scan_forward();
@@ -60,11 +84,11 @@ pub fn static_exchange_eval(pos: &Position, m: Move, threshold: Score) -> bool {
         }
 
         occupied &= !Bitboard::from_square(attacker_origin);
-        // Reveal diagonal sliders
+        // Reveal diagonal sliders (x-rays)
         if let piece::Type::Pawn | piece::Type::Bishop | piece::Type::Queen = attacker_type {
             attackers |= Bishop::targets(see_square, occupied) & diagonal_sliders;
         }
-        // Reveal line sliders
+        // Reveal line sliders (x-rays)
         if let piece::Type::Rook | piece::Type::Queen = attacker_type {
             attackers |= Rook::targets(see_square, occupied) & line_sliders;
         }
@@ -108,6 +132,72 @@ fn moved_material_value(pos: &Position, m: Move) -> i16 {
     piece_type_value(piece_type)
 }
 
+type LineTargets = fn(Square, Bitboard) -> Bitboard;
+
+// Returns the attackers of the side which are pinned to their king and can't
+// capture on the target square of the move (because it isn't on the line
+// between the king and the pinner), and the pieces pinning them. Attackers
+// revealed later in the exchange aren't checked.
+fn pins(
+    pos: &Position,
+    m: Move,
+    side: Side,
+    attackers: Bitboard,
+    occupied: Bitboard,
+) -> (Bitboard, Bitboard) {
+    let kings = pos.piece_occupancy(side, piece::Type::King);
+    if attackers == Bitboard::EMPTY || kings == Bitboard::EMPTY {
+        return (Bitboard::EMPTY, Bitboard::EMPTY);
+    }
+    let king = match kings.square_scan_forward() {
+        k if k == m.origin() => m.target(),
+        k => k,
+    };
+    let enemy = pos.side_occupancy(!side) & occupied;
+    let diagonal_snipers = enemy
+        & (pos.piece_type_occupancy(piece::Type::Bishop)
+            | pos.piece_type_occupancy(piece::Type::Queen));
+    let line_snipers = enemy
+        & (pos.piece_type_occupancy(piece::Type::Rook)
+            | pos.piece_type_occupancy(piece::Type::Queen));
+    // No slider on a line with the king
+    if Bishop::targets(king, Bitboard::EMPTY) & diagonal_snipers
+        | Rook::targets(king, Bitboard::EMPTY) & line_snipers
+        == Bitboard::EMPTY
+    {
+        return (Bitboard::EMPTY, Bitboard::EMPTY);
+    }
+    let target = Bitboard::from_square(m.target());
+
+    let mut pinned = Bitboard::EMPTY;
+    let mut pinners = Bitboard::EMPTY;
+    let lines: [(LineTargets, Bitboard); 4] = [
+        (Ray::file_targets, line_snipers),
+        (Ray::rank_targets, line_snipers),
+        (Ray::diagonal_targets, diagonal_snipers),
+        (Ray::anti_diagonal_targets, diagonal_snipers),
+    ];
+    for (ray, snipers) in lines {
+        let mut candidates = ray(king, Bitboard::EMPTY) & snipers;
+        while candidates != Bitboard::EMPTY {
+            let sniper = candidates.square_scan_forward_reset();
+            // With exactly one piece between them, the rays of the king and
+            // of the sniper both end on that piece
+            let between = ray(king, occupied) & ray(sniper, occupied) & occupied;
+            if between.pop_count() != 1 || between & attackers == Bitboard::EMPTY {
+                continue;
+            }
+            let free = occupied & !between;
+            let pin_line = ray(king, free) & ray(sniper, free) | Bitboard::from_square(sniper);
+            if pin_line & target == Bitboard::EMPTY {
+                pinned |= between;
+                pinners |= Bitboard::from_square(sniper);
+            }
+        }
+    }
+    (pinned, pinners)
+}
+
 fn initial_attackers(pos: &Position, see_square: Square, occupied: Bitboard) -> Bitboard {
     let mut attackers = Bitboard::EMPTY;
     let white_pawn_attackers = Pawn::attack_origins(
@@ -150,7 +240,11 @@ pub fn piece_type_value(t: piece::Type) -> Score {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use movegen::{fen::Fen, r#move::MoveType};
+    use movegen::{
+        fen::Fen,
+        move_generator::MoveGenerator,
+        r#move::{MoveList, MoveType},
+    };
 
     // SEE test suite, taken from Carp
     #[test]
@@ -240,4 +334,439 @@ mod tests {
             assert_eq!(expected, actual);
         }
     }
+
+    // Quiet moves: the exchange on the target square after the move
+    #[test]
+    fn see_quiet() {
+        for (fen, m, threshold, expected) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Move::new(Square::E2, Square::E4, MoveType::DOUBLE_PAWN_PUSH),
+                0,
+                true,
+            ),
+            // The knight is lost to the pawn
+            (
+                "4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1",
+                Move::new(Square::E4, Square::C5, MoveType::QUIET),
+                0,
+                false,
+            ),
+            (
+                "4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1",
+                Move::new(Square::E4, Square::C5, MoveType::QUIET),
+                -300,
+                true,
+            ),
+            // Defended, but the knight is traded for a pawn
+            (
+                "4k3/8/3p4/8/1P2N3/8/8/4K3 w - - 0 1",
+                Move::new(Square::E4, Square::C5, MoveType::QUIET),
+                -100,
+                false,
+            ),
+            (
+                "4k3/8/3p4/8/1P2N3/8/8/4K3 w - - 0 1",
+                Move::new(Square::E4, Square::C5, MoveType::QUIET),
+                -200,
+                true,
+            ),
+            // Queen sacrifice which gives check
+            (
+                "5r1k/6pp/4Q2N/8/8/8/6PP/6K1 w - - 0 1",
+                Move::new(Square::E6, Square::G8, MoveType::QUIET),
+                -500,
+                false,
+            ),
+            // The rook behind the moved rook defends the target square
+            (
+                "3rk3/8/8/8/8/8/3R4/3RK3 w - - 0 1",
+                Move::new(Square::D2, Square::D5, MoveType::QUIET),
+                0,
+                true,
+            ),
+            (
+                "3rk3/8/8/8/8/8/3R4/3RK3 w - - 0 1",
+                Move::new(Square::D2, Square::D5, MoveType::QUIET),
+                1,
+                false,
+            ),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let actual = static_exchange_eval(&pos, m, threshold);
+            assert_eq!(expected, actual, "{fen} {m}");
+        }
+    }
+
+    // The standard SEE test set (from Ethereal, with the piece values of
+    // this engine). The exchange value is the highest threshold which is
+    // reached.
+    #[test]
+    fn see_values() {
+        for (fen, uci_move, value) in [
+            (
+                "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+                "e1e5",
+                100,
+            ),
+            (
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5",
+                -200,
+            ),
+            (
+                "6k1/1pp4p/p1pb4/6q1/3P1pRr/2P4P/PP1Br1P1/5RKN w - - 0 1",
+                "f1f4",
+                -100,
+            ),
+            (
+                "5rk1/1pp2q1p/p1pb4/8/3P1NP1/2P5/1P1BQ1P1/5RK1 b - - 0 1",
+                "d6f4",
+                0,
+            ),
+            (
+                "4R3/2r3p1/5bk1/1p1r3p/p2PR1P1/P1BK1P2/1P6/8 b - - 0 1",
+                "h5g4",
+                0,
+            ),
+            (
+                "4R3/2r3p1/5bk1/1p1r1p1p/p2PR1P1/P1BK1P2/1P6/8 b - - 0 1",
+                "h5g4",
+                0,
+            ),
+            (
+                "4r1k1/5pp1/nbp4p/1p2p2q/1P2P1b1/1BP2N1P/1B2QPPK/3R4 b - - 0 1",
+                "g4f3",
+                0,
+            ),
+            (
+                "2r1r1k1/pp1bppbp/3p1np1/q3P3/2P2P2/1P2B3/P1N1B1PP/2RQ1RK1 b - - 0 1",
+                "d6e5",
+                100,
+            ),
+            (
+                "7r/5qpk/p1Qp1b1p/3r3n/BB3p2/5p2/P1P2P2/4RK1R w - - 0 1",
+                "e1e8",
+                0,
+            ),
+            (
+                "6rr/6pk/p1Qp1b1p/2n5/1B3p2/5p2/P1P2P2/4RK1R w - - 0 1",
+                "e1e8",
+                -500,
+            ),
+            (
+                "7r/5qpk/2Qp1b1p/1N1r3n/BB3p2/5p2/P1P2P2/4RK1R w - - 0 1",
+                "e1e8",
+                -500,
+            ),
+            ("6RR/4bP2/8/8/5r2/3K4/5p2/4k3 w - - 0 1", "f7f8q", 200),
+            ("6RR/4bP2/8/8/5r2/3K4/5p2/4k3 w - - 0 1", "f7f8n", 200),
+            ("7R/5P2/8/8/6r1/3K4/5p2/4k3 w - - 0 1", "f7f8q", 800),
+            ("7R/5P2/8/8/6r1/3K4/5p2/4k3 w - - 0 1", "f7f8b", 200),
+            ("7R/4bP2/8/8/1q6/3K4/5p2/4k3 w - - 0 1", "f7f8r", -100),
+            (
+                "8/4kp2/2npp3/1Nn5/1p2PQP1/7q/1PP1B3/4KR1r b - - 0 1",
+                "h1f1",
+                0,
+            ),
+            (
+                "8/4kp2/2npp3/1Nn5/1p2P1P1/7q/1PP1B3/4KR1r b - - 0 1",
+                "h1f1",
+                0,
+            ),
+            (
+                "2r2r1k/6bp/p7/2q2p1Q/3PpP2/1B6/P5PP/2RR3K b - - 0 1",
+                "c5c1",
+                100,
+            ),
+            (
+                "r2qk1nr/pp2ppbp/2b3p1/2p1p3/8/2N2N2/PPPP1PPP/R1BQR1K1 w kq - 0 1",
+                "f3e5",
+                100,
+            ),
+            // The set says 0, but the f6 pawn is pinned
+            (
+                "6r1/4kq2/b2p1p2/p1pPb3/p1P2B1Q/2P4P/2B1R1P1/6K1 w - - 0 1",
+                "f4e5",
+                100,
+            ),
+            (
+                "3q2nk/pb1r1p2/np6/3P2Pp/2p1P3/2R4B/PQ3P1P/3R2K1 w - h6 0 1",
+                "g5h6",
+                0,
+            ),
+            (
+                "3q2nk/pb1r1p2/np6/3P2Pp/2p1P3/2R1B2B/PQ3P1P/3R2K1 w - h6 0 1",
+                "g5h6",
+                100,
+            ),
+            (
+                "2r4r/1P4pk/p2p1b1p/7n/BB3p2/2R2p2/P1P2P2/4RK2 w - - 0 1",
+                "c3c8",
+                500,
+            ),
+            (
+                "2r4k/2r4p/p7/2b2p1b/4pP2/1BR5/P1R3PP/2Q4K w - - 0 1",
+                "c3c5",
+                300,
+            ),
+            (
+                "8/pp6/2pkp3/4bp2/2R3b1/2P5/PP4B1/1K6 w - - 0 1",
+                "g2c6",
+                -200,
+            ),
+            (
+                "4q3/1p1pr1k1/1B2rp2/6p1/p3PP2/P3R1P1/1P2R1K1/4Q3 b - - 0 1",
+                "e6e4",
+                -400,
+            ),
+            (
+                "4q3/1p1pr1kb/1B2rp2/6p1/p3PP2/P3R1P1/1P2R1K1/4Q3 b - - 0 1",
+                "h7e4",
+                100,
+            ),
+            (
+                "3r3k/3r4/2n1n3/8/3p4/2PR4/1B1Q4/3R3K w - - 0 1",
+                "d3d4",
+                -100,
+            ),
+            (
+                "1k1r4/1ppn3p/p4b2/4n3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5",
+                100,
+            ),
+            (
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5",
+                -200,
+            ),
+            (
+                "rnb2b1r/ppp2kpp/5n2/4P3/q2P3B/5R2/PPP2PPP/RN1QKB2 w Q - 0 1",
+                "h4f6",
+                100,
+            ),
+            (
+                "r2q1rk1/2p1bppp/p2p1n2/1p2P3/4P1b1/1nP1BN2/PP3PPP/RN1QR1K1 b - - 0 1",
+                "g4f3",
+                0,
+            ),
+            (
+                "r1bqkb1r/2pp1ppp/p1n5/1p2p3/3Pn3/1B3N2/PPP2PPP/RNBQ1RK1 b kq - 0 1",
+                "c6d4",
+                0,
+            ),
+            (
+                "r1bq1r2/pp1ppkbp/4N1p1/n3P1B1/8/2N5/PPP2PPP/R2QK2R w KQ - 0 1",
+                "e6g7",
+                0,
+            ),
+            (
+                "r1bq1r2/pp1ppkbp/4N1pB/n3P3/8/2N5/PPP2PPP/R2QK2R w KQ - 0 1",
+                "e6g7",
+                300,
+            ),
+            (
+                "rnq1k2r/1b3ppp/p2bpn2/1p1p4/3N4/1BN1P3/PPP2PPP/R1BQR1K1 b kq - 0 1",
+                "d6h2",
+                -200,
+            ),
+            (
+                "rn2k2r/1bq2ppp/p2bpn2/1p1p4/3N4/1BN1P3/PPP2PPP/R1BQR1K1 b kq - 0 1",
+                "d6h2",
+                100,
+            ),
+            (
+                "r2qkbn1/ppp1pp1p/3p1rp1/3Pn3/4P1b1/2N2N2/PPP2PPP/R1BQKB1R b KQq - 0 1",
+                "g4f3",
+                100,
+            ),
+            (
+                "rnbq1rk1/pppp1ppp/4pn2/8/1bPP4/P1N5/1PQ1PPPP/R1B1KBNR b KQ - 0 1",
+                "b4c3",
+                0,
+            ),
+            (
+                "r4rk1/3nppbp/bq1p1np1/2pP4/8/2N2NPP/PP2PPB1/R1BQR1K1 b - - 0 1",
+                "b6b2",
+                -800,
+            ),
+            (
+                "r4rk1/1q1nppbp/b2p1np1/2pP4/8/2N2NPP/PP2PPB1/R1BQR1K1 b - - 0 1",
+                "f6d5",
+                -200,
+            ),
+            (
+                "1r3r2/5p2/4p2p/2k1n1P1/2PN1nP1/1P3P2/8/2KR1B1R b - - 0 1",
+                "b8b3",
+                -400,
+            ),
+            (
+                "1r3r2/5p2/4p2p/4n1P1/kPPN1nP1/5P2/8/2KR1B1R b - - 0 1",
+                "b8b4",
+                100,
+            ),
+            (
+                "2r2rk1/5pp1/pp5p/q2p4/P3n3/1Q3NP1/1P2PP1P/2RR2K1 b - - 0 1",
+                "c8c1",
+                0,
+            ),
+            (
+                "5rk1/5pp1/2r4p/5b2/2R5/6Q1/R1P1qPP1/5NK1 b - - 0 1",
+                "f5c2",
+                -100,
+            ),
+            (
+                "1r3r1k/p4pp1/2p1p2p/qpQP3P/2P5/3R4/PP3PP1/1K1R4 b - - 0 1",
+                "a5a2",
+                -800,
+            ),
+            (
+                "1r5k/p4pp1/2p1p2p/qpQP3P/2P2P2/1P1R4/P4rP1/1K1R4 b - - 0 1",
+                "a5a2",
+                100,
+            ),
+            (
+                "r2q1rk1/1b2bppp/p2p1n2/1ppNp3/3nP3/P2P1N1P/BPP2PP1/R1BQR1K1 w - - 0 1",
+                "d5e7",
+                0,
+            ),
+            (
+                "rnbqrbn1/pp3ppp/3p4/2p2k2/4p3/3B1K2/PPP2PPP/RNB1Q1NR w - - 0 1",
+                "d3e4",
+                100,
+            ),
+            (
+                "rnb1k2r/p3p1pp/1p3p1b/7n/1N2N3/3P1PB1/PPP1P1PP/R2QKB1R w KQkq - 0 1",
+                "e4d6",
+                -200,
+            ),
+            (
+                "r1b1k2r/p4npp/1pp2p1b/7n/1N2N3/3P1PB1/PPP1P1PP/R2QKB1R w KQkq - 0 1",
+                "e4d6",
+                0,
+            ),
+            (
+                "2r1k2r/pb4pp/5p1b/2KB3n/4N3/2NP1PB1/PPP1P1PP/R2Q3R w k - 0 1",
+                "d5c6",
+                -300,
+            ),
+            (
+                "2r1k2r/pb4pp/5p1b/2KB3n/1N2N3/3P1PB1/PPP1P1PP/R2Q3R w k - 0 1",
+                "d5c6",
+                0,
+            ),
+            (
+                "2r1k3/pbr3pp/5p1b/2KB3n/1N2N3/3P1PB1/PPP1P1PP/R2Q3R w - - 0 1",
+                "d5c6",
+                -300,
+            ),
+            (
+                "5k2/p2P2pp/8/1pb5/1Nn1P1n1/6Q1/PPP4P/R3K1NR w KQ - 0 1",
+                "d7d8q",
+                800,
+            ),
+            (
+                "r4k2/p2P2pp/8/1pb5/1Nn1P1n1/6Q1/PPP4P/R3K1NR w KQ - 0 1",
+                "d7d8q",
+                -100,
+            ),
+            (
+                "5k2/p2P2pp/1b6/1p6/1Nn1P1n1/8/PPP4P/R2QK1NR w KQ - 0 1",
+                "d7d8q",
+                200,
+            ),
+            (
+                "4kbnr/p1P1pppp/b7/4q3/7n/8/PP1PPPPP/RNBQKBNR w KQk - 0 1",
+                "c7c8q",
+                -100,
+            ),
+            (
+                "4kbnr/p1P1pppp/b7/4q3/7n/8/PPQPPPPP/RNB1KBNR w KQk - 0 1",
+                "c7c8q",
+                200,
+            ),
+            (
+                "4kbnr/p1P4p/b1q5/5pP1/4n3/5Q2/PP1PPP1P/RNB1KBNR w KQk f6 0 1",
+                "g5f6",
+                0,
+            ),
+            (
+                "4kbnr/p1P4p/b1q5/5pP1/4n2Q/8/PP1PPP1P/RNB1KBNR w KQk f6 0 1",
+                "g5f6",
+                0,
+            ),
+            (
+                "1n2kb1r/p1P4p/2qb4/5pP1/4n2Q/8/PP1PPP1P/RNB1KBNR w KQk - 0 1",
+                "c7b8q",
+                200,
+            ),
+            (
+                "rnbqk2r/pp3ppp/2p1pn2/3p4/3P4/N1P1BN2/PPB1PPPb/R2Q1RK1 w kq - 0 1",
+                "g1h2",
+                300,
+            ),
+            ("3N4/2K5/2n5/1k6/8/8/8/8 b - - 0 1", "c6d8", 0),
+            ("3n3r/2P5/8/1k6/8/8/3Q4/4K3 w - - 0 1", "c7d8q", 700),
+            ("r2n3r/2P1P3/4N3/1k6/8/8/8/4K3 w - - 0 1", "e6d8", 300),
+            ("8/8/8/1k6/6b1/4N3/2p3K1/3n4 w - - 0 1", "e3d1", 0),
+            ("8/8/1k6/8/8/2N1N3/4p1K1/3n4 w - - 0 1", "c3d1", 100),
+            (
+                "r1bqk1nr/pppp1ppp/2n5/1B2p3/1b2P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1",
+                "e1g1",
+                0,
+            ),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let mut move_list = MoveList::new();
+            MoveGenerator::generate_moves(&mut move_list, &pos);
+            let m = *move_list
+                .iter()
+                .find(|m| to_uci(**m) == uci_move)
+                .unwrap_or_else(|| panic!("{fen}: {uci_move} is not legal"));
+            assert!(static_exchange_eval(&pos, m, value), "{fen} {uci_move}");
+            assert!(
+                !static_exchange_eval(&pos, m, value + 1),
+                "{fen} {uci_move}"
+            );
+        }
+    }
+
+    #[test]
+    fn see_pins_and_en_passant() {
+        for (fen, m, value) in [
+            // The knight is pinned and can't recapture
+            (
+                "4k3/8/4n3/8/3p4/8/2N5/4R1K1 w - - 0 1",
+                Move::new(Square::C2, Square::D4, MoveType::CAPTURE),
+                100,
+            ),
+            // The rook is pinned, but can capture the pinner
+            (
+                "4k3/8/4r3/8/4p3/8/8/4R1K1 w - - 0 1",
+                Move::new(Square::E1, Square::E4, MoveType::CAPTURE),
+                -400,
+            ),
+            // The captured pawn no longer blocks the rook
+            (
+                "k5n1/8/8/5pP1/8/8/8/4KR2 w - f6 0 1",
+                Move::new(Square::G5, Square::F6, MoveType::EN_PASSANT_CAPTURE),
+                100,
+            ),
+        ] {
+            let pos = Fen::str_to_pos(fen).unwrap();
+            assert!(static_exchange_eval(&pos, m, value), "{fen} {m}");
+            assert!(!static_exchange_eval(&pos, m, value + 1), "{fen} {m}");
+        }
+    }
+
+    fn to_uci(m: Move) -> String {
+        let promo = match m.promotion_piece() {
+            Some(piece::Type::Knight) => "n",
+            Some(piece::Type::Bishop) => "b",
+            Some(piece::Type::Rook) => "r",
+            Some(piece::Type::Queen) => "q",
+            _ => "",
+        };
+        format!("{}{}{promo}", m.origin(), m.target())
+    }
 }
@@ -0,0 +1,269 @@
+use eval::Score;
+use std::cmp;
+
+// Range of the UCI_Elo option
+pub const MIN_ELO: u16 = 1200;
+pub const MAX_ELO: u16 = 3000;
+
+// At the lowest strength, search this many nodes per move. The number of nodes
+// doubles every ELO_PER_NODE_DOUBLING Elo.
+const MIN_ELO_NODES: usize = 100;
+const ELO_PER_NODE_DOUBLING: u16 = 120;
+
+// At the lowest strength, consider all root moves within this margin of the
+// best move. The margin shrinks linearly to 0 at MAX_ELO.
+const MIN_ELO_SCORE_MARGIN: Score = 225;
+
+// Range of the Skill Level option. The highest level plays at full strength.
+pub const MIN_SKILL_LEVEL: u8 = 0;
+pub const MAX_SKILL_LEVEL: u8 = 20;
+
+// The score margin of the skill level grows by this much per level below the
+// maximum
+const SKILL_LEVEL_SCORE_MARGIN_PER_LEVEL: Score = 15;
+
+// With a skill level, only the best root moves are considered
+const SKILL_LEVEL_CANDIDATES: usize = 4;
+
+// Parameters for weakening the search to a given Elo rating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrengthLimit {
+    elo: u16,
+}
+
+impl StrengthLimit {
+    pub fn new(elo: u16) -> Self {
+        Self {
+            elo: elo.clamp(MIN_ELO, MAX_ELO),
+        }
+    }
+
+    pub fn elo(&self) -> u16 {
+        self.elo
+    }
+
+    pub fn max_nodes(&self) -> usize {
+        let elo_diff = self.elo - MIN_ELO;
+        let doublings = elo_diff / ELO_PER_NODE_DOUBLING;
+        let remainder = elo_diff % ELO_PER_NODE_DOUBLING;
+        let nodes = MIN_ELO_NODES << doublings;
+        // Interpolate linearly between two doublings
+        nodes + nodes * remainder as usize / ELO_PER_NODE_DOUBLING as usize
+    }
+
+    pub fn score_margin(&self) -> Score {
+        (MIN_ELO_SCORE_MARGIN as i32 * (MAX_ELO - self.elo) as i32 / (MAX_ELO - MIN_ELO) as i32)
+            as Score
+    }
+
+    // Choose one of the candidates (pairs of moves and scores). The best
+    // candidate has the score best_score. The choice only depends on the seed.
+    pub fn choose<T: Copy>(&self, candidates: &[(T, Score)], best_score: Score, seed: u64) -> T {
+        choose_within_margin(candidates, best_score, self.score_margin(), seed)
+    }
+}
+
+// Weakening by the Skill Level option. The search itself is not limited, only
+// the final move is chosen among the best root moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkillLevel {
+    level: u8,
+}
+
+impl SkillLevel {
+    pub fn new(level: u8) -> Self {
+        Self {
+            level: level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL),
+        }
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn score_margin(&self) -> Score {
+        (MAX_SKILL_LEVEL - self.level) as Score * SKILL_LEVEL_SCORE_MARGIN_PER_LEVEL
+    }
+
+    // Choose one of the best SKILL_LEVEL_CANDIDATES candidates (pairs of moves
+    // and scores). The best candidate has the score best_score. The seed is
+    // derived from the start position of the game, so that a game can be
+    // reproduced.
+    pub fn choose<T: Copy>(&self, candidates: &[(T, Score)], best_score: Score, seed: u64) -> T {
+        let mut best_candidates = candidates.to_vec();
+        // Stable, so the order of equal candidates doesn't depend on the sort
+        best_candidates.sort_by_key(|&(_, s)| cmp::Reverse(s));
+        best_candidates.truncate(SKILL_LEVEL_CANDIDATES);
+        choose_within_margin(&best_candidates, best_score, self.score_margin(), seed)
+    }
+}
+
+// The RandomizeEqualMoves option chooses among the root moves within this
+// margin of the best move
+pub const EQUAL_MOVES_SCORE_MARGIN: Score = 5;
+
+// Choose uniformly among the candidates (pairs of moves and scores) within
+// EQUAL_MOVES_SCORE_MARGIN of best_score. The choice only depends on the seed.
+pub fn choose_equal_move<T: Copy>(candidates: &[(T, Score)], best_score: Score, seed: u64) -> T {
+    debug_assert!(!candidates.is_empty());
+    let equal = candidates
+        .iter()
+        .filter(|&&(_, s)| best_score as i32 - s as i32 <= EQUAL_MOVES_SCORE_MARGIN as i32)
+        .map(|&(c, _)| c)
+        .collect::<Vec<_>>();
+    match equal.len() {
+        0 => candidates[0].0,
+        len => equal[(splitmix64(seed) % len as u64) as usize],
+    }
+}
+
+// Candidates outside the score margin are never chosen, the others are chosen
+// with a probability proportional to how close they are to the best score.
+fn choose_within_margin<T: Copy>(
+    candidates: &[(T, Score)],
+    best_score: Score,
+    margin: Score,
+    seed: u64,
+) -> T {
+    debug_assert!(!candidates.is_empty());
+    let margin = margin as i32;
+    let weight = |score: Score| (margin + 1 - (best_score as i32 - score as i32)).max(0) as u64;
+    let total_weight: u64 = candidates.iter().map(|&(_, s)| weight(s)).sum();
+    if total_weight == 0 {
+        return candidates[0].0;
+    }
+    let mut r = splitmix64(seed) % total_weight;
+    for &(c, s) in candidates {
+        let w = weight(s);
+        if r < w {
+            return c;
+        }
+        r -= w;
+    }
+    unreachable!();
+}
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elo_is_clamped() {
+        assert_eq!(MIN_ELO, StrengthLimit::new(0).elo());
+        assert_eq!(MIN_ELO, StrengthLimit::new(MIN_ELO).elo());
+        assert_eq!(MAX_ELO, StrengthLimit::new(MAX_ELO).elo());
+        assert_eq!(MAX_ELO, StrengthLimit::new(u16::MAX).elo());
+    }
+
+    #[test]
+    fn stronger_means_more_nodes_and_smaller_margin() {
+        assert_eq!(MIN_ELO_NODES, StrengthLimit::new(MIN_ELO).max_nodes());
+        assert_eq!(
+            MIN_ELO_SCORE_MARGIN,
+            StrengthLimit::new(MIN_ELO).score_margin()
+        );
+        assert_eq!(0, StrengthLimit::new(MAX_ELO).score_margin());
+
+        for elo in MIN_ELO..MAX_ELO {
+            let weaker = StrengthLimit::new(elo);
+            let stronger = StrengthLimit::new(elo + 1);
+            assert!(weaker.max_nodes() <= stronger.max_nodes());
+            assert!(weaker.score_margin() >= stronger.score_margin());
+        }
+    }
+
+    #[test]
+    fn choose_within_margin() {
+        let candidates = [('a', 0), ('b', -10), ('c', -1000), ('d', 5)];
+
+        // At the highest rating, always choose the best move
+        let limit = StrengthLimit::new(MAX_ELO);
+        for seed in 0..1000 {
+            assert_eq!('d', limit.choose(&candidates, 5, seed));
+        }
+
+        let limit = StrengthLimit::new(MIN_ELO);
+        let mut chosen = Vec::new();
+        for seed in 0..1000 {
+            let c = limit.choose(&candidates, 5, seed);
+            assert_ne!('c', c);
+            // Reproducible
+            assert_eq!(c, limit.choose(&candidates, 5, seed));
+            chosen.push(c);
+        }
+        assert!(chosen.contains(&'a'));
+        assert!(chosen.contains(&'b'));
+        assert!(chosen.contains(&'d'));
+    }
+
+    #[test]
+    fn skill_level_is_clamped() {
+        assert_eq!(MIN_SKILL_LEVEL, SkillLevel::new(MIN_SKILL_LEVEL).level());
+        assert_eq!(MAX_SKILL_LEVEL, SkillLevel::new(MAX_SKILL_LEVEL).level());
+        assert_eq!(MAX_SKILL_LEVEL, SkillLevel::new(u8::MAX).level());
+        assert_eq!(0, SkillLevel::new(MAX_SKILL_LEVEL).score_margin());
+        for level in MIN_SKILL_LEVEL..MAX_SKILL_LEVEL {
+            assert!(
+                SkillLevel::new(level).score_margin() > SkillLevel::new(level + 1).score_margin()
+            );
+        }
+    }
+
+    #[test]
+    fn skill_level_chooses_among_best_candidates() {
+        let candidates = [
+            ('a', -20),
+            ('b', -10),
+            ('c', -5),
+            ('d', 0),
+            ('e', -1000),
+            ('f', -15),
+        ];
+
+        let level = SkillLevel::new(MAX_SKILL_LEVEL);
+        for seed in 0..1000 {
+            assert_eq!('d', level.choose(&candidates, 0, seed));
+        }
+
+        let level = SkillLevel::new(MIN_SKILL_LEVEL);
+        let mut chosen = Vec::new();
+        for seed in 0..1000 {
+            let c = level.choose(&candidates, 0, seed);
+            // Only the 4 best candidates
+            assert!(['b', 'c', 'd', 'f'].contains(&c));
+            // Reproducible
+            assert_eq!(c, level.choose(&candidates, 0, seed));
+            chosen.push(c);
+        }
+        for c in ['b', 'c', 'd', 'f'] {
+            assert!(chosen.contains(&c));
+        }
+    }
+
+    #[test]
+    fn choose_equal_moves() {
+        let candidates = [('a', 0), ('b', -5), ('c', -6), ('d', 3), ('e', -1000)];
+        let mut chosen = Vec::new();
+        for seed in 0..1000 {
+            let c = choose_equal_move(&candidates, 3, seed);
+            // Never outside the margin
+            assert!(['a', 'd'].contains(&c));
+            // Reproducible
+            assert_eq!(c, choose_equal_move(&candidates, 3, seed));
+            chosen.push(c);
+        }
+        assert!(chosen.contains(&'a'));
+        assert!(chosen.contains(&'d'));
+
+        // The margin is inclusive
+        let candidates = [('a', 0), ('b', -EQUAL_MOVES_SCORE_MARGIN)];
+        assert!((0..1000).any(|seed| choose_equal_move(&candidates, 0, seed) == 'b'));
+    }
+}
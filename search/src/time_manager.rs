@@ -1,88 +1,298 @@
 use crate::SearchOptions;
+use eval::Score;
+use movegen::position::Position;
 use movegen::side::Side;
 use std::{cmp, time::Duration};
 
-const DEFAULT_MOVES_TO_GO: usize = 40;
+// With `movestogo`, the available time is divided by the moves to go plus a
+// reserve of moves. So the last move before the time control doesn't use up
+// the whole clock.
+const MOVES_TO_GO_RESERVE: u64 = 1;
+
+// In sudden death, a fraction of the available time is used for each move.
+// The fraction declines with the move number, so more time is spent early in
+// the game. It's lowered by 1 permille every few moves until it reaches the
+// minimum.
+const SUDDEN_DEATH_START_PERMILLE: u64 = 40;
+const SUDDEN_DEATH_MIN_PERMILLE: u64 = 20;
+const SUDDEN_DEATH_MOVES_PER_PERMILLE: u64 = 4;
+
+// Most of the increment is added to the soft limit. With the full increment,
+// we would be running into the hard limit almost every move if we have very
+// little time left.
+const INC_PERCENT: u64 = 75;
+
+// The hard limit is a multiple of the soft limit. Neither limit may exceed a
+// fixed fraction of the available time.
+const HARD_LIMIT_FACTOR: u64 = 3;
+const MAX_TIME_PERCENT: u64 = 60;
+
+// The soft limit is scaled by the fraction of nodes spent on the best root
+// move. If the best move takes most of the nodes, it is probably an easy move
+// and the search stops earlier. If it takes few nodes, other moves are close
+// and the search gets more time. The fraction is only reliable from this
+// depth on.
+pub const BEST_MOVE_NODES_MIN_DEPTH: usize = 6;
+const BEST_MOVE_NODES_SCALE_BASE: f64 = 1.5;
+const BEST_MOVE_NODES_SCALE_MIN: f64 = 0.5;
+const BEST_MOVE_NODES_SCALE_MAX: f64 = 1.5;
+
+// If the score drops by at least the threshold compared to the previous
+// iteration, the soft limit is extended by the given percentage. The
+// extension grows with the size of the drop, up to the maximum number of
+// steps.
+pub const SCORE_DROP_THRESHOLD: Score = 30;
+pub const SCORE_DROP_EXTENSION_PERCENT: i32 = 50;
+const SCORE_DROP_MAX_STEPS: i32 = 2;
+
+// Soft limit: no new iteration is started after it has been exceeded.
+// Hard limit: the search is aborted, even in the middle of an iteration.
+// The soft limit never exceeds the hard limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeLimits {
+    pub soft: Option<Duration>,
+    pub hard: Option<Duration>,
+}
 
 pub struct TimeManager;
 
 impl TimeManager {
-    pub fn calc_movetime_hard_limit(
-        side_to_move: Side,
-        options: &SearchOptions,
-    ) -> Option<Duration> {
+    pub fn calc_time_limits(pos: &Position, options: &SearchOptions) -> TimeLimits {
         if let Some(dur) = options.movetime {
-            return Some(dur);
+            return TimeLimits {
+                soft: None,
+                hard: Some(dur),
+            };
         }
+        let (time_millis, inc_millis) = match Self::remaining_time(pos.side_to_move(), options) {
+            Some(t) => t,
+            None => {
+                return TimeLimits {
+                    soft: None,
+                    hard: None,
+                }
+            }
+        };
 
-        const MIN_TIME: Duration = Duration::from_millis(0);
-
-        let moves_to_go = options.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO);
-        let (time_millis, inc_millis) = match side_to_move {
-            Side::White if options.white_time.is_some() => (
-                options
-                    .white_time
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-                options
-                    .white_inc
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-            ),
-            Side::Black if options.black_time.is_some() => (
-                options
-                    .black_time
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-                options
-                    .black_inc
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-            ),
-            _ => return None,
+        // The move overhead is kept in reserve, so that we don't lose on time
+        // because of communication delays
+        let available = time_millis.saturating_sub(options.move_overhead.as_millis() as u64);
+        let base = match options.moves_to_go {
+            // `movestogo 0` would divide by zero. It's treated like
+            // `movestogo 1`.
+            Some(mtg) => available / (cmp::max(mtg, 1) as u64 + MOVES_TO_GO_RESERVE),
+            None => available * Self::sudden_death_permille(pos.move_count()) / 1000,
         };
-        let quot = (moves_to_go as f64).sqrt() as u64;
-        let max_time = time_millis as u64 / quot + inc_millis as u64;
-        let hard_time_limit = match time_millis.checked_sub(options.move_overhead.as_millis()) {
-            Some(t) => Duration::from_millis(cmp::min(t as u64, max_time)),
-            None => MIN_TIME,
+        let max_time = available * MAX_TIME_PERCENT / 100;
+        let soft = cmp::min(base + inc_millis * INC_PERCENT / 100, max_time);
+        let hard = cmp::min(soft * HARD_LIMIT_FACTOR, max_time);
+        TimeLimits {
+            soft: Some(Duration::from_millis(soft)),
+            hard: Some(Duration::from_millis(hard)),
+        }
+    }
+
+    fn sudden_death_permille(move_count: usize) -> u64 {
+        SUDDEN_DEATH_START_PERMILLE
+            .saturating_sub(move_count as u64 / SUDDEN_DEATH_MOVES_PER_PERMILLE)
+            .max(SUDDEN_DEATH_MIN_PERMILLE)
+    }
+
+    // Remaining time and increment of the side to move in milliseconds. None
+    // if there is no time control for this side.
+    fn remaining_time(side_to_move: Side, options: &SearchOptions) -> Option<(u64, u64)> {
+        let (time, inc) = match side_to_move {
+            Side::White => (options.white_time?, options.white_inc),
+            Side::Black => (options.black_time?, options.black_inc),
         };
-        Some(hard_time_limit)
+        Some((
+            time.as_millis() as u64,
+            inc.unwrap_or(Duration::ZERO).as_millis() as u64,
+        ))
+    }
+
+    // Factor for the soft limit, depending on the fraction of the root nodes
+    // that were searched for the best move
+    pub fn best_move_nodes_scale(best_move_node_fraction: f64) -> f64 {
+        (BEST_MOVE_NODES_SCALE_BASE - best_move_node_fraction)
+            .clamp(BEST_MOVE_NODES_SCALE_MIN, BEST_MOVE_NODES_SCALE_MAX)
     }
 
-    pub fn calc_movetime_soft_limit(
-        side_to_move: Side,
-        options: &SearchOptions,
-    ) -> Option<Duration> {
-        let moves_to_go = options.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO);
-        let (time_millis, inc_millis) = match side_to_move {
-            Side::White if options.white_time.is_some() => (
-                options
-                    .white_time
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-                options
-                    .white_inc
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-            ),
-            Side::Black if options.black_time.is_some() => (
-                options
-                    .black_time
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-                options
-                    .black_inc
-                    .unwrap_or(Duration::from_millis(0))
-                    .as_millis(),
-            ),
-            _ => return None,
+    // Factor for the soft limit, depending on how much the score dropped
+    // compared to the previous iteration
+    pub fn score_drop_scale(
+        prev_score: Score,
+        score: Score,
+        threshold: Score,
+        extension_percent: i32,
+    ) -> f64 {
+        let drop = prev_score as i32 - score as i32;
+        if threshold <= 0 || drop < threshold as i32 {
+            return 1.0;
+        }
+        let steps = cmp::min(drop / threshold as i32, SCORE_DROP_MAX_STEPS);
+        1.0 + (steps * extension_percent) as f64 / 100.0
+    }
+
+    // The soft limit scaled by the given factor. It is never extended beyond
+    // the hard limit.
+    pub fn scaled_soft_limit(soft: Duration, scale: f64, hard: Option<Duration>) -> Duration {
+        let scaled = soft.mul_f64(scale);
+        match hard {
+            Some(hard) => cmp::min(scaled, hard),
+            None => scaled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(time_millis: u64, inc_millis: u64, moves_to_go: Option<usize>) -> SearchOptions {
+        SearchOptions {
+            white_time: Some(Duration::from_millis(time_millis)),
+            black_time: Some(Duration::from_millis(time_millis)),
+            white_inc: Some(Duration::from_millis(inc_millis)),
+            black_inc: Some(Duration::from_millis(inc_millis)),
+            moves_to_go,
+            move_overhead: Duration::from_millis(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn best_move_nodes_scale() {
+        // Easy move: most nodes were spent on the best move
+        assert_eq!(0.5, TimeManager::best_move_nodes_scale(1.0));
+        assert!(TimeManager::best_move_nodes_scale(0.9) < 1.0);
+        assert_eq!(1.0, TimeManager::best_move_nodes_scale(0.5));
+        // Unstable: other moves took most of the nodes
+        assert!(TimeManager::best_move_nodes_scale(0.2) > 1.0);
+        assert_eq!(1.5, TimeManager::best_move_nodes_scale(0.0));
+        let mut prev = f64::INFINITY;
+        for i in 0..=100 {
+            let scale = TimeManager::best_move_nodes_scale(i as f64 / 100.0);
+            assert!(scale <= prev);
+            prev = scale;
+        }
+    }
+
+    #[test]
+    fn score_drop_scale() {
+        let scale = |prev, score| {
+            TimeManager::score_drop_scale(
+                prev,
+                score,
+                SCORE_DROP_THRESHOLD,
+                SCORE_DROP_EXTENSION_PERCENT,
+            )
+        };
+        // Stable or rising scores don't extend the time
+        assert_eq!(1.0, scale(50, 50));
+        assert_eq!(1.0, scale(50, 300));
+        assert_eq!(1.0, scale(50, 21));
+        // Drops are extended in steps of the threshold
+        assert_eq!(1.5, scale(50, 20));
+        assert_eq!(1.5, scale(50, -9));
+        assert_eq!(2.0, scale(50, -10));
+        assert_eq!(2.0, scale(50, -500));
+        // A threshold of 0 disables the extension
+        assert_eq!(1.0, TimeManager::score_drop_scale(50, -500, 0, 50));
+    }
+
+    #[test]
+    fn scaled_soft_limit() {
+        let soft = Duration::from_millis(1_000);
+        let hard = Some(Duration::from_millis(1_800));
+        assert_eq!(
+            Duration::from_millis(500),
+            TimeManager::scaled_soft_limit(soft, 0.5, hard)
+        );
+        assert_eq!(
+            Duration::from_millis(1_500),
+            TimeManager::scaled_soft_limit(soft, 1.5, hard)
+        );
+        // Capped by the hard limit
+        assert_eq!(
+            Duration::from_millis(1_800),
+            TimeManager::scaled_soft_limit(soft, 3.0, hard)
+        );
+        assert_eq!(
+            Duration::from_millis(3_000),
+            TimeManager::scaled_soft_limit(soft, 3.0, None)
+        );
+    }
+
+    #[test]
+    fn time_limits() {
+        // Remaining time, increment, moves to go, move number, expected soft
+        // and hard limit. All times in milliseconds, the move overhead is
+        // 10 ms.
+        #[rustfmt::skip]
+        let cases: [(u64, u64, Option<usize>, usize, u64, u64); 15] = [
+            // Bullet 1+0
+            (60_000, 0, None, 1, 2_399, 7_197),
+            (20_000, 0, None, 40, 599, 1_797),
+            // Bullet 1+1
+            (5_000, 1_000, None, 30, 914, 2_742),
+            // Blitz 3+2
+            (180_000, 2_000, None, 1, 8_699, 26_097),
+            (30_000, 2_000, None, 60, 2_249, 6_747),
+            // Less time left than the increment
+            (1_000, 2_000, None, 50, 594, 594),
+            // The fraction doesn't decline below the minimum
+            (60_000, 0, None, 120, 1_199, 3_597),
+            // Classical 40 moves in 90 minutes, then 30 minutes + 30 seconds
+            (5_400_000, 0, Some(40), 1, 131_707, 395_121),
+            (1_800_000, 30_000, None, 41, 76_499, 229_497),
+            (60_000, 0, Some(5), 36, 9_998, 29_994),
+            // Last move before the time control
+            (10_000, 0, Some(1), 40, 4_995, 5_994),
+            (10_000, 1_000, Some(1), 40, 5_745, 5_994),
+            // `movestogo 0` is treated like `movestogo 1`
+            (10_000, 0, Some(0), 40, 4_995, 5_994),
+            // Less time left than the move overhead
+            (5, 0, None, 30, 0, 0),
+            (5, 0, Some(1), 30, 0, 0),
+        ];
+        for (time, inc, moves_to_go, move_count, soft, hard) in cases {
+            let mut pos = Position::initial();
+            pos.set_move_count(move_count);
+            let opt = options(time, inc, moves_to_go);
+            let expected = TimeLimits {
+                soft: Some(Duration::from_millis(soft)),
+                hard: Some(Duration::from_millis(hard)),
+            };
+            assert_eq!(
+                expected,
+                TimeManager::calc_time_limits(&pos, &opt),
+                "time: {time}, inc: {inc}, moves to go: {moves_to_go:?}, move: {move_count}",
+            );
+            pos.set_side_to_move(Side::Black);
+            assert_eq!(expected, TimeManager::calc_time_limits(&pos, &opt));
+        }
+    }
+
+    #[test]
+    fn no_time_control() {
+        let mut pos = Position::initial();
+        pos.set_side_to_move(Side::Black);
+        let opt = SearchOptions {
+            white_time: Some(Duration::from_millis(60_000)),
+            ..Default::default()
+        };
+        let limits = TimeManager::calc_time_limits(&pos, &opt);
+        assert_eq!(None, limits.soft);
+        assert_eq!(None, limits.hard);
+
+        // Fixed time per move: search until the hard limit
+        let opt = SearchOptions {
+            movetime: Some(Duration::from_millis(1_000)),
+            ..Default::default()
         };
-        // We don't add the full increment to the soft limit. Otherwise we would
-        // be running into the hard limit almost every move if we have very
-        // little time left.
-        const INC_DIVISOR: u64 = 2;
-        let soft_limit = time_millis as u64 / moves_to_go as u64 + inc_millis as u64 / INC_DIVISOR;
-        Some(Duration::from_millis(soft_limit))
+        let limits = TimeManager::calc_time_limits(&pos, &opt);
+        assert_eq!(None, limits.soft);
+        assert_eq!(Some(Duration::from_millis(1_000)), limits.hard);
     }
 }
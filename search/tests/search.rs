@@ -1,6 +1,6 @@
 use crossbeam_channel::{unbounded, Receiver};
 use eval::complex::Complex;
-use eval::{Eval, ScoreVariant, BLACK_WIN, EQ_POSITION, NEG_INF, WHITE_WIN};
+use eval::{Eval, GamePhase, Score, ScoreVariant, BLACK_WIN, EQ_POSITION, NEG_INF, WHITE_WIN};
 use movegen::fen::Fen;
 use movegen::move_generator::MoveGenerator;
 use movegen::piece;
@@ -9,12 +9,14 @@ use movegen::position_history::PositionHistory;
 use movegen::r#move::{Move, MoveList, MoveType};
 use movegen::side::Side;
 use movegen::square::Square;
-use search::alpha_beta::AlphaBeta;
-use search::search::{Search, SearchInfo, SearchResult};
+use search::alpha_beta::{AlphaBeta, CONTEMPT_ENDGAME_PERCENT};
+use search::bench::{self, BENCH_FENS};
+use search::search::{ScoreBound, Search, SearchCommand, SearchInfo, SearchResult};
+use search::search_params::{AlphaBetaParams, SearchParamsEachAlgo};
 use search::searcher::Searcher;
 use search::SearchOptions;
 use std::cmp;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const TABLE_SIZE: usize = 16 * 1024 * 1024;
 const TIMEOUT_PER_TEST: Duration = Duration::from_millis(30000);
@@ -24,6 +26,17 @@ fn evaluator() -> impl Eval {
     Complex::new()
 }
 
+// Evaluates every position the same, from white's point of view. Searches
+// which have to find something without the help of the evaluation use it.
+#[derive(Clone)]
+struct ConstantEval(Score);
+
+impl Eval for ConstantEval {
+    fn eval(&mut self, _pos: &Position) -> Score {
+        self.0
+    }
+}
+
 struct SearchTester {
     searcher: Searcher,
     result_receiver: Receiver<SearchInfo>,
@@ -58,6 +71,10 @@ impl SearchTester {
             println!("{:?}", received);
             match received {
                 Ok(SearchInfo::DepthFinished(res)) => search_result = Some(res),
+                Ok(SearchInfo::AspirationFail(_)) => continue,
+                Ok(SearchInfo::CurrentMove(_)) => continue,
+                Ok(SearchInfo::Progress(_)) => continue,
+                Ok(SearchInfo::Trace(_)) => continue,
                 Ok(SearchInfo::Stopped(_)) => return search_result.unwrap(),
                 unexp => panic!("Expected Ok(SearchInfo::DepthFinished(_)), got {:?}", unexp),
             };
@@ -175,11 +192,11 @@ fn stalemate(search_algo: impl Search + Send + 'static) {
     pos.set_side_to_move(Side::Black);
     let pos_history = PositionHistory::new(pos);
 
-    let depth = 1;
-    let expected = SearchResult::new(depth, 0, EQ_POSITION, 0, 0, 0, Move::NULL, MoveList::new());
+    // Nothing is searched, the result is reported for depth 0
+    let expected = SearchResult::new(0, 0, EQ_POSITION, 0, 0, 0, Move::NULL, MoveList::new());
 
     let mut tester = SearchTester::new(search_algo);
-    let actual = tester.search(pos_history, depth);
+    let actual = tester.search(pos_history, 1);
     assert_eq!(expected.depth(), actual.depth());
     assert_eq!(expected.score(), actual.score());
     assert_eq!(expected.nodes(), actual.nodes());
@@ -206,6 +223,95 @@ fn search_quiescence(search_algo: impl Search + Send + 'static) {
     assert_eq!(max_score, tester.search(pos_history.clone(), depth).score());
 }
 
+fn depth_zero(search_algo: impl Search + Send + 'static) {
+    let mut tester = SearchTester::new(search_algo);
+
+    // Without captures, the score is the static evaluation
+    let pos = Position::initial();
+    let actual = tester.search(PositionHistory::new(pos.clone()), 0);
+    assert_eq!(0, actual.depth());
+    assert_eq!(evaluator().eval(&pos), actual.score());
+    assert!(actual.principal_variation().is_empty());
+
+    // The best move starts the best capture sequence. The extra pawn keeps
+    // the result from being a drawn KPK endgame.
+    let pos = Fen::str_to_pos("4k3/8/8/3q4/4P3/8/P7/4K3 w - - 0 1").unwrap();
+    let actual = tester.search(PositionHistory::new(pos), 0);
+    let exp_move = Move::new(Square::E4, Square::D5, MoveType::CAPTURE);
+    assert_eq!(exp_move, actual.best_move());
+    assert_eq!(&[exp_move], actual.principal_variation().as_slice());
+    assert!(actual.score() > 0);
+
+    // In check, the evasions are searched
+    let pos = Fen::str_to_pos("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1").unwrap();
+    let actual = tester.search(PositionHistory::new(pos), 0);
+    assert_eq!(
+        Move::new(Square::E1, Square::D2, MoveType::CAPTURE),
+        actual.best_move()
+    );
+
+    // Quiet checks are searched in the first ply
+    let pos = Fen::str_to_pos("k7/8/1K6/8/8/8/8/7R w - - 0 1").unwrap();
+    let actual = tester.search(PositionHistory::new(pos), 0);
+    let exp_move = Move::new(Square::H1, Square::H8, MoveType::QUIET);
+    assert_eq!(exp_move, actual.best_move());
+    assert_eq!(WHITE_WIN - 1, actual.score());
+
+    // Checkmate and stalemate
+    let pos =
+        Fen::str_to_pos("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+    let actual = tester.search(PositionHistory::new(pos), 0);
+    assert_eq!(BLACK_WIN, actual.score());
+    assert_eq!(Move::NULL, actual.best_move());
+    let pos = Fen::str_to_pos("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+    let actual = tester.search(PositionHistory::new(pos), 0);
+    assert_eq!(EQ_POSITION, actual.score());
+}
+
+fn quiescence_evasion_mates(search_algo: impl Search + Send + 'static) {
+    let mut tester = SearchTester::new(search_algo);
+
+    // 1. Qxh7+ Kf8 2. Qxg7#. Black is up material, but can't stand pat in
+    // check. The only evasion leads to a mate by a capture.
+    let pos = Fen::str_to_pos("r3q1k1/4bppp/8/7N/8/8/2Q2PPP/1B4K1 w - - 0 1").unwrap();
+    let actual = tester.search(PositionHistory::new(pos), 0);
+    assert_eq!(
+        Move::new(Square::C2, Square::H7, MoveType::CAPTURE),
+        actual.best_move()
+    );
+    assert_eq!(WHITE_WIN - 3, actual.score());
+
+    // The same line after 1. Qxh7+, with the mate at the correct ply
+    let pos = Fen::str_to_pos("r3q1k1/4bppQ/8/7N/8/8/5PPP/1B4K1 b - - 0 1").unwrap();
+    for depth in 0..=1 {
+        let actual = tester.search(PositionHistory::new(pos.clone()), depth);
+        assert_eq!(
+            Move::new(Square::G8, Square::F8, MoveType::QUIET),
+            actual.best_move()
+        );
+        assert_eq!(WHITE_WIN - 2, actual.score());
+    }
+}
+
+fn selective_depth(search_algo: impl Search + Send + 'static) {
+    let mut tester = SearchTester::new(search_algo);
+
+    // Quiescence search reaches beyond the nominal depth if there are captures
+    let pos_history = PositionHistory::new(
+        Fen::str_to_pos("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap(),
+    );
+    for depth in 1..=3 {
+        let actual = tester.search(pos_history.clone(), depth);
+        assert_eq!(depth, actual.depth());
+        assert!(actual.selective_depth() > depth);
+    }
+
+    // Without any captures, the selective depth equals the search depth
+    let pos_history = PositionHistory::new(Position::initial());
+    let actual = tester.search(pos_history, 1);
+    assert_eq!(1, actual.selective_depth());
+}
+
 fn pv_valid_after_hash_table_hit_depth_1(search_algo: impl Search + Send + 'static) {
     let pos = Position::initial();
     let depth = 1;
@@ -253,6 +359,18 @@ fn pv_valid_after_hash_table_hit_depth_greater_than_1(search_algo: impl Search +
     );
 }
 
+fn pv_valid_with_multiple_threads(search_algo: impl Search + Send + 'static, depth: usize) {
+    let mut tester = SearchTester::new(search_algo);
+    for fen in BENCH_FENS.iter().take(8) {
+        let pos_history = PositionHistory::new(Fen::str_to_pos(fen).unwrap());
+        let res = tester.search(pos_history.clone(), depth);
+        assert_eq!(depth, res.depth());
+        let mut pv = res.principal_variation().clone();
+        assert_eq!(Some(&res.best_move()), pv.first());
+        assert!(check_moves_valid(&mut pos_history.clone(), &mut pv));
+    }
+}
+
 fn check_moves_valid(pos_history: &mut PositionHistory, pv: &mut MoveList) -> bool {
     if pv.is_empty() {
         return true;
@@ -268,6 +386,356 @@ fn check_moves_valid(pos_history: &mut PositionHistory, pv: &mut MoveList) -> bo
     }
 }
 
+fn aspiration_fail_reports_bound(mut search_algo: impl Search, depth: usize) {
+    let (_command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    let mut fail_count = 0;
+    for fen in &BENCH_FENS[..10] {
+        let pos_history = PositionHistory::new(Fen::str_to_pos(fen).unwrap());
+        search_algo.clear_hash_table();
+        search_algo.search(
+            pos_history,
+            SearchOptions {
+                depth: Some(depth),
+                ..Default::default()
+            },
+            &command_receiver,
+            &info_sender,
+        );
+        let mut failed_depth = None;
+        for info in info_receiver.try_iter() {
+            match info {
+                SearchInfo::AspirationFail(res) => {
+                    assert_ne!(ScoreBound::Exact, res.score_bound());
+                    assert!(failed_depth.is_none() || failed_depth == Some(res.depth()));
+                    failed_depth = Some(res.depth());
+                    fail_count += 1;
+                }
+                SearchInfo::DepthFinished(res) => {
+                    // The re-search of a failed depth must end with an exact score
+                    assert_eq!(ScoreBound::Exact, res.score_bound());
+                    assert!(failed_depth.is_none() || failed_depth == Some(res.depth()));
+                    failed_depth = None;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(None, failed_depth);
+    }
+    assert!(fail_count > 0);
+}
+
+fn refutations_in_analyse_mode(mut search_algo: impl Search, depth: usize) {
+    let (_command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    let mut refutation_count = 0;
+    for fen in &BENCH_FENS[..5] {
+        let pos = Fen::str_to_pos(fen).unwrap();
+        let mut root_moves = MoveList::new();
+        MoveGenerator::generate_moves(&mut root_moves, &pos);
+        for analyse_mode in [false, true] {
+            search_algo.clear_hash_table();
+            search_algo.search(
+                PositionHistory::new(pos.clone()),
+                SearchOptions {
+                    depth: Some(depth),
+                    analyse_mode,
+                    ..Default::default()
+                },
+                &command_receiver,
+                &info_sender,
+            );
+            for info in info_receiver.try_iter() {
+                if let SearchInfo::DepthFinished(res) = info {
+                    if !analyse_mode {
+                        assert!(res.refutations().is_empty());
+                    }
+                    for refutation in res.refutations() {
+                        // A root move that isn't the best move, followed by a
+                        // legal reply
+                        assert_eq!(2, refutation.len());
+                        assert!(root_moves.contains(&refutation[0]));
+                        assert_ne!(res.best_move(), refutation[0]);
+                        let mut pos_history = PositionHistory::new(pos.clone());
+                        pos_history.do_move(refutation[0]);
+                        let mut replies = MoveList::new();
+                        MoveGenerator::generate_moves(&mut replies, pos_history.current_pos());
+                        assert!(replies.contains(&refutation[1]));
+                        refutation_count += 1;
+                    }
+                }
+            }
+        }
+    }
+    assert!(refutation_count > 0);
+}
+
+fn full_pv_in_analyse_mode(mut search_algo: impl Search, depth: usize) {
+    let (_command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    let mut longest_fail_high_pv = [0; 2];
+    for fen in &BENCH_FENS[..10] {
+        let pos = Fen::str_to_pos(fen).unwrap();
+        for analyse_mode in [false, true] {
+            search_algo.clear_hash_table();
+            search_algo.search(
+                PositionHistory::new(pos.clone()),
+                SearchOptions {
+                    depth: Some(depth),
+                    analyse_mode,
+                    ..Default::default()
+                },
+                &command_receiver,
+                &info_sender,
+            );
+            for info in info_receiver.try_iter() {
+                if let SearchInfo::AspirationFail(res) = info {
+                    // The PV must be a sequence of legal moves
+                    let pv = res.principal_variation();
+                    let mut pos_history = PositionHistory::new(pos.clone());
+                    for m in pv.iter().take_while(|m| **m != Move::NULL) {
+                        let mut legal_moves = MoveList::new();
+                        MoveGenerator::generate_moves(&mut legal_moves, pos_history.current_pos());
+                        assert!(legal_moves.contains(m));
+                        pos_history.do_move(*m);
+                    }
+                    assert!(pv.len() <= res.depth());
+                    let rel_bound = match pos.side_to_move() {
+                        Side::White => res.score_bound(),
+                        Side::Black => -res.score_bound(),
+                    };
+                    if rel_bound == ScoreBound::LowerBound {
+                        let longest = &mut longest_fail_high_pv[analyse_mode as usize];
+                        *longest = cmp::max(*longest, pv.len());
+                    }
+                }
+            }
+        }
+    }
+    // Without analyse mode, the PV of a fail high is only the move that failed
+    // high
+    assert_eq!(1, longest_fail_high_pv[0]);
+    assert!(longest_fail_high_pv[1] > 1);
+}
+
+fn forced_move_searched_in_analyse_mode(mut search_algo: impl Search) {
+    let (_command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    // Kxb2 is the only legal move
+    let pos = Fen::str_to_pos("k7/8/8/8/8/8/1q6/K7 w - - 0 1").unwrap();
+    for analyse_mode in [false, true] {
+        search_algo.search(
+            PositionHistory::new(pos.clone()),
+            SearchOptions {
+                white_time: Some(Duration::from_millis(1000)),
+                black_time: Some(Duration::from_millis(1000)),
+                analyse_mode,
+                ..Default::default()
+            },
+            &command_receiver,
+            &info_sender,
+        );
+        let infos: Vec<SearchInfo> = info_receiver.try_iter().collect();
+        let depths_finished = infos
+            .iter()
+            .filter(|info| matches!(info, SearchInfo::DepthFinished(_)))
+            .count();
+        // Without analyse mode, the forced move is played without searching
+        assert_eq!(analyse_mode, depths_finished > 0);
+        assert!(matches!(
+            infos.last(),
+            Some(SearchInfo::Stopped(m)) if *m == Move::new(Square::A1, Square::B2, MoveType::CAPTURE)
+        ));
+    }
+}
+
+fn depth_one_completes_without_time(mut search_algo: impl Search) {
+    let (_command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    let time_controls = [
+        SearchOptions {
+            white_time: Some(Duration::from_millis(1)),
+            black_time: Some(Duration::from_millis(1)),
+            move_overhead: Duration::from_millis(1000),
+            ..Default::default()
+        },
+        SearchOptions {
+            movetime: Some(Duration::from_millis(1)),
+            ..Default::default()
+        },
+    ];
+    for fen in BENCH_FENS {
+        let pos = Fen::str_to_pos(fen).unwrap();
+        let mut legal_moves = MoveList::new();
+        MoveGenerator::generate_moves(&mut legal_moves, &pos);
+        // Forced moves are played without searching
+        if legal_moves.len() < 2 {
+            continue;
+        }
+        for search_options in time_controls.iter().cloned() {
+            search_algo.search(
+                PositionHistory::new(pos.clone()),
+                search_options,
+                &command_receiver,
+                &info_sender,
+            );
+            let infos: Vec<SearchInfo> = info_receiver.try_iter().collect();
+            assert!(infos
+                .iter()
+                .any(|info| matches!(info, SearchInfo::DepthFinished(res) if res.depth() == 1)));
+            assert!(matches!(
+                infos.last(),
+                Some(SearchInfo::Stopped(m)) if legal_moves.contains(m)
+            ));
+        }
+    }
+}
+
+fn commands_after_stop_during_depth_one(mut search_algo: impl Search) {
+    let (command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    let pos_history = PositionHistory::new(Position::initial());
+    let search_command =
+        || SearchCommand::Search(Box::new((pos_history.clone(), SearchOptions::default())));
+    // The commands are received during depth 1, which is completed anyway.
+    // A command after the stop is left in the channel. A new search or
+    // terminate command without a stop ends the search and is handed back to
+    // the caller.
+    for (with_stop, next_command) in [
+        (true, search_command()),
+        (true, SearchCommand::Terminate),
+        (false, search_command()),
+        (false, SearchCommand::Terminate),
+    ] {
+        let is_search = matches!(next_command, SearchCommand::Search(_));
+        if with_stop {
+            command_sender.send(SearchCommand::Stop).unwrap();
+        }
+        command_sender.send(next_command).unwrap();
+        let returned = search_algo.search(
+            pos_history.clone(),
+            SearchOptions::default(),
+            &command_receiver,
+            &info_sender,
+        );
+        let next = match with_stop {
+            true => {
+                assert!(returned.is_none());
+                command_receiver.try_recv().ok()
+            }
+            false => returned,
+        };
+        match next {
+            Some(SearchCommand::Search(_)) => assert!(is_search),
+            Some(SearchCommand::Terminate) => assert!(!is_search),
+            unexp => panic!("Expected the command after the stop, got {unexp:?}"),
+        }
+        assert!(command_receiver.is_empty());
+        let infos: Vec<SearchInfo> = info_receiver.try_iter().collect();
+        assert!(infos
+            .iter()
+            .all(|info| !matches!(info, SearchInfo::DepthFinished(res) if res.depth() > 1)));
+        assert!(matches!(infos.last(), Some(SearchInfo::Stopped(m)) if *m != Move::NULL));
+    }
+}
+
+fn randomize_equal_moves(mut search_algo: impl Search) {
+    let (_command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    // The tables are cleared, so that the searches are reproducible
+    let mut search = |pos: &Position, options| {
+        search_algo.clear_hash_table();
+        search_algo.search(
+            PositionHistory::new(pos.clone()),
+            options,
+            &command_receiver,
+            &info_sender,
+        );
+        match info_receiver.try_iter().last() {
+            Some(SearchInfo::Stopped(m)) => m,
+            unexp => panic!("Expected Some(SearchInfo::Stopped(_)), got {:?}", unexp),
+        }
+    };
+    let mut best_move = |pos: &Position, seed| {
+        search(
+            pos,
+            SearchOptions {
+                depth: Some(5),
+                equal_moves_seed: Some(seed),
+                ..Default::default()
+            },
+        )
+    };
+
+    // Only the clearly best move is chosen
+    let pos = Fen::str_to_pos("4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1").unwrap();
+    for seed in 0..8 {
+        assert_eq!(
+            Move::new(Square::E4, Square::D5, MoveType::CAPTURE),
+            best_move(&pos, seed)
+        );
+    }
+
+    // In the initial position, some moves are about equal. The choice is
+    // reproducible.
+    let pos = Position::initial();
+    let chosen = (0..8).map(|seed| best_move(&pos, seed)).collect::<Vec<_>>();
+    assert!(chosen.iter().any(|&m| m != chosen[0]));
+    for (seed, &m) in chosen.iter().enumerate() {
+        assert_eq!(m, best_move(&pos, seed as u64));
+    }
+
+    // The verification searches obey the node limit. The main search uses up
+    // all nodes, so no other move can be verified.
+    let nodes_limited = |seed| SearchOptions {
+        nodes: Some(20_000),
+        equal_moves_seed: seed,
+        ..Default::default()
+    };
+    let unrandomized = search(&pos, nodes_limited(None));
+    for seed in 0..8 {
+        assert_eq!(unrandomized, search(&pos, nodes_limited(Some(seed))));
+    }
+}
+
+fn current_move_after_min_time(search_algo: impl Search + Send + 'static) {
+    let tester = SearchTester::new(search_algo);
+    let pos = Fen::str_to_pos("8/8/4k3/8/8/4K3/4P3/8 w - - 0 1").unwrap();
+    let mut root_moves = MoveList::new();
+    MoveGenerator::generate_moves(&mut root_moves, &pos);
+    let start = Instant::now();
+    tester.searcher.search(
+        PositionHistory::new(pos),
+        SearchOptions {
+            infinite: true,
+            ..Default::default()
+        },
+    );
+    // No current move info in the first seconds of the search
+    let current_move = loop {
+        match tester.result_receiver.recv_timeout(TIMEOUT_PER_TEST) {
+            Ok(SearchInfo::CurrentMove(current_move)) => break current_move,
+            Ok(SearchInfo::Stopped(_)) | Err(_) => panic!("Expected current move info"),
+            Ok(_) => {}
+        }
+    };
+    assert!(start.elapsed() >= Duration::from_secs(3));
+    assert!(current_move.depth() > 1);
+    assert!(root_moves.contains(&current_move.current_move()));
+    // The number counts the moves in the order they are searched
+    assert!((1..=root_moves.len()).contains(&current_move.move_number()));
+    tester.searcher.stop();
+    loop {
+        if let SearchInfo::Stopped(_) = tester
+            .result_receiver
+            .recv_timeout(TIMEOUT_PER_TEST)
+            .unwrap()
+        {
+            break;
+        }
+    }
+}
+
 fn count_searched_nodes(search_algo: impl Search + Send + 'static, pos: Position, depth: usize) {
     let mut tester = SearchTester::new(search_algo);
     let pos_history = PositionHistory::new(pos.clone());
@@ -336,6 +804,80 @@ fn avoid_threefold_repetition_in_winning_position(search_algo: impl Search + Sen
     assert_ne!(b2c2, res.best_move());
 }
 
+// A repetition draw depends on the path to the position. It must not be
+// reused from the hash table when the position is reached without the
+// repetition.
+fn repetition_draw_not_reused_from_hash_table(search_algo: impl Search + Send + 'static) {
+    // Clearly winning for black
+    let fen = "8/r2p1k2/1pp1p1p1/4Pp2/5P2/3R4/3P4/4K3 b - - 5 46";
+    let pos = Fen::str_to_pos(fen).unwrap();
+    let mut pos_history = PositionHistory::new(pos);
+
+    let f7e7 = Move::new(Square::F7, Square::E7, MoveType::QUIET);
+    let d3g3 = Move::new(Square::D3, Square::G3, MoveType::QUIET);
+    let e7f7 = Move::new(Square::E7, Square::F7, MoveType::QUIET);
+    let g3d3 = Move::new(Square::G3, Square::D3, MoveType::QUIET);
+
+    for m in [f7e7, d3g3, e7f7, g3d3, f7e7, d3g3, e7f7] {
+        pos_history.do_move(m);
+    }
+    let pos_without_history = PositionHistory::new(pos_history.current_pos().clone());
+
+    let mut tester = SearchTester::new(search_algo);
+    let res = tester.search(pos_history, 4);
+    assert_eq!(EQ_POSITION, res.score());
+
+    // Same position, but there is no repetition
+    for depth in 1..=2 {
+        let res = tester.search(pos_without_history.clone(), depth);
+        assert!(
+            eval::score::is_centipawns(res.score()) && res.score() < -100,
+            "Expected a clear advantage for black at depth {depth}, got {}",
+            res.score()
+        );
+    }
+}
+
+// Repeating a position from inside the search tree once is enough for a draw
+fn twofold_repetition_in_search_tree(search_algo: impl Search + Send + 'static) {
+    // White can only escape with a perpetual check (Qf8+ Kh7 Qf7+ Kh8 Qf8+)
+    let fen = "7k/5Q2/6pp/6q1/8/3rr3/8/7K w - - 0 1";
+    let pos = Fen::str_to_pos(fen).unwrap();
+    let mut tester = SearchTester::new(search_algo);
+    for depth in 5..=6 {
+        let res = tester.search(PositionHistory::new(pos.clone()), depth);
+        assert_eq!(EQ_POSITION, res.score(), "Depth {depth}");
+    }
+}
+
+// A position that occurred once before the root must be repeated twice
+// more to draw
+fn twofold_repetition_of_game_history(search_algo: impl Search + Send + 'static) {
+    // Clearly winning for black
+    let fen = "8/r2p1k2/1pp1p1p1/4Pp2/5P2/3R4/3P4/4K3 b - - 5 46";
+    let pos = Fen::str_to_pos(fen).unwrap();
+    let mut pos_history = PositionHistory::new(pos);
+
+    let f7e7 = Move::new(Square::F7, Square::E7, MoveType::QUIET);
+    let d3g3 = Move::new(Square::D3, Square::G3, MoveType::QUIET);
+    let e7f7 = Move::new(Square::E7, Square::F7, MoveType::QUIET);
+
+    // Rg3d3 repeats the position before f7e7
+    for m in [f7e7, d3g3, e7f7] {
+        pos_history.do_move(m);
+    }
+
+    let mut tester = SearchTester::new(search_algo);
+    for depth in 1..=3 {
+        let res = tester.search(pos_history.clone(), depth);
+        assert!(
+            eval::score::is_centipawns(res.score()) && res.score() < -100,
+            "Expected a clear advantage for black at depth {depth}, got {}",
+            res.score()
+        );
+    }
+}
+
 fn fifty_move_rule(search_algo: impl Search + Send + 'static) {
     let depth = 2;
     let mut tester = SearchTester::new(search_algo);
@@ -359,6 +901,108 @@ fn fifty_move_rule(search_algo: impl Search + Send + 'static) {
     assert!(eval::score::is_black_mating(res.score()));
 }
 
+// White is a rook up, but the pawns are blocked and there are no captures,
+// so the fifty-move counter can't be reset. The closer the draw, the smaller
+// the advantage.
+fn fifty_move_rule_scales_score(search_algo: impl Search + Send + 'static) {
+    let depth = 3;
+    let mut tester = SearchTester::new(search_algo);
+    let mut prev_score = None;
+    let mut first_score = None;
+    for plies in [0, 60, 90] {
+        let fen = format!("8/8/4k3/p1p1p3/P1P1P3/4K3/8/7R w - - {plies} 60");
+        let pos = Fen::str_to_pos(&fen).unwrap();
+        tester.clear_hash_table();
+        let res = tester.search(PositionHistory::new(pos), depth);
+        assert!(res.score() > EQ_POSITION);
+        if let Some(prev) = prev_score {
+            assert!(
+                res.score() < prev,
+                "Expected a smaller score than {prev} after {plies} plies, got {}",
+                res.score()
+            );
+        }
+        prev_score = Some(res.score());
+        first_score = first_score.or(prev_score);
+    }
+    // The scaling is gentle, the rook is still worth a lot
+    assert!(2 * prev_score.unwrap() > first_score.unwrap());
+}
+
+fn contempt_biases_draw_scores(mut search_algo: impl Search) {
+    let (_command_sender, command_receiver) = unbounded();
+    let (info_sender, info_receiver) = unbounded();
+    let search = |search_algo: &mut dyn Search, fen: &str, contempt: Score| {
+        search_algo.clear_hash_table();
+        search_algo.search(
+            PositionHistory::new(Fen::str_to_pos(fen).unwrap()),
+            SearchOptions {
+                depth: Some(2),
+                contempt,
+                ..Default::default()
+            },
+            &command_receiver,
+            &info_sender,
+        );
+        info_receiver
+            .try_iter()
+            .filter_map(|info| match info {
+                SearchInfo::DepthFinished(res) => Some(res),
+                _ => None,
+            })
+            .last()
+            .unwrap()
+    };
+
+    // Every move draws by the fifty-move rule. The draw is worse for the side
+    // to move at the root by the contempt, no matter which side that is. The
+    // contempt is scaled down by the game phase (only a rook is left).
+    let fen_draw_white = "8/8/8/8/8/4k1r1/8/5K2 w - - 99 1";
+    let fen_draw_black = "5k2/8/4K1R1/8/8/8/8/8 b - - 99 1";
+    let phase = GamePhase::new(&Fen::str_to_pos(fen_draw_white).unwrap()).game_phase_clamped();
+    assert_eq!(2, phase);
+    let phase_percent = CONTEMPT_ENDGAME_PERCENT
+        + (100 - CONTEMPT_ENDGAME_PERCENT) * phase as i32 / GamePhase::MAX as i32;
+    for contempt in [-50, 0, 30] {
+        let scaled = (contempt as i32 * phase_percent / 100) as Score;
+        assert_eq!(
+            -scaled,
+            search(&mut search_algo, fen_draw_white, contempt).score()
+        );
+        // Scores are from white's point of view
+        assert_eq!(
+            scaled,
+            search(&mut search_algo, fen_draw_black, contempt).score()
+        );
+    }
+
+    // Mate scores are not affected
+    let fen_mate = "8/7k/7P/8/8/8/6Q1/6K1 w - - 0 1";
+    assert_eq!(
+        search(&mut search_algo, fen_mate, 0).score(),
+        search(&mut search_algo, fen_mate, 100).score()
+    );
+    assert!(eval::score::is_white_mating(
+        search(&mut search_algo, fen_mate, 100).score()
+    ));
+
+    // Without the endgame scaling, the full contempt applies
+    search_algo.set_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        contempt_endgame_percent: Some(100),
+        ..Default::default()
+    }));
+    for contempt in [-50, 0, 30] {
+        assert_eq!(
+            -contempt,
+            search(&mut search_algo, fen_draw_white, contempt).score()
+        );
+        assert_eq!(
+            contempt,
+            search(&mut search_algo, fen_draw_black, contempt).score()
+        );
+    }
+}
+
 fn underpromotions(search_algo: impl Search + Send + 'static) {
     let mut tester = SearchTester::new(search_algo);
 
@@ -560,6 +1204,55 @@ fn mate_in_x_capture_and_check(search_algo: impl Search + Send + 'static) {
     }
 }
 
+// The mating moves hang the queen. Quiet moves which lose material are
+// pruned at low depth, unless they give check.
+fn quiet_sacrifice_not_pruned(search_algo: impl Search + Send + 'static) {
+    let mut tester = SearchTester::new(search_algo);
+    let test_positions = [
+        // Smothered mate: Kh8 Qg8+ Rxg8 Nf7#
+        (
+            "5rk1/6pp/4Q2N/8/8/8/6PP/6K1 b - - 0 1",
+            ScoreVariant::Mate(Side::White, 2),
+        ),
+    ];
+
+    for (fen, exp_score) in test_positions {
+        for depth in 4..=6 {
+            tester.clear_hash_table();
+            let pos = Fen::str_to_pos(fen).unwrap();
+            let pos_history = PositionHistory::new(pos.clone());
+            let res = tester.search(pos_history, depth);
+            assert_eq!(exp_score, ScoreVariant::from(res.score()), "{fen} {depth}");
+        }
+    }
+}
+
+// Mate scores are stored relative to the position in the hash table. A hash
+// hit at a different ply must report the exact mate distance.
+fn mate_distance_from_hash_table_at_different_ply(search_algo: impl Search + Send + 'static) {
+    let mut tester = SearchTester::new(search_algo);
+    // Kh1g1 is the only legal move
+    let fen_before = "8/8/8/8/8/5rk1/8/7K w - - 0 1";
+    let fen_after = "8/8/8/8/8/5rk1/8/6K1 b - - 1 1";
+    let exp_score = ScoreVariant::Mate(Side::Black, -2);
+    let pos_before = PositionHistory::new(Fen::str_to_pos(fen_before).unwrap());
+    let pos_after = PositionHistory::new(Fen::str_to_pos(fen_after).unwrap());
+
+    // Stored at the root, probed at ply 1. Depth 1 alone doesn't find the
+    // mate.
+    let res = tester.search(pos_after.clone(), 4);
+    assert_eq!(exp_score, ScoreVariant::from(res.score()));
+    let res = tester.search(pos_before.clone(), 1);
+    assert_eq!(exp_score, ScoreVariant::from(res.score()));
+
+    // Stored at ply 1, probed at the root
+    tester.clear_hash_table();
+    let res = tester.search(pos_before, 5);
+    assert_eq!(exp_score, ScoreVariant::from(res.score()));
+    let res = tester.search(pos_after, 1);
+    assert_eq!(exp_score, ScoreVariant::from(res.score()));
+}
+
 fn mate_in_x_various_depths(search_algo: impl Search + Send + 'static) {
     let mut tester = SearchTester::new(search_algo);
     let test_positions = [
@@ -664,6 +1357,72 @@ fn alpha_beta_search_quiescence() {
     search_quiescence(alpha_beta);
 }
 
+#[test]
+fn alpha_beta_depth_zero() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    depth_zero(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_quiescence_evasion_mates() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    quiescence_evasion_mates(alpha_beta);
+    // The mates don't depend on the evaluation, even if it favors either side
+    // by far
+    for score in [-1000, 0, 1000] {
+        let alpha_beta = AlphaBeta::new(Box::new(ConstantEval(score)), TABLE_SIZE);
+        quiescence_evasion_mates(alpha_beta);
+    }
+}
+
+#[test]
+fn alpha_beta_aspiration_fail_reports_bound() {
+    let depth = 6;
+    aspiration_fail_reports_bound(AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE), depth);
+}
+
+#[test]
+fn alpha_beta_refutations_in_analyse_mode() {
+    let depth = 5;
+    refutations_in_analyse_mode(AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE), depth);
+}
+
+#[test]
+fn alpha_beta_full_pv_in_analyse_mode() {
+    let depth = 5;
+    full_pv_in_analyse_mode(AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE), depth);
+}
+
+#[test]
+fn alpha_beta_depth_one_completes_without_time() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    depth_one_completes_without_time(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_commands_after_stop_during_depth_one() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    commands_after_stop_during_depth_one(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_randomize_equal_moves() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    randomize_equal_moves(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_forced_move_searched_in_analyse_mode() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    forced_move_searched_in_analyse_mode(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_selective_depth() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    selective_depth(alpha_beta);
+}
+
 #[test]
 fn alpha_beta_pv_valid_after_hash_table_hit_depth_1() {
     let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
@@ -677,6 +1436,11 @@ fn alpha_beta_pv_valid_after_hash_table_hit_depth_greater_than_1() {
     pv_valid_after_hash_table_hit_depth_greater_than_1(alpha_beta);
 }
 
+#[test]
+fn alpha_beta_current_move_after_min_time() {
+    current_move_after_min_time(AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE));
+}
+
 #[test]
 #[ignore = "benchmark"]
 fn alpha_beta_count_searched_nodes_middlegame_position() {
@@ -699,12 +1463,42 @@ fn alpha_beta_avoid_threefold_repetition_in_winning_position() {
     avoid_threefold_repetition_in_winning_position(alpha_beta);
 }
 
+#[test]
+fn alpha_beta_repetition_draw_not_reused_from_hash_table() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    repetition_draw_not_reused_from_hash_table(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_twofold_repetition_in_search_tree() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    twofold_repetition_in_search_tree(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_twofold_repetition_of_game_history() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    twofold_repetition_of_game_history(alpha_beta);
+}
+
 #[test]
 fn alpha_beta_fifty_move_rule() {
     let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
     fifty_move_rule(alpha_beta);
 }
 
+#[test]
+fn alpha_beta_fifty_move_rule_scales_score() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    fifty_move_rule_scales_score(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_contempt_biases_draw_scores() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    contempt_biases_draw_scores(alpha_beta);
+}
+
 #[test]
 #[ignore]
 fn alpha_beta_underpromotions() {
@@ -725,12 +1519,24 @@ fn alpha_beta_mate_in_x_no_capture_no_check() {
     mate_in_x_no_capture_no_check(alpha_beta);
 }
 
+#[test]
+fn alpha_beta_mate_distance_from_hash_table_at_different_ply() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    mate_distance_from_hash_table_at_different_ply(alpha_beta);
+}
+
 #[test]
 fn alpha_beta_mate_in_x_capture_and_check() {
     let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
     mate_in_x_capture_and_check(alpha_beta);
 }
 
+#[test]
+fn alpha_beta_quiet_sacrifice_not_pruned() {
+    let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    quiet_sacrifice_not_pruned(alpha_beta);
+}
+
 #[test]
 #[ignore]
 fn alpha_beta_mate_in_x_various_depths() {
@@ -744,3 +1550,68 @@ fn alpha_beta_pv_truncated_after_mate() {
     let alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
     pv_truncated_after_mate(alpha_beta);
 }
+
+#[test]
+fn alpha_beta_mate_in_x_multiple_threads() {
+    let mut alpha_beta = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    alpha_beta.set_threads(4);
+    mate_in_x_no_capture_no_check(alpha_beta);
+}
+
+#[test]
+fn alpha_beta_thread_local_history_multiple_threads() {
+    let mut alpha_beta = AlphaBeta::new(Box::new(evaluator()), 64 * 1024);
+    alpha_beta.set_threads(4);
+    alpha_beta.set_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        shared_history: Some(false),
+        ..Default::default()
+    }));
+    assert_eq!(4, alpha_beta.threads());
+    pv_valid_with_multiple_threads(alpha_beta, 6);
+}
+
+// Stress test for the shared transposition table. The table is small, so the
+// threads keep overwriting each other's entries. Run it with a thread sanitizer
+// (e.g. RUSTFLAGS="-Zsanitizer=thread") to detect data races.
+#[test]
+fn alpha_beta_pv_valid_with_multiple_threads() {
+    let mut alpha_beta = AlphaBeta::new(Box::new(evaluator()), 64 * 1024);
+    alpha_beta.set_threads(8);
+    assert_eq!(8, alpha_beta.threads());
+    pv_valid_with_multiple_threads(alpha_beta, 6);
+}
+
+#[test]
+fn bench_is_reproducible() {
+    let depth = 3;
+    let mut search_algo = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    let mut nodes_per_pos = Vec::new();
+    let (nodes, _) = bench::bench(&mut search_algo, depth, |_fen, res| {
+        assert_eq!(depth, res.depth());
+        nodes_per_pos.push(res.nodes());
+    });
+    assert_eq!(BENCH_FENS.len(), nodes_per_pos.len());
+    assert_eq!(nodes, nodes_per_pos.iter().sum::<u64>());
+
+    // The search state of the previous run must not change the node count
+    let (nodes_again, _) = bench::bench(&mut search_algo, depth, |_, _| {});
+    assert_eq!(nodes, nodes_again);
+}
+
+#[test]
+fn speedtest_counts_completed_searches() {
+    // Shallow enough to complete several searches in a debug build
+    let depth = 3;
+    let mut search_algo = AlphaBeta::new(Box::new(evaluator()), TABLE_SIZE);
+    let (command_sender, command_receiver) = unbounded();
+    let duration = Duration::from_millis(3000);
+    let (nodes, time) = bench::speedtest(&mut search_algo, depth, duration, &command_receiver);
+    assert!(nodes > 0);
+    assert!(time > Duration::ZERO && time <= duration);
+
+    // A stop command ends the speedtest. The interrupted search isn't counted.
+    command_sender.send(SearchCommand::Stop).unwrap();
+    let (nodes, time) = bench::speedtest(&mut search_algo, depth, duration, &command_receiver);
+    assert_eq!(0, nodes);
+    assert_eq!(Duration::ZERO, time);
+}
@@ -1,7 +1,10 @@
 use std::fmt::Display;
 
 use eval::{
-    params::{BISHOP_MOB_LEN, DISTANCE_LEN, KNIGHT_MOB_LEN, MOB_LEN, QUEEN_MOB_LEN, ROOK_MOB_LEN},
+    params::{
+        BISHOP_MOB_LEN, DISTANCE_LEN, KING_DANGER_LEN, KNIGHT_MOB_LEN, MOB_LEN, PASSED_PAWN_LEN,
+        QUEEN_MOB_LEN, ROOK_MOB_LEN,
+    },
     score_pair::ScorePair,
     Score,
 };
@@ -9,9 +12,14 @@ use eval::{
 use crate::{
     feature_evaluator::WeightVector,
     position_features::{
-        PST_SIZE, START_IDX_BACKWARD_PAWN, START_IDX_BISHOP_PAIR, START_IDX_DOUBLED_PAWN,
-        START_IDX_ISOLATED_PAWN, START_IDX_KING_TROPISM, START_IDX_MOBILITY, START_IDX_PASSED_PAWN,
-        START_IDX_PST, START_IDX_TEMPO,
+        PST_SIZE, START_IDX_BACKWARD_PAWN, START_IDX_BISHOP_OUTPOST, START_IDX_BISHOP_PAIR,
+        START_IDX_DOUBLED_PAWN, START_IDX_ISOLATED_PAWN, START_IDX_KING_DANGER,
+        START_IDX_KING_TROPISM, START_IDX_KNIGHT_OUTPOST, START_IDX_MOBILITY,
+        START_IDX_PASSED_PAWN, START_IDX_PASSED_PAWN_CONNECTED, START_IDX_PASSED_PAWN_FREE_PATH,
+        START_IDX_PASSED_PAWN_PROTECTED, START_IDX_PAWN_SHIELD_ADVANCED,
+        START_IDX_PAWN_SHIELD_MISSING, START_IDX_PAWN_SHIELD_OPEN_FILE, START_IDX_PST,
+        START_IDX_ROOK_OPEN_FILE, START_IDX_ROOK_SEMI_OPEN_FILE, START_IDX_ROOK_SEVENTH,
+        START_IDX_TEMPO,
     },
 };
 
@@ -24,12 +32,23 @@ pub struct EvalParams {
     pst_queen: [ScorePair; 32],
     pst_king: [ScorePair; 32],
     tempo: ScorePair,
-    passed_pawn: ScorePair,
+    passed_pawn: [ScorePair; PASSED_PAWN_LEN],
+    passed_pawn_protected: ScorePair,
+    passed_pawn_connected: ScorePair,
+    passed_pawn_free_path: ScorePair,
     isolated_pawn: ScorePair,
     backward_pawn: ScorePair,
     doubled_pawn: ScorePair,
+    pawn_shield_missing: ScorePair,
+    pawn_shield_advanced: ScorePair,
+    pawn_shield_open_file: ScorePair,
     mobility: [ScorePair; MOB_LEN],
     bishop_pair: ScorePair,
+    rook_open_file: ScorePair,
+    rook_semi_open_file: ScorePair,
+    rook_seventh: ScorePair,
+    knight_outpost: ScorePair,
+    bishop_outpost: ScorePair,
     distance_friendly_pawn: [ScorePair; DISTANCE_LEN],
     distance_enemy_pawn: [ScorePair; DISTANCE_LEN],
     distance_friendly_knight: [ScorePair; DISTANCE_LEN],
@@ -42,6 +61,7 @@ pub struct EvalParams {
     distance_enemy_queen: [ScorePair; DISTANCE_LEN],
     distance_friendly_king: [ScorePair; DISTANCE_LEN],
     distance_enemy_king: [ScorePair; DISTANCE_LEN],
+    king_danger: [ScorePair; KING_DANGER_LEN],
 }
 
 impl Default for EvalParams {
@@ -54,12 +74,23 @@ impl Default for EvalParams {
             pst_queen: [ScorePair(0, 0); 32],
             pst_king: [ScorePair(0, 0); 32],
             tempo: ScorePair(0, 0),
-            passed_pawn: ScorePair(0, 0),
+            passed_pawn: [ScorePair(0, 0); PASSED_PAWN_LEN],
+            passed_pawn_protected: ScorePair(0, 0),
+            passed_pawn_connected: ScorePair(0, 0),
+            passed_pawn_free_path: ScorePair(0, 0),
             isolated_pawn: ScorePair(0, 0),
             backward_pawn: ScorePair(0, 0),
             doubled_pawn: ScorePair(0, 0),
+            pawn_shield_missing: ScorePair(0, 0),
+            pawn_shield_advanced: ScorePair(0, 0),
+            pawn_shield_open_file: ScorePair(0, 0),
             mobility: [ScorePair(0, 0); MOB_LEN],
             bishop_pair: ScorePair(0, 0),
+            rook_open_file: ScorePair(0, 0),
+            rook_semi_open_file: ScorePair(0, 0),
+            rook_seventh: ScorePair(0, 0),
+            knight_outpost: ScorePair(0, 0),
+            bishop_outpost: ScorePair(0, 0),
             distance_friendly_pawn: [ScorePair(0, 0); DISTANCE_LEN],
             distance_enemy_pawn: [ScorePair(0, 0); DISTANCE_LEN],
             distance_friendly_knight: [ScorePair(0, 0); DISTANCE_LEN],
@@ -72,6 +103,7 @@ impl Default for EvalParams {
             distance_enemy_queen: [ScorePair(0, 0); DISTANCE_LEN],
             distance_friendly_king: [ScorePair(0, 0); DISTANCE_LEN],
             distance_enemy_king: [ScorePair(0, 0); DISTANCE_LEN],
+            king_danger: [ScorePair(0, 0); KING_DANGER_LEN],
         }
     }
 }
@@ -97,16 +129,41 @@ impl From<&WeightVector> for EvalParams {
         }
 
         eval_params.tempo.0 = weights[START_IDX_TEMPO].round() as Score;
-        eval_params.tempo.1 = weights[START_IDX_TEMPO + 1].round() as Score;
 
-        eval_params.passed_pawn.0 = weights[START_IDX_PASSED_PAWN].round() as Score;
-        eval_params.passed_pawn.1 = weights[START_IDX_PASSED_PAWN + 1].round() as Score;
+        for rank in 0..PASSED_PAWN_LEN {
+            let offset = START_IDX_PASSED_PAWN + 2 * rank;
+            eval_params.passed_pawn[rank].0 = weights[offset].round() as Score;
+            eval_params.passed_pawn[rank].1 = weights[offset + 1].round() as Score;
+        }
+        eval_params.passed_pawn_protected.0 =
+            weights[START_IDX_PASSED_PAWN_PROTECTED].round() as Score;
+        eval_params.passed_pawn_protected.1 =
+            weights[START_IDX_PASSED_PAWN_PROTECTED + 1].round() as Score;
+        eval_params.passed_pawn_connected.0 =
+            weights[START_IDX_PASSED_PAWN_CONNECTED].round() as Score;
+        eval_params.passed_pawn_connected.1 =
+            weights[START_IDX_PASSED_PAWN_CONNECTED + 1].round() as Score;
+        eval_params.passed_pawn_free_path.0 =
+            weights[START_IDX_PASSED_PAWN_FREE_PATH].round() as Score;
+        eval_params.passed_pawn_free_path.1 =
+            weights[START_IDX_PASSED_PAWN_FREE_PATH + 1].round() as Score;
         eval_params.isolated_pawn.0 = weights[START_IDX_ISOLATED_PAWN].round() as Score;
         eval_params.isolated_pawn.1 = weights[START_IDX_ISOLATED_PAWN + 1].round() as Score;
         eval_params.backward_pawn.0 = weights[START_IDX_BACKWARD_PAWN].round() as Score;
         eval_params.backward_pawn.1 = weights[START_IDX_BACKWARD_PAWN + 1].round() as Score;
         eval_params.doubled_pawn.0 = weights[START_IDX_DOUBLED_PAWN].round() as Score;
         eval_params.doubled_pawn.1 = weights[START_IDX_DOUBLED_PAWN + 1].round() as Score;
+        eval_params.pawn_shield_missing.0 = weights[START_IDX_PAWN_SHIELD_MISSING].round() as Score;
+        eval_params.pawn_shield_missing.1 =
+            weights[START_IDX_PAWN_SHIELD_MISSING + 1].round() as Score;
+        eval_params.pawn_shield_advanced.0 =
+            weights[START_IDX_PAWN_SHIELD_ADVANCED].round() as Score;
+        eval_params.pawn_shield_advanced.1 =
+            weights[START_IDX_PAWN_SHIELD_ADVANCED + 1].round() as Score;
+        eval_params.pawn_shield_open_file.0 =
+            weights[START_IDX_PAWN_SHIELD_OPEN_FILE].round() as Score;
+        eval_params.pawn_shield_open_file.1 =
+            weights[START_IDX_PAWN_SHIELD_OPEN_FILE + 1].round() as Score;
 
         for idx in 0..MOB_LEN {
             let offset = START_IDX_MOBILITY + 2 * idx;
@@ -117,6 +174,19 @@ impl From<&WeightVector> for EvalParams {
         eval_params.bishop_pair.0 = weights[START_IDX_BISHOP_PAIR].round() as Score;
         eval_params.bishop_pair.1 = weights[START_IDX_BISHOP_PAIR + 1].round() as Score;
 
+        eval_params.rook_open_file.0 = weights[START_IDX_ROOK_OPEN_FILE].round() as Score;
+        eval_params.rook_open_file.1 = weights[START_IDX_ROOK_OPEN_FILE + 1].round() as Score;
+        eval_params.rook_semi_open_file.0 = weights[START_IDX_ROOK_SEMI_OPEN_FILE].round() as Score;
+        eval_params.rook_semi_open_file.1 =
+            weights[START_IDX_ROOK_SEMI_OPEN_FILE + 1].round() as Score;
+        eval_params.rook_seventh.0 = weights[START_IDX_ROOK_SEVENTH].round() as Score;
+        eval_params.rook_seventh.1 = weights[START_IDX_ROOK_SEVENTH + 1].round() as Score;
+
+        eval_params.knight_outpost.0 = weights[START_IDX_KNIGHT_OUTPOST].round() as Score;
+        eval_params.knight_outpost.1 = weights[START_IDX_KNIGHT_OUTPOST + 1].round() as Score;
+        eval_params.bishop_outpost.0 = weights[START_IDX_BISHOP_OUTPOST].round() as Score;
+        eval_params.bishop_outpost.1 = weights[START_IDX_BISHOP_OUTPOST + 1].round() as Score;
+
         let mut king_tropism_idx = START_IDX_KING_TROPISM;
         for distance in [
             &mut eval_params.distance_friendly_pawn,
@@ -141,6 +211,12 @@ impl From<&WeightVector> for EvalParams {
             king_tropism_idx += 2 * DISTANCE_LEN;
         }
 
+        for idx in 0..KING_DANGER_LEN {
+            let offset = START_IDX_KING_DANGER + 2 * idx;
+            eval_params.king_danger[idx].0 = weights[offset].round() as Score;
+            eval_params.king_danger[idx].1 = weights[offset + 1].round() as Score;
+        }
+
         eval_params
     }
 }
@@ -154,8 +230,33 @@ impl Display for EvalParams {
         )?;
         writeln!(
             f,
-            "pub const PASSED_PAWN: ScorePair = ScorePair({}, {});",
-            self.passed_pawn.0, self.passed_pawn.1
+            "const PASSED_PAWN_MG_EG: ([Score; PASSED_PAWN_LEN], [Score; PASSED_PAWN_LEN]) =
+    ([{}], [{}]);",
+            self.passed_pawn
+                .iter()
+                .map(|p| p.0.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.passed_pawn
+                .iter()
+                .map(|p| p.1.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )?;
+        writeln!(
+            f,
+            "pub const PASSED_PAWN_PROTECTED: ScorePair = ScorePair({}, {});",
+            self.passed_pawn_protected.0, self.passed_pawn_protected.1
+        )?;
+        writeln!(
+            f,
+            "pub const PASSED_PAWN_CONNECTED: ScorePair = ScorePair({}, {});",
+            self.passed_pawn_connected.0, self.passed_pawn_connected.1
+        )?;
+        writeln!(
+            f,
+            "pub const PASSED_PAWN_FREE_PATH: ScorePair = ScorePair({}, {});",
+            self.passed_pawn_free_path.0, self.passed_pawn_free_path.1
         )?;
         writeln!(
             f,
@@ -172,12 +273,52 @@ impl Display for EvalParams {
             "pub const DOUBLED_PAWN: ScorePair = ScorePair({}, {});",
             self.doubled_pawn.0, self.doubled_pawn.1
         )?;
+        writeln!(
+            f,
+            "pub const PAWN_SHIELD_MISSING: ScorePair = ScorePair({}, {});",
+            self.pawn_shield_missing.0, self.pawn_shield_missing.1
+        )?;
+        writeln!(
+            f,
+            "pub const PAWN_SHIELD_ADVANCED: ScorePair = ScorePair({}, {});",
+            self.pawn_shield_advanced.0, self.pawn_shield_advanced.1
+        )?;
+        writeln!(
+            f,
+            "pub const PAWN_SHIELD_OPEN_FILE: ScorePair = ScorePair({}, {});",
+            self.pawn_shield_open_file.0, self.pawn_shield_open_file.1
+        )?;
 
         writeln!(
             f,
             "pub const BISHOP_PAIR: ScorePair = ScorePair({}, {});",
             self.bishop_pair.0, self.bishop_pair.1
         )?;
+        writeln!(
+            f,
+            "pub const ROOK_OPEN_FILE: ScorePair = ScorePair({}, {});",
+            self.rook_open_file.0, self.rook_open_file.1
+        )?;
+        writeln!(
+            f,
+            "pub const ROOK_SEMI_OPEN_FILE: ScorePair = ScorePair({}, {});",
+            self.rook_semi_open_file.0, self.rook_semi_open_file.1
+        )?;
+        writeln!(
+            f,
+            "pub const ROOK_SEVENTH: ScorePair = ScorePair({}, {});",
+            self.rook_seventh.0, self.rook_seventh.1
+        )?;
+        writeln!(
+            f,
+            "pub const KNIGHT_OUTPOST: ScorePair = ScorePair({}, {});",
+            self.knight_outpost.0, self.knight_outpost.1
+        )?;
+        writeln!(
+            f,
+            "pub const BISHOP_OUTPOST: ScorePair = ScorePair({}, {});",
+            self.bishop_outpost.0, self.bishop_outpost.1
+        )?;
 
         self.fmt_mob(f)?;
 
@@ -222,6 +363,25 @@ impl Display for EvalParams {
             )?;
         }
 
+        writeln!(
+            f,
+            "const KING_DANGER_MG_EG: ([Score; KING_DANGER_LEN], [Score; KING_DANGER_LEN]) =
+    ([{}], [{}]);",
+            self.king_danger
+                .iter()
+                .map(|d| d.0.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.king_danger
+                .iter()
+                .map(|d| d.1.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )?;
+
+        // The piece-square tables are part of movegen, which sums them up
+        // incrementally
+        writeln!(f, "// movegen/src/piece_square_table.rs")?;
         for (pst, piece) in [
             (self.pst_pawn, "PAWN"),
             (self.pst_knight, "KNIGHT"),
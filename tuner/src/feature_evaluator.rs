@@ -1,10 +1,16 @@
-use eval::params::{self, DISTANCE_LEN};
+use eval::params::{self, DISTANCE_LEN, KING_DANGER_LEN, PASSED_PAWN_LEN};
+use movegen::piece_square_table;
 use nalgebra::SVector;
 
 use crate::position_features::{
     EvalType, PositionFeatures, NUM_FEATURES, PST_SIZE, START_IDX_BACKWARD_PAWN,
-    START_IDX_BISHOP_PAIR, START_IDX_DOUBLED_PAWN, START_IDX_ISOLATED_PAWN, START_IDX_KING_TROPISM,
-    START_IDX_MOBILITY, START_IDX_PASSED_PAWN, START_IDX_PST, START_IDX_TEMPO,
+    START_IDX_BISHOP_OUTPOST, START_IDX_BISHOP_PAIR, START_IDX_DOUBLED_PAWN,
+    START_IDX_ISOLATED_PAWN, START_IDX_KING_DANGER, START_IDX_KING_TROPISM,
+    START_IDX_KNIGHT_OUTPOST, START_IDX_MOBILITY, START_IDX_PASSED_PAWN,
+    START_IDX_PASSED_PAWN_CONNECTED, START_IDX_PASSED_PAWN_FREE_PATH,
+    START_IDX_PASSED_PAWN_PROTECTED, START_IDX_PAWN_SHIELD_ADVANCED, START_IDX_PAWN_SHIELD_MISSING,
+    START_IDX_PAWN_SHIELD_OPEN_FILE, START_IDX_PST, START_IDX_ROOK_OPEN_FILE,
+    START_IDX_ROOK_SEMI_OPEN_FILE, START_IDX_ROOK_SEVENTH, START_IDX_TEMPO,
 };
 
 type Weight = f64;
@@ -50,12 +56,12 @@ pub fn initialize_weights() -> WeightVector {
 
     let mut pst_idx = START_IDX_PST;
     for pst in [
-        params::PST_PAWN,
-        params::PST_KNIGHT,
-        params::PST_BISHOP,
-        params::PST_ROOK,
-        params::PST_QUEEN,
-        params::PST_KING,
+        piece_square_table::PST_PAWN,
+        piece_square_table::PST_KNIGHT,
+        piece_square_table::PST_BISHOP,
+        piece_square_table::PST_ROOK,
+        piece_square_table::PST_QUEEN,
+        piece_square_table::PST_KING,
     ] {
         for square_idx in 0..PST_SIZE {
             weights[pst_idx + 2 * square_idx] = pst[square_idx].0.into();
@@ -65,22 +71,47 @@ pub fn initialize_weights() -> WeightVector {
     }
 
     weights[START_IDX_TEMPO] = params::TEMPO.0.into();
-    weights[START_IDX_TEMPO + 1] = params::TEMPO.1.into();
 
-    weights[START_IDX_PASSED_PAWN] = params::PASSED_PAWN.0.into();
-    weights[START_IDX_PASSED_PAWN + 1] = params::PASSED_PAWN.1.into();
+    for rank in 0..PASSED_PAWN_LEN {
+        weights[START_IDX_PASSED_PAWN + 2 * rank] = params::PASSED_PAWN[rank].0.into();
+        weights[START_IDX_PASSED_PAWN + 2 * rank + 1] = params::PASSED_PAWN[rank].1.into();
+    }
+    weights[START_IDX_PASSED_PAWN_PROTECTED] = params::PASSED_PAWN_PROTECTED.0.into();
+    weights[START_IDX_PASSED_PAWN_PROTECTED + 1] = params::PASSED_PAWN_PROTECTED.1.into();
+    weights[START_IDX_PASSED_PAWN_CONNECTED] = params::PASSED_PAWN_CONNECTED.0.into();
+    weights[START_IDX_PASSED_PAWN_CONNECTED + 1] = params::PASSED_PAWN_CONNECTED.1.into();
+    weights[START_IDX_PASSED_PAWN_FREE_PATH] = params::PASSED_PAWN_FREE_PATH.0.into();
+    weights[START_IDX_PASSED_PAWN_FREE_PATH + 1] = params::PASSED_PAWN_FREE_PATH.1.into();
     weights[START_IDX_ISOLATED_PAWN] = params::ISOLATED_PAWN.0.into();
     weights[START_IDX_ISOLATED_PAWN + 1] = params::ISOLATED_PAWN.1.into();
     weights[START_IDX_BACKWARD_PAWN] = params::BACKWARD_PAWN.0.into();
     weights[START_IDX_BACKWARD_PAWN + 1] = params::BACKWARD_PAWN.1.into();
     weights[START_IDX_DOUBLED_PAWN] = params::DOUBLED_PAWN.0.into();
     weights[START_IDX_DOUBLED_PAWN + 1] = params::DOUBLED_PAWN.1.into();
+    weights[START_IDX_PAWN_SHIELD_MISSING] = params::PAWN_SHIELD_MISSING.0.into();
+    weights[START_IDX_PAWN_SHIELD_MISSING + 1] = params::PAWN_SHIELD_MISSING.1.into();
+    weights[START_IDX_PAWN_SHIELD_ADVANCED] = params::PAWN_SHIELD_ADVANCED.0.into();
+    weights[START_IDX_PAWN_SHIELD_ADVANCED + 1] = params::PAWN_SHIELD_ADVANCED.1.into();
+    weights[START_IDX_PAWN_SHIELD_OPEN_FILE] = params::PAWN_SHIELD_OPEN_FILE.0.into();
+    weights[START_IDX_PAWN_SHIELD_OPEN_FILE + 1] = params::PAWN_SHIELD_OPEN_FILE.1.into();
 
     initialize_mobility(&mut weights);
 
     weights[START_IDX_BISHOP_PAIR] = params::BISHOP_PAIR.0.into();
     weights[START_IDX_BISHOP_PAIR + 1] = params::BISHOP_PAIR.1.into();
 
+    weights[START_IDX_ROOK_OPEN_FILE] = params::ROOK_OPEN_FILE.0.into();
+    weights[START_IDX_ROOK_OPEN_FILE + 1] = params::ROOK_OPEN_FILE.1.into();
+    weights[START_IDX_ROOK_SEMI_OPEN_FILE] = params::ROOK_SEMI_OPEN_FILE.0.into();
+    weights[START_IDX_ROOK_SEMI_OPEN_FILE + 1] = params::ROOK_SEMI_OPEN_FILE.1.into();
+    weights[START_IDX_ROOK_SEVENTH] = params::ROOK_SEVENTH.0.into();
+    weights[START_IDX_ROOK_SEVENTH + 1] = params::ROOK_SEVENTH.1.into();
+
+    weights[START_IDX_KNIGHT_OUTPOST] = params::KNIGHT_OUTPOST.0.into();
+    weights[START_IDX_KNIGHT_OUTPOST + 1] = params::KNIGHT_OUTPOST.1.into();
+    weights[START_IDX_BISHOP_OUTPOST] = params::BISHOP_OUTPOST.0.into();
+    weights[START_IDX_BISHOP_OUTPOST + 1] = params::BISHOP_OUTPOST.1.into();
+
     let mut king_tropism_idx = START_IDX_KING_TROPISM;
     for distance in [
         params::DISTANCE_FRIENDLY_PAWN,
@@ -103,6 +134,11 @@ pub fn initialize_weights() -> WeightVector {
         king_tropism_idx += 2 * DISTANCE_LEN;
     }
 
+    for idx in 0..KING_DANGER_LEN {
+        weights[START_IDX_KING_DANGER + 2 * idx] = params::KING_DANGER[idx].0.into();
+        weights[START_IDX_KING_DANGER + 2 * idx + 1] = params::KING_DANGER[idx].1.into();
+    }
+
     weights
 }
 
@@ -141,7 +177,15 @@ mod tests {
 
     #[test]
     fn tuner_eval_matches_actual_eval() {
-        let fens = ["8/6pk/5p2/P1R4P/1P5P/5K2/2P5/6r1 b - - 2 70"];
+        let fens = [
+            "8/6pk/5p2/P1R4P/1P5P/5K2/2P5/6r1 b - - 2 70",
+            "8/1k6/8/2PP2p1/8/5p2/4n1K1/8 w - - 0 50",
+            "r4rk1/ppp2ppp/8/8/6nq/8/5PPP/R1B2RK1 w - - 0 20",
+            "2kr3r/ppp2p2/2n5/6p1/8/5NP1/PPP2P1P/2KR3R b - - 0 15",
+            "r2r2k1/1p3ppp/8/8/8/8/P4PPP/3R1RK1 w - - 0 25",
+            "6k1/1R3ppp/8/8/8/8/r4PPP/6K1 b - - 0 30",
+            "r1bq1rk1/1p3ppp/p7/3N4/2P1P3/8/PP3PPP/R2QKB1R w KQ - 0 12",
+        ];
 
         let mut evaluator = Complex::new();
         let feature_evaluator = FeatureEvaluator::new();
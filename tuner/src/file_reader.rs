@@ -4,7 +4,7 @@ use std::{
     path::Path,
 };
 
-use eval::{eval::HasMatingMaterial, Eval};
+use eval::{eval::HasMatingMaterial, kpk::Kpk, params, scale_factor::ScaleFactor, Eval};
 use movegen::{fen::Fen, side::Side};
 
 use crate::{
@@ -23,24 +23,22 @@ pub fn read_training_data(
 
     if let Ok(lines) = read_lines(filename) {
         for line in lines.map_while(Result::ok) {
-            let mut s = line.split(" c9 ");
-            let short_fen = s.next().unwrap();
-            let pos = Fen::shortened_str_to_pos(short_fen).unwrap();
-            let outcome = match s.next().unwrap().split('\"').nth(1).unwrap() {
-                "1-0" => Outcome::WhiteWin,
-                "1/2-1/2" => Outcome::Draw,
-                "0-1" => Outcome::BlackWin,
-                invalid => panic!("Invalid outcome: {invalid}"),
-            };
-            let pos_eval = pos_evaluator.eval(&pos);
-            let training_pos = TrainingPosition { pos, outcome };
+            let (short_fen, training_pos) = parse_line(&line);
+            let pos_eval = pos_evaluator.eval(&training_pos.pos);
+            // The features don't cover the scale factor and the KPK bitbase
+            let is_scaled =
+                ScaleFactor::scale_factor(&training_pos.pos) != params::SCALE_FACTOR_NORMAL;
+            let is_kpk = Kpk::is_kpk(&training_pos.pos);
             let training_features = TrainingFeatures::from(&training_pos);
             let feature_eval = feature_evaluator.eval(&training_features.features);
 
-            // Exclude draws by insufficient material
-            if pos_eval != 0
-                || pos_evaluator.has_mating_material(Side::White)
-                    && pos_evaluator.has_mating_material(Side::Black)
+            // Exclude draws by insufficient material, drawish endgames with a
+            // scaled evaluation and KPK endgames
+            if !is_scaled
+                && !is_kpk
+                && (pos_eval != 0
+                    || pos_evaluator.has_mating_material(Side::White)
+                        && pos_evaluator.has_mating_material(Side::Black))
             {
                 // Validate that the evaluations match
                 assert!(
@@ -59,6 +57,32 @@ pub fn read_training_data(
     training_data
 }
 
+// Reads the positions and outcomes only, without extracting the features
+pub fn read_training_positions(filename: &str) -> Vec<TrainingPosition> {
+    let mut training_positions = Vec::new();
+    if let Ok(lines) = read_lines(filename) {
+        for line in lines.map_while(Result::ok) {
+            training_positions.push(parse_line(&line).1);
+        }
+    }
+    training_positions
+}
+
+// Each line is a shortened FEN, followed by the outcome, e.g.
+// rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - c9 "1/2-1/2";
+fn parse_line(line: &str) -> (&str, TrainingPosition) {
+    let mut s = line.split(" c9 ");
+    let short_fen = s.next().unwrap();
+    let pos = Fen::shortened_str_to_pos(short_fen).unwrap();
+    let outcome = match s.next().unwrap().split('\"').nth(1).unwrap() {
+        "1-0" => Outcome::WhiteWin,
+        "1/2-1/2" => Outcome::Draw,
+        "0-1" => Outcome::BlackWin,
+        invalid => panic!("Invalid outcome: {invalid}"),
+    };
+    (short_fen, TrainingPosition { pos, outcome })
+}
+
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where
     P: AsRef<Path>,
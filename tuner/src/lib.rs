@@ -3,6 +3,7 @@ pub mod eval_params;
 pub mod feature_evaluator;
 pub mod file_reader;
 pub mod optimizer;
+pub mod wdl_model;
 
 mod position_features;
 mod training;
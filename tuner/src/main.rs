@@ -4,13 +4,15 @@ use std::{
 };
 
 use clap::{Args, Parser, Subcommand};
-use eval::complex::Complex;
+use eval::{complex::Complex, Eval};
+use movegen::side::Side;
 use tuner::{
     error_function::ErrorFunction,
     eval_params::EvalParams,
     feature_evaluator::{initialize_weights, FeatureEvaluator},
     file_reader,
     optimizer::{self, AdamParams, Checkpoint},
+    wdl_model::WdlModel,
 };
 
 #[derive(Debug, Parser)]
@@ -29,6 +31,8 @@ enum Commands {
     Optimize(OptimizeArgs),
     /// Print evaluation parameters
     Print(PrintArgs),
+    /// Fit the win/draw/loss model to the outcomes of the training positions
+    FitWdl(FitWdlArgs),
 }
 
 #[derive(Debug, Args)]
@@ -55,6 +59,12 @@ struct PrintArgs {
     weight_file: String,
 }
 
+#[derive(Debug, Args)]
+struct FitWdlArgs {
+    #[arg(short, long)]
+    training_data_file: String,
+}
+
 fn main() -> std::io::Result<()> {
     let cli = Cli::parse();
 
@@ -67,6 +77,7 @@ fn main() -> std::io::Result<()> {
             args.num_epochs,
         )?,
         Commands::Print(args) => write_weights(&args.weight_file)?,
+        Commands::FitWdl(args) => fit_wdl_model(&args.training_data_file),
     };
 
     Ok(())
@@ -127,3 +138,21 @@ fn write_weights(weight_file: &str) -> std::io::Result<()> {
     println!("{eval_params}");
     Ok(())
 }
+
+// The scores are the static evaluations of the training positions
+fn fit_wdl_model(training_data_file: &str) {
+    let mut pos_evaluator = Complex::new();
+    let mut model = WdlModel::new();
+    for training_pos in file_reader::read_training_positions(training_data_file) {
+        let pos = &training_pos.pos;
+        model.add(
+            pos_evaluator.eval(pos),
+            training_pos.outcome,
+            pos.side_to_move() == Side::White,
+        );
+    }
+    let (a, b) = model.fit();
+    println!("Positions: {}", model.count());
+    println!("pub const WDL_MODEL_A: f64 = {a:.1};");
+    println!("pub const WDL_MODEL_B: f64 = {b:.1};");
+}
@@ -1,8 +1,11 @@
 use eval::{
+    attacks::Attacks,
     complex::Complex,
+    king_safety::KingSafety,
     mobility::Mobility,
-    params::{DISTANCE_LEN, MOB_LEN},
+    params::{DISTANCE_LEN, KING_DANGER_LEN, MOB_LEN, PASSED_PAWN_LEN},
     pawn_structure::PawnStructure,
+    piece_placement::PiecePlacement,
     GamePhase,
 };
 use movegen::{bitboard::Bitboard, piece, position::Position, side::Side};
@@ -16,33 +19,78 @@ pub const PST_SIZE: usize = 32;
 const NUM_SIDES: usize = 2;
 const NUM_PIECE_TYPES: usize = 6;
 const NUM_PST_FEATURES: usize = 2 * NUM_PIECE_TYPES * PST_SIZE;
-const NUM_TEMPO_FEATURES: usize = 2;
-const NUM_PASSED_PAWN_FEATURES: usize = 2;
+// The tempo only has a middlegame feature
+const NUM_TEMPO_FEATURES: usize = 1;
+const NUM_PASSED_PAWN_FEATURES: usize = 2 * PASSED_PAWN_LEN;
+const NUM_PASSED_PAWN_PROTECTED_FEATURES: usize = 2;
+const NUM_PASSED_PAWN_CONNECTED_FEATURES: usize = 2;
+const NUM_PASSED_PAWN_FREE_PATH_FEATURES: usize = 2;
 const NUM_ISOLATED_PAWN_FEATURES: usize = 2;
 const NUM_BACKWARD_PAWN_FEATURES: usize = 2;
 const NUM_DOUBLED_PAWN_FEATURES: usize = 2;
+const NUM_PAWN_SHIELD_MISSING_FEATURES: usize = 2;
+const NUM_PAWN_SHIELD_ADVANCED_FEATURES: usize = 2;
+const NUM_PAWN_SHIELD_OPEN_FILE_FEATURES: usize = 2;
 const NUM_MOBILITY_FEATURES: usize = 2 * MOB_LEN;
 const NUM_BISHOP_PAIR_FEATURES: usize = 2;
+const NUM_ROOK_OPEN_FILE_FEATURES: usize = 2;
+const NUM_ROOK_SEMI_OPEN_FILE_FEATURES: usize = 2;
+const NUM_ROOK_SEVENTH_FEATURES: usize = 2;
+const NUM_KNIGHT_OUTPOST_FEATURES: usize = 2;
+const NUM_BISHOP_OUTPOST_FEATURES: usize = 2;
 const NUM_KING_TROPISM_FEATURES: usize = 2 * NUM_SIDES * NUM_PIECE_TYPES * DISTANCE_LEN;
+const NUM_KING_DANGER_FEATURES: usize = 2 * KING_DANGER_LEN;
 pub const NUM_FEATURES: usize = NUM_PST_FEATURES
     + NUM_TEMPO_FEATURES
     + NUM_PASSED_PAWN_FEATURES
+    + NUM_PASSED_PAWN_PROTECTED_FEATURES
+    + NUM_PASSED_PAWN_CONNECTED_FEATURES
+    + NUM_PASSED_PAWN_FREE_PATH_FEATURES
     + NUM_ISOLATED_PAWN_FEATURES
     + NUM_BACKWARD_PAWN_FEATURES
     + NUM_DOUBLED_PAWN_FEATURES
+    + NUM_PAWN_SHIELD_MISSING_FEATURES
+    + NUM_PAWN_SHIELD_ADVANCED_FEATURES
+    + NUM_PAWN_SHIELD_OPEN_FILE_FEATURES
     + NUM_MOBILITY_FEATURES
     + NUM_BISHOP_PAIR_FEATURES
-    + NUM_KING_TROPISM_FEATURES;
+    + NUM_ROOK_OPEN_FILE_FEATURES
+    + NUM_ROOK_SEMI_OPEN_FILE_FEATURES
+    + NUM_ROOK_SEVENTH_FEATURES
+    + NUM_KNIGHT_OUTPOST_FEATURES
+    + NUM_BISHOP_OUTPOST_FEATURES
+    + NUM_KING_TROPISM_FEATURES
+    + NUM_KING_DANGER_FEATURES;
 
 pub const START_IDX_PST: usize = 0;
 pub const START_IDX_TEMPO: usize = START_IDX_PST + NUM_PST_FEATURES;
 pub const START_IDX_PASSED_PAWN: usize = START_IDX_TEMPO + NUM_TEMPO_FEATURES;
-pub const START_IDX_ISOLATED_PAWN: usize = START_IDX_PASSED_PAWN + NUM_PASSED_PAWN_FEATURES;
+pub const START_IDX_PASSED_PAWN_PROTECTED: usize = START_IDX_PASSED_PAWN + NUM_PASSED_PAWN_FEATURES;
+pub const START_IDX_PASSED_PAWN_CONNECTED: usize =
+    START_IDX_PASSED_PAWN_PROTECTED + NUM_PASSED_PAWN_PROTECTED_FEATURES;
+pub const START_IDX_PASSED_PAWN_FREE_PATH: usize =
+    START_IDX_PASSED_PAWN_CONNECTED + NUM_PASSED_PAWN_CONNECTED_FEATURES;
+pub const START_IDX_ISOLATED_PAWN: usize =
+    START_IDX_PASSED_PAWN_FREE_PATH + NUM_PASSED_PAWN_FREE_PATH_FEATURES;
 pub const START_IDX_BACKWARD_PAWN: usize = START_IDX_ISOLATED_PAWN + NUM_ISOLATED_PAWN_FEATURES;
 pub const START_IDX_DOUBLED_PAWN: usize = START_IDX_BACKWARD_PAWN + NUM_BACKWARD_PAWN_FEATURES;
-pub const START_IDX_MOBILITY: usize = START_IDX_DOUBLED_PAWN + NUM_DOUBLED_PAWN_FEATURES;
+pub const START_IDX_PAWN_SHIELD_MISSING: usize = START_IDX_DOUBLED_PAWN + NUM_DOUBLED_PAWN_FEATURES;
+pub const START_IDX_PAWN_SHIELD_ADVANCED: usize =
+    START_IDX_PAWN_SHIELD_MISSING + NUM_PAWN_SHIELD_MISSING_FEATURES;
+pub const START_IDX_PAWN_SHIELD_OPEN_FILE: usize =
+    START_IDX_PAWN_SHIELD_ADVANCED + NUM_PAWN_SHIELD_ADVANCED_FEATURES;
+pub const START_IDX_MOBILITY: usize =
+    START_IDX_PAWN_SHIELD_OPEN_FILE + NUM_PAWN_SHIELD_OPEN_FILE_FEATURES;
 pub const START_IDX_BISHOP_PAIR: usize = START_IDX_MOBILITY + NUM_MOBILITY_FEATURES;
-pub const START_IDX_KING_TROPISM: usize = START_IDX_BISHOP_PAIR + NUM_BISHOP_PAIR_FEATURES;
+pub const START_IDX_ROOK_OPEN_FILE: usize = START_IDX_BISHOP_PAIR + NUM_BISHOP_PAIR_FEATURES;
+pub const START_IDX_ROOK_SEMI_OPEN_FILE: usize =
+    START_IDX_ROOK_OPEN_FILE + NUM_ROOK_OPEN_FILE_FEATURES;
+pub const START_IDX_ROOK_SEVENTH: usize =
+    START_IDX_ROOK_SEMI_OPEN_FILE + NUM_ROOK_SEMI_OPEN_FILE_FEATURES;
+pub const START_IDX_KNIGHT_OUTPOST: usize = START_IDX_ROOK_SEVENTH + NUM_ROOK_SEVENTH_FEATURES;
+pub const START_IDX_BISHOP_OUTPOST: usize = START_IDX_KNIGHT_OUTPOST + NUM_KNIGHT_OUTPOST_FEATURES;
+pub const START_IDX_KING_TROPISM: usize = START_IDX_BISHOP_OUTPOST + NUM_BISHOP_OUTPOST_FEATURES;
+pub const START_IDX_KING_DANGER: usize = START_IDX_KING_TROPISM + NUM_KING_TROPISM_FEATURES;
 
 #[derive(Debug, Clone)]
 pub struct PositionFeatures {
@@ -69,16 +117,20 @@ impl From<&Position> for PositionFeatures {
         let game_phase = extract_psts(&mut features, pos);
         extract_tempo(&mut features, pos);
         extract_pawn_structure(&mut features, pos);
-        extract_mobility(&mut features, pos);
+        let attacks = Attacks::both(pos);
+        extract_mobility(&mut features, pos, &attacks);
         extract_bishop_pair(&mut features, pos);
+        extract_rook_files(&mut features, pos);
+        extract_outposts(&mut features, pos);
         extract_king_tropism(&mut features, pos);
+        extract_king_safety(&mut features, pos, &attacks);
 
         let mg_phase = 1.0 - game_phase;
         let eg_phase = game_phase;
         for (_row, col, feat) in features.triplet_iter_mut() {
-            *feat *= match col % 2 {
-                0 => mg_phase,
-                _ => eg_phase,
+            *feat *= match is_endgame_feature(col) {
+                false => mg_phase,
+                true => eg_phase,
             };
         }
 
@@ -86,6 +138,15 @@ impl From<&Position> for PositionFeatures {
     }
 }
 
+// Each term has a middlegame feature followed by an endgame feature, except
+// the tempo. The features after it are shifted by its single feature.
+fn is_endgame_feature(idx: usize) -> bool {
+    match idx < START_IDX_PASSED_PAWN {
+        true => idx != START_IDX_TEMPO && idx % 2 == 1,
+        false => (idx - NUM_TEMPO_FEATURES) % 2 == 1,
+    }
+}
+
 fn extract_psts(features: &mut CooMatrix<FeatureType>, pos: &Position) -> EvalType {
     let mut game_phase = GamePhase::default();
     let mut offset = START_IDX_PST;
@@ -130,16 +191,51 @@ fn extract_tempo(features: &mut CooMatrix<FeatureType>, pos: &Position) {
         Side::White => 1.0,
         Side::Black => -1.0,
     };
+    // Middlegame only, there is no endgame feature
     features.push(0, START_IDX_TEMPO, val);
-    features.push(0, START_IDX_TEMPO + 1, val);
 }
 
 fn extract_pawn_structure(features: &mut CooMatrix<FeatureType>, pos: &Position) {
     let white_pawns = pos.piece_occupancy(Side::White, piece::Type::Pawn);
     let black_pawns = pos.piece_occupancy(Side::Black, piece::Type::Pawn);
-    let passed_pawn_count = PawnStructure::passed_pawn_count(white_pawns, black_pawns).into();
-    features.push(0, START_IDX_PASSED_PAWN, passed_pawn_count);
-    features.push(0, START_IDX_PASSED_PAWN + 1, passed_pawn_count);
+    let passed_pawn_counts = PawnStructure::passed_pawn_rank_counts(white_pawns, black_pawns);
+    for (rank, c) in passed_pawn_counts.iter().enumerate() {
+        if *c != 0 {
+            features.push(0, START_IDX_PASSED_PAWN + 2 * rank, (*c).into());
+            features.push(0, START_IDX_PASSED_PAWN + 2 * rank + 1, (*c).into());
+        }
+    }
+    let protected_passed_pawn_count =
+        PawnStructure::protected_passed_pawn_count(white_pawns, black_pawns).into();
+    features.push(
+        0,
+        START_IDX_PASSED_PAWN_PROTECTED,
+        protected_passed_pawn_count,
+    );
+    features.push(
+        0,
+        START_IDX_PASSED_PAWN_PROTECTED + 1,
+        protected_passed_pawn_count,
+    );
+    let connected_passed_pawn_count =
+        PawnStructure::connected_passed_pawn_count(white_pawns, black_pawns).into();
+    features.push(
+        0,
+        START_IDX_PASSED_PAWN_CONNECTED,
+        connected_passed_pawn_count,
+    );
+    features.push(
+        0,
+        START_IDX_PASSED_PAWN_CONNECTED + 1,
+        connected_passed_pawn_count,
+    );
+    let free_passed_pawn_count = PawnStructure::free_passed_pawn_count(pos).into();
+    features.push(0, START_IDX_PASSED_PAWN_FREE_PATH, free_passed_pawn_count);
+    features.push(
+        0,
+        START_IDX_PASSED_PAWN_FREE_PATH + 1,
+        free_passed_pawn_count,
+    );
     let isolated_pawn_count = PawnStructure::isolated_pawn_count(white_pawns, black_pawns).into();
     features.push(0, START_IDX_ISOLATED_PAWN, isolated_pawn_count);
     features.push(0, START_IDX_ISOLATED_PAWN + 1, isolated_pawn_count);
@@ -149,10 +245,23 @@ fn extract_pawn_structure(features: &mut CooMatrix<FeatureType>, pos: &Position)
     let doubled_pawn_count = PawnStructure::doubled_pawn_count(white_pawns, black_pawns).into();
     features.push(0, START_IDX_DOUBLED_PAWN, doubled_pawn_count);
     features.push(0, START_IDX_DOUBLED_PAWN + 1, doubled_pawn_count);
+    let shield_missing_count = PawnStructure::pawn_shield_missing_count(pos).into();
+    features.push(0, START_IDX_PAWN_SHIELD_MISSING, shield_missing_count);
+    features.push(0, START_IDX_PAWN_SHIELD_MISSING + 1, shield_missing_count);
+    let shield_advanced_count = PawnStructure::pawn_shield_advanced_count(pos).into();
+    features.push(0, START_IDX_PAWN_SHIELD_ADVANCED, shield_advanced_count);
+    features.push(0, START_IDX_PAWN_SHIELD_ADVANCED + 1, shield_advanced_count);
+    let shield_open_file_count = PawnStructure::pawn_shield_open_file_count(pos).into();
+    features.push(0, START_IDX_PAWN_SHIELD_OPEN_FILE, shield_open_file_count);
+    features.push(
+        0,
+        START_IDX_PAWN_SHIELD_OPEN_FILE + 1,
+        shield_open_file_count,
+    );
 }
 
-fn extract_mobility(features: &mut CooMatrix<FeatureType>, pos: &Position) {
-    let mob_counts = Mobility::mobility_counts(pos);
+fn extract_mobility(features: &mut CooMatrix<FeatureType>, pos: &Position, attacks: &[Attacks; 2]) {
+    let mob_counts = Mobility::mobility_counts(pos, attacks);
     let mut idx = START_IDX_MOBILITY;
     for c in mob_counts.knight_mob {
         if c != 0 {
@@ -190,6 +299,27 @@ fn extract_bishop_pair(features: &mut CooMatrix<FeatureType>, pos: &Position) {
     features.push(0, START_IDX_BISHOP_PAIR + 1, multiplier);
 }
 
+fn extract_rook_files(features: &mut CooMatrix<FeatureType>, pos: &Position) {
+    let open_file_count = PiecePlacement::rook_open_file_count(pos).into();
+    features.push(0, START_IDX_ROOK_OPEN_FILE, open_file_count);
+    features.push(0, START_IDX_ROOK_OPEN_FILE + 1, open_file_count);
+    let semi_open_file_count = PiecePlacement::rook_semi_open_file_count(pos).into();
+    features.push(0, START_IDX_ROOK_SEMI_OPEN_FILE, semi_open_file_count);
+    features.push(0, START_IDX_ROOK_SEMI_OPEN_FILE + 1, semi_open_file_count);
+    let seventh_count = PiecePlacement::rook_seventh_count(pos).into();
+    features.push(0, START_IDX_ROOK_SEVENTH, seventh_count);
+    features.push(0, START_IDX_ROOK_SEVENTH + 1, seventh_count);
+}
+
+fn extract_outposts(features: &mut CooMatrix<FeatureType>, pos: &Position) {
+    let knight_outpost_count = PiecePlacement::knight_outpost_count(pos).into();
+    features.push(0, START_IDX_KNIGHT_OUTPOST, knight_outpost_count);
+    features.push(0, START_IDX_KNIGHT_OUTPOST + 1, knight_outpost_count);
+    let bishop_outpost_count = PiecePlacement::bishop_outpost_count(pos).into();
+    features.push(0, START_IDX_BISHOP_OUTPOST, bishop_outpost_count);
+    features.push(0, START_IDX_BISHOP_OUTPOST + 1, bishop_outpost_count);
+}
+
 fn extract_king_tropism(features: &mut CooMatrix<FeatureType>, pos: &Position) {
     let mut offset = START_IDX_KING_TROPISM;
     let enemy_offset = 2 * DISTANCE_LEN;
@@ -238,3 +368,18 @@ fn extract_king_tropism(features: &mut CooMatrix<FeatureType>, pos: &Position) {
         offset += 2 * NUM_SIDES * DISTANCE_LEN;
     }
 }
+
+fn extract_king_safety(
+    features: &mut CooMatrix<FeatureType>,
+    pos: &Position,
+    attacks: &[Attacks; 2],
+) {
+    let white_idx = KingSafety::danger_index(pos, Side::White, attacks);
+    let black_idx = KingSafety::danger_index(pos, Side::Black, attacks);
+    // Middlegame
+    features.push(0, START_IDX_KING_DANGER + 2 * white_idx, 1.0);
+    features.push(0, START_IDX_KING_DANGER + 2 * black_idx, -1.0);
+    // Endgame
+    features.push(0, START_IDX_KING_DANGER + 2 * white_idx + 1, 1.0);
+    features.push(0, START_IDX_KING_DANGER + 2 * black_idx + 1, -1.0);
+}
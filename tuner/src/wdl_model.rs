@@ -0,0 +1,147 @@
+use eval::Score;
+
+use crate::training::Outcome;
+
+// Scores beyond this are rare and don't tell much about the draw rate.
+// Positions with such scores are left out.
+const MAX_SCORE: Score = 1000;
+
+// Search ranges of the constants of the win/draw/loss model in eval::score
+const A_RANGE: (i32, i32) = (1, 600);
+const B_RANGE: (i32, i32) = (10, 300);
+const COARSE_STEP: i32 = 5;
+
+// Fits the constants of the win/draw/loss model (WDL_MODEL_A and
+// WDL_MODEL_B in eval::score) to game outcomes by maximum likelihood. The
+// outcomes are counted per score, from the side to move's point of view.
+#[derive(Debug, Clone)]
+pub struct WdlModel {
+    // Win, draw and loss counts, indexed by the score + MAX_SCORE
+    counts: Vec<[u64; 3]>,
+}
+
+impl Default for WdlModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WdlModel {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![[0; 3]; 2 * MAX_SCORE as usize + 1],
+        }
+    }
+
+    // The score and the outcome are from white's point of view, like the
+    // evaluation and the training data
+    pub fn add(&mut self, score: Score, outcome: Outcome, white_to_move: bool) {
+        if score.abs() > MAX_SCORE {
+            return;
+        }
+        let outcome_idx = match (white_to_move, outcome) {
+            (true, Outcome::WhiteWin) | (false, Outcome::BlackWin) => 0,
+            (_, Outcome::Draw) => 1,
+            (true, Outcome::BlackWin) | (false, Outcome::WhiteWin) => 2,
+        };
+        let score = match white_to_move {
+            true => score,
+            false => -score,
+        };
+        self.counts[(score + MAX_SCORE) as usize][outcome_idx] += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().flatten().sum()
+    }
+
+    // Grid search, first coarse, then around the coarse maximum. Returns
+    // (WDL_MODEL_A, WDL_MODEL_B).
+    pub fn fit(&self) -> (f64, f64) {
+        let best = |a_range: (i32, i32), b_range: (i32, i32), step: usize| {
+            let mut best = (f64::NEG_INFINITY, 0, 0);
+            for a in (a_range.0..=a_range.1).step_by(step) {
+                for b in (b_range.0..=b_range.1).step_by(step) {
+                    let ll = self.log_likelihood(a as f64, b as f64);
+                    if ll > best.0 {
+                        best = (ll, a, b);
+                    }
+                }
+            }
+            (best.1, best.2)
+        };
+        let (a, b) = best(A_RANGE, B_RANGE, COARSE_STEP as usize);
+        let around = |x: i32, range: (i32, i32)| {
+            (
+                (x - COARSE_STEP).max(range.0),
+                (x + COARSE_STEP).min(range.1),
+            )
+        };
+        let (a, b) = best(around(a, A_RANGE), around(b, B_RANGE), 1);
+        (a as f64, b as f64)
+    }
+
+    // Same model as eval::score::win_draw_loss
+    pub fn log_likelihood(&self, a: f64, b: f64) -> f64 {
+        let logistic = |x: f64| 1.0 / (1.0 + ((a - x) / b).exp());
+        let mut ll = 0.0;
+        for (idx, [win, draw, loss]) in self.counts.iter().enumerate() {
+            if win + draw + loss == 0 {
+                continue;
+            }
+            let s = idx as f64 - MAX_SCORE as f64;
+            let p_win = logistic(s);
+            let p_loss = logistic(-s);
+            let p_draw = (1.0 - p_win - p_loss).max(f64::MIN_POSITIVE);
+            ll +=
+                *win as f64 * p_win.ln() + *draw as f64 * p_draw.ln() + *loss as f64 * p_loss.ln();
+        }
+        ll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WdlModel, MAX_SCORE};
+    use crate::training::Outcome;
+
+    #[test]
+    fn fit_recovers_model_constants() {
+        // Outcomes which follow the model with A = 200 and B = 80 exactly
+        let (a, b) = (200.0, 80.0);
+        let logistic = |x: f64| 1.0 / (1.0 + ((a - x) / b).exp());
+        let mut model = WdlModel::new();
+        for score in (-MAX_SCORE..=MAX_SCORE).step_by(10) {
+            let win = (10000.0 * logistic(score as f64)).round() as usize;
+            let loss = (10000.0 * logistic(-score as f64)).round() as usize;
+            let draw = 10000 - win - loss;
+            for (outcome, count) in [
+                (Outcome::WhiteWin, win),
+                (Outcome::Draw, draw),
+                (Outcome::BlackWin, loss),
+            ] {
+                for _ in 0..count {
+                    model.add(score, outcome, true);
+                }
+            }
+        }
+        assert_eq!((a, b), model.fit());
+    }
+
+    #[test]
+    fn add_counts_from_side_to_move() {
+        let mut white = WdlModel::new();
+        let mut black = WdlModel::new();
+        white.add(100, Outcome::WhiteWin, true);
+        white.add(-50, Outcome::Draw, true);
+        // The same from black's side
+        black.add(-100, Outcome::BlackWin, false);
+        black.add(50, Outcome::Draw, false);
+        assert_eq!(white.counts, black.counts);
+        assert_eq!(2, white.count());
+
+        // Out of range
+        white.add(MAX_SCORE + 1, Outcome::WhiteWin, true);
+        assert_eq!(2, white.count());
+    }
+}
@@ -0,0 +1,165 @@
+use engine::EngineOptions;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy)]
+pub enum Direction {
+    In,
+    Out,
+    // Search tree trace, never written to the real output
+    Trace,
+}
+
+struct Entry {
+    path: String,
+    direction: Direction,
+    time: SystemTime,
+    line: String,
+}
+
+// Logs the UCI traffic to the file given in the engine options. The file is
+// written by a separate thread, so a slow disk never delays the real output.
+// The thread is only started once there is something to log.
+pub struct DebugLog {
+    engine_options: Arc<Mutex<EngineOptions>>,
+    sender: Option<Sender<Entry>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DebugLog {
+    pub fn new(engine_options: Arc<Mutex<EngineOptions>>) -> Self {
+        Self {
+            engine_options,
+            sender: None,
+            thread: None,
+        }
+    }
+
+    pub fn log(&mut self, direction: Direction, line: &str) {
+        let path = match self.engine_options.lock() {
+            Ok(opt) => opt.debug_log_file.clone(),
+            Err(e) => panic!("{e}"),
+        };
+        // The entry must still be sent if the path is empty and the thread is
+        // running, because the thread then needs to close the file.
+        if path.is_empty() && self.sender.is_none() {
+            return;
+        }
+        let entry = Entry {
+            path,
+            direction,
+            time: SystemTime::now(),
+            line: String::from(line.trim_end_matches(['\r', '\n'])),
+        };
+        let sender = self.sender.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            self.thread = Some(thread::spawn(move || {
+                let mut file = LogFile::default();
+                for entry in receiver {
+                    file.write(&entry);
+                }
+            }));
+            sender
+        });
+        let _ = sender.send(entry);
+    }
+}
+
+impl Drop for DebugLog {
+    fn drop(&mut self) {
+        // Closing the channel stops the thread after the pending entries are
+        // written
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Default)]
+struct LogFile {
+    path: String,
+    file: Option<File>,
+}
+
+impl LogFile {
+    fn write(&mut self, entry: &Entry) {
+        if entry.path != self.path {
+            // Close the old file. The new one is opened below.
+            self.path = entry.path.clone();
+            self.file = None;
+            if !self.path.is_empty() {
+                match OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                {
+                    Ok(f) => self.file = Some(f),
+                    Err(e) => eprintln!("Error opening debug log file {}: {e}", self.path),
+                }
+            }
+        }
+        if let Some(f) = &mut self.file {
+            let marker = match entry.direction {
+                Direction::In => ">>",
+                Direction::Out => "<<",
+                Direction::Trace => "##",
+            };
+            let since_epoch = entry.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+            let _ = writeln!(
+                f,
+                "{}.{:03} {marker} {}",
+                since_epoch.as_secs(),
+                since_epoch.subsec_millis(),
+                entry.line,
+            )
+            .and_then(|_| f.flush());
+        }
+    }
+}
+
+// Forwards everything to the wrapped writer and logs each complete line
+pub struct LoggingWriter {
+    writer: Box<dyn Write + Send>,
+    debug_log: DebugLog,
+    partial_line: Vec<u8>,
+}
+
+impl LoggingWriter {
+    pub fn new(writer: Box<dyn Write + Send>, debug_log: DebugLog) -> Self {
+        Self {
+            writer,
+            debug_log,
+            partial_line: Vec::new(),
+        }
+    }
+
+    pub fn log_input(&mut self, line: &str) {
+        self.debug_log.log(Direction::In, line);
+    }
+
+    pub fn log_trace(&mut self, line: &str) {
+        self.debug_log.log(Direction::Trace, line);
+    }
+}
+
+impl Write for LoggingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.partial_line.extend_from_slice(&buf[..written]);
+        while let Some(end) = self.partial_line.iter().position(|b| *b == b'\n') {
+            let line = self.partial_line.drain(..=end).collect::<Vec<u8>>();
+            self.debug_log
+                .log(Direction::Out, String::from_utf8_lossy(&line).as_ref());
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
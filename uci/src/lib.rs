@@ -1,9 +1,11 @@
 pub use parser::{Parser, ParserMessage};
+pub use uci_move::UciMove;
 pub use uci_out::UciOut;
 
 pub mod uci_in;
 pub mod uci_option;
 
+mod debug_log;
 mod parser;
 mod uci_move;
 mod uci_out;
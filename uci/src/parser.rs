@@ -22,6 +22,12 @@ pub enum UciError {
     InvalidArgument(String),
     #[error("Uci error: Unknown command `{0}`")]
     UnknownCommand(String),
+    #[error("Uci error: Invalid value for option `{0}`: {1}")]
+    InvalidOptionValue(String, String),
+    #[error("Uci error: No position set")]
+    NoPosition,
+    #[error("Uci error: Illegal move `{0}` at index {1} of the move list in position `{2}`")]
+    IllegalMove(String, usize, String),
 }
 
 impl Parser {
@@ -38,6 +44,7 @@ impl Parser {
         engine: &mut Engine,
     ) -> Result<Option<ParserMessage>, Box<dyn Error + '_>> {
         debug_assert!(s.ends_with('\n') || s.is_empty());
+        self.uci_out.log_input(s);
         // From the UCI specification:
         // If the engine or the GUI receives an unknown command or token it should just
         // ignore it and try to parse the rest of the string in this line.
@@ -1,9 +1,15 @@
+pub mod bench;
+pub mod d;
 pub mod debug;
+pub mod eval;
+pub mod genfens;
 pub mod go;
 pub mod is_ready;
+pub mod perft;
 pub mod position;
 pub mod quit;
 pub mod set_option;
+pub mod speedtest;
 pub mod stop;
 pub mod uci;
 pub mod ucinewgame;
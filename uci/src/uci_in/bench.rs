@@ -0,0 +1,52 @@
+use crate::parser::{ParserMessage, UciError};
+use crate::UciOut;
+use engine::Engine;
+use eval::complex::Complex;
+use search::alpha_beta::AlphaBeta;
+use search::bench::{self as search_bench, BENCH_HASH_BYTES, DEFAULT_BENCH_DEPTH};
+use std::error::Error;
+
+// Not part of the UCI protocol. Searches a fixed set of positions and prints
+// the total node count, which serves as a signature of the search.
+pub fn run_command(
+    uci_out: &mut UciOut,
+    args: &str,
+    _engine: &mut Engine,
+) -> Result<Option<ParserMessage>, Box<dyn Error>> {
+    let depth = parse_depth(args)?;
+    run(uci_out, depth)?;
+    Ok(None)
+}
+
+pub fn parse_depth(args: &str) -> Result<usize, Box<dyn Error>> {
+    let mut words = args.split_whitespace();
+    let depth = match words.next() {
+        Some(d) => match d.parse::<usize>() {
+            Ok(depth) if depth > 0 => depth,
+            _ => return Err(Box::new(UciError::InvalidArgument(d.to_string()))),
+        },
+        None => DEFAULT_BENCH_DEPTH,
+    };
+    if words.next().is_some() {
+        return Err(Box::new(UciError::InvalidArgument(
+            args.trim_end().to_string(),
+        )));
+    }
+    Ok(depth)
+}
+
+// Runs the bench on a separate search instance, independent of the engine's
+// hash size and search state
+pub fn run(uci_out: &mut UciOut, depth: usize) -> Result<(), Box<dyn Error>> {
+    let mut search_algo = AlphaBeta::new(Box::new(Complex::new()), BENCH_HASH_BYTES);
+    let mut index = 0;
+    let mut res = Ok(());
+    let (nodes, time) = search_bench::bench(&mut search_algo, depth, |fen, search_result| {
+        if res.is_ok() {
+            res = uci_out.bench_position(index, fen, search_result);
+        }
+        index += 1;
+    });
+    res?;
+    uci_out.bench_total(nodes, time)
+}
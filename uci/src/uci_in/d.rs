@@ -0,0 +1,24 @@
+use crate::parser::{ParserMessage, UciError};
+use crate::UciOut;
+use engine::Engine;
+use std::error::Error;
+
+// Not part of the UCI protocol. Prints the current position for debugging.
+pub fn run_command(
+    uci_out: &mut UciOut,
+    args: &str,
+    engine: &mut Engine,
+) -> Result<Option<ParserMessage>, Box<dyn Error>> {
+    // There must be no arguments after "d"
+    if !args.trim().is_empty() {
+        return Err(Box::new(UciError::InvalidArgument(
+            args.trim_end().to_string(),
+        )));
+    }
+
+    match (engine.position(), engine.position_hash()) {
+        (Some(pos), Some(hash)) => uci_out.position(pos, hash)?,
+        _ => return Err(Box::new(UciError::NoPosition)),
+    }
+    Ok(None)
+}
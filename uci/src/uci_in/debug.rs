@@ -6,16 +6,18 @@ use std::error::Error;
 pub fn run_command(
     uci_out: &mut UciOut,
     args: &str,
-    _engine: &mut Engine,
+    engine: &mut Engine,
 ) -> Result<Option<ParserMessage>, Box<dyn Error>> {
     match args.trim() {
         "on" => {
             uci_out.set_debug(true);
+            engine.set_debug(true);
             uci_out.info_string("debug on")?;
         }
         "off" => {
             uci_out.info_string("debug off")?;
             uci_out.set_debug(false);
+            engine.set_debug(false);
         }
         _ => {
             return Err(Box::new(UciError::InvalidArgument(format!(
@@ -0,0 +1,26 @@
+use crate::parser::{ParserMessage, UciError};
+use crate::UciOut;
+use engine::Engine;
+use eval::complex::Complex;
+use std::error::Error;
+
+// Not part of the UCI protocol. Prints the contribution of each evaluation
+// term for the current position.
+pub fn run_command(
+    uci_out: &mut UciOut,
+    args: &str,
+    engine: &mut Engine,
+) -> Result<Option<ParserMessage>, Box<dyn Error>> {
+    // There must be no arguments after "eval"
+    if !args.trim().is_empty() {
+        return Err(Box::new(UciError::InvalidArgument(
+            args.trim_end().to_string(),
+        )));
+    }
+
+    match engine.position() {
+        Some(pos) => uci_out.eval_trace(&Complex::new().eval_trace(pos))?,
+        None => return Err(Box::new(UciError::NoPosition)),
+    }
+    Ok(None)
+}
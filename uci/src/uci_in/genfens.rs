@@ -0,0 +1,169 @@
+use crate::parser::{ParserMessage, UciError};
+use crate::UciOut;
+use engine::{Engine, Variant};
+use movegen::fen::Fen;
+use movegen::move_generator::MoveGenerator;
+use movegen::position::Position;
+use movegen::position_history::PositionHistory;
+use movegen::r#move::MoveList;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+
+pub const DEFAULT_GENFENS_PLIES: usize = 8;
+
+// Give up if this many attempts per requested position didn't yield enough
+// unique positions, e.g. because the random ply depth is very small
+const MAX_ATTEMPTS_PER_POSITION: usize = 1000;
+
+// Not part of the UCI protocol. Prints opening positions for selfplay data
+// generation, as expected by OpenBench:
+// genfens <count> [seed <seed>] [book <path|None>] [plies <plies>]
+// Each position is reached by random legal moves from the start position or
+// from a random position of the book (one FEN or EPD per line). The output
+// only depends on the arguments.
+pub fn run_command(
+    uci_out: &mut UciOut,
+    args: &str,
+    engine: &mut Engine,
+) -> Result<Option<ParserMessage>, Box<dyn Error>> {
+    run(uci_out, args, engine.variant())?;
+    Ok(None)
+}
+
+pub fn run(uci_out: &mut UciOut, args: &str, variant: Variant) -> Result<(), Box<dyn Error>> {
+    let invalid = || {
+        Box::new(UciError::InvalidArgument(format!(
+            "genfens {}",
+            args.trim()
+        )))
+    };
+    let mut words = args.split_whitespace();
+    let count = match words.next().map(|w| w.parse::<usize>()) {
+        Some(Ok(count)) => count,
+        _ => return Err(invalid()),
+    };
+    let mut seed = 0;
+    let mut book = None;
+    let mut plies = DEFAULT_GENFENS_PLIES;
+    while let Some(name) = words.next() {
+        let value = words.next().ok_or_else(invalid)?;
+        match name {
+            "seed" => seed = value.parse::<u64>().map_err(|_| invalid())?,
+            "book" if value.eq_ignore_ascii_case("none") => book = None,
+            "book" => book = Some(value),
+            "plies" => plies = value.parse::<usize>().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        }
+    }
+
+    let start_positions = match book {
+        Some(path) => read_book(path, variant)?,
+        None => vec![Position::initial()],
+    };
+    let fens = generate(count, seed, &start_positions, plies)
+        .iter()
+        .map(|pos| match variant {
+            Variant::Standard => Fen::pos_to_str(pos),
+            Variant::Chess960(_, _) => Fen::pos_to_str_chess_960(pos),
+        })
+        .collect::<Vec<String>>();
+    for fen in fens.iter() {
+        uci_out.genfens(fen)?;
+    }
+    if fens.len() < count {
+        uci_out.warn(&format!(
+            "genfens found only {} unique positions, try more plies",
+            fens.len()
+        ))?;
+    }
+    Ok(())
+}
+
+// Returns up to count unique positions, each reached by playing random moves
+// from one of the start positions. Positions without legal moves are skipped.
+pub fn generate(
+    count: usize,
+    seed: u64,
+    start_positions: &[Position],
+    plies: usize,
+) -> Vec<Position> {
+    let mut rng = Rng::new(seed);
+    let mut positions = Vec::with_capacity(count);
+    let mut seen = HashSet::new();
+    let mut move_list = MoveList::new();
+    let max_attempts = count.saturating_mul(MAX_ATTEMPTS_PER_POSITION);
+    for _ in 0..max_attempts {
+        if positions.len() == count || start_positions.is_empty() {
+            break;
+        }
+        let start = &start_positions[rng.below(start_positions.len())];
+        let mut pos_hist = PositionHistory::new(start.clone());
+        for _ in 0..plies {
+            MoveGenerator::generate_moves(&mut move_list, pos_hist.current_pos());
+            if move_list.is_empty() {
+                break;
+            }
+            pos_hist.do_move(move_list[rng.below(move_list.len())]);
+        }
+        MoveGenerator::generate_moves(&mut move_list, pos_hist.current_pos());
+        if !move_list.is_empty() && seen.insert(pos_hist.current_pos_hash()) {
+            positions.push(pos_hist.current_pos().clone());
+        }
+    }
+    positions
+}
+
+fn read_book(path: &str, variant: Variant) -> Result<Vec<Position>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| UciError::InvalidArgument(format!("book {path}: {e}")))?;
+    let mut positions = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let pos = parse_book_line(line, variant).ok_or_else(|| {
+            UciError::InvalidArgument(format!("book {path}: invalid position `{line}`"))
+        })?;
+        positions.push(pos);
+    }
+    if positions.is_empty() {
+        return Err(Box::new(UciError::InvalidArgument(format!(
+            "book {path}: no positions"
+        ))));
+    }
+    Ok(positions)
+}
+
+// A FEN or an EPD line. EPD lines have operations instead of the clocks.
+fn parse_book_line(line: &str, variant: Variant) -> Option<Position> {
+    let str_to_pos = match variant {
+        Variant::Standard => Fen::str_to_pos,
+        Variant::Chess960(_, _) => Fen::str_to_pos_chess_960,
+    };
+    let fields = line.split_whitespace().collect::<Vec<&str>>();
+    let parse = |n: usize| str_to_pos(&fields[..fields.len().min(n)].join(" ")).ok();
+    parse(6).or_else(|| parse(4))
+}
+
+// Xorshift* generator, so that the output only depends on the seed
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state must not be zero
+        match seed.wrapping_add(0x9e37_79b9_7f4a_7c15) {
+            0 => Self(1),
+            state => Self(state),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        debug_assert!(n > 0);
+        (self.next_u64() % n as u64) as usize
+    }
+}
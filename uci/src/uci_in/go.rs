@@ -3,6 +3,7 @@ use crate::uci_move::UciMove;
 use crate::UciOut;
 use engine::{Engine, EngineError};
 use movegen::r#move::MoveList;
+use search::search::MAX_PLY;
 use search::SearchOptions;
 use std::collections::HashSet;
 use std::error::Error;
@@ -24,8 +25,16 @@ fn parse_options(
 ) -> Result<SearchOptions, Box<dyn Error>> {
     let mut options = SearchOptions::default();
     let mut seen_options = HashSet::new();
+    let mut ignored_tokens = Vec::new();
     let mut s = go_args;
     while let Some((opt, tail)) = split_first_word(s) {
+        if !is_option(opt) {
+            // From the UCI specification: unknown tokens are ignored and the
+            // rest of the line is parsed
+            ignored_tokens.push(opt);
+            s = tail;
+            continue;
+        }
         if !seen_options.insert(opt) {
             return Err(Box::new(UciError::InvalidArgument(format!(
                 "Option `{opt}` must not appear more than once in\ngo {go_args}",
@@ -52,15 +61,49 @@ fn parse_options(
             }
             _ => {
                 return Err(Box::new(UciError::InvalidArgument(format!(
-                    "go {}",
-                    go_args.trim_end()
+                    "Unknown option `{opt}` in\ngo {go_args}",
                 ))))
             }
         }
     }
+    if !ignored_tokens.is_empty() {
+        uci_out.warn(&format!(
+            "ignoring unknown tokens `{}` in command: go {}",
+            ignored_tokens.join(" "),
+            go_args.trim()
+        ))?;
+    }
+    if let Some(depth) = options.depth.filter(|&d| d > MAX_PLY) {
+        uci_out.warn(&format!(
+            "depth {depth} exceeds the maximum depth, using `depth {MAX_PLY}` instead",
+        ))?;
+        options.depth = Some(MAX_PLY);
+    }
+    // Without any limits, `go` searches until `stop`, like `go infinite`
+    if seen_options.is_empty() {
+        options.infinite = true;
+    }
     Ok(options)
 }
 
+fn is_option(token: &str) -> bool {
+    matches!(
+        token,
+        "searchmoves"
+            | "ponder"
+            | "wtime"
+            | "btime"
+            | "winc"
+            | "binc"
+            | "movestogo"
+            | "depth"
+            | "nodes"
+            | "mate"
+            | "movetime"
+            | "infinite"
+    )
+}
+
 fn parse_usize<'a>(
     uci_out: &UciOut,
     opt: &str,
@@ -6,7 +6,7 @@ use std::error::Error;
 pub fn run_command(
     uci_out: &mut UciOut,
     args: &str,
-    _engine: &mut Engine,
+    engine: &mut Engine,
 ) -> Result<Option<ParserMessage>, Box<dyn Error>> {
     // There must be no arguments after "isready"
     if !args.trim().is_empty() {
@@ -15,6 +15,8 @@ pub fn run_command(
         )));
     }
 
+    // Only waits for pending option changes, not for a running search
+    engine.wait_until_ready();
     uci_out.ready_ok()?;
     Ok(None)
 }
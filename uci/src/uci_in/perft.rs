@@ -0,0 +1,39 @@
+use crate::parser::{ParserMessage, UciError};
+use crate::UciOut;
+use engine::Engine;
+use movegen::position_history::PositionHistory;
+use perft::{PerformanceTester, PERFT_HASH_BYTES};
+use std::error::Error;
+use std::time::Instant;
+
+// Not part of the UCI protocol. Counts the leaf nodes of the move generation
+// tree of the current position.
+pub fn run_command(
+    uci_out: &mut UciOut,
+    args: &str,
+    engine: &mut Engine,
+) -> Result<Option<ParserMessage>, Box<dyn Error>> {
+    let mut words = args.split_whitespace();
+    let depth = match (words.next(), words.next()) {
+        (Some(d), None) => d
+            .parse::<usize>()
+            .map_err(|_| UciError::InvalidArgument(args.trim_end().to_string()))?,
+        _ => {
+            return Err(Box::new(UciError::InvalidArgument(
+                args.trim_end().to_string(),
+            )))
+        }
+    };
+    let pos = match engine.position() {
+        Some(pos) => pos.clone(),
+        None => return Err(Box::new(UciError::NoPosition)),
+    };
+
+    // The table only lives until the command is finished. It has a fixed small
+    // size, so that it doesn't double the memory usage of the engine.
+    let mut perft = PerformanceTester::new(PositionHistory::new(pos), PERFT_HASH_BYTES);
+    let start = Instant::now();
+    let nodes = perft.count_nodes(depth);
+    uci_out.perft(nodes, start.elapsed())?;
+    Ok(None)
+}
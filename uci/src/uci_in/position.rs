@@ -5,114 +5,176 @@ use engine::{Engine, Variant};
 use movegen::fen::Fen;
 use movegen::position::Position as Pos;
 use movegen::position_history::PositionHistory;
+use movegen::r#move::Move;
 use std::error::Error;
 
 pub fn run_command(
-    _uci_out: &mut UciOut,
+    uci_out: &mut UciOut,
     args: &str,
     engine: &mut Engine,
 ) -> Result<Option<ParserMessage>, Box<dyn Error>> {
-    let (mut pos_hist, moves_str) = match split_first_word(args) {
-        Some(("fen", tail)) => parse_fen(tail, engine)?,
-        Some(("startpos", tail)) => (PositionHistory::new(Pos::initial()), tail),
-        _ => {
-            return Err(Box::new(UciError::InvalidArgument(format!(
-                "position {}",
-                args.trim_end()
-            ))))
+    // From the UCI specification: unknown tokens are ignored and the rest of
+    // the line is parsed
+    let mut ignored_tokens = Vec::new();
+    let mut s = args;
+    let (pos_hist, mut moves_str) = loop {
+        match split_first_word(s) {
+            Some(("fen", tail)) => break parse_fen(tail, engine)?,
+            Some(("startpos", tail)) => break (PositionHistory::new(Pos::initial()), tail),
+            Some((token, tail)) => {
+                ignored_tokens.push(token);
+                s = tail;
+            }
+            None => {
+                return Err(Box::new(UciError::InvalidArgument(format!(
+                    "position {}",
+                    args.trim_end()
+                ))))
+            }
+        }
+    };
+    let moves = loop {
+        match split_first_word(moves_str) {
+            Some(("moves", tail)) => break Some(tail),
+            Some((token, tail)) => {
+                ignored_tokens.push(token);
+                moves_str = tail;
+            }
+            None => break None,
         }
     };
+    if !ignored_tokens.is_empty() {
+        uci_out.warn(&format!(
+            "ignoring unknown tokens `{}` in command: position {}",
+            ignored_tokens.join(" "),
+            args.trim()
+        ))?;
+    }
 
+    let move_strs = moves
+        .map(|tail| tail.split_whitespace().collect::<Vec<_>>())
+        .unwrap_or_default();
     let var = engine.variant();
-
-    match split_first_word(moves_str) {
-        Some(("moves", tail)) => {
-            let iter = tail.split_whitespace();
-            match var {
-                Variant::Standard => {
-                    for move_str in iter {
-                        match UciMove::str_to_move(pos_hist.current_pos(), move_str) {
-                            Some(m) => pos_hist.do_move(m),
-                            None => {
-                                return Err(Box::new(UciError::InvalidArgument(format!(
-                                    "Invalid move `{}` in command: position {}",
-                                    move_str,
-                                    args.trim_end()
-                                ))))
-                            }
-                        }
-                    }
-                }
-                Variant::Chess960(king_rook, queen_rook) => {
-                    for move_str in iter {
-                        match UciMove::str_to_move_chess_960(
-                            pos_hist.current_pos(),
-                            move_str,
-                            king_rook,
-                            queen_rook,
-                        ) {
-                            Some(m) => pos_hist.do_move(m),
-                            None => {
-                                return Err(Box::new(UciError::InvalidArgument(format!(
-                                    "Invalid move `{}` in command: position {}",
-                                    move_str,
-                                    args.trim_end()
-                                ))))
-                            }
-                        }
-                    }
-                }
+    // GUIs resend the whole game for every move. Only the new moves are played
+    // if the game continues the one already set.
+    let mut pos_hist = continue_game(&pos_hist, &move_strs, engine).unwrap_or(pos_hist);
+    let moves_played = pos_hist.moves().len();
+    for (index, move_str) in move_strs.iter().enumerate().skip(moves_played) {
+        match str_to_move(var, pos_hist.current_pos(), move_str) {
+            Some(m) => do_move(&mut pos_hist, m),
+            None => {
+                // Keep the position before the illegal move, so that it can be
+                // inspected (e.g. with `d`)
+                let fen = match var {
+                    Variant::Standard => Fen::pos_to_str(pos_hist.current_pos()),
+                    Variant::Chess960(_, _) => Fen::pos_to_str_chess_960(pos_hist.current_pos()),
+                };
+                engine.set_position_history(Some(pos_hist));
+                return Err(Box::new(UciError::IllegalMove(
+                    move_str.to_string(),
+                    index,
+                    fen,
+                )));
             }
         }
-        None => {}
-        _ => {
-            return Err(Box::new(UciError::InvalidArgument(format!(
-                "position {}",
-                args.trim_end()
-            ))))
-        }
-    };
+    }
 
     engine.set_position_history(Some(pos_hist));
     Ok(None)
 }
 
+// The engine's current game, if the new game starts from the same position
+// and the moves played so far are a prefix of the new moves
+fn continue_game(
+    start: &PositionHistory,
+    move_strs: &[&str],
+    engine: &Engine,
+) -> Option<PositionHistory> {
+    let current = engine.position_history()?;
+    let played = current.moves();
+    if played.len() > move_strs.len()
+        || played
+            .iter()
+            .zip(move_strs)
+            .any(|(m, s)| move_to_str(engine.variant(), *m) != *s)
+    {
+        return None;
+    }
+    let mut current_start = current.clone();
+    for _ in played {
+        current_start.undo_last_move();
+    }
+    match current_start.current_pos() == start.current_pos() {
+        true => Some(current.clone()),
+        false => None,
+    }
+}
+
+fn str_to_move(var: Variant, pos: &Pos, move_str: &str) -> Option<Move> {
+    match var {
+        Variant::Standard => UciMove::str_to_move(pos, move_str),
+        Variant::Chess960(king_rook, queen_rook) => {
+            UciMove::str_to_move_chess_960(pos, move_str, king_rook, queen_rook)
+        }
+    }
+}
+
+fn move_to_str(var: Variant, m: Move) -> String {
+    match var {
+        Variant::Standard => UciMove::move_to_str(m),
+        Variant::Chess960(king_rook, queen_rook) => {
+            UciMove::move_to_str_chess_960(m, king_rook, queen_rook)
+        }
+    }
+}
+
+fn do_move(pos_hist: &mut PositionHistory, m: Move) {
+    pos_hist.do_move(m);
+    // A null move (`0000`) passes the turn. Positions on both sides of it
+    // must not count as repetitions, so the history starts anew.
+    if m == Move::NULL {
+        *pos_hist = PositionHistory::new(pos_hist.current_pos().clone());
+    }
+}
+
+// The FEN ends before `moves`. Abbreviated FENs with fewer than six fields are
+// accepted.
 fn parse_fen<'a>(
     args: &'a str,
-    engine: &'a Engine,
+    engine: &Engine,
 ) -> Result<(PositionHistory, &'a str), Box<dyn Error>> {
+    const MAX_FEN_FIELDS: usize = 6;
     let trimmed = args.trim_start();
-    match trimmed
-        .chars()
-        .scan(0, |count, c| {
-            *count += c.is_whitespace() as usize;
-            Some(*count)
-        })
-        .position(|c| c == 6)
-    {
-        Some(fen_end) => {
-            let opt_pos = match engine.variant() {
-                Variant::Standard => Fen::str_to_pos(&trimmed[..fen_end]),
-                Variant::Chess960(_, _) => Fen::str_to_pos_chess_960(&trimmed[..fen_end]),
-            };
-            match opt_pos {
-                Ok(pos) => {
-                    if let Variant::Chess960(_, _) = engine.variant() {
-                        engine.set_variant(Variant::Chess960(
-                            pos.kingside_castling_file(),
-                            pos.queenside_castling_file(),
-                        ));
-                    }
-                    Ok((PositionHistory::new(pos), &trimmed[fen_end..]))
-                }
-                Err(e) => Err(Box::new(UciError::InvalidArgument(format!(
-                    "position fen {args}\n{e}",
-                )))),
-            }
+    let mut tail = trimmed;
+    for _ in 0..MAX_FEN_FIELDS {
+        match split_first_word(tail) {
+            Some(("moves", _)) | None => break,
+            Some((_, t)) => tail = t,
         }
-        None => Err(Box::new(UciError::InvalidArgument(format!(
+    }
+    let fen = &trimmed[..trimmed.len() - tail.len()];
+    if fen.is_empty() {
+        return Err(Box::new(UciError::InvalidArgument(format!(
             "position fen {}",
             args.trim_end()
+        ))));
+    }
+    let opt_pos = match engine.variant() {
+        Variant::Standard => Fen::str_to_pos(fen),
+        Variant::Chess960(_, _) => Fen::str_to_pos_chess_960(fen),
+    };
+    match opt_pos {
+        Ok(pos) => {
+            if let Variant::Chess960(_, _) = engine.variant() {
+                engine.set_variant(Variant::Chess960(
+                    pos.kingside_castling_file(),
+                    pos.queenside_castling_file(),
+                ));
+            }
+            Ok((PositionHistory::new(pos), tail))
+        }
+        Err(e) => Err(Box::new(UciError::InvalidArgument(format!(
+            "position fen {args}\n{e}",
         )))),
     }
 }
@@ -4,7 +4,7 @@ use engine::Engine;
 use std::error::Error;
 
 pub fn run_command(
-    _uci_out: &mut UciOut,
+    uci_out: &mut UciOut,
     args: &str,
     _engine: &mut Engine,
 ) -> Result<Option<ParserMessage>, Box<dyn Error>> {
@@ -15,5 +15,7 @@ pub fn run_command(
         )));
     }
 
+    // Stop a running speedtest and wait until its result is printed
+    uci_out.stop_speedtest();
     Ok(Some(ParserMessage::Quit))
 }
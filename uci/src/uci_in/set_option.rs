@@ -14,7 +14,12 @@ pub fn run_command(
             let mut name_parts = Vec::new();
             let mut value = None;
             let mut remaining = args_after_name;
-            while let Some((word, tail)) = split_first_word(remaining) {
+            loop {
+                // The last word has no whitespace after it
+                let (word, tail) = split_first_word(remaining).unwrap_or((remaining.trim(), ""));
+                if word.is_empty() {
+                    break;
+                }
                 let word_lower = word.to_lowercase();
                 if word_lower == "value" {
                     value = Some(tail.to_string());
@@ -35,32 +40,37 @@ pub fn run_command(
                 None => return make_err_invalid_argument(args),
             };
 
-            match &opt.r#type {
-                OptionType::Check(props) => {
-                    let val = match value {
-                        Some(v) => match v.parse::<bool>() {
-                            Ok(v) => v,
-                            Err(_) => return make_err_invalid_argument(args),
-                        },
-                        None => return make_err_invalid_argument(args),
-                    };
-                    uci_out.info_string(&(props.fun)(engine, val))?;
-                }
-                OptionType::Spin(props) => {
-                    let val = match value {
-                        Some(v) => match v.parse::<i64>() {
-                            Ok(v) => v,
-                            Err(_) => return make_err_invalid_argument(args),
-                        },
-                        None => return make_err_invalid_argument(args),
-                    };
-                    if val < props.min || val > props.max {
-                        return make_err_invalid_argument(args);
-                    }
-                    uci_out.info_string(&(props.fun)(engine, val))?;
-                }
-                _ => todo!("Implement other types!"),
-            }
+            let value = value.as_deref();
+            let invalid_value = |reason| -> Result<Option<ParserMessage>, Box<dyn Error>> {
+                Err(Box::new(UciError::InvalidOptionValue(
+                    String::from(opt.name),
+                    reason,
+                )))
+            };
+            // The option is only changed if the value is valid
+            let info = match &opt.r#type {
+                OptionType::Button(props) => match props.parse(value) {
+                    Ok(()) => (props.fun)(engine),
+                    Err(reason) => return invalid_value(reason),
+                },
+                OptionType::Check(props) => match props.parse(value) {
+                    Ok(val) => (props.fun)(engine, val),
+                    Err(reason) => return invalid_value(reason),
+                },
+                OptionType::Combo(props) => match props.parse(value) {
+                    Ok(val) => (props.fun)(engine, val),
+                    Err(reason) => return invalid_value(reason),
+                },
+                OptionType::Spin(props) => match props.parse(value) {
+                    Ok(val) => (props.fun)(engine, val),
+                    Err(reason) => return invalid_value(reason),
+                },
+                OptionType::String(props) => match props.parse(value) {
+                    Ok(val) => (props.fun)(engine, &val),
+                    Err(reason) => return invalid_value(reason),
+                },
+            };
+            uci_out.info_string(&info)?;
         }
         _ => return make_err_invalid_argument(args),
     };
@@ -0,0 +1,80 @@
+use crate::parser::{ParserMessage, UciError};
+use crate::UciOut;
+use crossbeam_channel::{unbounded, Sender};
+use engine::Engine;
+use eval::complex::Complex;
+use search::alpha_beta::AlphaBeta;
+use search::bench::{self as search_bench, BENCH_HASH_BYTES, SPEEDTEST_DEPTH};
+use search::search::SearchCommand;
+use std::error::Error;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub const DEFAULT_SPEEDTEST_SECONDS: u64 = 10;
+
+// Not part of the UCI protocol. Measures the search speed for the given number
+// of seconds. Unlike the bench, the result depends on the machine. The
+// speedtest runs in the background on a separate search instance with the
+// small hash table of the bench, so `stop` ends it early and the engine's
+// position and hash table are left untouched.
+pub fn run_command(
+    uci_out: &mut UciOut,
+    args: &str,
+    _engine: &mut Engine,
+) -> Result<Option<ParserMessage>, Box<dyn Error>> {
+    let mut words = args.split_whitespace();
+    let seconds = match (words.next(), words.next()) {
+        (None, _) => DEFAULT_SPEEDTEST_SECONDS,
+        (Some(s), None) => match s.parse::<u64>() {
+            Ok(seconds) if seconds > 0 => seconds,
+            _ => return Err(Box::new(UciError::InvalidArgument(s.to_string()))),
+        },
+        _ => {
+            return Err(Box::new(UciError::InvalidArgument(
+                args.trim_end().to_string(),
+            )))
+        }
+    };
+    let speedtest = Speedtest::start(Duration::from_secs(seconds), uci_out.clone());
+    uci_out.set_speedtest(speedtest);
+    Ok(None)
+}
+
+// Dropping the speedtest stops it and waits until the result is printed
+pub struct Speedtest {
+    command_sender: Sender<SearchCommand>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Speedtest {
+    pub fn start(duration: Duration, mut uci_out: UciOut) -> Self {
+        let (command_sender, command_receiver) = unbounded();
+        let thread = thread::spawn(move || {
+            let mut search_algo = AlphaBeta::new(Box::new(Complex::new()), BENCH_HASH_BYTES);
+            let (nodes, time) = search_bench::speedtest(
+                &mut search_algo,
+                SPEEDTEST_DEPTH,
+                duration,
+                &command_receiver,
+            );
+            // The search runs on a single thread
+            let _ = uci_out.speedtest(&[nodes], time);
+        });
+        Self {
+            command_sender,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Speedtest {
+    fn drop(&mut self) {
+        let _ = self.command_sender.send(SearchCommand::Stop);
+        if let Some(thread) = self.thread.take() {
+            // The speedtest thread may hold the last reference to the output
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@ use engine::Engine;
 use std::error::Error;
 
 pub fn run_command(
-    _uci_out: &mut UciOut,
+    uci_out: &mut UciOut,
     args: &str,
     engine: &mut Engine,
 ) -> Result<Option<ParserMessage>, Box<dyn Error>> {
@@ -16,5 +16,6 @@ pub fn run_command(
     }
 
     engine.stop();
+    uci_out.stop_speedtest();
     Ok(None)
 }
@@ -31,7 +31,7 @@ impl UciMove {
 
     pub fn str_to_move(pos: &Position, move_str: &str) -> Option<Move> {
         if move_str == "0000" {
-            return Some(Move::NULL);
+            return Self::null_move(pos);
         }
 
         let re_uci_move = Regex::new("([a-h][1-8]){2}[nbrq]?").unwrap();
@@ -48,6 +48,14 @@ impl UciMove {
             .copied()
     }
 
+    // Passing the turn is only legal if the side to move is not in check
+    fn null_move(pos: &Position) -> Option<Move> {
+        match pos.is_in_check(pos.side_to_move()) {
+            true => None,
+            false => Some(Move::NULL),
+        }
+    }
+
     pub fn move_to_str_chess_960(m: Move, king_rook: File, queen_rook: File) -> String {
         match m {
             Move::NULL => String::from("0000"),
@@ -78,7 +86,7 @@ impl UciMove {
         queen_rook: File,
     ) -> Option<Move> {
         if move_str == "0000" {
-            return Some(Move::NULL);
+            return Self::null_move(pos);
         }
 
         let re_uci_move = Regex::new("([a-h][1-8]){2}[nbrq]?").unwrap();
@@ -186,6 +194,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn str_to_null_move() {
+        let pos = Position::initial();
+        assert_eq!(Some(Move::NULL), UciMove::str_to_move(&pos, "0000"));
+        assert_eq!(
+            Some(Move::NULL),
+            UciMove::str_to_move_chess_960(&pos, "0000", File::H, File::A)
+        );
+
+        // In check
+        let pos = Fen::str_to_pos("4k3/8/8/8/8/8/8/4KR2 w - - 0 1").unwrap();
+        assert_eq!(Some(Move::NULL), UciMove::str_to_move(&pos, "0000"));
+        let pos = Fen::str_to_pos("4k3/8/8/8/8/8/8/4K2r w - - 0 1").unwrap();
+        assert_eq!(None, UciMove::str_to_move(&pos, "0000"));
+        assert_eq!(
+            None,
+            UciMove::str_to_move_chess_960(&pos, "0000", File::H, File::A)
+        );
+    }
+
     #[test]
     fn move_to_str_to_move_roundtrip() {
         // Position from https://www.chessprogramming.org/Perft_Results
@@ -1,16 +1,26 @@
-use engine::{Engine, Variant, DEFAULT_HASH_MB, DEFAULT_MOVE_OVERHEAD_MILLIS};
+use engine::{
+    Engine, Variant, DEFAULT_BOOK_DEPTH, DEFAULT_CONTEMPT, DEFAULT_ELO, DEFAULT_HASH_MB,
+    DEFAULT_MOVE_OVERHEAD_MILLIS, DEFAULT_SEED, DEFAULT_SKILL_LEVEL, DEFAULT_TRACE_DEPTH,
+    MAX_BOOK_DEPTH, MAX_CONTEMPT, MAX_HASH_MB, MAX_SEED, MAX_TRACE_DEPTH, MIN_BOOK_DEPTH,
+    MIN_CONTEMPT, MIN_HASH_MB,
+};
 use eval::Score;
 use movegen::file::File;
 use search::search_params::{AlphaBetaParams, SearchParamsEachAlgo};
+use search::strength::{MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO, MIN_SKILL_LEVEL};
 use std::time::Duration;
 
 #[allow(dead_code)]
 pub enum OptionType {
-    Button,
+    Button(ButtonProps),
     Check(CheckProps),
-    Combo,
+    Combo(ComboProps),
     Spin(SpinProps),
-    String,
+    String(StringProps),
+}
+
+pub struct ButtonProps {
+    pub fun: fn(&mut Engine) -> String,
 }
 
 pub struct CheckProps {
@@ -18,6 +28,12 @@ pub struct CheckProps {
     pub fun: fn(&mut Engine, value: bool) -> String,
 }
 
+pub struct ComboProps {
+    pub default: &'static str,
+    pub vars: &'static [&'static str],
+    pub fun: fn(&mut Engine, value: &str) -> String,
+}
+
 pub struct SpinProps {
     pub default: i64,
     pub min: i64,
@@ -25,21 +41,89 @@ pub struct SpinProps {
     pub fun: fn(&mut Engine, value: i64) -> String,
 }
 
+pub struct StringProps {
+    pub default: &'static str,
+    pub fun: fn(&mut Engine, value: &str) -> String,
+}
+
+// The parse methods check the value of a setoption command against the
+// declared type. The error describes why the value is invalid.
+
+impl ButtonProps {
+    pub fn parse(&self, value: Option<&str>) -> Result<(), String> {
+        match value {
+            None => Ok(()),
+            Some(_) => Err(String::from("a button doesn't take a value")),
+        }
+    }
+}
+
+impl CheckProps {
+    pub fn parse(&self, value: Option<&str>) -> Result<bool, String> {
+        match value.map(|v| v.to_lowercase()).as_deref() {
+            Some("true") => Ok(true),
+            Some("false") => Ok(false),
+            _ => Err(String::from("expected true or false")),
+        }
+    }
+}
+
+impl ComboProps {
+    // Returns the declared var matching the value
+    pub fn parse(&self, value: Option<&str>) -> Result<&'static str, String> {
+        let expected = || format!("expected one of {}", self.vars.join(", "));
+        match value {
+            Some(v) => match self.vars.iter().find(|var| var.eq_ignore_ascii_case(v)) {
+                Some(var) => Ok(var),
+                None => Err(expected()),
+            },
+            None => Err(expected()),
+        }
+    }
+}
+
+impl SpinProps {
+    pub fn parse(&self, value: Option<&str>) -> Result<i64, String> {
+        let out_of_range = || format!("expected an integer from {} to {}", self.min, self.max);
+        match value.map(|v| v.parse::<i64>()) {
+            Some(Ok(v)) if (self.min..=self.max).contains(&v) => Ok(v),
+            _ => Err(out_of_range()),
+        }
+    }
+}
+
+impl StringProps {
+    pub fn parse(&self, value: Option<&str>) -> Result<String, String> {
+        match value {
+            // By convention, "<empty>" is used for empty strings
+            Some("<empty>") => Ok(String::new()),
+            Some(v) => Ok(String::from(v)),
+            None => Err(String::from("expected a string")),
+        }
+    }
+}
+
 pub struct UciOption {
     pub name: &'static str,
     pub r#type: OptionType,
 }
 
-pub const OPTIONS: [UciOption; 3] = [
+pub const OPTIONS: [UciOption; 17] = [
     UciOption {
         name: "Hash",
         r#type: OptionType::Spin(SpinProps {
             default: DEFAULT_HASH_MB as i64,
-            min: 1,
-            max: 65536,
+            min: MIN_HASH_MB as i64,
+            max: MAX_HASH_MB as i64,
             fun: set_hash_size,
         }),
     },
+    UciOption {
+        name: "Clear Hash",
+        r#type: OptionType::Button(ButtonProps {
+            fun: clear_hash_table,
+        }),
+    },
     UciOption {
         name: "Move Overhead",
         r#type: OptionType::Spin(SpinProps {
@@ -49,6 +133,38 @@ pub const OPTIONS: [UciOption; 3] = [
             fun: set_move_overhead,
         }),
     },
+    UciOption {
+        name: "Contempt",
+        r#type: OptionType::Spin(SpinProps {
+            default: DEFAULT_CONTEMPT as i64,
+            min: MIN_CONTEMPT as i64,
+            max: MAX_CONTEMPT as i64,
+            fun: set_contempt,
+        }),
+    },
+    UciOption {
+        name: "OwnBook",
+        r#type: OptionType::Check(CheckProps {
+            default: false,
+            fun: set_own_book,
+        }),
+    },
+    UciOption {
+        name: "BookFile",
+        r#type: OptionType::String(StringProps {
+            default: "",
+            fun: set_book_file,
+        }),
+    },
+    UciOption {
+        name: "BookDepth",
+        r#type: OptionType::Spin(SpinProps {
+            default: DEFAULT_BOOK_DEPTH as i64,
+            min: MIN_BOOK_DEPTH as i64,
+            max: MAX_BOOK_DEPTH as i64,
+            fun: set_book_depth,
+        }),
+    },
     UciOption {
         name: "UCI_Chess960",
         r#type: OptionType::Check(CheckProps {
@@ -56,19 +172,121 @@ pub const OPTIONS: [UciOption; 3] = [
             fun: set_chess_960,
         }),
     },
+    UciOption {
+        name: "UCI_ShowWDL",
+        r#type: OptionType::Check(CheckProps {
+            default: false,
+            fun: set_show_wdl,
+        }),
+    },
+    UciOption {
+        name: "Skill Level",
+        r#type: OptionType::Spin(SpinProps {
+            default: DEFAULT_SKILL_LEVEL as i64,
+            min: MIN_SKILL_LEVEL as i64,
+            max: MAX_SKILL_LEVEL as i64,
+            fun: set_skill_level,
+        }),
+    },
+    UciOption {
+        name: "UCI_LimitStrength",
+        r#type: OptionType::Check(CheckProps {
+            default: false,
+            fun: set_limit_strength,
+        }),
+    },
+    UciOption {
+        name: "UCI_Elo",
+        r#type: OptionType::Spin(SpinProps {
+            default: DEFAULT_ELO as i64,
+            min: MIN_ELO as i64,
+            max: MAX_ELO as i64,
+            fun: set_elo,
+        }),
+    },
+    UciOption {
+        name: "RandomizeEqualMoves",
+        r#type: OptionType::Check(CheckProps {
+            default: false,
+            fun: set_randomize_equal_moves,
+        }),
+    },
+    UciOption {
+        name: "Seed",
+        r#type: OptionType::Spin(SpinProps {
+            default: DEFAULT_SEED as i64,
+            min: 0,
+            max: MAX_SEED as i64,
+            fun: set_seed,
+        }),
+    },
+    UciOption {
+        name: "UCI_AnalyseMode",
+        r#type: OptionType::Check(CheckProps {
+            default: false,
+            fun: set_analyse_mode,
+        }),
+    },
+    UciOption {
+        name: "Debug Log File",
+        r#type: OptionType::String(StringProps {
+            default: "",
+            fun: set_debug_log_file,
+        }),
+    },
+    UciOption {
+        name: "TraceDepth",
+        r#type: OptionType::Spin(SpinProps {
+            default: DEFAULT_TRACE_DEPTH as i64,
+            min: 0,
+            max: MAX_TRACE_DEPTH as i64,
+            fun: set_trace_depth,
+        }),
+    },
 ];
 
 fn set_hash_size(engine: &mut Engine, megabytes: i64) -> String {
     let bytes = 2_usize.pow(20) * megabytes as usize;
-    engine.set_hash_size(bytes);
+    let megabytes = engine.set_hash_size(bytes) / 2_usize.pow(20);
     format!("Hash set to {megabytes} MB")
 }
 
+fn clear_hash_table(engine: &mut Engine) -> String {
+    engine.clear_hash_table();
+    String::from("Hash cleared")
+}
+
 fn set_move_overhead(engine: &mut Engine, move_overhead: i64) -> String {
     engine.set_move_overhead(Duration::from_millis(move_overhead as u64));
     format!("Move Overhead set to {move_overhead} ms")
 }
 
+fn set_contempt(engine: &mut Engine, contempt: i64) -> String {
+    engine.set_contempt(contempt as Score);
+    format!("Contempt set to {contempt}")
+}
+
+fn set_own_book(engine: &mut Engine, enable: bool) -> String {
+    engine.set_own_book(enable);
+    match enable {
+        true => String::from("Own book enabled"),
+        false => String::from("Own book disabled"),
+    }
+}
+
+fn set_book_file(engine: &mut Engine, path: &str) -> String {
+    match engine.set_book_file(path) {
+        Ok(_) if path.is_empty() => String::from("Book file removed"),
+        Ok(entries) => format!("Book file {path} loaded with {entries} entries"),
+        Err(e) => format!("Error loading book file {path}: {e}"),
+    }
+}
+
+fn set_book_depth(engine: &mut Engine, depth: i64) -> String {
+    engine.set_book_depth(depth as usize);
+    format!("BookDepth set to {depth}")
+}
+
 fn set_chess_960(engine: &mut Engine, enable: bool) -> String {
     engine.set_variant(Variant::Chess960(File::H, File::A));
     match enable {
@@ -77,6 +295,53 @@ fn set_chess_960(engine: &mut Engine, enable: bool) -> String {
     }
 }
 
+fn set_show_wdl(engine: &mut Engine, enable: bool) -> String {
+    engine.set_show_wdl(enable);
+    match enable {
+        true => String::from("WDL output enabled"),
+        false => String::from("WDL output disabled"),
+    }
+}
+
+fn set_limit_strength(engine: &mut Engine, enable: bool) -> String {
+    engine.set_limit_strength(enable);
+    match enable {
+        true => String::from("Strength limit enabled"),
+        false => String::from("Strength limit disabled"),
+    }
+}
+
+fn set_elo(engine: &mut Engine, elo: i64) -> String {
+    engine.set_elo(elo as u16);
+    format!("UCI_Elo set to {elo}")
+}
+
+fn set_skill_level(engine: &mut Engine, level: i64) -> String {
+    engine.set_skill_level(level as u8);
+    format!("Skill Level set to {level}")
+}
+
+fn set_randomize_equal_moves(engine: &mut Engine, enable: bool) -> String {
+    engine.set_randomize_equal_moves(enable);
+    match enable {
+        true => String::from("Randomization of equal moves enabled"),
+        false => String::from("Randomization of equal moves disabled"),
+    }
+}
+
+fn set_seed(engine: &mut Engine, seed: i64) -> String {
+    engine.set_seed(seed as u64);
+    format!("Seed set to {seed}")
+}
+
+fn set_analyse_mode(engine: &mut Engine, enable: bool) -> String {
+    engine.set_analyse_mode(enable);
+    match enable {
+        true => String::from("Analyse mode enabled"),
+        false => String::from("Analyse mode disabled"),
+    }
+}
+
 #[allow(dead_code)]
 fn set_futility_margin_base(engine: &mut Engine, margin_base: i64) -> String {
     engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
@@ -104,6 +369,33 @@ fn set_futility_pruning_max_depth(engine: &mut Engine, depth: i64) -> String {
     format!("futility-pruning-max-depth set to {depth}")
 }
 
+#[allow(dead_code)]
+fn set_futility_margin_improving(engine: &mut Engine, margin: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        futility_margin_improving: Some(margin as Score),
+        ..Default::default()
+    }));
+    format!("futility-margin-improving set to {margin}")
+}
+
+#[allow(dead_code)]
+fn set_reverse_futility_margin_improving(engine: &mut Engine, margin: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        reverse_futility_margin_improving: Some(margin as Score),
+        ..Default::default()
+    }));
+    format!("reverse-futility-margin-improving set to {margin}")
+}
+
+#[allow(dead_code)]
+fn set_late_move_pruning_not_improving_percent(engine: &mut Engine, percent: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        late_move_pruning_not_improving_percent: Some(percent as usize),
+        ..Default::default()
+    }));
+    format!("late-move-pruning-not-improving-percent set to {percent}")
+}
+
 #[allow(dead_code)]
 fn set_reverse_futility_margin_base(engine: &mut Engine, margin_base: i64) -> String {
     engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
@@ -131,6 +423,105 @@ fn set_reverse_futility_pruning_max_depth(engine: &mut Engine, depth: i64) -> St
     format!("reverse-futility-pruning-max-depth set to {depth}")
 }
 
+#[allow(dead_code)]
+fn set_razoring_margin_base(engine: &mut Engine, margin_base: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        razoring_margin_base: Some(margin_base as Score),
+        ..Default::default()
+    }));
+    format!("razoring-margin-base set to {margin_base}")
+}
+
+#[allow(dead_code)]
+fn set_razoring_margin_per_depth(engine: &mut Engine, margin_per_depth: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        razoring_margin_per_depth: Some(margin_per_depth as Score),
+        ..Default::default()
+    }));
+    format!("razoring-margin-per-depth set to {margin_per_depth}")
+}
+
+#[allow(dead_code)]
+fn set_razoring_max_depth(engine: &mut Engine, depth: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        razoring_max_depth: Some(depth as usize),
+        ..Default::default()
+    }));
+    format!("razoring-max-depth set to {depth}")
+}
+
+#[allow(dead_code)]
+fn set_probcut_margin(engine: &mut Engine, margin: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        probcut_margin: Some(margin as Score),
+        ..Default::default()
+    }));
+    format!("probcut-margin set to {margin}")
+}
+
+#[allow(dead_code)]
+fn set_probcut_depth_reduction(engine: &mut Engine, reduction: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        probcut_depth_reduction: Some(reduction as usize),
+        ..Default::default()
+    }));
+    format!("probcut-depth-reduction set to {reduction}")
+}
+
+#[allow(dead_code)]
+fn set_probcut_min_depth(engine: &mut Engine, depth: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        probcut_min_depth: Some(depth as usize),
+        ..Default::default()
+    }));
+    format!("probcut-min-depth set to {depth}")
+}
+
+#[allow(dead_code)]
+fn set_multicut_margin(engine: &mut Engine, margin: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        multicut_margin: Some(margin as Score),
+        ..Default::default()
+    }));
+    format!("multicut-margin set to {margin}")
+}
+
+#[allow(dead_code)]
+fn set_multicut_depth_reduction(engine: &mut Engine, reduction: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        multicut_depth_reduction: Some(reduction as usize),
+        ..Default::default()
+    }));
+    format!("multicut-depth-reduction set to {reduction}")
+}
+
+#[allow(dead_code)]
+fn set_multicut_min_depth(engine: &mut Engine, depth: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        multicut_min_depth: Some(depth as usize),
+        ..Default::default()
+    }));
+    format!("multicut-min-depth set to {depth}")
+}
+
+#[allow(dead_code)]
+fn set_multicut_moves(engine: &mut Engine, moves: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        multicut_moves: Some(moves as usize),
+        ..Default::default()
+    }));
+    format!("multicut-moves set to {moves}")
+}
+
+#[allow(dead_code)]
+fn set_multicut_required_cutoffs(engine: &mut Engine, cutoffs: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        multicut_required_cutoffs: Some(cutoffs as usize),
+        ..Default::default()
+    }));
+    format!("multicut-required-cutoffs set to {cutoffs}")
+}
+
 #[allow(dead_code)]
 fn set_late_move_pruning_base(engine: &mut Engine, base: i64) -> String {
     engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
@@ -185,6 +576,150 @@ fn set_see_pruning_max_depth(engine: &mut Engine, depth: i64) -> String {
     format!("see-pruning-max-depth set to {depth}")
 }
 
+#[allow(dead_code)]
+fn set_see_pruning_max_depth_quiet(engine: &mut Engine, depth: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        see_pruning_max_depth_quiet: Some(depth as usize),
+        ..Default::default()
+    }));
+    format!("see-pruning-max-depth-quiet set to {depth}")
+}
+
+#[allow(dead_code)]
+fn set_shared_history(engine: &mut Engine, enable: bool) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        shared_history: Some(enable),
+        ..Default::default()
+    }));
+    format!("shared-history set to {enable}")
+}
+
+#[allow(dead_code)]
+fn set_null_move_zugzwang_guard(engine: &mut Engine, enable: bool) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        null_move_zugzwang_guard: Some(enable),
+        ..Default::default()
+    }));
+    format!("null-move-zugzwang-guard set to {enable}")
+}
+
+#[allow(dead_code)]
+fn set_null_move_verification_min_depth(engine: &mut Engine, depth: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        null_move_verification_min_depth: Some(depth as usize),
+        ..Default::default()
+    }));
+    format!("null-move-verification-min-depth set to {depth}")
+}
+
+#[allow(dead_code)]
+fn set_internal_iterative_reduction(engine: &mut Engine, enable: bool) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        internal_iterative_reduction: Some(enable),
+        ..Default::default()
+    }));
+    format!("internal-iterative-reduction set to {enable}")
+}
+
+#[allow(dead_code)]
+fn set_singular_extension_min_depth(engine: &mut Engine, depth: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        singular_extension_min_depth: Some(depth as usize),
+        ..Default::default()
+    }));
+    format!("singular-extension-min-depth set to {depth}")
+}
+
+#[allow(dead_code)]
+fn set_singular_extension_max_table_depth_diff(engine: &mut Engine, diff: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        singular_extension_max_table_depth_diff: Some(diff as usize),
+        ..Default::default()
+    }));
+    format!("singular-extension-max-table-depth-diff set to {diff}")
+}
+
+#[allow(dead_code)]
+fn set_singular_extension_margin_per_depth(engine: &mut Engine, margin: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        singular_extension_margin_per_depth: Some(margin as Score),
+        ..Default::default()
+    }));
+    format!("singular-extension-margin-per-depth set to {margin}")
+}
+
+#[allow(dead_code)]
+fn set_max_extensions(engine: &mut Engine, max_extensions: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        max_extensions: Some(max_extensions as usize),
+        ..Default::default()
+    }));
+    format!("max-extensions set to {max_extensions}")
+}
+
+#[allow(dead_code)]
+fn set_history_max(engine: &mut Engine, value: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        history_max: Some(value as i32),
+        ..Default::default()
+    }));
+    format!("history-max set to {value}")
+}
+
+#[allow(dead_code)]
+fn set_history_bonus_quadratic(engine: &mut Engine, value: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        history_bonus_quadratic: Some(value as i32),
+        ..Default::default()
+    }));
+    format!("history-bonus-quadratic set to {value}")
+}
+
+#[allow(dead_code)]
+fn set_history_bonus_linear(engine: &mut Engine, value: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        history_bonus_linear: Some(value as i32),
+        ..Default::default()
+    }));
+    format!("history-bonus-linear set to {value}")
+}
+
+#[allow(dead_code)]
+fn set_late_move_reduction_not_improving(engine: &mut Engine, value: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        late_move_reduction_not_improving: Some(value as i32),
+        ..Default::default()
+    }));
+    format!("late-move-reduction-not-improving set to {value}")
+}
+
+#[allow(dead_code)]
+fn set_late_move_reduction_cut_node(engine: &mut Engine, value: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        late_move_reduction_cut_node: Some(value as i32),
+        ..Default::default()
+    }));
+    format!("late-move-reduction-cut-node set to {value}")
+}
+
+#[allow(dead_code)]
+fn set_late_move_reduction_killer_or_counter(engine: &mut Engine, value: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        late_move_reduction_killer_or_counter: Some(value as i32),
+        ..Default::default()
+    }));
+    format!("late-move-reduction-killer-or-counter set to {value}")
+}
+
+#[allow(dead_code)]
+fn set_late_move_reduction_history_divisor(engine: &mut Engine, value: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        late_move_reduction_history_divisor: Some(value as i32),
+        ..Default::default()
+    }));
+    format!("late-move-reduction-history-divisor set to {value}")
+}
+
 #[allow(dead_code)]
 fn set_aspiration_window_initial_width(engine: &mut Engine, width: i64) -> String {
     engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
@@ -202,3 +737,146 @@ fn set_aspiration_window_grow_rate(engine: &mut Engine, grow_rate: i64) -> Strin
     }));
     format!("aspiration-window-grow-rate set to {grow_rate}")
 }
+
+#[allow(dead_code)]
+fn set_score_drop_threshold(engine: &mut Engine, threshold: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        score_drop_threshold: Some(threshold as Score),
+        ..Default::default()
+    }));
+    format!("score-drop-threshold set to {threshold}")
+}
+
+#[allow(dead_code)]
+fn set_score_drop_extension_percent(engine: &mut Engine, percent: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        score_drop_extension_percent: Some(percent as i32),
+        ..Default::default()
+    }));
+    format!("score-drop-extension-percent set to {percent}")
+}
+
+#[allow(dead_code)]
+fn set_contempt_losing_score(engine: &mut Engine, score: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        contempt_losing_score: Some(score as Score),
+        ..Default::default()
+    }));
+    format!("contempt-losing-score set to {score}")
+}
+
+#[allow(dead_code)]
+fn set_contempt_endgame_percent(engine: &mut Engine, percent: i64) -> String {
+    engine.set_search_params(SearchParamsEachAlgo::AlphaBeta(AlphaBetaParams {
+        contempt_endgame_percent: Some(percent as i32),
+        ..Default::default()
+    }));
+    format!("contempt-endgame-percent set to {percent}")
+}
+
+fn set_debug_log_file(engine: &mut Engine, path: &str) -> String {
+    engine.set_debug_log_file(path);
+    match path.is_empty() {
+        true => String::from("Debug log disabled"),
+        false => format!("Debug log file set to {path}"),
+    }
+}
+
+fn set_trace_depth(engine: &mut Engine, depth: i64) -> String {
+    engine.set_trace_depth(depth as usize);
+    format!("TraceDepth set to {depth}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop_button(_: &mut Engine) -> String {
+        String::new()
+    }
+
+    fn noop_check(_: &mut Engine, _: bool) -> String {
+        String::new()
+    }
+
+    fn noop_str(_: &mut Engine, _: &str) -> String {
+        String::new()
+    }
+
+    fn noop_spin(_: &mut Engine, _: i64) -> String {
+        String::new()
+    }
+
+    #[test]
+    fn parse_button() {
+        let props = ButtonProps { fun: noop_button };
+        assert_eq!(Ok(()), props.parse(None));
+        assert!(props.parse(Some("")).is_err());
+        assert!(props.parse(Some("true")).is_err());
+    }
+
+    #[test]
+    fn parse_check() {
+        let props = CheckProps {
+            default: false,
+            fun: noop_check,
+        };
+        assert_eq!(Ok(true), props.parse(Some("true")));
+        assert_eq!(Ok(true), props.parse(Some("True")));
+        assert_eq!(Ok(false), props.parse(Some("false")));
+        assert!(props.parse(None).is_err());
+        assert!(props.parse(Some("")).is_err());
+        assert!(props.parse(Some("1")).is_err());
+        assert!(props.parse(Some("banana")).is_err());
+    }
+
+    #[test]
+    fn parse_combo() {
+        let props = ComboProps {
+            default: "Normal",
+            vars: &["Solid", "Normal", "Risky"],
+            fun: noop_str,
+        };
+        assert_eq!(Ok("Solid"), props.parse(Some("Solid")));
+        assert_eq!(Ok("Risky"), props.parse(Some("risky")));
+        assert!(props.parse(None).is_err());
+        assert!(props.parse(Some("")).is_err());
+        assert!(props.parse(Some("Aggressive")).is_err());
+    }
+
+    #[test]
+    fn parse_spin() {
+        let props = SpinProps {
+            default: 16,
+            min: 1,
+            max: 1024,
+            fun: noop_spin,
+        };
+        assert_eq!(Ok(1), props.parse(Some("1")));
+        assert_eq!(Ok(16), props.parse(Some("16")));
+        assert_eq!(Ok(1024), props.parse(Some("1024")));
+        assert!(props.parse(None).is_err());
+        assert!(props.parse(Some("")).is_err());
+        assert!(props.parse(Some("0")).is_err());
+        assert!(props.parse(Some("-5")).is_err());
+        assert!(props.parse(Some("1025")).is_err());
+        assert!(props.parse(Some("1.5")).is_err());
+        assert!(props.parse(Some("banana")).is_err());
+        assert!(props.parse(Some("99999999999999999999")).is_err());
+    }
+
+    #[test]
+    fn parse_string() {
+        let props = StringProps {
+            default: "",
+            fun: noop_str,
+        };
+        assert_eq!(Ok(String::from("book.bin")), props.parse(Some("book.bin")));
+        assert_eq!(
+            Ok(String::from("my book.bin")),
+            props.parse(Some("my book.bin"))
+        );
+        assert_eq!(Ok(String::new()), props.parse(Some("<empty>")));
+        assert!(props.parse(None).is_err());
+    }
+}
@@ -1,15 +1,22 @@
+use crate::debug_log::{DebugLog, LoggingWriter};
+use crate::uci_in::speedtest::Speedtest;
 use crate::uci_move::UciMove;
 use crate::uci_option::{OptionType, UciOption, OPTIONS};
 use crate::uci_score::UciScore;
 use engine::{EngineOptions, EngineOut, Variant};
+use eval::trace::EvalTrace;
+use movegen::fen::Fen;
+use movegen::position::Position;
 use movegen::r#move::Move;
-use search::search::SearchResult;
+use movegen::zobrist::Zobrist;
+use search::search::{CurrentMove, ScoreBound, SearchProgress, SearchResult};
 use std::error::Error;
 use std::io::Write;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 struct UciOutInner {
-    writer: Box<dyn Write + Send>,
+    writer: LoggingWriter,
     engine_version: String,
     engine_options: Arc<Mutex<EngineOptions>>,
     debug: bool,
@@ -18,6 +25,9 @@ struct UciOutInner {
 #[derive(Clone)]
 pub struct UciOut {
     inner: Arc<Mutex<UciOutInner>>,
+    // Separate from the inner state, because the speedtest thread writes its
+    // result while it is being stopped
+    speedtest: Arc<Mutex<Option<Speedtest>>>,
 }
 
 impl EngineOut for UciOut {
@@ -28,35 +38,57 @@ impl EngineOut for UciOut {
         match search_result {
             Some(res) => match self.inner.lock() {
                 Ok(mut inner) => {
-                    let move_to_str: Box<dyn Fn(Move) -> String> = match inner.engine_options.lock()
-                    {
-                        Ok(opt) => match opt.variant {
-                            Variant::Standard => Box::new(UciMove::move_to_str),
-                            Variant::Chess960(king_rook, queen_rook) => Box::new(move |m| {
-                                UciMove::move_to_str_chess_960(m, king_rook, queen_rook)
-                            }),
-                        },
-                        Err(e) => panic!("{}", e),
+                    let move_to_str = Self::move_to_str_fn(&inner.engine_options);
+                    let show_wdl = match inner.engine_options.lock() {
+                        Ok(opt) => opt.show_wdl,
+                        Err(e) => panic!("{e}"),
+                    };
+                    let wdl_str = match show_wdl {
+                        true => {
+                            let (w, d, l) = eval::score::win_draw_loss(res.score());
+                            format!(" wdl {w} {d} {l}")
+                        }
+                        false => String::new(),
+                    };
+                    let bound_str = match res.score_bound() {
+                        ScoreBound::Exact => "",
+                        ScoreBound::LowerBound => " lowerbound",
+                        ScoreBound::UpperBound => " upperbound",
                     };
-                    let pv_str = res
+                    let pv = res
                         .principal_variation()
                         .iter()
                         .take_while(|m| **m != Move::NULL)
                         .map(|m| move_to_str(*m))
-                        .collect::<Vec<String>>()
-                        .join(" ");
-                    Ok(writeln!(
+                        .collect::<Vec<String>>();
+                    // There is no PV if the root position has no legal moves
+                    let pv_str = match pv.is_empty() {
+                        true => String::new(),
+                        false => format!(" pv {}", pv.join(" ")),
+                    };
+                    writeln!(
                         inner.writer,
-                        "info depth {} seldepth {} score {} nodes {} nps {} time {} hashfull {} pv {}",
+                        "info depth {} seldepth {} score {}{}{} nodes {} nps {} time {} hashfull {}{}",
                         res.depth(),
                         res.selective_depth(),
                         UciScore::from(res.score()),
+                        bound_str,
+                        wdl_str,
                         res.nodes(),
                         res.nodes_per_second(),
                         res.time_ms(),
                         res.hash_load_factor_permille(),
                         pv_str
-                    )?)
+                    )?;
+                    for refutation in res.refutations() {
+                        let refutation_str = refutation
+                            .iter()
+                            .map(|m| move_to_str(*m))
+                            .collect::<Vec<String>>()
+                            .join(" ");
+                        writeln!(inner.writer, "info refutation {refutation_str}")?;
+                    }
+                    Ok(())
                 }
                 Err(e) => {
                     self.info_string(format!("{e}").as_str())?;
@@ -67,6 +99,47 @@ impl EngineOut for UciOut {
         }
     }
 
+    fn info_current_move(&self, current_move: CurrentMove) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => {
+                let move_to_str = Self::move_to_str_fn(&inner.engine_options);
+                Ok(writeln!(
+                    inner.writer,
+                    "info depth {} currmove {} currmovenumber {}",
+                    current_move.depth(),
+                    move_to_str(current_move.current_move()),
+                    current_move.move_number(),
+                )?)
+            }
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    fn info_progress(&self, progress: SearchProgress) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(
+                inner.writer,
+                "info nodes {} nps {} hashfull {} time {}",
+                progress.nodes(),
+                progress.nodes_per_second(),
+                progress.hash_load_factor_permille(),
+                progress.time_ms(),
+            )?),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    // The trace only goes to the debug log
+    fn trace(&self, line: &str) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => {
+                inner.writer.log_trace(line);
+                Ok(())
+            }
+            Err(e) => panic!("{e}"),
+        }
+    }
+
     fn info_string(&self, s: &str) -> Result<(), Box<dyn Error>> {
         match self.inner.lock() {
             Ok(mut inner) => match inner.debug {
@@ -81,16 +154,7 @@ impl EngineOut for UciOut {
         match search_result {
             Some(res) => match self.inner.lock() {
                 Ok(mut inner) => {
-                    let move_to_str: Box<dyn Fn(Move) -> String> = match inner.engine_options.lock()
-                    {
-                        Ok(opt) => match opt.variant {
-                            Variant::Standard => Box::new(UciMove::move_to_str),
-                            Variant::Chess960(king_rook, queen_rook) => Box::new(move |m| {
-                                UciMove::move_to_str_chess_960(m, king_rook, queen_rook)
-                            }),
-                        },
-                        Err(e) => panic!("{e}"),
-                    };
+                    let move_to_str = Self::move_to_str_fn(&inner.engine_options);
                     Ok(writeln!(inner.writer, "bestmove {}", move_to_str(res))?)
                 }
                 Err(e) => {
@@ -101,6 +165,13 @@ impl EngineOut for UciOut {
             None => Ok(()),
         }
     }
+
+    fn flush(&self) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(inner.writer.flush()?),
+            Err(e) => panic!("{e}"),
+        }
+    }
 }
 
 impl UciOut {
@@ -111,14 +182,32 @@ impl UciOut {
     ) -> Self {
         Self {
             inner: Arc::new(Mutex::new(UciOutInner {
-                writer,
+                writer: LoggingWriter::new(writer, DebugLog::new(Arc::clone(&engine_options))),
                 engine_version: String::from(engine_version),
                 debug: false,
                 engine_options,
             })),
+            speedtest: Arc::new(Mutex::new(None)),
         }
     }
 
+    // Replaces a running speedtest
+    pub fn set_speedtest(&self, speedtest: Speedtest) {
+        let prev = match self.speedtest.lock() {
+            Ok(mut st) => st.replace(speedtest),
+            Err(e) => panic!("{e}"),
+        };
+        drop(prev);
+    }
+
+    pub fn stop_speedtest(&self) {
+        let prev = match self.speedtest.lock() {
+            Ok(mut st) => st.take(),
+            Err(e) => panic!("{e}"),
+        };
+        drop(prev);
+    }
+
     pub fn set_debug(&self, tf: bool) {
         match self.inner.lock() {
             Ok(mut inner) => inner.debug = tf,
@@ -126,6 +215,14 @@ impl UciOut {
         }
     }
 
+    // Input lines are only written to the debug log
+    pub fn log_input(&self, line: &str) {
+        match self.inner.lock() {
+            Ok(mut inner) => inner.writer.log_input(line),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
     pub fn id(&mut self) -> Result<(), Box<dyn Error>> {
         match self.inner.lock() {
             Ok(mut inner) => {
@@ -176,8 +273,157 @@ impl UciOut {
         }
     }
 
+    pub fn position(&mut self, pos: &Position, hash: Zobrist) -> Result<(), Box<dyn Error>> {
+        let fen = Fen::pos_to_str(pos);
+        let fen_chess_960 = Fen::pos_to_str_chess_960(pos);
+        // Castling rights and en passant square are the third and fourth FEN fields
+        let mut fen_fields = fen.split_whitespace().skip(2);
+        let castling_rights = fen_fields.next().unwrap_or("-");
+        let en_passant_square = fen_fields.next().unwrap_or("-");
+        match self.inner.lock() {
+            Ok(mut inner) => {
+                writeln!(inner.writer, "\n{pos}")?;
+                writeln!(inner.writer, "Fen: {fen}")?;
+                writeln!(inner.writer, "Chess960 Fen: {fen_chess_960}")?;
+                writeln!(inner.writer, "Key: {:016X}", u64::from(hash))?;
+                writeln!(inner.writer, "Castling rights: {castling_rights}")?;
+                writeln!(inner.writer, "En passant square: {en_passant_square}")?;
+                Ok(writeln!(
+                    inner.writer,
+                    "Halfmove clock: {}",
+                    pos.plies_since_pawn_move_or_capture()
+                )?)
+            }
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    pub fn bench_position(
+        &mut self,
+        index: usize,
+        fen: &str,
+        search_result: &SearchResult,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(
+                inner.writer,
+                "Position {:>2}: {} nodes {} time {}",
+                index + 1,
+                fen,
+                search_result.nodes(),
+                search_result.time_ms(),
+            )?),
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    pub fn bench_total(&mut self, nodes: u64, time: Duration) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(
+                inner.writer,
+                "Total time (ms): {}\nNodes searched: {nodes}\nNodes/second: {}",
+                time.as_millis(),
+                nodes_per_second(nodes, time),
+            )?),
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    // The total time is shared by all threads
+    pub fn speedtest(
+        &mut self,
+        nodes_per_thread: &[u64],
+        time: Duration,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => {
+                for (idx, &nodes) in nodes_per_thread.iter().enumerate() {
+                    writeln!(
+                        inner.writer,
+                        "Thread {}: nodes {nodes} nps {}",
+                        idx + 1,
+                        nodes_per_second(nodes, time),
+                    )?;
+                }
+                let nodes = nodes_per_thread.iter().sum();
+                Ok(writeln!(
+                    inner.writer,
+                    "Total time (ms): {}\nNodes searched: {nodes}\nNodes/second: {}",
+                    time.as_millis(),
+                    nodes_per_second(nodes, time),
+                )?)
+            }
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    pub fn genfens(&mut self, fen: &str) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(inner.writer, "info string genfens {fen}")?),
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    pub fn eval_trace(&mut self, trace: &EvalTrace) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(write!(inner.writer, "{trace}")?),
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    pub fn perft(&mut self, nodes: usize, time: Duration) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(
+                inner.writer,
+                "Nodes searched: {nodes}\nTotal time (ms): {}",
+                time.as_millis(),
+            )?),
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    fn move_to_str_fn(engine_options: &Arc<Mutex<EngineOptions>>) -> Box<dyn Fn(Move) -> String> {
+        match engine_options.lock() {
+            Ok(opt) => match opt.variant {
+                Variant::Standard => Box::new(UciMove::move_to_str),
+                Variant::Chess960(king_rook, queen_rook) => {
+                    Box::new(move |m| UciMove::move_to_str_chess_960(m, king_rook, queen_rook))
+                }
+            },
+            Err(e) => panic!("{e}"),
+        }
+    }
+
     fn option(&mut self, opt: &UciOption) -> Result<(), Box<dyn Error>> {
         match &opt.r#type {
+            OptionType::Button(_) => match self.inner.lock() {
+                Ok(mut inner) => writeln!(inner.writer, "option name {} type button", opt.name)?,
+                Err(e) => {
+                    self.info_string(format!("{e}").as_str())?;
+                    panic!("{e}")
+                }
+            },
             OptionType::Check(props) => match self.inner.lock() {
                 Ok(mut inner) => writeln!(
                     inner.writer,
@@ -200,10 +446,46 @@ impl UciOut {
                     panic!("{e}")
                 }
             },
-            OptionType::Button | OptionType::Combo | OptionType::String => {
-                unimplemented!();
-            }
+            OptionType::Combo(props) => match self.inner.lock() {
+                Ok(mut inner) => writeln!(
+                    inner.writer,
+                    "option name {} type combo default {}{}",
+                    opt.name,
+                    props.default,
+                    props
+                        .vars
+                        .iter()
+                        .map(|v| format!(" var {v}"))
+                        .collect::<String>(),
+                )?,
+                Err(e) => {
+                    self.info_string(format!("{e}").as_str())?;
+                    panic!("{e}")
+                }
+            },
+            OptionType::String(props) => match self.inner.lock() {
+                Ok(mut inner) => writeln!(
+                    inner.writer,
+                    "option name {} type string default {}",
+                    opt.name,
+                    match props.default.is_empty() {
+                        true => "<empty>",
+                        false => props.default,
+                    },
+                )?,
+                Err(e) => {
+                    self.info_string(format!("{e}").as_str())?;
+                    panic!("{e}")
+                }
+            },
         }
         Ok(())
     }
 }
+
+fn nodes_per_second(nodes: u64, time: Duration) -> u64 {
+    match time.as_micros() {
+        0 => 0,
+        us => (1_000_000 * nodes as u128 / us) as u64,
+    }
+}
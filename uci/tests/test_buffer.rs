@@ -1,9 +1,11 @@
 use std::io;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct TestBuffer {
-    buf: Arc<Mutex<Vec<u8>>>,
+    // The condition variable is notified on every write
+    buf: Arc<(Mutex<Vec<u8>>, Condvar)>,
 }
 
 impl Default for TestBuffer {
@@ -15,13 +17,14 @@ impl Default for TestBuffer {
 impl TestBuffer {
     pub fn new() -> Self {
         Self {
-            buf: Arc::new(Mutex::new(Vec::new())),
+            buf: Arc::new((Mutex::new(Vec::new()), Condvar::new())),
         }
     }
 
     pub fn into_inner(self) -> Vec<u8> {
         Arc::try_unwrap(self.buf)
             .expect("More than one Arc refers to the inner Vec")
+            .0
             .into_inner()
             .expect("Error accessing inner value of mutex")
     }
@@ -31,16 +34,45 @@ impl TestBuffer {
     }
 
     pub fn split_off(&mut self, at: usize) -> Vec<u8> {
-        self.buf.lock().expect("Error locking mutex").split_off(at)
+        self.buf
+            .0
+            .lock()
+            .expect("Error locking mutex")
+            .split_off(at)
+    }
+
+    // Blocks until the buffer contains s and returns the output up to and
+    // including s. The output is removed from the buffer. Panics if s isn't
+    // written within the timeout.
+    pub fn wait_for(&mut self, s: &str, timeout: Duration) -> String {
+        let (buf, written) = &*self.buf;
+        let find = |buf: &Vec<u8>| {
+            buf.windows(s.len())
+                .position(|w| w == s.as_bytes())
+                .map(|pos| pos + s.len())
+        };
+        let (mut buf, res) = written
+            .wait_timeout_while(buf.lock().expect("Error locking mutex"), timeout, |buf| {
+                find(buf).is_none()
+            })
+            .expect("Error locking mutex");
+        assert!(!res.timed_out(), "Timeout waiting for {s:?}");
+        let end = find(&buf).expect("Expected the output to contain the string");
+        let rest = buf.split_off(end);
+        String::from_utf8(std::mem::replace(&mut *buf, rest))
+            .expect("Error converting Vec<u8> to String")
     }
 }
 
 impl io::Write for TestBuffer {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.buf.lock().expect("Error locking mutex").write(buf)
+        let (inner, written) = &*self.buf;
+        let res = inner.lock().expect("Error locking mutex").write(buf);
+        written.notify_all();
+        res
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.buf.lock().expect("Error locking mutex").flush()
+        self.buf.0.lock().expect("Error locking mutex").flush()
     }
 }
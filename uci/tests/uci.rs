@@ -2,18 +2,27 @@ mod test_buffer;
 
 use crate::test_buffer::TestBuffer;
 use assert_matches::assert_matches;
-use engine::{Engine, EngineOptions, Variant};
+use engine::{Engine, EngineOptions, EngineOut, Variant};
+use eval::complex::Complex;
 use eval::material_mobility::MaterialMobility;
+use eval::Eval;
 use movegen::fen::Fen;
+use movegen::move_generator::MoveGenerator;
+use movegen::polyglot::PolyglotKey;
 use movegen::position::Position;
+use movegen::r#move::{Move, MoveList, MoveType};
+use movegen::side::Side;
+use movegen::square::Square;
+use movegen::zobrist::Zobrist;
 use regex::Regex;
 use search::alpha_beta::AlphaBeta;
-use search::search::Search;
+use search::search::{CurrentMove, ScoreBound, Search, SearchResult, MAX_PLY};
 use std::str;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use uci::uci_in::{
-    debug, go, is_ready, position, quit, set_option, stop, uci as cmd_uci, ucinewgame,
+    bench, d, debug, eval as cmd_eval, genfens, go, is_ready, perft, position, quit, set_option,
+    speedtest, stop, uci as cmd_uci, ucinewgame,
 };
 use uci::UciOut;
 use uci::{Parser, ParserMessage};
@@ -48,6 +57,16 @@ fn run_command_uci() {
     assert!(out.contains("id name"));
     assert!(out.contains("id author"));
     assert!(out.contains("option name Hash type spin default"));
+    assert!(out.contains("option name Clear Hash type button\n"));
+    assert!(out.contains("option name Contempt type spin default 0 min -100 max 100\n"));
+    assert!(out.contains("option name OwnBook type check default false\n"));
+    assert!(out.contains("option name BookFile type string default <empty>\n"));
+    assert!(out.contains("option name BookDepth type spin default 20 min 1 max 100\n"));
+    assert!(out.contains("option name Skill Level type spin default 20 min 0 max 20\n"));
+    assert!(out.contains("option name RandomizeEqualMoves type check default false\n"));
+    assert!(out.contains("option name Seed type spin default 0 min 0 max 4294967295\n"));
+    assert!(out.contains("option name Debug Log File type string default <empty>\n"));
+    assert!(out.contains("option name TraceDepth type spin default 0 min 0 max 8\n"));
     assert!(out.contains("min"));
     assert!(out.contains("max"));
     assert!(out.contains("uciok\n"));
@@ -105,7 +124,7 @@ fn run_command_debug() {
 #[test]
 fn run_command_setoption() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
-    let test_writer = TestBuffer::new();
+    let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
     let uci_out = UciOut::new(
         Box::new(test_writer.clone()),
@@ -126,6 +145,16 @@ fn run_command_setoption() {
         "setoption name Hash value\n",
         "setoption name Hash value invalid\n",
         "setoption name Hash value 16 invalid\n",
+        "setoption name Clear Hash value\n",
+        "setoption name Clear Hash value true\n",
+        "setoption name Hash value -5\n",
+        "setoption name Hash value 0\n",
+        "setoption name Hash value 65537\n",
+        "setoption name Hash value 1.5\n",
+        "setoption name Move Overhead value banana\n",
+        "setoption name UCI_Chess960\n",
+        "setoption name UCI_Chess960 value\n",
+        "setoption name UCI_Chess960 value yes\n",
     ];
     for inv_cmd in invalid_commands {
         print!("{}", inv_cmd);
@@ -135,17 +164,55 @@ fn run_command_setoption() {
     let valid_commands = [
         "setoption name Hash value 16\n",
         "setoption name hash value 16\n",
+        "setoption name Clear Hash\n",
+        "setoption name clear hash\n",
     ];
     for val_cmd in valid_commands {
         print!("{}", val_cmd);
         assert!(p.run_command(val_cmd, &mut engine).is_ok());
     }
+
+    // Invalid values are rejected with a description and don't change the option
+    let hash_size = engine.hash_size();
+    let move_overhead = engine.move_overhead();
+    let err = p
+        .run_command("setoption name Hash value -5\n", &mut engine)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Hash"));
+    assert!(err.contains("expected an integer from 1 to 65536"));
+    let err = p
+        .run_command("setoption name Move Overhead value banana\n", &mut engine)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Move Overhead"));
+    assert!(err.contains("expected an integer from 0 to 10000"));
+    let err = p
+        .run_command("setoption name UCI_Chess960 value yes\n", &mut engine)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("expected true or false"));
+    assert_eq!(hash_size, engine.hash_size());
+    assert_eq!(move_overhead, engine.move_overhead());
+
+    // The hash size is rounded down to a power of two
+    p.register_command(String::from("debug"), Box::new(debug::run_command));
+    assert!(p.run_command("debug on\n", &mut engine).is_ok());
+    let _ = test_writer.split_off(0);
+    assert!(p
+        .run_command("setoption name Hash value 3\n", &mut engine)
+        .is_ok());
+    assert!(contains(
+        test_writer.split_off(0),
+        "info string Hash set to 2 MB\n"
+    ));
+    assert_eq!(2 * 2_usize.pow(20), engine.hash_size());
 }
 
 #[test]
-fn run_command_position() {
+fn show_wdl() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
-    let test_writer = TestBuffer::new();
+    let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
     let uci_out = UciOut::new(
         Box::new(test_writer.clone()),
@@ -156,43 +223,119 @@ fn run_command_position() {
     let mut p = Parser::new(uci_out);
 
     p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
 
-    assert_eq!(None, engine.position());
+    let re = Regex::new(r"score cp -?\d+ wdl (\d+) (\d+) (\d+) nodes").unwrap();
 
-    let invalid_commands = [
-        "position\n",
-        "position invalid\n",
-        "position fen\n",
-        "position startpos invalid\n",
-        "position startpos moves e2e5\n",
-        "position fen invalid_fen\n",
-        &format!("position fen {} not_moves\n", FEN_STR),
-        &format!("position fen {} moves invalid_move\n", FEN_STR),
-    ];
-    for inv_cmd in invalid_commands {
-        assert!(p.run_command(inv_cmd, &mut engine).is_err());
-    }
-    assert_eq!(None, engine.position());
+    // Disabled by default
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    assert!(!output.contains("wdl"));
+
+    assert!(p
+        .run_command("setoption name UCI_ShowWDL value true\n", &mut engine)
+        .is_ok());
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    let captures = re.captures(&output).unwrap();
+    let wdl_sum: u16 = (1..=3).map(|i| captures[i].parse::<u16>().unwrap()).sum();
+    assert_eq!(1000, wdl_sum);
+
+    // Mate scores
+    assert!(p
+        .run_command(
+            "position fen 8/7k/7P/8/8/8/6Q1/6K1 w - - 0 1\n",
+            &mut engine
+        )
+        .is_ok());
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove g2g7"));
+    assert!(output.contains("score mate 1 wdl 1000 0 0"));
 
+    assert!(p
+        .run_command("setoption name UCI_ShowWDL value false\n", &mut engine)
+        .is_ok());
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    assert_eq!(Some(&Position::initial()), engine.position());
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    assert!(!output.contains("wdl"));
+}
+
+#[test]
+fn limit_strength() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+    p.register_command(
+        String::from("ucinewgame"),
+        Box::new(ucinewgame::run_command),
+    );
+
+    let re_nodes = Regex::new(r"nodes (\d+)").unwrap();
+    let re_best_move = Regex::new(r"bestmove (\S+)").unwrap();
+    let mut search = |p: &mut Parser, engine: &mut Engine| -> (u64, String) {
+        assert!(p.run_command("ucinewgame\n", engine).is_ok());
+        assert!(p
+            .run_command(format!("position fen {FEN_STR}\n").as_str(), engine)
+            .is_ok());
+        assert!(p.run_command("go depth 20\n", engine).is_ok());
+        std::thread::sleep(Duration::from_millis(500));
+        let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+        let max_nodes = re_nodes
+            .captures_iter(&output)
+            .map(|c| c[1].parse::<u64>().unwrap())
+            .max()
+            .unwrap();
+        let best_move = re_best_move.captures(&output).unwrap()[1].to_string();
+        (max_nodes, best_move)
+    };
 
     assert!(p
-        .run_command(format!("position fen {}\n", FEN_STR).as_str(), &mut engine)
+        .run_command("setoption name UCI_LimitStrength value true\n", &mut engine)
         .is_ok());
-    assert_eq!(Fen::str_to_pos(FEN_STR).ok().as_ref(), engine.position());
+    assert!(p
+        .run_command("setoption name UCI_Elo value 1200\n", &mut engine)
+        .is_ok());
+    let (nodes, best_move) = search(&mut p, &mut engine);
+    // The search stops after depth 1 is finished at the earliest
+    assert!(nodes < 2000);
+    // The chosen move is reproducible
+    for _ in 0..3 {
+        assert_eq!((nodes, best_move.clone()), search(&mut p, &mut engine));
+    }
 
     assert!(p
-        .run_command("position startpos moves e2e4 c7c5 g1f3\n", &mut engine)
+        .run_command("setoption name UCI_Elo value 1800\n", &mut engine)
         .is_ok());
-    let fen = "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2";
-    assert_eq!(Fen::str_to_pos(fen).ok().as_ref(), engine.position());
+    let (stronger_nodes, _) = search(&mut p, &mut engine);
+    assert!(stronger_nodes > nodes);
 }
 
 #[test]
-fn run_command_position_chess_960() {
+fn skill_level() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
-    let test_writer = TestBuffer::new();
+    let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
     let uci_out = UciOut::new(
         Box::new(test_writer.clone()),
@@ -203,50 +346,177 @@ fn run_command_position_chess_960() {
     let mut p = Parser::new(uci_out);
 
     p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
     p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+    p.register_command(
+        String::from("ucinewgame"),
+        Box::new(ucinewgame::run_command),
+    );
+
+    let re_best_move = Regex::new(r"bestmove (\S+)").unwrap();
+    let mut search = |p: &mut Parser, engine: &mut Engine| -> String {
+        assert!(p.run_command("ucinewgame\n", engine).is_ok());
+        assert!(p
+            .run_command("position startpos moves e2e4 e7e5\n", engine)
+            .is_ok());
+        assert!(p.run_command("go depth 4\n", engine).is_ok());
+        std::thread::sleep(Duration::from_millis(500));
+        let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+        re_best_move.captures(&output).unwrap()[1].to_string()
+    };
 
+    let full_strength_move = search(&mut p, &mut engine);
     assert!(p
-        .run_command("setoption name UCI_Chess960 value true\n", &mut engine)
+        .run_command("setoption name Skill Level value 20\n", &mut engine)
         .is_ok());
+    assert_eq!(full_strength_move, search(&mut p, &mut engine));
+
+    assert!(p
+        .run_command("setoption name Skill Level value 0\n", &mut engine)
+        .is_ok());
+    let weak_move = search(&mut p, &mut engine);
+    // The chosen move is reproducible
+    for _ in 0..3 {
+        assert_eq!(weak_move, search(&mut p, &mut engine));
+    }
+}
+
+#[test]
+fn run_command_position() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
 
-    assert_matches!(engine.variant(), Variant::Chess960(_, _));
     assert_eq!(None, engine.position());
 
     let invalid_commands = [
-        &format!("position fen {}\n", FEN_STR),
-        &format!("position fen {} moves e1g1\n", FEN_STR),
+        "position\n",
+        "position invalid\n",
+        "position fen\n",
+        "position fen invalid_fen\n",
     ];
     for inv_cmd in invalid_commands {
         assert!(p.run_command(inv_cmd, &mut engine).is_err());
     }
     assert_eq!(None, engine.position());
 
+    // Illegal moves are reported with their index and position. The position
+    // before the illegal move is set.
+    let illegal_moves = [
+        (
+            "position startpos moves e2e5\n",
+            0,
+            Fen::pos_to_str(&Position::initial()),
+        ),
+        // Passing the turn while in check
+        (
+            "position startpos moves e2e4 d7d5 f1b5 0000\n",
+            3,
+            String::from("rnbqkbnr/ppp1pppp/8/1B1p4/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 1 2"),
+        ),
+        (
+            &format!("position fen {} moves invalid_move\n", FEN_STR),
+            0,
+            Fen::pos_to_str(&Fen::str_to_pos(FEN_STR).unwrap()),
+        ),
+    ];
+    for (cmd, index, fen) in illegal_moves {
+        let err = format!(
+            "{}",
+            p.run_command(cmd, &mut engine).expect_err("Expected error")
+        );
+        assert!(err.contains(&format!(" at index {index} ")), "{err}");
+        assert!(err.ends_with(&format!("in position `{fen}`")), "{err}");
+        assert_eq!(Fen::str_to_pos(&fen).ok().as_ref(), engine.position());
+    }
+    let err = format!(
+        "{}",
+        p.run_command("position startpos moves e2e4 e7e5 e1e3\n", &mut engine)
+            .expect_err("Expected error")
+    );
+    assert_eq!(
+        "Uci error: Illegal move `e1e3` at index 2 of the move list in position \
+         `rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2`",
+        err
+    );
+
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
     assert_eq!(Some(&Position::initial()), engine.position());
 
     assert!(p
-        .run_command(
-            format!("position fen {}\n", FEN_STR_CHESS_960).as_str(),
-            &mut engine
-        )
+        .run_command(format!("position fen {}\n", FEN_STR).as_str(), &mut engine)
         .is_ok());
     assert_eq!(Fen::str_to_pos(FEN_STR).ok().as_ref(), engine.position());
 
+    assert!(p
+        .run_command("position startpos moves e2e4 c7c5 g1f3\n", &mut engine)
+        .is_ok());
+    let fen = "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2";
+    assert_eq!(Fen::str_to_pos(fen).ok().as_ref(), engine.position());
+
+    // Abbreviated FENs without clocks or en passant square
+    let abbreviated = [
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ -",
+            "0 1",
+        ),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1",
+            "1 1",
+        ),
+        ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ", "0 1"),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ 1 8",
+            "1 8",
+        ),
+    ];
+    for (short_fen, clocks) in abbreviated {
+        assert!(p
+            .run_command(&format!("position fen {short_fen}\n"), &mut engine)
+            .is_ok());
+        let fen = format!("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - {clocks}");
+        assert_eq!(Fen::str_to_pos(&fen).ok().as_ref(), engine.position());
+        assert!(p
+            .run_command(
+                &format!("position fen {short_fen} moves e1g1\n"),
+                &mut engine
+            )
+            .is_ok());
+        assert_eq!(
+            Some(Side::Black),
+            engine.position().map(|pos| pos.side_to_move())
+        );
+    }
     assert!(p
         .run_command(
-            &format!("position fen {} moves e1h1\n", FEN_STR_CHESS_960),
+            "position fen rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ x moves e1g1\n",
             &mut engine
         )
+        .is_err());
+
+    // Null moves pass the turn and clear the en passant square
+    assert!(p
+        .run_command("position startpos moves e2e4 0000 g1f3 0000\n", &mut engine)
         .is_ok());
-    let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQ1RK1 b - - 2 8";
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 3 3";
+    assert_eq!(Fen::str_to_pos(fen).ok().as_ref(), engine.position());
     assert_eq!(
-        Fen::str_to_pos_chess_960(fen).ok().as_ref(),
-        engine.position()
+        Fen::str_to_pos(fen).ok().map(|pos| Zobrist::new(&pos)),
+        engine.position_hash()
     );
 }
 
 #[test]
-fn run_command_ucinewgame() {
+fn run_command_position_incremental() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -259,27 +529,71 @@ fn run_command_ucinewgame() {
     let mut p = Parser::new(uci_out);
 
     p.register_command(String::from("position"), Box::new(position::run_command));
-    p.register_command(
-        String::from("ucinewgame"),
-        Box::new(ucinewgame::run_command),
-    );
-
-    assert_eq!(None, engine.position());
 
-    assert!(p.run_command("ucinewgame invalid\n", &mut engine).is_err());
-
-    assert!(p.run_command("ucinewgame\n", &mut engine).is_ok());
-    assert_eq!(None, engine.position());
-
-    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    assert_eq!(Some(&Position::initial()), engine.position());
+    let commands = [
+        // New moves are appended to the current game
+        (
+            "position startpos moves e2e4\n",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+        ),
+        (
+            "position startpos moves e2e4 c7c5 g1f3\n",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+        ),
+        (
+            "position startpos moves e2e4 c7c5 g1f3\n",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+        ),
+        // Take back a move
+        (
+            "position startpos moves e2e4 c7c5\n",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+        ),
+        // Different moves
+        (
+            "position startpos moves e2e4 e7e5\n",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+        ),
+        // Different start position with the same moves
+        (
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w Qkq - 0 1 \
+             moves e2e4 e7e5 g1f3\n",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB2 b Qkq - 1 2",
+        ),
+        (
+            "position startpos moves e2e4 e7e5 g1f3\n",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+        ),
+        // After a null move, the game is rebuilt
+        (
+            "position startpos moves e2e4 0000 g1f3\n",
+            "rnbqkbnr/pppppppp/8/8/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 2 2",
+        ),
+        (
+            "position startpos moves e2e4 0000 g1f3 e7e5\n",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3",
+        ),
+    ];
+    for (cmd, fen) in commands {
+        assert!(p.run_command(cmd, &mut engine).is_ok());
+        let pos = Fen::str_to_pos(fen).unwrap();
+        assert_eq!(Some(&pos), engine.position(), "{cmd}");
+        assert_eq!(Some(Zobrist::new(&pos)), engine.position_hash(), "{cmd}");
+    }
 
-    assert!(p.run_command("ucinewgame\n", &mut engine).is_ok());
-    assert_eq!(None, engine.position());
+    // An illegal new move keeps the game up to the move before it
+    assert!(p
+        .run_command(
+            "position startpos moves e2e4 0000 g1f3 e7e5 e1e3\n",
+            &mut engine
+        )
+        .is_err());
+    let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3";
+    assert_eq!(Fen::str_to_pos(fen).ok().as_ref(), engine.position());
 }
 
 #[test]
-fn run_command_go() {
+fn max_depth() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -295,54 +609,460 @@ fn run_command_go() {
     p.register_command(String::from("go"), Box::new(go::run_command));
     p.register_command(String::from("stop"), Box::new(stop::run_command));
 
-    // Run "go" without setting a position
-    assert!(p.run_command("go invalid\n", &mut engine).is_err());
-    assert!(p.run_command("go\n", &mut engine).is_err());
-
-    // Run "go" with invalid options
-    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    assert!(p.run_command("go invalid\n", &mut engine).is_err());
-    assert!(p.run_command("go depth\n", &mut engine).is_err());
-    assert!(p.run_command("go depth invalid\n", &mut engine).is_err());
-    assert!(p.run_command("go movetime\n", &mut engine).is_err());
-    assert!(p.run_command("go movetime invalid\n", &mut engine).is_err());
-    // Options must not appear more than once
-    assert!(p.run_command("go depth 2 depth 2\n", &mut engine).is_err());
-
-    // Run "go" followed by "stop" after setting a position
+    // The depth is clamped
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    assert!(p.run_command("go\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
-    assert!(output.contains("info"));
-    assert!(output.contains("depth"));
-    assert!(output.contains("score"));
-    assert!(output.contains("nodes"));
-    assert!(output.contains("nps"));
-    assert!(output.contains("time"));
-    assert!(output.contains("hashfull"));
-    assert!(output.contains("pv"));
-    assert!(!output.contains("bestmove"));
+    assert!(p.run_command("go depth 300\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
     assert!(p.run_command("stop\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(20));
     let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains(&format!(
+        "info string warning: depth 300 exceeds the maximum depth, using `depth {MAX_PLY}` instead\n"
+    )));
     assert!(output.contains("bestmove"));
+
+    // A long search in a trivial position ends cleanly
+    assert!(p
+        .run_command("position fen 8/8/8/4k3/8/8/8/4K3 w - - 0 1\n", &mut engine)
+        .is_ok());
+    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(1000));
     assert!(p.run_command("stop\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(20));
     let output = String::from_utf8(test_writer.split_off(0)).unwrap();
-    assert!(!output.contains("info"));
-    assert!(!output.contains("bestmove"));
+    assert_eq!(1, output.matches("bestmove").count());
+    assert!(!output.contains("warning"));
+}
 
-    // Option "depth"
+#[test]
+fn unknown_tokens_are_ignored() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    assert!(p
+        .run_command("position foo startpos bar moves e2e4 c7c5\n", &mut engine)
+        .is_ok());
+    let fen = "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+    assert_eq!(Fen::str_to_pos(fen).ok().as_ref(), engine.position());
+    assert_eq!(
+        "info string warning: ignoring unknown tokens `foo bar` in command: \
+         position foo startpos bar moves e2e4 c7c5\n",
+        String::from_utf8(test_writer.split_off(0)).unwrap()
+    );
+
+    assert!(p
+        .run_command(
+            format!("position fen {} not_moves\n", FEN_STR).as_str(),
+            &mut engine
+        )
+        .is_ok());
+    assert_eq!(Fen::str_to_pos(FEN_STR).ok().as_ref(), engine.position());
+    assert!(contains(test_writer.split_off(0), "`not_moves`"));
+
+    // The known options are still applied
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    assert!(p
+        .run_command(
+            "go wtime 1000 btime 1000 winc 10 binc 10 ponderless depth 3\n",
+            &mut engine
+        )
+        .is_ok());
     std::thread::sleep(Duration::from_millis(400));
     let output = String::from_utf8(test_writer.split_off(0)).unwrap();
-    assert!(output.contains("depth 2"));
+    assert_eq!(
+        1,
+        output
+            .matches("ignoring unknown tokens `ponderless`")
+            .count()
+    );
+    assert!(output.contains("info depth 3 "));
+    assert!(!output.contains("info depth 4 "));
     assert!(output.contains("bestmove"));
+
+    // Without any known options, `go` searches until `stop`
+    assert!(p.run_command("go foo bar\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("ignoring unknown tokens `foo bar`"));
+    assert!(!output.contains("bestmove"));
     assert!(p.run_command("stop\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(20));
-    assert!(!contains(test_writer.split_off(0), "bestmove"));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+
+    // Invalid values of known options are still errors
+    assert!(p.run_command("go depth foo\n", &mut engine).is_err());
+    assert!(p
+        .run_command("position startpos moves e2e4 foo\n", &mut engine)
+        .is_err());
+}
+
+#[test]
+fn run_command_d() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    {
+        let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+        let mut p = Parser::new(uci_out);
+
+        p.register_command(String::from("d"), Box::new(d::run_command));
+        p.register_command(String::from("position"), Box::new(position::run_command));
+
+        // No position set
+        assert!(p.run_command("d\n", &mut engine).is_err());
+
+        assert!(p
+            .run_command(
+                "position startpos moves e2e4 c7c5 g1f3 d7d6 e1e2 e8d7\n",
+                &mut engine
+            )
+            .is_ok());
+        assert!(p.run_command("d invalid\n", &mut engine).is_err());
+        assert!(p.run_command("d\n", &mut engine).is_ok());
+    }
+    let out = test_writer.into_string();
+    assert!(out.contains(
+        "r n b q - b n r\n\
+         p p - k p p p p\n\
+         - - - p - - - -\n\
+         - - p - - - - -\n\
+         - - - - P - - -\n\
+         - - - - - N - -\n\
+         P P P P K P P P\n\
+         R N B Q - B - R\n"
+    ));
+    assert!(out.contains("Fen: rnbq1bnr/pp1kpppp/3p4/2p5/4P3/5N2/PPPPKPPP/RNBQ1B1R w - - 2 4\n"));
+    assert!(out
+        .contains("Chess960 Fen: rnbq1bnr/pp1kpppp/3p4/2p5/4P3/5N2/PPPPKPPP/RNBQ1B1R w - - 2 4\n"));
+    assert!(Regex::new(r"Key: [0-9A-F]{16}\n").unwrap().is_match(&out));
+    assert!(out.contains("Castling rights: -\n"));
+    assert!(out.contains("En passant square: -\n"));
+    assert!(out.contains("Halfmove clock: 2\n"));
+}
+
+#[test]
+fn run_command_bench() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    {
+        let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+        let mut p = Parser::new(uci_out);
+
+        p.register_command(String::from("bench"), Box::new(bench::run_command));
+
+        let invalid_commands = [
+            "bench invalid\n",
+            "bench 0\n",
+            "bench -1\n",
+            "bench 2 invalid\n",
+        ];
+        for inv_cmd in invalid_commands {
+            assert!(p.run_command(inv_cmd, &mut engine).is_err());
+        }
+        assert!(p.run_command("bench 2\n", &mut engine).is_ok());
+    }
+    let out = test_writer.into_string();
+    let re_pos = Regex::new(r"Position +\d+: .+ nodes \d+ time \d+\n").unwrap();
+    assert_eq!(40, re_pos.find_iter(&out).count());
+    assert!(
+        Regex::new(r"Total time \(ms\): \d+\nNodes searched: \d+\nNodes/second: \d+\n$")
+            .unwrap()
+            .is_match(&out)
+    );
+}
+
+#[test]
+fn run_command_position_chess_960() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+
+    assert!(p
+        .run_command("setoption name UCI_Chess960 value true\n", &mut engine)
+        .is_ok());
+
+    assert_matches!(engine.variant(), Variant::Chess960(_, _));
+    assert_eq!(None, engine.position());
+
+    let invalid_commands = [
+        &format!("position fen {}\n", FEN_STR),
+        &format!("position fen {} moves e1g1\n", FEN_STR),
+    ];
+    for inv_cmd in invalid_commands {
+        assert!(p.run_command(inv_cmd, &mut engine).is_err());
+    }
+    assert_eq!(None, engine.position());
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert_eq!(Some(&Position::initial()), engine.position());
+
+    assert!(p
+        .run_command(
+            format!("position fen {}\n", FEN_STR_CHESS_960).as_str(),
+            &mut engine
+        )
+        .is_ok());
+    assert_eq!(Fen::str_to_pos(FEN_STR).ok().as_ref(), engine.position());
+
+    assert!(p
+        .run_command(
+            &format!("position fen {} moves e1h1\n", FEN_STR_CHESS_960),
+            &mut engine
+        )
+        .is_ok());
+    let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQ1RK1 b - - 2 8";
+    assert_eq!(
+        Fen::str_to_pos_chess_960(fen).ok().as_ref(),
+        engine.position()
+    );
+
+    // Abbreviated FEN
+    assert!(p
+        .run_command(
+            "position fen rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w HA moves e1h1\n",
+            &mut engine
+        )
+        .is_ok());
+    let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQ1RK1 b - - 1 1";
+    assert_eq!(
+        Fen::str_to_pos_chess_960(fen).ok().as_ref(),
+        engine.position()
+    );
+}
+
+#[test]
+fn run_command_perft() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    {
+        let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+        let mut p = Parser::new(uci_out);
+
+        p.register_command(String::from("perft"), Box::new(perft::run_command));
+        p.register_command(String::from("position"), Box::new(position::run_command));
+        p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+
+        // No position set
+        assert!(p.run_command("perft 1\n", &mut engine).is_err());
+
+        assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+        let invalid_commands = ["perft\n", "perft invalid\n", "perft -1\n", "perft 1 2\n"];
+        for inv_cmd in invalid_commands {
+            assert!(p.run_command(inv_cmd, &mut engine).is_err());
+        }
+        assert!(p.run_command("perft 3\n", &mut engine).is_ok());
+
+        assert!(p
+            .run_command("setoption name UCI_Chess960 value true\n", &mut engine)
+            .is_ok());
+        assert!(p
+            .run_command(
+                format!("position fen {}\n", FEN_STR_CHESS_960).as_str(),
+                &mut engine
+            )
+            .is_ok());
+        assert!(p.run_command("perft 3\n", &mut engine).is_ok());
+    }
+    let out = test_writer.into_string();
+    let re = Regex::new(r"Nodes searched: (\d+)\nTotal time \(ms\): \d+\n").unwrap();
+    let nodes = re
+        .captures_iter(&out)
+        .map(|c| c[1].parse::<usize>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![8_902, 62_379], nodes);
+}
+
+#[test]
+fn run_command_eval() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    {
+        let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+        let mut p = Parser::new(uci_out);
+
+        p.register_command(String::from("eval"), Box::new(cmd_eval::run_command));
+        p.register_command(String::from("position"), Box::new(position::run_command));
+
+        // No position set
+        assert!(p.run_command("eval\n", &mut engine).is_err());
+
+        assert!(p
+            .run_command(format!("position fen {}\n", FEN_STR).as_str(), &mut engine)
+            .is_ok());
+        assert!(p.run_command("eval invalid\n", &mut engine).is_err());
+        assert!(p.run_command("eval\n", &mut engine).is_ok());
+    }
+    let out = test_writer.into_string();
+    for term in [
+        "Material + PST",
+        "Tempo",
+        "Passed pawns",
+        "Mobility",
+        "Bishop pair",
+        "King tropism",
+        "Total",
+    ] {
+        assert!(
+            Regex::new(&format!(
+                r"{} \|( +-?\d+){{2}} \|( +-?\d+){{2}} \|( +-?\d+){{2}}\n",
+                regex::escape(term)
+            ))
+            .unwrap()
+            .is_match(&out),
+            "{term}\n{out}"
+        );
+    }
+    let trace = Complex::new().eval_trace(&Fen::str_to_pos(FEN_STR).unwrap());
+    assert!(out.contains(&format!(
+        "Blended score (white side): {}\n",
+        trace.blended_score()
+    )));
+    let score = Complex::new().eval(&Fen::str_to_pos(FEN_STR).unwrap());
+    assert!(out.contains(&format!("Final evaluation (white side): {score}\n")));
+}
+
+#[test]
+fn run_command_ucinewgame() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(
+        String::from("ucinewgame"),
+        Box::new(ucinewgame::run_command),
+    );
+
+    assert_eq!(None, engine.position());
+
+    assert!(p.run_command("ucinewgame invalid\n", &mut engine).is_err());
+
+    assert!(p.run_command("ucinewgame\n", &mut engine).is_ok());
+    assert_eq!(None, engine.position());
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert_eq!(Some(&Position::initial()), engine.position());
+
+    assert!(p.run_command("ucinewgame\n", &mut engine).is_ok());
+    assert_eq!(None, engine.position());
+}
+
+#[test]
+fn run_command_go() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    // Run "go" without setting a position
+    assert!(p.run_command("go invalid\n", &mut engine).is_err());
+    assert!(p.run_command("go\n", &mut engine).is_err());
+
+    // Run "go" with invalid options
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth\n", &mut engine).is_err());
+    assert!(p.run_command("go depth invalid\n", &mut engine).is_err());
+    assert!(p.run_command("go movetime\n", &mut engine).is_err());
+    assert!(p.run_command("go movetime invalid\n", &mut engine).is_err());
+    // Options must not appear more than once
+    assert!(p.run_command("go depth 2 depth 2\n", &mut engine).is_err());
+
+    // Run "go" followed by "stop" after setting a position
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("info"));
+    assert!(output.contains("depth"));
+    assert!(output.contains("score"));
+    assert!(output.contains("nodes"));
+    assert!(output.contains("nps"));
+    assert!(output.contains("time"));
+    assert!(output.contains("hashfull"));
+    assert!(output.contains("pv"));
+    assert!(!output.contains("bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(!output.contains("info"));
+    assert!(!output.contains("bestmove"));
+
+    // Option "depth"
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("depth 2"));
+    assert!(output.contains("bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(!contains(test_writer.split_off(0), "bestmove"));
 
     // Option "nodes" should not stop the search before depth 1 is finished
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
@@ -394,25 +1114,345 @@ fn run_command_go() {
     assert!(p.run_command("stop\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(20));
     assert!(contains(test_writer.split_off(0), "bestmove"));
-    assert!(p.run_command("stop\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    assert!(!contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(!contains(test_writer.split_off(0), "bestmove"));
+
+    assert!(p
+        .run_command("go movetime 100 infinite\n", &mut engine)
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    assert!(!contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(!contains(test_writer.split_off(0), "bestmove"));
+}
+
+#[test]
+fn hashfull() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(
+        String::from("ucinewgame"),
+        Box::new(ucinewgame::run_command),
+    );
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+
+    let re = Regex::new(r"hashfull (\d+)").unwrap();
+    let hashfull_values = |output: &str| -> Vec<u16> {
+        re.captures_iter(output)
+            .map(|c| c[1].parse::<u16>().unwrap())
+            .collect()
+    };
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth 6\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(1000));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    let values = hashfull_values(&output);
+    assert!(!values.is_empty());
+    let last = *values.last().unwrap();
+    assert!(last > 0);
+    // The table fills up while searching
+    assert!(values.windows(2).all(|w| w[0] <= w[1]));
+
+    // The table is cleared on ucinewgame
+    assert!(p.run_command("ucinewgame\n", &mut engine).is_ok());
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth 1\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    let values = hashfull_values(&output);
+    assert_eq!(1, values.len());
+    assert!(values[0] < last);
+
+    // The table is cleared with the "Clear Hash" option
+    assert!(p.run_command("go depth 6\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(1000));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    let last = *hashfull_values(&output).last().unwrap();
+    assert!(p
+        .run_command("setoption name Clear Hash\n", &mut engine)
+        .is_ok());
+    assert!(p.run_command("go depth 1\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    let values = hashfull_values(&output);
+    assert_eq!(1, values.len());
+    assert!(values[0] < last);
+}
+
+#[test]
+fn currmove() {
+    // When the info is sent is covered by the search tests
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(Box::new(test_writer.clone()), "0.1.2", engine_options);
+    let m = Move::new(Square::E2, Square::E3, MoveType::QUIET);
+    assert!(uci_out
+        .info_current_move(CurrentMove::new(12, m, 5))
+        .is_ok());
+    drop(uci_out);
+    assert_eq!(
+        "info depth 12 currmove e2e3 currmovenumber 5\n",
+        test_writer.into_string()
+    );
+}
+
+#[test]
+fn run_command_go_time() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(p.run_command("go wtime\n", &mut engine).is_err());
+    assert!(p.run_command("go btime\n", &mut engine).is_err());
+    assert!(p.run_command("go winc\n", &mut engine).is_err());
+    assert!(p.run_command("go binc\n", &mut engine).is_err());
+    assert!(p
+        .run_command("go wtime 500 btime 800 winc 100 binc 100\n", &mut engine)
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(500));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+
+    assert!(p
+        .run_command("position startpos moves e2e4\n", &mut engine)
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(p
+        .run_command("go wtime 500 btime 800 winc 100 binc 100\n", &mut engine)
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(800));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+}
+
+#[test]
+fn run_command_go_time_limit_exceeded() {
+    // Even if the time limit is exceeded, the engine should still send
+    // a bestmove response to a go command
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+
+    assert!(p
+        .run_command("setoption name Move Overhead value 100\n", &mut engine)
+        .is_ok());
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(p.run_command("go wtime 0 btime 0\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+
+    assert!(p
+        .run_command("position startpos moves e2e4\n", &mut engine)
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(p.run_command("go wtime 0 btime 0\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+}
+
+#[test]
+fn run_command_go_twice() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    assert!(!contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    assert!(!contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+}
+
+#[test]
+fn stop_and_go_during_depth_one() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    // The stop and the second go arrive while the first search is still in
+    // depth 1. Both searches answer with a bestmove.
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    test_writer.wait_for("bestmove", Duration::from_secs(10));
+    test_writer.wait_for("bestmove", Duration::from_secs(10));
+}
+
+#[test]
+fn run_command_go_without_limits() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    // Bare `go` searches until `stop`, even if the search has finished
+    // (mate in one, so the maximum depth is reached quickly)
+    assert!(p
+        .run_command(
+            "position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\n",
+            &mut engine
+        )
+        .is_ok());
+    assert!(p.run_command("go\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(500));
+    assert!(!contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(contains(test_writer.split_off(0), "bestmove a1a8"));
+
+    // `movestogo 0` must not crash the time manager
+    assert!(p
+        .run_command("go wtime 1000 movestogo 0\n", &mut engine)
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(1100));
+    assert!(contains(test_writer.split_off(0), "bestmove a1a8"));
+}
+
+#[test]
+fn run_command_go_with_negative_value() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p
+        .run_command("go wtime 100 btime -1\n", &mut engine)
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("go wtime -1\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("go depth -1\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+    assert!(contains(test_writer.split_off(0), "bestmove"));
+}
 
-    assert!(p
-        .run_command("go movetime 100 infinite\n", &mut engine)
-        .is_ok());
+#[test]
+fn run_command_isready_during_go() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("isready"), Box::new(is_ready::run_command));
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(400));
     assert!(!contains(test_writer.split_off(0), "bestmove"));
-    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    assert!(p.run_command("isready\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(20));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
+    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(out_str.contains("readyok\n"));
     assert!(p.run_command("stop\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(20));
-    assert!(!contains(test_writer.split_off(0), "bestmove"));
+    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(out_str.contains("bestmove"));
 }
 
 #[test]
-fn run_command_go_time() {
+fn isready_answered_immediately_during_go() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -424,36 +1464,42 @@ fn run_command_go_time() {
     let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
     let mut p = Parser::new(uci_out);
 
+    p.register_command(String::from("isready"), Box::new(is_ready::run_command));
     p.register_command(String::from("position"), Box::new(position::run_command));
     p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    let mut is_ready = |p: &mut Parser, engine: &mut Engine| {
+        let start = Instant::now();
+        assert!(p.run_command("isready\n", engine).is_ok());
+        assert!(String::from_utf8(test_writer.split_off(0))
+            .unwrap()
+            .contains("readyok\n"));
+        // Generous for slow CI machines, but far below the search time
+        assert!(start.elapsed() < Duration::from_millis(500));
+    };
 
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    assert!(p.run_command("go wtime\n", &mut engine).is_err());
-    assert!(p.run_command("go btime\n", &mut engine).is_err());
-    assert!(p.run_command("go winc\n", &mut engine).is_err());
-    assert!(p.run_command("go binc\n", &mut engine).is_err());
-    assert!(p
-        .run_command("go wtime 500 btime 800 winc 100 binc 100\n", &mut engine)
-        .is_ok());
-    std::thread::sleep(Duration::from_millis(500));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("go movetime 5000\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    for _ in 0..10 {
+        is_ready(&mut p, &mut engine);
+        std::thread::sleep(Duration::from_millis(50));
+    }
 
+    // Option changes are applied after the search and don't delay readyok
     assert!(p
-        .run_command("position startpos moves e2e4\n", &mut engine)
-        .is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    assert!(p
-        .run_command("go wtime 500 btime 800 winc 100 binc 100\n", &mut engine)
+        .run_command("setoption name Hash value 8\n", &mut engine)
         .is_ok());
-    std::thread::sleep(Duration::from_millis(800));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
+    is_ready(&mut p, &mut engine);
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    test_writer.wait_for("bestmove", Duration::from_secs(10));
+    assert_eq!(8 * 2_usize.pow(20), engine.hash_size());
 }
 
 #[test]
-fn run_command_go_time_limit_exceeded() {
-    // Even if the time limit is exceeded, the engine should still send
-    // a bestmove response to a go command
+fn bestmove_for_every_go() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -465,31 +1511,82 @@ fn run_command_go_time_limit_exceeded() {
     let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
     let mut p = Parser::new(uci_out);
 
-    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
     p.register_command(String::from("position"), Box::new(position::run_command));
     p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
 
-    assert!(p
-        .run_command("setoption name Move Overhead value 100\n", &mut engine)
-        .is_ok());
-
+    // Xorshift, so the delays are reproducible
+    let mut rand_state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random_delay = || {
+        rand_state ^= rand_state << 13;
+        rand_state ^= rand_state >> 7;
+        rand_state ^= rand_state << 17;
+        Duration::from_micros(rand_state % 2000)
+    };
+
+    let go_commands = [
+        "go infinite\n",
+        "go wtime 10000 btime 10000\n",
+        "go movetime 5\n",
+        "go depth 3\n",
+    ];
+    let num_searches = 3000;
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    assert!(p.run_command("go wtime 0 btime 0\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(50));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
+    for i in 0..num_searches {
+        assert!(p
+            .run_command(go_commands[i % go_commands.len()], &mut engine)
+            .is_ok());
+        std::thread::sleep(random_delay());
+        assert!(p.run_command("stop\n", &mut engine).is_ok());
+        std::thread::sleep(random_delay());
+    }
 
-    assert!(p
-        .run_command("position startpos moves e2e4\n", &mut engine)
-        .is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    assert!(p.run_command("go wtime 0 btime 0\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(50));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
+    let start = Instant::now();
+    let mut output = String::new();
+    while output.matches("bestmove").count() < num_searches
+        && start.elapsed() < Duration::from_secs(10)
+    {
+        std::thread::sleep(Duration::from_millis(10));
+        output.push_str(&String::from_utf8(test_writer.split_off(0)).unwrap());
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    output.push_str(&String::from_utf8(test_writer.split_off(0)).unwrap());
+    assert_eq!(num_searches, output.matches("bestmove").count());
+    assert!(!output.contains("bestmove 0000"));
 }
 
 #[test]
-fn run_command_go_twice() {
+fn run_command_quit() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+
+    p.register_command(String::from("quit"), Box::new(quit::run_command));
+
+    assert!(p.run_command("quit invalid\n", &mut engine).is_err());
+    let res = p.run_command("quit\n", &mut engine);
+    assert!(res.is_ok());
+    assert_eq!(Some(ParserMessage::Quit), res.unwrap());
+    assert!(String::from_utf8(test_writer.split_off(0))
+        .unwrap()
+        .is_empty());
+    let res = p.run_command("ignored quit\n", &mut engine);
+    assert!(res.is_ok());
+    assert_eq!(Some(ParserMessage::Quit), res.unwrap());
+    assert!(String::from_utf8(test_writer.split_off(0))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn info_score_equal_from_both_sides() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -503,22 +1600,94 @@ fn run_command_go_twice() {
 
     p.register_command(String::from("position"), Box::new(position::run_command));
     p.register_command(String::from("go"), Box::new(go::run_command));
-    p.register_command(String::from("stop"), Box::new(stop::run_command));
 
-    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-    assert!(!contains(test_writer.split_off(0), "bestmove"));
-    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
+    let re_info_score = Regex::new(r"score cp \d+").unwrap();
+
+    // Set up King's Gambit
+    assert!(p
+        .run_command(
+            "position fen rnbqkbnr/pppp1ppp/8/4p3/4PP2/8/PPPP2PP/RNBQKBNR b KQkq - 0 2\n",
+            &mut engine
+        )
+        .is_ok());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(p.run_command("go depth 1\n", &mut engine).is_ok());
     std::thread::sleep(Duration::from_millis(400));
-    assert!(!contains(test_writer.split_off(0), "bestmove"));
-    assert!(p.run_command("stop\n", &mut engine).is_ok());
+
+    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
+    let out_lines = out_str.split('\n');
+    let mut rev_out_lines = out_lines.rev();
+    let _empty = rev_out_lines.next();
+    let best_move_first = rev_out_lines.next().unwrap();
+    let info_first = rev_out_lines.next().unwrap();
+    let score_first = re_info_score
+        .captures(info_first)
+        .unwrap()
+        .get(0)
+        .unwrap()
+        .as_str();
+
+    // Set up mirrored position
+    assert!(p
+        .run_command(
+            "position fen rnbqkbnr/pppp2pp/8/4pp2/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2\n",
+            &mut engine
+        )
+        .is_ok());
     std::thread::sleep(Duration::from_millis(20));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("go depth 1\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(400));
+
+    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
+    let out_lines = out_str.split('\n');
+    let mut rev_out_lines = out_lines.rev();
+    let _empty = rev_out_lines.next();
+    let best_move_second = rev_out_lines.next().unwrap();
+    let info_second = rev_out_lines.next().unwrap();
+    let score_second = re_info_score
+        .captures(info_second)
+        .unwrap()
+        .get(0)
+        .unwrap()
+        .as_str();
+
+    assert_ne!(best_move_first, best_move_second);
+    assert_eq!(score_first, score_second);
 }
 
 #[test]
-fn run_command_go_with_negative_value() {
+fn info_score_bound() {
+    let test_writer = TestBuffer::new();
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::new(Mutex::new(EngineOptions::default())),
+    );
+    let res = SearchResult::new(5, 7, 25, 1000, 1000, 0, Move::NULL, MoveList::new());
+    assert_eq!(ScoreBound::Exact, res.score_bound());
+    let lower = res.clone().with_score_bound(ScoreBound::LowerBound);
+    // From the other side's point of view, a lower bound is an upper bound
+    assert_eq!(ScoreBound::UpperBound, (-lower.clone()).score_bound());
+
+    assert!(uci_out.info_depth_finished(Some(res)).is_ok());
+    assert!(uci_out.info_depth_finished(Some(lower.clone())).is_ok());
+    assert!(uci_out.info_depth_finished(Some(-lower)).is_ok());
+    drop(uci_out);
+    let out = test_writer.into_string();
+    let mut lines = out.lines();
+    assert!(lines.next().unwrap().contains(" score cp 25 nodes "));
+    assert!(lines
+        .next()
+        .unwrap()
+        .contains(" score cp 25 lowerbound nodes "));
+    assert!(lines
+        .next()
+        .unwrap()
+        .contains(" score cp -25 upperbound nodes "));
+}
+
+#[test]
+fn analyse_mode_refutations() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -532,24 +1701,50 @@ fn run_command_go_with_negative_value() {
 
     p.register_command(String::from("position"), Box::new(position::run_command));
     p.register_command(String::from("go"), Box::new(go::run_command));
-    p.register_command(String::from("stop"), Box::new(stop::run_command));
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
 
+    // Disabled by default
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth 3\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    assert!(!output.contains("refutation"));
+
     assert!(p
-        .run_command("go wtime 100 btime -1\n", &mut engine)
+        .run_command("setoption name UCI_AnalyseMode value true\n", &mut engine)
         .is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
-    assert!(p.run_command("go wtime -1\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
-    assert!(p.run_command("go depth -1\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-    assert!(contains(test_writer.split_off(0), "bestmove"));
+    assert!(p.run_command("go depth 3\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    let re =
+        Regex::new(r"(?m)^info refutation [a-h][1-8][a-h][1-8] [a-h][1-8][a-h][1-8]$").unwrap();
+    assert!(re.is_match(&output));
+
+    assert!(p
+        .run_command("setoption name UCI_AnalyseMode value false\n", &mut engine)
+        .is_ok());
+    assert!(p.run_command("go depth 3\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("bestmove"));
+    assert!(!output.contains("refutation"));
 }
 
 #[test]
-fn run_command_isready_during_go() {
+fn own_book() {
+    // A book with a single entry, e2e4 in the initial position
+    let mut book = Vec::new();
+    book.extend_from_slice(&u64::from(PolyglotKey::new(&Position::initial())).to_be_bytes());
+    // Target file, target rank, origin file, origin rank, 3 bits each
+    let e2e4: u16 = 4 | 3 << 3 | 4 << 6 | 1 << 9;
+    book.extend_from_slice(&e2e4.to_be_bytes());
+    book.extend_from_slice(&1_u16.to_be_bytes());
+    book.extend_from_slice(&0_u32.to_be_bytes());
+    let book_path = std::env::temp_dir().join(format!("fatalii_book_{}.bin", std::process::id()));
+    std::fs::write(&book_path, book).unwrap();
+
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -561,57 +1756,109 @@ fn run_command_isready_during_go() {
     let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
     let mut p = Parser::new(uci_out);
 
-    p.register_command(String::from("isready"), Box::new(is_ready::run_command));
     p.register_command(String::from("position"), Box::new(position::run_command));
     p.register_command(String::from("go"), Box::new(go::run_command));
-    p.register_command(String::from("stop"), Box::new(stop::run_command));
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+    p.register_command(String::from("debug"), Box::new(debug::run_command));
+    assert!(p.run_command("debug on\n", &mut engine).is_ok());
+
+    assert!(p
+        .run_command(
+            "setoption name BookFile value nonexistent.bin\n",
+            &mut engine
+        )
+        .is_ok());
+    assert!(contains(
+        test_writer.split_off(0),
+        "info string Error loading book file nonexistent.bin"
+    ));
+    assert!(p
+        .run_command(
+            format!("setoption name BookFile value {}\n", book_path.display()).as_str(),
+            &mut engine
+        )
+        .is_ok());
+    assert!(contains(
+        test_writer.split_off(0),
+        "loaded with 1 entries\n"
+    ));
+    std::fs::remove_file(&book_path).unwrap();
 
+    // The book is only used with OwnBook enabled
     assert!(p.run_command("position startpos\n", &mut engine).is_ok());
-    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-    assert!(!contains(test_writer.split_off(0), "bestmove"));
-    assert!(p.run_command("isready\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
-    assert!(out_str.contains("readyok\n"));
-    assert!(p.run_command("stop\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
-    assert!(out_str.contains("bestmove"));
-}
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(output.contains("info depth 2"));
+    assert!(output.contains("bestmove"));
+
+    assert!(p
+        .run_command("setoption name OwnBook value true\n", &mut engine)
+        .is_ok());
+    let _ = test_writer.split_off(0);
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(
+        "bestmove e2e4\n",
+        String::from_utf8(test_writer.split_off(0)).unwrap()
+    );
+
+    // Not in the book
+    assert!(p
+        .run_command("position startpos moves e2e4\n", &mut engine)
+        .is_ok());
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(contains(test_writer.split_off(0), "info depth 2"));
 
-#[test]
-fn run_command_quit() {
-    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
-    let mut test_writer = TestBuffer::new();
-    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
-    let uci_out = UciOut::new(
-        Box::new(test_writer.clone()),
-        "0.1.2",
-        Arc::clone(&engine_options),
+    // The Polyglot key doesn't depend on the move number, but the book is
+    // only used up to BookDepth
+    assert!(p
+        .run_command(
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 5\n",
+            &mut engine
+        )
+        .is_ok());
+    assert!(p
+        .run_command("setoption name BookDepth value 4\n", &mut engine)
+        .is_ok());
+    let _ = test_writer.split_off(0);
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(contains(test_writer.split_off(0), "info depth 2"));
+    assert!(p
+        .run_command("setoption name BookDepth value 5\n", &mut engine)
+        .is_ok());
+    let _ = test_writer.split_off(0);
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(
+        "bestmove e2e4\n",
+        String::from_utf8(test_writer.split_off(0)).unwrap()
     );
-    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
-    let mut p = Parser::new(uci_out);
 
-    p.register_command(String::from("quit"), Box::new(quit::run_command));
+    // Neither are searches in analyse mode
+    assert!(p
+        .run_command("setoption name UCI_AnalyseMode value true\n", &mut engine)
+        .is_ok());
+    let _ = test_writer.split_off(0);
+    assert!(p.run_command("go depth 2\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(contains(test_writer.split_off(0), "info depth 2"));
+    assert!(p
+        .run_command("setoption name UCI_AnalyseMode value false\n", &mut engine)
+        .is_ok());
 
-    assert!(p.run_command("quit invalid\n", &mut engine).is_err());
-    let res = p.run_command("quit\n", &mut engine);
-    assert!(res.is_ok());
-    assert_eq!(Some(ParserMessage::Quit), res.unwrap());
-    assert!(String::from_utf8(test_writer.split_off(0))
-        .unwrap()
-        .is_empty());
-    let res = p.run_command("ignored quit\n", &mut engine);
-    assert!(res.is_ok());
-    assert_eq!(Some(ParserMessage::Quit), res.unwrap());
-    assert!(String::from_utf8(test_writer.split_off(0))
-        .unwrap()
-        .is_empty());
+    // Infinite searches are never answered from the book
+    assert!(p.run_command("go infinite\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(100));
+    engine.stop();
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(contains(test_writer.split_off(0), "info depth"));
 }
 
 #[test]
-fn info_score_equal_from_both_sides() {
+fn analyse_mode_ignores_contempt() {
     let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
     let mut test_writer = TestBuffer::new();
     let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
@@ -625,59 +1872,30 @@ fn info_score_equal_from_both_sides() {
 
     p.register_command(String::from("position"), Box::new(position::run_command));
     p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("setoption"), Box::new(set_option::run_command));
 
-    let re_info_score = Regex::new(r"score cp \d+").unwrap();
-
-    // Set up King's Gambit
+    // Every move draws by the fifty-move rule
     assert!(p
         .run_command(
-            "position fen rnbqkbnr/pppp1ppp/8/4p3/4PP2/8/PPPP2PP/RNBQKBNR b KQkq - 0 2\n",
+            "position fen 5k2/8/4K1R1/8/8/8/8/8 b - - 99 1\n",
             &mut engine
         )
         .is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    assert!(p.run_command("go depth 1\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-
-    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
-    let out_lines = out_str.split('\n');
-    let mut rev_out_lines = out_lines.rev();
-    let _empty = rev_out_lines.next();
-    let best_move_first = rev_out_lines.next().unwrap();
-    let info_first = rev_out_lines.next().unwrap();
-    let score_first = re_info_score
-        .captures(info_first)
-        .unwrap()
-        .get(0)
-        .unwrap()
-        .as_str();
-
-    // Set up mirrored position
     assert!(p
-        .run_command(
-            "position fen rnbqkbnr/pppp2pp/8/4pp2/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2\n",
-            &mut engine
-        )
+        .run_command("setoption name Contempt value 50\n", &mut engine)
         .is_ok());
-    std::thread::sleep(Duration::from_millis(20));
-    assert!(p.run_command("go depth 1\n", &mut engine).is_ok());
-    std::thread::sleep(Duration::from_millis(400));
-
-    let out_str = String::from_utf8(test_writer.split_off(0)).unwrap();
-    let out_lines = out_str.split('\n');
-    let mut rev_out_lines = out_lines.rev();
-    let _empty = rev_out_lines.next();
-    let best_move_second = rev_out_lines.next().unwrap();
-    let info_second = rev_out_lines.next().unwrap();
-    let score_second = re_info_score
-        .captures(info_second)
-        .unwrap()
-        .get(0)
-        .unwrap()
-        .as_str();
+    assert!(p.run_command("go depth 3\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    // Scaled down in the endgame, to half the contempt without pieces
+    // (50 * (50 + 50 * 2 / 24) / 100)
+    assert!(contains(test_writer.split_off(0), "score cp -27 "));
 
-    assert_ne!(best_move_first, best_move_second);
-    assert_eq!(score_first, score_second);
+    assert!(p
+        .run_command("setoption name UCI_AnalyseMode value true\n", &mut engine)
+        .is_ok());
+    assert!(p.run_command("go depth 3\n", &mut engine).is_ok());
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(contains(test_writer.split_off(0), "score cp 0 "));
 }
 
 #[test]
@@ -900,3 +2118,380 @@ fn stress() {
         }
     }
 }
+
+#[test]
+fn debug_log_file() {
+    let log_path = |name: &str| {
+        std::env::temp_dir().join(format!("fatalii_{name}_{}.log", std::process::id()))
+    };
+    let first_path = log_path("first");
+    let second_path = log_path("second");
+    let _ = std::fs::remove_file(&first_path);
+    let _ = std::fs::remove_file(&second_path);
+
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    {
+        let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+        let mut p = Parser::new(uci_out);
+        p.register_command(String::from("isready"), Box::new(is_ready::run_command));
+        p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+
+        for path in [&first_path, &second_path] {
+            assert!(p
+                .run_command(
+                    format!("setoption name Debug Log File value {}\n", path.display()).as_str(),
+                    &mut engine
+                )
+                .is_ok());
+            assert!(p.run_command("isready\n", &mut engine).is_ok());
+        }
+        assert!(p
+            .run_command("setoption name Debug Log File value <empty>\n", &mut engine)
+            .is_ok());
+        assert!(p.run_command("isready\n", &mut engine).is_ok());
+        // Dropping the output writes the pending log entries
+    }
+    // The real output is not affected by logging
+    assert_eq!("readyok\nreadyok\nreadyok\n", test_writer.into_string());
+
+    let line_regex = Regex::new(r"^\d+\.\d{3} (>>|<<) ").unwrap();
+    for (path, expected_lines) in [
+        // A changed path applies from the next line on
+        (
+            &first_path,
+            vec![
+                ">> isready",
+                "<< readyok",
+                ">> setoption name Debug Log File value",
+            ],
+        ),
+        (
+            &second_path,
+            vec![
+                ">> isready",
+                "<< readyok",
+                ">> setoption name Debug Log File value <empty>",
+            ],
+        ),
+    ] {
+        let log = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let lines = log.lines().collect::<Vec<&str>>();
+        assert_eq!(expected_lines.len(), lines.len(), "{log}");
+        for (line, expected) in lines.iter().zip(expected_lines) {
+            assert!(line_regex.is_match(line), "{line}");
+            assert!(line.contains(expected), "{line}");
+        }
+    }
+}
+
+#[test]
+fn run_command_genfens() {
+    let book_path =
+        std::env::temp_dir().join(format!("fatalii_genfens_{}.epd", std::process::id()));
+    // One FEN and one EPD line
+    std::fs::write(
+        &book_path,
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\nr3k2r/8/8/8/8/8/8/R3K2R w KQkq - bm O-O; id \"castling\";\n",
+    )
+    .unwrap();
+
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+    p.register_command(String::from("genfens"), Box::new(genfens::run_command));
+
+    let invalid_commands = [
+        "genfens\n",
+        "genfens -1\n",
+        "genfens 10 seed\n",
+        "genfens 10 seed x\n",
+        "genfens 10 invalid 1\n",
+        "genfens 10 book nonexistent.epd\n",
+    ];
+    for cmd in invalid_commands {
+        assert!(p.run_command(cmd, &mut engine).is_err(), "{cmd}");
+    }
+
+    let mut genfens = |cmd: &str| {
+        assert!(p.run_command(cmd, &mut engine).is_ok());
+        let out = String::from_utf8(test_writer.split_off(0)).unwrap();
+        out.lines()
+            .map(|l| String::from(l.strip_prefix("info string genfens ").unwrap()))
+            .collect::<Vec<String>>()
+    };
+
+    let fens = genfens("genfens 20 seed 42 book None\n");
+    assert_eq!(20, fens.len());
+    let mut unique = fens.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(fens.len(), unique.len());
+    for fen in fens.iter() {
+        let pos = Fen::str_to_pos(fen).unwrap();
+        // 8 random plies from the start position
+        assert_eq!(5, pos.move_count());
+        assert_eq!(Side::White, pos.side_to_move());
+    }
+    // The positions only depend on the seed
+    assert_eq!(fens, genfens("genfens 20 seed 42 book None\n"));
+    assert_ne!(fens, genfens("genfens 20 seed 43 book None\n"));
+
+    let fens =
+        genfens(format!("genfens 10 seed 1 book {} plies 1\n", book_path.display()).as_str());
+    std::fs::remove_file(&book_path).unwrap();
+    assert_eq!(10, fens.len());
+    for fen in fens.iter() {
+        let pos = Fen::str_to_pos(fen).unwrap();
+        assert_eq!(Side::Black, pos.side_to_move());
+        assert!(fen.starts_with("4k3/") || fen.starts_with("r3k2r/"));
+    }
+}
+
+#[test]
+fn run_command_speedtest() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("speedtest"), Box::new(speedtest::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+    p.register_command(String::from("quit"), Box::new(quit::run_command));
+
+    for cmd in ["speedtest 0\n", "speedtest x\n", "speedtest 1 2\n"] {
+        assert!(p.run_command(cmd, &mut engine).is_err(), "{cmd}");
+    }
+
+    assert!(p
+        .run_command("position startpos moves e2e4\n", &mut engine)
+        .is_ok());
+    let pos_hash = engine.position_hash();
+    // The speedtest runs in the background and stops early on stop
+    let start = Instant::now();
+    assert!(p.run_command("speedtest 60\n", &mut engine).is_ok());
+    assert!(p.run_command("stop\n", &mut engine).is_ok());
+    assert!(start.elapsed() < Duration::from_millis(5000));
+    let out = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(out.contains("Thread 1: nodes "));
+    assert!(out.contains("Nodes/second: "));
+    // The engine state is left untouched
+    assert_eq!(pos_hash, engine.position_hash());
+
+    // Quit stops the speedtest, but its result is still printed
+    assert!(p.run_command("speedtest 1\n", &mut engine).is_ok());
+    assert_eq!(
+        Some(ParserMessage::Quit),
+        p.run_command("quit\n", &mut engine).unwrap()
+    );
+    assert!(contains(test_writer.split_off(0), "Nodes searched: "));
+}
+
+#[test]
+fn trace_search_tree() {
+    let log_path = std::env::temp_dir().join(format!("fatalii_trace_{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&log_path);
+
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    {
+        let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+        let mut p = Parser::new(uci_out);
+        p.register_command(String::from("debug"), Box::new(debug::run_command));
+        p.register_command(String::from("go"), Box::new(go::run_command));
+        p.register_command(String::from("position"), Box::new(position::run_command));
+        p.register_command(String::from("setoption"), Box::new(set_option::run_command));
+
+        assert!(p
+            .run_command(
+                format!(
+                    "setoption name Debug Log File value {}\n",
+                    log_path.display()
+                )
+                .as_str(),
+                &mut engine
+            )
+            .is_ok());
+        assert!(p
+            .run_command("setoption name TraceDepth value 3\n", &mut engine)
+            .is_ok());
+        assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+        // Not traced without debug mode
+        assert!(p.run_command("go depth 3\n", &mut engine).is_ok());
+        let output = test_writer.wait_for("bestmove", Duration::from_secs(10));
+        assert!(p.run_command("debug on\n", &mut engine).is_ok());
+        assert!(p.run_command("go depth 3\n", &mut engine).is_ok());
+        let output = output + &test_writer.wait_for("bestmove", Duration::from_secs(10));
+        // The trace is not part of the real output
+        assert!(!output.contains("iteration"));
+        // Dropping the output writes the pending log entries
+    }
+
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    std::fs::remove_file(&log_path).unwrap();
+    // The moves are in the engine's own notation (e.g. "e4xd5", "0-0" or
+    // "null" for a null move)
+    let move_list = r#"((?:"(?:[a-h][1-8]x?[a-h][1-8](?:=[NBRQ])?|0-0(?:-0)?|null)",?)*)"#;
+    let bound = r#"(?:null|"(?:exact|lower|upper)")"#;
+    let trace_regex = Regex::new(&format!(
+        r#"^\d+\.\d{{3}} ## \{{"iteration":(\d+),"ply":(\d+),"path":\[{move_list}\],"depth":(\d+),"alpha":-?\d+,"beta":-?\d+,"static_eval":(?:null|-?\d+),"table":(?:null|"(?:miss|hit|cutoff)"),"moves":\[{move_list}\],"score":(?:null|-?\d+),"bound":{bound}\}}$"#
+    ))
+    .unwrap();
+    let trace_lines = log
+        .lines()
+        .filter(|line| line.contains(" ## "))
+        .collect::<Vec<&str>>();
+    assert!(!trace_lines.is_empty());
+    // The debug command comes before all traced nodes
+    let debug_on_line = log.lines().position(|l| l.contains(">> debug on")).unwrap();
+    let first_trace_line = log.lines().position(|l| l.contains(" ## ")).unwrap();
+    assert!(debug_on_line < first_trace_line);
+
+    let mut root_iterations = Vec::new();
+    for line in trace_lines {
+        let captures = trace_regex
+            .captures(line)
+            .unwrap_or_else(|| panic!("{line}"));
+        let iteration = captures[1].parse::<usize>().unwrap();
+        let ply = captures[2].parse::<usize>().unwrap();
+        let path_len = captures[3].matches('"').count() / 2;
+        assert_eq!(ply, path_len, "{line}");
+        assert!(ply <= 3, "{line}");
+        assert!((1..=3).contains(&iteration), "{line}");
+        if ply == 0 {
+            // The root searches all moves (unless the hash table cuts it off)
+            let moves = captures[5].matches('"').count() / 2;
+            assert!(moves > 0 || line.contains(r#""table":"cutoff""#), "{line}");
+            root_iterations.push(iteration);
+        }
+    }
+    // Each iteration has at least one root node (more if the aspiration window
+    // fails)
+    for iteration in 1..=3 {
+        assert!(root_iterations.contains(&iteration), "{root_iterations:?}");
+    }
+}
+
+#[test]
+fn go_depth_zero() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("position"), Box::new(position::run_command));
+
+    // The best move starts the best capture sequence
+    assert!(p
+        .run_command(
+            "position fen 4k3/8/8/3q4/4P3/8/P7/4K3 w - - 0 1\n",
+            &mut engine
+        )
+        .is_ok());
+    assert!(p.run_command("go depth 0\n", &mut engine).is_ok());
+    let output = test_writer.wait_for("bestmove", Duration::from_secs(10));
+    assert!(output.starts_with("info depth 0 "), "{output}");
+    assert!(output.contains(" pv e4d5\n"), "{output}");
+    assert_eq!(
+        " e4d5\n",
+        test_writer.wait_for("\n", Duration::from_secs(10))
+    );
+
+    // Without a capture, the PV is empty, but there is still a legal best move
+    assert!(p.run_command("position startpos\n", &mut engine).is_ok());
+    assert!(p.run_command("go depth 0\n", &mut engine).is_ok());
+    let output = test_writer.wait_for("bestmove", Duration::from_secs(10));
+    assert!(output.starts_with("info depth 0 "), "{output}");
+    assert!(!output.contains(" pv"), "{output}");
+    let best_move = test_writer.wait_for("\n", Duration::from_secs(10));
+    let mut root_moves = MoveList::new();
+    MoveGenerator::generate_moves(&mut root_moves, &Position::initial());
+    assert!(root_moves
+        .iter()
+        .any(|m| format!(" {}{}\n", m.origin(), m.target()) == best_move));
+}
+
+#[test]
+fn no_legal_root_moves() {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let mut test_writer = TestBuffer::new();
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    let uci_out = UciOut::new(
+        Box::new(test_writer.clone()),
+        "0.1.2",
+        Arc::clone(&engine_options),
+    );
+    let mut engine = Engine::new(search_algo, uci_out.clone(), engine_options);
+    let mut p = Parser::new(uci_out);
+    p.register_command(String::from("go"), Box::new(go::run_command));
+    p.register_command(String::from("position"), Box::new(position::run_command));
+    p.register_command(String::from("stop"), Box::new(stop::run_command));
+
+    for (fen, score) in [
+        // Checkmate
+        ("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", "mate 0"),
+        ("8/8/8/8/8/1k6/1q6/K7 w - - 0 1", "mate 0"),
+        // Stalemate
+        ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", "cp 0"),
+        ("8/8/8/8/8/1k6/2q5/K7 w - - 0 1", "cp 0"),
+    ] {
+        assert!(p
+            .run_command(format!("position fen {fen}\n").as_str(), &mut engine)
+            .is_ok());
+        for go in ["go depth 5\n", "go wtime 1000 btime 1000\n"] {
+            assert!(p.run_command(go, &mut engine).is_ok());
+            std::thread::sleep(Duration::from_millis(100));
+            let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+            let lines = output.lines().collect::<Vec<&str>>();
+            assert_eq!(2, lines.len(), "{fen}: {output}");
+            assert!(lines[0]
+                .starts_with(format!("info depth 0 seldepth 0 score {score} nodes 0 ").as_str()));
+            // No PV
+            assert!(!lines[0].contains("pv"), "{fen}: {output}");
+            assert_eq!("bestmove 0000", lines[1], "{fen}");
+        }
+
+        // An infinite search still waits for stop
+        assert!(p.run_command("go infinite\n", &mut engine).is_ok());
+        std::thread::sleep(Duration::from_millis(100));
+        let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+        assert!(output.contains(format!("score {score}").as_str()), "{fen}");
+        assert!(!output.contains("bestmove"), "{fen}");
+        assert!(p.run_command("stop\n", &mut engine).is_ok());
+        std::thread::sleep(Duration::from_millis(20));
+        let output = String::from_utf8(test_writer.split_off(0)).unwrap();
+        assert_eq!("bestmove 0000\n", output, "{fen}");
+    }
+}
@@ -0,0 +1,5 @@
+pub use xboard::{Xboard, XboardError, XboardMessage};
+pub use xboard_out::XboardOut;
+
+mod xboard;
+mod xboard_out;
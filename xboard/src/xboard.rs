@@ -0,0 +1,264 @@
+use crate::XboardOut;
+use engine::Engine;
+use movegen::fen::Fen;
+use movegen::move_generator::MoveGenerator;
+use movegen::position::Position;
+use movegen::position_history::PositionHistory;
+use movegen::r#move::MoveList;
+use movegen::side::Side;
+use search::SearchOptions;
+use std::error::Error;
+use std::time::Duration;
+use uci::UciMove;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum XboardMessage {
+    Quit,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum XboardError {
+    #[error("Error (unknown command): {0}")]
+    UnknownCommand(String),
+    #[error("Error (invalid argument): {0}")]
+    InvalidArgument(String),
+    #[error("Illegal move: {0}")]
+    IllegalMove(String),
+    #[error("tellusererror Illegal position")]
+    IllegalPosition,
+}
+
+// Set by the `level` command
+#[derive(Debug, Clone, Copy)]
+struct TimeControl {
+    // 0 means the base time is for the whole game
+    moves_per_session: usize,
+    base: Duration,
+    increment: Duration,
+}
+
+impl TimeControl {
+    fn moves_to_go(&self, move_count: usize) -> Option<usize> {
+        match self.moves_per_session {
+            0 => None,
+            mps => Some(mps - (move_count.max(1) - 1) % mps),
+        }
+    }
+}
+
+// Unlike UCI, the CECP (XBoard) protocol keeps the game state in the engine.
+// The GUI only sends the moves, and the engine plays its own moves when it's
+// on the move.
+pub struct Xboard {
+    xboard_out: XboardOut,
+    pos_hist: PositionHistory,
+    // The side the engine plays, None in force mode
+    engine_side: Option<Side>,
+    time_control: Option<TimeControl>,
+    engine_time: Option<Duration>,
+    opponent_time: Option<Duration>,
+    movetime: Option<Duration>,
+    depth: Option<usize>,
+}
+
+impl Xboard {
+    pub fn new(xboard_out: XboardOut) -> Self {
+        Self {
+            xboard_out,
+            pos_hist: PositionHistory::new(Position::initial()),
+            engine_side: Some(Side::Black),
+            time_control: None,
+            engine_time: None,
+            opponent_time: None,
+            movetime: None,
+            depth: None,
+        }
+    }
+
+    pub fn run_command(
+        &mut self,
+        s: &str,
+        engine: &mut Engine,
+    ) -> Result<Option<XboardMessage>, Box<dyn Error>> {
+        debug_assert!(s.ends_with('\n') || s.is_empty());
+        // The engine's own move is played asynchronously. Apply it before the
+        // next command, which may depend on it.
+        if let Some(m) = self.xboard_out.take_engine_move() {
+            self.pos_hist.do_move(m);
+        }
+
+        let line = s.trim();
+        let (cmd, args) = match line.split_once(char::is_whitespace) {
+            Some((cmd, args)) => (cmd, args.trim()),
+            None => (line, ""),
+        };
+        match cmd {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" | "draw" => {}
+            "protover" => {
+                if Self::parse_number::<usize>(line, args)? >= 2 {
+                    self.xboard_out.features()?;
+                }
+            }
+            "new" => {
+                self.stop_thinking(engine);
+                self.pos_hist = PositionHistory::new(Position::initial());
+                self.engine_side = Some(Side::Black);
+                self.engine_time = None;
+                self.opponent_time = None;
+                self.movetime = None;
+                self.depth = None;
+                engine.clear_hash_table();
+            }
+            "variant" => {
+                if args != "normal" {
+                    return Err(Box::new(XboardError::InvalidArgument(line.to_string())));
+                }
+            }
+            "force" | "result" => {
+                self.stop_thinking(engine);
+                self.engine_side = None;
+            }
+            "go" => {
+                self.engine_side = Some(self.pos_hist.current_pos().side_to_move());
+                self.think(engine)?;
+            }
+            "usermove" => self.user_move(args, engine)?,
+            "?" => engine.stop(),
+            "time" => self.engine_time = Some(Self::parse_centiseconds(line, args)?),
+            "otim" => self.opponent_time = Some(Self::parse_centiseconds(line, args)?),
+            "level" => {
+                self.time_control = Some(Self::parse_level(line, args)?);
+                self.movetime = None;
+            }
+            "st" => self.movetime = Some(Self::parse_seconds(line, args)?),
+            "sd" => self.depth = Some(Self::parse_number(line, args)?),
+            "setboard" => {
+                self.stop_thinking(engine);
+                let pos = Fen::str_to_pos(args).map_err(|_| XboardError::IllegalPosition)?;
+                self.pos_hist = PositionHistory::new(pos);
+            }
+            "ping" => self.xboard_out.pong(args)?,
+            "post" => self.xboard_out.set_post(true),
+            "nopost" => self.xboard_out.set_post(false),
+            "quit" => {
+                self.stop_thinking(engine);
+                return Ok(Some(XboardMessage::Quit));
+            }
+            _ => return Err(Box::new(XboardError::UnknownCommand(line.to_string()))),
+        }
+        Ok(None)
+    }
+
+    fn user_move(&mut self, move_str: &str, engine: &mut Engine) -> Result<(), Box<dyn Error>> {
+        match UciMove::str_to_move(self.pos_hist.current_pos(), move_str) {
+            Some(m) => self.pos_hist.do_move(m),
+            None => return Err(Box::new(XboardError::IllegalMove(move_str.to_string()))),
+        }
+        if self.engine_side == Some(self.pos_hist.current_pos().side_to_move()) {
+            self.think(engine)?;
+        }
+        Ok(())
+    }
+
+    fn think(&mut self, engine: &mut Engine) -> Result<(), Box<dyn Error>> {
+        let pos = self.pos_hist.current_pos();
+        let mut move_list = MoveList::new();
+        MoveGenerator::generate_moves(&mut move_list, pos);
+        if move_list.is_empty() {
+            let side_to_move = pos.side_to_move();
+            return match (pos.is_in_check(side_to_move), side_to_move) {
+                (true, Side::White) => self.xboard_out.result("0-1", "Black mates"),
+                (true, Side::Black) => self.xboard_out.result("1-0", "White mates"),
+                (false, _) => self.xboard_out.result("1/2-1/2", "Stalemate"),
+            };
+        }
+
+        engine.set_position_history(Some(self.pos_hist.clone()));
+        self.xboard_out.start_thinking();
+        engine.search(self.search_options())?;
+        Ok(())
+    }
+
+    fn stop_thinking(&self, engine: &mut Engine) {
+        self.xboard_out.abort_thinking();
+        engine.stop();
+    }
+
+    fn search_options(&self) -> SearchOptions {
+        let mut options = SearchOptions {
+            depth: self.depth,
+            movetime: self.movetime,
+            ..Default::default()
+        };
+        if self.movetime.is_some() {
+            return options;
+        }
+
+        let own_time = self.engine_time.or(self.time_control.map(|tc| tc.base));
+        if let Some(own_time) = own_time {
+            let opponent_time = self.opponent_time.unwrap_or(own_time);
+            let (white_time, black_time) = match self.pos_hist.current_pos().side_to_move() {
+                Side::White => (own_time, opponent_time),
+                Side::Black => (opponent_time, own_time),
+            };
+            let increment = self.time_control.map(|tc| tc.increment);
+            options.white_time = Some(white_time);
+            options.black_time = Some(black_time);
+            options.white_inc = increment;
+            options.black_inc = increment;
+            options.moves_to_go = self
+                .time_control
+                .and_then(|tc| tc.moves_to_go(self.pos_hist.current_pos().move_count()));
+        }
+        options
+    }
+
+    fn parse_number<T: std::str::FromStr>(line: &str, args: &str) -> Result<T, XboardError> {
+        args.parse::<T>()
+            .map_err(|_| XboardError::InvalidArgument(line.to_string()))
+    }
+
+    // Clock times are sent in centiseconds and may be negative if the engine
+    // has run out of time
+    fn parse_centiseconds(line: &str, args: &str) -> Result<Duration, XboardError> {
+        let centis = Self::parse_number::<i64>(line, args)?;
+        Ok(Duration::from_millis(10 * centis.max(0) as u64))
+    }
+
+    fn parse_seconds(line: &str, args: &str) -> Result<Duration, XboardError> {
+        let secs = Self::parse_number::<f64>(line, args)?;
+        match secs.is_finite() {
+            true => Ok(Duration::from_secs_f64(secs.max(0.0))),
+            false => Err(XboardError::InvalidArgument(line.to_string())),
+        }
+    }
+
+    // level MPS BASE INC, where BASE is in minutes, optionally with seconds
+    // (e.g. 0:30), and INC is in seconds
+    fn parse_level(line: &str, args: &str) -> Result<TimeControl, XboardError> {
+        let err = || XboardError::InvalidArgument(line.to_string());
+        let mut iter = args.split_whitespace();
+        let (mps_str, base_str, inc_str) = match (iter.next(), iter.next(), iter.next()) {
+            (Some(mps), Some(base), Some(inc)) => (mps, base, inc),
+            _ => return Err(err()),
+        };
+        if iter.next().is_some() {
+            return Err(err());
+        }
+
+        let moves_per_session = mps_str.parse::<usize>().map_err(|_| err())?;
+        let base_secs = match base_str.split_once(':') {
+            Some((mins, secs)) => {
+                60 * mins.parse::<u64>().map_err(|_| err())?
+                    + secs.parse::<u64>().map_err(|_| err())?
+            }
+            None => 60 * base_str.parse::<u64>().map_err(|_| err())?,
+        };
+        Ok(TimeControl {
+            moves_per_session,
+            base: Duration::from_secs(base_secs),
+            increment: Self::parse_seconds(line, inc_str)?,
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use engine::EngineOut;
+use eval::ScoreVariant;
+use movegen::r#move::Move;
+use search::search::{CurrentMove, ScoreBound, SearchProgress, SearchResult};
+use std::error::Error;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use uci::UciMove;
+
+struct XboardOutInner {
+    writer: Box<dyn Write + Send>,
+    engine_version: String,
+    post: bool,
+    thinking: bool,
+    // Searches that were aborted, but whose best move hasn't arrived yet
+    moves_to_discard: usize,
+    // The last move played by the engine, not yet seen by the front-end
+    engine_move: Option<Move>,
+}
+
+#[derive(Clone)]
+pub struct XboardOut {
+    inner: Arc<Mutex<XboardOutInner>>,
+}
+
+impl EngineOut for XboardOut {
+    fn info_depth_finished(
+        &self,
+        search_result: Option<SearchResult>,
+    ) -> Result<(), Box<dyn Error>> {
+        match search_result {
+            Some(res) => match self.inner.lock() {
+                Ok(mut inner) => {
+                    // The thinking output has no notation for bounds, so only
+                    // exact scores are posted
+                    if !inner.post
+                        || inner.moves_to_discard > 0
+                        || res.score_bound() != ScoreBound::Exact
+                    {
+                        return Ok(());
+                    }
+                    let pv_str = res
+                        .principal_variation()
+                        .iter()
+                        .take_while(|m| **m != Move::NULL)
+                        .map(|m| UciMove::move_to_str(*m))
+                        .collect::<Vec<String>>()
+                        .join(" ");
+                    Ok(writeln!(
+                        inner.writer,
+                        "{} {} {} {} {}",
+                        res.depth(),
+                        Self::score_to_int(res.score()),
+                        res.time_ms() / 10,
+                        res.nodes(),
+                        pv_str
+                    )?)
+                }
+                Err(e) => {
+                    self.info_string(format!("{e}").as_str())?;
+                    panic!("{e}")
+                }
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn info_current_move(&self, _current_move: CurrentMove) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn info_progress(&self, _progress: SearchProgress) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn trace(&self, _line: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn info_string(&self, s: &str) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(inner.writer, "# {s}")?),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    fn best_move(&self, search_result: Option<Move>) -> Result<(), Box<dyn Error>> {
+        match search_result {
+            Some(m) => match self.inner.lock() {
+                Ok(mut inner) => {
+                    if inner.moves_to_discard > 0 {
+                        inner.moves_to_discard -= 1;
+                        return Ok(());
+                    }
+                    inner.thinking = false;
+                    // There is no move to play if the game is over
+                    if m == Move::NULL {
+                        return Ok(());
+                    }
+                    inner.engine_move = Some(m);
+                    Ok(writeln!(inner.writer, "move {}", UciMove::move_to_str(m))?)
+                }
+                Err(e) => {
+                    self.info_string(format!("{e}").as_str())?;
+                    panic!("{e}");
+                }
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&self) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(inner.writer.flush()?),
+            Err(e) => panic!("{e}"),
+        }
+    }
+}
+
+impl XboardOut {
+    pub fn new(writer: Box<dyn Write + Send>, engine_version: &str) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(XboardOutInner {
+                writer,
+                engine_version: String::from(engine_version),
+                post: false,
+                thinking: false,
+                moves_to_discard: 0,
+                engine_move: None,
+            })),
+        }
+    }
+
+    pub fn set_post(&self, tf: bool) {
+        match self.inner.lock() {
+            Ok(mut inner) => inner.post = tf,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    pub fn start_thinking(&self) {
+        match self.inner.lock() {
+            Ok(mut inner) => inner.thinking = true,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    // The best move of an aborted search must not be played. It arrives
+    // asynchronously, so we only remember to drop it.
+    pub fn abort_thinking(&self) {
+        match self.inner.lock() {
+            Ok(mut inner) => {
+                if inner.thinking {
+                    inner.thinking = false;
+                    inner.moves_to_discard += 1;
+                }
+            }
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    pub fn take_engine_move(&self) -> Option<Move> {
+        match self.inner.lock() {
+            Ok(mut inner) => inner.engine_move.take(),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    pub fn features(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => {
+                let version = inner.engine_version.clone();
+                writeln!(inner.writer, "feature done=0")?;
+                writeln!(
+                    inner.writer,
+                    "feature myname=\"Fatalii {version}\" ping=1 setboard=1 usermove=1 time=1 draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 variants=\"normal\"",
+                )?;
+                Ok(writeln!(inner.writer, "feature done=1")?)
+            }
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    pub fn pong(&mut self, n: &str) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(inner.writer, "pong {n}")?),
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    pub fn result(&mut self, result: &str, comment: &str) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(inner.writer, "{result} {{{comment}}}")?),
+            Err(e) => {
+                self.info_string(format!("{e}").as_str())?;
+                panic!("{e}")
+            }
+        }
+    }
+
+    // Errors are part of the protocol, so they go to the same output
+    pub fn error(&mut self, e: &dyn Error) -> Result<(), Box<dyn Error>> {
+        match self.inner.lock() {
+            Ok(mut inner) => Ok(writeln!(inner.writer, "{e}")?),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    // Centipawns from the engine's point of view. Mate in n moves is
+    // 100000 + n, getting mated in n moves is -100000 - n.
+    fn score_to_int(score: eval::Score) -> i32 {
+        match ScoreVariant::from(score) {
+            ScoreVariant::Centipawns(cp) => cp as i32,
+            ScoreVariant::Mate(_, dist) if dist > 0 => 100000 + dist as i32,
+            ScoreVariant::Mate(_, dist) => -100000 + dist as i32,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+pub struct TestBuffer {
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Default for TestBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestBuffer {
+    pub fn new() -> Self {
+        Self {
+            buf: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn split_off(&mut self, at: usize) -> Vec<u8> {
+        self.buf.lock().expect("Error locking mutex").split_off(at)
+    }
+}
+
+impl io::Write for TestBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.lock().expect("Error locking mutex").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.buf.lock().expect("Error locking mutex").flush()
+    }
+}
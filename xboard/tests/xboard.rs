@@ -0,0 +1,249 @@
+mod test_buffer;
+
+use crate::test_buffer::TestBuffer;
+use assert_matches::assert_matches;
+use engine::{Engine, EngineOptions};
+use eval::material_mobility::MaterialMobility;
+use movegen::move_generator::MoveGenerator;
+use movegen::position::Position;
+use movegen::position_history::PositionHistory;
+use movegen::r#move::MoveList;
+use regex::Regex;
+use search::alpha_beta::AlphaBeta;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use uci::UciMove;
+use xboard::{Xboard, XboardError, XboardMessage, XboardOut};
+
+const EVALUATOR: MaterialMobility = MaterialMobility::new();
+const TABLE_SIZE: usize = 16 * 1024;
+
+fn new_engine(xboard_out: &XboardOut) -> Engine {
+    let search_algo = AlphaBeta::new(Box::new(EVALUATOR), TABLE_SIZE);
+    let engine_options = Arc::new(Mutex::new(EngineOptions::default()));
+    Engine::new(search_algo, xboard_out.clone(), engine_options)
+}
+
+// Plays the move in `move_str` (e.g. "e2e4" or "move e2e4") on `pos_hist`
+fn play(pos_hist: &mut PositionHistory, move_str: &str) {
+    let move_str = move_str.trim().trim_start_matches("move ");
+    let m = UciMove::str_to_move(pos_hist.current_pos(), move_str).unwrap();
+    pos_hist.do_move(m);
+}
+
+// Collects the output until it contains `pattern`
+fn wait_for(test_writer: &mut TestBuffer, pattern: &str) -> String {
+    let re = Regex::new(pattern).unwrap();
+    let start = Instant::now();
+    let mut out = String::new();
+    while !re.is_match(&out) {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "Timeout waiting for `{pattern}`, output:\n{out}"
+        );
+        thread::sleep(Duration::from_millis(10));
+        out.push_str(&String::from_utf8(test_writer.split_off(0)).unwrap());
+    }
+    out
+}
+
+#[test]
+fn protover_ping_and_unknown_commands() {
+    let mut test_writer = TestBuffer::new();
+    let xboard_out = XboardOut::new(Box::new(test_writer.clone()), "0.1.2");
+    let mut engine = new_engine(&xboard_out);
+    let mut xb = Xboard::new(xboard_out);
+
+    assert_matches!(xb.run_command("xboard\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("protover 2\n", &mut engine), Ok(None));
+    let out = String::from_utf8(test_writer.split_off(0)).unwrap();
+    assert!(out.starts_with("feature done=0\n"));
+    assert!(out.contains("myname=\"Fatalii 0.1.2\""));
+    assert!(out.contains("usermove=1"));
+    assert!(out.contains("setboard=1"));
+    assert!(out.contains("ping=1"));
+    assert!(out.ends_with("feature done=1\n"));
+
+    assert_matches!(xb.run_command("accepted usermove\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("ping 17\n", &mut engine), Ok(None));
+    assert_eq!(
+        "pong 17\n",
+        String::from_utf8(test_writer.split_off(0)).unwrap()
+    );
+
+    let res = xb.run_command("invalid command\n", &mut engine);
+    let err = res.expect_err("Expected error");
+    assert_matches!(
+        err.downcast_ref::<XboardError>(),
+        Some(XboardError::UnknownCommand(_))
+    );
+    assert_eq!("Error (unknown command): invalid command", format!("{err}"));
+
+    assert!(xb.run_command("protover two\n", &mut engine).is_err());
+    assert!(xb.run_command("variant suicide\n", &mut engine).is_err());
+    assert_matches!(xb.run_command("variant normal\n", &mut engine), Ok(None));
+    assert_matches!(
+        xb.run_command("quit\n", &mut engine),
+        Ok(Some(XboardMessage::Quit))
+    );
+}
+
+#[test]
+fn time_controls() {
+    let test_writer = TestBuffer::new();
+    let xboard_out = XboardOut::new(Box::new(test_writer.clone()), "0.1.2");
+    let mut engine = new_engine(&xboard_out);
+    let mut xb = Xboard::new(xboard_out);
+
+    assert_matches!(xb.run_command("level 40 5 0\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("level 0 2:30 1\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("level 0 0:05 0.5\n", &mut engine), Ok(None));
+    assert!(xb.run_command("level 40 5\n", &mut engine).is_err());
+    assert!(xb.run_command("level 40 x 0\n", &mut engine).is_err());
+    assert!(xb.run_command("level -1 5 0\n", &mut engine).is_err());
+    assert!(xb.run_command("level 40 5 0 1\n", &mut engine).is_err());
+
+    assert_matches!(xb.run_command("time 30000\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("otim -150\n", &mut engine), Ok(None));
+    assert!(xb.run_command("time\n", &mut engine).is_err());
+
+    assert_matches!(xb.run_command("st 2\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("st 0.5\n", &mut engine), Ok(None));
+    assert!(xb.run_command("st inf\n", &mut engine).is_err());
+    assert_matches!(xb.run_command("sd 3\n", &mut engine), Ok(None));
+    assert!(xb.run_command("sd -3\n", &mut engine).is_err());
+}
+
+#[test]
+fn play_moves() {
+    let mut test_writer = TestBuffer::new();
+    let xboard_out = XboardOut::new(Box::new(test_writer.clone()), "0.1.2");
+    let mut engine = new_engine(&xboard_out);
+    let mut xb = Xboard::new(xboard_out);
+
+    assert_matches!(xb.run_command("new\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("sd 2\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("post\n", &mut engine), Ok(None));
+
+    let res = xb.run_command("usermove e2e5\n", &mut engine);
+    let err = res.expect_err("Expected error");
+    assert_matches!(
+        err.downcast_ref::<XboardError>(),
+        Some(XboardError::IllegalMove(_))
+    );
+    assert_eq!("Illegal move: e2e5", format!("{err}"));
+
+    // The engine plays black and answers the user's move
+    let mut pos_hist = PositionHistory::new(Position::initial());
+    assert_matches!(xb.run_command("usermove e2e4\n", &mut engine), Ok(None));
+    play(&mut pos_hist, "e2e4");
+    let out = wait_for(&mut test_writer, "move [a-h][1-8][a-h][1-8]\n");
+    assert!(Regex::new(r"(?m)^2 -?\d+ \d+ \d+ [a-h][1-8][a-h][1-8]")
+        .unwrap()
+        .is_match(&out));
+    play(&mut pos_hist, out.lines().last().unwrap());
+
+    // The engine's move has been applied, so white can move again
+    assert_matches!(xb.run_command("usermove d2d4\n", &mut engine), Ok(None));
+    play(&mut pos_hist, "d2d4");
+    let out = wait_for(&mut test_writer, "move [a-h][1-8][a-h][1-8]\n");
+    play(&mut pos_hist, out.lines().last().unwrap());
+
+    // In force mode, the engine only records the moves
+    assert_matches!(xb.run_command("force\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("nopost\n", &mut engine), Ok(None));
+    let mut move_list = MoveList::new();
+    MoveGenerator::generate_moves(&mut move_list, pos_hist.current_pos());
+    let user_move = UciMove::move_to_str(move_list[0]);
+    assert_matches!(
+        xb.run_command(&format!("usermove {user_move}\n"), &mut engine),
+        Ok(None)
+    );
+    play(&mut pos_hist, &user_move);
+    thread::sleep(Duration::from_millis(100));
+    assert!(test_writer.split_off(0).is_empty());
+
+    // `go` makes the engine play the side to move
+    assert_matches!(xb.run_command("go\n", &mut engine), Ok(None));
+    let out = wait_for(&mut test_writer, "move [a-h][1-8][a-h][1-8]\n");
+    assert!(out.starts_with("move "));
+    play(&mut pos_hist, &out);
+    let mut move_list = MoveList::new();
+    MoveGenerator::generate_moves(&mut move_list, pos_hist.current_pos());
+    let user_move = UciMove::move_to_str(move_list[0]);
+    assert_matches!(
+        xb.run_command(&format!("usermove {user_move}\n"), &mut engine),
+        Ok(None)
+    );
+    wait_for(&mut test_writer, "move [a-h][1-8][a-h][1-8]\n");
+
+    assert_matches!(
+        xb.run_command("result 1-0 {White resigns}\n", &mut engine),
+        Ok(None)
+    );
+    assert_matches!(
+        xb.run_command("quit\n", &mut engine),
+        Ok(Some(XboardMessage::Quit))
+    );
+}
+
+#[test]
+fn abort_search() {
+    let mut test_writer = TestBuffer::new();
+    let xboard_out = XboardOut::new(Box::new(test_writer.clone()), "0.1.2");
+    let mut engine = new_engine(&xboard_out);
+    let mut xb = Xboard::new(xboard_out);
+
+    // The best move of a search interrupted by `force` must not be played
+    assert_matches!(xb.run_command("new\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("usermove e2e4\n", &mut engine), Ok(None));
+    thread::sleep(Duration::from_millis(50));
+    assert_matches!(xb.run_command("force\n", &mut engine), Ok(None));
+    thread::sleep(Duration::from_millis(100));
+    assert!(!String::from_utf8(test_writer.split_off(0))
+        .unwrap()
+        .contains("move"));
+    // Black is still to move
+    assert_matches!(xb.run_command("usermove e7e5\n", &mut engine), Ok(None));
+
+    // `?` makes the engine move immediately
+    assert_matches!(xb.run_command("go\n", &mut engine), Ok(None));
+    thread::sleep(Duration::from_millis(50));
+    assert_matches!(xb.run_command("?\n", &mut engine), Ok(None));
+    wait_for(&mut test_writer, "move [a-h][1-8][a-h][1-8]\n");
+}
+
+#[test]
+fn setboard() {
+    let mut test_writer = TestBuffer::new();
+    let xboard_out = XboardOut::new(Box::new(test_writer.clone()), "0.1.2");
+    let mut engine = new_engine(&xboard_out);
+    let mut xb = Xboard::new(xboard_out);
+
+    assert_matches!(xb.run_command("new\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("force\n", &mut engine), Ok(None));
+    let res = xb.run_command("setboard invalid fen\n", &mut engine);
+    assert_eq!(
+        "tellusererror Illegal position",
+        format!("{}", res.expect_err("Expected error"))
+    );
+
+    // Mate in one
+    assert_matches!(
+        xb.run_command("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\n", &mut engine),
+        Ok(None)
+    );
+    assert_matches!(xb.run_command("sd 3\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("go\n", &mut engine), Ok(None));
+    let out = wait_for(&mut test_writer, "move [a-h][1-8][a-h][1-8]\n");
+    assert!(out.contains("move a1a8\n"));
+
+    // The engine is checkmated and reports the result instead of a move
+    assert_matches!(xb.run_command("force\n", &mut engine), Ok(None));
+    assert_matches!(xb.run_command("go\n", &mut engine), Ok(None));
+    assert_eq!(
+        "1-0 {White mates}\n",
+        String::from_utf8(test_writer.split_off(0)).unwrap()
+    );
+}